@@ -0,0 +1,76 @@
+//! Per-quote CPU cost of the signing hot path: decoding the request, formatting and encoding
+//! the price, and signing the context. Run with `cargo bench`.
+
+use alloy::primitives::Address;
+use criterion::{criterion_group, criterion_main, Criterion};
+use rain_oracle_server::oracle::{build_context, format_pyth_price};
+use rain_oracle_server::order::{decode_request, encode_request};
+use rain_oracle_server::sign::{HashScheme, Signer};
+use rain_oracle_server::PriceDirection;
+use std::hint::black_box;
+
+// Well-known test key — DO NOT use in production
+const TEST_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+fn decode(c: &mut Criterion) {
+    let body = encode_request(
+        Address::repeat_byte(1),
+        Address::repeat_byte(2),
+        Address::repeat_byte(3),
+    );
+    c.bench_function("decode_request", |b| {
+        b.iter(|| decode_request(black_box(&body), None).unwrap())
+    });
+}
+
+fn format_price(c: &mut Criterion) {
+    c.bench_function("format_pyth_price", |b| {
+        b.iter(|| format_pyth_price(black_box(310012345678), black_box(-8)))
+    });
+}
+
+fn context(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build_context");
+    for (name, direction) in [
+        ("as_is", PriceDirection::AsIs),
+        ("inverted", PriceDirection::Inverted),
+    ] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                build_context(
+                    black_box(310012345678),
+                    black_box(-8),
+                    black_box(1700000005),
+                    direction,
+                )
+                .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn sign(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let signer = Signer::new(TEST_KEY).unwrap();
+    let context = build_context(310012345678, -8, 1700000005, PriceDirection::AsIs).unwrap();
+    c.bench_function("sign_context", |b| {
+        b.iter(|| {
+            runtime
+                .block_on(signer.sign_context(black_box(&context)))
+                .unwrap()
+        })
+    });
+    c.bench_function("sign_context_sync", |b| {
+        b.iter(|| {
+            signer
+                .sign_context_sync(black_box(&context), HashScheme::Packed)
+                .unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, decode, format_price, context, sign);
+criterion_main!(benches);
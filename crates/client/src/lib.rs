@@ -0,0 +1,323 @@
+use alloy::primitives::{keccak256, Address, Bytes, FixedBytes, Keccak256, Signature, B256, U256};
+use alloy::sol;
+use alloy::sol_types::SolValue;
+use serde::Deserialize;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+sol! {
+    struct IOV2 {
+        address token;
+        bytes32 vaultId;
+    }
+
+    struct EvaluableV4 {
+        address interpreter;
+        address store;
+        bytes bytecode;
+    }
+
+    struct OrderV4 {
+        address owner;
+        EvaluableV4 evaluable;
+        IOV2[] validInputs;
+        IOV2[] validOutputs;
+        bytes32 nonce;
+    }
+}
+
+/// Why a response failed verification.
+#[derive(Debug, thiserror::Error)]
+pub enum VerifyError {
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+    #[error("Signature recovers to {recovered}, not the claimed signer {claimed}")]
+    SignerMismatch {
+        claimed: Address,
+        recovered: Address,
+    },
+    #[error("Signer {0} is not trusted")]
+    UntrustedSigner(Address),
+}
+
+/// An unknown hash scheme, or a tag given to (or missing from) the wrong one.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct InvalidHashScheme(String);
+
+/// ABI-encode a `/context` request body: `abi.encode(order, inputIOIndex, outputIOIndex,
+/// counterparty)`, as the taker would call `takeOrders` with.
+pub fn encode_request(
+    order: OrderV4,
+    input_io_index: U256,
+    output_io_index: U256,
+    counterparty: Address,
+) -> Vec<u8> {
+    (order, input_io_index, output_io_index, counterparty).abi_encode()
+}
+
+/// ABI-encode a `/context` body for a minimal order taking `input_token` and giving
+/// `output_token`, e.g. to fetch a quote without a real order.
+pub fn encode_minimal_request(
+    input_token: Address,
+    output_token: Address,
+    counterparty: Address,
+) -> Vec<u8> {
+    let io = |token| IOV2 {
+        token,
+        vaultId: Default::default(),
+    };
+    let order = OrderV4 {
+        owner: Address::ZERO,
+        evaluable: EvaluableV4 {
+            interpreter: Address::ZERO,
+            store: Address::ZERO,
+            bytecode: Default::default(),
+        },
+        validInputs: vec![io(input_token)],
+        validOutputs: vec![io(output_token)],
+        nonce: Default::default(),
+    };
+    encode_request(order, U256::ZERO, U256::ZERO, counterparty)
+}
+
+/// `keccak256(abi.encodePacked(context))`, the hash a signed context's signature is over
+/// (EIP-191 prefixed). `bytes32[]` packs as the slots back to back, so they're fed to the
+/// hasher in place rather than copied into one buffer first.
+pub fn context_hash(context: &[FixedBytes<32>]) -> B256 {
+    let mut hasher = Keccak256::new();
+    for slot in context {
+        hasher.update(slot);
+    }
+    hasher.finalize()
+}
+
+/// Recover the address that produced `signature` over `context`, as the orderbook does.
+pub fn recover_context_signer(
+    context: &[FixedBytes<32>],
+    signature: &[u8],
+) -> Result<Address, VerifyError> {
+    HashScheme::Packed.recover_signer(context, signature)
+}
+
+/// How a context is packed into the hash that is signed, to match what the verifying
+/// contract recomputes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HashScheme {
+    /// `keccak256(abi.encodePacked(context))`, as `LibContext.build` checks it
+    #[default]
+    Packed,
+    /// `keccak256(abi.encode(context))`
+    AbiEncoded,
+    /// `keccak256(abi.encodePacked(tag, context))`, binding signatures to one verifier
+    Tagged(B256),
+}
+
+impl HashScheme {
+    /// Parse `packed`, `abi_encoded` or `tagged`. `tagged` needs `tag`, which is hashed into
+    /// the `bytes32` prefix the way a contract declares `keccak256("...")` as its domain.
+    pub fn parse(scheme: &str, tag: Option<&str>) -> Result<Self, InvalidHashScheme> {
+        let invalid = |reason: String| Err(InvalidHashScheme(reason));
+        match (scheme, tag) {
+            ("packed", None) => Ok(Self::Packed),
+            ("abi_encoded", None) => Ok(Self::AbiEncoded),
+            ("tagged", Some(tag)) if !tag.is_empty() => Ok(Self::Tagged(keccak256(tag))),
+            ("tagged", _) => invalid("The tagged hash scheme needs a tag".to_string()),
+            ("packed" | "abi_encoded", Some(_)) => {
+                invalid("Only the tagged hash scheme takes a tag".to_string())
+            }
+            (other, _) => invalid(format!(
+                "Unknown hash scheme {:?} (expected packed, abi_encoded or tagged)",
+                other
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Packed => "packed",
+            Self::AbiEncoded => "abi_encoded",
+            Self::Tagged(_) => "tagged",
+        }
+    }
+
+    /// The domain tag prefixed to the context, for the tagged scheme.
+    pub fn tag(&self) -> Option<B256> {
+        match self {
+            Self::Tagged(tag) => Some(*tag),
+            _ => None,
+        }
+    }
+
+    /// The hash signed (EIP-191 prefixed) for `context`.
+    pub fn hash(&self, context: &[FixedBytes<32>]) -> B256 {
+        match self {
+            Self::Packed => context_hash(context),
+            Self::AbiEncoded => keccak256(context.to_vec().abi_encode()),
+            Self::Tagged(tag) => {
+                let mut hasher = Keccak256::new();
+                hasher.update(tag);
+                for slot in context {
+                    hasher.update(slot);
+                }
+                hasher.finalize()
+            }
+        }
+    }
+
+    /// Recover the address that produced `signature` over `context` hashed this way.
+    pub fn recover_signer(
+        &self,
+        context: &[FixedBytes<32>],
+        signature: &[u8],
+    ) -> Result<Address, VerifyError> {
+        let hash = self.hash(context);
+        Signature::from_raw(signature)
+            .and_then(|signature| signature.recover_address_from_msg(hash.as_slice()))
+            .map_err(|e| VerifyError::InvalidSignature(e.to_string()))
+    }
+}
+
+/// A co-signer's signature over a response's context.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CoSignature {
+    pub signer: Address,
+    pub signature: Bytes,
+}
+
+/// The signed parts of a `/context` response; other fields are ignored when deserializing.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SignedContext {
+    pub signer: Address,
+    pub context: Vec<FixedBytes<32>>,
+    pub signature: Bytes,
+    #[serde(default)]
+    pub cosignatures: Vec<CoSignature>,
+}
+
+impl SignedContext {
+    /// Check that the signature and every co-signature recover to the signer they claim over
+    /// the context hashed with `scheme` (the server's `SIGNED_HASH_SCHEME`), and that the
+    /// signer is one of `trusted` (e.g. the signer the order's expression checks for), before
+    /// the context is submitted on-chain. Returns the signer.
+    pub fn verify(&self, trusted: &[Address], scheme: HashScheme) -> Result<Address, VerifyError> {
+        let signatures = std::iter::once((self.signer, &self.signature)).chain(
+            self.cosignatures
+                .iter()
+                .map(|cosignature| (cosignature.signer, &cosignature.signature)),
+        );
+        for (claimed, signature) in signatures {
+            let recovered = scheme.recover_signer(&self.context, signature)?;
+            if recovered != claimed {
+                return Err(VerifyError::SignerMismatch { claimed, recovered });
+            }
+        }
+        if !trusted.contains(&self.signer) {
+            return Err(VerifyError::UntrustedSigner(self.signer));
+        }
+        Ok(self.signer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::signers::local::PrivateKeySigner;
+    use alloy::signers::SignerSync;
+
+    // Test private key — DO NOT use in production
+    const TEST_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    fn signed(context: Vec<FixedBytes<32>>, scheme: HashScheme) -> SignedContext {
+        let signer: PrivateKeySigner = TEST_KEY.parse().unwrap();
+        let signature = signer
+            .sign_message_sync(scheme.hash(&context).as_slice())
+            .unwrap();
+        SignedContext {
+            signer: signer.address(),
+            context,
+            signature: signature.as_bytes().to_vec().into(),
+            cosignatures: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let packed = HashScheme::Packed;
+        let response = signed(vec![FixedBytes::from(U256::from(1000u64))], packed);
+        let signer = response.signer;
+        assert_eq!(response.verify(&[signer], packed).unwrap(), signer);
+        assert!(matches!(
+            response.verify(&[Address::repeat_byte(1)], packed),
+            Err(VerifyError::UntrustedSigner(_))
+        ));
+
+        let mut tampered = response.clone();
+        tampered.context[0] = FixedBytes::from(U256::from(2000u64));
+        assert!(matches!(
+            tampered.verify(&[signer], packed),
+            Err(VerifyError::SignerMismatch { .. })
+        ));
+
+        let mut cosigned = response.clone();
+        cosigned.cosignatures.push(CoSignature {
+            signer: Address::repeat_byte(1),
+            signature: response.signature.clone(),
+        });
+        assert!(cosigned.verify(&[signer], packed).is_err());
+
+        let mut truncated = response;
+        truncated.signature = Bytes::from(vec![0u8; 10]);
+        assert!(matches!(
+            truncated.verify(&[signer], packed),
+            Err(VerifyError::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_with_hash_scheme() {
+        let tagged = HashScheme::parse("tagged", Some("rain.oracle.v1")).unwrap();
+        let response = signed(vec![FixedBytes::from(U256::from(1000u64))], tagged);
+        let signer = response.signer;
+        assert_eq!(response.verify(&[signer], tagged).unwrap(), signer);
+        assert!(matches!(
+            response.verify(&[signer], HashScheme::Packed),
+            Err(VerifyError::SignerMismatch { .. })
+        ));
+        assert!(HashScheme::parse("tagged", None).is_err());
+        assert!(HashScheme::parse("packed", Some("tag")).is_err());
+        assert!(HashScheme::parse("eip712", None).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_response() {
+        let response = signed(
+            vec![FixedBytes::from(U256::from(1000u64))],
+            HashScheme::Packed,
+        );
+        let json = serde_json::json!({
+            "quote_id": "00000000-0000-0000-0000-000000000000",
+            "signer": response.signer,
+            "context": response.context,
+            "signature": response.signature,
+        });
+        let parsed: SignedContext = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, response);
+    }
+
+    #[test]
+    fn test_encode_minimal_request() {
+        let body = encode_minimal_request(
+            Address::repeat_byte(0x83),
+            Address::repeat_byte(0x42),
+            Address::repeat_byte(7),
+        );
+        let (order, input, output, counterparty) =
+            <(OrderV4, U256, U256, Address)>::abi_decode(&body).unwrap();
+        assert_eq!(order.validInputs[0].token, Address::repeat_byte(0x83));
+        assert_eq!(order.validOutputs[0].token, Address::repeat_byte(0x42));
+        assert_eq!((input, output), (U256::ZERO, U256::ZERO));
+        assert_eq!(counterparty, Address::repeat_byte(7));
+    }
+}
@@ -0,0 +1,67 @@
+use alloy::primitives::{Address, U256};
+use alloy::sol_types::SolValue;
+use wasm_bindgen::prelude::*;
+
+use crate::{HashScheme, OrderV4, SignedContext};
+
+fn address(value: &str) -> Result<Address, JsError> {
+    value
+        .parse()
+        .map_err(|e| JsError::new(&format!("Invalid address {}: {}", value, e)))
+}
+
+/// Encode a `/context` request body for an ABI-encoded `OrderV4`.
+#[wasm_bindgen(js_name = encodeRequest)]
+pub fn encode_request(
+    order: &[u8],
+    input_io_index: u32,
+    output_io_index: u32,
+    counterparty: &str,
+) -> Result<Vec<u8>, JsError> {
+    let order =
+        OrderV4::abi_decode(order).map_err(|e| JsError::new(&format!("Invalid order: {}", e)))?;
+    Ok(crate::encode_request(
+        order,
+        U256::from(input_io_index),
+        U256::from(output_io_index),
+        address(counterparty)?,
+    ))
+}
+
+/// Encode a `/context` request body for a minimal order taking `inputToken` and giving
+/// `outputToken`.
+#[wasm_bindgen(js_name = encodeMinimalRequest)]
+pub fn encode_minimal_request(
+    input_token: &str,
+    output_token: &str,
+    counterparty: &str,
+) -> Result<Vec<u8>, JsError> {
+    Ok(crate::encode_minimal_request(
+        address(input_token)?,
+        address(output_token)?,
+        address(counterparty)?,
+    ))
+}
+
+/// Verify a `/context` response body (JSON) was signed by one of `trustedSigners`,
+/// returning the signer's address. `hashScheme` and `hashTag` are the server's
+/// `SIGNED_HASH_SCHEME` and `SIGNED_HASH_TAG` (`packed` with no tag when omitted).
+#[wasm_bindgen(js_name = verifyResponse)]
+pub fn verify_response(
+    response: &str,
+    trusted_signers: Vec<String>,
+    hash_scheme: Option<String>,
+    hash_tag: Option<String>,
+) -> Result<String, JsError> {
+    let response: SignedContext = serde_json::from_str(response)
+        .map_err(|e| JsError::new(&format!("Invalid response: {}", e)))?;
+    let trusted = trusted_signers
+        .iter()
+        .map(|signer| address(signer))
+        .collect::<Result<Vec<_>, _>>()?;
+    let scheme = HashScheme::parse(
+        hash_scheme.as_deref().unwrap_or("packed"),
+        hash_tag.as_deref(),
+    )?;
+    Ok(response.verify(&trusted, scheme)?.to_string())
+}
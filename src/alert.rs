@@ -0,0 +1,408 @@
+use serde_json::json;
+use std::collections::{HashSet, VecDeque};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::status::FeedStatus;
+use crate::AppState;
+
+const PAGERDUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+
+/// Timeout for a single alert POST.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where alerts are sent. Both the webhook URL and the routing key are secrets, so `Debug`
+/// shows only which kind of destination it is.
+#[derive(Clone, PartialEq)]
+pub enum AlertDestination {
+    /// Slack incoming webhook URL
+    Slack(String),
+    /// PagerDuty Events API v2 routing key
+    PagerDuty(String),
+}
+
+impl AlertDestination {
+    /// `slack` or `pagerduty`, for logs.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Slack(_) => "slack",
+            Self::PagerDuty(_) => "pagerduty",
+        }
+    }
+}
+
+impl std::fmt::Debug for AlertDestination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "AlertDestination({})", self.kind())
+    }
+}
+
+impl FromStr for AlertDestination {
+    type Err = anyhow::Error;
+
+    /// Parse `slack:<webhook url>` or `pagerduty:<routing key>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().split_once(':') {
+            Some(("slack", url)) => Ok(Self::Slack(url.to_string())),
+            Some(("pagerduty", key)) => Ok(Self::PagerDuty(key.to_string())),
+            _ => anyhow::bail!(
+                "Invalid alert destination '{}' (expected slack:<url> or pagerduty:<routing key>)",
+                s
+            ),
+        }
+    }
+}
+
+/// Thresholds that trigger alerts.
+#[derive(Debug, Clone)]
+pub struct AlertConfig {
+    /// Alert once a feed has been failing continuously for this long
+    pub feed_down_after: Duration,
+    /// Alert once this many quotes fail with an internal (fetch/sign) error within `window`
+    pub signing_errors: u64,
+    pub window: Duration,
+    /// How often conditions are evaluated
+    pub check_interval: Duration,
+}
+
+/// A condition starting or clearing. `key` identifies the condition so a trigger and its
+/// resolve can be matched up (PagerDuty's `dedup_key`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertEvent {
+    Trigger { key: String, summary: String },
+    Resolve { key: String, summary: String },
+}
+
+/// Sends alert events to every configured destination.
+#[derive(Clone)]
+pub struct Alerter {
+    client: reqwest::Client,
+    destinations: Vec<AlertDestination>,
+    pagerduty_url: String,
+}
+
+impl Alerter {
+    pub fn new(destinations: Vec<AlertDestination>) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("reqwest client builds with a timeout"),
+            destinations,
+            pagerduty_url: PAGERDUTY_EVENTS_URL.to_string(),
+        }
+    }
+
+    /// Send PagerDuty events somewhere other than the public Events API.
+    pub fn with_pagerduty_url(mut self, url: &str) -> Self {
+        self.pagerduty_url = url.to_string();
+        self
+    }
+
+    /// Deliver `event` to every destination, logging (not returning) failures so one broken
+    /// destination doesn't stop the others.
+    pub async fn send(&self, event: &AlertEvent) {
+        for destination in &self.destinations {
+            let (url, body) = match destination {
+                AlertDestination::Slack(url) => (url.as_str(), slack_body(event)),
+                AlertDestination::PagerDuty(key) => {
+                    (self.pagerduty_url.as_str(), pagerduty_body(key, event))
+                }
+            };
+            let result = self
+                .client
+                .post(url)
+                .json(&body)
+                .send()
+                .await
+                .and_then(|r| r.error_for_status());
+            // The error's URL would be the Slack webhook, itself a secret
+            if let Err(e) = result {
+                tracing::warn!(
+                    "Failed to send alert to {}: {}",
+                    destination.kind(),
+                    e.without_url()
+                );
+            }
+        }
+    }
+}
+
+fn slack_body(event: &AlertEvent) -> serde_json::Value {
+    let text = match event {
+        AlertEvent::Trigger { summary, .. } => format!(":rotating_light: {}", summary),
+        AlertEvent::Resolve { summary, .. } => format!(":white_check_mark: Resolved: {}", summary),
+    };
+    json!({ "text": text })
+}
+
+fn pagerduty_body(routing_key: &str, event: &AlertEvent) -> serde_json::Value {
+    match event {
+        AlertEvent::Trigger { key, summary } => json!({
+            "routing_key": routing_key,
+            "event_action": "trigger",
+            "dedup_key": key,
+            "payload": {
+                "summary": summary,
+                "source": "rain-oracle-server",
+                "severity": "critical",
+            },
+        }),
+        AlertEvent::Resolve { key, .. } => json!({
+            "routing_key": routing_key,
+            "event_action": "resolve",
+            "dedup_key": key,
+        }),
+    }
+}
+
+/// Tracks which conditions are currently alerting, so each fires once when it starts and
+/// resolves once when it clears.
+#[derive(Default)]
+struct Conditions {
+    active: HashSet<String>,
+    /// (unix seconds, total internal errors) samples covering the error window
+    error_samples: VecDeque<(i64, u64)>,
+}
+
+impl Conditions {
+    fn set(&mut self, key: String, summary: String, firing: bool, events: &mut Vec<AlertEvent>) {
+        if firing && self.active.insert(key.clone()) {
+            events.push(AlertEvent::Trigger { key, summary });
+        } else if !firing && self.active.remove(&key) {
+            events.push(AlertEvent::Resolve { key, summary });
+        }
+    }
+
+    /// Compare the current feed stats, circuit breaker states and internal error total against
+    /// the thresholds.
+    fn evaluate(
+        &mut self,
+        config: &AlertConfig,
+        now: i64,
+        feeds: &[FeedStatus],
+        circuits: &[(String, bool)],
+        internal_errors: u64,
+    ) -> Vec<AlertEvent> {
+        let mut events = Vec::new();
+
+        for (feed_id, open) in circuits {
+            self.set(
+                format!("circuit_open:{}", feed_id),
+                format!(
+                    "Circuit breaker for Pyth feed {} is open; quotes are failing fast",
+                    feed_id
+                ),
+                *open,
+                &mut events,
+            );
+        }
+
+        let down_after = config.feed_down_after.as_secs() as i64;
+        for feed in feeds {
+            let down_for = feed.failing_since_unix.map(|since| now - since);
+            self.set(
+                format!("feed_down:{}", feed.feed_id),
+                format!(
+                    "Pyth feed {} failing for {}s: {}",
+                    feed.feed_id,
+                    down_for.unwrap_or(0),
+                    feed.last_error.as_deref().unwrap_or("")
+                ),
+                down_for.is_some_and(|secs| secs >= down_after),
+                &mut events,
+            );
+        }
+
+        let window = config.window.as_secs() as i64;
+        self.error_samples.push_back((now, internal_errors));
+        while self
+            .error_samples
+            .front()
+            .is_some_and(|(at, _)| now - at > window)
+        {
+            self.error_samples.pop_front();
+        }
+        let in_window = internal_errors - self.error_samples.front().map_or(0, |(_, n)| *n);
+        self.set(
+            "signing_errors".to_string(),
+            format!(
+                "{} quotes failed with internal errors in the last {}s",
+                in_window, window
+            ),
+            in_window >= config.signing_errors,
+            &mut events,
+        );
+
+        events
+    }
+}
+
+/// Start evaluating alert conditions every `check_interval`, sending triggers and resolves.
+pub fn spawn(state: Arc<AppState>, config: AlertConfig, alerter: Alerter) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut conditions = Conditions::default();
+        let mut ticker = tokio::time::interval(config.check_interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            let now = state.clock.now().as_secs() as i64;
+            let circuits = state
+                .circuit_breaker
+                .as_ref()
+                .map(|breaker| breaker.snapshot())
+                .unwrap_or_default();
+            let events = conditions.evaluate(
+                &config,
+                now,
+                &state.feed_stats.snapshot(),
+                &circuits,
+                state.metrics.error_count("internal_error"),
+            );
+            for event in &events {
+                tracing::warn!("Alert: {:?}", event);
+                alerter.send(event).await;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Json, Router};
+    use std::sync::Mutex;
+
+    fn config() -> AlertConfig {
+        AlertConfig {
+            feed_down_after: Duration::from_secs(60),
+            signing_errors: 3,
+            window: Duration::from_secs(60),
+            check_interval: Duration::from_secs(10),
+        }
+    }
+
+    fn feed(failing_since: Option<i64>) -> FeedStatus {
+        FeedStatus {
+            feed_id: "ff61".to_string(),
+            failing_since_unix: failing_since,
+            last_error: failing_since.map(|_| "hermes down".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_destination() {
+        assert_eq!(
+            "slack:https://hooks.slack.com/services/x"
+                .parse::<AlertDestination>()
+                .unwrap(),
+            AlertDestination::Slack("https://hooks.slack.com/services/x".to_string())
+        );
+        assert_eq!(
+            "pagerduty:abc123".parse::<AlertDestination>().unwrap(),
+            AlertDestination::PagerDuty("abc123".to_string())
+        );
+        assert!("email:ops@example.com".parse::<AlertDestination>().is_err());
+        let destination: AlertDestination = "pagerduty:abc123".parse().unwrap();
+        assert_eq!(format!("{:?}", destination), "AlertDestination(pagerduty)");
+    }
+
+    #[test]
+    fn test_feed_down_fires_once_and_resolves() {
+        let config = config();
+        let mut conditions = Conditions::default();
+
+        assert!(conditions
+            .evaluate(&config, 1030, &[feed(Some(1000))], &[], 0)
+            .is_empty());
+        let events = conditions.evaluate(&config, 1060, &[feed(Some(1000))], &[], 0);
+        assert!(
+            matches!(&events[..], [AlertEvent::Trigger { key, .. }] if key == "feed_down:ff61")
+        );
+        assert!(conditions
+            .evaluate(&config, 1070, &[feed(Some(1000))], &[], 0)
+            .is_empty());
+
+        let events = conditions.evaluate(&config, 1080, &[feed(None)], &[], 0);
+        assert!(
+            matches!(&events[..], [AlertEvent::Resolve { key, .. }] if key == "feed_down:ff61")
+        );
+    }
+
+    #[test]
+    fn test_signing_errors_within_window() {
+        let config = config();
+        let mut conditions = Conditions::default();
+
+        assert!(conditions.evaluate(&config, 1000, &[], &[], 10).is_empty());
+        assert!(conditions.evaluate(&config, 1010, &[], &[], 12).is_empty());
+        let events = conditions.evaluate(&config, 1020, &[], &[], 13);
+        assert!(
+            matches!(&events[..], [AlertEvent::Trigger { key, .. }] if key == "signing_errors")
+        );
+
+        // The burst ages out of the window.
+        let events = conditions.evaluate(&config, 1090, &[], &[], 13);
+        assert!(matches!(&events[..], [AlertEvent::Resolve { .. }]));
+    }
+
+    #[test]
+    fn test_circuit_open_fires_once_and_resolves() {
+        let config = config();
+        let mut conditions = Conditions::default();
+        let circuit = |open| [("ff61".to_string(), open)];
+
+        assert!(conditions
+            .evaluate(&config, 1000, &[], &circuit(false), 0)
+            .is_empty());
+        let events = conditions.evaluate(&config, 1010, &[], &circuit(true), 0);
+        assert!(
+            matches!(&events[..], [AlertEvent::Trigger { key, .. }] if key == "circuit_open:ff61")
+        );
+        assert!(conditions
+            .evaluate(&config, 1020, &[], &circuit(true), 0)
+            .is_empty());
+        let events = conditions.evaluate(&config, 1030, &[], &circuit(false), 0);
+        assert!(
+            matches!(&events[..], [AlertEvent::Resolve { key, .. }] if key == "circuit_open:ff61")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_to_slack_and_pagerduty() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let app = Router::new().route(
+            "/{dest}",
+            post(move |Json(body): Json<serde_json::Value>| {
+                let sink = sink.clone();
+                async move { sink.lock().unwrap().push(body) }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let alerter = Alerter::new(vec![
+            AlertDestination::Slack(format!("http://{}/slack", addr)),
+            AlertDestination::PagerDuty("key".to_string()),
+        ])
+        .with_pagerduty_url(&format!("http://{}/pagerduty", addr));
+        alerter
+            .send(&AlertEvent::Trigger {
+                key: "signing_errors".to_string(),
+                summary: "3 quotes failed".to_string(),
+            })
+            .await;
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0]["text"], ":rotating_light: 3 quotes failed");
+        assert_eq!(received[1]["event_action"], "trigger");
+        assert_eq!(received[1]["dedup_key"], "signing_errors");
+        assert_eq!(received[1]["payload"]["summary"], "3 quotes failed");
+    }
+}
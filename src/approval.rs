@@ -0,0 +1,302 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::auth::AuthError;
+use crate::{limits, AppError, AppState};
+
+/// Header carrying an admin API key, for admin actions under the two-person rule.
+pub const ADMIN_KEY_HEADER: &str = "x-admin-key";
+
+/// A configured admin credential.
+#[derive(Clone)]
+pub struct AdminKey {
+    /// Label used in logs and staged changes (never the key itself)
+    pub name: String,
+    pub key: String,
+}
+
+/// Shows the name only, never the key.
+impl std::fmt::Debug for AdminKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminKey")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FromStr for AdminKey {
+    type Err = String;
+
+    /// Parse `name:key`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().split_once(':') {
+            Some((name, key)) if !name.is_empty() && !key.is_empty() => Ok(Self {
+                name: name.to_string(),
+                key: key.to_string(),
+            }),
+            _ => Err("expected name:key".to_string()),
+        }
+    }
+}
+
+/// An admin action that changes what the server signs, and so needs a second approver.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminAction {
+    /// Clear today's signing count, resuming a pair paused by its daily limit
+    ResetDailyLimit,
+}
+
+impl AdminAction {
+    async fn apply(self, state: &AppState) -> anyhow::Result<()> {
+        match self {
+            Self::ResetDailyLimit => limits::reset_today(state).await,
+        }
+    }
+}
+
+/// A staged admin action waiting for a second admin's approval.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct PendingChange {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    pub action: AdminAction,
+    /// Name of the admin key that staged the change
+    pub staged_by: String,
+    /// Unix seconds
+    pub staged_at: u64,
+    /// Unix seconds after which the change can no longer be approved
+    pub expires_at: u64,
+}
+
+/// Two-person rule: admin actions are staged by one admin key and only take effect once a
+/// different admin key approves them.
+pub struct Approvals {
+    /// Admin key names, keyed by key
+    keys: HashMap<String, String>,
+    ttl: Duration,
+    pending: Mutex<HashMap<Uuid, PendingChange>>,
+}
+
+impl Approvals {
+    /// Needs at least two distinct admin names, or nothing could ever be approved.
+    pub fn new(keys: impl IntoIterator<Item = AdminKey>, ttl: Duration) -> anyhow::Result<Self> {
+        let keys: HashMap<_, _> = keys.into_iter().map(|k| (k.key, k.name)).collect();
+        let mut names: Vec<_> = keys.values().collect();
+        names.sort();
+        names.dedup();
+        anyhow::ensure!(
+            names.len() >= 2,
+            "The two-person rule needs at least two admin keys with different names"
+        );
+        Ok(Self {
+            keys,
+            ttl,
+            pending: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Name of the admin key sent in the request headers.
+    pub fn admin(&self, headers: &HeaderMap) -> Result<String, AuthError> {
+        let key = headers
+            .get(ADMIN_KEY_HEADER)
+            .ok_or(AuthError::MissingHeader(ADMIN_KEY_HEADER))?
+            .to_str()
+            .map_err(|_| AuthError::MalformedHeader(ADMIN_KEY_HEADER))?;
+        self.keys
+            .get(key.trim())
+            .cloned()
+            .ok_or(AuthError::InvalidApiKey)
+    }
+
+    /// Stage `action` on behalf of `admin`.
+    pub fn stage(&self, action: AdminAction, admin: String, now: u64) -> PendingChange {
+        let change = PendingChange {
+            id: Uuid::new_v4(),
+            action,
+            staged_by: admin,
+            staged_at: now,
+            expires_at: now + self.ttl.as_secs(),
+        };
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, change| change.expires_at > now);
+        pending.insert(change.id, change.clone());
+        change
+    }
+
+    /// Changes still waiting for approval, oldest first.
+    pub fn pending(&self, now: u64) -> Vec<PendingChange> {
+        let mut changes: Vec<_> = self
+            .pending
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|change| change.expires_at > now)
+            .cloned()
+            .collect();
+        changes.sort_by_key(|change| change.staged_at);
+        changes
+    }
+
+    /// Take the change `id` for `approver` to apply. Fails if it is unknown or expired, or if
+    /// `approver` staged it.
+    pub fn approve(&self, id: Uuid, approver: &str, now: u64) -> Result<PendingChange, AppError> {
+        let mut pending = self.pending.lock().unwrap();
+        let change = pending
+            .get(&id)
+            .filter(|change| change.expires_at > now)
+            .ok_or_else(|| AppError::NotFound(format!("No pending change with ID {}", id)))?;
+        if change.staged_by == approver {
+            return Err(AuthError::SelfApproval(approver.to_string()).into());
+        }
+        Ok(pending.remove(&id).expect("change is pending"))
+    }
+}
+
+/// Run `action` now, or stage it for approval when the two-person rule is enabled. Returns
+/// `None` once applied, or the staged change.
+pub async fn apply_or_stage(
+    state: &AppState,
+    headers: &HeaderMap,
+    action: AdminAction,
+) -> Result<Option<PendingChange>, AppError> {
+    let Some(approvals) = &state.approvals else {
+        action.apply(state).await?;
+        return Ok(None);
+    };
+    let admin = approvals.admin(headers)?;
+    let change = approvals.stage(action, admin, state.clock.now().as_secs());
+    tracing::warn!(
+        "Admin '{}' staged {:?} as change {}; awaiting approval",
+        change.staged_by,
+        change.action,
+        change.id
+    );
+    Ok(Some(change))
+}
+
+/// `202 Accepted` response for a staged change.
+pub fn staged_response(change: PendingChange) -> Response {
+    (StatusCode::ACCEPTED, Json(change)).into_response()
+}
+
+fn approvals(state: &AppState) -> Result<&Approvals, AppError> {
+    state
+        .approvals
+        .as_ref()
+        .ok_or_else(|| AppError::NotFound("The two-person rule is not enabled".to_string()))
+}
+
+/// GET handler — admin changes waiting for a second approver.
+#[utoipa::path(
+    get,
+    path = "/admin/changes",
+    params(("X-Admin-Key" = String, Header, description = "Admin API key")),
+    responses(
+        (status = 200, description = "Pending changes", body = Vec<PendingChange>),
+        (status = 401, description = "Missing or unknown admin key", body = crate::ErrorResponse),
+        (status = 404, description = "The two-person rule is not enabled", body = crate::ErrorResponse),
+    ),
+)]
+pub async fn pending(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<PendingChange>>, AppError> {
+    let approvals = approvals(&state)?;
+    approvals.admin(&headers)?;
+    Ok(Json(approvals.pending(state.clock.now().as_secs())))
+}
+
+/// POST handler — approve and apply a change staged by a different admin.
+#[utoipa::path(
+    post,
+    path = "/admin/changes/{id}/approve",
+    params(
+        ("id" = String, Path, description = "Pending change ID"),
+        ("X-Admin-Key" = String, Header, description = "Admin API key (not the one that staged the change)"),
+    ),
+    responses(
+        (status = 200, description = "The applied change", body = PendingChange),
+        (status = 401, description = "Missing or unknown admin key", body = crate::ErrorResponse),
+        (status = 403, description = "The approver staged the change", body = crate::ErrorResponse),
+        (status = 404, description = "No such pending change", body = crate::ErrorResponse),
+    ),
+)]
+pub async fn approve(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Json<PendingChange>, AppError> {
+    let approvals = approvals(&state)?;
+    let approver = approvals.admin(&headers)?;
+    let change = approvals.approve(id, &approver, state.clock.now().as_secs())?;
+    change.action.apply(&state).await?;
+    tracing::warn!(
+        "Admin '{}' approved {:?} (change {}, staged by '{}')",
+        approver,
+        change.action,
+        change.id,
+        change.staged_by
+    );
+    Ok(Json(change))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approvals() -> Approvals {
+        Approvals::new(
+            ["alice:ka".parse().unwrap(), "bob:kb".parse().unwrap()],
+            Duration::from_secs(60),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_needs_two_admins() {
+        let one: AdminKey = "alice:ka".parse().unwrap();
+        assert!(Approvals::new([one.clone()], Duration::from_secs(60)).is_err());
+        let same_name: AdminKey = "alice:kb".parse().unwrap();
+        assert!(Approvals::new([one, same_name], Duration::from_secs(60)).is_err());
+        assert!("alice".parse::<AdminKey>().is_err());
+    }
+
+    #[test]
+    fn test_second_admin_must_approve() {
+        let approvals = approvals();
+        let change = approvals.stage(AdminAction::ResetDailyLimit, "alice".to_string(), 100);
+        assert_eq!(approvals.pending(100).len(), 1);
+
+        let err = approvals.approve(change.id, "alice", 110).unwrap_err();
+        assert!(matches!(
+            err,
+            AppError::Unauthorized(AuthError::SelfApproval(_))
+        ));
+        let approved = approvals.approve(change.id, "bob", 110).unwrap();
+        assert_eq!(approved.action, AdminAction::ResetDailyLimit);
+        assert!(approvals.pending(110).is_empty());
+        assert!(approvals.approve(change.id, "bob", 110).is_err());
+    }
+
+    #[test]
+    fn test_changes_expire() {
+        let approvals = approvals();
+        let change = approvals.stage(AdminAction::ResetDailyLimit, "alice".to_string(), 100);
+        assert!(approvals.pending(160).is_empty());
+        assert!(matches!(
+            approvals.approve(change.id, "bob", 160),
+            Err(AppError::NotFound(_))
+        ));
+    }
+}
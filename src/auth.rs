@@ -0,0 +1,579 @@
+use async_trait::async_trait;
+use axum::{
+    body::Body,
+    extract::{OriginalUri, Request, State},
+    http::{request::Parts, HeaderMap, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::Arc;
+use zeroize::Zeroizing;
+
+use crate::{AppError, AppState};
+
+/// Header carrying the hex HMAC-SHA256 of `"{timestamp}\n{method}\n{path_and_query}\n{body}"`.
+pub const SIGNATURE_HEADER: &str = "x-signature";
+/// Header carrying the unix timestamp (seconds) the client signed the request at.
+pub const TIMESTAMP_HEADER: &str = "x-timestamp";
+
+/// Largest body the auth middleware will buffer (matches axum's default body limit).
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Client authentication failures (returned as 401, or 403 for missing permissions).
+#[derive(Debug, thiserror::Error)]
+pub enum AuthError {
+    #[error("Missing {0} header")]
+    MissingHeader(&'static str),
+
+    #[error("Invalid {0} header")]
+    MalformedHeader(&'static str),
+
+    #[error("Request timestamp is {age_seconds}s from server time (max {max_age_seconds}s)")]
+    StaleTimestamp {
+        age_seconds: u64,
+        max_age_seconds: u64,
+    },
+
+    #[error("Request signature does not match")]
+    InvalidSignature,
+
+    #[error("Invalid bearer token: {0}")]
+    InvalidToken(String),
+
+    #[error("Token is missing required scope '{0}'")]
+    InsufficientScope(String),
+
+    #[error("Unknown API key")]
+    InvalidApiKey,
+
+    #[error("Missing credentials for {0}")]
+    MissingCredentials(String),
+
+    #[error("Client identity '{0}' is not allowed")]
+    UnknownIdentity(String),
+
+    #[error("Admin '{0}' staged this change and can't also approve it")]
+    SelfApproval(String),
+}
+
+impl AuthError {
+    pub(crate) fn error_code(&self) -> &'static str {
+        match self {
+            Self::MissingHeader(_) => "missing_auth",
+            Self::MalformedHeader(_) => "malformed_auth",
+            Self::StaleTimestamp { .. } => "stale_timestamp",
+            Self::InvalidSignature => "invalid_signature",
+            Self::InvalidToken(_) => "invalid_token",
+            Self::InsufficientScope(_) => "insufficient_scope",
+            Self::InvalidApiKey => "invalid_api_key",
+            Self::MissingCredentials(_) => "missing_auth",
+            Self::UnknownIdentity(_) => "unknown_identity",
+            Self::SelfApproval(_) => "self_approval",
+        }
+    }
+
+    pub(crate) fn status_code(&self) -> StatusCode {
+        match self {
+            Self::InsufficientScope(_) | Self::SelfApproval(_) => StatusCode::FORBIDDEN,
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
+
+    /// What the error code means, for the `/errors` catalog.
+    pub(crate) fn description(&self) -> &'static str {
+        match self {
+            Self::MissingHeader(_) | Self::MissingCredentials(_) => {
+                "The request carries no credentials for a required authentication mechanism"
+            }
+            Self::MalformedHeader(_) => "An authentication header could not be parsed",
+            Self::StaleTimestamp { .. } => {
+                "The signed request timestamp is too far from server time"
+            }
+            Self::InvalidSignature => "The HMAC request signature does not match",
+            Self::InvalidToken(_) => "The bearer token is invalid or expired",
+            Self::InsufficientScope(_) => "The bearer token lacks a scope the route requires",
+            Self::InvalidApiKey => "The API key is not recognised",
+            Self::UnknownIdentity(_) => "The client certificate identity is not allowed",
+            Self::SelfApproval(_) => "An admin tried to approve a change they staged",
+        }
+    }
+
+    /// One of each variant, for the `/errors` catalog.
+    pub(crate) fn samples() -> Vec<Self> {
+        vec![
+            Self::MissingHeader(""),
+            Self::MalformedHeader(""),
+            Self::StaleTimestamp {
+                age_seconds: 0,
+                max_age_seconds: 0,
+            },
+            Self::InvalidSignature,
+            Self::InvalidToken(String::new()),
+            Self::InsufficientScope(String::new()),
+            Self::InvalidApiKey,
+            Self::MissingCredentials(String::new()),
+            Self::UnknownIdentity(String::new()),
+            Self::SelfApproval(String::new()),
+        ]
+    }
+}
+
+/// Who a request was authenticated as.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Identity {
+    /// Name of the [`Authenticator`] that accepted the request, e.g. `hmac`
+    pub mechanism: &'static str,
+    /// JWT `sub`, API key name or client certificate identity, when the mechanism has one
+    pub subject: Option<String>,
+}
+
+/// Every identity a request was authenticated as, attached as a request extension.
+#[derive(Debug, Clone, Default)]
+pub struct Authenticated(pub Vec<Identity>);
+
+/// A request authentication mechanism. Implement this to plug custom authentication into
+/// [`AuthChain`].
+#[async_trait]
+pub trait Authenticator: Send + Sync {
+    /// Short name used in identities and errors, e.g. `jwt`
+    fn name(&self) -> &'static str;
+
+    /// Authenticate a request from its head and (already buffered) body. Returns `Ok(None)`
+    /// when the request carries none of this mechanism's credentials, and an error when it
+    /// carries invalid ones. May attach extensions (e.g. JWT claims) to `parts`.
+    async fn authenticate(
+        &self,
+        parts: &mut Parts,
+        body: &[u8],
+        now: u64,
+    ) -> Result<Option<Identity>, AuthError>;
+}
+
+/// How an [`AuthChain`] combines its authenticators.
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum AuthMode {
+    /// Every authenticator must accept the request
+    #[default]
+    All,
+    /// The first authenticator whose credentials the request carries must accept it
+    Any,
+}
+
+/// Authenticators applied in order to `/context` requests.
+#[derive(Clone, Default)]
+pub struct AuthChain {
+    authenticators: Vec<Arc<dyn Authenticator>>,
+    mode: AuthMode,
+}
+
+impl AuthChain {
+    pub fn with_mode(mut self, mode: AuthMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Append an authenticator to the chain.
+    pub fn with(mut self, authenticator: Arc<dyn Authenticator>) -> Self {
+        self.authenticators.push(authenticator);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.authenticators.is_empty()
+    }
+
+    fn names(&self) -> String {
+        let names: Vec<&str> = self.authenticators.iter().map(|a| a.name()).collect();
+        names.join(match self.mode {
+            AuthMode::All => " and ",
+            AuthMode::Any => " or ",
+        })
+    }
+
+    /// Run the chain over a request, returning the identities it was authenticated as.
+    pub async fn authenticate(
+        &self,
+        parts: &mut Parts,
+        body: &[u8],
+        now: u64,
+    ) -> Result<Vec<Identity>, AuthError> {
+        let mut identities = Vec::new();
+        for authenticator in &self.authenticators {
+            match authenticator.authenticate(parts, body, now).await? {
+                Some(identity) if self.mode == AuthMode::Any => return Ok(vec![identity]),
+                Some(identity) => identities.push(identity),
+                None if self.mode == AuthMode::All => {
+                    return Err(AuthError::MissingCredentials(
+                        authenticator.name().to_string(),
+                    ))
+                }
+                None => {}
+            }
+        }
+        if identities.is_empty() && !self.authenticators.is_empty() {
+            return Err(AuthError::MissingCredentials(self.names()));
+        }
+        Ok(identities)
+    }
+}
+
+/// Shared-secret HMAC request authentication.
+///
+/// Clients send `X-Timestamp: <unix seconds>` and
+/// `X-Signature: hex(HMAC-SHA256(secret, "<timestamp>\n<METHOD>\n<path>[?<query>]\n" ++ body))`,
+/// so a signature can't be moved to another endpoint or query string. Requests whose
+/// timestamp is further than `max_age_seconds` from server time are rejected, which
+/// bounds how long a captured request can be replayed.
+pub struct HmacAuth {
+    secret: Zeroizing<Vec<u8>>,
+    max_age_seconds: u64,
+}
+
+impl HmacAuth {
+    pub fn new(secret: &[u8], max_age_seconds: u64) -> Self {
+        Self {
+            secret: Zeroizing::new(secret.to_vec()),
+            max_age_seconds,
+        }
+    }
+
+    fn mac(&self, timestamp: &str, method: &str, path: &str, body: &[u8]) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        for part in [timestamp, method, path] {
+            mac.update(part.as_bytes());
+            mac.update(b"\n");
+        }
+        mac.update(body);
+        mac
+    }
+
+    /// Compute the hex signature a client should send for a `method` request to `path` (with
+    /// its query string, if any) (for clients and tests).
+    pub fn sign(&self, timestamp: &str, method: &str, path: &str, body: &[u8]) -> String {
+        hex::encode(
+            self.mac(timestamp, method, path, body)
+                .finalize()
+                .into_bytes(),
+        )
+    }
+
+    /// Verify a request's timestamp and signature against the current time.
+    pub fn verify(
+        &self,
+        timestamp: &str,
+        signature: &str,
+        method: &str,
+        path: &str,
+        body: &[u8],
+        now: u64,
+    ) -> Result<(), AuthError> {
+        let ts: u64 = timestamp
+            .trim()
+            .parse()
+            .map_err(|_| AuthError::MalformedHeader(TIMESTAMP_HEADER))?;
+        let age_seconds = now.abs_diff(ts);
+        if age_seconds > self.max_age_seconds {
+            return Err(AuthError::StaleTimestamp {
+                age_seconds,
+                max_age_seconds: self.max_age_seconds,
+            });
+        }
+
+        let signature = signature.trim();
+        let signature = hex::decode(signature.strip_prefix("0x").unwrap_or(signature))
+            .map_err(|_| AuthError::MalformedHeader(SIGNATURE_HEADER))?;
+        self.mac(timestamp, method, path, body)
+            .verify_slice(&signature)
+            .map_err(|_| AuthError::InvalidSignature)
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &'static str) -> Result<&'a str, AuthError> {
+    headers
+        .get(name)
+        .ok_or(AuthError::MissingHeader(name))?
+        .to_str()
+        .map_err(|_| AuthError::MalformedHeader(name))
+}
+
+#[async_trait]
+impl Authenticator for HmacAuth {
+    fn name(&self) -> &'static str {
+        "hmac"
+    }
+
+    async fn authenticate(
+        &self,
+        parts: &mut Parts,
+        body: &[u8],
+        now: u64,
+    ) -> Result<Option<Identity>, AuthError> {
+        let headers = &parts.headers;
+        if !headers.contains_key(TIMESTAMP_HEADER) && !headers.contains_key(SIGNATURE_HEADER) {
+            return Ok(None);
+        }
+        let timestamp = header(headers, TIMESTAMP_HEADER)?;
+        let signature = header(headers, SIGNATURE_HEADER)?;
+        // The URI the client sent, before any router nesting stripped a prefix
+        let uri = parts
+            .extensions
+            .get::<OriginalUri>()
+            .map_or(&parts.uri, |OriginalUri(uri)| uri);
+        let path = uri.path_and_query().map_or("/", |path| path.as_str());
+        self.verify(timestamp, signature, parts.method.as_str(), path, body, now)?;
+        Ok(Some(Identity {
+            mechanism: self.name(),
+            subject: None,
+        }))
+    }
+}
+
+/// Client certificate authentication, for deployments where a TLS-terminating proxy verifies
+/// client certificates and forwards the verified identity (e.g. the subject or SHA-256
+/// fingerprint) in a header. The proxy must strip that header from client requests.
+pub struct MtlsAuth {
+    header: String,
+    allowed: Vec<String>,
+}
+
+impl MtlsAuth {
+    /// Read the client identity from `header`. An empty `allowed` list accepts any identity
+    /// the proxy forwards.
+    pub fn new(header: &str, allowed: Vec<String>) -> Self {
+        Self {
+            header: header.to_ascii_lowercase(),
+            allowed,
+        }
+    }
+}
+
+#[async_trait]
+impl Authenticator for MtlsAuth {
+    fn name(&self) -> &'static str {
+        "mtls"
+    }
+
+    async fn authenticate(
+        &self,
+        parts: &mut Parts,
+        _body: &[u8],
+        _now: u64,
+    ) -> Result<Option<Identity>, AuthError> {
+        let Some(value) = parts.headers.get(&self.header) else {
+            return Ok(None);
+        };
+        let identity = value
+            .to_str()
+            .map_err(|_| AuthError::MalformedHeader("client identity"))?
+            .trim();
+        if !self.allowed.is_empty() && !self.allowed.iter().any(|a| a == identity) {
+            return Err(AuthError::UnknownIdentity(identity.to_string()));
+        }
+        Ok(Some(Identity {
+            mechanism: self.name(),
+            subject: Some(identity.to_string()),
+        }))
+    }
+}
+
+/// Middleware — runs the configured [`AuthChain`] before the body reaches the decoder, and
+/// attaches the resulting [`Authenticated`] identities. Passes requests through untouched
+/// when no authentication is configured.
+pub async fn authenticate(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if state.auth.is_empty() {
+        return Ok(next.run(request).await);
+    }
+
+    let (mut parts, body) = request.into_parts();
+    let bytes = axum::body::to_bytes(body, MAX_BODY_BYTES)
+        .await
+        .map_err(|e| crate::OracleRequestError::InvalidBody(e.to_string()))?;
+
+    let identities = state
+        .auth
+        .authenticate(&mut parts, &bytes, state.clock.now().as_secs())
+        .await?;
+    tracing::debug!("Authenticated as {:?}", identities);
+    parts.extensions.insert(Authenticated(identities));
+
+    Ok(next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    #[test]
+    fn test_sign_verify_roundtrip() {
+        let auth = HmacAuth::new(b"secret", 30);
+        let sig = auth.sign("1700000000", "POST", "/context", b"body");
+        auth.verify("1700000000", &sig, "POST", "/context", b"body", NOW)
+            .unwrap();
+        auth.verify(
+            "1700000000",
+            &format!("0x{}", sig),
+            "POST",
+            "/context",
+            b"body",
+            NOW,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_known_vector() {
+        // printf '1700000000\nPOST\n/context?expiry_seconds=30\nbody' | openssl dgst -sha256 -hmac secret
+        let auth = HmacAuth::new(b"secret", 30);
+        assert_eq!(
+            auth.sign("1700000000", "POST", "/context?expiry_seconds=30", b"body"),
+            "dd7c119997d9e62595e78f972fe76bb549f45afd81b98341600f5b32f95cffd3"
+        );
+    }
+
+    #[test]
+    fn test_tampered_body_rejected() {
+        let auth = HmacAuth::new(b"secret", 30);
+        let sig = auth.sign("1700000000", "POST", "/context", b"body");
+        let err = auth
+            .verify("1700000000", &sig, "POST", "/context", b"other", NOW)
+            .unwrap_err();
+        assert!(matches!(err, AuthError::InvalidSignature));
+    }
+
+    #[test]
+    fn test_moved_request_rejected() {
+        let auth = HmacAuth::new(b"secret", 30);
+        let sig = auth.sign("1700000000", "POST", "/context", b"body");
+        for (method, path) in [
+            ("GET", "/context"),
+            ("POST", "/context/batch"),
+            ("POST", "/context?expiry_seconds=300"),
+        ] {
+            let err = auth
+                .verify("1700000000", &sig, method, path, b"body", NOW)
+                .unwrap_err();
+            assert!(matches!(err, AuthError::InvalidSignature));
+        }
+    }
+
+    #[test]
+    fn test_wrong_secret_rejected() {
+        let sig = HmacAuth::new(b"other", 30).sign("1700000000", "POST", "/context", b"body");
+        let err = HmacAuth::new(b"secret", 30)
+            .verify("1700000000", &sig, "POST", "/context", b"body", NOW)
+            .unwrap_err();
+        assert!(matches!(err, AuthError::InvalidSignature));
+    }
+
+    #[test]
+    fn test_stale_timestamp_rejected() {
+        let auth = HmacAuth::new(b"secret", 30);
+        let sig = auth.sign("1699999900", "POST", "/context", b"body");
+        let err = auth
+            .verify("1699999900", &sig, "POST", "/context", b"body", NOW)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            AuthError::StaleTimestamp {
+                age_seconds: 100,
+                ..
+            }
+        ));
+
+        // Future timestamps beyond the window are rejected too
+        let sig = auth.sign("1700000100", "POST", "/context", b"body");
+        assert!(auth
+            .verify("1700000100", &sig, "POST", "/context", b"body", NOW)
+            .is_err());
+    }
+
+    fn parts(headers: &[(&'static str, &str)]) -> Parts {
+        let mut request = Request::post("/context").body(Body::empty()).unwrap();
+        for (name, value) in headers {
+            request.headers_mut().insert(*name, value.parse().unwrap());
+        }
+        request.into_parts().0
+    }
+
+    #[tokio::test]
+    async fn test_chain_modes() {
+        let hmac: Arc<dyn Authenticator> = Arc::new(HmacAuth::new(b"secret", 30));
+        let mtls: Arc<dyn Authenticator> = Arc::new(MtlsAuth::new(
+            "X-Client-Identity",
+            vec!["CN=mm".to_string()],
+        ));
+        let signature =
+            HmacAuth::new(b"secret", 30).sign("1700000000", "POST", "/context", b"body");
+        let signed = [
+            (TIMESTAMP_HEADER, "1700000000"),
+            (SIGNATURE_HEADER, signature.as_str()),
+        ];
+        let cert = [("x-client-identity", "CN=mm")];
+
+        let all = AuthChain::default().with(hmac.clone()).with(mtls.clone());
+        let err = all
+            .authenticate(&mut parts(&signed), b"body", NOW)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AuthError::MissingCredentials(ref name) if name == "mtls"));
+        let both = [signed[0], signed[1], cert[0]];
+        let identities = all
+            .authenticate(&mut parts(&both), b"body", NOW)
+            .await
+            .unwrap();
+        assert_eq!(identities.len(), 2);
+
+        let any = AuthChain::default()
+            .with_mode(AuthMode::Any)
+            .with(hmac)
+            .with(mtls);
+        let identities = any
+            .authenticate(&mut parts(&cert), b"body", NOW)
+            .await
+            .unwrap();
+        assert_eq!(identities[0].subject.as_deref(), Some("CN=mm"));
+        assert!(matches!(
+            any.authenticate(&mut parts(&[]), b"body", NOW)
+                .await
+                .unwrap_err(),
+            AuthError::MissingCredentials(ref names) if names == "hmac or mtls"
+        ));
+        // Credentials that are present but invalid fail the request
+        assert!(matches!(
+            any.authenticate(
+                &mut parts(&[("x-client-identity", "CN=other")]),
+                b"body",
+                NOW
+            )
+            .await
+            .unwrap_err(),
+            AuthError::UnknownIdentity(_)
+        ));
+    }
+
+    #[test]
+    fn test_malformed_headers() {
+        let auth = HmacAuth::new(b"secret", 30);
+        assert!(matches!(
+            auth.verify("yesterday", "00", "POST", "/context", b"", NOW)
+                .unwrap_err(),
+            AuthError::MalformedHeader(TIMESTAMP_HEADER)
+        ));
+        assert!(matches!(
+            auth.verify("1700000000", "zz", "POST", "/context", b"", NOW)
+                .unwrap_err(),
+            AuthError::MalformedHeader(SIGNATURE_HEADER)
+        ));
+    }
+}
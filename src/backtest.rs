@@ -0,0 +1,341 @@
+use rain_math_float::Float;
+use std::collections::BTreeMap;
+use std::io::BufRead;
+use std::sync::Arc;
+
+use crate::guard::{GuardInput, PricingGuard};
+use crate::metrics::percentile;
+use crate::oracle::{self, NonPositivePrice};
+use crate::pyth::{Hermes, HermesError, PriceData};
+use crate::storage::direction_str;
+use crate::{PriceDirection, TokenPairConfig};
+
+/// A historical price to replay, in Pyth's coefficient/exponent form.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceSample {
+    pub timestamp: i64,
+    pub price: i128,
+    pub expo: i32,
+}
+
+/// Parse a decimal price like `3100.12` into a Pyth-style coefficient and exponent.
+fn parse_decimal(s: &str) -> anyhow::Result<(i128, i32)> {
+    let s = s.trim();
+    let (int_part, frac_part) = s.split_once('.').unwrap_or((s, ""));
+    anyhow::ensure!(
+        frac_part.chars().all(|c| c.is_ascii_digit()),
+        "Invalid price '{}'",
+        s
+    );
+    let price = format!("{}{}", int_part, frac_part)
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Invalid price '{}': {}", s, e))?;
+    Ok((price, -(frac_part.len() as i32)))
+}
+
+/// Read `timestamp,price` rows (unix seconds, decimal price) from a CSV, skipping a header
+/// row and blank lines.
+pub fn read_csv(reader: impl BufRead) -> anyhow::Result<Vec<PriceSample>> {
+    let mut samples = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || (i == 0 && line.starts_with(|c: char| c.is_ascii_alphabetic())) {
+            continue;
+        }
+        let (timestamp, price) = line
+            .split_once(',')
+            .ok_or_else(|| anyhow::anyhow!("Line {}: expected timestamp,price", i + 1))?;
+        let timestamp = crate::export::parse_time(timestamp.trim())
+            .map_err(|e| anyhow::anyhow!("Line {}: {}", i + 1, e))?;
+        let (price, expo) =
+            parse_decimal(price).map_err(|e| anyhow::anyhow!("Line {}: {}", i + 1, e))?;
+        samples.push(PriceSample {
+            timestamp,
+            price,
+            expo,
+        });
+    }
+    Ok(samples)
+}
+
+/// Fetch the feed's price every `step_seconds` from `from` up to `until` from Hermes'
+/// historical updates (the data behind Pyth Benchmarks). Failed fetches are kept, since
+/// the live server would have refused to quote at those times too.
+pub async fn fetch_samples(
+    hermes: &Hermes,
+    feed_id: &str,
+    from: i64,
+    until: i64,
+    step_seconds: u64,
+) -> Vec<anyhow::Result<PriceSample>> {
+    let mut samples = Vec::new();
+    let mut timestamp = from;
+    while timestamp < until {
+        samples.push(
+            hermes
+                .fetch_price_at(feed_id, timestamp)
+                .await
+                .map(|data| PriceSample {
+                    timestamp,
+                    price: data.price,
+                    expo: data.expo,
+                }),
+        );
+        timestamp += step_seconds as i64;
+    }
+    samples
+}
+
+/// Outcome of replaying prices in one direction.
+#[derive(Debug)]
+pub struct BacktestReport {
+    pub direction: PriceDirection,
+    pub samples: usize,
+    /// Refused quotes by the error code the server would have returned
+    pub refused: BTreeMap<&'static str, usize>,
+    /// Signed price relative to the mid price, in basis points, for each quote issued (sorted)
+    pub markup_bps: Vec<f64>,
+}
+
+impl BacktestReport {
+    /// The markup at percentile `p` (0-100), by nearest rank.
+    pub fn markup_percentile(&self, p: f64) -> Option<f64> {
+        percentile(&self.markup_bps, p)
+    }
+}
+
+impl std::fmt::Display for BacktestReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let refused: usize = self.refused.values().sum();
+        write!(
+            f,
+            "{}: {} samples, {} refused ({:.2}%)",
+            direction_str(self.direction),
+            self.samples,
+            refused,
+            100.0 * refused as f64 / self.samples.max(1) as f64
+        )?;
+        for (code, count) in &self.refused {
+            write!(f, "\n  refused {}: {}", code, count)?;
+        }
+        if let (Some(min), Some(max)) = (self.markup_bps.first(), self.markup_bps.last()) {
+            write!(f, "\n  markup bps: min {:.4}", min)?;
+            for p in [50.0, 90.0, 99.0] {
+                write!(f, " p{} {:.4}", p, self.markup_percentile(p).unwrap_or(0.0))?;
+            }
+            write!(f, " max {:.4}", max)?;
+        }
+        Ok(())
+    }
+}
+
+/// The error code the server would have refused a quote with.
+fn refusal_code(err: &anyhow::Error) -> &'static str {
+    if err.downcast_ref::<NonPositivePrice>().is_some() {
+        "invalid_upstream_price"
+    } else if let Some(err) = err.downcast_ref::<HermesError>() {
+        match err {
+            HermesError::RateLimited { .. } => "upstream_rate_limited",
+            HermesError::Unverified(_) => "unverified_upstream_price",
+        }
+    } else {
+        "internal_error"
+    }
+}
+
+/// Price a sample the way `/context` would, returning the signed price and the mid price it
+/// was derived from (inverted for `Inverted`).
+fn signed_and_mid(
+    sample: &PriceSample,
+    direction: PriceDirection,
+    pair: &TokenPairConfig,
+) -> anyhow::Result<(f64, f64)> {
+    let context = oracle::build_pair_context(sample.price, sample.expo, 0, direction, pair)?;
+    let signed = Float::from(alloy::primitives::B256::from(context[0]))
+        .format()
+        .map_err(|e| anyhow::anyhow!("Failed to format signed price: {:?}", e))?
+        .parse::<f64>()?;
+    let mid: f64 = oracle::feed_price(sample.price, sample.expo, pair.feed_inverted)?
+        .format()
+        .map_err(|e| anyhow::anyhow!("Failed to format mid price: {:?}", e))?
+        .parse()?;
+    Ok(match direction {
+        PriceDirection::AsIs => (signed, mid),
+        PriceDirection::Inverted => (signed, 1.0 / mid),
+    })
+}
+
+/// The code of the first of `guards` to refuse `sample`, checked as if it were published and
+/// quoted at its timestamp.
+async fn guard_refusal(
+    sample: &PriceSample,
+    feed_id: &str,
+    guards: &[Arc<dyn PricingGuard>],
+) -> Option<&'static str> {
+    let price = PriceData {
+        price: sample.price,
+        expo: sample.expo,
+        publish_time: sample.timestamp,
+    };
+    let input = GuardInput {
+        feed_id,
+        price: &price,
+        now: sample.timestamp.max(0) as u64,
+    };
+    for guard in guards {
+        if let Err(rejection) = guard.check(input).await {
+            return Some(rejection.code);
+        }
+    }
+    None
+}
+
+/// Replay `samples` through `guards` and `pair`'s signing pipeline in `direction`, counting
+/// refusals and the markup of each quote that would have been issued. Gas compensation, price
+/// jitter and decimal scaling aren't replayed: they depend on live gas prices, randomness and
+/// each order's tokens.
+pub async fn replay(
+    samples: &[anyhow::Result<PriceSample>],
+    direction: PriceDirection,
+    pair: &TokenPairConfig,
+    feed_id: &str,
+    guards: &[Arc<dyn PricingGuard>],
+) -> BacktestReport {
+    let mut report = BacktestReport {
+        direction,
+        samples: samples.len(),
+        refused: BTreeMap::new(),
+        markup_bps: Vec::new(),
+    };
+    for sample in samples {
+        let priced = match sample {
+            Ok(sample) => match guard_refusal(sample, feed_id, guards).await {
+                Some(code) => {
+                    *report.refused.entry(code).or_default() += 1;
+                    continue;
+                }
+                None => signed_and_mid(sample, direction, pair),
+            },
+            Err(err) => {
+                *report.refused.entry(refusal_code(err)).or_default() += 1;
+                continue;
+            }
+        };
+        match priced {
+            Ok((signed, mid)) => report.markup_bps.push((signed / mid - 1.0) * 10_000.0),
+            Err(err) => *report.refused.entry(refusal_code(&err)).or_default() += 1,
+        }
+    }
+    report.markup_bps.sort_by(f64::total_cmp);
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guard::PriceBounds;
+    use crate::rounding::{PriceRounding, RoundingDirection};
+
+    fn pair() -> TokenPairConfig {
+        TokenPairConfig::new(
+            "0x4200000000000000000000000000000000000006",
+            "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+        )
+        .unwrap()
+    }
+
+    fn sample(timestamp: i64, price: i128) -> anyhow::Result<PriceSample> {
+        Ok(PriceSample {
+            timestamp,
+            price,
+            expo: -8,
+        })
+    }
+
+    #[test]
+    fn test_read_csv() {
+        let csv = "timestamp,price\n1700000000,3100.12\n\n2023-11-14T22:13:30Z,2\n";
+        let samples = read_csv(csv.as_bytes()).unwrap();
+        assert_eq!(
+            samples,
+            [
+                PriceSample {
+                    timestamp: 1700000000,
+                    price: 310012,
+                    expo: -2
+                },
+                PriceSample {
+                    timestamp: 1700000010,
+                    price: 2,
+                    expo: 0
+                },
+            ]
+        );
+        assert!(read_csv("1700000000;3100".as_bytes()).is_err());
+        assert!(read_csv("1700000000,31.0.0".as_bytes()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replay_counts_refusals() {
+        let samples = vec![
+            sample(1, 310012345678),
+            sample(2, 0),
+            Err(HermesError::RateLimited { retry_after: None }.into()),
+            Err(anyhow::anyhow!("connection reset")),
+        ];
+        let report = replay(&samples, PriceDirection::AsIs, &pair(), "feed", &[]).await;
+        assert_eq!(report.samples, 4);
+        assert_eq!(report.refused["invalid_upstream_price"], 1);
+        assert_eq!(report.refused["upstream_rate_limited"], 1);
+        assert_eq!(report.refused["internal_error"], 1);
+        assert_eq!(report.markup_bps, [0.0]);
+    }
+
+    #[tokio::test]
+    async fn test_replay_applies_guards() {
+        let samples = [sample(1, 310012345678), sample(2, 410012345678)];
+        let guards: [Arc<dyn PricingGuard>; 1] =
+            [Arc::new(PriceBounds::new(None, Some(4000.0)).unwrap())];
+        let report = replay(&samples, PriceDirection::AsIs, &pair(), "feed", &guards).await;
+        assert_eq!(report.refused[crate::guard::REJECTED_CODE], 1);
+        assert_eq!(report.markup_bps.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_replay_reports_rounding_markup() {
+        let samples = [sample(1, 310012345678)];
+        let rounding = PriceRounding::significant_digits(4, RoundingDirection::Up).unwrap();
+        let report = replay(
+            &samples,
+            PriceDirection::AsIs,
+            &pair().with_rounding(Some(rounding)),
+            "feed",
+            &[],
+        )
+        .await;
+        // 3101 / 3100.12345678 - 1
+        assert!((report.markup_bps[0] - 2.8267).abs() < 1e-3);
+
+        let pair = pair().with_pipeline("[invert?, mul 1.001]".parse().unwrap());
+        for direction in [PriceDirection::AsIs, PriceDirection::Inverted] {
+            let report = replay(&samples, direction, &pair, "feed", &[]).await;
+            assert!((report.markup_bps[0] - 10.0).abs() < 1e-6);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_feed_inverted() {
+        // Markup is measured against the flipped feed price, so there is none
+        let samples = [Ok(PriceSample {
+            timestamp: 1,
+            price: 150,
+            expo: 0,
+        })];
+        let pair = pair().with_feed_inverted(true);
+        for direction in [PriceDirection::AsIs, PriceDirection::Inverted] {
+            let report = replay(&samples, direction, &pair, "feed", &[]).await;
+            assert!(report.markup_bps[0].abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use axum::body::Bytes;
+use axum::extract::{rejection::QueryRejection, Extension, Query, RawQuery, State};
+use axum::http::{header, HeaderMap};
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::{
+    auth, encoding, idempotency, msgpack, oracle, quota, AppError, AppState, ContextQuery,
+    ErrorResponse, OracleRequestError,
+};
+
+/// Most requests accepted in one `/context/batch` call.
+pub const MAX_BATCH_SIZE: usize = 500;
+
+/// Outcome of one request in a batch: a signed context, or why it was refused.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct BatchResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quote: Option<oracle::OracleResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<ErrorResponse>,
+}
+
+/// Response for `POST /context/batch`.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct BatchResponse {
+    /// One result per request, in request order
+    results: Vec<BatchResult>,
+}
+
+/// The ABI-encoded requests in a batch body: a MessagePack array of binary (or `0x` hex
+/// string) values when the content type is MessagePack, otherwise a JSON array of `0x` hex
+/// strings.
+fn decode_requests(headers: &HeaderMap, body: &[u8]) -> Result<Vec<Vec<u8>>, OracleRequestError> {
+    let is_msgpack = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|media| msgpack::is_msgpack(media.trim()));
+    let requests = if is_msgpack {
+        msgpack::decode_binary_array(body)
+            .map_err(|e| OracleRequestError::InvalidBatch(e.to_string()))?
+    } else {
+        serde_json::from_slice::<Vec<String>>(body)
+            .map_err(|e| OracleRequestError::InvalidBatch(e.to_string()))?
+            .into_iter()
+            .map(String::into_bytes)
+            .collect()
+    };
+    if requests.is_empty() {
+        return Err(OracleRequestError::InvalidBatch("empty batch".to_string()));
+    }
+    if requests.len() > MAX_BATCH_SIZE {
+        return Err(OracleRequestError::InvalidBatch(format!(
+            "{} requests, at most {} allowed",
+            requests.len(),
+            MAX_BATCH_SIZE
+        )));
+    }
+    Ok(requests)
+}
+
+fn failure(err: &AppError) -> ErrorResponse {
+    match err {
+        AppError::Internal(e) => {
+            let error_id = crate::log_internal_error(e);
+            ErrorResponse {
+                error: err.error_code().to_string(),
+                detail: crate::redacted_detail(&error_id),
+                error_id: Some(error_id),
+                guard: None,
+            }
+        }
+        _ => ErrorResponse::from(err),
+    }
+}
+
+/// POST handler — signs many `/context` requests in one round trip, for solvers quoting
+/// large sets of orders. Each request is handled exactly as `/context` would handle it, and a
+/// refused request doesn't fail the rest of the batch. Bodies and responses can be
+/// MessagePack, which carries the orders and signatures as binary rather than hex.
+#[utoipa::path(
+    post,
+    path = "/context/batch",
+    params(
+        ContextQuery,
+        ("X-Order-Version" = Option<String>, Header, description = "Pin the order ABI version (3 or 4) for every request instead of auto-detecting"),
+        ("X-Taker-Id" = Option<String>, Header, description = "Taker identifier for logs, metrics and audit records"),
+    ),
+    request_body(
+        description = "Up to 500 ABI-encoded `/context` bodies: a JSON array of 0x-prefixed hex strings, or a MessagePack array of binary values",
+        content(
+            (Vec<String> = "application/json"),
+            (Vec<u8> = "application/msgpack"),
+        ),
+    ),
+    responses(
+        (status = 200, description = "A result per request, as CBOR or MessagePack when preferred by `Accept`",
+            content(
+                (BatchResponse = "application/json"),
+                (BatchResponse = "application/cbor"),
+                (BatchResponse = "application/msgpack"),
+            )),
+        (status = 400, description = "The body is not a list of 1 to 500 requests, or carries an Idempotency-Key", body = crate::ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials (when auth is enabled)", body = crate::ErrorResponse),
+        (status = 429, description = "Daily quota for the API key is exhausted", body = crate::ErrorResponse),
+    ),
+)]
+pub(crate) async fn context_batch(
+    State(state): State<Arc<AppState>>,
+    query: Result<Query<ContextQuery>, QueryRejection>,
+    identity: Option<Extension<auth::Authenticated>>,
+    api_key: Option<Extension<quota::ApiKeyName>>,
+    RawQuery(raw_query): RawQuery,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, AppError> {
+    let Query(query) = query.map_err(|e| OracleRequestError::InvalidQuery(e.body_text()))?;
+    // Each request would share the key with a different body, so every retry but the first
+    // would be refused as a reuse.
+    if headers.contains_key(idempotency::IDEMPOTENCY_KEY_HEADER) {
+        return Err(OracleRequestError::InvalidIdempotencyKey(
+            "not supported on /context/batch".to_string(),
+        )
+        .into());
+    }
+    let requests = decode_requests(&headers, &body)?;
+    let key_name = api_key
+        .as_ref()
+        .map(|Extension(quota::ApiKeyName(name))| name.as_str());
+    let mut results = Vec::with_capacity(requests.len());
+    let mut issued = 0;
+    for request in &requests {
+        // The middleware reserved the first quote; every one after it needs its own
+        let reserved = if issued > 0 {
+            match quota::reserve_extra(&state, key_name).await {
+                Ok(reserved) => reserved,
+                Err(e) => {
+                    results.push(BatchResult {
+                        quote: None,
+                        error: Some(failure(&e)),
+                    });
+                    continue;
+                }
+            }
+        } else {
+            None
+        };
+        let result = crate::signed_context_recorded(
+            &state,
+            Ok(Query(ContextQuery {
+                expiry_seconds: query.expiry_seconds,
+            })),
+            raw_query.as_deref(),
+            identity.clone(),
+            api_key.clone(),
+            &headers,
+            request,
+        )
+        .await;
+        results.push(match result {
+            Ok((quote, _)) => {
+                issued += 1;
+                BatchResult {
+                    quote: Some(quote),
+                    error: None,
+                }
+            }
+            Err(e) => {
+                quota::release(&state, key_name, reserved).await;
+                BatchResult {
+                    quote: None,
+                    error: Some(failure(&e)),
+                }
+            }
+        });
+    }
+    let format = encoding::ResponseFormat::from_headers(&headers);
+    Ok((
+        Extension(quota::QuotesIssued(issued)),
+        [(header::VARY, "accept")],
+        format.respond(BatchResponse { results }),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_requests() {
+        let json = HeaderMap::new();
+        assert_eq!(
+            decode_requests(&json, br#"["0x01", "0x02"]"#).unwrap(),
+            [b"0x01".to_vec(), b"0x02".to_vec()]
+        );
+        assert!(decode_requests(&json, b"[]").is_err());
+        assert!(decode_requests(&json, b"0x01").is_err());
+        let too_many = serde_json::to_vec(&vec!["0x01"; MAX_BATCH_SIZE + 1]).unwrap();
+        assert!(decode_requests(&json, &too_many).is_err());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            "application/x-msgpack; charset=binary".parse().unwrap(),
+        );
+        assert_eq!(
+            decode_requests(&headers, &[0x91, 0xc4, 2, 0, 1]).unwrap(),
+            [vec![0, 1]]
+        );
+    }
+}
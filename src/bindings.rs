@@ -0,0 +1,44 @@
+use ts_rs::TS;
+
+use crate::jsonrpc::SignedContextParams;
+use crate::oracle::{CoSignature, Diagnostics, OracleResponse, QuotePrice};
+use crate::{ErrorResponse, PriceDirection};
+
+/// Where the declarations are written, relative to the crate root.
+pub const OUTPUT_PATH: &str = "bindings/oracle.d.ts";
+
+/// TypeScript declarations for the JSON bodies clients receive from `/context` (and its
+/// errors) and send to `oracle_getSignedContext`, in one file the SDK can import as is.
+pub fn declarations() -> String {
+    let mut out = String::from("// Generated by `cargo test --features ts`. Do not edit.\n");
+    for decl in [
+        OracleResponse::decl(),
+        CoSignature::decl(),
+        QuotePrice::decl(),
+        Diagnostics::decl(),
+        PriceDirection::decl(),
+        ErrorResponse::decl(),
+        SignedContextParams::decl(),
+    ] {
+        out.push_str("\nexport ");
+        out.push_str(&decl);
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// The build step: writes [`OUTPUT_PATH`], so a diff shows when a payload changed shape.
+    #[test]
+    fn test_export_bindings() {
+        let declarations = declarations();
+        assert!(declarations.contains("export type OracleResponse = "));
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(OUTPUT_PATH);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, declarations).unwrap();
+    }
+}
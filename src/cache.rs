@@ -0,0 +1,108 @@
+use alloy::primitives::Address;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::oracle::OracleResponse;
+use crate::PriceDirection;
+
+/// What makes two quote requests interchangeable: same pair, direction and context layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub direction: PriceDirection,
+    pub decimal_shift: i32,
+    pub expiry_seconds: u64,
+}
+
+/// Short-lived cache of fully signed responses, so a burst of identical requests within
+/// one price tick is served without fetching or signing again.
+pub struct ResponseCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<CacheKey, (Instant, OracleResponse)>>,
+}
+
+impl ResponseCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The cached response for `key`, if it was stored less than the TTL before `now`.
+    pub fn get(&self, key: &CacheKey, now: Instant) -> Option<OracleResponse> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(key)
+            .filter(|(stored_at, _)| now.saturating_duration_since(*stored_at) < self.ttl)
+            .map(|(_, response)| response.clone())
+    }
+
+    /// Store `response` for `key`, dropping any entries that have expired.
+    pub fn insert(&self, key: CacheKey, response: OracleResponse, now: Instant) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (stored_at, _)| now.saturating_duration_since(*stored_at) < self.ttl);
+        entries.insert(key, (now, response));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::Bytes;
+
+    fn key(direction: PriceDirection) -> CacheKey {
+        CacheKey {
+            base_token: Address::repeat_byte(1),
+            quote_token: Address::repeat_byte(2),
+            direction,
+            decimal_shift: 0,
+            expiry_seconds: 5,
+        }
+    }
+
+    fn response() -> OracleResponse {
+        OracleResponse {
+            quote_id: uuid::Uuid::new_v4(),
+            signer: Address::repeat_byte(3),
+            context: vec![],
+            signature: Bytes::from_static(b"sig"),
+            cosignatures: vec![],
+            nonce: None,
+            price: None,
+            debug: None,
+        }
+    }
+
+    #[test]
+    fn test_hit_within_ttl() {
+        let cache = ResponseCache::new(Duration::from_millis(200));
+        let now = Instant::now();
+        let cached = response();
+        cache.insert(key(PriceDirection::AsIs), cached.clone(), now);
+
+        let hit = cache
+            .get(&key(PriceDirection::AsIs), now + Duration::from_millis(150))
+            .unwrap();
+        assert_eq!(hit.quote_id, cached.quote_id);
+        assert!(cache
+            .get(&key(PriceDirection::AsIs), now + Duration::from_millis(200))
+            .is_none());
+    }
+
+    #[test]
+    fn test_keyed_by_direction_and_expiry() {
+        let cache = ResponseCache::new(Duration::from_millis(200));
+        let now = Instant::now();
+        cache.insert(key(PriceDirection::AsIs), response(), now);
+
+        assert!(cache.get(&key(PriceDirection::Inverted), now).is_none());
+        let longer = CacheKey {
+            expiry_seconds: 30,
+            ..key(PriceDirection::AsIs)
+        };
+        assert!(cache.get(&longer, now).is_none());
+    }
+}
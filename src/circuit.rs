@@ -0,0 +1,151 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[derive(Debug, Default)]
+struct FeedCircuit {
+    consecutive_failures: u32,
+    /// Unix seconds until which fetches are refused, while the circuit is open
+    open_until: Option<u64>,
+}
+
+/// Stops fetching a feed after `threshold` consecutive upstream failures, so requests fail
+/// fast for `cooldown` instead of each waiting out a timeout against a dead upstream. Once
+/// the cooldown has passed, one request is let through as a probe: if it succeeds the circuit
+/// closes, otherwise it stays open for another cooldown.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    feeds: Mutex<BTreeMap<String, FeedCircuit>>,
+}
+
+impl CircuitBreaker {
+    /// `threshold` must be at least 1 and `cooldown` at least a second.
+    pub fn new(threshold: u32, cooldown: Duration) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            threshold > 0,
+            "Circuit breaker threshold must be at least 1"
+        );
+        anyhow::ensure!(
+            cooldown >= Duration::from_secs(1),
+            "Circuit breaker cooldown must be at least 1 second"
+        );
+        Ok(Self {
+            threshold,
+            cooldown,
+            feeds: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    /// Whether `feed_id` may be fetched at unix time `now`. While the circuit is open, returns
+    /// the seconds until the next probe is allowed.
+    pub fn check(&self, feed_id: &str, now: u64) -> Result<(), u64> {
+        let mut feeds = self.feeds.lock().unwrap();
+        let Some(circuit) = feeds.get_mut(feed_id) else {
+            return Ok(());
+        };
+        match circuit.open_until {
+            Some(until) if now < until => Err(until - now),
+            Some(_) => {
+                // This request is the probe; others keep failing fast until it reports back
+                // (or for another cooldown, if it never does)
+                circuit.open_until = Some(now + self.cooldown.as_secs());
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Record the outcome of a fetch of `feed_id` at unix time `now`. Returns whether this
+    /// failure opened the circuit.
+    pub fn record(&self, feed_id: &str, now: u64, success: bool) -> bool {
+        let mut feeds = self.feeds.lock().unwrap();
+        let circuit = feeds.entry(feed_id.to_string()).or_default();
+        if success {
+            if circuit.open_until.take().is_some() {
+                tracing::info!("Circuit for feed {} closed", feed_id);
+            }
+            circuit.consecutive_failures = 0;
+            return false;
+        }
+        circuit.consecutive_failures += 1;
+        let mut opened = false;
+        if circuit.consecutive_failures >= self.threshold {
+            if circuit.open_until.is_none() {
+                opened = true;
+                tracing::warn!(
+                    "Circuit for feed {} opened after {} consecutive failures; failing fast for {}s",
+                    feed_id,
+                    circuit.consecutive_failures,
+                    self.cooldown.as_secs()
+                );
+            }
+            circuit.open_until = Some(now + self.cooldown.as_secs());
+        }
+        opened
+    }
+
+    /// Each fetched feed and whether its circuit is open.
+    pub fn snapshot(&self) -> Vec<(String, bool)> {
+        self.feeds
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(feed_id, circuit)| (feed_id.clone(), circuit.open_until.is_some()))
+            .collect()
+    }
+
+    /// Render whether each fetched feed's circuit is open in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP oracle_feed_circuit_open Whether fetches of a feed are failing fast after repeated upstream failures.\n");
+        out.push_str("# TYPE oracle_feed_circuit_open gauge\n");
+        for (feed_id, open) in self.snapshot() {
+            let _ = writeln!(
+                out,
+                "oracle_feed_circuit_open{{feed=\"{}\"}} {}",
+                feed_id,
+                u8::from(open)
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_after_threshold_and_probes() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(30)).unwrap();
+        assert!(breaker.check("feed", 100).is_ok());
+        assert!(!breaker.record("feed", 100, false));
+        assert!(breaker.check("feed", 100).is_ok());
+        assert!(breaker.record("feed", 101, false));
+        assert_eq!(breaker.check("feed", 101), Err(30));
+        assert_eq!(breaker.check("feed", 120), Err(11));
+        assert!(breaker.check("other", 120).is_ok());
+        assert_eq!(breaker.snapshot(), vec![("feed".to_string(), true)]);
+        assert!(breaker
+            .render()
+            .contains("oracle_feed_circuit_open{feed=\"feed\"} 1"));
+
+        // One probe after the cooldown; the rest still fail fast
+        assert!(breaker.check("feed", 131).is_ok());
+        assert_eq!(breaker.check("feed", 131), Err(30));
+        // A failed probe keeps it open rather than opening it again
+        assert!(!breaker.record("feed", 132, false));
+        assert_eq!(breaker.check("feed", 140), Err(22));
+
+        assert!(breaker.check("feed", 162).is_ok());
+        breaker.record("feed", 162, true);
+        assert!(breaker.check("feed", 162).is_ok());
+        // A single failure doesn't reopen it
+        breaker.record("feed", 163, false);
+        assert!(breaker.check("feed", 163).is_ok());
+        assert!(CircuitBreaker::new(0, Duration::from_secs(30)).is_err());
+    }
+}
@@ -0,0 +1,220 @@
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::UdpSocket;
+
+/// Source of wall-clock time for expiry calculation.
+///
+/// Abstracted so handlers can be tested deterministically.
+pub trait Clock: Send + Sync {
+    /// Time since the unix epoch.
+    fn now(&self) -> Duration;
+}
+
+/// The system wall clock.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system clock is before the unix epoch")
+    }
+}
+
+/// A clock frozen at a fixed instant (for tests).
+pub struct FixedClock(pub Duration);
+
+impl Clock for FixedClock {
+    fn now(&self) -> Duration {
+        self.0
+    }
+}
+
+/// Seconds between the NTP epoch (1900) and the unix epoch (1970).
+const NTP_UNIX_OFFSET_SECS: u64 = 2_208_988_800;
+
+/// Encode a unix time as a 64-bit NTP timestamp (32.32 fixed point seconds since 1900).
+fn encode_ntp_timestamp(unix: Duration) -> [u8; 8] {
+    let secs = (unix.as_secs() + NTP_UNIX_OFFSET_SECS) as u32;
+    let frac = ((unix.subsec_nanos() as u64) << 32) / 1_000_000_000;
+    let mut out = [0u8; 8];
+    out[..4].copy_from_slice(&secs.to_be_bytes());
+    out[4..].copy_from_slice(&(frac as u32).to_be_bytes());
+    out
+}
+
+/// Decode a 64-bit NTP timestamp into unix milliseconds.
+fn decode_ntp_timestamp(bytes: &[u8]) -> i64 {
+    let secs = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as i64;
+    let frac = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as i64;
+    (secs - NTP_UNIX_OFFSET_SECS as i64) * 1000 + ((frac * 1000) >> 32)
+}
+
+/// Local clock skew from the standard NTP offset formula, in milliseconds.
+///
+/// t1 = client transmit, t2 = server receive, t3 = server transmit, t4 = client receive.
+/// Positive means the local clock is ahead of the server.
+fn skew_ms(t1: i64, t2: i64, t3: i64, t4: i64) -> i64 {
+    -((t2 - t1) + (t3 - t4)) / 2
+}
+
+/// Measure local clock skew against an NTP server (SNTP, single round trip), in milliseconds.
+pub async fn measure_skew(server: &str, clock: &dyn Clock) -> anyhow::Result<i64> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    socket.connect(server).await?;
+
+    // LI = 0, VN = 3, Mode = 3 (client)
+    let mut request = [0u8; 48];
+    request[0] = 0x1B;
+    let t1 = clock.now();
+    request[40..48].copy_from_slice(&encode_ntp_timestamp(t1));
+    socket.send(&request).await?;
+
+    let mut response = [0u8; 48];
+    let len = tokio::time::timeout(Duration::from_secs(5), socket.recv(&mut response))
+        .await
+        .map_err(|_| anyhow::anyhow!("NTP request to {} timed out", server))??;
+    let t4 = clock.now();
+    if len < 48 {
+        anyhow::bail!("Short NTP response from {} ({} bytes)", server, len);
+    }
+
+    let t2 = decode_ntp_timestamp(&response[32..40]);
+    let t3 = decode_ntp_timestamp(&response[40..48]);
+    Ok(skew_ms(
+        t1.as_millis() as i64,
+        t2,
+        t3,
+        t4.as_millis() as i64,
+    ))
+}
+
+/// Tracks the most recently measured clock skew against a threshold.
+pub struct ClockSkewMonitor {
+    max_skew_ms: i64,
+    refuse_when_skewed: bool,
+    last_skew_ms: AtomicI64,
+    skewed: AtomicBool,
+}
+
+impl ClockSkewMonitor {
+    pub fn new(max_skew: Duration, refuse_when_skewed: bool) -> Self {
+        Self {
+            max_skew_ms: max_skew.as_millis() as i64,
+            refuse_when_skewed,
+            last_skew_ms: AtomicI64::new(0),
+            skewed: AtomicBool::new(false),
+        }
+    }
+
+    /// Record a measurement, returning whether it exceeds the threshold.
+    pub fn record(&self, skew_ms: i64) -> bool {
+        let skewed = skew_ms.abs() > self.max_skew_ms;
+        self.last_skew_ms.store(skew_ms, Ordering::Relaxed);
+        self.skewed.store(skewed, Ordering::Relaxed);
+        skewed
+    }
+
+    /// Most recently measured skew in milliseconds (positive = local clock ahead).
+    pub fn last_skew_ms(&self) -> i64 {
+        self.last_skew_ms.load(Ordering::Relaxed)
+    }
+
+    /// Whether quotes should currently be refused because of clock skew.
+    pub fn should_refuse(&self) -> bool {
+        self.refuse_when_skewed && self.skewed.load(Ordering::Relaxed)
+    }
+
+    /// Measure once against `server` and record the result, logging excessive skew.
+    pub async fn check(&self, server: &str, clock: &dyn Clock) -> anyhow::Result<i64> {
+        let skew = measure_skew(server, clock).await?;
+        if self.record(skew) {
+            tracing::warn!(
+                "Local clock skew {}ms exceeds {}ms (NTP server {}){}",
+                skew,
+                self.max_skew_ms,
+                server,
+                if self.refuse_when_skewed {
+                    " — refusing to sign quotes"
+                } else {
+                    ""
+                }
+            );
+        } else {
+            tracing::debug!("Local clock skew {}ms (NTP server {})", skew, server);
+        }
+        Ok(skew)
+    }
+
+    /// Re-check skew periodically in the background. Failed measurements are logged and
+    /// leave the previous result in place.
+    pub fn spawn_periodic(
+        self: Arc<Self>,
+        server: String,
+        clock: Arc<dyn Clock>,
+        interval: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.check(&server, clock.as_ref()).await {
+                    tracing::warn!("NTP clock check failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ntp_timestamp_roundtrip() {
+        let unix = Duration::from_millis(1_700_000_000_123);
+        let encoded = encode_ntp_timestamp(unix);
+        assert_eq!(decode_ntp_timestamp(&encoded), 1_700_000_000_122);
+    }
+
+    #[test]
+    fn test_skew_ms() {
+        // Server 500ms behind local, 20ms round trip
+        assert_eq!(skew_ms(1000, 510, 510, 1020), 500);
+        // Server 300ms ahead of local
+        assert_eq!(skew_ms(1000, 1310, 1310, 1020), -300);
+        // In sync
+        assert_eq!(skew_ms(1000, 1010, 1010, 1020), 0);
+    }
+
+    #[test]
+    fn test_monitor_threshold() {
+        let monitor = ClockSkewMonitor::new(Duration::from_millis(1000), true);
+        assert!(!monitor.should_refuse());
+
+        assert!(!monitor.record(999));
+        assert!(!monitor.should_refuse());
+
+        assert!(monitor.record(-1500));
+        assert!(monitor.should_refuse());
+        assert_eq!(monitor.last_skew_ms(), -1500);
+
+        assert!(!monitor.record(10));
+        assert!(!monitor.should_refuse());
+    }
+
+    #[test]
+    fn test_monitor_log_only() {
+        let monitor = ClockSkewMonitor::new(Duration::from_millis(1000), false);
+        assert!(monitor.record(5000));
+        assert!(!monitor.should_refuse());
+    }
+
+    #[test]
+    fn test_fixed_clock() {
+        let clock = FixedClock(Duration::from_secs(42));
+        assert_eq!(clock.now(), Duration::from_secs(42));
+    }
+}
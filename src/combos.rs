@@ -0,0 +1,229 @@
+use alloy::primitives::Address;
+use axum::body::Bytes;
+use axum::extract::{rejection::QueryRejection, Extension, Query, State};
+use axum::http::HeaderMap;
+use axum::Json;
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::{
+    auth, metrics, oracle, quota, AppError, AppState, ContextQuery, OracleRequestError,
+    PriceDirection,
+};
+
+/// A signed quote for one `(input, output)` IO combination of the order.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ComboQuote {
+    pub input_io_index: usize,
+    pub output_io_index: usize,
+    #[schema(value_type = String)]
+    pub input_token: Address,
+    #[schema(value_type = String)]
+    pub output_token: Address,
+    #[serde(flatten)]
+    pub quote: oracle::OracleResponse,
+}
+
+/// Response for `POST /context/all`.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ComboQuotesResponse {
+    /// One quote per IO combination matching the configured pair, inputs first
+    pub quotes: Vec<ComboQuote>,
+}
+
+/// POST handler — quotes every `(input, output)` combination of the order that maps to the
+/// configured pair, e.g. USDC and USDT inputs against a WETH output, so takers can pick the
+/// best route. The body's IO indexes are ignored.
+#[utoipa::path(
+    post,
+    path = "/context/all",
+    params(
+        ContextQuery,
+        ("X-Order-Version" = Option<String>, Header, description = "Pin the order ABI version (3 or 4) instead of auto-detecting"),
+        ("X-Taker-Id" = Option<String>, Header, description = "Taker identifier for logs, metrics and audit records"),
+    ),
+    request_body(
+        description = "Same body as `/context`; the IO indexes are ignored",
+        content(
+            (Vec<u8> = "application/octet-stream"),
+            (String = "text/plain"),
+        ),
+    ),
+    responses(
+        (status = 200, description = "A signed context per matching IO combination", body = ComboQuotesResponse),
+        (status = 400, description = "Invalid request, or no IO combination matches the configured pair", body = crate::ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials (when auth is enabled)", body = crate::ErrorResponse),
+        (status = 429, description = "Daily quota for the API key is exhausted", body = crate::ErrorResponse),
+        (status = 503, description = "Temporarily unable to sign", body = crate::ErrorResponse),
+    ),
+)]
+pub(crate) async fn context_all(
+    State(state): State<Arc<AppState>>,
+    query: Result<Query<ContextQuery>, QueryRejection>,
+    identity: Option<Extension<auth::Authenticated>>,
+    api_key: Option<Extension<quota::ApiKeyName>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(Extension<quota::QuotesIssued>, Json<ComboQuotesResponse>), AppError> {
+    let started = std::time::Instant::now();
+    let mut labels = metrics::RequestLabels::default();
+    let identity = identity.as_ref().map(|Extension(identity)| identity);
+    let api_key = api_key.map(|Extension(quota::ApiKeyName(name))| name);
+    let result = quote_all(
+        &state,
+        query,
+        identity,
+        api_key,
+        &headers,
+        &body,
+        &mut labels,
+    )
+    .await;
+    state.metrics.record(
+        labels,
+        result.as_ref().err().map(AppError::error_code),
+        started.elapsed(),
+    );
+    let response = result?;
+    let issued = quota::QuotesIssued(response.quotes.len() as u64);
+    Ok((Extension(issued), Json(response)))
+}
+
+async fn quote_all(
+    state: &AppState,
+    query: Result<Query<ContextQuery>, QueryRejection>,
+    identity: Option<&auth::Authenticated>,
+    api_key: Option<String>,
+    headers: &HeaderMap,
+    body: &[u8],
+    labels: &mut metrics::RequestLabels,
+) -> Result<ComboQuotesResponse, AppError> {
+    let Query(query) = query.map_err(|e| OracleRequestError::InvalidQuery(e.body_text()))?;
+    let expiry_seconds = state.effective_expiry_seconds(query.expiry_seconds);
+    let crate::CheckedRequest { request, taker_id } =
+        crate::check_request(state, identity, headers, body, labels)?;
+    let order = &request.order;
+    let order_nonce = order.into();
+    crate::order_nonce::check(state, &order_nonce).await?;
+
+    let combos = combinations(state, &order.valid_inputs, &order.valid_outputs)?;
+    if let Some((_, _, direction)) = combos.first() {
+        labels.set_pair(
+            state.token_pair.base_token,
+            state.token_pair.quote_token,
+            *direction,
+        );
+    }
+    // The middleware reserved the first quote; refuse up front if the rest don't fit the quota
+    let mut reserved = Vec::with_capacity(combos.len().saturating_sub(1));
+    for _ in 1..combos.len() {
+        match crate::quota::reserve_extra(state, api_key.as_deref()).await {
+            Ok(day) => reserved.push(day),
+            Err(e) => {
+                release_all(state, api_key.as_deref(), reserved).await;
+                return Err(e);
+            }
+        }
+    }
+    let result = sign_all(
+        state,
+        &request,
+        &combos,
+        expiry_seconds,
+        api_key.clone(),
+        taker_id,
+    )
+    .await;
+    if result.is_err() {
+        release_all(state, api_key.as_deref(), reserved).await;
+    }
+    let quotes = result?;
+    crate::order_nonce::record(state, &order_nonce).await;
+    Ok(ComboQuotesResponse { quotes })
+}
+
+/// Give back quotes reserved for combinations that weren't signed.
+async fn release_all(state: &AppState, api_key: Option<&str>, reserved: Vec<Option<u64>>) {
+    for day in reserved {
+        quota::release(state, api_key, day).await;
+    }
+}
+
+/// Sign a quote for each of `combos`, failing on the first that can't be signed.
+async fn sign_all(
+    state: &AppState,
+    request: &crate::order::OracleRequest,
+    combos: &[(usize, usize, PriceDirection)],
+    expiry_seconds: u64,
+    api_key: Option<String>,
+    taker_id: Option<String>,
+) -> Result<Vec<ComboQuote>, AppError> {
+    let order = &request.order;
+    let mut quotes = Vec::with_capacity(combos.len());
+    for &(input_io_index, output_io_index, direction) in combos {
+        let input = order.valid_inputs[input_io_index];
+        let output = order.valid_outputs[output_io_index];
+        let decimal_shift = state.decimal_shift(input, output).await?;
+        let vault = crate::inventory::Vault {
+            version: order.version,
+            owner: order.owner,
+            output,
+        };
+        let _reservation = crate::inventory::reserve(state, &vault).await?;
+        let quote = crate::issue_quote(
+            state,
+            direction,
+            decimal_shift,
+            expiry_seconds,
+            request.counterparty,
+            api_key.clone(),
+            taker_id.clone(),
+            Some((order.hash, &vault)),
+        )
+        .await?;
+        quotes.push(ComboQuote {
+            input_io_index,
+            output_io_index,
+            input_token: input.token,
+            output_token: output.token,
+            quote,
+        });
+    }
+    Ok(quotes)
+}
+
+/// `(input index, output index, direction)` of every IO combination matching the configured
+/// pair. Fails with the first combination's error if none match.
+fn combinations(
+    state: &AppState,
+    inputs: &[crate::order::Io],
+    outputs: &[crate::order::Io],
+) -> Result<Vec<(usize, usize, PriceDirection)>, OracleRequestError> {
+    let mut combos = Vec::new();
+    let mut first_err = None;
+    for (i, input) in inputs.iter().enumerate() {
+        for (o, output) in outputs.iter().enumerate() {
+            match state.price_direction(input.token, output.token) {
+                Ok(direction) => combos.push((i, o, direction)),
+                Err(e) => {
+                    first_err.get_or_insert(e);
+                }
+            }
+        }
+    }
+    if !combos.is_empty() {
+        return Ok(combos);
+    }
+    Err(first_err.unwrap_or_else(|| {
+        let (kind, len) = if inputs.is_empty() {
+            ("input", inputs.len())
+        } else {
+            ("output", outputs.len())
+        };
+        OracleRequestError::InvalidIndex {
+            kind,
+            index: 0,
+            len,
+        }
+    }))
+}
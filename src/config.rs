@@ -0,0 +1,196 @@
+use clap::Command;
+use std::path::{Path, PathBuf};
+
+/// Load a `.env` file from the working directory or a parent, if there is one. Variables
+/// already set in the environment take precedence over the file.
+pub fn load_dotenv() -> anyhow::Result<Option<PathBuf>> {
+    match dotenvy::dotenv() {
+        Ok(path) => Ok(Some(path)),
+        Err(e) if e.not_found() => Ok(None),
+        Err(e) => Err(anyhow::anyhow!("Failed to load .env: {}", e)),
+    }
+}
+
+/// Parse a config file: a JSON object of environment variable names to values. Lists are
+/// joined with commas, as the comma-separated variables expect.
+pub fn parse(json: &str) -> anyhow::Result<Vec<(String, String)>> {
+    let settings: serde_json::Map<String, serde_json::Value> = serde_json::from_str(json)?;
+    settings
+        .into_iter()
+        .map(|(name, value)| {
+            let value = match value {
+                serde_json::Value::Array(values) => values
+                    .into_iter()
+                    .map(scalar)
+                    .collect::<Option<Vec<_>>>()
+                    .map(|values| values.join(",")),
+                value => scalar(value),
+            }
+            .ok_or_else(|| anyhow::anyhow!("{} must be a string, number, bool or list", name))?;
+            Ok((name, value))
+        })
+        .collect()
+}
+
+fn scalar(value: serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) => Some(s),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        serde_json::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Read a config file.
+pub fn load(path: &Path) -> anyhow::Result<Vec<(String, String)>> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    parse(&json).map_err(|e| anyhow::anyhow!("Invalid config file {}: {}", path.display(), e))
+}
+
+/// Environment variables read by `command` and its subcommands.
+fn env_names(command: &Command) -> Vec<String> {
+    command
+        .get_arguments()
+        .filter_map(|arg| arg.get_env())
+        .map(|name| name.to_string_lossy().into_owned())
+        .chain(command.get_subcommands().flat_map(env_names))
+        .collect()
+}
+
+/// Make each setting the default of the arguments of `command` (and its subcommands) read from
+/// that variable. The defaults are hidden from help and usage errors, since the file may hold
+/// secrets.
+fn with_defaults(command: Command, settings: &[(String, String)]) -> Command {
+    let defaults: Vec<(String, String)> = command
+        .get_arguments()
+        .filter_map(|arg| {
+            let name = arg.get_env()?.to_str()?;
+            let (_, value) = settings.iter().find(|(setting, _)| setting == name)?;
+            Some((arg.get_id().to_string(), value.clone()))
+        })
+        .collect();
+    let subcommands: Vec<String> = command
+        .get_subcommands()
+        .map(|subcommand| subcommand.get_name().to_string())
+        .collect();
+    let command = defaults.into_iter().fold(command, |command, (id, value)| {
+        command.mut_arg(id, |arg| arg.default_value(value).hide_default_value(true))
+    });
+    subcommands.into_iter().fold(command, |command, name| {
+        command.mut_subcommand(name, |subcommand| with_defaults(subcommand, settings))
+    })
+}
+
+/// Use config file settings as defaults: each becomes the default of the argument read from
+/// that variable, so flags and the environment win over the file, and the file over built-in
+/// defaults. The process environment isn't touched. Names `command` doesn't read are rejected
+/// so a typo can't go unnoticed. Also returns the names that take effect, i.e. those `is_set`
+/// says aren't already in the environment.
+pub fn apply(
+    command: Command,
+    settings: Vec<(String, String)>,
+    is_set: impl Fn(&str) -> bool,
+) -> anyhow::Result<(Command, Vec<String>)> {
+    let known = env_names(&command);
+    if let Some((name, _)) = settings.iter().find(|(name, _)| !known.contains(name)) {
+        anyhow::bail!("Unknown setting {} in config file", name);
+    }
+    let applied = settings
+        .iter()
+        .map(|(name, _)| name.clone())
+        .filter(|name| !is_set(name))
+        .collect();
+    Ok((with_defaults(command, &settings), applied))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let settings = parse(
+            r#"{"PORT": 8080, "ALLOW_DEGRADED_START": true, "BASE_TOKEN_ALIASES": ["0xa", "0xb"]}"#,
+        )
+        .unwrap();
+        assert!(settings.contains(&("PORT".to_string(), "8080".to_string())));
+        assert!(settings.contains(&("ALLOW_DEGRADED_START".to_string(), "true".to_string())));
+        assert!(settings.contains(&("BASE_TOKEN_ALIASES".to_string(), "0xa,0xb".to_string())));
+        assert!(parse(r#"{"PORT": {"value": 1}}"#).is_err());
+        assert!(parse("[]").is_err());
+    }
+
+    fn command() -> Command {
+        Command::new("test")
+            .arg(
+                clap::Arg::new("port")
+                    .long("port")
+                    .env("CONFIG_TEST_PORT")
+                    .default_value("3000"),
+            )
+            .arg(
+                clap::Arg::new("aliases")
+                    .long("aliases")
+                    .env("CONFIG_TEST_ALIASES")
+                    .value_delimiter(','),
+            )
+            .subcommand(
+                Command::new("export")
+                    .arg(clap::Arg::new("url").long("url").env("CONFIG_TEST_URL")),
+            )
+    }
+
+    fn settings(settings: &[(&str, &str)]) -> Vec<(String, String)> {
+        settings
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_settings_are_defaults() {
+        let file = settings(&[
+            ("CONFIG_TEST_PORT", "8080"),
+            ("CONFIG_TEST_ALIASES", "0xa,0xb"),
+            ("CONFIG_TEST_URL", "sqlite::memory:"),
+        ]);
+        let (command, applied) = apply(command(), file, |name| name == "CONFIG_TEST_PORT").unwrap();
+        assert_eq!(applied, ["CONFIG_TEST_ALIASES", "CONFIG_TEST_URL"]);
+
+        let matches = command
+            .clone()
+            .try_get_matches_from(["test", "export"])
+            .unwrap();
+        assert_eq!(matches.get_one::<String>("port").unwrap(), "8080");
+        let aliases: Vec<&String> = matches.get_many("aliases").unwrap().collect();
+        assert_eq!(aliases, ["0xa", "0xb"]);
+        let (_, export) = matches.subcommand().unwrap();
+        assert_eq!(export.get_one::<String>("url").unwrap(), "sqlite::memory:");
+
+        // Flags win over the file
+        let matches = command
+            .try_get_matches_from(["test", "--port", "9000"])
+            .unwrap();
+        assert_eq!(matches.get_one::<String>("port").unwrap(), "9000");
+    }
+
+    #[test]
+    fn test_settings_are_hidden_from_help() {
+        let file = settings(&[("CONFIG_TEST_URL", "postgres://user:hunter2@db")]);
+        let (mut command, _) = apply(command(), file, |_| false).unwrap();
+        let help = command
+            .find_subcommand_mut("export")
+            .unwrap()
+            .render_long_help()
+            .to_string();
+        assert!(help.contains("--url"));
+        assert!(!help.contains("hunter2"));
+    }
+
+    #[test]
+    fn test_unknown_setting() {
+        let typo = settings(&[("CONFIG_TEST_TYPO", "1")]);
+        assert!(apply(command(), typo, |_| false).is_err());
+    }
+}
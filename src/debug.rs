@@ -0,0 +1,101 @@
+use alloy::primitives::B256;
+use axum::extract::{rejection::QueryRejection, Query};
+use axum::Json;
+use rain_math_float::Float;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::{AppError, OracleRequestError};
+
+/// Query for `GET /debug/float` — exactly one of `value` or `hex`.
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct FloatQuery {
+    /// Decimal string to encode, e.g. "3100.12"
+    value: Option<String>,
+    /// Rain DecimalFloat bytes32 to decode, e.g. "0x..."
+    hex: Option<String>,
+}
+
+/// Both representations of a Rain DecimalFloat.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct FloatResponse {
+    /// Decimal string, as formatted by Rain
+    pub value: String,
+    /// Rain DecimalFloat bytes32
+    #[schema(value_type = String)]
+    pub hex: B256,
+}
+
+fn encode(value: &str) -> Result<FloatResponse, OracleRequestError> {
+    let float = Float::parse(value.to_string())
+        .map_err(|e| OracleRequestError::InvalidFloat(format!("'{}': {:?}", value, e)))?;
+    let formatted = float
+        .format()
+        .map_err(|e| OracleRequestError::InvalidFloat(format!("'{}': {:?}", value, e)))?;
+    Ok(FloatResponse {
+        value: formatted,
+        hex: float.into(),
+    })
+}
+
+fn decode(hex: &str) -> Result<FloatResponse, OracleRequestError> {
+    let bytes = B256::from_str(hex.trim())
+        .map_err(|e| OracleRequestError::InvalidFloat(format!("'{}': {}", hex, e)))?;
+    let value = Float::from(bytes)
+        .format()
+        .map_err(|e| OracleRequestError::InvalidFloat(format!("'{}': {:?}", hex, e)))?;
+    Ok(FloatResponse { value, hex: bytes })
+}
+
+/// GET handler — converts between decimal strings and Rain DecimalFloat bytes32,
+/// for integrators troubleshooting context contents.
+#[utoipa::path(
+    get,
+    path = "/debug/float",
+    params(FloatQuery),
+    responses(
+        (status = 200, description = "Both representations of the float", body = FloatResponse),
+        (status = 400, description = "Invalid query or float", body = crate::ErrorResponse),
+    ),
+)]
+pub async fn float(
+    query: Result<Query<FloatQuery>, QueryRejection>,
+) -> Result<Json<FloatResponse>, AppError> {
+    let Query(query) = query.map_err(|e| OracleRequestError::InvalidQuery(e.body_text()))?;
+    let response = match (query.value.as_deref(), query.hex.as_deref()) {
+        (Some(value), None) => encode(value)?,
+        (None, Some(hex)) => decode(hex)?,
+        _ => {
+            return Err(OracleRequestError::InvalidQuery(
+                "expected exactly one of `value` or `hex`".to_string(),
+            )
+            .into())
+        }
+    };
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        let encoded = encode("3100.12").unwrap();
+        let decoded = decode(&encoded.hex.to_string()).unwrap();
+        assert_eq!(decoded.hex, encoded.hex);
+        assert_eq!(decoded.value, "3100.12");
+    }
+
+    #[test]
+    fn test_encode_invalid_decimal() {
+        let err = encode("not a number").unwrap_err();
+        assert!(matches!(err, OracleRequestError::InvalidFloat(_)));
+    }
+
+    #[test]
+    fn test_decode_invalid_hex() {
+        let err = decode("0x1234").unwrap_err();
+        assert!(matches!(err, OracleRequestError::InvalidFloat(_)));
+    }
+}
@@ -0,0 +1,665 @@
+use alloy::primitives::Address;
+use axum::{
+    extract::State,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::{pyth::Hermes, AppState};
+
+/// One chain served from a shared process, read from a deployments file. Settings left out
+/// fall back to the top-level configuration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Deployment {
+    /// Path prefix the deployment's routes are served under, e.g. `base` for `/base/context`
+    pub name: String,
+    /// Checked against `eth_chainId` at startup when an RPC URL is configured
+    pub chain_id: u64,
+    pub base_token: Address,
+    pub quote_token: Address,
+    #[serde(default)]
+    pub base_token_aliases: Vec<Address>,
+    #[serde(default)]
+    pub quote_token_aliases: Vec<Address>,
+    /// Expected decimals, checked against the chain at startup
+    pub base_token_decimals: Option<u8>,
+    pub quote_token_decimals: Option<u8>,
+    /// File holding the deployment's signing key
+    pub signer_private_key_file: Option<PathBuf>,
+    pub pyth_price_feed_id: Option<String>,
+    pub pyth_price_feed_symbol: Option<String>,
+    /// The feed is quoted the other way round for this pair
+    pub feed_inverted: Option<bool>,
+    /// Decimal places the signed price is also rendered with
+    pub price_display_decimals: Option<u8>,
+    pub rpc_url: Option<String>,
+    /// JSON API the feed price is sanity-checked against, with the price at
+    /// `reference_price_pointer`
+    pub reference_price_url: Option<String>,
+    pub reference_price_pointer: Option<String>,
+    /// Refuse feed prices older than this many seconds
+    pub max_price_age_seconds: Option<u64>,
+    /// Refuse feed prices outside this range, quoted the same way round as the feed
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    /// Quoting windows for the pair, e.g. market hours for an equity feed
+    pub schedule: Option<crate::schedule::Schedule>,
+    /// Sign the context sources into this pair's contexts
+    pub derivative: Option<bool>,
+    /// Extra context slots for a derivative pair, replacing the top-level ones
+    pub context_sources: Option<Vec<crate::sources::ContextSource>>,
+    /// How this deployment's verifier expects contexts hashed: `packed`, `abi_encoded` or
+    /// `tagged` (with `signed_hash_tag`)
+    pub signed_hash_scheme: Option<String>,
+    pub signed_hash_tag: Option<String>,
+}
+
+/// Parse a deployments file, checking names are unique path segments and no chain's pair
+/// is served twice. The file is either a JSON array of deployments or an object of named
+/// profiles (e.g. `dev`, `prod`) each holding one, chosen by `profile`. `${VAR}` in string
+/// values is replaced with the environment variable `VAR`.
+pub fn parse(json: &str, profile: Option<&str>) -> anyhow::Result<Vec<Deployment>> {
+    parse_with(json, profile, |name| std::env::var(name).ok())
+}
+
+/// Parse a deployments file leaving `${VAR}` placeholders as written, so a file can be checked
+/// and compared without reading the environment (and without echoing the secrets it holds).
+pub fn parse_raw(json: &str, profile: Option<&str>) -> anyhow::Result<Vec<Deployment>> {
+    parse_with(json, profile, |name| Some(format!("${{{}}}", name)))
+}
+
+fn parse_with(
+    json: &str,
+    profile: Option<&str>,
+    env: impl Fn(&str) -> Option<String>,
+) -> anyhow::Result<Vec<Deployment>> {
+    let mut value = select_profile(serde_json::from_str(json)?, profile)?;
+    interpolate(&mut value, &env)?;
+    let deployments: Vec<Deployment> = serde_json::from_value(value)?;
+    anyhow::ensure!(!deployments.is_empty(), "No deployments configured");
+    for (i, deployment) in deployments.iter().enumerate() {
+        let name = &deployment.name;
+        anyhow::ensure!(
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+            "Invalid deployment name '{}' (use letters, digits, '-' and '_')",
+            name
+        );
+        anyhow::ensure!(
+            deployments[..i].iter().all(|other| &other.name != name),
+            "Duplicate deployment name '{}'",
+            name
+        );
+        if let Some(other) = deployments[..i].iter().find(|other| {
+            (other.chain_id, other.base_token, other.quote_token)
+                == (
+                    deployment.chain_id,
+                    deployment.base_token,
+                    deployment.quote_token,
+                )
+        }) {
+            anyhow::bail!(
+                "Deployments '{}' and '{}' both serve {}/{} on chain {}",
+                other.name,
+                name,
+                deployment.base_token,
+                deployment.quote_token,
+                deployment.chain_id
+            );
+        }
+    }
+    Ok(deployments)
+}
+
+/// The deployments array for `profile`.
+fn select_profile(
+    file: serde_json::Value,
+    profile: Option<&str>,
+) -> anyhow::Result<serde_json::Value> {
+    match (file, profile) {
+        (deployments @ serde_json::Value::Array(_), None) => Ok(deployments),
+        (serde_json::Value::Array(_), Some(profile)) => {
+            anyhow::bail!(
+                "Profile '{}' selected but the file has no profiles",
+                profile
+            )
+        }
+        (serde_json::Value::Object(mut profiles), Some(profile)) => {
+            let available = profiles.keys().cloned().collect::<Vec<_>>().join(", ");
+            profiles.remove(profile).ok_or_else(|| {
+                anyhow::anyhow!("No profile '{}' (available: {})", profile, available)
+            })
+        }
+        (serde_json::Value::Object(profiles), None) => anyhow::bail!(
+            "The file has profiles; select one with --profile (available: {})",
+            profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+        ),
+        _ => anyhow::bail!("Expected an array of deployments or an object of profiles"),
+    }
+}
+
+/// Replace `${VAR}` in every string value with the variable's value. Unset variables are
+/// an error rather than an empty string, so a missing secret can't go unnoticed.
+fn interpolate(
+    value: &mut serde_json::Value,
+    env: &impl Fn(&str) -> Option<String>,
+) -> anyhow::Result<()> {
+    match value {
+        serde_json::Value::String(s) => {
+            let mut out = String::with_capacity(s.len());
+            let mut rest = s.as_str();
+            while let Some(start) = rest.find("${") {
+                out.push_str(&rest[..start]);
+                let end = rest[start..]
+                    .find('}')
+                    .ok_or_else(|| anyhow::anyhow!("Unclosed '${{' in \"{}\"", s))?;
+                let name = &rest[start + 2..start + end];
+                anyhow::ensure!(
+                    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+                    "Invalid variable name '{}'",
+                    name
+                );
+                let var = env(name)
+                    .ok_or_else(|| anyhow::anyhow!("Environment variable {} is not set", name))?;
+                out.push_str(&var);
+                rest = &rest[start + end + 1..];
+            }
+            out.push_str(rest);
+            *s = out;
+        }
+        serde_json::Value::Array(values) => {
+            for value in values {
+                interpolate(value, env)?;
+            }
+        }
+        serde_json::Value::Object(fields) => {
+            for value in fields.values_mut() {
+                interpolate(value, env)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Read deployments from a JSON file.
+pub fn load(path: &Path, profile: Option<&str>) -> anyhow::Result<Vec<Deployment>> {
+    load_with(path, profile, parse)
+}
+
+/// Read deployments from a JSON file with `${VAR}` placeholders left as written.
+pub fn load_raw(path: &Path, profile: Option<&str>) -> anyhow::Result<Vec<Deployment>> {
+    load_with(path, profile, parse_raw)
+}
+
+fn load_with(
+    path: &Path,
+    profile: Option<&str>,
+    parse: fn(&str, Option<&str>) -> anyhow::Result<Vec<Deployment>>,
+) -> anyhow::Result<Vec<Deployment>> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    parse(&json, profile)
+        .map_err(|e| anyhow::anyhow!("Invalid deployments file {}: {}", path.display(), e))
+}
+
+/// Build public and admin routers serving each deployment's routes under `/{name}`, e.g.
+/// `/base/context` and `/arbitrum/context`.
+pub fn create_apps(deployments: Vec<(String, Arc<AppState>)>) -> (Router, Router) {
+    routers(deployments, false)
+}
+
+/// Build a single router serving each deployment's public and admin routes, for servers
+/// without a separate admin listener (see [`crate::create_app`]).
+pub fn create_app(deployments: Vec<(String, Arc<AppState>)>) -> Router {
+    let (public, admin) = routers(deployments, true);
+    public.merge(admin)
+}
+
+fn routers(deployments: Vec<(String, Arc<AppState>)>, shared: bool) -> (Router, Router) {
+    let all = Arc::new(deployments.clone());
+    let mut public = Router::new()
+        .route("/", get(|| async { "ok" }))
+        .route("/version", get(crate::version::version))
+        .route("/errors", get(crate::errors::errors))
+        .route(
+            "/pairs",
+            get(move || async move { Json(crate::pairs::deployment_pairs(&all).await) }),
+        );
+    let mut admin = Router::new();
+    for (name, state) in deployments {
+        let prefix = format!("/{}", name);
+        let (deployment_public, deployment_admin) = crate::routers(state, shared);
+        public = public.nest(&prefix, deployment_public);
+        admin = admin.nest(&prefix, deployment_admin);
+    }
+    (public, admin)
+}
+
+/// A setting that differs between the running and candidate deployment.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct FieldChange {
+    pub field: String,
+    #[schema(value_type = Object)]
+    pub running: serde_json::Value,
+    #[schema(value_type = Object)]
+    pub candidate: serde_json::Value,
+}
+
+/// A deployment present in both configs with different settings.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct DeploymentChange {
+    pub name: String,
+    pub fields: Vec<FieldChange>,
+}
+
+/// How a candidate deployments file differs from the running one, by deployment name.
+#[derive(Debug, Default, Serialize, utoipa::ToSchema)]
+pub struct ConfigDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<DeploymentChange>,
+}
+
+/// Settings that can carry credentials (API keys in URLs, key file paths), reported in diffs
+/// only as changed, never with their values.
+const REDACTED_FIELDS: [&str; 4] = [
+    "rpc_url",
+    "reference_price_url",
+    "signer_private_key_file",
+    "context_sources",
+];
+
+/// Settings of a deployment as a map, so deployments can be compared field by field.
+fn fields(deployment: &Deployment) -> BTreeMap<String, serde_json::Value> {
+    match serde_json::to_value(deployment) {
+        Ok(serde_json::Value::Object(fields)) => fields.into_iter().collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+/// `value` as shown in a diff: secret-bearing settings are replaced with `"(redacted)"`.
+fn shown(field: &str, value: serde_json::Value) -> serde_json::Value {
+    if value.is_null() || !REDACTED_FIELDS.contains(&field) {
+        value
+    } else {
+        serde_json::Value::String("(redacted)".to_string())
+    }
+}
+
+/// Compare deployments by name. Unset settings count as `null`.
+pub fn diff(running: &[Deployment], candidate: &[Deployment]) -> ConfigDiff {
+    let mut diff = ConfigDiff::default();
+    for deployment in candidate {
+        let Some(current) = running.iter().find(|d| d.name == deployment.name) else {
+            diff.added.push(deployment.name.clone());
+            continue;
+        };
+        let current = fields(current);
+        let changes: Vec<_> = fields(deployment)
+            .into_iter()
+            .filter(|(field, value)| current.get(field) != Some(value))
+            .map(|(field, candidate)| FieldChange {
+                running: shown(&field, current.get(&field).cloned().unwrap_or_default()),
+                candidate: shown(&field, candidate),
+                field,
+            })
+            .collect();
+        if !changes.is_empty() {
+            diff.changed.push(DeploymentChange {
+                name: deployment.name.clone(),
+                fields: changes,
+            });
+        }
+    }
+    diff.removed = running
+        .iter()
+        .filter(|d| candidate.iter().all(|c| c.name != d.name))
+        .map(|d| d.name.clone())
+        .collect();
+    diff
+}
+
+/// Check the Pyth feeds a deployment sets exist: symbols must resolve to a single feed, and
+/// feed IDs must return a price. Deployments without their own feed use the running one, and
+/// feeds given as `${VAR}` placeholders can't be checked without the environment.
+async fn check_feeds(hermes: &Hermes, deployment: &Deployment) -> anyhow::Result<()> {
+    let placeholder = |value: &&String| value.contains("${");
+    if let Some(symbol) = deployment
+        .pyth_price_feed_symbol
+        .as_ref()
+        .filter(|v| !placeholder(v))
+    {
+        hermes.feed_id_for_symbol(symbol).await.map_err(|e| {
+            anyhow::anyhow!("Pyth feed symbol '{}' does not resolve: {:#}", symbol, e)
+        })?;
+    } else if let Some(feed_id) = deployment
+        .pyth_price_feed_id
+        .as_ref()
+        .filter(|v| !placeholder(v))
+    {
+        hermes
+            .fetch_price(feed_id.trim_start_matches("0x"))
+            .await
+            .map_err(|e| anyhow::anyhow!("Pyth feed {} has no price: {:#}", feed_id, e))?;
+    }
+    Ok(())
+}
+
+/// Response for `POST /admin/config/validate`.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ValidationResponse {
+    pub valid: bool,
+    /// Why the candidate is invalid, one message per problem
+    pub errors: Vec<String>,
+    /// Differences from the running deployments (absent if the candidate doesn't parse)
+    pub diff: Option<ConfigDiff>,
+}
+
+/// Validate a candidate deployments file and diff it against the running deployments, both
+/// with `${VAR}` placeholders left as written: the candidate comes from an admin request, and
+/// interpolating it from the server's environment would let it read any variable back.
+pub async fn validate(
+    hermes: &Hermes,
+    running: &[Deployment],
+    profile: Option<&str>,
+    json: &str,
+) -> ValidationResponse {
+    let candidate = match parse_raw(json, profile) {
+        Ok(candidate) => candidate,
+        Err(e) => {
+            return ValidationResponse {
+                valid: false,
+                errors: vec![e.to_string()],
+                diff: None,
+            }
+        }
+    };
+    let mut errors = Vec::new();
+    for deployment in &candidate {
+        if let Err(e) = check_feeds(hermes, deployment).await {
+            errors.push(format!("{}: {:#}", deployment.name, e));
+        }
+    }
+    ValidationResponse {
+        valid: errors.is_empty(),
+        errors,
+        diff: Some(diff(running, &candidate)),
+    }
+}
+
+/// What `POST /admin/config/validate` checks candidates against.
+pub struct ConfigState {
+    /// Deployments the process is serving, as written in the file (see [`load_raw`])
+    pub running: Vec<Deployment>,
+    /// Profile candidates are read with, as at startup
+    pub profile: Option<String>,
+    /// Client used to check candidate feeds
+    pub hermes: Hermes,
+}
+
+/// POST handler — validate a candidate deployments file without applying it.
+#[utoipa::path(
+    post,
+    path = "/admin/config/validate",
+    request_body(content = String, content_type = "application/json", description = "Candidate deployments file"),
+    responses(
+        (status = 200, description = "Validation result and diff against the running deployments", body = ValidationResponse),
+    ),
+)]
+pub async fn validate_config(
+    State(config): State<Arc<ConfigState>>,
+    body: String,
+) -> Json<ValidationResponse> {
+    Json(
+        validate(
+            &config.hermes,
+            &config.running,
+            config.profile.as_deref(),
+            &body,
+        )
+        .await,
+    )
+}
+
+/// Admin routes for operating the deployments file.
+pub fn config_router(config: ConfigState) -> Router {
+    Router::new()
+        .route("/admin/config/validate", post(validate_config))
+        .with_state(Arc::new(config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TEST_KEY;
+    use crate::TokenPairConfig;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_parse() {
+        let deployments = parse(
+            r#"[
+                {"name": "base", "chain_id": 8453,
+                 "base_token": "0x4200000000000000000000000000000000000006",
+                 "quote_token": "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913"},
+                {"name": "arbitrum", "chain_id": 42161,
+                 "base_token": "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1",
+                 "quote_token": "0xaf88d065e77c8cC2239327C5EDb3A432268e5831",
+                 "rpc_url": "https://arb1.arbitrum.io/rpc"}
+            ]"#,
+            None,
+        )
+        .unwrap();
+        assert_eq!(deployments.len(), 2);
+        assert_eq!(deployments[1].chain_id, 42161);
+        assert!(deployments[0].rpc_url.is_none());
+
+        let deployment = |name: &str| {
+            format!(
+                r#"{{"name": "{}", "chain_id": 1, "base_token": "{}", "quote_token": "{}"}}"#,
+                name,
+                Address::ZERO,
+                Address::ZERO
+            )
+        };
+        assert!(parse("[]", None).is_err());
+        assert!(parse(&format!("[{}]", deployment("base/context")), None).is_err());
+        assert!(parse(&format!("[{0}, {0}]", deployment("base")), None).is_err());
+        // Same pair on the same chain under two names
+        assert!(parse(&format!("[{}, {}]", deployment("a"), deployment("b")), None).is_err());
+    }
+
+    #[test]
+    fn test_profiles_and_interpolation() {
+        let json = r#"{
+            "staging": [{"name": "base", "chain_id": 84532,
+                "base_token": "0x4200000000000000000000000000000000000006",
+                "quote_token": "0x036CbD53842c5426634e7929541eC2318f3dCF7e"}],
+            "prod": [{"name": "base", "chain_id": 8453,
+                "base_token": "0x4200000000000000000000000000000000000006",
+                "quote_token": "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+                "rpc_url": "https://base.example/${RPC_TOKEN}/rpc"}]
+        }"#;
+        let env = |name: &str| (name == "RPC_TOKEN").then(|| "s3cret".to_string());
+
+        let prod = parse_with(json, Some("prod"), env).unwrap();
+        assert_eq!(prod[0].chain_id, 8453);
+        assert_eq!(
+            prod[0].rpc_url.as_deref(),
+            Some("https://base.example/s3cret/rpc")
+        );
+        assert_eq!(
+            parse_with(json, Some("staging"), env).unwrap()[0].chain_id,
+            84532
+        );
+        assert!(parse_with(json, Some("dev"), env).is_err());
+        assert!(parse_with(json, None, env).is_err());
+        // Unset variables are an error
+        assert!(parse_with(json, Some("prod"), |_| None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validate_diffs_against_running() {
+        let running = parse(
+            r#"[
+                {"name": "base", "chain_id": 8453,
+                 "base_token": "0x4200000000000000000000000000000000000006",
+                 "quote_token": "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913"},
+                {"name": "old", "chain_id": 1,
+                 "base_token": "0x4200000000000000000000000000000000000006",
+                 "quote_token": "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913"}
+            ]"#,
+            None,
+        )
+        .unwrap();
+        let hermes = Hermes::default().with_base_url("http://127.0.0.1:1");
+
+        let result = validate(
+            &hermes,
+            &running,
+            None,
+            r#"[
+                {"name": "base", "chain_id": 8453,
+                 "base_token": "0x4200000000000000000000000000000000000006",
+                 "quote_token": "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+                 "rpc_url": "https://mainnet.base.org"},
+                {"name": "arbitrum", "chain_id": 42161,
+                 "base_token": "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1",
+                 "quote_token": "0xaf88d065e77c8cC2239327C5EDb3A432268e5831"}
+            ]"#,
+        )
+        .await;
+        assert!(result.valid, "{:?}", result.errors);
+        let diff = result.diff.unwrap();
+        assert_eq!(diff.added, ["arbitrum"]);
+        assert_eq!(diff.removed, ["old"]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].fields[0].field, "rpc_url");
+        assert!(diff.changed[0].fields[0].running.is_null());
+
+        // Bad address
+        let result = validate(
+            &hermes,
+            &running,
+            None,
+            r#"[{"name": "base", "chain_id": 8453, "base_token": "0x42", "quote_token": "0x00"}]"#,
+        )
+        .await;
+        assert!(!result.valid);
+        assert!(result.diff.is_none());
+
+        // Feed symbol that can't be resolved
+        let result = validate(
+            &hermes,
+            &running,
+            None,
+            r#"[{"name": "base", "chain_id": 8453,
+                 "base_token": "0x4200000000000000000000000000000000000006",
+                 "quote_token": "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+                 "pyth_price_feed_symbol": "ETH/USD"}]"#,
+        )
+        .await;
+        assert!(!result.valid);
+        assert!(result.errors[0].starts_with("base: Pyth feed symbol"));
+        assert!(result.diff.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_validate_never_returns_secrets() {
+        let secret = std::env::var("PATH").unwrap();
+        let running = parse_raw(
+            r#"[{"name": "base", "chain_id": 8453,
+                 "base_token": "0x4200000000000000000000000000000000000006",
+                 "quote_token": "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+                 "rpc_url": "https://mainnet.example/v2/running-api-key"}]"#,
+            None,
+        )
+        .unwrap();
+        let hermes = Hermes::default().with_base_url("http://127.0.0.1:1");
+        let result = validate(
+            &hermes,
+            &running,
+            None,
+            r#"[{"name": "base", "chain_id": 8453,
+                 "base_token": "0x4200000000000000000000000000000000000006",
+                 "quote_token": "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+                 "rpc_url": "https://mainnet.example/v2/${PATH}",
+                 "pyth_price_feed_id": "${PATH}",
+                 "signed_hash_tag": "${PATH}"}]"#,
+        )
+        .await;
+        assert!(result.valid, "{:?}", result.errors);
+        let body = serde_json::to_string(&result).unwrap();
+        assert!(!body.contains(&secret), "{}", body);
+        assert!(!body.contains("running-api-key"), "{}", body);
+        let fields = &result.diff.unwrap().changed[0].fields;
+        let rpc_url = fields.iter().find(|f| f.field == "rpc_url").unwrap();
+        assert_eq!(rpc_url.running, "(redacted)");
+        assert_eq!(rpc_url.candidate, "(redacted)");
+        let tag = fields
+            .iter()
+            .find(|f| f.field == "signed_hash_tag")
+            .unwrap();
+        assert_eq!(tag.candidate, "${PATH}");
+    }
+
+    #[tokio::test]
+    async fn test_routes_by_prefix() {
+        let state = |base: &str, quote: &str| {
+            Arc::new(
+                AppState::new(
+                    TEST_KEY,
+                    "00",
+                    5,
+                    TokenPairConfig::new(base, quote).unwrap(),
+                )
+                .unwrap(),
+            )
+        };
+        let (public, admin) = create_apps(vec![
+            (
+                "base".to_string(),
+                state(
+                    "0x4200000000000000000000000000000000000006",
+                    "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+                ),
+            ),
+            (
+                "arbitrum".to_string(),
+                state(
+                    "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1",
+                    "0xaf88d065e77c8cC2239327C5EDb3A432268e5831",
+                ),
+            ),
+        ]);
+        let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+
+        for uri in ["/", "/base", "/arbitrum/readyz"] {
+            let response = public.clone().oneshot(get(uri)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+        }
+        let response = public.clone().oneshot(get("/context")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = admin.oneshot(get("/arbitrum/status")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let status: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            status["pairs"][0]["base_token"],
+            "0x82af49447d8a07e3bd95bd0d56f35241523fbab1"
+        );
+    }
+}
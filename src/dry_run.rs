@@ -0,0 +1,185 @@
+use alloy::primitives::FixedBytes;
+use axum::body::Bytes;
+use axum::extract::{rejection::QueryRejection, Extension, Query, State};
+use axum::http::HeaderMap;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::{auth, explain, metrics, oracle, AppError, AppState, ContextQuery};
+
+/// Outcome of one stage of the `/context` pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Passed,
+    Failed,
+    /// Not run because an earlier stage it depends on failed
+    Skipped,
+}
+
+/// A pipeline stage and, if it failed, the error `/context` would have returned.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct DryRunCheck {
+    /// `request` (decoding, limits, IO indexes, token pair, decimals), `warm_up`,
+    /// `clock_skew` or `price` (fetch, price sanity, expiry)
+    pub name: &'static str,
+    pub status: CheckStatus,
+    /// Error code `/context` would have returned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl DryRunCheck {
+    /// Internal error messages are replaced by a logged correlation ID unless `verbose`, as
+    /// in `/context` responses.
+    fn new<T>(name: &'static str, result: &Result<T, AppError>, verbose: bool) -> Self {
+        let (status, error, detail) = match result {
+            Ok(_) => (CheckStatus::Passed, None, None),
+            Err(err @ AppError::Internal(internal)) if !verbose => (
+                CheckStatus::Failed,
+                Some(err.error_code().to_string()),
+                Some(crate::redacted_detail(&crate::log_internal_error(internal))),
+            ),
+            Err(err) => (
+                CheckStatus::Failed,
+                Some(err.error_code().to_string()),
+                Some(err.detail()),
+            ),
+        };
+        Self {
+            name,
+            status,
+            error,
+            detail,
+        }
+    }
+
+    fn skipped(name: &'static str) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Skipped,
+            error: None,
+            detail: None,
+        }
+    }
+}
+
+/// What `/context` would have done with a request, without signing it.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct DryRunResponse {
+    /// Whether every check passed, i.e. `/context` would have signed
+    pub would_sign: bool,
+    pub checks: Vec<DryRunCheck>,
+    /// The context that would have been signed (before any nonce slot is appended)
+    #[schema(value_type = Option<Vec<String>>)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<Vec<FixedBytes<32>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug: Option<oracle::Diagnostics>,
+    /// How the price was arrived at (with `?explain=true`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<explain::Explanation>,
+}
+
+/// Query parameters `/context/dry-run` takes on top of those of `/context`.
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct DryRunQuery {
+    /// Include the pricing explanation that would be recorded with the quote
+    #[serde(default)]
+    explain: bool,
+}
+
+/// POST handler — runs a `/context` request through decoding, pair resolution, the clock
+/// check and the price fetch, but stops short of signing, and reports which checks passed.
+#[utoipa::path(
+    post,
+    path = "/context/dry-run",
+    params(
+        ContextQuery,
+        DryRunQuery,
+        ("X-Order-Version" = Option<String>, Header, description = "Pin the order ABI version (3 or 4) instead of auto-detecting"),
+    ),
+    request_body(
+        description = "Same body as `/context`",
+        content(
+            (Vec<u8> = "application/octet-stream"),
+            (String = "text/plain"),
+        ),
+    ),
+    responses(
+        (status = 200, description = "Checks run and the would-be context", body = DryRunResponse),
+        (status = 401, description = "Missing or invalid credentials (when auth is enabled)", body = crate::ErrorResponse),
+    ),
+)]
+pub(crate) async fn dry_run(
+    State(state): State<Arc<AppState>>,
+    query: Result<Query<ContextQuery>, QueryRejection>,
+    Query(DryRunQuery { explain }): Query<DryRunQuery>,
+    identity: Option<Extension<auth::Authenticated>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Json<DryRunResponse> {
+    let mut labels = metrics::RequestLabels::default();
+    let request =
+        crate::resolve_request(&state, query, identity, &headers, &body, &mut labels).await;
+    let ready = crate::check_ready(&state);
+    let clock = crate::check_clock_skew(&state);
+    let schedule = crate::check_schedule(&state);
+    let priced = match &request {
+        Ok(request) => Some(
+            crate::price_context(
+                &state,
+                request.direction,
+                request.decimal_shift,
+                request.expiry_seconds,
+            )
+            .await,
+        ),
+        Err(_) => None,
+    };
+
+    let verbose = state.verbose_errors;
+    let mut checks = vec![
+        DryRunCheck::new("request", &request, verbose),
+        DryRunCheck::new("warm_up", &ready, verbose),
+        DryRunCheck::new("clock_skew", &clock, verbose),
+        DryRunCheck::new("schedule", &schedule, verbose),
+    ];
+    checks.push(match &priced {
+        Some(priced) => DryRunCheck::new("price", priced, verbose),
+        None => DryRunCheck::skipped("price"),
+    });
+    let would_sign = checks.iter().all(|c| c.status == CheckStatus::Passed);
+
+    let (context, debug, explanation) = match (request, priced) {
+        (Ok(request), Some(Ok(priced))) => {
+            let debug = crate::diagnostics(
+                &state,
+                &priced.context,
+                request.direction,
+                priced.publish_time,
+                priced.expiry,
+            )
+            .ok();
+            let explanation = explain.then(|| {
+                let mut explanation = priced.explanation;
+                explanation
+                    .guards
+                    .splice(0..0, crate::signing_guards(&state));
+                explanation
+            });
+            (Some(priced.context), debug, explanation)
+        }
+        _ => (None, None, None),
+    };
+    Json(DryRunResponse {
+        would_sign,
+        checks,
+        context,
+        debug,
+        explanation,
+    })
+}
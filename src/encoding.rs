@@ -0,0 +1,146 @@
+use axum::{
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+/// Media type for CBOR (RFC 8949).
+pub const CBOR: &str = "application/cbor";
+
+/// How to encode a response body, chosen from the request's `Accept` header.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResponseFormat {
+    Json,
+    /// Addresses, bytes and IDs as CBOR byte strings rather than hex, so responses are
+    /// smaller and cheaper to parse
+    Cbor,
+    /// Like CBOR, with addresses, bytes and IDs as MessagePack binary
+    Msgpack,
+}
+
+impl ResponseFormat {
+    /// The acceptable format with the highest `q`, preferring the one listed first on a
+    /// tie. JSON unless CBOR or MessagePack is preferred, including when nothing listed is
+    /// supported.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let Some(accept) = headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+        else {
+            return Self::Json;
+        };
+        let mut best = (Self::Json, 0.0);
+        for range in accept.split(',') {
+            let mut params = range.split(';').map(str::trim);
+            let format = match params.next().unwrap_or_default() {
+                media if media.eq_ignore_ascii_case(CBOR) => Self::Cbor,
+                media if crate::msgpack::is_msgpack(media) => Self::Msgpack,
+                media
+                    if media.eq_ignore_ascii_case("application/json")
+                        || media == "application/*"
+                        || media == "*/*" =>
+                {
+                    Self::Json
+                }
+                _ => continue,
+            };
+            let q = params
+                .find_map(|param| param.strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if q > best.1 {
+                best = (format, q);
+            }
+        }
+        best.0
+    }
+
+    /// Encode `value` in this format.
+    pub fn respond<T: Serialize>(self, value: T) -> Response {
+        match self {
+            Self::Json => Json(value).into_response(),
+            Self::Cbor => Cbor(value).into_response(),
+            Self::Msgpack => Msgpack(value).into_response(),
+        }
+    }
+}
+
+/// A CBOR-encoded response body.
+pub struct Cbor<T>(pub T);
+
+impl<T: Serialize> IntoResponse for Cbor<T> {
+    fn into_response(self) -> Response {
+        let mut body = Vec::new();
+        match ciborium::into_writer(&self.0, &mut body) {
+            Ok(()) => (
+                [(header::CONTENT_TYPE, HeaderValue::from_static(CBOR))],
+                body,
+            )
+                .into_response(),
+            Err(e) => crate::AppError::Internal(anyhow::anyhow!("Failed to encode CBOR: {}", e))
+                .into_response(),
+        }
+    }
+}
+
+/// A MessagePack-encoded response body.
+pub struct Msgpack<T>(pub T);
+
+impl<T: Serialize> IntoResponse for Msgpack<T> {
+    fn into_response(self) -> Response {
+        match crate::msgpack::to_vec(&self.0) {
+            Ok(body) => (
+                [(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(crate::msgpack::MSGPACK),
+                )],
+                body,
+            )
+                .into_response(),
+            Err(e) => {
+                crate::AppError::Internal(anyhow::anyhow!("Failed to encode MessagePack: {}", e))
+                    .into_response()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(accept: &str) -> ResponseFormat {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, accept.parse().unwrap());
+        ResponseFormat::from_headers(&headers)
+    }
+
+    #[test]
+    fn test_from_headers() {
+        assert_eq!(
+            ResponseFormat::from_headers(&HeaderMap::new()),
+            ResponseFormat::Json
+        );
+        assert_eq!(format("application/cbor"), ResponseFormat::Cbor);
+        assert_eq!(
+            format("application/json, application/cbor"),
+            ResponseFormat::Json
+        );
+        assert_eq!(
+            format("application/cbor, application/json"),
+            ResponseFormat::Cbor
+        );
+        assert_eq!(
+            format("application/cbor;q=0.5, application/json"),
+            ResponseFormat::Json
+        );
+        assert_eq!(format("*/*;q=0.1, application/cbor"), ResponseFormat::Cbor);
+        assert_eq!(format("application/msgpack"), ResponseFormat::Msgpack);
+        assert_eq!(
+            format("application/x-msgpack, application/json"),
+            ResponseFormat::Msgpack
+        );
+        assert_eq!(format("text/html"), ResponseFormat::Json);
+    }
+}
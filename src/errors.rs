@@ -0,0 +1,72 @@
+use axum::Json;
+use serde::Serialize;
+
+use crate::AppError;
+
+/// One error code clients can receive in the `error` field of an error response.
+#[derive(Debug, Clone, PartialEq, Serialize, utoipa::ToSchema)]
+pub struct ErrorInfo {
+    /// Machine-readable error code, e.g. `invalid_body`
+    pub code: &'static str,
+    /// HTTP status the error is returned with
+    pub status: u16,
+    pub description: &'static str,
+}
+
+/// Response for `GET /errors`.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ErrorCatalog {
+    pub errors: Vec<ErrorInfo>,
+}
+
+/// Every error code the server returns, built from one sample of each error variant. Codes
+/// shared by several variants are listed once.
+pub fn catalog() -> Vec<ErrorInfo> {
+    let mut errors: Vec<ErrorInfo> = Vec::new();
+    for err in AppError::samples() {
+        let info = ErrorInfo {
+            code: err.error_code(),
+            status: err.status_code().as_u16(),
+            description: err.description(),
+        };
+        if !errors.iter().any(|e| e.code == info.code) {
+            errors.push(info);
+        }
+    }
+    errors
+}
+
+/// GET handler — the catalog of error codes, so clients can map errors without hardcoding
+/// them.
+#[utoipa::path(
+    get,
+    path = "/errors",
+    responses((status = 200, description = "Error codes with their HTTP statuses", body = ErrorCatalog)),
+)]
+pub async fn errors() -> Json<ErrorCatalog> {
+    Json(ErrorCatalog { errors: catalog() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog() {
+        let errors = catalog();
+        let status = |code: &str| errors.iter().find(|e| e.code == code).unwrap().status;
+        assert_eq!(status("invalid_body"), 400);
+        assert_eq!(status("unsupported_token_pair"), 400);
+        assert_eq!(status("missing_auth"), 401);
+        assert_eq!(status("insufficient_scope"), 403);
+        assert_eq!(status("not_found"), 404);
+        assert_eq!(status("quota_exceeded"), 429);
+        assert_eq!(status("internal_error"), 500);
+        assert_eq!(status("warming_up"), 503);
+        assert_eq!(
+            errors.iter().filter(|e| e.code == "missing_auth").count(),
+            1
+        );
+        assert!(errors.iter().all(|e| !e.description.is_empty()));
+    }
+}
@@ -0,0 +1,55 @@
+use rain_math_float::Float;
+use serde::{Deserialize, Serialize};
+
+use crate::PriceDirection;
+
+/// A price read from an upstream source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct SourcePrice {
+    pub feed_id: String,
+    /// Decimal price as published
+    pub price: String,
+    /// Unix seconds
+    pub publish_time: i64,
+}
+
+/// One step of turning the source price into the signed price.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct PricingStep {
+    /// e.g. `flip feed`, `invert?`, `mul 0.999`, `scale 10^-12`, `gas`, `jitter`, `round 6sf down`
+    pub step: String,
+    /// Decimal price after the step
+    pub price: String,
+}
+
+/// Why a quote was signed at its price: the source prices, the price they combined to, each
+/// step applied after that and the guards the quote passed. Kept with the audit record for
+/// post-incident analysis.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct Explanation {
+    pub sources: Vec<SourcePrice>,
+    /// Decimal price the steps start from
+    pub aggregate: String,
+    pub direction: PriceDirection,
+    pub steps: Vec<PricingStep>,
+    /// Guards evaluated before signing, in order, e.g. `warm_up`, `positive_price`
+    pub guards: Vec<String>,
+}
+
+/// `price` as a decimal string.
+pub fn format_price(price: Float) -> anyhow::Result<String> {
+    price
+        .format()
+        .map_err(|e| anyhow::anyhow!("Failed to format price: {:?}", e))
+}
+
+impl Explanation {
+    /// Record a step and the price after it.
+    pub fn push_step(&mut self, step: impl Into<String>, price: Float) -> anyhow::Result<()> {
+        self.steps.push(PricingStep {
+            step: step.into(),
+            price: format_price(price)?,
+        });
+        Ok(())
+    }
+}
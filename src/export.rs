@@ -0,0 +1,198 @@
+use alloy::primitives::Address;
+use std::io::Write;
+use std::str::FromStr;
+
+use crate::storage::{direction_str, AuditRecord, QuoteFilter, Storage};
+
+/// Output format for `export`.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+}
+
+const CSV_HEADER: &str = "quote_id,signature_hash,issued_at,signer,counterparty,api_key,base_token,quote_token,direction,context,signature,taker_id,order_hash";
+
+/// Parse a time bound given as unix seconds or RFC 3339 (e.g. `2024-01-31T00:00:00Z`).
+pub fn parse_time(s: &str) -> anyhow::Result<i64> {
+    if let Ok(secs) = s.parse::<i64>() {
+        return Ok(secs);
+    }
+    let dt = chrono::DateTime::parse_from_rfc3339(s).map_err(|e| {
+        anyhow::anyhow!(
+            "Invalid time '{}' (expected unix seconds or RFC 3339): {}",
+            s,
+            e
+        )
+    })?;
+    Ok(dt.timestamp())
+}
+
+/// Parse a pair given as `BASE/QUOTE` token addresses.
+pub fn parse_pair(s: &str) -> anyhow::Result<(Address, Address)> {
+    let (base, quote) = s
+        .split_once('/')
+        .ok_or_else(|| anyhow::anyhow!("Invalid pair '{}' (expected BASE/QUOTE)", s))?;
+    Ok((
+        Address::from_str(base.trim())?,
+        Address::from_str(quote.trim())?,
+    ))
+}
+
+/// Quote a CSV field if it contains a delimiter, quote or newline.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_row(record: &AuditRecord) -> String {
+    let context = record
+        .context
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+    [
+        record.quote_id.to_string(),
+        record.signature_hash.to_string(),
+        record.issued_at.to_string(),
+        record.signer.to_string(),
+        record.counterparty.to_string(),
+        record.api_key.clone().unwrap_or_default(),
+        record.base_token.to_string(),
+        record.quote_token.to_string(),
+        direction_str(record.direction).to_string(),
+        context,
+        record.signature.to_string(),
+        record.taker_id.clone().unwrap_or_default(),
+        record
+            .order_hash
+            .map(|hash| hash.to_string())
+            .unwrap_or_default(),
+    ]
+    .iter()
+    .map(|f| csv_field(f))
+    .collect::<Vec<_>>()
+    .join(",")
+}
+
+/// Write every recorded quote matching `filter` to `out`. Returns the number of rows written.
+pub async fn export(
+    storage: &dyn Storage,
+    filter: &QuoteFilter,
+    format: ExportFormat,
+    out: &mut impl Write,
+) -> anyhow::Result<usize> {
+    let records = storage.quotes(filter).await?;
+    if format == ExportFormat::Csv {
+        writeln!(out, "{}", CSV_HEADER)?;
+    }
+    for record in &records {
+        match format {
+            ExportFormat::Csv => writeln!(out, "{}", csv_row(record))?,
+            ExportFormat::Jsonl => writeln!(out, "{}", serde_json::to_string(record)?)?,
+        }
+    }
+    out.flush()?;
+    Ok(records.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+    use crate::PriceDirection;
+    use alloy::primitives::{Bytes, FixedBytes};
+
+    fn record(issued_at: i64, api_key: &str) -> AuditRecord {
+        let signature = Bytes::from(issued_at.to_be_bytes().to_vec());
+        AuditRecord {
+            quote_id: uuid::Uuid::new_v4(),
+            signature_hash: AuditRecord::signature_hash(&signature),
+            issued_at,
+            signer: Address::repeat_byte(1),
+            counterparty: Address::repeat_byte(2),
+            api_key: Some(api_key.to_string()),
+            taker_id: None,
+            order_hash: None,
+            output_vault: None,
+            base_token: Address::repeat_byte(4),
+            quote_token: Address::repeat_byte(5),
+            direction: PriceDirection::AsIs,
+            context: vec![FixedBytes::repeat_byte(0xaa), FixedBytes::repeat_byte(0xbb)],
+            signature,
+            explanation: None,
+        }
+    }
+
+    async fn storage() -> MemoryStorage {
+        let storage = MemoryStorage::default();
+        storage.record_quote(&record(100, "acme")).await.unwrap();
+        storage.record_quote(&record(200, "a,b")).await.unwrap();
+        storage
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("1700000000").unwrap(), 1700000000);
+        assert_eq!(parse_time("2023-11-14T22:13:20Z").unwrap(), 1700000000);
+        assert!(parse_time("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_parse_pair() {
+        let (base, quote) = parse_pair(
+            "0x4200000000000000000000000000000000000006/0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+        )
+        .unwrap();
+        assert_eq!(
+            base,
+            Address::from_str("0x4200000000000000000000000000000000000006").unwrap()
+        );
+        assert_eq!(
+            quote,
+            Address::from_str("0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913").unwrap()
+        );
+        assert!(parse_pair("0x4200000000000000000000000000000000000006").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_export_csv() {
+        let mut out = Vec::new();
+        let n = export(
+            &storage().await,
+            &QuoteFilter::default(),
+            ExportFormat::Csv,
+            &mut out,
+        )
+        .await
+        .unwrap();
+        assert_eq!(n, 2);
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], CSV_HEADER);
+        assert!(lines[1].contains(",100,"));
+        assert!(lines[1].contains(",acme,"));
+        assert!(lines[1].contains(",as_is,"));
+        assert!(lines[2].contains(",\"a,b\","));
+    }
+
+    #[tokio::test]
+    async fn test_export_jsonl_filtered() {
+        let filter = QuoteFilter {
+            since: Some(150),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let n = export(&storage().await, &filter, ExportFormat::Jsonl, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(n, 1);
+        let row: AuditRecord = serde_json::from_slice(&out).unwrap();
+        assert_eq!(row.issued_at, 200);
+    }
+}
@@ -0,0 +1,172 @@
+use alloy::primitives::FixedBytes;
+use rain_math_float::Float;
+
+use crate::rpc;
+
+/// Where the current gas price comes from.
+#[derive(Debug, Clone)]
+pub enum GasPriceSource {
+    /// `eth_gasPrice` on a JSON-RPC endpoint
+    Rpc(String),
+    /// A gas oracle API returning JSON with the gas price in gwei at `pointer` (a JSON
+    /// pointer such as `/fast/maxFee`)
+    Oracle { url: String, pointer: String },
+}
+
+/// Widens the signed price by the estimated cost of filling a quote, so small fills don't
+/// become unprofitable when gas spikes.
+///
+/// The fill cost is `gas price * fill gas`, converted from the native token into the quote
+/// token with the feed price — so the feed's base asset must be the chain's gas token (e.g.
+/// ETH/USD for a WETH/USDC pair on Base) — and charged as a fraction of `reference_fill`, the
+/// typical fill size in quote tokens.
+#[derive(Debug, Clone)]
+pub struct GasCompensation {
+    source: GasPriceSource,
+    fill_gas: u64,
+    reference_fill: String,
+    client: reqwest::Client,
+}
+
+fn parse_float(s: &str) -> anyhow::Result<Float> {
+    Float::parse(s.to_string())
+        .map_err(|e| anyhow::anyhow!("Failed to parse '{}' as Rain float: {:?}", s, e))
+}
+
+/// Multiplier on the signed price charging the fill cost against `reference_fill`:
+/// `1 + gas_price_wei * fill_gas / 1e18 * native_price / reference_fill`.
+pub fn markup_factor(
+    gas_price_wei: u64,
+    fill_gas: u64,
+    native_price: Float,
+    reference_fill: Float,
+) -> anyhow::Result<Float> {
+    let arithmetic = |e| anyhow::anyhow!("Failed to compute gas markup: {:?}", e);
+    let cost_wei = u128::from(gas_price_wei) * u128::from(fill_gas);
+    let cost_native = crate::oracle::format_pyth_price(i128::try_from(cost_wei)?, -18);
+    let cost_quote = (parse_float(&cost_native)? * native_price).map_err(arithmetic)?;
+    let fraction = (cost_quote / reference_fill).map_err(arithmetic)?;
+    (parse_float("1")? + fraction).map_err(arithmetic)
+}
+
+/// Read a gwei gas price from a gas oracle response and convert it to wei.
+fn parse_oracle_gwei(body: &serde_json::Value, pointer: &str) -> anyhow::Result<u64> {
+    let value = body
+        .pointer(pointer)
+        .ok_or_else(|| anyhow::anyhow!("Gas oracle response has no value at {}", pointer))?;
+    let gwei = match value {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+    .filter(|gwei: &f64| gwei.is_finite() && *gwei >= 0.0)
+    .ok_or_else(|| anyhow::anyhow!("Invalid gas price {} at {}", value, pointer))?;
+    Ok((gwei * 1e9).round() as u64)
+}
+
+impl GasCompensation {
+    /// `fill_gas` is the gas a fill is expected to use; `reference_fill` the fill size (in
+    /// quote tokens) the cost is spread over.
+    pub fn new(
+        source: GasPriceSource,
+        fill_gas: u64,
+        reference_fill: &str,
+    ) -> anyhow::Result<Self> {
+        let reference = parse_float(reference_fill)?;
+        anyhow::ensure!(
+            reference
+                .gt(parse_float("0")?)
+                .map_err(|e| anyhow::anyhow!("Failed to compare reference fill: {:?}", e))?,
+            "Reference fill size must be positive"
+        );
+        Ok(Self {
+            source,
+            fill_gas,
+            reference_fill: reference_fill.to_string(),
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// The current gas price in wei.
+    pub async fn gas_price(&self) -> anyhow::Result<u64> {
+        match &self.source {
+            GasPriceSource::Rpc(rpc_url) => rpc::gas_price_with(&self.client, rpc_url).await,
+            GasPriceSource::Oracle { url, pointer } => {
+                let body: serde_json::Value = self
+                    .client
+                    .get(url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                parse_oracle_gwei(&body, pointer)
+            }
+        }
+    }
+
+    /// Multiply a context price slot by the markup for the current gas price. `native_price`
+    /// is the feed price (quote tokens per native token).
+    pub async fn widen(
+        &self,
+        price: FixedBytes<32>,
+        native_price: Float,
+    ) -> anyhow::Result<FixedBytes<32>> {
+        let factor = markup_factor(
+            self.gas_price().await?,
+            self.fill_gas,
+            native_price,
+            parse_float(&self.reference_fill)?,
+        )?;
+        let widened = (Float::from(alloy::primitives::B256::from(price)) * factor)
+            .map_err(|e| anyhow::anyhow!("Failed to widen price: {:?}", e))?;
+        let bytes: alloy::primitives::B256 = widened.into();
+        Ok(FixedBytes::from(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markup_factor() {
+        // 150k gas at 20 gwei = 0.003 ETH = 9 USDC at 3000, charged against a 900 USDC fill
+        let factor = markup_factor(
+            20_000_000_000,
+            150_000,
+            parse_float("3000").unwrap(),
+            parse_float("900").unwrap(),
+        )
+        .unwrap();
+        assert!(factor.eq(parse_float("1.01").unwrap()).unwrap());
+
+        let free = markup_factor(
+            0,
+            150_000,
+            parse_float("3000").unwrap(),
+            parse_float("900").unwrap(),
+        )
+        .unwrap();
+        assert!(free.eq(parse_float("1").unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_parse_oracle_gwei() {
+        let body = serde_json::json!({ "fast": { "maxFee": 1.5 }, "safe": "0.02", "bad": true });
+        assert_eq!(
+            parse_oracle_gwei(&body, "/fast/maxFee").unwrap(),
+            1_500_000_000
+        );
+        assert_eq!(parse_oracle_gwei(&body, "/safe").unwrap(), 20_000_000);
+        assert!(parse_oracle_gwei(&body, "/bad").is_err());
+        assert!(parse_oracle_gwei(&body, "/missing").is_err());
+    }
+
+    #[test]
+    fn test_new_rejects_non_positive_reference_fill() {
+        let source = GasPriceSource::Rpc("http://localhost:8545".to_string());
+        assert!(GasCompensation::new(source.clone(), 150_000, "0").is_err());
+        assert!(GasCompensation::new(source, 150_000, "1000").is_ok());
+    }
+}
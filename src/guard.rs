@@ -0,0 +1,237 @@
+use async_trait::async_trait;
+use std::time::Duration;
+
+use crate::pyth::PriceData;
+use crate::{incident, oracle, AppError, AppState, UnavailableError};
+
+/// Error code of a rejection by a guard without a code of its own.
+pub const REJECTED_CODE: &str = "price_guard_rejected";
+
+/// The feed price a [`PricingGuard`] checks before anything is signed from it.
+#[derive(Debug, Clone, Copy)]
+pub struct GuardInput<'a> {
+    pub feed_id: &'a str,
+    pub price: &'a PriceData,
+    /// Current unix time (seconds)
+    pub now: u64,
+}
+
+impl GuardInput<'_> {
+    /// The price as the feed quotes it (e.g. ETH/USD), before any inversion or pipeline.
+    pub fn feed_price(&self) -> anyhow::Result<f64> {
+        Ok(oracle::format_pyth_price(self.price.price, self.price.expo).parse()?)
+    }
+}
+
+/// Why a guard refused a price. Refused prices pause the pair with `503` and the guard's
+/// error code, naming the guard in the body's `guard` field.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{reason}")]
+pub struct GuardRejection {
+    /// Name of the guard that refused
+    pub guard: String,
+    /// Error code to refuse with ([`REJECTED_CODE`] unless the guard has its own)
+    pub code: &'static str,
+    pub reason: String,
+    /// Other prices the decision was based on, as `(source, price)`, kept with the incident
+    pub sources: Vec<(String, String)>,
+}
+
+impl GuardRejection {
+    pub fn new(guard: &str, reason: impl Into<String>) -> Self {
+        Self {
+            guard: guard.to_string(),
+            code: REJECTED_CODE,
+            reason: reason.into(),
+            sources: Vec::new(),
+        }
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = code;
+        self
+    }
+
+    pub fn with_source(mut self, source: &str, price: impl ToString) -> Self {
+        self.sources.push((source.to_string(), price.to_string()));
+        self
+    }
+}
+
+/// A check on the feed price before it is signed. A pair evaluates its guards in the order
+/// they were added with [`AppState::with_pricing_guard`], stopping at the first rejection, so
+/// library users can add their own without touching the handlers.
+#[async_trait]
+pub trait PricingGuard: Send + Sync + std::fmt::Debug {
+    /// Name listed in quote explanations and reported when the guard refuses.
+    fn name(&self) -> &str;
+
+    async fn check(&self, input: GuardInput<'_>) -> Result<(), GuardRejection>;
+}
+
+/// Refuses prices published more than `max_age` ago.
+#[derive(Debug, Clone, Copy)]
+pub struct Staleness {
+    max_age: Duration,
+}
+
+impl Staleness {
+    pub fn new(max_age: Duration) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            max_age >= Duration::from_secs(1),
+            "Maximum price age must be at least 1 second"
+        );
+        Ok(Self { max_age })
+    }
+}
+
+#[async_trait]
+impl PricingGuard for Staleness {
+    fn name(&self) -> &str {
+        "staleness"
+    }
+
+    async fn check(&self, input: GuardInput<'_>) -> Result<(), GuardRejection> {
+        let age = (input.now as i64).saturating_sub(input.price.publish_time);
+        if age > self.max_age.as_secs() as i64 {
+            return Err(GuardRejection::new(
+                self.name(),
+                format!(
+                    "Feed price is {}s old (limit {}s)",
+                    age,
+                    self.max_age.as_secs()
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Refuses feed prices outside `[min, max]`, quoted the same way round as the feed.
+#[derive(Debug, Clone, Copy)]
+pub struct PriceBounds {
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl PriceBounds {
+    pub fn new(min: Option<f64>, max: Option<f64>) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            min.or(max).is_some(),
+            "Price bounds need a minimum or a maximum"
+        );
+        anyhow::ensure!(
+            min.into_iter().chain(max).all(|p| p.is_finite() && p > 0.0),
+            "Price bounds must be positive"
+        );
+        if let (Some(min), Some(max)) = (min, max) {
+            anyhow::ensure!(min < max, "Minimum price must be below the maximum");
+        }
+        Ok(Self { min, max })
+    }
+}
+
+#[async_trait]
+impl PricingGuard for PriceBounds {
+    fn name(&self) -> &str {
+        "bounds"
+    }
+
+    async fn check(&self, input: GuardInput<'_>) -> Result<(), GuardRejection> {
+        let price = input
+            .feed_price()
+            .map_err(|e| GuardRejection::new(self.name(), e.to_string()))?;
+        let out_of_bounds = |bound: &str, limit: f64| {
+            GuardRejection::new(
+                self.name(),
+                format!("Feed price {} is {} {}", price, bound, limit),
+            )
+        };
+        if let Some(min) = self.min.filter(|min| price < *min) {
+            return Err(out_of_bounds("below the minimum", min));
+        }
+        if let Some(max) = self.max.filter(|max| price > *max) {
+            return Err(out_of_bounds("above the maximum", max));
+        }
+        Ok(())
+    }
+}
+
+/// Run the pair's guards over `price` in order, recording an incident for the first to refuse.
+pub(crate) async fn check(state: &AppState, price: &PriceData) -> Result<(), AppError> {
+    let input = GuardInput {
+        feed_id: &state.pyth_price_feed_id,
+        price,
+        now: state.clock.now().as_secs(),
+    };
+    for guard in &state.pricing_guards {
+        if let Err(rejection) = guard.check(input).await {
+            tracing::warn!("Pausing quotes: {} guard: {}", rejection.guard, rejection);
+            let sources = rejection
+                .sources
+                .iter()
+                .map(|(source, price)| incident::SnapshotPrice {
+                    source: source.clone(),
+                    price: price.clone(),
+                    publish_time: None,
+                })
+                .collect();
+            incident::capture(state, rejection.code, rejection.to_string(), sources).await;
+            return Err(UnavailableError::PriceGuard(rejection).into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(price: &PriceData, now: u64) -> GuardInput<'_> {
+        GuardInput {
+            feed_id: "feed",
+            price,
+            now,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_staleness() {
+        let price = PriceData {
+            price: 310012345678,
+            expo: -8,
+            publish_time: 1700000000,
+        };
+        let guard = Staleness::new(Duration::from_secs(60)).unwrap();
+        assert!(guard.check(input(&price, 1700000060)).await.is_ok());
+        let rejection = guard.check(input(&price, 1700000061)).await.unwrap_err();
+        assert_eq!(rejection.guard, "staleness");
+        assert_eq!(rejection.code, REJECTED_CODE);
+        assert_eq!(rejection.reason, "Feed price is 61s old (limit 60s)");
+        assert!(Staleness::new(Duration::ZERO).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_price_bounds() {
+        let price = PriceData {
+            price: 310012345678,
+            expo: -8,
+            publish_time: 1700000000,
+        };
+        let within = PriceBounds::new(Some(1000.0), Some(5000.0)).unwrap();
+        assert!(within.check(input(&price, 0)).await.is_ok());
+        let below = PriceBounds::new(Some(3200.0), None).unwrap();
+        let rejection = below.check(input(&price, 0)).await.unwrap_err();
+        assert_eq!(rejection.guard, "bounds");
+        assert_eq!(
+            rejection.reason,
+            "Feed price 3100.12345678 is below the minimum 3200"
+        );
+        let above = PriceBounds::new(None, Some(3000.0)).unwrap();
+        assert!(above.check(input(&price, 0)).await.is_err());
+
+        assert!(PriceBounds::new(None, None).is_err());
+        assert!(PriceBounds::new(Some(5000.0), Some(1000.0)).is_err());
+        assert!(PriceBounds::new(Some(-1.0), None).is_err());
+    }
+}
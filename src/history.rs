@@ -0,0 +1,425 @@
+use alloy::primitives::Address;
+use axum::extract::{rejection::QueryRejection, Path, Query, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use rain_math_float::Float;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use crate::pyth::PriceData;
+use crate::storage::direction_str;
+use crate::{oracle, AppError, AppState, OracleRequestError, PriceDirection};
+
+/// Query for `GET /price/{pair}/at`.
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct PriceAtQuery {
+    /// Unix timestamp (seconds) to look the price up at
+    timestamp: i64,
+}
+
+/// The configured feed's price, current or at a past time, in the direction of the requested
+/// pair.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct PairPrice {
+    /// Token priced
+    #[schema(value_type = String)]
+    pub base_token: Address,
+    /// Token the price is denominated in
+    #[schema(value_type = String)]
+    pub quote_token: Address,
+    /// Decimal price, computed exactly as it would have been signed into context[0]
+    pub price: String,
+    pub direction: PriceDirection,
+    pub feed_id: String,
+    /// Pyth publish time of the price as an ISO 8601 timestamp
+    pub publish_time: String,
+}
+
+/// Recent prices per feed, newest last, as fetched while quoting (by `/context`, `/price` and
+/// the quote stream). Each feed keeps at most `capacity` distinct publish times, dropping the
+/// oldest.
+#[derive(Debug)]
+pub struct PriceHistory {
+    capacity: usize,
+    feeds: Mutex<HashMap<String, VecDeque<PriceData>>>,
+}
+
+impl Default for PriceHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_CAPACITY)
+    }
+}
+
+/// Samples kept per feed by default: an hour of one-second updates.
+pub const DEFAULT_HISTORY_CAPACITY: usize = 3600;
+
+impl PriceHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            feeds: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Add a fetched price, unless it's no newer than the last one kept (the same update
+    /// fetched again).
+    pub fn record(&self, feed_id: &str, data: &PriceData) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut feeds = self.feeds.lock().unwrap();
+        let samples = feeds.entry(feed_id.to_string()).or_default();
+        if samples
+            .back()
+            .is_some_and(|last| last.publish_time >= data.publish_time)
+        {
+            return;
+        }
+        if samples.len() == self.capacity {
+            samples.pop_front();
+        }
+        samples.push_back(*data);
+    }
+
+    /// Prices of `feed_id` published at or after unix time `since`, oldest first.
+    pub fn since(&self, feed_id: &str, since: i64) -> Vec<PriceData> {
+        let feeds = self.feeds.lock().unwrap();
+        let Some(samples) = feeds.get(feed_id) else {
+            return Vec::new();
+        };
+        let start = samples.partition_point(|sample| sample.publish_time < since);
+        samples.range(start..).copied().collect()
+    }
+}
+
+/// Query for `GET /price/{pair}/history`.
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct HistoryQuery {
+    /// How far back to go, in seconds before now (default 60)
+    seconds: Option<u64>,
+}
+
+/// One price from the history, as it would have been signed.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct PriceSample {
+    pub price: String,
+    /// Pyth publish time (ISO 8601)
+    pub publish_time: String,
+}
+
+/// Recent prices of the configured feed in the direction of the requested pair.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct PriceHistoryResponse {
+    #[schema(value_type = String)]
+    pub base_token: Address,
+    #[schema(value_type = String)]
+    pub quote_token: Address,
+    pub direction: PriceDirection,
+    pub feed_id: String,
+    /// Oldest first
+    pub samples: Vec<PriceSample>,
+}
+
+/// Parse a `BASE-QUOTE` pair of token addresses.
+fn parse_pair(pair: &str) -> Result<(Address, Address), OracleRequestError> {
+    let invalid =
+        |detail: String| OracleRequestError::InvalidQuery(format!("pair '{}': {}", pair, detail));
+    let (base, quote) = pair
+        .split_once('-')
+        .ok_or_else(|| invalid("expected BASE-QUOTE token addresses".to_string()))?;
+    Ok((
+        base.trim().parse().map_err(|e| invalid(format!("{}", e)))?,
+        quote
+            .trim()
+            .parse()
+            .map_err(|e| invalid(format!("{}", e)))?,
+    ))
+}
+
+/// Resolve a `BASE-QUOTE` path segment to the direction an order quoting base in quote is
+/// signed in.
+fn pair_direction(
+    state: &AppState,
+    pair: &str,
+) -> Result<(Address, Address, PriceDirection), AppError> {
+    let (base_token, quote_token) = parse_pair(pair)?;
+    // Quoting base in quote is what an order with input=quote, output=base is signed.
+    let direction = state.price_direction(quote_token, base_token)?;
+    Ok((base_token, quote_token, direction))
+}
+
+/// Price `data` for the pair exactly as it would be signed into context[0].
+fn pair_price(
+    state: &AppState,
+    data: &PriceData,
+    base_token: Address,
+    quote_token: Address,
+    direction: PriceDirection,
+) -> Result<PairPrice, AppError> {
+    let context =
+        oracle::build_pair_context(data.price, data.expo, 0, direction, &state.token_pair)
+            .map_err(crate::upstream_error)?;
+    let price = Float::from(alloy::primitives::B256::from(context[0]))
+        .format()
+        .map_err(|e| anyhow::anyhow!("Failed to format price: {:?}", e))?;
+    Ok(PairPrice {
+        base_token,
+        quote_token,
+        price,
+        direction,
+        feed_id: state.pyth_price_feed_id.clone(),
+        publish_time: oracle::format_unix_timestamp(data.publish_time),
+    })
+}
+
+/// ETag for a price: it only changes when Pyth publishes a new price (or the pair is
+/// requested the other way round).
+fn etag(publish_time: i64, direction: PriceDirection) -> HeaderValue {
+    HeaderValue::from_str(&format!(
+        "\"{}-{}\"",
+        publish_time,
+        direction_str(direction)
+    ))
+    .expect("ETag is ASCII")
+}
+
+/// Whether `If-None-Match` lists `etag` (or `*`), compared weakly as RFC 9110 requires.
+fn not_modified(headers: &HeaderMap, etag: &HeaderValue) -> bool {
+    let Some(etag) = etag.to_str().ok() else {
+        return false;
+    };
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// `304 Not Modified` if the client already has this price, otherwise the price with its ETag.
+fn conditional(headers: &HeaderMap, publish_time: i64, price: PairPrice) -> Response {
+    let etag = etag(publish_time, price.direction);
+    if not_modified(headers, &etag) {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+    }
+    ([(header::ETAG, etag)], Json(price)).into_response()
+}
+
+/// GET handler — the feed's latest price for a pair, computed as it would be signed. Sends an
+/// ETag, so dashboards polling with `If-None-Match` get `304` until Pyth publishes again.
+#[utoipa::path(
+    get,
+    path = "/price/{pair}",
+    params(
+        ("pair" = String, Path, description = "`BASE-QUOTE` token addresses; either order of the configured pair"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag of a previously returned price"),
+    ),
+    responses(
+        (status = 200, description = "The latest price", body = PairPrice,
+            headers(("ETag" = String, description = "Changes when a new price is published"))),
+        (status = 304, description = "The price is unchanged since the given ETag"),
+        (status = 400, description = "Invalid pair", body = crate::ErrorResponse),
+        (status = 503, description = "Upstream rate limited or returned an unusable price", body = crate::ErrorResponse),
+    ),
+)]
+pub async fn price(
+    State(state): State<Arc<AppState>>,
+    Path(pair): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let (data, price) = latest(&state, &pair).await?;
+    Ok(conditional(&headers, data.publish_time, price))
+}
+
+async fn latest(state: &AppState, pair: &str) -> Result<(PriceData, PairPrice), AppError> {
+    let (base_token, quote_token, direction) = pair_direction(state, pair)?;
+    let data = crate::fetch_price(state)
+        .await
+        .map_err(crate::upstream_error)?;
+    let price = pair_price(state, &data, base_token, quote_token, direction)?;
+    Ok((data, price))
+}
+
+/// The feed's latest price for a `BASE-QUOTE` pair, as `GET /price/{pair}` returns it.
+pub(crate) async fn latest_price(state: &AppState, pair: &str) -> Result<PairPrice, AppError> {
+    Ok(latest(state, pair).await?.1)
+}
+
+/// Whether Hermes reported that it has no price for the requested time.
+fn is_not_found(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<reqwest::Error>()
+        .and_then(|e| e.status())
+        .is_some_and(|status| status == reqwest::StatusCode::NOT_FOUND)
+}
+
+/// GET handler — the price at a past timestamp from Pyth's historical data, for settling
+/// disputes and backtesting configured spreads.
+#[utoipa::path(
+    get,
+    path = "/price/{pair}/at",
+    params(
+        ("pair" = String, Path, description = "`BASE-QUOTE` token addresses; either order of the configured pair"),
+        PriceAtQuery,
+        ("If-None-Match" = Option<String>, Header, description = "ETag of a previously returned price"),
+    ),
+    responses(
+        (status = 200, description = "The historical price", body = PairPrice,
+            headers(("ETag" = String, description = "Identifies the published price"))),
+        (status = 304, description = "The price is unchanged since the given ETag"),
+        (status = 400, description = "Invalid pair or timestamp", body = crate::ErrorResponse),
+        (status = 404, description = "No price published at this time", body = crate::ErrorResponse),
+        (status = 503, description = "Upstream rate limited or returned an unusable price", body = crate::ErrorResponse),
+    ),
+)]
+pub async fn price_at(
+    State(state): State<Arc<AppState>>,
+    Path(pair): Path<String>,
+    query: Result<Query<PriceAtQuery>, QueryRejection>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let Query(query) = query.map_err(|e| OracleRequestError::InvalidQuery(e.body_text()))?;
+    let (base_token, quote_token, direction) = pair_direction(&state, &pair)?;
+
+    let now = state.clock.now().as_secs() as i64;
+    if query.timestamp > now {
+        return Err(OracleRequestError::InvalidQuery(format!(
+            "timestamp {} is in the future",
+            query.timestamp
+        ))
+        .into());
+    }
+
+    let feed_id = &state.pyth_price_feed_id;
+    let data = match state.hermes.fetch_price_at(feed_id, query.timestamp).await {
+        Ok(data) => data,
+        Err(err) if is_not_found(&err) => {
+            return Err(AppError::NotFound(format!(
+                "No Pyth price for feed {} at {}",
+                feed_id,
+                oracle::format_unix_timestamp(query.timestamp)
+            )))
+        }
+        Err(err) => return Err(crate::upstream_error(err)),
+    };
+
+    let price = pair_price(&state, &data, base_token, quote_token, direction)?;
+    Ok(conditional(&headers, data.publish_time, price))
+}
+
+/// GET handler — prices of the feed fetched in the last `seconds`, computed as they would be
+/// signed. Only prices this instance fetched while quoting are kept, so gaps mean nobody asked.
+#[utoipa::path(
+    get,
+    path = "/price/{pair}/history",
+    params(
+        ("pair" = String, Path, description = "`BASE-QUOTE` token addresses; either order of the configured pair"),
+        HistoryQuery,
+    ),
+    responses(
+        (status = 200, description = "Recent prices, oldest first", body = PriceHistoryResponse),
+        (status = 400, description = "Invalid pair or window", body = crate::ErrorResponse),
+    ),
+)]
+pub async fn price_history(
+    State(state): State<Arc<AppState>>,
+    Path(pair): Path<String>,
+    query: Result<Query<HistoryQuery>, QueryRejection>,
+) -> Result<Json<PriceHistoryResponse>, AppError> {
+    let Query(query) = query.map_err(|e| OracleRequestError::InvalidQuery(e.body_text()))?;
+    let seconds = query.seconds.unwrap_or(60);
+    if seconds == 0 {
+        return Err(
+            OracleRequestError::InvalidQuery("seconds must be positive".to_string()).into(),
+        );
+    }
+    let (base_token, quote_token, direction) = pair_direction(&state, &pair)?;
+
+    let now = state.clock.now().as_secs() as i64;
+    let since = now.saturating_sub(i64::try_from(seconds).unwrap_or(i64::MAX));
+    let samples = state
+        .price_history
+        .since(&state.pyth_price_feed_id, since)
+        .iter()
+        .map(|data| {
+            let price = pair_price(&state, data, base_token, quote_token, direction)?;
+            Ok(PriceSample {
+                price: price.price,
+                publish_time: price.publish_time,
+            })
+        })
+        .collect::<Result<_, AppError>>()?;
+    Ok(Json(PriceHistoryResponse {
+        base_token,
+        quote_token,
+        direction,
+        feed_id: state.pyth_price_feed_id.clone(),
+        samples,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{USDC, WETH};
+
+    #[test]
+    fn test_parse_pair() {
+        let (base, quote) = parse_pair(&format!("{}-{}", WETH, USDC)).unwrap();
+        assert_eq!(base, WETH.parse::<Address>().unwrap());
+        assert_eq!(quote, USDC.parse::<Address>().unwrap());
+
+        assert!(parse_pair(WETH).is_err());
+        assert!(parse_pair(&format!("{}-0x12", WETH)).is_err());
+    }
+
+    #[test]
+    fn test_not_modified() {
+        let etag = etag(1700000000, PriceDirection::AsIs);
+        assert_eq!(etag, "\"1700000000-as_is\"");
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::IF_NONE_MATCH, value.parse().unwrap());
+            headers
+        };
+        assert!(!not_modified(&HeaderMap::new(), &etag));
+        assert!(not_modified(&headers("\"1700000000-as_is\""), &etag));
+        assert!(not_modified(
+            &headers("\"1699999999-as_is\", W/\"1700000000-as_is\""),
+            &etag
+        ));
+        assert!(not_modified(&headers("*"), &etag));
+        assert!(!not_modified(&headers("\"1700000000-inverted\""), &etag));
+    }
+
+    #[test]
+    fn test_price_history_ring_buffer() {
+        let history = PriceHistory::new(3);
+        let data = |publish_time| PriceData {
+            price: 310012345678,
+            expo: -8,
+            publish_time,
+        };
+        for publish_time in [100, 101, 101, 99, 102, 103] {
+            history.record("feed", &data(publish_time));
+        }
+        let times = |since| {
+            history
+                .since("feed", since)
+                .iter()
+                .map(|d| d.publish_time)
+                .collect::<Vec<_>>()
+        };
+        // Repeats and older prices are skipped; the oldest is evicted past capacity
+        assert_eq!(times(0), [101, 102, 103]);
+        assert_eq!(times(102), [102, 103]);
+        assert!(times(104).is_empty());
+        assert!(history.since("other", 0).is_empty());
+
+        let disabled = PriceHistory::new(0);
+        disabled.record("feed", &data(100));
+        assert!(disabled.since("feed", 0).is_empty());
+    }
+}
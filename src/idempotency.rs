@@ -0,0 +1,323 @@
+use alloy::primitives::{keccak256, B256};
+use axum::http::HeaderMap;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OwnedMutexGuard;
+
+use crate::oracle::OracleResponse;
+use crate::OracleRequestError;
+
+/// Request header naming a logical quote; retries carrying the same key get the same response.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Response header set to `true` when a response was replayed for a repeated key.
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// Longest accepted `Idempotency-Key`.
+const MAX_KEY_LEN: usize = 255;
+
+/// An idempotency key, scoped to the API key that sent it so clients can't replay (or
+/// collide with) each other's quotes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IdempotencyKey {
+    pub api_key: Option<String>,
+    pub key: String,
+}
+
+impl IdempotencyKey {
+    /// Read the `Idempotency-Key` header, if present.
+    pub fn from_headers(
+        headers: &HeaderMap,
+        api_key: Option<String>,
+    ) -> Result<Option<Self>, OracleRequestError> {
+        let Some(value) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+            return Ok(None);
+        };
+        let key = value
+            .to_str()
+            .map_err(|_| OracleRequestError::InvalidIdempotencyKey("not ASCII".to_string()))?
+            .trim();
+        if key.is_empty() || key.len() > MAX_KEY_LEN {
+            return Err(OracleRequestError::InvalidIdempotencyKey(format!(
+                "must be 1 to {} characters",
+                MAX_KEY_LEN
+            )));
+        }
+        Ok(Some(Self {
+            api_key,
+            key: key.to_string(),
+        }))
+    }
+}
+
+/// Hash of what a request asked for (query string and body), so a key reused for a
+/// different request is refused rather than answered with the wrong quote.
+pub fn fingerprint(query: Option<&str>, body: &[u8]) -> B256 {
+    let query = query.unwrap_or_default();
+    let mut bytes = Vec::with_capacity(query.len() + 1 + body.len());
+    bytes.extend_from_slice(query.as_bytes());
+    bytes.push(b'\n');
+    bytes.extend_from_slice(body);
+    keccak256(bytes)
+}
+
+struct Entry {
+    expires_at: Instant,
+    fingerprint: B256,
+    response: OracleResponse,
+}
+
+/// A key's stored response, locked while a request with that key is being signed.
+type Slot = Arc<tokio::sync::Mutex<Option<Entry>>>;
+
+/// What to do with a request carrying an idempotency key.
+pub enum Attempt {
+    /// Replay the response stored for the key.
+    Replay(Box<OracleResponse>),
+    /// Sign the request, then [`Pending::complete`] it. Other requests with the key wait
+    /// until this is completed or dropped.
+    Sign(Pending),
+}
+
+/// A key being signed for. Dropping it without completing (e.g. on an error) stores
+/// nothing, so the next request with the key signs anew.
+pub struct Pending {
+    ttl: Duration,
+    slot: OwnedMutexGuard<Option<Entry>>,
+}
+
+impl Pending {
+    /// Store `response` for the key until the TTL passes, or until `lifetime` (the quote's
+    /// remaining validity, when known) ends if that is sooner.
+    pub fn complete(
+        mut self,
+        fingerprint: B256,
+        response: OracleResponse,
+        lifetime: Option<Duration>,
+        now: Instant,
+    ) {
+        let keep = lifetime.map_or(self.ttl, |lifetime| lifetime.min(self.ttl));
+        *self.slot = Some(Entry {
+            expires_at: now + keep,
+            fingerprint,
+            response,
+        });
+    }
+}
+
+/// Signed responses by idempotency key, kept for a TTL (but never past the quote's expiry) so
+/// retried requests get the exact same signature instead of accumulating several live ones.
+/// Concurrent requests with the same key are signed once: the first holds the key's slot and
+/// the others replay its response.
+pub struct IdempotencyCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<IdempotencyKey, Slot>>,
+}
+
+impl IdempotencyCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Wait for any request already signing for `key`, then replay the response stored for
+    /// it, or take the key to sign if nothing live is stored. Fails if the key was used for a
+    /// request with a different fingerprint. Drops expired keys no request is using.
+    pub async fn begin(
+        &self,
+        key: &IdempotencyKey,
+        fingerprint: B256,
+        now: Instant,
+    ) -> Result<Attempt, OracleRequestError> {
+        let slot = {
+            let mut entries = self.entries.lock().unwrap();
+            entries.retain(|_, slot| {
+                Arc::strong_count(slot) > 1
+                    || slot.try_lock().map_or(true, |entry| {
+                        entry.as_ref().is_some_and(|entry| entry.expires_at > now)
+                    })
+            });
+            entries.entry(key.clone()).or_default().clone()
+        };
+        let mut slot = slot.lock_owned().await;
+        match slot.as_ref().filter(|entry| entry.expires_at > now) {
+            Some(entry) if entry.fingerprint != fingerprint => {
+                Err(OracleRequestError::IdempotencyKeyReused(key.key.clone()))
+            }
+            Some(entry) => Ok(Attempt::Replay(Box::new(entry.response.clone()))),
+            None => {
+                *slot = None;
+                Ok(Attempt::Sign(Pending {
+                    ttl: self.ttl,
+                    slot,
+                }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{Address, Bytes};
+
+    fn key(api_key: Option<&str>) -> IdempotencyKey {
+        IdempotencyKey {
+            api_key: api_key.map(str::to_string),
+            key: "retry-1".to_string(),
+        }
+    }
+
+    fn response() -> OracleResponse {
+        OracleResponse {
+            quote_id: uuid::Uuid::new_v4(),
+            signer: Address::repeat_byte(3),
+            context: vec![],
+            signature: Bytes::from_static(b"sig"),
+            cosignatures: vec![],
+            nonce: None,
+            price: None,
+            debug: None,
+        }
+    }
+
+    /// Sign for `key` and store `response`, with the quote valid for `lifetime`.
+    async fn store(
+        cache: &IdempotencyCache,
+        key: &IdempotencyKey,
+        fingerprint: B256,
+        response: OracleResponse,
+        lifetime: Option<Duration>,
+        now: Instant,
+    ) {
+        match cache.begin(key, fingerprint, now).await.unwrap() {
+            Attempt::Sign(pending) => pending.complete(fingerprint, response, lifetime, now),
+            Attempt::Replay(_) => panic!("expected to sign"),
+        }
+    }
+
+    async fn replayed(
+        cache: &IdempotencyCache,
+        key: &IdempotencyKey,
+        fingerprint: B256,
+        now: Instant,
+    ) -> Option<OracleResponse> {
+        match cache.begin(key, fingerprint, now).await.unwrap() {
+            Attempt::Replay(response) => Some(*response),
+            Attempt::Sign(_) => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_within_ttl() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60));
+        let now = Instant::now();
+        let body = fingerprint(None, b"order");
+        let stored = response();
+        store(&cache, &key(Some("mm")), body, stored.clone(), None, now).await;
+
+        let later = now + Duration::from_secs(59);
+        let replay = replayed(&cache, &key(Some("mm")), body, later).await;
+        assert_eq!(replay.unwrap().quote_id, stored.quote_id);
+        // Scoped by API key
+        assert!(replayed(&cache, &key(Some("other")), body, now)
+            .await
+            .is_none());
+        let expired = now + Duration::from_secs(60);
+        assert!(replayed(&cache, &key(Some("mm")), body, expired)
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_replay_ends_with_the_quote() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60));
+        let now = Instant::now();
+        let body = fingerprint(None, b"order");
+        let lifetime = Some(Duration::from_secs(5));
+        store(&cache, &key(None), body, response(), lifetime, now).await;
+
+        assert!(
+            replayed(&cache, &key(None), body, now + Duration::from_secs(4))
+                .await
+                .is_some()
+        );
+        assert!(
+            replayed(&cache, &key(None), body, now + Duration::from_secs(5))
+                .await
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_sign_once() {
+        let cache = Arc::new(IdempotencyCache::new(Duration::from_secs(60)));
+        let now = Instant::now();
+        let body = fingerprint(None, b"order");
+        let Attempt::Sign(pending) = cache.begin(&key(None), body, now).await.unwrap() else {
+            panic!("expected to sign");
+        };
+
+        let retry = tokio::spawn({
+            let cache = cache.clone();
+            async move { replayed(&cache, &key(None), body, now).await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!retry.is_finished());
+        let stored = response();
+        pending.complete(body, stored.clone(), None, now);
+        assert_eq!(retry.await.unwrap().unwrap().quote_id, stored.quote_id);
+
+        // A request that fails stores nothing, so the next one signs
+        let Attempt::Sign(pending) = cache.begin(&key(Some("mm")), body, now).await.unwrap() else {
+            panic!("expected to sign");
+        };
+        drop(pending);
+        assert!(replayed(&cache, &key(Some("mm")), body, now)
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reused_for_different_request() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60));
+        let now = Instant::now();
+        store(
+            &cache,
+            &key(None),
+            fingerprint(None, b"order"),
+            response(),
+            None,
+            now,
+        )
+        .await;
+        let err = cache
+            .begin(
+                &key(None),
+                fingerprint(Some("expiry_seconds=30"), b"order"),
+                now,
+            )
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, OracleRequestError::IdempotencyKeyReused(_)));
+    }
+
+    #[test]
+    fn test_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert!(IdempotencyKey::from_headers(&headers, None)
+            .unwrap()
+            .is_none());
+        headers.insert(IDEMPOTENCY_KEY_HEADER, " retry-1 ".parse().unwrap());
+        assert_eq!(
+            IdempotencyKey::from_headers(&headers, None).unwrap(),
+            Some(key(None))
+        );
+        headers.insert(IDEMPOTENCY_KEY_HEADER, "x".repeat(256).parse().unwrap());
+        assert!(IdempotencyKey::from_headers(&headers, None).is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{Query, State};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::pairs::{self, PairInfo};
+use crate::storage::{AuditRecord, Incident, QuoteFilter};
+use crate::{oracle, AppError, AppState, OracleRequestError};
+
+/// How far back a snapshot's prices and quotes go.
+const SNAPSHOT_WINDOW_SECS: i64 = 300;
+
+/// Most recent quotes kept in a snapshot.
+const SNAPSHOT_QUOTES: usize = 20;
+
+/// Incidents `GET /incidents` returns by default, and at most.
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 1000;
+
+/// Captures incidents at most once per guard per `cooldown`, so a guard that stays tripped
+/// (every request refused on a deviation, say) records the start of it rather than a
+/// snapshot per request.
+#[derive(Debug)]
+pub struct IncidentRecorder {
+    cooldown: Duration,
+    /// Unix seconds each guard was last captured
+    last: Mutex<HashMap<String, u64>>,
+}
+
+impl Default for IncidentRecorder {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(SNAPSHOT_WINDOW_SECS as u64))
+    }
+}
+
+impl IncidentRecorder {
+    pub fn new(cooldown: Duration) -> Self {
+        Self {
+            cooldown,
+            last: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether `guard` tripping at `now` should be captured, claiming the capture if so.
+    fn claim(&self, guard: &str, now: u64) -> bool {
+        let mut last = self.last.lock().unwrap();
+        if last
+            .get(guard)
+            .is_some_and(|at| now < at + self.cooldown.as_secs())
+        {
+            return false;
+        }
+        last.insert(guard.to_string(), now);
+        true
+    }
+}
+
+/// A price seen from one source around the time of an incident.
+#[derive(Debug, Serialize)]
+pub struct SnapshotPrice {
+    /// `pyth` for the configured feed, `reference` for the reference price
+    pub source: String,
+    pub price: String,
+    /// Publish time (ISO 8601), when the source reports one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub publish_time: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Snapshot {
+    /// Feed prices fetched in the last five minutes, oldest first, then any other source's
+    prices: Vec<SnapshotPrice>,
+    /// The last quotes signed for the pair in the last five minutes, oldest first
+    recent_quotes: Vec<AuditRecord>,
+    /// The pair configuration in effect, pricing included even when `/pairs` redacts it
+    config: PairInfo,
+}
+
+/// Record that `guard` (an error code) tripped because of `detail`, with a snapshot of recent
+/// feed prices, `prices` from other sources, recent quotes and the pair configuration. Skipped
+/// within the cooldown of the guard's last capture; failures are logged, never returned, so a
+/// storage outage doesn't change how the guard itself responds.
+pub async fn capture(state: &AppState, guard: &str, detail: String, prices: Vec<SnapshotPrice>) {
+    let now = state.clock.now().as_secs();
+    if !state.incidents.claim(guard, now) {
+        return;
+    }
+    let since = now as i64 - SNAPSHOT_WINDOW_SECS;
+    let feed_prices = state
+        .price_history
+        .since(&state.pyth_price_feed_id, since)
+        .into_iter()
+        .map(|data| SnapshotPrice {
+            source: "pyth".to_string(),
+            price: oracle::format_pyth_price(data.price, data.expo),
+            publish_time: Some(oracle::format_unix_timestamp(data.publish_time)),
+        });
+    let pair = &state.token_pair;
+    let filter = QuoteFilter {
+        since: Some(since),
+        until: None,
+        pair: Some((pair.base_token, pair.quote_token)),
+    };
+    let recent_quotes = match state.storage.quotes(&filter).await {
+        Ok(mut quotes) => {
+            let skip = quotes.len().saturating_sub(SNAPSHOT_QUOTES);
+            quotes.drain(..skip);
+            quotes
+        }
+        Err(e) => {
+            tracing::warn!(
+                "Failed to load recent quotes for incident snapshot: {:#}",
+                e
+            );
+            Vec::new()
+        }
+    };
+    let mut config = pairs::pair_info(state).await;
+    config.pricing = Some(pairs::pricing_info(state));
+    let snapshot = Snapshot {
+        prices: feed_prices.chain(prices).collect(),
+        recent_quotes,
+        config,
+    };
+
+    let incident = Incident {
+        incident_id: Uuid::new_v4(),
+        occurred_at: now as i64,
+        guard: guard.to_string(),
+        detail,
+        snapshot: match serde_json::to_value(&snapshot) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                tracing::error!("Failed to serialize incident snapshot: {}", e);
+                return;
+            }
+        },
+    };
+    match state.storage.record_incident(&incident).await {
+        Ok(()) => tracing::warn!(
+            "Recorded incident {} ({}): {}",
+            incident.incident_id,
+            incident.guard,
+            incident.detail
+        ),
+        Err(e) => tracing::error!("Failed to record {} incident: {:#}", guard, e),
+    }
+}
+
+/// Query for `GET /incidents`.
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct IncidentsQuery {
+    /// Most incidents to return (default 50, at most 1000)
+    limit: Option<usize>,
+}
+
+/// Response for `GET /incidents`.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct IncidentsResponse {
+    /// Newest first
+    pub incidents: Vec<Incident>,
+}
+
+/// GET handler — recent incidents, each with the snapshot captured when its guard tripped.
+#[utoipa::path(
+    get,
+    path = "/incidents",
+    params(IncidentsQuery),
+    responses(
+        (status = 200, description = "Recent incidents", body = IncidentsResponse),
+        (status = 400, description = "Invalid limit", body = crate::ErrorResponse),
+    ),
+)]
+pub async fn incidents(
+    State(state): State<Arc<AppState>>,
+    query: Result<Query<IncidentsQuery>, QueryRejection>,
+) -> Result<Json<IncidentsResponse>, AppError> {
+    let Query(query) = query.map_err(|e| OracleRequestError::InvalidQuery(e.body_text()))?;
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if limit > MAX_LIMIT {
+        return Err(OracleRequestError::InvalidQuery(format!(
+            "limit must be at most {}",
+            MAX_LIMIT
+        ))
+        .into());
+    }
+    Ok(Json(IncidentsResponse {
+        incidents: state.storage.incidents(limit).await?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claim_once_per_cooldown() {
+        let recorder = IncidentRecorder::new(Duration::from_secs(60));
+        assert!(recorder.claim("circuit_open", 100));
+        assert!(!recorder.claim("circuit_open", 159));
+        assert!(recorder.claim("reference_deviation", 159));
+        assert!(recorder.claim("circuit_open", 160));
+    }
+}
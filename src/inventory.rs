@@ -0,0 +1,288 @@
+use alloy::primitives::{utils::format_units, Address, Bytes, B256, U256};
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use rain_math_float::Float;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OwnedMutexGuard;
+
+use crate::order::{Io, OrderVersion};
+use crate::storage::{AuditRecord, QuoteFilter};
+use crate::{rpc, AppError, AppState, ExpiryMode, UnavailableError};
+
+sol! {
+    /// OrderBookV4 (OrderV3): the raw token amount in a vault.
+    function vaultBalance(address owner, address token, uint256 vaultId) external view returns (uint256);
+
+    /// OrderBookV5 (OrderV4): the vault balance as a Rain float.
+    function vaultBalance2(address owner, address token, bytes32 vaultId) external view returns (bytes32);
+}
+
+/// The vault an order's output is paid from.
+#[derive(Debug, Clone, Copy)]
+pub struct Vault {
+    pub version: OrderVersion,
+    pub owner: Address,
+    pub output: Io,
+}
+
+impl Vault {
+    /// Identifies the vault across orders that share it, as `owner:token:vault_id`.
+    pub fn key(&self) -> String {
+        format!(
+            "{}:{}:{}",
+            self.owner, self.output.token, self.output.vault_id
+        )
+    }
+
+    fn calldata(&self) -> Bytes {
+        match self.version {
+            OrderVersion::V4 => vaultBalance2Call {
+                owner: self.owner,
+                token: self.output.token,
+                vaultId: self.output.vault_id,
+            }
+            .abi_encode(),
+            OrderVersion::V3 => vaultBalanceCall {
+                owner: self.owner,
+                token: self.output.token,
+                vaultId: U256::from_be_bytes(self.output.vault_id.0),
+            }
+            .abi_encode(),
+        }
+        .into()
+    }
+
+    /// Parse the balance call's return data as a token amount.
+    fn parse_balance(&self, data: &[u8]) -> anyhow::Result<f64> {
+        let decimal = match self.version {
+            OrderVersion::V4 => {
+                let balance: B256 = vaultBalance2Call::abi_decode_returns(data)?;
+                Float::from(balance)
+                    .format()
+                    .map_err(|e| anyhow::anyhow!("Invalid vault balance: {:?}", e))?
+            }
+            OrderVersion::V3 => {
+                let balance: U256 = vaultBalanceCall::abi_decode_returns(data)?;
+                let decimals = self.output.decimals.ok_or_else(|| {
+                    anyhow::anyhow!("Order declares no decimals for {}", self.output.token)
+                })?;
+                format_units(balance, decimals)?
+            }
+        };
+        Ok(decimal.parse()?)
+    }
+}
+
+/// Stops signing against an output vault once it can't cover every quote that might still
+/// be taken against it, so concurrent takers can't oversell the vault. Each unexpired quote
+/// (whose signed expiry hasn't passed) is assumed to take up to `quote_size` of the output
+/// token, whichever order sharing the vault it was signed for.
+#[derive(Debug)]
+pub struct InventoryThrottle {
+    rpc_url: String,
+    orderbook: Address,
+    quote_size: f64,
+    client: reqwest::Client,
+    /// Per vault key, held from the outstanding count until the quote is recorded, so two
+    /// takers can't both claim the last of a vault's inventory
+    reservations: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+/// Signing another quote could take more than the output vault holds.
+#[derive(Debug, Clone, Copy, PartialEq, thiserror::Error)]
+#[error("Output vault holds {balance}, not enough for another quote of {quote_size} on top of {outstanding} outstanding")]
+pub struct InventoryExhausted {
+    pub balance: f64,
+    pub quote_size: f64,
+    pub outstanding: u64,
+    /// Seconds until the oldest outstanding quote expires
+    pub retry_after_secs: Option<u64>,
+}
+
+impl InventoryThrottle {
+    /// `quote_size` is in whole output tokens and must be positive.
+    pub fn new(rpc_url: &str, orderbook: Address, quote_size: f64) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            quote_size.is_finite() && quote_size > 0.0,
+            "Inventory quote size must be positive"
+        );
+        Ok(Self {
+            rpc_url: rpc_url.to_string(),
+            orderbook,
+            quote_size,
+            client: reqwest::Client::new(),
+            reservations: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn quote_size(&self) -> f64 {
+        self.quote_size
+    }
+
+    /// The vault's current balance, in whole tokens.
+    pub async fn balance(&self, vault: &Vault) -> anyhow::Result<f64> {
+        let result: String = rpc::call_with(
+            &self.client,
+            &self.rpc_url,
+            "eth_call",
+            json!([{ "to": self.orderbook, "data": vault.calldata() }, "latest"]),
+        )
+        .await?;
+        let data = hex::decode(result.trim_start_matches("0x"))?;
+        vault.parse_balance(&data)
+    }
+
+    /// Wait for any other reservation for the vault `key` to be released, then take it.
+    async fn lock(&self, key: String) -> OwnedMutexGuard<()> {
+        let lock = {
+            let mut reservations = self.reservations.lock().unwrap();
+            // Drop the locks nobody holds or waits for
+            reservations.retain(|_, lock| Arc::strong_count(lock) > 1);
+            reservations.entry(key).or_default().clone()
+        };
+        lock.lock_owned().await
+    }
+
+    /// Check another quote fits in `balance` alongside `outstanding` unexpired ones.
+    pub fn check(&self, balance: f64, outstanding: u64) -> Result<(), InventoryExhausted> {
+        if (outstanding + 1) as f64 * self.quote_size > balance {
+            return Err(InventoryExhausted {
+                balance,
+                quote_size: self.quote_size,
+                outstanding,
+                retry_after_secs: None,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Longest a quote issued now could stay valid for, in seconds: the maximum expiry plus the
+/// safety margin for timestamp expiry (block alignment never goes past that), or the expiry's
+/// blocks at the configured block time. `None` when that can't be known (block expiry without
+/// a block time).
+fn quote_lifetime(state: &AppState) -> Option<u64> {
+    let block_time = state.expiry_block_time.filter(|&t| t > 0);
+    match state.token_pair.expiry {
+        ExpiryMode::Timestamp => {
+            Some(state.max_expiry_seconds + state.expiry_safety_margin.as_secs_f64().ceil() as u64)
+        }
+        ExpiryMode::BlockNumber { blocks } => block_time.map(|t| blocks * t),
+    }
+}
+
+/// A recorded quote's signed expiry (`context[1]`), as unix seconds or a block number.
+fn signed_expiry(record: &AuditRecord) -> Option<u64> {
+    let expiry = Float::from(*record.context.get(1)?).format().ok()?;
+    Some(expiry.parse::<f64>().ok()? as u64)
+}
+
+/// Refuse to sign against `vault` once it is spoken for. The returned guard must be held
+/// until the quote is recorded; it only holds up other quotes against the same vault.
+pub async fn reserve(
+    state: &AppState,
+    vault: &Vault,
+) -> Result<Option<OwnedMutexGuard<()>>, AppError> {
+    let Some(throttle) = &state.inventory else {
+        return Ok(None);
+    };
+    let key = vault.key();
+    let guard = throttle.lock(key.clone()).await;
+    let now = state.clock.now().as_secs();
+    // Quotes count until the expiry they were signed with, so compare against the current
+    // block for block-number expiry
+    let current = match state.token_pair.expiry {
+        ExpiryMode::Timestamp => now,
+        ExpiryMode::BlockNumber { .. } => {
+            rpc::block_number_with(&throttle.client, &throttle.rpc_url)
+                .await
+                .map_err(|e| UnavailableError::InventoryUnavailable(e.to_string()))?
+        }
+    };
+    let filter = QuoteFilter {
+        since: quote_lifetime(state).map(|lifetime| now.saturating_sub(lifetime) as i64),
+        until: None,
+        pair: Some((state.token_pair.base_token, state.token_pair.quote_token)),
+    };
+    let mut outstanding: Vec<u64> = state
+        .storage
+        .quotes(&filter)
+        .await?
+        .iter()
+        .filter(|record| record.output_vault.as_deref() == Some(key.as_str()))
+        .filter_map(signed_expiry)
+        .filter(|&expiry| expiry > current)
+        .collect();
+    outstanding.sort_unstable();
+    let balance = throttle
+        .balance(vault)
+        .await
+        .map_err(|e| UnavailableError::InventoryUnavailable(e.to_string()))?;
+    throttle
+        .check(balance, outstanding.len() as u64)
+        .map_err(|mut e| {
+            // The first to expire frees up room
+            e.retry_after_secs = match state.token_pair.expiry {
+                ExpiryMode::Timestamp => outstanding.first().map(|&expiry| expiry - now),
+                ExpiryMode::BlockNumber { .. } => state
+                    .expiry_block_time
+                    .zip(outstanding.first())
+                    .map(|(t, &expiry)| (expiry - current) * t),
+            };
+            UnavailableError::InventoryExhausted(e)
+        })?;
+    Ok(Some(guard))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::sol_types::SolValue;
+
+    fn vault(version: OrderVersion) -> Vault {
+        Vault {
+            version,
+            owner: Address::repeat_byte(0x11),
+            output: Io {
+                token: Address::repeat_byte(0x22),
+                decimals: Some(6),
+                vault_id: B256::with_last_byte(7),
+            },
+        }
+    }
+
+    #[test]
+    fn test_calldata() {
+        let v4 = vault(OrderVersion::V4).calldata();
+        assert_eq!(v4[..4], vaultBalance2Call::SELECTOR);
+        let v3 = vault(OrderVersion::V3).calldata();
+        assert_eq!(v3[..4], vaultBalanceCall::SELECTOR);
+        // Both encode the vault ID as the same 32-byte word
+        assert_eq!(v4[4..], v3[4..]);
+    }
+
+    #[test]
+    fn test_parse_balance() {
+        let raw = U256::from(2_500_000u64).abi_encode();
+        assert_eq!(vault(OrderVersion::V3).parse_balance(&raw).unwrap(), 2.5);
+        let float: B256 = Float::parse("2.5".to_string()).unwrap().into();
+        assert_eq!(
+            vault(OrderVersion::V4)
+                .parse_balance(&float.abi_encode())
+                .unwrap(),
+            2.5
+        );
+        assert!(vault(OrderVersion::V3).parse_balance(&[]).is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let throttle = InventoryThrottle::new("http://127.0.0.1:1", Address::ZERO, 1.0).unwrap();
+        assert!(throttle.check(2.5, 1).is_ok());
+        let err = throttle.check(2.5, 2).unwrap_err();
+        assert_eq!(err.outstanding, 2);
+        assert!(InventoryThrottle::new("http://127.0.0.1:1", Address::ZERO, 0.0).is_err());
+    }
+}
@@ -0,0 +1,80 @@
+use alloy::primitives::{FixedBytes, B256};
+use rain_math_float::Float;
+
+/// Raises the signed price by a random amount of up to `max_bps` basis points on every
+/// quote, so takers can't reverse-engineer the exact feed price and timing from our quotes
+/// to front-run our updates. The jitter only ever moves the price against the taker.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceJitter {
+    max_bps: u32,
+}
+
+impl PriceJitter {
+    /// `max_bps` must be between 1 and 10000.
+    pub fn new(max_bps: u32) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            (1..=10_000).contains(&max_bps),
+            "Price jitter must be between 1 and 10000 bps"
+        );
+        Ok(Self { max_bps })
+    }
+
+    pub fn max_bps(&self) -> u32 {
+        self.max_bps
+    }
+
+    /// Multiplier for a uniform sample `u` in `[0, 1)`: `1 + u * max_bps / 10000`.
+    fn factor_for(&self, u: f64) -> anyhow::Result<Float> {
+        let factor = format!("{:.12}", 1.0 + u * f64::from(self.max_bps) / 10_000.0);
+        Float::parse(factor.clone())
+            .map_err(|e| anyhow::anyhow!("Failed to parse jitter factor {}: {:?}", factor, e))
+    }
+
+    /// Multiply a context price slot by a fresh random factor.
+    pub fn apply(&self, price: FixedBytes<32>) -> anyhow::Result<FixedBytes<32>> {
+        let random = B256::random();
+        let bits = u64::from_be_bytes(random[..8].try_into().expect("8 bytes"));
+        // Top 53 bits, uniform in [0, 1)
+        let u = (bits >> 11) as f64 / (1u64 << 53) as f64;
+        let jittered = (Float::from(B256::from(price)) * self.factor_for(u)?)
+            .map_err(|e| anyhow::anyhow!("Failed to jitter price: {:?}", e))?;
+        let bytes: B256 = jittered.into();
+        Ok(FixedBytes::from(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(slot: FixedBytes<32>) -> f64 {
+        Float::from(B256::from(slot))
+            .format()
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_new() {
+        assert!(PriceJitter::new(0).is_err());
+        assert!(PriceJitter::new(10_001).is_err());
+        assert_eq!(PriceJitter::new(5).unwrap().max_bps(), 5);
+    }
+
+    #[test]
+    fn test_jitter_stays_in_band_against_taker() {
+        let jitter = PriceJitter::new(10).unwrap();
+        let factor = |u| jitter.factor_for(u).unwrap().format().unwrap();
+        assert_eq!(factor(0.0).parse::<f64>().unwrap(), 1.0);
+        assert_eq!(factor(0.5).parse::<f64>().unwrap(), 1.0005);
+
+        let original: B256 = Float::parse("3100".to_string()).unwrap().into();
+        let original = FixedBytes::from(original);
+        let prices: Vec<f64> = (0..50)
+            .map(|_| price(jitter.apply(original).unwrap()))
+            .collect();
+        assert!(prices.iter().all(|p| (3100.0..3100.0 * 1.001).contains(p)));
+        assert!(prices.iter().any(|p| *p != prices[0]));
+    }
+}
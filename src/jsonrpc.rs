@@ -0,0 +1,351 @@
+use axum::{
+    body::Bytes,
+    extract::{Extension, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::{auth, quota, AppError, AppState, ErrorResponse};
+
+/// JSON-RPC 2.0 error codes.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+/// Any other refusal; `data.error` carries the same code the REST endpoints would return
+const SERVER_ERROR: i64 = -32000;
+
+#[derive(Deserialize)]
+struct RpcRequest {
+    jsonrpc: String,
+    method: String,
+    #[serde(default)]
+    params: Value,
+    /// Absent for notifications, which get no response
+    id: Option<Value>,
+}
+
+#[derive(Serialize)]
+struct RpcResponse {
+    jsonrpc: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+    id: Value,
+}
+
+#[derive(Debug, Serialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+    /// Boxed to keep `Result<_, RpcError>` small
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Box<ErrorResponse>>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+impl From<AppError> for RpcError {
+    fn from(err: AppError) -> Self {
+        let (code, data) = match &err {
+            AppError::Internal(e) => {
+                let error_id = crate::log_internal_error(e);
+                (
+                    INTERNAL_ERROR,
+                    ErrorResponse {
+                        error: err.error_code().to_string(),
+                        detail: crate::redacted_detail(&error_id),
+                        error_id: Some(error_id),
+                        guard: None,
+                    },
+                )
+            }
+            AppError::BadRequest(_) => (INVALID_PARAMS, ErrorResponse::from(&err)),
+            _ => (SERVER_ERROR, ErrorResponse::from(&err)),
+        };
+        Self {
+            code,
+            message: data.detail.clone(),
+            data: Some(Box::new(data)),
+        }
+    }
+}
+
+impl From<&AppError> for ErrorResponse {
+    fn from(err: &AppError) -> Self {
+        Self {
+            error: err.error_code().to_string(),
+            detail: err.detail(),
+            error_id: None,
+            guard: err.guard(),
+        }
+    }
+}
+
+/// Params of `oracle_getSignedContext`, by position (`[order, options]`) or by name.
+#[derive(Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub(crate) struct SignedContextParams {
+    /// `0x`-prefixed ABI-encoded request, as `/context` takes
+    order: String,
+    #[cfg_attr(feature = "ts", ts(as = "Option<u32>", optional))]
+    expiry_seconds: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct ContextOptions {
+    expiry_seconds: Option<u64>,
+}
+
+/// Params of `oracle_getPrice`: an optional `BASE-QUOTE` pair, by position or by name.
+#[derive(Deserialize, Default)]
+struct PriceParams {
+    pair: Option<String>,
+}
+
+fn invalid_params(e: impl std::fmt::Display) -> RpcError {
+    RpcError::new(INVALID_PARAMS, format!("Invalid params: {}", e))
+}
+
+fn signed_context_params(params: Value) -> Result<SignedContextParams, RpcError> {
+    match params {
+        Value::Array(mut params) if (1..=2).contains(&params.len()) => {
+            let options = match (params.len() == 2).then(|| params.pop()).flatten() {
+                Some(options) => {
+                    serde_json::from_value::<ContextOptions>(options).map_err(invalid_params)?
+                }
+                None => ContextOptions {
+                    expiry_seconds: None,
+                },
+            };
+            Ok(SignedContextParams {
+                order: serde_json::from_value(params.remove(0)).map_err(invalid_params)?,
+                expiry_seconds: options.expiry_seconds,
+            })
+        }
+        Value::Object(_) => serde_json::from_value(params).map_err(invalid_params),
+        _ => Err(invalid_params(
+            "expected [order] or [order, {expiry_seconds}]",
+        )),
+    }
+}
+
+fn price_params(params: Value) -> Result<PriceParams, RpcError> {
+    match params {
+        Value::Null => Ok(PriceParams::default()),
+        Value::Array(params) if params.is_empty() => Ok(PriceParams::default()),
+        Value::Array(mut params) if params.len() == 1 => Ok(PriceParams {
+            pair: serde_json::from_value(params.remove(0)).map_err(invalid_params)?,
+        }),
+        Value::Object(_) => serde_json::from_value(params).map_err(invalid_params),
+        _ => Err(invalid_params("expected [] or [pair]")),
+    }
+}
+
+/// What a call may use from the HTTP request it arrived in.
+struct Call<'a> {
+    state: &'a AppState,
+    identity: Option<&'a auth::Authenticated>,
+    api_key: Option<&'a quota::ApiKeyName>,
+    headers: &'a HeaderMap,
+}
+
+impl Call<'_> {
+    async fn dispatch(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        let result = match method {
+            "oracle_getSignedContext" => {
+                let params = signed_context_params(params)?;
+                let query = params
+                    .expiry_seconds
+                    .map(|secs| format!("expiry_seconds={}", secs));
+                let (response, _) = crate::signed_context_recorded(
+                    self.state,
+                    Ok(axum::extract::Query(crate::ContextQuery {
+                        expiry_seconds: params.expiry_seconds,
+                    })),
+                    query.as_deref(),
+                    self.identity.cloned().map(Extension),
+                    self.api_key.cloned().map(Extension),
+                    self.headers,
+                    params.order.as_bytes(),
+                )
+                .await?;
+                serde_json::to_value(response)
+            }
+            "oracle_getPrice" => {
+                let pair = match price_params(params)?.pair {
+                    Some(pair) => pair,
+                    None => format!(
+                        "{}-{}",
+                        self.state.token_pair.base_token, self.state.token_pair.quote_token
+                    ),
+                };
+                serde_json::to_value(crate::history::latest_price(self.state, &pair).await?)
+            }
+            "oracle_listPairs" => {
+                serde_json::to_value(vec![crate::status::pair_status(self.state)])
+            }
+            _ => {
+                return Err(RpcError::new(
+                    METHOD_NOT_FOUND,
+                    format!("Method not found: {}", method),
+                ))
+            }
+        };
+        result.map_err(|e| AppError::Internal(e.into()).into())
+    }
+
+    /// Run one request, or `None` for a notification.
+    async fn handle(&self, request: Value) -> Option<RpcResponse> {
+        let request: RpcRequest = match serde_json::from_value(request) {
+            Ok(request) => request,
+            Err(e) => {
+                return Some(failure(
+                    Value::Null,
+                    RpcError::new(INVALID_REQUEST, format!("Invalid request: {}", e)),
+                ))
+            }
+        };
+        if request.jsonrpc != "2.0" {
+            return Some(failure(
+                request.id.unwrap_or_default(),
+                RpcError::new(INVALID_REQUEST, "Invalid request: jsonrpc must be \"2.0\""),
+            ));
+        }
+        let result = self.dispatch(&request.method, request.params).await;
+        let id = request.id?;
+        Some(match result {
+            Ok(result) => RpcResponse {
+                jsonrpc: "2.0",
+                result: Some(result),
+                error: None,
+                id,
+            },
+            Err(error) => failure(id, error),
+        })
+    }
+}
+
+fn failure(id: Value, error: RpcError) -> RpcResponse {
+    RpcResponse {
+        jsonrpc: "2.0",
+        result: None,
+        error: Some(error),
+        id,
+    }
+}
+
+/// POST handler — JSON-RPC 2.0 facade over `/context`, `/price/{pair}` and the configured
+/// pairs, for tooling built around Ethereum-style RPC clients. Batches are supported.
+#[utoipa::path(
+    post,
+    path = "/rpc",
+    request_body(content = Object, description = "JSON-RPC 2.0 request or batch: `oracle_getSignedContext`, `oracle_getPrice` or `oracle_listPairs`"),
+    responses(
+        (status = 200, description = "JSON-RPC 2.0 response or batch of responses", body = Object),
+        (status = 204, description = "Only notifications were sent"),
+        (status = 404, description = "The JSON-RPC facade is not enabled"),
+    ),
+)]
+pub async fn rpc(
+    State(state): State<Arc<AppState>>,
+    identity: Option<Extension<auth::Authenticated>>,
+    api_key: Option<Extension<quota::ApiKeyName>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let call = Call {
+        state: &state,
+        identity: identity.as_ref().map(|Extension(identity)| identity),
+        api_key: api_key.as_ref().map(|Extension(api_key)| api_key),
+        headers: &headers,
+    };
+    let request: Value = match serde_json::from_slice(&body) {
+        Ok(request) => request,
+        Err(e) => {
+            let error = RpcError::new(PARSE_ERROR, format!("Parse error: {}", e));
+            return Json(failure(Value::Null, error)).into_response();
+        }
+    };
+    match request {
+        Value::Array(requests) if requests.is_empty() => Json(failure(
+            Value::Null,
+            RpcError::new(INVALID_REQUEST, "Invalid request: empty batch"),
+        ))
+        .into_response(),
+        Value::Array(requests) => {
+            let mut responses = Vec::new();
+            for request in requests {
+                responses.extend(call.handle(request).await);
+            }
+            if responses.is_empty() {
+                StatusCode::NO_CONTENT.into_response()
+            } else {
+                Json(responses).into_response()
+            }
+        }
+        request => match call.handle(request).await {
+            Some(response) => Json(response).into_response(),
+            None => StatusCode::NO_CONTENT.into_response(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_signed_context_params() {
+        let params = signed_context_params(json!(["0x00"])).unwrap();
+        assert_eq!(params.order, "0x00");
+        assert_eq!(params.expiry_seconds, None);
+        let params = signed_context_params(json!(["0x00", {"expiry_seconds": 30}])).unwrap();
+        assert_eq!(params.expiry_seconds, Some(30));
+        let params = signed_context_params(json!({"order": "0x00", "expiry_seconds": 30})).unwrap();
+        assert_eq!(params.expiry_seconds, Some(30));
+
+        for params in [
+            json!([]),
+            json!([1]),
+            json!("0x00"),
+            json!({"expiry_seconds": 30}),
+        ] {
+            let err = signed_context_params(params).err().unwrap();
+            assert_eq!(err.code, INVALID_PARAMS);
+        }
+    }
+
+    #[test]
+    fn test_price_params() {
+        assert!(price_params(Value::Null).unwrap().pair.is_none());
+        assert!(price_params(json!([])).unwrap().pair.is_none());
+        assert_eq!(
+            price_params(json!(["a-b"])).unwrap().pair.as_deref(),
+            Some("a-b")
+        );
+        assert_eq!(
+            price_params(json!({"pair": "a-b"}))
+                .unwrap()
+                .pair
+                .as_deref(),
+            Some("a-b")
+        );
+        assert!(price_params(json!(["a-b", "c-d"])).is_err());
+    }
+}
@@ -0,0 +1,329 @@
+use async_trait::async_trait;
+use axum::http::request::Parts;
+use jsonwebtoken::{jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::auth::{AuthError, Authenticator, Identity};
+
+/// Minimum time between JWKS refetches triggered by unknown key IDs.
+const JWKS_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Claims attached to the request (as an extension) once a bearer token is verified.
+#[derive(Debug, Clone, Deserialize)]
+pub struct JwtClaims {
+    pub sub: Option<String>,
+    /// Space-separated OAuth scopes
+    #[serde(default)]
+    pub scope: Option<String>,
+}
+
+impl JwtClaims {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scope
+            .as_deref()
+            .is_some_and(|scopes| scopes.split_whitespace().any(|s| s == scope))
+    }
+}
+
+enum JwtKeys {
+    /// HMAC shared secret (HS256/384/512)
+    Secret(DecodingKey),
+    /// Asymmetric keys from a JWKS endpoint, refetched when an unknown `kid` appears
+    Jwks {
+        url: String,
+        set: RwLock<JwkSet>,
+        last_refresh: Mutex<Instant>,
+    },
+}
+
+/// JWT bearer token authentication against a shared secret or a JWKS URL.
+pub struct JwtAuth {
+    keys: JwtKeys,
+    issuer: Option<String>,
+    audience: Option<String>,
+    required_scope: Option<String>,
+}
+
+async fn fetch_jwks(url: &str) -> anyhow::Result<JwkSet> {
+    Ok(reqwest::get(url).await?.error_for_status()?.json().await?)
+}
+
+impl JwtAuth {
+    /// Validate HS256/384/512 tokens signed with a shared secret.
+    pub fn with_secret(secret: &[u8]) -> Self {
+        Self::from_keys(JwtKeys::Secret(DecodingKey::from_secret(secret)))
+    }
+
+    /// Validate asymmetric tokens against keys published at a JWKS URL.
+    pub async fn with_jwks_url(url: &str) -> anyhow::Result<Self> {
+        let set = fetch_jwks(url).await?;
+        tracing::info!("Loaded {} JWKS key(s) from {}", set.keys.len(), url);
+        Ok(Self::from_keys(JwtKeys::Jwks {
+            url: url.to_string(),
+            set: RwLock::new(set),
+            last_refresh: Mutex::new(Instant::now()),
+        }))
+    }
+
+    fn from_keys(keys: JwtKeys) -> Self {
+        Self {
+            keys,
+            issuer: None,
+            audience: None,
+            required_scope: None,
+        }
+    }
+
+    pub fn with_issuer(mut self, issuer: &str) -> Self {
+        self.issuer = Some(issuer.to_string());
+        self
+    }
+
+    pub fn with_audience(mut self, audience: &str) -> Self {
+        self.audience = Some(audience.to_string());
+        self
+    }
+
+    /// Require this scope to be present in the token's `scope` claim.
+    pub fn with_required_scope(mut self, scope: &str) -> Self {
+        self.required_scope = Some(scope.to_string());
+        self
+    }
+
+    fn validation(&self, algorithms: &[Algorithm]) -> Validation {
+        let mut validation = Validation::new(algorithms[0]);
+        validation.algorithms = algorithms.to_vec();
+        match &self.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        validation
+    }
+
+    /// Find the JWKS key for `kid`, refetching the set (rate limited) if it is unknown.
+    async fn jwks_key(
+        &self,
+        url: &str,
+        set: &RwLock<JwkSet>,
+        last_refresh: &Mutex<Instant>,
+        kid: Option<&str>,
+    ) -> Result<DecodingKey, AuthError> {
+        let lookup = |set: &JwkSet| match kid {
+            Some(kid) => set.find(kid).cloned(),
+            None => set.keys.first().cloned(),
+        };
+
+        if let Some(jwk) = lookup(&set.read().unwrap()) {
+            return DecodingKey::from_jwk(&jwk).map_err(|e| AuthError::InvalidToken(e.to_string()));
+        }
+
+        let should_refresh = {
+            let mut last = last_refresh.lock().unwrap();
+            let due = last.elapsed() >= JWKS_REFRESH_INTERVAL;
+            if due {
+                *last = Instant::now();
+            }
+            due
+        };
+        if should_refresh {
+            match fetch_jwks(url).await {
+                Ok(fresh) => *set.write().unwrap() = fresh,
+                Err(e) => tracing::warn!("Failed to refresh JWKS from {}: {}", url, e),
+            }
+        }
+
+        let jwk = lookup(&set.read().unwrap())
+            .ok_or_else(|| AuthError::InvalidToken(format!("unknown key id {:?}", kid)))?;
+        DecodingKey::from_jwk(&jwk).map_err(|e| AuthError::InvalidToken(e.to_string()))
+    }
+
+    /// Verify a bearer token and return its claims.
+    pub async fn verify(&self, token: &str) -> Result<JwtClaims, AuthError> {
+        let header = jsonwebtoken::decode_header(token)
+            .map_err(|e| AuthError::InvalidToken(e.to_string()))?;
+
+        let (key, validation) = match &self.keys {
+            JwtKeys::Secret(key) => (
+                key.clone(),
+                self.validation(&[Algorithm::HS256, Algorithm::HS384, Algorithm::HS512]),
+            ),
+            JwtKeys::Jwks {
+                url,
+                set,
+                last_refresh,
+            } => {
+                // Never accept symmetric algorithms against public keys (alg confusion).
+                if matches!(
+                    header.alg,
+                    Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+                ) {
+                    return Err(AuthError::InvalidToken(format!(
+                        "algorithm {:?} not allowed with JWKS",
+                        header.alg
+                    )));
+                }
+                let key = self
+                    .jwks_key(url, set, last_refresh, header.kid.as_deref())
+                    .await?;
+                (key, self.validation(&[header.alg]))
+            }
+        };
+
+        let claims = jsonwebtoken::decode::<JwtClaims>(token, &key, &validation)
+            .map_err(|e| AuthError::InvalidToken(e.to_string()))?
+            .claims;
+
+        if let Some(scope) = &self.required_scope {
+            if !claims.has_scope(scope) {
+                return Err(AuthError::InsufficientScope(scope.clone()));
+            }
+        }
+
+        Ok(claims)
+    }
+}
+
+/// Verifies the `Authorization: Bearer` token and attaches [`JwtClaims`] to the request.
+#[async_trait]
+impl Authenticator for JwtAuth {
+    fn name(&self) -> &'static str {
+        "jwt"
+    }
+
+    async fn authenticate(
+        &self,
+        parts: &mut Parts,
+        _body: &[u8],
+        _now: u64,
+    ) -> Result<Option<Identity>, AuthError> {
+        let Some(header) = parts.headers.get(axum::http::header::AUTHORIZATION) else {
+            return Ok(None);
+        };
+        let header = header
+            .to_str()
+            .map_err(|_| AuthError::MalformedHeader("authorization"))?;
+        let token = header
+            .strip_prefix("Bearer ")
+            .or_else(|| header.strip_prefix("bearer "))
+            .ok_or(AuthError::MalformedHeader("authorization"))?
+            .trim();
+
+        let claims = self.verify(token).await?;
+        tracing::debug!(
+            "JWT authenticated: sub={:?} scope={:?}",
+            claims.sub,
+            claims.scope
+        );
+        let subject = claims.sub.clone();
+        parts.extensions.insert(claims);
+        Ok(Some(Identity {
+            mechanism: self.name(),
+            subject,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct TestClaims<'a> {
+        sub: &'a str,
+        scope: &'a str,
+        exp: u64,
+        iss: &'a str,
+    }
+
+    fn token(secret: &[u8], scope: &str, exp: u64) -> String {
+        encode(
+            &Header::new(Algorithm::HS256),
+            &TestClaims {
+                sub: "market-maker-1",
+                scope,
+                exp,
+                iss: "platform",
+            },
+            &EncodingKey::from_secret(secret),
+        )
+        .unwrap()
+    }
+
+    fn future() -> u64 {
+        jsonwebtoken::get_current_timestamp() + 3600
+    }
+
+    #[tokio::test]
+    async fn test_valid_secret_token() {
+        let auth = JwtAuth::with_secret(b"secret");
+        let claims = auth
+            .verify(&token(b"secret", "quote read", future()))
+            .await
+            .unwrap();
+        assert_eq!(claims.sub.as_deref(), Some("market-maker-1"));
+        assert!(claims.has_scope("quote"));
+        assert!(!claims.has_scope("admin"));
+    }
+
+    #[tokio::test]
+    async fn test_wrong_secret_rejected() {
+        let auth = JwtAuth::with_secret(b"secret");
+        let err = auth
+            .verify(&token(b"other", "quote", future()))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AuthError::InvalidToken(_)));
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_rejected() {
+        let auth = JwtAuth::with_secret(b"secret");
+        let err = auth
+            .verify(&token(b"secret", "quote", 1_000_000))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AuthError::InvalidToken(_)));
+    }
+
+    #[tokio::test]
+    async fn test_required_scope() {
+        let auth = JwtAuth::with_secret(b"secret").with_required_scope("quote");
+        auth.verify(&token(b"secret", "read quote", future()))
+            .await
+            .unwrap();
+        let err = auth
+            .verify(&token(b"secret", "read", future()))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AuthError::InsufficientScope(_)));
+    }
+
+    #[tokio::test]
+    async fn test_issuer_checked() {
+        let auth = JwtAuth::with_secret(b"secret").with_issuer("someone-else");
+        assert!(auth
+            .verify(&token(b"secret", "quote", future()))
+            .await
+            .is_err());
+
+        let auth = JwtAuth::with_secret(b"secret").with_issuer("platform");
+        assert!(auth
+            .verify(&token(b"secret", "quote", future()))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_garbage_token() {
+        let auth = JwtAuth::with_secret(b"secret");
+        let err = auth.verify("not.a.jwt").await.unwrap_err();
+        assert!(matches!(err, AuthError::InvalidToken(_)));
+    }
+}
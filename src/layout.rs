@@ -0,0 +1,132 @@
+use alloy::primitives::B256;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::OracleRequestError;
+
+/// What to do when an order's expression expects more signed-context slots than we sign.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum LayoutCheckMode {
+    /// Log a warning and sign anyway
+    Warn,
+    /// Refuse the request with `400 context_layout_mismatch`
+    Reject,
+}
+
+/// A known expression template: the keccak256 of its bytecode and how many signed-context
+/// slots it reads.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExpressionTemplate {
+    pub bytecode_hash: B256,
+    pub slots: usize,
+}
+
+impl FromStr for ExpressionTemplate {
+    type Err = String;
+
+    /// Parse `<bytecode hash>:<slots>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (hash, slots) = s
+            .trim()
+            .split_once(':')
+            .ok_or_else(|| "expected <bytecode hash>:<slots>".to_string())?;
+        Ok(Self {
+            bytecode_hash: hash
+                .parse()
+                .map_err(|e| format!("invalid bytecode hash '{}': {}", hash, e))?,
+            slots: slots
+                .parse()
+                .map_err(|e| format!("invalid slot count '{}': {}", slots, e))?,
+        })
+    }
+}
+
+/// Static check of an order's expression against the signed-context layout we produce, so a
+/// mismatch is caught at quote time instead of failing on-chain when the order is taken.
+/// Orders whose bytecode isn't a known template pass unchecked.
+pub struct LayoutCheck {
+    templates: HashMap<B256, usize>,
+    mode: LayoutCheckMode,
+}
+
+impl LayoutCheck {
+    pub fn new(
+        templates: impl IntoIterator<Item = ExpressionTemplate>,
+        mode: LayoutCheckMode,
+    ) -> Self {
+        Self {
+            templates: templates
+                .into_iter()
+                .map(|t| (t.bytecode_hash, t.slots))
+                .collect(),
+            mode,
+        }
+    }
+
+    /// Check an order with bytecode hash `bytecode_hash` against a context of `provided`
+    /// slots.
+    pub fn check(&self, bytecode_hash: B256, provided: usize) -> Result<(), OracleRequestError> {
+        let Some(&required) = self.templates.get(&bytecode_hash) else {
+            return Ok(());
+        };
+        if required <= provided {
+            return Ok(());
+        }
+        match self.mode {
+            LayoutCheckMode::Warn => {
+                tracing::warn!(
+                    "Order expression {} reads {} signed-context slots but we sign {}; \
+                     the take will fail on-chain",
+                    bytecode_hash,
+                    required,
+                    provided
+                );
+                Ok(())
+            }
+            LayoutCheckMode::Reject => Err(OracleRequestError::ContextLayoutMismatch {
+                bytecode_hash,
+                required,
+                provided,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_template() {
+        let template: ExpressionTemplate = format!("{}:3", B256::repeat_byte(1)).parse().unwrap();
+        assert_eq!(template.bytecode_hash, B256::repeat_byte(1));
+        assert_eq!(template.slots, 3);
+        assert!("0x01:3".parse::<ExpressionTemplate>().is_err());
+        assert!(format!("{}", B256::ZERO)
+            .parse::<ExpressionTemplate>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let template = ExpressionTemplate {
+            bytecode_hash: B256::repeat_byte(1),
+            slots: 3,
+        };
+        let reject = LayoutCheck::new([template], LayoutCheckMode::Reject);
+        assert!(reject.check(B256::repeat_byte(1), 3).is_ok());
+        assert!(matches!(
+            reject.check(B256::repeat_byte(1), 2),
+            Err(OracleRequestError::ContextLayoutMismatch {
+                required: 3,
+                provided: 2,
+                ..
+            })
+        ));
+        // Unknown templates pass
+        assert!(reject.check(B256::repeat_byte(2), 0).is_ok());
+
+        let warn = LayoutCheck::new([template], LayoutCheckMode::Warn);
+        assert!(warn.check(B256::repeat_byte(1), 2).is_ok());
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::AppState;
+
+/// Elects one replica among those sharing a storage backend to run the background pollers
+/// (orderbook watcher, webhook relay), so N replicas don't make N copies of the same
+/// upstream calls. Every replica keeps serving quotes either way.
+///
+/// The leader holds a lease in storage and renews it every third of its TTL; if it dies,
+/// another replica takes over once the lease expires.
+#[derive(Debug)]
+pub struct LeaderElection {
+    name: String,
+    holder: String,
+    ttl: Duration,
+    leading: AtomicBool,
+}
+
+impl LeaderElection {
+    /// Compete for the lease `name` (replicas of the same deployment must use the same
+    /// name) with a fresh holder ID. `ttl` must be at least 3 seconds.
+    pub fn new(name: &str, ttl: Duration) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            ttl >= Duration::from_secs(3),
+            "Leader lease must be at least 3 seconds"
+        );
+        Ok(Self {
+            name: name.to_string(),
+            holder: uuid::Uuid::new_v4().to_string(),
+            ttl,
+            leading: AtomicBool::new(false),
+        })
+    }
+
+    /// ID this replica holds the lease under.
+    pub fn holder(&self) -> &str {
+        &self.holder
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.leading.load(Ordering::Relaxed)
+    }
+
+    /// Take or renew the lease. Losing it (including failing to reach storage) steps down,
+    /// since another replica may take over once the lease runs out.
+    pub async fn renew(&self, state: &AppState) -> bool {
+        let now = state.clock.now().as_secs();
+        let leading = match state
+            .storage
+            .acquire_lease(&self.name, &self.holder, now, self.ttl.as_secs())
+            .await
+        {
+            Ok(leading) => leading,
+            Err(e) => {
+                tracing::warn!("Failed to renew leader lease '{}': {:#}", self.name, e);
+                false
+            }
+        };
+        if self.leading.swap(leading, Ordering::Relaxed) != leading {
+            if leading {
+                tracing::info!("Elected leader for '{}'; running pollers", self.name);
+            } else {
+                tracing::info!("No longer leader for '{}'; pausing pollers", self.name);
+            }
+        }
+        leading
+    }
+}
+
+/// Renew the lease and refresh the orders the leader's watcher saw removed, every third of
+/// the lease TTL. Does nothing without leader election.
+pub fn spawn(state: Arc<AppState>) -> Option<JoinHandle<()>> {
+    let interval = state.leader.as_ref()?.ttl / 3;
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            if let Some(leader) = &state.leader {
+                leader.renew(&state).await;
+            }
+            match state.storage.removed_orders().await {
+                Ok(orders) => state.removed_orders.replace(orders),
+                Err(e) => tracing::warn!("Failed to refresh removed orders: {:#}", e),
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{MemoryStorage, Storage};
+    use crate::tests::test_state;
+
+    fn replica(storage: Arc<dyn Storage>) -> AppState {
+        test_state()
+            .with_storage(storage)
+            .with_leader_election(LeaderElection::new("pollers", Duration::from_secs(15)).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_one_replica_leads() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::default());
+        let (a, b) = (replica(storage.clone()), replica(storage));
+        assert!(!a.is_leader());
+        assert!(a.leader.as_ref().unwrap().renew(&a).await);
+        assert!(!b.leader.as_ref().unwrap().renew(&b).await);
+        assert!(a.is_leader());
+        assert!(!b.is_leader());
+        assert!(LeaderElection::new("pollers", Duration::from_secs(1)).is_err());
+    }
+}
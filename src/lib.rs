@@ -1,58 +1,119 @@
+pub mod alert;
+pub mod approval;
+pub mod auth;
+pub mod backtest;
+pub mod batch;
+#[cfg(feature = "ts")]
+pub mod bindings;
+pub mod cache;
+pub mod circuit;
+pub mod clock;
+pub mod combos;
+pub mod config;
+pub mod debug;
+pub mod deployment;
+pub mod dry_run;
+pub mod encoding;
+pub mod errors;
+pub mod explain;
+pub mod export;
+pub mod gas;
+pub mod guard;
+pub mod history;
+pub mod idempotency;
+pub mod incident;
+pub mod inventory;
+pub mod jitter;
+pub mod jsonrpc;
+pub mod jwt;
+pub mod layout;
+pub mod leader;
+pub mod limits;
+pub mod listener;
+pub mod loadtest;
+pub mod logging;
+pub mod metrics;
+pub mod msgpack;
+pub mod nonce;
+pub mod openapi;
 pub mod oracle;
+pub mod order;
+pub mod order_nonce;
+pub mod pairs;
+pub mod pipeline;
 pub mod pyth;
+pub mod quota;
+pub mod reference;
+pub mod relay;
+pub mod remote;
+pub mod rounding;
+pub mod rpc;
+pub mod schedule;
+pub mod secrets;
+pub mod shared_price;
 pub mod sign;
+pub mod sources;
+pub mod status;
+pub mod storage;
+pub mod stream;
+pub mod systemd;
+pub mod taker;
+pub mod timing;
+pub mod version;
+pub mod watcher;
+pub mod wormhole;
 
-use alloy::primitives::Address;
-use alloy::sol;
-use alloy::sol_types::SolValue;
+use alloy::primitives::{Address, FixedBytes, B256};
 use axum::{
     body::Bytes,
-    extract::State,
-    http::StatusCode,
+    extract::{rejection::QueryRejection, Extension, Path, Query, RawQuery, State},
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     routing::{get, post},
     Json, Router,
 };
-use serde::Serialize;
+use clock::{Clock, ClockSkewMonitor, SystemClock};
+use serde::{Deserialize, Serialize};
 use sign::Signer;
+use std::borrow::Cow;
 use std::str::FromStr;
 use std::sync::Arc;
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::CorsLayer;
 
-// Minimal OrderV4 definition for ABI decoding — avoids pulling in rain_orderbook_bindings.
-sol! {
-    struct IOV2 {
-        address token;
-        bytes32 vaultId;
-    }
-
-    struct EvaluableV4 {
-        address interpreter;
-        address store;
-        bytes bytecode;
-    }
-
-    struct OrderV4 {
-        address owner;
-        EvaluableV4 evaluable;
-        IOV2[] validInputs;
-        IOV2[] validOutputs;
-        bytes32 nonce;
-    }
-}
-
-/// Decoded POST body: (OrderV4, uint256 inputIOIndex, uint256 outputIOIndex, address counterparty)
-type OracleRequestBody = (OrderV4, alloy::primitives::U256, alloy::primitives::U256, Address);
-
 /// Token pair config — maps token addresses to base/quote roles for a Pyth feed.
 ///
 /// The Pyth feed returns price as base/quote (e.g. ETH/USD = ~1900).
 /// - base_token: the token priced by the feed (e.g. WETH)
 /// - quote_token: the denomination (e.g. USDC)
+///
+/// Aliases are other addresses for the same asset (e.g. bridged WETH, USDC.e) that resolve
+/// to the same feed and direction.
 #[derive(Clone)]
 pub struct TokenPairConfig {
     pub base_token: Address,
     pub quote_token: Address,
+    pub base_aliases: Vec<Address>,
+    pub quote_aliases: Vec<Address>,
+    pub expiry: ExpiryMode,
+    /// Scale the signed price by the decimal difference between the input and output
+    /// tokens, so it is a ratio of raw token amounts rather than a human price.
+    pub scale_by_decimals: bool,
+    /// Round the signed price (after any decimal scaling) to this precision.
+    pub rounding: Option<rounding::PriceRounding>,
+    /// Transformations from the feed price to the signed price (before any decimal scaling).
+    pub pipeline: pipeline::Pipeline,
+    /// The feed is quoted the other way round (quote per base is `1 / feed`), e.g. a USD/JPY
+    /// feed for a JPY-quoted pair.
+    pub feed_inverted: bool,
+    /// Expected `decimals()` of the base token, used instead of an on-chain lookup and
+    /// checked against the chain at startup
+    pub base_decimals: Option<u8>,
+    /// Expected `decimals()` of the quote token
+    pub quote_decimals: Option<u8>,
+    /// Decimal places the signed price is rendered with in responses, logs and metrics,
+    /// alongside the exact Rain float
+    pub display_decimals: Option<u8>,
 }
 
 impl TokenPairConfig {
@@ -62,12 +123,109 @@ impl TokenPairConfig {
                 .map_err(|e| anyhow::anyhow!("Invalid base token address: {}", e))?,
             quote_token: Address::from_str(quote_token)
                 .map_err(|e| anyhow::anyhow!("Invalid quote token address: {}", e))?,
+            base_aliases: Vec::new(),
+            quote_aliases: Vec::new(),
+            expiry: ExpiryMode::Timestamp,
+            scale_by_decimals: false,
+            rounding: None,
+            pipeline: pipeline::Pipeline::default(),
+            feed_inverted: false,
+            base_decimals: None,
+            quote_decimals: None,
+            display_decimals: None,
         })
     }
+
+    /// Scale the signed price by `10^(input decimals - output decimals)`.
+    pub fn with_decimals_scaling(mut self, scale_by_decimals: bool) -> Self {
+        self.scale_by_decimals = scale_by_decimals;
+        self
+    }
+
+    /// Round the signed price to a number of significant digits or a tick size.
+    pub fn with_rounding(mut self, rounding: Option<rounding::PriceRounding>) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// Flip the feed price before the usual direction logic, for feeds quoted the other way
+    /// round.
+    pub fn with_feed_inverted(mut self, feed_inverted: bool) -> Self {
+        self.feed_inverted = feed_inverted;
+        self
+    }
+
+    /// Expected decimals of the base and quote tokens.
+    pub fn with_decimals(mut self, base_decimals: Option<u8>, quote_decimals: Option<u8>) -> Self {
+        self.base_decimals = base_decimals;
+        self.quote_decimals = quote_decimals;
+        self
+    }
+
+    /// Also render the signed price with `display_decimals` fixed decimal places.
+    pub fn with_display_decimals(mut self, display_decimals: Option<u8>) -> Self {
+        self.display_decimals = display_decimals;
+        self
+    }
+
+    /// Transform the feed price with `pipeline` instead of only inverting it.
+    pub fn with_pipeline(mut self, pipeline: pipeline::Pipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// Accept these addresses in place of the base token.
+    pub fn with_base_aliases(mut self, aliases: Vec<Address>) -> Self {
+        self.base_aliases = aliases;
+        self
+    }
+
+    /// Accept these addresses in place of the quote token.
+    pub fn with_quote_aliases(mut self, aliases: Vec<Address>) -> Self {
+        self.quote_aliases = aliases;
+        self
+    }
+
+    fn is_base(&self, token: Address) -> bool {
+        token == self.base_token || self.base_aliases.contains(&token)
+    }
+
+    fn is_quote(&self, token: Address) -> bool {
+        token == self.quote_token || self.quote_aliases.contains(&token)
+    }
+
+    pub fn with_expiry(mut self, expiry: ExpiryMode) -> Self {
+        self.expiry = expiry;
+        self
+    }
 }
 
-/// Whether to return the price as-is or inverted.
+/// A token's on-chain `decimals()` differs from the pair configuration.
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "Token {token} ({symbol}) has {actual} decimals on chain, but is configured with {configured}"
+)]
+pub struct DecimalsMismatch {
+    pub token: Address,
+    pub symbol: String,
+    pub configured: u8,
+    pub actual: u8,
+}
+
+/// How the quote validity in context slot [1] is expressed.
 #[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExpiryMode {
+    /// Unix timestamp: now + the server's expiry seconds. Validate against `block-timestamp()`.
+    Timestamp,
+    /// Block number: current head block (from the configured RPC) + `blocks`.
+    /// Validate against `block-number()`.
+    BlockNumber { blocks: u64 },
+}
+
+/// Whether to return the price as-is or inverted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, utoipa::ToSchema)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "snake_case")]
 pub enum PriceDirection {
     /// Input is quote, output is base → return price as-is
     /// e.g. input=USDC, output=WETH → "how many USDC per WETH" → ~1900
@@ -77,12 +235,77 @@ pub enum PriceDirection {
     Inverted,
 }
 
+/// Delay between warm-up fetch attempts.
+const WARM_UP_RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Name Hermes' rate-limit backoff is shared under in storage.
+const HERMES_UPSTREAM: &str = "hermes";
+
+/// Longest Hermes `Retry-After` shared with other replicas, so one bogus header can't stall them
+/// all for long.
+const MAX_SHARED_BACKOFF: std::time::Duration = std::time::Duration::from_secs(300);
+
 /// Application state shared across handlers.
 pub struct AppState {
-    signer: Signer,
+    signers: sign::SignerRegistry,
     pyth_price_feed_id: String,
+    /// Symbol the feed ID was resolved from, if configured by symbol
+    pyth_price_feed_symbol: Option<String>,
     expiry_seconds: u64,
+    max_expiry_seconds: u64,
+    /// Added to timestamp expiries to cover network and block-inclusion delay
+    expiry_safety_margin: std::time::Duration,
+    /// Block time to round timestamp expiries up to, in seconds
+    expiry_block_time: Option<u64>,
     token_pair: TokenPairConfig,
+    rpc_url: Option<String>,
+    chain_id: Option<u64>,
+    clock: Arc<dyn Clock>,
+    clock_skew: Option<Arc<ClockSkewMonitor>>,
+    diagnostics: bool,
+    feed_stats: status::FeedStats,
+    price_history: history::PriceHistory,
+    incidents: incident::IncidentRecorder,
+    started_at: status::StartTime,
+    auth: auth::AuthChain,
+    quotas: Option<Arc<quota::Quotas>>,
+    approvals: Option<approval::Approvals>,
+    /// Quotes the pair may sign per UTC day, counted in storage
+    daily_quote_limit: Option<u64>,
+    schedule: Option<schedule::Schedule>,
+    storage: Arc<dyn storage::Storage>,
+    nonces: Option<nonce::NonceGenerator>,
+    layout_check: Option<layout::LayoutCheck>,
+    hermes: pyth::Hermes,
+    response_cache: Option<cache::ResponseCache>,
+    idempotency: Option<idempotency::IdempotencyCache>,
+    token_decimals: std::sync::Mutex<std::collections::HashMap<Address, u8>>,
+    request_limits: order::RequestLimits,
+    metrics: metrics::Metrics,
+    fills: watcher::FillStats,
+    removed_orders: watcher::RemovedOrders,
+    /// Refuse order nonces seen with a different owner or expression within this long
+    order_nonce_window: Option<std::time::Duration>,
+    request_logging: Option<logging::RequestLogging>,
+    gas_compensation: Option<gas::GasCompensation>,
+    price_jitter: Option<jitter::PriceJitter>,
+    /// Checks on the feed price before signing, in order
+    pricing_guards: Vec<Arc<dyn guard::PricingGuard>>,
+    circuit_breaker: Option<circuit::CircuitBreaker>,
+    slow_request_threshold: Option<std::time::Duration>,
+    inventory: Option<inventory::InventoryThrottle>,
+    leader: Option<leader::LeaderElection>,
+    shared_prices: Option<shared_price::SharedPriceCache>,
+    quote_stream: Option<stream::QuoteStream>,
+    context_sources: Option<sources::ContextSources>,
+    /// False until the warm-up fetch completes, when warm-up is enabled
+    ready: std::sync::atomic::AtomicBool,
+    /// Send internal error messages to clients instead of just a correlation ID
+    verbose_errors: bool,
+    /// Serve the JSON-RPC facade at `/rpc`
+    json_rpc: bool,
+    /// Leave pricing details out of `/pairs`
+    redact_pricing: bool,
 }
 
 impl AppState {
@@ -92,25 +315,621 @@ impl AppState {
         expiry_seconds: u64,
         token_pair: TokenPairConfig,
     ) -> anyhow::Result<Self> {
-        let signer = Signer::new(private_key)?;
-        Ok(Self {
-            signer,
+        Ok(Self::from_signer(
+            Signer::new(private_key)?,
+            pyth_price_feed_id,
+            expiry_seconds,
+            token_pair,
+        ))
+    }
+
+    /// Like [`AppState::new`], with an already constructed (e.g. remote) default signer.
+    pub fn from_signer(
+        signer: Signer,
+        pyth_price_feed_id: &str,
+        expiry_seconds: u64,
+        token_pair: TokenPairConfig,
+    ) -> Self {
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+        Self {
+            signers: sign::SignerRegistry::new(signer),
             pyth_price_feed_id: pyth_price_feed_id.to_string(),
+            pyth_price_feed_symbol: None,
             expiry_seconds,
+            max_expiry_seconds: expiry_seconds,
+            expiry_safety_margin: std::time::Duration::ZERO,
+            expiry_block_time: None,
             token_pair,
-        })
+            rpc_url: None,
+            chain_id: None,
+            clock_skew: None,
+            diagnostics: false,
+            feed_stats: status::FeedStats::new([pyth_price_feed_id]),
+            price_history: history::PriceHistory::default(),
+            incidents: incident::IncidentRecorder::default(),
+            started_at: status::StartTime::now(clock.now().as_secs() as i64),
+            clock,
+            auth: auth::AuthChain::default(),
+            quotas: None,
+            approvals: None,
+            daily_quote_limit: None,
+            schedule: None,
+            storage: Arc::new(storage::MemoryStorage::default()),
+            nonces: None,
+            layout_check: None,
+            hermes: pyth::Hermes::default(),
+            response_cache: None,
+            idempotency: None,
+            token_decimals: Default::default(),
+            request_limits: order::RequestLimits::default(),
+            metrics: metrics::Metrics::default(),
+            fills: watcher::FillStats::default(),
+            removed_orders: watcher::RemovedOrders::default(),
+            order_nonce_window: None,
+            request_logging: None,
+            gas_compensation: None,
+            price_jitter: None,
+            pricing_guards: Vec::new(),
+            circuit_breaker: None,
+            slow_request_threshold: None,
+            inventory: None,
+            leader: None,
+            shared_prices: None,
+            quote_stream: None,
+            context_sources: None,
+            ready: std::sync::atomic::AtomicBool::new(true),
+            verbose_errors: false,
+            json_rpc: false,
+            redact_pricing: false,
+        }
+    }
+
+    /// Report not ready (and refuse `/context` with `503 warming_up`) until [`AppState::warm_up`]
+    /// has run.
+    pub fn with_warm_up(self) -> Self {
+        self.ready
+            .store(false, std::sync::atomic::Ordering::Relaxed);
+        self
+    }
+
+    /// Whether the server is ready to serve quotes.
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Fetch the configured feed until it returns a price, then mark the server ready. If
+    /// `timeout` passes first, `allow_degraded` marks it ready anyway (quotes then fetch
+    /// synchronously as usual); otherwise it stays not ready and keeps retrying until the feed
+    /// answers.
+    pub async fn warm_up(&self, timeout: std::time::Duration, allow_degraded: bool) {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut timed_out = false;
+        loop {
+            let Err(e) = self.check_feeds().await else {
+                break;
+            };
+            let now = tokio::time::Instant::now();
+            if !timed_out && now >= deadline {
+                if allow_degraded {
+                    tracing::warn!("Warm-up timed out, starting degraded: {:#}", e);
+                    break;
+                }
+                tracing::error!(
+                    "Warm-up timed out; staying not ready until the feed answers \
+                     (set ALLOW_DEGRADED_START to serve anyway): {:#}",
+                    e
+                );
+                timed_out = true;
+            } else {
+                tracing::debug!("Warm-up fetch failed, retrying: {:#}", e);
+            }
+            let retry_at = now + WARM_UP_RETRY_INTERVAL;
+            tokio::time::sleep_until(if timed_out {
+                retry_at
+            } else {
+                deadline.min(retry_at)
+            })
+            .await;
+        }
+        self.ready.store(true, std::sync::atomic::Ordering::Relaxed);
+        tracing::info!("Ready");
+    }
+
+    /// Also sign every context with `signer`, returning its signature alongside the primary one.
+    pub fn with_cosigner(mut self, signer: Signer) -> Self {
+        self.signers.add_cosigner(signer);
+        self
+    }
+
+    /// Rotate the default signer: the next signer co-signs during the rotation's overlap
+    /// window and signs alone after it.
+    pub fn with_signer_rotation(mut self, rotation: sign::Rotation) -> Self {
+        self.signers.set_rotation(rotation);
+        self
+    }
+
+    /// Hash contexts `scheme`'s way before signing them, for verifiers that don't recompute
+    /// `abi.encodePacked(context)`.
+    pub fn with_hash_scheme(mut self, scheme: sign::HashScheme) -> Self {
+        self.signers.set_hash_scheme(scheme);
+        self
+    }
+
+    /// Sign quotes for the `base`/`quote` pair with their own key instead of the default one.
+    pub fn with_pair_signer(mut self, base: Address, quote: Address, signer: Signer) -> Self {
+        self.signers.insert(base, quote, signer);
+        self
+    }
+
+    /// Log `/context` request bodies and response summaries at debug level.
+    pub fn with_request_logging(mut self, logging: logging::RequestLogging) -> Self {
+        self.request_logging = Some(logging);
+        self
+    }
+
+    /// Include internal error messages in `500` bodies. Off by default, since they can carry
+    /// upstream URLs and other internals; clients get a correlation ID to match against the
+    /// server logs instead.
+    pub fn with_verbose_errors(mut self, verbose: bool) -> Self {
+        self.verbose_errors = verbose;
+        self
+    }
+
+    /// Report the symbol the feed was configured by in `/pairs`.
+    pub fn with_feed_symbol(mut self, symbol: &str) -> Self {
+        self.pyth_price_feed_symbol = Some(symbol.to_string());
+        self
+    }
+
+    /// Leave the price pipeline, rounding and gas settings out of `/pairs`.
+    pub fn with_redacted_pricing(mut self, redact: bool) -> Self {
+        self.redact_pricing = redact;
+        self
+    }
+
+    /// Serve `GET /stream`, pushing signed quotes to Server-Sent Events subscribers. Call
+    /// [`stream::spawn`] to start signing them.
+    pub fn with_quote_stream(mut self, stream: stream::QuoteStream) -> Self {
+        self.quote_stream = Some(stream);
+        self
+    }
+
+    /// Serve `POST /rpc`, a JSON-RPC 2.0 facade over the quoting and price routes.
+    pub fn with_json_rpc(mut self, enabled: bool) -> Self {
+        self.json_rpc = enabled;
+        self
+    }
+
+    /// Override the default size limits on `/context` requests.
+    pub fn with_request_limits(mut self, limits: order::RequestLimits) -> Self {
+        self.request_limits = limits;
+        self
+    }
+
+    /// Serve identical quote requests from memory for `ttl` after the first is signed.
+    /// Ignored when nonces are enabled, since every quote must then carry its own nonce.
+    pub fn with_response_cache(mut self, ttl: std::time::Duration) -> Self {
+        self.response_cache = Some(cache::ResponseCache::new(ttl));
+        self
+    }
+
+    /// Replay the signed response to a `/context` request carrying an `Idempotency-Key`
+    /// already used within `ttl`, instead of signing again.
+    pub fn with_idempotency(mut self, ttl: std::time::Duration) -> Self {
+        self.idempotency = Some(idempotency::IdempotencyCache::new(ttl));
+        self
+    }
+
+    /// Fetch prices through this Hermes client (e.g. one carrying an API key).
+    pub fn with_hermes(mut self, hermes: pyth::Hermes) -> Self {
+        self.hermes = hermes;
+        self
+    }
+
+    /// Append a replay-protection nonce to every context as slot [2].
+    pub fn with_nonces(mut self, nonces: nonce::NonceGenerator) -> Self {
+        self.nonces = Some(nonces);
+        self
+    }
+
+    /// Check orders built from known expression templates against the context layout.
+    pub fn with_layout_check(mut self, check: layout::LayoutCheck) -> Self {
+        self.layout_check = Some(check);
+        self
+    }
+
+    /// Number of signed-context slots in each quote: price, expiry and the nonce if enabled.
+    fn context_len(&self) -> usize {
+        self.context_slots().len()
+    }
+
+    /// Names of the signed context slots, in order.
+    fn context_slots(&self) -> Vec<String> {
+        let mut slots = vec!["price".to_string(), "expiry".to_string()];
+        if self.nonces.is_some() {
+            slots.push("nonce".to_string());
+        }
+        if let Some(sources) = &self.context_sources {
+            slots.extend(sources.names().map(String::from));
+        }
+        slots
+    }
+
+    /// Keep audit records and usage counters in shared storage instead of process memory.
+    pub fn with_storage(mut self, storage: Arc<dyn storage::Storage>) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    /// Authenticate `/context` requests with an `X-Api-Key` and enforce per-key daily quotas.
+    pub fn with_quotas(mut self, quotas: quota::Quotas) -> Self {
+        let quotas = Arc::new(quotas);
+        self.quotas = Some(quotas.clone());
+        self.with_authenticator(quotas)
+    }
+
+    /// Authenticate `/context` requests with a JWT bearer token.
+    pub fn with_jwt_auth(self, auth: jwt::JwtAuth) -> Self {
+        self.with_authenticator(Arc::new(auth))
+    }
+
+    /// Authenticate `/context` requests with an HMAC signature.
+    pub fn with_hmac_auth(self, auth: auth::HmacAuth) -> Self {
+        self.with_authenticator(Arc::new(auth))
+    }
+
+    /// Append an authenticator to the `/context` auth chain. Authenticators run in the
+    /// order they are added.
+    pub fn with_authenticator(mut self, authenticator: Arc<dyn auth::Authenticator>) -> Self {
+        self.auth = self.auth.with(authenticator);
+        self
+    }
+
+    /// Require every authenticator to pass (the default), or any one of them.
+    pub fn with_auth_mode(mut self, mode: auth::AuthMode) -> Self {
+        self.auth = self.auth.with_mode(mode);
+        self
+    }
+
+    /// Include a human-readable `debug` block in responses.
+    pub fn with_diagnostics(mut self, diagnostics: bool) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// Replace the wall clock used for expiry (defaults to the system clock).
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Refuse to sign while the monitor reports excessive clock skew (if it is configured to).
+    pub fn with_clock_skew_monitor(mut self, monitor: Arc<ClockSkewMonitor>) -> Self {
+        self.clock_skew = Some(monitor);
+        self
+    }
+
+    /// Require admin actions that change signing to be staged by one admin key and approved
+    /// by another.
+    pub fn with_approvals(mut self, approvals: approval::Approvals) -> Self {
+        self.approvals = Some(approvals);
+        self
+    }
+
+    /// Pause signing for the pair once `limit` quotes have been signed in a UTC day, until
+    /// the next day or an admin reset.
+    pub fn with_daily_quote_limit(mut self, limit: u64) -> Self {
+        self.daily_quote_limit = Some(limit);
+        self
+    }
+
+    /// Only quote the pair during `schedule`'s windows.
+    pub fn with_schedule(mut self, schedule: schedule::Schedule) -> Self {
+        self.schedule = Some(schedule);
+        self
+    }
+
+    /// Cap for client-requested expiries (defaults to the configured expiry).
+    pub fn with_max_expiry_seconds(mut self, max_expiry_seconds: u64) -> Self {
+        self.max_expiry_seconds = max_expiry_seconds;
+        self
+    }
+
+    /// Extend timestamp expiries by `margin`, so contexts measured from server time don't
+    /// expire while the transaction is still in flight.
+    pub fn with_expiry_safety_margin(mut self, margin: std::time::Duration) -> Self {
+        self.expiry_safety_margin = margin;
+        self
+    }
+
+    /// Round timestamp expiries up to the next multiple of `seconds`, the chain's block time.
+    pub fn with_expiry_block_time(mut self, seconds: u64) -> Self {
+        self.expiry_block_time = Some(seconds);
+        self
+    }
+
+    /// Resolve the expiry for a request: the client's requested value clamped to
+    /// `[1, max_expiry_seconds]`, or the configured default.
+    fn effective_expiry_seconds(&self, requested: Option<u64>) -> u64 {
+        requested
+            .map(|r| r.clamp(1, self.max_expiry_seconds))
+            .unwrap_or(self.expiry_seconds)
+    }
+
+    /// Set the JSON-RPC endpoint used to look up the head block for block-number expiry.
+    pub fn with_rpc_url(mut self, rpc_url: &str) -> Self {
+        self.rpc_url = Some(rpc_url.to_string());
+        self
+    }
+
+    /// Record the chain this state serves, checked against the RPC by [`AppState::check_chain_id`].
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// Check the configured chain ID matches the RPC endpoint's, when both are set.
+    pub async fn check_chain_id(&self) -> anyhow::Result<()> {
+        if let (Some(expected), Some(rpc_url)) = (self.chain_id, &self.rpc_url) {
+            let actual = rpc::chain_id(rpc_url).await?;
+            anyhow::ensure!(
+                actual == expected,
+                "RPC endpoint is on chain {}, expected chain {}",
+                actual,
+                expected
+            );
+        }
+        Ok(())
+    }
+
+    /// Widen the signed price by the estimated gas cost of a fill.
+    pub fn with_gas_compensation(mut self, gas_compensation: gas::GasCompensation) -> Self {
+        self.gas_compensation = Some(gas_compensation);
+        self
+    }
+
+    /// Sign each of `sources`' values into the context after the price, expiry and nonce, for
+    /// derivative pairs whose strategies read more than the spot price.
+    pub fn with_context_sources(mut self, sources: sources::ContextSources) -> Self {
+        self.context_sources = Some(sources).filter(|s| !s.is_empty());
+        self
+    }
+
+    /// Raise every signed price by a random amount within `jitter`'s band.
+    pub fn with_price_jitter(mut self, jitter: jitter::PriceJitter) -> Self {
+        self.price_jitter = Some(jitter);
+        self
+    }
+
+    /// Pause the pair while the feed price deviates from `reference_price` by more than its
+    /// limit.
+    pub fn with_reference_price(self, reference_price: reference::ReferencePrice) -> Self {
+        self.with_pricing_guard(reference_price)
+    }
+
+    /// Check the feed price with `guard` before signing, after the guards added before it.
+    pub fn with_pricing_guard(mut self, guard: impl guard::PricingGuard + 'static) -> Self {
+        self.pricing_guards.push(Arc::new(guard));
+        self
+    }
+
+    /// Refuse orders whose nonce was seen with a different owner or expression within
+    /// `window`, tracking nonces in storage.
+    pub fn with_order_nonce_window(mut self, window: std::time::Duration) -> Self {
+        self.order_nonce_window = Some(window);
+        self
+    }
+
+    /// Log a per-stage breakdown of `/context` requests taking at least `threshold`.
+    pub fn with_slow_request_threshold(mut self, threshold: std::time::Duration) -> Self {
+        self.slow_request_threshold = Some(threshold);
+        self
+    }
+
+    /// Keep the last `capacity` fetched prices for `/price/{pair}/history` (0 keeps none).
+    pub fn with_price_history_size(mut self, capacity: usize) -> Self {
+        self.price_history = history::PriceHistory::new(capacity);
+        self
+    }
+
+    /// Capture an incident at most once per tripped guard per `cooldown`.
+    pub fn with_incident_cooldown(mut self, cooldown: std::time::Duration) -> Self {
+        self.incidents = incident::IncidentRecorder::new(cooldown);
+        self
+    }
+
+    /// Fail fast with `503 circuit_open` while `breaker` has tripped on repeated Hermes
+    /// failures.
+    pub fn with_circuit_breaker(mut self, breaker: circuit::CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(breaker);
+        self
+    }
+
+    /// Only run the background pollers while `election` holds the leader lease in storage.
+    pub fn with_leader_election(mut self, election: leader::LeaderElection) -> Self {
+        self.leader = Some(election);
+        self
+    }
+
+    /// Quote from the feed price the leader publishes to storage while it is fresh, rather
+    /// than each replica fetching from Hermes per request.
+    pub fn with_shared_price_cache(mut self, cache: shared_price::SharedPriceCache) -> Self {
+        self.shared_prices = Some(cache);
+        self
     }
 
+    /// Whether this replica should run the background pollers: always, unless leader
+    /// election is enabled and another replica holds the lease.
+    pub fn is_leader(&self) -> bool {
+        self.leader.as_ref().is_none_or(|leader| leader.is_leader())
+    }
+
+    /// Stop signing for an order once its output vault can't cover its unexpired quotes.
+    pub fn with_inventory_throttle(mut self, throttle: inventory::InventoryThrottle) -> Self {
+        self.inventory = Some(throttle);
+        self
+    }
+
+    /// Address of the default signer.
     pub fn signer_address(&self) -> Address {
-        self.signer.address()
+        self.signers.default_signer().address()
+    }
+
+    /// Addresses of every configured signer, default first.
+    pub fn signer_addresses(&self) -> Vec<Address> {
+        self.signers.signers().iter().map(|s| s.address()).collect()
+    }
+
+    /// Startup check of the configured tokens over the RPC endpoint: logs each token's
+    /// `symbol()` and `decimals()`, and fails with [`DecimalsMismatch`] if they differ from
+    /// the configured decimals. Does nothing without an RPC URL.
+    pub async fn check_tokens(&self) -> anyhow::Result<()> {
+        let Some(rpc_url) = self.rpc_url.as_deref() else {
+            return Ok(());
+        };
+        let pair = &self.token_pair;
+        let tokens = [
+            ("Base", pair.base_token, pair.base_decimals),
+            ("Quote", pair.quote_token, pair.quote_decimals),
+        ]
+        .into_iter()
+        .chain(pair.base_aliases.iter().map(|t| ("Base alias", *t, None)))
+        .chain(pair.quote_aliases.iter().map(|t| ("Quote alias", *t, None)));
+        for (role, token, configured) in tokens {
+            let (symbol, decimals) = tokio::try_join!(
+                rpc::token_symbol(rpc_url, token),
+                rpc::token_decimals(rpc_url, token),
+            )?;
+            tracing::info!(
+                "{} token {}: {} ({} decimals)",
+                role,
+                token,
+                symbol,
+                decimals
+            );
+            if let Some(configured) = configured.filter(|c| *c != decimals) {
+                return Err(DecimalsMismatch {
+                    token,
+                    symbol,
+                    configured,
+                    actual: decimals,
+                }
+                .into());
+            }
+            self.token_decimals.lock().unwrap().insert(token, decimals);
+        }
+        Ok(())
+    }
+
+    /// Startup check that the configured feed returns a price from Hermes.
+    pub async fn check_feeds(&self) -> anyhow::Result<()> {
+        let data = fetch_price(self).await.map_err(|e| {
+            e.context(format!(
+                "Pyth feed {} returned no price",
+                self.pyth_price_feed_id
+            ))
+        })?;
+        tracing::info!(
+            "Feed {} OK: {}",
+            self.pyth_price_feed_id,
+            oracle::format_pyth_price(data.price, data.expo)
+        );
+        Ok(())
+    }
+
+    /// Startup check that every signer produces signatures recovering to its own address.
+    /// A failover chain passes if any of its signers does.
+    pub async fn check_signer(&self) -> anyhow::Result<()> {
+        let context = [FixedBytes::<32>::repeat_byte(0x01)];
+        let scheme = self.signers.hash_scheme();
+        for signer in self.signers.configured() {
+            let (signature, address) = signer.sign_context_with(&context, scheme).await?;
+            let recovered = signer.recover(&context, &signature, scheme)?;
+            anyhow::ensure!(
+                recovered == address,
+                "Signature recovers to {} instead of signer {}",
+                recovered,
+                address
+            );
+        }
+        Ok(())
+    }
+
+    /// Re-probe every signer failover chain, so a recovered primary is preferred again.
+    pub async fn check_signer_health(&self) {
+        self.signers.check_health().await;
+    }
+
+    /// Decimals of `token`: as declared in the order if present, otherwise looked up on
+    /// chain once and remembered. Refuses the request if they can't be known without an RPC
+    /// URL.
+    async fn token_decimals(&self, token: Address, declared: Option<u8>) -> Result<u8, AppError> {
+        if let Some(decimals) = declared {
+            return Ok(decimals);
+        }
+        let configured = if token == self.token_pair.base_token {
+            self.token_pair.base_decimals
+        } else if token == self.token_pair.quote_token {
+            self.token_pair.quote_decimals
+        } else {
+            None
+        };
+        if let Some(decimals) = configured {
+            return Ok(decimals);
+        }
+        if let Some(decimals) = self.token_decimals.lock().unwrap().get(&token) {
+            return Ok(*decimals);
+        }
+        let rpc_url = self
+            .rpc_url
+            .as_deref()
+            .ok_or(OracleRequestError::UnknownTokenDecimals(token))?;
+        let decimals = rpc::token_decimals(rpc_url, token).await?;
+        self.token_decimals.lock().unwrap().insert(token, decimals);
+        Ok(decimals)
+    }
+
+    /// Power of ten to scale the price by for an order buying `input` with `output`, or 0
+    /// when the pair doesn't scale by decimals.
+    async fn decimal_shift(&self, input: order::Io, output: order::Io) -> Result<i32, AppError> {
+        if !self.token_pair.scale_by_decimals {
+            return Ok(0);
+        }
+        let (input_decimals, output_decimals) = tokio::try_join!(
+            self.token_decimals(input.token, input.decimals),
+            self.token_decimals(output.token, output.decimals),
+        )?;
+        Ok(i32::from(input_decimals) - i32::from(output_decimals))
+    }
+
+    /// [`AppState::decimal_shift`] for the configured base and quote tokens quoted in
+    /// `direction`, for quotes not priced from an order (e.g. the quote stream).
+    pub(crate) async fn pair_decimal_shift(
+        &self,
+        direction: PriceDirection,
+    ) -> Result<i32, AppError> {
+        let io = |token| order::Io {
+            token,
+            decimals: None,
+            vault_id: B256::ZERO,
+        };
+        let (base, quote) = (
+            io(self.token_pair.base_token),
+            io(self.token_pair.quote_token),
+        );
+        match direction {
+            // input=quote, output=base
+            PriceDirection::AsIs => self.decimal_shift(quote, base).await,
+            PriceDirection::Inverted => self.decimal_shift(base, quote).await,
+        }
     }
 
     /// Determine price direction from the order's input/output tokens.
     fn price_direction(&self, input_token: Address, output_token: Address) -> Result<PriceDirection, OracleRequestError> {
-        let is_input_base = input_token == self.token_pair.base_token;
-        let is_input_quote = input_token == self.token_pair.quote_token;
-        let is_output_base = output_token == self.token_pair.base_token;
-        let is_output_quote = output_token == self.token_pair.quote_token;
+        let is_input_base = self.token_pair.is_base(input_token);
+        let is_input_quote = self.token_pair.is_quote(input_token);
+        let is_output_base = self.token_pair.is_base(output_token);
+        let is_output_quote = self.token_pair.is_quote(output_token);
 
         match (is_input_base, is_input_quote, is_output_base, is_output_quote) {
             // input=quote (USDC), output=base (WETH) → price as-is (USDC per WETH)
@@ -127,104 +946,1171 @@ impl AppState {
     }
 }
 
-pub fn create_app(state: AppState) -> Router {
-    let shared_state = Arc::new(state);
-    Router::new()
+/// Build a single router serving both the public and admin routes, for deployments
+/// that don't need a separate admin listener. `POST /limits/reset` is left out unless the
+/// two-person rule is enabled, since nothing else would authenticate it on a public address.
+pub fn create_app(state: impl Into<Arc<AppState>>) -> Router {
+    let (public, admin) = routers(state.into(), true);
+    public.merge(admin)
+}
+
+/// Build separate public and admin routers sharing one state, so the public router can
+/// be exposed through a load balancer while the admin router stays on a private address.
+pub fn create_apps(state: impl Into<Arc<AppState>>) -> (Router, Router) {
+    routers(state.into(), false)
+}
+
+/// Public and admin routers; `shared` when both are served on the same listener.
+pub(crate) fn routers(state: Arc<AppState>, shared: bool) -> (Router, Router) {
+    let reset = !shared || state.approvals.is_some();
+    (public_router(state.clone()), admin_router(state, reset))
+}
+
+/// Wrap a `/context`-style route in authentication, API key quotas and request logging.
+fn context_route(
+    state: &Arc<AppState>,
+    route: axum::routing::MethodRouter<Arc<AppState>>,
+) -> axum::routing::MethodRouter<Arc<AppState>> {
+    route
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            quota::enforce_quota,
+        ))
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            auth::authenticate,
+        ))
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            logging::log_requests,
+        ))
+}
+
+/// Routes for takers and integrators.
+fn public_router(state: Arc<AppState>) -> Router {
+    let router = Router::new()
+        .route("/context", context_route(&state, post(post_signed_context)))
+        .route(
+            "/context/dry-run",
+            context_route(&state, post(dry_run::dry_run)),
+        )
+        .route(
+            "/context/all",
+            context_route(&state, post(combos::context_all)),
+        )
+        .route(
+            "/context/batch",
+            context_route(&state, post(batch::context_batch)),
+        )
         .route("/", get(health))
-        .route("/context", post(post_signed_context))
+        .route("/readyz", get(readyz))
+        .route("/version", get(version::version))
+        .route("/signer", get(status::signer))
+        .route("/pairs", get(pairs::pairs))
+        .route("/errors", get(errors::errors))
+        .route("/usage", get(quota::usage))
+        .route("/debug/float", get(debug::float))
+        .route("/price/{pair}", get(history::price))
+        .route("/price/{pair}/at", get(history::price_at))
+        .route("/price/{pair}/history", get(history::price_history))
+        .route("/openapi.json", get(openapi::openapi_json));
+    let router = if state.json_rpc {
+        router.route("/rpc", context_route(&state, post(jsonrpc::rpc)))
+    } else {
+        router
+    };
+    let router = if state.quote_stream.is_some() {
+        router.route("/stream", context_route(&state, get(stream::subscribe)))
+    } else {
+        router
+    };
+    router
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            show_internal_errors,
+        ))
+        .layer(CompressionLayer::new())
         .layer(CorsLayer::permissive())
-        .with_state(shared_state)
+        .with_state(state)
+}
+
+/// Operational routes (status, metrics) that should not be exposed publicly.
+/// Admin routes; `reset` also routes `POST /limits/reset`.
+fn admin_router(state: Arc<AppState>, reset: bool) -> Router {
+    let router = Router::new()
+        .route("/status", get(status::status))
+        .route("/health/feeds", get(status::feed_health))
+        .route("/quote/{id}", get(get_quote))
+        .route("/metrics", get(metrics::metrics))
+        .route("/limits", get(limits::limits));
+    let router = if reset {
+        router.route("/limits/reset", post(limits::reset))
+    } else {
+        router
+    };
+    router
+        .route("/incidents", get(incident::incidents))
+        .route("/admin/changes", get(approval::pending))
+        .route("/admin/changes/{id}/approve", post(approval::approve))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            show_internal_errors,
+        ))
+        .layer(CompressionLayer::new())
+        .with_state(state)
 }
 
+#[utoipa::path(get, path = "/", responses((status = 200, description = "Server is up", body = String)))]
 async fn health() -> &'static str {
     "ok"
 }
 
+/// GET handler — readiness for load balancers: `503 warming_up` until the first price has
+/// been fetched (when warm-up is enabled).
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    responses(
+        (status = 200, description = "Ready to serve quotes", body = String),
+        (status = 503, description = "Still warming up", body = ErrorResponse),
+    ),
+)]
+async fn readyz(State(state): State<Arc<AppState>>) -> Result<&'static str, AppError> {
+    check_ready(&state)?;
+    Ok("ready")
+}
+
 /// Error response body for client-facing errors.
-#[derive(Serialize)]
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
 struct ErrorResponse {
+    /// Machine-readable error code, e.g. `invalid_body`
     error: String,
+    /// Human-readable description
+    detail: String,
+    /// Correlation ID of an internal error, logged with the full error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ts", ts(optional))]
+    error_id: Option<String>,
+    /// Pricing guard that refused the price, e.g. `staleness`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ts", ts(optional))]
+    guard: Option<String>,
+}
+
+/// The full message of an internal error, attached to its response so
+/// [`show_internal_errors`] can put it back in the body when verbose errors are enabled.
+#[derive(Clone)]
+struct InternalErrorDetail {
+    error_id: String,
     detail: String,
 }
 
-/// POST handler — receives ABI-encoded (OrderV4, uint256 inputIOIndex, uint256 outputIOIndex, address counterparty).
+/// Log an internal error under a fresh correlation ID, and return the ID.
+fn log_internal_error(err: &anyhow::Error) -> String {
+    let error_id = uuid::Uuid::new_v4().to_string();
+    tracing::error!(error_id = %error_id, "Internal error: {:?}", err);
+    error_id
+}
+
+/// The `detail` clients get for an internal error instead of the error itself.
+fn redacted_detail(error_id: &str) -> String {
+    format!("Internal error (error ID {})", error_id)
+}
+
+/// Middleware — restores the full message in internal error bodies when verbose errors are
+/// enabled.
+async fn show_internal_errors(
+    State(state): State<Arc<AppState>>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let response = next.run(request).await;
+    if !state.verbose_errors {
+        return response;
+    }
+    let Some(internal) = response.extensions().get::<InternalErrorDetail>().cloned() else {
+        return response;
+    };
+    let (parts, _) = response.into_parts();
+    let body = Json(ErrorResponse {
+        error: "internal_error".to_string(),
+        detail: internal.detail,
+        error_id: Some(internal.error_id),
+        guard: None,
+    });
+    let mut verbose = body.into_response();
+    *verbose.status_mut() = parts.status;
+    verbose
+}
+
+/// Normalise the request body into raw ABI bytes.
+///
+/// Accepts either raw ABI-encoded bytes or a `0x`-prefixed hex string (e.g. curl or
+/// SDKs posting `text/plain`). The format is detected from the body itself rather than
+/// the content type, since clients are inconsistent about setting it. Raw ABI bytes
+/// can never start with `0x`: the first word is a tuple offset whose leading byte is zero.
+fn decode_body(body: &[u8]) -> Result<Cow<'_, [u8]>, OracleRequestError> {
+    let trimmed = body.trim_ascii();
+    match trimmed
+        .strip_prefix(b"0x")
+        .or_else(|| trimmed.strip_prefix(b"0X"))
+    {
+        Some(hex_str) => hex::decode(hex_str)
+            .map(Cow::Owned)
+            .map_err(|e| OracleRequestError::InvalidBody(format!("invalid hex string: {}", e))),
+        None => Ok(Cow::Borrowed(body)),
+    }
+}
+
+/// Optional query parameters on `/context`.
+#[derive(Deserialize, utoipa::IntoParams)]
+struct ContextQuery {
+    /// Requested expiry in seconds, clamped to the server's maximum (timestamp expiry only).
+    expiry_seconds: Option<u64>,
+}
+
+/// Read the optional order version hint header.
+fn order_version_hint(
+    headers: &HeaderMap,
+) -> Result<Option<order::OrderVersion>, OracleRequestError> {
+    headers
+        .get(order::ORDER_VERSION_HEADER)
+        .map(|value| {
+            value
+                .to_str()
+                .map_err(|_| OracleRequestError::InvalidOrderVersion("<non-ascii>".to_string()))?
+                .parse()
+        })
+        .transpose()
+}
+
+/// POST handler — receives ABI-encoded (OrderV4, uint256 inputIOIndex, uint256 outputIOIndex, address counterparty),
+/// either as raw bytes or as a `0x`-prefixed hex string. OrderV3 bodies are also accepted, either
+/// auto-detected or pinned with the `X-Order-Version` header.
 /// Decodes the order to determine input/output tokens and returns the correctly-directed price.
+#[utoipa::path(
+    post,
+    path = "/context",
+    params(
+        ContextQuery,
+        ("X-Order-Version" = Option<String>, Header, description = "Pin the order ABI version (3 or 4) instead of auto-detecting"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Return the same signed response to retries of this request (when idempotency is enabled)"),
+        ("X-Taker-Id" = Option<String>, Header, description = "Taker label for logs, metrics and the audit trail; must match the counterparty or an authenticated identity when auth is enabled"),
+        ("Authorization" = Option<String>, Header, description = "Bearer JWT (JWT auth)"),
+        ("X-Api-Key" = Option<String>, Header, description = "API key (when API keys are configured)"),
+        ("X-Timestamp" = Option<String>, Header, description = "Unix seconds the request was signed at (HMAC auth)"),
+        ("X-Signature" = Option<String>, Header, description = "hex(HMAC-SHA256(secret, \"<timestamp>\\n<METHOD>\\n<path>[?<query>]\\n<body>\")) (HMAC auth)"),
+    ),
+    request_body(
+        description = "ABI-encoded (order, inputIOIndex, outputIOIndex, counterparty), as raw bytes or a 0x-prefixed hex string",
+        content(
+            (Vec<u8> = "application/octet-stream"),
+            (String = "text/plain"),
+        ),
+    ),
+    responses(
+        (status = 200, description = "Signed context, as CBOR when preferred by `Accept`",
+            content(
+                (oracle::OracleResponse = "application/json"),
+                (oracle::OracleResponse = "application/cbor"),
+            ),
+            headers(
+                ("X-Quote-Id" = String, description = "ID of the issued quote"),
+                ("Idempotent-Replayed" = Option<String>, description = "`true` when the response was replayed for a repeated Idempotency-Key"),
+            )),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid request signature or bearer token (when auth is enabled)", body = ErrorResponse),
+        (status = 403, description = "Bearer token lacks the required scope", body = ErrorResponse),
+        (status = 429, description = "Daily quota for the API key is exhausted", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse),
+        (status = 503, description = "Temporarily unable to sign", body = ErrorResponse,
+            headers(("Retry-After" = u64, description = "Seconds to wait, when the price feed provider is rate limiting"))),
+    ),
+)]
 async fn post_signed_context(
     State(state): State<Arc<AppState>>,
+    query: Result<Query<ContextQuery>, QueryRejection>,
+    identity: Option<Extension<auth::Authenticated>>,
+    api_key: Option<Extension<quota::ApiKeyName>>,
+    RawQuery(raw_query): RawQuery,
+    headers: HeaderMap,
     body: Bytes,
 ) -> Result<impl IntoResponse, AppError> {
-    // Decode the ABI-encoded request body
-    let (order, input_io_index, output_io_index, _counterparty) =
-        <OracleRequestBody>::abi_decode(&body)
-            .map_err(|e| OracleRequestError::InvalidBody(e.to_string()))?;
-
-    let input_idx = input_io_index.try_into().unwrap_or(usize::MAX);
-    let output_idx = output_io_index.try_into().unwrap_or(usize::MAX);
-
-    // Extract input/output token addresses from the order
-    let input_token = order
-        .validInputs
-        .get(input_idx)
-        .ok_or_else(|| OracleRequestError::InvalidIndex {
-            kind: "input",
-            index: input_idx,
-            len: order.validInputs.len(),
-        })?
-        .token;
-
-    let output_token = order
-        .validOutputs
-        .get(output_idx)
-        .ok_or_else(|| OracleRequestError::InvalidIndex {
-            kind: "output",
-            index: output_idx,
-            len: order.validOutputs.len(),
-        })?
-        .token;
-
-    // Determine price direction
-    let direction = state.price_direction(input_token, output_token)?;
-
-    tracing::debug!(
-        "Oracle request: input={} output={} direction={:?}",
-        input_token,
-        output_token,
-        direction
+    let format = encoding::ResponseFormat::from_headers(&headers);
+    let (response, replayed) = signed_context_recorded(
+        &state,
+        query,
+        raw_query.as_deref(),
+        identity,
+        api_key,
+        &headers,
+        &body,
+    )
+    .await?;
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(
+        QUOTE_ID_HEADER,
+        response.quote_id.to_string().parse().unwrap(),
     );
-
-    build_signed_context_response(&state, direction).await
+    response_headers.insert(
+        axum::http::header::VARY,
+        axum::http::HeaderValue::from_static("accept"),
+    );
+    if replayed {
+        response_headers.insert(
+            idempotency::IDEMPOTENT_REPLAYED_HEADER,
+            axum::http::HeaderValue::from_static("true"),
+        );
+    }
+    let started = std::time::Instant::now();
+    let response = format.respond(response);
+    state
+        .metrics
+        .record_stages(&[(timing::Stage::Serialization, started.elapsed())]);
+    Ok((response_headers, response))
 }
 
-async fn build_signed_context_response(
+/// [`signed_context`], recording the request's metrics.
+async fn signed_context_recorded(
     state: &AppState,
-    direction: PriceDirection,
-) -> Result<impl IntoResponse, AppError> {
-    let price_data = pyth::fetch_price(&state.pyth_price_feed_id).await?;
-
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    let expiry = now + state.expiry_seconds;
-
-    let context = oracle::build_context(price_data.price, price_data.expo, expiry, direction)?;
+    query: Result<Query<ContextQuery>, QueryRejection>,
+    raw_query: Option<&str>,
+    identity: Option<Extension<auth::Authenticated>>,
+    api_key: Option<Extension<quota::ApiKeyName>>,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<(oracle::OracleResponse, bool), AppError> {
+    let started = std::time::Instant::now();
+    let mut labels = metrics::RequestLabels::default();
+    let (result, timings) = timing::scope(signed_context(
+        state,
+        query,
+        raw_query,
+        identity,
+        api_key,
+        headers,
+        body,
+        &mut labels,
+    ))
+    .await;
+    let elapsed = started.elapsed();
+    if state
+        .slow_request_threshold
+        .is_some_and(|threshold| elapsed >= threshold)
+    {
+        tracing::warn!(
+            "Slow /context request for {} took {:.1}ms ({}): {}",
+            if labels.pair.is_empty() {
+                "unresolved pair"
+            } else {
+                &labels.pair
+            },
+            elapsed.as_secs_f64() * 1000.0,
+            result.as_ref().err().map_or("ok", AppError::error_code),
+            timing::format(&timings)
+        );
+    }
+    state.metrics.record_stages(&timings);
+    state.metrics.record(
+        labels,
+        result.as_ref().err().map(AppError::error_code),
+        elapsed,
+    );
+    result
+}
 
-    let (signature, signer) = state.signer.sign_context(&context).await?;
+/// Decode, validate and quote a `/context` request, filling in metric `labels` as the
+/// request is resolved. Also returns whether the response was replayed for a repeated
+/// `Idempotency-Key`.
+#[allow(clippy::too_many_arguments)]
+async fn signed_context(
+    state: &AppState,
+    query: Result<Query<ContextQuery>, QueryRejection>,
+    raw_query: Option<&str>,
+    identity: Option<Extension<auth::Authenticated>>,
+    api_key: Option<Extension<quota::ApiKeyName>>,
+    headers: &HeaderMap,
+    body: &[u8],
+    labels: &mut metrics::RequestLabels,
+) -> Result<(oracle::OracleResponse, bool), AppError> {
+    let request = timing::time(
+        timing::Stage::Decode,
+        resolve_request(state, query, identity, headers, body, labels),
+    )
+    .await?;
+    let api_key = api_key.map(|Extension(quota::ApiKeyName(name))| name);
 
-    let response = oracle::OracleResponse {
-        signer,
-        context,
-        signature,
+    let idempotent = match &state.idempotency {
+        Some(cache) => idempotency::IdempotencyKey::from_headers(headers, api_key.clone())?
+            .map(|key| (cache, key, idempotency::fingerprint(raw_query, body))),
+        None => None,
+    };
+    let pending = match &idempotent {
+        Some((cache, key, fingerprint)) => {
+            match cache
+                .begin(key, *fingerprint, std::time::Instant::now())
+                .await?
+            {
+                idempotency::Attempt::Replay(response) => return Ok((*response, true)),
+                idempotency::Attempt::Sign(pending) => Some(pending),
+            }
+        }
+        None => None,
     };
 
-    Ok(Json(response))
+    let _reservation = inventory::reserve(state, &request.vault).await?;
+    let response = issue_quote(
+        state,
+        request.direction,
+        request.decimal_shift,
+        request.expiry_seconds,
+        request.counterparty,
+        api_key,
+        request.taker_id,
+        Some((request.order_hash, &request.vault)),
+    )
+    .await?;
+    order_nonce::record(state, &request.order_nonce).await;
+    if let (Some(pending), Some((_, _, fingerprint))) = (pending, idempotent) {
+        pending.complete(
+            fingerprint,
+            response.clone(),
+            remaining_validity(state, &response),
+            std::time::Instant::now(),
+        );
+    }
+    Ok((response, false))
 }
 
-/// Client-facing request errors (returned as 400).
-#[derive(Debug, thiserror::Error)]
-pub enum OracleRequestError {
-    #[error("Invalid ABI-encoded body: {0}")]
-    InvalidBody(String),
+/// How much longer a quote with timestamp expiry stays valid, from its signed `context[1]`.
+/// `None` for block-number expiry, where that depends on block times.
+fn remaining_validity(
+    state: &AppState,
+    response: &oracle::OracleResponse,
+) -> Option<std::time::Duration> {
+    if state.token_pair.expiry != ExpiryMode::Timestamp {
+        return None;
+    }
+    let expiry = rain_math_float::Float::from(B256::from(*response.context.get(1)?))
+        .format()
+        .ok()?
+        .parse::<f64>()
+        .ok()? as u64;
+    Some(std::time::Duration::from_secs(
+        expiry.saturating_sub(state.clock.now().as_secs()),
+    ))
+}
+
+/// What a decoded and validated `/context` request asks to be quoted.
+struct QuoteRequest {
+    direction: PriceDirection,
+    decimal_shift: i32,
+    expiry_seconds: u64,
+    counterparty: Address,
+    taker_id: Option<String>,
+    order_hash: B256,
+    order_nonce: order_nonce::OrderNonce,
+    vault: inventory::Vault,
+}
+
+/// A decoded `/context` body that passed the order-level checks, with the taker it was sent
+/// by.
+struct CheckedRequest {
+    request: order::OracleRequest,
+    taker_id: Option<String>,
+}
+
+/// Decode a `/context` body and run the order-level checks: body and IO limits, the taker
+/// header, removed orders and the context layout.
+fn check_request(
+    state: &AppState,
+    identity: Option<&auth::Authenticated>,
+    headers: &HeaderMap,
+    body: &[u8],
+    labels: &mut metrics::RequestLabels,
+) -> Result<CheckedRequest, AppError> {
+    let body = decode_body(body)?;
+    state.request_limits.check_body(&body)?;
+
+    // Decode the ABI-encoded request body
+    let request = order::decode_request(&body, order_version_hint(headers)?)?;
+    labels.counterparty = request.counterparty.to_string();
+    let taker_id = taker::from_headers(headers)?;
+    if let Some(taker_id) = &taker_id {
+        if !state.auth.is_empty() {
+            taker::verify(taker_id, request.counterparty, identity)?;
+        }
+        labels.taker = taker_id.clone();
+    }
+    let order = &request.order;
+    state.request_limits.check_order(order)?;
+    if state.removed_orders.contains(&order.hash) {
+        return Err(OracleRequestError::OrderRemoved(order.hash).into());
+    }
+    if let Some(check) = &state.layout_check {
+        check.check(order.bytecode_hash, state.context_len())?;
+    }
+    Ok(CheckedRequest { request, taker_id })
+}
+
+/// Decode and validate a `/context` request: body limits, IO indexes, token pair and decimals.
+async fn resolve_request(
+    state: &AppState,
+    query: Result<Query<ContextQuery>, QueryRejection>,
+    identity: Option<Extension<auth::Authenticated>>,
+    headers: &HeaderMap,
+    body: &[u8],
+    labels: &mut metrics::RequestLabels,
+) -> Result<QuoteRequest, AppError> {
+    let Query(query) = query.map_err(|e| OracleRequestError::InvalidQuery(e.body_text()))?;
+    let expiry_seconds = state.effective_expiry_seconds(query.expiry_seconds);
+    let identity = identity.as_ref().map(|Extension(identity)| identity);
+    let CheckedRequest { request, taker_id } =
+        check_request(state, identity, headers, body, labels)?;
+    let order = &request.order;
+    order_nonce::check(state, &order.into()).await?;
+
+    let input_idx = request.input_io_index.try_into().unwrap_or(usize::MAX);
+    let output_idx = request.output_io_index.try_into().unwrap_or(usize::MAX);
+
+    // Extract input/output IOs from the order
+    let input =
+        *order
+            .valid_inputs
+            .get(input_idx)
+            .ok_or_else(|| OracleRequestError::InvalidIndex {
+                kind: "input",
+                index: input_idx,
+                len: order.valid_inputs.len(),
+            })?;
+
+    let output =
+        *order
+            .valid_outputs
+            .get(output_idx)
+            .ok_or_else(|| OracleRequestError::InvalidIndex {
+                kind: "output",
+                index: output_idx,
+                len: order.valid_outputs.len(),
+            })?;
+    let (input_token, output_token) = (input.token, output.token);
+
+    // Determine price direction
+    let direction = state.price_direction(input_token, output_token)?;
+    labels.set_pair(
+        state.token_pair.base_token,
+        state.token_pair.quote_token,
+        direction,
+    );
+    let decimal_shift = state.decimal_shift(input, output).await?;
+
+    tracing::debug!(
+        "Oracle request: order={:?} input={} output={} direction={:?} taker={:?} identity={:?}",
+        order.version,
+        input_token,
+        output_token,
+        direction,
+        taker_id,
+        identity.map(|identity| &identity.0)
+    );
+
+    Ok(QuoteRequest {
+        direction,
+        decimal_shift,
+        expiry_seconds,
+        counterparty: request.counterparty,
+        taker_id,
+        order_hash: order.hash,
+        order_nonce: order.into(),
+        vault: inventory::Vault {
+            version: order.version,
+            owner: order.owner,
+            output,
+        },
+    })
+}
+
+/// Header carrying the ID of the issued quote.
+pub const QUOTE_ID_HEADER: &str = "x-quote-id";
+
+/// GET handler — the stored record of a previously issued quote, for resolving disputes.
+#[utoipa::path(
+    get,
+    path = "/quote/{id}",
+    params(("id" = String, Path, description = "Quote ID from the `/context` response")),
+    responses(
+        (status = 200, description = "The issued quote", body = storage::AuditRecord),
+        (status = 400, description = "Malformed quote ID", body = ErrorResponse),
+        (status = 404, description = "No quote with this ID", body = ErrorResponse),
+    ),
+)]
+async fn get_quote(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<storage::AuditRecord>, AppError> {
+    let quote_id = uuid::Uuid::parse_str(&id)
+        .map_err(|e| OracleRequestError::InvalidQuoteId(format!("'{}': {}", id, e)))?;
+    state
+        .storage
+        .quote(quote_id)
+        .await?
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("No quote with ID {}", quote_id)))
+}
+
+/// Fetch, build, sign and record a quote for `direction`, unless an identical one was just
+/// issued and is still in the response cache. `order` is the hash and output vault of the
+/// order quoted for, if any.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn issue_quote(
+    state: &AppState,
+    direction: PriceDirection,
+    decimal_shift: i32,
+    expiry_seconds: u64,
+    counterparty: Address,
+    api_key: Option<String>,
+    taker_id: Option<String>,
+    order: Option<(B256, &inventory::Vault)>,
+) -> Result<oracle::OracleResponse, AppError> {
+    check_ready(state)?;
+    check_clock_skew(state)?;
+    check_schedule(state)?;
+
+    let cache_key = cache::CacheKey {
+        base_token: state.token_pair.base_token,
+        quote_token: state.token_pair.quote_token,
+        direction,
+        decimal_shift,
+        expiry_seconds,
+    };
+    // A cached quote is taken against the vault like any other, so inventory throttling has
+    // to see every one it hands out
+    let response_cache = state
+        .response_cache
+        .as_ref()
+        .filter(|_| state.nonces.is_none() && state.inventory.is_none());
+    let lookup_started = std::time::Instant::now();
+    if let Some(cached) = response_cache.and_then(|c| c.get(&cache_key, lookup_started)) {
+        timing::record(timing::Stage::CacheHit, lookup_started.elapsed());
+        return Ok(cached);
+    }
+    limits::check(state).await?;
+
+    let priced = price_context(state, direction, decimal_shift, expiry_seconds).await?;
+    let response = sign_priced_context(
+        state,
+        priced,
+        direction,
+        counterparty,
+        api_key,
+        taker_id,
+        order,
+    )
+    .await?;
+    if let Some(cache) = response_cache {
+        cache.insert(cache_key, response.clone(), std::time::Instant::now());
+    }
+    Ok(response)
+}
+
+/// Sign and record a priced context: the half of [`issue_quote`] after the guards and the
+/// price fetch.
+#[allow(clippy::too_many_arguments)]
+async fn sign_priced_context(
+    state: &AppState,
+    priced: PricedContext,
+    direction: PriceDirection,
+    counterparty: Address,
+    api_key: Option<String>,
+    taker_id: Option<String>,
+    order: Option<(B256, &inventory::Vault)>,
+) -> Result<oracle::OracleResponse, AppError> {
+    let PricedContext {
+        mut context,
+        publish_time,
+        expiry,
+        mut explanation,
+    } = priced;
+    explanation.guards.splice(0..0, signing_guards(state));
+    let nonce = match &state.nonces {
+        Some(nonces) => {
+            let (slot, display) = nonces.next()?;
+            // After the price and expiry, ahead of any context source slots
+            context.insert(2, slot);
+            Some(display)
+        }
+        None => None,
+    };
+
+    let reserved = limits::reserve(state).await?;
+    let now = state.clock.now().as_secs();
+    let signing_started = std::time::Instant::now();
+    let signed = async {
+        let (signature, signer) = state
+            .signers
+            .for_pair(
+                state.token_pair.base_token,
+                state.token_pair.quote_token,
+                now,
+            )
+            .sign_context_with(&context, state.signers.hash_scheme())
+            .await
+            .map_err(signing_error)?;
+        let cosignatures: Vec<_> = state
+            .signers
+            .cosign(&context, now)
+            .await
+            .map_err(signing_error)?
+            .into_iter()
+            .map(|(signature, signer)| oracle::CoSignature { signer, signature })
+            .collect();
+        Ok::<_, AppError>((signature, signer, cosignatures))
+    }
+    .await;
+    let (signature, signer, cosignatures) = match signed {
+        Ok(signed) => signed,
+        Err(e) => {
+            limits::release(state, reserved).await;
+            return Err(e);
+        }
+    };
+    timing::record(timing::Stage::Signing, signing_started.elapsed());
+
+    let record = storage::AuditRecord {
+        quote_id: uuid::Uuid::new_v4(),
+        signature_hash: storage::AuditRecord::signature_hash(&signature),
+        issued_at: state.clock.now().as_secs() as i64,
+        signer,
+        counterparty,
+        api_key,
+        taker_id,
+        order_hash: order.map(|(hash, _)| hash),
+        output_vault: order.map(|(_, vault)| vault.key()),
+        base_token: state.token_pair.base_token,
+        quote_token: state.token_pair.quote_token,
+        direction,
+        context,
+        signature,
+        explanation: Some(explanation),
+    };
+    state.fills.record_issued();
+    let recording_started = std::time::Instant::now();
+    match state.storage.record_quote(&record).await {
+        Ok(true) => {}
+        Ok(false) => tracing::debug!(
+            "Quote {} repeats a previously issued signature {}",
+            record.quote_id,
+            record.signature_hash
+        ),
+        Err(e) => tracing::error!("Failed to record quote {}: {}", record.quote_id, e),
+    }
+    timing::record(timing::Stage::Recording, recording_started.elapsed());
+
+    let price = match state.token_pair.display_decimals {
+        Some(decimals) => {
+            let price = oracle::QuotePrice::new(record.context[0], decimals)?;
+            tracing::debug!(
+                "Quote {} signed at {} ({} as signed)",
+                record.quote_id,
+                price.decimal,
+                price.signed
+            );
+            state.metrics.record_signed_price(
+                state.token_pair.base_token,
+                state.token_pair.quote_token,
+                direction,
+                &price.decimal,
+            );
+            Some(price)
+        }
+        None => None,
+    };
+    let debug = if state.diagnostics {
+        Some(diagnostics(
+            state,
+            &record.context,
+            direction,
+            publish_time,
+            expiry,
+        )?)
+    } else {
+        None
+    };
+
+    // The record is stored by now, so the response takes its context and signature
+    Ok(oracle::OracleResponse {
+        quote_id: record.quote_id,
+        signer,
+        context: record.context,
+        signature: record.signature,
+        cosignatures,
+        nonce,
+        price,
+        debug,
+    })
+}
+
+/// Refuse to sign until warm-up has finished.
+fn check_ready(state: &AppState) -> Result<(), AppError> {
+    if state.is_ready() {
+        Ok(())
+    } else {
+        Err(UnavailableError::WarmingUp.into())
+    }
+}
+
+/// Refuse to sign while the local clock is known to be skewed.
+fn check_clock_skew(state: &AppState) -> Result<(), AppError> {
+    match state.clock_skew.as_ref().filter(|m| m.should_refuse()) {
+        Some(monitor) => Err(UnavailableError::ClockSkew {
+            skew_ms: monitor.last_skew_ms(),
+        }
+        .into()),
+        None => Ok(()),
+    }
+}
+
+/// Refuse to sign outside the pair's quoting schedule.
+fn check_schedule(state: &AppState) -> Result<(), AppError> {
+    let Some(schedule) = &state.schedule else {
+        return Ok(());
+    };
+    let now = state.clock.now().as_secs();
+    if schedule.is_open(now) {
+        return Ok(());
+    }
+    Err(UnavailableError::MarketClosed {
+        retry_after_secs: schedule.next_open(now).map(|open| open - now),
+    }
+    .into())
+}
+
+/// An unsigned context (price and expiry slots) and the price publish time it was built from.
+struct PricedContext {
+    context: Vec<FixedBytes<32>>,
+    publish_time: i64,
+    expiry: u64,
+    explanation: explain::Explanation,
+}
+
+/// Names of the guards [`issue_quote`] checks before pricing, for the quote's explanation.
+fn signing_guards(state: &AppState) -> Vec<String> {
+    let mut guards = vec!["warm_up".to_string()];
+    if state.clock_skew.is_some() {
+        guards.push("clock_skew".to_string());
+    }
+    if state.schedule.is_some() {
+        guards.push("schedule".to_string());
+    }
+    if state.daily_quote_limit.is_some() {
+        guards.push("daily_limit".to_string());
+    }
+    if state.inventory.is_some() {
+        guards.push("inventory".to_string());
+    }
+    guards
+}
+
+/// The configured context sources' slots, empty without any.
+async fn context_source_slots(state: &AppState) -> anyhow::Result<Vec<FixedBytes<32>>> {
+    match &state.context_sources {
+        Some(sources) => sources.slots().await,
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Fetch the price and expiry and build the context to sign for `direction`.
+async fn price_context(
+    state: &AppState,
+    direction: PriceDirection,
+    decimal_shift: i32,
+    expiry_seconds: u64,
+) -> Result<PricedContext, AppError> {
+    let (price_data, expiry, extra_slots) = tokio::try_join!(
+        fetch_price(state),
+        compute_expiry(state, expiry_seconds),
+        context_source_slots(state),
+    )
+    .map_err(upstream_error)?;
+    guard::check(state, &price_data).await?;
+    let pricing_started = std::time::Instant::now();
+
+    let built = oracle::build_pair_context_explained(
+        price_data.price,
+        price_data.expo,
+        expiry,
+        direction,
+        &state.token_pair,
+    )
+    .map_err(upstream_error);
+    let (mut context, steps) = match built {
+        Ok(built) => built,
+        Err(err) => {
+            if let AppError::Unavailable(e @ UnavailableError::InvalidUpstreamPrice(_)) = &err {
+                incident::capture(state, e.error_code(), e.to_string(), Vec::new()).await;
+            }
+            return Err(err);
+        }
+    };
+    let source_price = oracle::format_pyth_price(price_data.price, price_data.expo);
+    let mut explanation = explain::Explanation {
+        sources: vec![explain::SourcePrice {
+            feed_id: state.pyth_price_feed_id.clone(),
+            price: source_price.clone(),
+            publish_time: price_data.publish_time,
+        }],
+        aggregate: source_price,
+        direction,
+        steps: Vec::new(),
+        guards: state
+            .pricing_guards
+            .iter()
+            .map(|guard| guard.name())
+            .chain(std::iter::once("positive_price"))
+            .map(String::from)
+            .collect(),
+    };
+    for (step, price) in steps {
+        explanation.push_step(step, price)?;
+    }
+    let slot_price = |slot: FixedBytes<32>| rain_math_float::Float::from(B256::from(slot));
+
+    if decimal_shift != 0 {
+        context[0] = oracle::scale_price(context[0], decimal_shift)?;
+        explanation.push_step(
+            format!("scale 10^{}", decimal_shift),
+            slot_price(context[0]),
+        )?;
+    }
+    if let Some(gas) = &state.gas_compensation {
+        let native_price = oracle::feed_price(
+            price_data.price,
+            price_data.expo,
+            state.token_pair.feed_inverted,
+        )?;
+        context[0] = gas.widen(context[0], native_price).await?;
+        explanation.push_step("gas", slot_price(context[0]))?;
+    }
+    if let Some(jitter) = &state.price_jitter {
+        context[0] = jitter.apply(context[0])?;
+        explanation.push_step("jitter", slot_price(context[0]))?;
+    }
+    context.extend(extra_slots);
+    timing::record(timing::Stage::Pricing, pricing_started.elapsed());
+    Ok(PricedContext {
+        context,
+        publish_time: price_data.publish_time,
+        expiry,
+        explanation,
+    })
+}
+
+/// Human-readable view of `context` for the configured feed and expiry mode.
+fn diagnostics(
+    state: &AppState,
+    context: &[FixedBytes<32>],
+    direction: PriceDirection,
+    publish_time: i64,
+    expiry: u64,
+) -> anyhow::Result<oracle::Diagnostics> {
+    oracle::Diagnostics::new(
+        context,
+        direction,
+        &state.pyth_price_feed_id,
+        publish_time,
+        expiry,
+        matches!(state.token_pair.expiry, ExpiryMode::BlockNumber { .. }),
+    )
+}
+
+/// Fetch the configured feed's latest price: the one the leader shared when fresh, otherwise
+/// from Hermes.
+async fn fetch_price(state: &AppState) -> anyhow::Result<pyth::PriceData> {
+    if let Some(data) = shared_price::latest(state).await {
+        return Ok(data);
+    }
+    fetch_hermes_price(state).await
+}
+
+/// Fetch the configured feed from Hermes, recording the outcome for `/status`. While Hermes has
+/// asked any replica sharing the storage backend to back off, fails as rate limited without
+/// calling it.
+async fn fetch_hermes_price(state: &AppState) -> anyhow::Result<pyth::PriceData> {
+    let feed_id = &state.pyth_price_feed_id;
+    if let Some(breaker) = &state.circuit_breaker {
+        breaker
+            .check(feed_id, state.clock.now().as_secs())
+            .map_err(|retry_after_secs| UnavailableError::CircuitOpen { retry_after_secs })?;
+    }
+    let now = state.clock.now().as_secs() as i64;
+    match state.storage.upstream_backoff(HERMES_UPSTREAM).await {
+        Ok(Some(until)) if until > now => {
+            return Err(pyth::HermesError::RateLimited {
+                retry_after: Some(std::time::Duration::from_secs((until - now) as u64)),
+            }
+            .into());
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Failed to read the Hermes backoff: {:#}", e),
+    }
+    let started = std::time::Instant::now();
+    let result = state.hermes.fetch_price(feed_id).await;
+    if let Some(pyth::HermesError::RateLimited {
+        retry_after: Some(retry_after),
+    }) = result.as_ref().err().and_then(|err| err.downcast_ref())
+    {
+        let backoff = (*retry_after).min(MAX_SHARED_BACKOFF);
+        let until = now + backoff.as_secs_f64().ceil() as i64;
+        if let Err(e) = state
+            .storage
+            .set_upstream_backoff(HERMES_UPSTREAM, until)
+            .await
+        {
+            tracing::warn!("Failed to share the Hermes backoff: {:#}", e);
+        }
+    }
+    state.feed_stats.record_latency(feed_id, started.elapsed());
+    timing::record(timing::Stage::PriceFetch, started.elapsed());
+    let now = state.clock.now().as_secs() as i64;
+    if let Some(breaker) = &state.circuit_breaker {
+        // Rate limiting means Hermes is up, and it comes with its own Retry-After
+        let rate_limited = result.as_ref().is_err_and(|err| {
+            matches!(
+                err.downcast_ref(),
+                Some(pyth::HermesError::RateLimited { .. })
+            )
+        });
+        if !rate_limited && breaker.record(feed_id, now as u64, result.is_ok()) {
+            if let Err(err) = &result {
+                let detail = format!("Circuit for feed {} opened: {:#}", feed_id, err);
+                incident::capture(state, "circuit_open", detail, Vec::new()).await;
+            }
+        }
+    }
+    match &result {
+        Ok(data) => {
+            state.feed_stats.record_success(feed_id, now, data);
+            state.price_history.record(feed_id, data);
+        }
+        Err(err) => state.feed_stats.record_error(feed_id, now, err),
+    }
+    result
+}
+
+/// Surface Hermes rate limiting and unusable prices as distinct 503s rather than internal
+/// errors.
+fn upstream_error(err: anyhow::Error) -> AppError {
+    let err = match err.downcast::<UnavailableError>() {
+        Ok(err) => return err.into(),
+        Err(err) => err,
+    };
+    if let Some(pyth::HermesError::RateLimited { retry_after }) = err.downcast_ref() {
+        return UnavailableError::UpstreamRateLimited {
+            retry_after: *retry_after,
+        }
+        .into();
+    }
+    if let Some(pyth::HermesError::Unverified(reason)) = err.downcast_ref() {
+        return UnavailableError::UnverifiedUpstreamPrice(reason.clone()).into();
+    }
+    if let Some(price) = err.downcast_ref::<oracle::NonPositivePrice>() {
+        return UnavailableError::InvalidUpstreamPrice(price.to_string()).into();
+    }
+    err.into()
+}
+
+/// Surface a saturated remote signer as a 503 the taker can retry, rather than an internal
+/// error.
+fn signing_error(err: anyhow::Error) -> AppError {
+    match err.downcast_ref::<remote::SigningQueueFull>() {
+        Some(full) => UnavailableError::SigningQueueFull(*full).into(),
+        None => err.into(),
+    }
+}
+
+/// Compute the value for context slot [1] according to the pair's expiry mode.
+async fn compute_expiry(state: &AppState, expiry_seconds: u64) -> anyhow::Result<u64> {
+    match state.token_pair.expiry {
+        ExpiryMode::Timestamp => Ok(timestamp_expiry(
+            state.clock.now(),
+            expiry_seconds,
+            state.max_expiry_seconds,
+            state.expiry_safety_margin,
+            state.expiry_block_time,
+        )),
+        ExpiryMode::BlockNumber { blocks } => {
+            let rpc_url = state
+                .rpc_url
+                .as_deref()
+                .ok_or_else(|| anyhow::anyhow!("Block number expiry requires an RPC URL"))?;
+            Ok(rpc::block_number(rpc_url).await? + blocks)
+        }
+    }
+}
+
+/// Unix seconds `expiry_seconds` after `now`, extended by the safety margin and rounded up to
+/// the next block boundary when a block time is set. Never more than `max_expiry_seconds` plus
+/// the margin after `now`: where rounding up would pass that, the expiry isn't aligned.
+fn timestamp_expiry(
+    now: std::time::Duration,
+    expiry_seconds: u64,
+    max_expiry_seconds: u64,
+    margin: std::time::Duration,
+    block_time: Option<u64>,
+) -> u64 {
+    let expiry = (now + std::time::Duration::from_secs(expiry_seconds) + margin).as_secs();
+    let latest = (now + std::time::Duration::from_secs(max_expiry_seconds) + margin).as_secs();
+    match block_time.filter(|&t| t > 0) {
+        Some(t) => (expiry.div_ceil(t) * t).min(latest.max(expiry)),
+        None => expiry,
+    }
+}
+
+/// Client-facing request errors (returned as 400).
+#[derive(Debug, thiserror::Error)]
+pub enum OracleRequestError {
+    #[error("Invalid ABI-encoded body: {0}")]
+    InvalidBody(String),
+
+    #[error("Invalid batch: {0}")]
+    InvalidBatch(String),
+
+    #[error("Invalid order version header: {0} (expected 3 or 4)")]
+    InvalidOrderVersion(String),
+
+    #[error("Invalid query string: {0}")]
+    InvalidQuery(String),
+
+    #[error("Order too large: {0}")]
+    OrderTooLarge(String),
+
+    #[error("Invalid quote ID {0}")]
+    InvalidQuoteId(String),
+
+    #[error("Invalid Rain float: {0}")]
+    InvalidFloat(String),
+
+    #[error("Invalid Idempotency-Key header: {0}")]
+    InvalidIdempotencyKey(String),
+
+    #[error("Idempotency-Key '{0}' was already used for a different request")]
+    IdempotencyKeyReused(String),
+
+    #[error("Invalid X-Taker-Id header: {0}")]
+    InvalidTakerId(String),
+
+    #[error("X-Taker-Id '{0}' is neither the order's counterparty nor an authenticated identity")]
+    TakerIdMismatch(String),
+
+    #[error("Order {0} has been removed from the orderbook")]
+    OrderRemoved(B256),
+
+    #[error("Order nonce {0} was recently used by a different order")]
+    OrderNonceReused(B256),
+
+    #[error("Decimals of token {0} are not in the order and there is no RPC URL to look them up")]
+    UnknownTokenDecimals(Address),
 
     #[error("Invalid {kind} IO index: {index} (order has {len} {kind}s)")]
     InvalidIndex {
@@ -233,6 +2119,13 @@ pub enum OracleRequestError {
         len: usize,
     },
 
+    #[error("Order expression {bytecode_hash} reads {required} signed-context slots but quotes carry {provided}")]
+    ContextLayoutMismatch {
+        bytecode_hash: B256,
+        required: usize,
+        provided: usize,
+    },
+
     #[error("Unsupported token pair: input {input_token} / output {output_token} does not match configured pair (base={base_token}, quote={quote_token})")]
     UnsupportedTokenPair {
         input_token: Address,
@@ -242,49 +2135,457 @@ pub enum OracleRequestError {
     },
 }
 
+/// The server is temporarily unable to issue quotes (returned as 503).
+#[derive(Debug, thiserror::Error)]
+pub enum UnavailableError {
+    #[error("Local clock skew of {skew_ms}ms exceeds the configured limit; refusing to sign")]
+    ClockSkew { skew_ms: i64 },
+
+    #[error("{0}; refusing to sign")]
+    InvalidUpstreamPrice(String),
+
+    #[error("Price feed update failed signature verification ({0}); refusing to sign")]
+    UnverifiedUpstreamPrice(String),
+
+    #[error("{0}; pair paused")]
+    PriceGuard(guard::GuardRejection),
+
+    #[error("Price feed provider is rate limiting requests; retry later")]
+    UpstreamRateLimited {
+        retry_after: Option<std::time::Duration>,
+    },
+
+    #[error("Server is warming up; retry shortly")]
+    WarmingUp,
+
+    #[error("{0}; refusing to sign")]
+    InventoryExhausted(inventory::InventoryExhausted),
+
+    #[error("Vault balance unavailable ({0}); refusing to sign")]
+    InventoryUnavailable(String),
+
+    #[error("Market is closed for this pair; quoting resumes at its next scheduled window")]
+    MarketClosed { retry_after_secs: Option<u64> },
+
+    #[error(
+        "Daily limit of {limit} signed quotes reached for this pair; paused until the next UTC day"
+    )]
+    DailyLimitReached { limit: u64, retry_after_secs: u64 },
+
+    #[error("{0}; retry shortly")]
+    SigningQueueFull(remote::SigningQueueFull),
+
+    #[error("Price feed is failing; refusing requests for {retry_after_secs}s before retrying it")]
+    CircuitOpen { retry_after_secs: u64 },
+}
+
+impl UnavailableError {
+    fn error_code(&self) -> &'static str {
+        match self {
+            Self::ClockSkew { .. } => "clock_skew",
+            Self::InvalidUpstreamPrice(_) => "invalid_upstream_price",
+            Self::UnverifiedUpstreamPrice(_) => "unverified_upstream_price",
+            Self::PriceGuard(rejection) => rejection.code,
+            Self::UpstreamRateLimited { .. } => "upstream_rate_limited",
+            Self::WarmingUp => "warming_up",
+            Self::InventoryExhausted(_) => "inventory_exhausted",
+            Self::InventoryUnavailable(_) => "inventory_unavailable",
+            Self::MarketClosed { .. } => "market_closed",
+            Self::DailyLimitReached { .. } => "daily_limit_reached",
+            Self::SigningQueueFull(_) => "signing_queue_full",
+            Self::CircuitOpen { .. } => "circuit_open",
+        }
+    }
+
+    /// What the error code means, for the `/errors` catalog.
+    fn description(&self) -> &'static str {
+        match self {
+            Self::ClockSkew { .. } => "The server clock is skewed, so it refuses to sign",
+            Self::InvalidUpstreamPrice(_) => {
+                "The price feed returned a price that fails sanity checks"
+            }
+            Self::UnverifiedUpstreamPrice(_) => {
+                "The price feed's update failed Wormhole guardian signature verification"
+            }
+            Self::PriceGuard(rejection) => match rejection.code {
+                "reference_deviation" => {
+                    "The feed price deviates too far from the reference price, so the pair is paused"
+                }
+                "reference_unavailable" => {
+                    "The reference price couldn't be fetched to check the feed price against"
+                }
+                _ => "A pricing guard refused the feed price, so the pair is paused; `guard` names it",
+            },
+            Self::UpstreamRateLimited { .. } => {
+                "The price feed provider is rate limiting the server"
+            }
+            Self::WarmingUp => "The server has not fetched its first price yet",
+            Self::InventoryExhausted(_) => {
+                "The order's output vault can't cover another quote on top of its unexpired ones"
+            }
+            Self::InventoryUnavailable(_) => {
+                "The order's output vault balance couldn't be read to check inventory against"
+            }
+            Self::MarketClosed { .. } => "The pair is outside its quoting schedule",
+            Self::DailyLimitReached { .. } => {
+                "The pair's daily limit of signed quotes is used up until the next UTC day"
+            }
+            Self::SigningQueueFull(_) => {
+                "The remote signer has too many requests waiting, so new ones are refused"
+            }
+            Self::CircuitOpen { .. } => {
+                "The price feed failed repeatedly, so requests fail fast until it is retried"
+            }
+        }
+    }
+
+    /// One of each variant, for the `/errors` catalog.
+    fn samples() -> Vec<Self> {
+        vec![
+            Self::ClockSkew { skew_ms: 0 },
+            Self::InvalidUpstreamPrice(String::new()),
+            Self::UnverifiedUpstreamPrice(String::new()),
+            Self::PriceGuard(guard::GuardRejection::new("", "")),
+            Self::PriceGuard(
+                guard::GuardRejection::new("reference_deviation", "")
+                    .with_code("reference_deviation"),
+            ),
+            Self::PriceGuard(
+                guard::GuardRejection::new("reference_deviation", "")
+                    .with_code("reference_unavailable"),
+            ),
+            Self::UpstreamRateLimited { retry_after: None },
+            Self::WarmingUp,
+            Self::InventoryExhausted(inventory::InventoryExhausted {
+                balance: 0.0,
+                quote_size: 0.0,
+                outstanding: 0,
+                retry_after_secs: None,
+            }),
+            Self::InventoryUnavailable(String::new()),
+            Self::MarketClosed {
+                retry_after_secs: None,
+            },
+            Self::DailyLimitReached {
+                limit: 0,
+                retry_after_secs: 0,
+            },
+            Self::SigningQueueFull(remote::SigningQueueFull {
+                signer: Address::ZERO,
+                queued: 0,
+            }),
+            Self::CircuitOpen {
+                retry_after_secs: 0,
+            },
+        ]
+    }
+
+    /// Name of the pricing guard that refused the price, sent as `guard` in the body.
+    fn guard(&self) -> Option<&str> {
+        match self {
+            Self::PriceGuard(rejection) => Some(&rejection.guard),
+            Self::InvalidUpstreamPrice(_) => Some("positive_price"),
+            _ => None,
+        }
+    }
+
+    /// Seconds to send in a `Retry-After` header, if known.
+    fn retry_after_secs(&self) -> Option<u64> {
+        match self {
+            Self::ClockSkew { .. }
+            | Self::InvalidUpstreamPrice(_)
+            | Self::UnverifiedUpstreamPrice(_)
+            | Self::PriceGuard(_)
+            | Self::InventoryUnavailable(_) => None,
+            Self::InventoryExhausted(e) => e.retry_after_secs,
+            Self::WarmingUp => Some(WARM_UP_RETRY_INTERVAL.as_secs()),
+            Self::SigningQueueFull(_) => Some(1),
+            Self::CircuitOpen { retry_after_secs } => Some(*retry_after_secs),
+            Self::MarketClosed { retry_after_secs } => *retry_after_secs,
+            Self::DailyLimitReached {
+                retry_after_secs, ..
+            } => Some(*retry_after_secs),
+            // Round up so clients never retry before the provider allows it.
+            Self::UpstreamRateLimited { retry_after } => {
+                retry_after.map(|d| d.as_secs() + u64::from(d.subsec_nanos() > 0))
+            }
+        }
+    }
+}
+
 /// Application error type for axum handlers.
+#[derive(Debug)]
 pub enum AppError {
     Internal(anyhow::Error),
     BadRequest(OracleRequestError),
+    Unauthorized(auth::AuthError),
+    Unavailable(UnavailableError),
+    TooManyRequests(quota::QuotaError),
+    NotFound(String),
+}
+
+impl AppError {
+    /// Machine-readable code sent as `error` in the response body.
+    fn error_code(&self) -> &'static str {
+        match self {
+            AppError::Internal(_) => "internal_error",
+            AppError::BadRequest(err) => err.error_code(),
+            AppError::Unauthorized(err) => err.error_code(),
+            AppError::Unavailable(err) => err.error_code(),
+            AppError::TooManyRequests(err) => err.error_code(),
+            AppError::NotFound(_) => "not_found",
+        }
+    }
+
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(err) => err.status_code(),
+            AppError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+        }
+    }
+
+    /// What the error code means, for the `/errors` catalog.
+    fn description(&self) -> &'static str {
+        match self {
+            AppError::Internal(_) => {
+                "An unexpected server error; the error_id correlates it with the server logs"
+            }
+            AppError::BadRequest(err) => err.description(),
+            AppError::Unauthorized(err) => err.description(),
+            AppError::Unavailable(err) => err.description(),
+            AppError::TooManyRequests(err) => err.description(),
+            AppError::NotFound(_) => "The requested resource does not exist",
+        }
+    }
+
+    /// One of each error variant, for the `/errors` catalog.
+    fn samples() -> Vec<Self> {
+        let mut samples: Vec<Self> = OracleRequestError::samples()
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        samples.extend(auth::AuthError::samples().into_iter().map(Into::into));
+        samples.extend(quota::QuotaError::samples().into_iter().map(Into::into));
+        samples.extend(UnavailableError::samples().into_iter().map(Into::into));
+        samples.push(AppError::NotFound(String::new()));
+        samples.push(AppError::Internal(anyhow::anyhow!("")));
+        samples
+    }
+
+    /// Pricing guard that refused the price, sent as `guard` in the response body.
+    fn guard(&self) -> Option<String> {
+        match self {
+            AppError::Unavailable(err) => err.guard().map(String::from),
+            _ => None,
+        }
+    }
+
+    /// Human-readable description sent as `detail` in the response body.
+    fn detail(&self) -> String {
+        match self {
+            AppError::Internal(err) => format!("{}", err),
+            AppError::BadRequest(err) => format!("{}", err),
+            AppError::Unauthorized(err) => format!("{}", err),
+            AppError::Unavailable(err) => format!("{}", err),
+            AppError::TooManyRequests(err) => format!("{}", err),
+            AppError::NotFound(detail) => detail.clone(),
+        }
+    }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> axum::response::Response {
+        let status = self.status_code();
         match self {
             AppError::Internal(err) => {
-                tracing::error!("Internal error: {:?}", err);
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
+                let error_id = log_internal_error(&err);
+                let mut response = (
+                    status,
                     Json(ErrorResponse {
                         error: "internal_error".to_string(),
-                        detail: format!("{}", err),
+                        detail: redacted_detail(&error_id),
+                        error_id: Some(error_id.clone()),
+                        guard: None,
                     }),
                 )
-                    .into_response()
+                    .into_response();
+                response.extensions_mut().insert(InternalErrorDetail {
+                    error_id,
+                    detail: format!("{}", err),
+                });
+                response
             }
             AppError::BadRequest(err) => {
                 tracing::warn!("Bad request: {}", err);
                 (
-                    StatusCode::BAD_REQUEST,
+                    status,
                     Json(ErrorResponse {
                         error: err.error_code().to_string(),
                         detail: format!("{}", err),
+                        error_id: None,
+                        guard: None,
                     }),
                 )
                     .into_response()
             }
-        }
-    }
-}
-
-impl OracleRequestError {
-    fn error_code(&self) -> &'static str {
-        match self {
-            Self::InvalidBody(_) => "invalid_body",
-            Self::InvalidIndex { .. } => "invalid_index",
-            Self::UnsupportedTokenPair { .. } => "unsupported_token_pair",
-        }
-    }
+            AppError::Unauthorized(err) => {
+                tracing::warn!("Unauthorized: {}", err);
+                (
+                    status,
+                    Json(ErrorResponse {
+                        error: err.error_code().to_string(),
+                        detail: format!("{}", err),
+                        error_id: None,
+                        guard: None,
+                    }),
+                )
+                    .into_response()
+            }
+            AppError::Unavailable(err) => {
+                tracing::warn!("Unavailable: {}", err);
+                let mut response = (
+                    status,
+                    Json(ErrorResponse {
+                        error: err.error_code().to_string(),
+                        detail: format!("{}", err),
+                        error_id: None,
+                        guard: err.guard().map(String::from),
+                    }),
+                )
+                    .into_response();
+                if let Some(secs) = err.retry_after_secs() {
+                    response
+                        .headers_mut()
+                        .insert(axum::http::header::RETRY_AFTER, secs.into());
+                }
+                response
+            }
+            AppError::NotFound(detail) => (
+                status,
+                Json(ErrorResponse {
+                    error: "not_found".to_string(),
+                    detail,
+                    error_id: None,
+                    guard: None,
+                }),
+            )
+                .into_response(),
+            AppError::TooManyRequests(err) => {
+                tracing::warn!("Too many requests: {}", err);
+                (
+                    status,
+                    Json(ErrorResponse {
+                        error: err.error_code().to_string(),
+                        detail: format!("{}", err),
+                        error_id: None,
+                        guard: None,
+                    }),
+                )
+                    .into_response()
+            }
+        }
+    }
+}
+
+impl OracleRequestError {
+    fn error_code(&self) -> &'static str {
+        match self {
+            Self::InvalidBody(_) => "invalid_body",
+            Self::InvalidBatch(_) => "invalid_batch",
+            Self::InvalidOrderVersion(_) => "invalid_order_version",
+            Self::InvalidQuery(_) => "invalid_query",
+            Self::OrderTooLarge(_) => "order_too_large",
+            Self::InvalidQuoteId(_) => "invalid_quote_id",
+            Self::InvalidFloat(_) => "invalid_float",
+            Self::InvalidIdempotencyKey(_) => "invalid_idempotency_key",
+            Self::IdempotencyKeyReused(_) => "idempotency_key_reused",
+            Self::InvalidTakerId(_) => "invalid_taker_id",
+            Self::TakerIdMismatch(_) => "taker_id_mismatch",
+            Self::OrderRemoved(_) => "order_removed",
+            Self::OrderNonceReused(_) => "order_nonce_reused",
+            Self::UnknownTokenDecimals(_) => "unknown_token_decimals",
+            Self::InvalidIndex { .. } => "invalid_index",
+            Self::ContextLayoutMismatch { .. } => "context_layout_mismatch",
+            Self::UnsupportedTokenPair { .. } => "unsupported_token_pair",
+        }
+    }
+
+    /// What the error code means, for the `/errors` catalog.
+    fn description(&self) -> &'static str {
+        match self {
+            Self::InvalidBody(_) => "The request body is not a valid ABI-encoded order request",
+            Self::InvalidBatch(_) => {
+                "The /context/batch body is not a non-empty list of requests within the size limit"
+            }
+            Self::InvalidOrderVersion(_) => "The order version header is not 3 or 4",
+            Self::InvalidQuery(_) => "A query string parameter is invalid",
+            Self::OrderTooLarge(_) => "The order exceeds the configured size limits",
+            Self::InvalidQuoteId(_) => "The quote ID is not a valid UUID",
+            Self::InvalidFloat(_) => "The value is not a valid Rain float",
+            Self::InvalidIdempotencyKey(_) => "The Idempotency-Key header is empty or too long",
+            Self::IdempotencyKeyReused(_) => {
+                "The Idempotency-Key was already used for a different request"
+            }
+            Self::InvalidTakerId(_) => "The X-Taker-Id header is malformed",
+            Self::TakerIdMismatch(_) => {
+                "The X-Taker-Id is neither the counterparty nor an authenticated identity"
+            }
+            Self::OrderRemoved(_) => "The order has been removed from the orderbook",
+            Self::OrderNonceReused(_) => {
+                "The order's owner recently quoted the same nonce with a different expression"
+            }
+            Self::UnknownTokenDecimals(_) => {
+                "The order doesn't declare a token's decimals and the server can't look them up"
+            }
+            Self::InvalidIndex { .. } => "An input or output IO index is out of range",
+            Self::ContextLayoutMismatch { .. } => {
+                "The order's expression reads more signed-context slots than the server signs"
+            }
+            Self::UnsupportedTokenPair { .. } => {
+                "The order's tokens don't match the configured pair"
+            }
+        }
+    }
+
+    /// One of each variant, for the `/errors` catalog.
+    fn samples() -> Vec<Self> {
+        vec![
+            Self::InvalidBody(String::new()),
+            Self::InvalidBatch(String::new()),
+            Self::InvalidOrderVersion(String::new()),
+            Self::InvalidQuery(String::new()),
+            Self::OrderTooLarge(String::new()),
+            Self::InvalidQuoteId(String::new()),
+            Self::InvalidFloat(String::new()),
+            Self::InvalidIdempotencyKey(String::new()),
+            Self::IdempotencyKeyReused(String::new()),
+            Self::InvalidTakerId(String::new()),
+            Self::TakerIdMismatch(String::new()),
+            Self::OrderRemoved(B256::ZERO),
+            Self::OrderNonceReused(B256::ZERO),
+            Self::UnknownTokenDecimals(Address::ZERO),
+            Self::InvalidIndex {
+                kind: "input",
+                index: 0,
+                len: 0,
+            },
+            Self::ContextLayoutMismatch {
+                bytecode_hash: B256::ZERO,
+                required: 0,
+                provided: 0,
+            },
+            Self::UnsupportedTokenPair {
+                input_token: Address::ZERO,
+                output_token: Address::ZERO,
+                base_token: Address::ZERO,
+                quote_token: Address::ZERO,
+            },
+        ]
+    }
 }
 
 impl From<anyhow::Error> for AppError {
@@ -298,3 +2599,2416 @@ impl From<OracleRequestError> for AppError {
         Self::BadRequest(err)
     }
 }
+
+impl From<auth::AuthError> for AppError {
+    fn from(err: auth::AuthError) -> Self {
+        Self::Unauthorized(err)
+    }
+}
+
+impl From<UnavailableError> for AppError {
+    fn from(err: UnavailableError) -> Self {
+        Self::Unavailable(err)
+    }
+}
+
+impl From<quota::QuotaError> for AppError {
+    fn from(err: quota::QuotaError) -> Self {
+        Self::TooManyRequests(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::sol_types::SolValue;
+    use axum::body::Body;
+    use axum::http::Request;
+    use http_body_util::BodyExt;
+    use order::{EvaluableV4, OrderV4};
+    use tower::ServiceExt;
+
+    // Test private key — DO NOT use in production
+    pub(crate) const TEST_KEY: &str =
+        "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    pub(crate) const WETH: &str = "0x4200000000000000000000000000000000000006";
+    pub(crate) const USDC: &str = "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913";
+
+    /// State for the WETH/USDC pair signing with [`TEST_KEY`], for tests to build on.
+    pub(crate) fn test_state() -> AppState {
+        let token_pair = TokenPairConfig::new(WETH, USDC).unwrap();
+        AppState::new(TEST_KEY, "00", 5, token_pair).unwrap()
+    }
+
+    /// [`test_state`] pricing from a [`mock_hermes`] server.
+    async fn priced_state() -> AppState {
+        test_state().with_hermes(pyth::Hermes::default().with_base_url(&mock_hermes().await))
+    }
+
+    fn test_app() -> Router {
+        create_app(test_state())
+    }
+
+    async fn post_context(app: Router, body: impl Into<Body>) -> (StatusCode, serde_json::Value) {
+        send(app, Request::post("/context").body(body.into()).unwrap()).await
+    }
+
+    async fn send(app: Router, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[test]
+    fn test_decode_body_raw_passthrough() {
+        let raw = [0u8, 0, 0, 32, 1, 2, 3];
+        assert_eq!(decode_body(&raw).unwrap().as_ref(), &raw);
+    }
+
+    #[test]
+    fn test_decode_body_hex_string() {
+        assert_eq!(
+            decode_body(b"0x00ff10").unwrap().as_ref(),
+            &[0x00, 0xff, 0x10]
+        );
+        assert_eq!(decode_body(b"0X00FF").unwrap().as_ref(), &[0x00, 0xff]);
+    }
+
+    #[test]
+    fn test_decode_body_hex_string_with_whitespace() {
+        assert_eq!(decode_body(b"  0xabcd\n").unwrap().as_ref(), &[0xab, 0xcd]);
+    }
+
+    #[test]
+    fn test_decode_body_invalid_hex() {
+        let err = decode_body(b"0xzz").unwrap_err();
+        assert_eq!(err.error_code(), "invalid_body");
+        assert!(err.to_string().contains("invalid hex string"));
+    }
+
+    #[tokio::test]
+    async fn test_post_invalid_hex_body_is_bad_request() {
+        let (status, body) = post_context(test_app(), "0xnothex").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_body");
+    }
+
+    #[tokio::test]
+    async fn test_post_hex_body_with_bad_index() {
+        let order = OrderV4 {
+            owner: Address::ZERO,
+            evaluable: EvaluableV4 {
+                interpreter: Address::ZERO,
+                store: Address::ZERO,
+                bytecode: Default::default(),
+            },
+            validInputs: vec![],
+            validOutputs: vec![],
+            nonce: Default::default(),
+        };
+        let encoded = (
+            order,
+            alloy::primitives::U256::ZERO,
+            alloy::primitives::U256::ZERO,
+            Address::ZERO,
+        )
+            .abi_encode();
+        let (status, body) = post_context(test_app(), format!("0x{}", hex::encode(encoded))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_index");
+    }
+
+    #[tokio::test]
+    async fn test_internal_error_verbosity() {
+        let encoded = order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+        // An unreachable Hermes fails the price fetch with an internal error naming its URL
+        let app = |verbose| {
+            let state = test_state()
+                .with_hermes(pyth::Hermes::default().with_base_url("http://127.0.0.1:1"))
+                .with_verbose_errors(verbose);
+            create_app(state)
+        };
+
+        let (status, body) = post_context(app(false), encoded.clone()).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["error"], "internal_error");
+        let error_id = body["error_id"].as_str().unwrap();
+        assert_eq!(
+            body["detail"],
+            format!("Internal error (error ID {})", error_id)
+        );
+
+        let (status, body) = post_context(app(true), encoded).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(body["error_id"].is_string());
+        assert!(body["detail"].as_str().unwrap().contains("127.0.0.1"));
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_fails_fast() {
+        let state = test_state()
+            .with_hermes(pyth::Hermes::default().with_base_url("http://127.0.0.1:1"))
+            .with_circuit_breaker(
+                circuit::CircuitBreaker::new(2, std::time::Duration::from_secs(30)).unwrap(),
+            );
+        let app = create_app(state);
+        let encoded = order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+
+        for _ in 0..2 {
+            let (status, body) = post_context(app.clone(), encoded.clone()).await;
+            assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+            assert_eq!(body["error"], "internal_error");
+        }
+        let response = app
+            .clone()
+            .oneshot(Request::post("/context").body(Body::from(encoded)).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let retry_after: u64 = response.headers()[axum::http::header::RETRY_AFTER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((29..=30).contains(&retry_after));
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"], "circuit_open");
+
+        // Opening the circuit recorded one incident, with the pair config in its snapshot
+        let (status, body) =
+            send(app, Request::get("/incidents").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::OK);
+        let incidents = body["incidents"].as_array().unwrap();
+        assert_eq!(incidents.len(), 1);
+        assert_eq!(incidents[0]["guard"], "circuit_open");
+        let snapshot = &incidents[0]["snapshot"];
+        assert_eq!(snapshot["prices"], serde_json::json!([]));
+        assert_eq!(snapshot["config"]["pricing"]["gas_compensation"], false);
+    }
+
+    /// Whatever the body, a request that can't be quoted gets a structured 400, never a
+    /// panic or 5xx.
+    #[test]
+    fn prop_context_rejects_with_structured_400() {
+        use proptest::prelude::*;
+
+        let index = || {
+            prop_oneof![
+                (0u64..4).prop_map(alloy::primitives::U256::from),
+                Just(alloy::primitives::U256::from(usize::MAX)),
+                Just(alloy::primitives::U256::MAX),
+            ]
+        };
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let app = test_app();
+        proptest!(|(
+            inputs in proptest::collection::vec(any::<[u8; 20]>(), 0..4),
+            outputs in proptest::collection::vec(any::<[u8; 20]>(), 0..4),
+            input_index in index(),
+            output_index in index(),
+            hex_body: bool,
+        )| {
+            let io = |token: &[u8; 20]| order::IOV2 {
+                token: Address::from(*token),
+                vaultId: Default::default(),
+            };
+            let order = OrderV4 {
+                owner: Address::ZERO,
+                evaluable: EvaluableV4 {
+                    interpreter: Address::ZERO,
+                    store: Address::ZERO,
+                    bytecode: Default::default(),
+                },
+                validInputs: inputs.iter().map(io).collect(),
+                validOutputs: outputs.iter().map(io).collect(),
+                nonce: Default::default(),
+            };
+            let encoded = (order, input_index, output_index, Address::ZERO).abi_encode();
+            let body = if hex_body {
+                Body::from(format!("0x{}", hex::encode(encoded)))
+            } else {
+                Body::from(encoded)
+            };
+            let (status, body) = runtime.block_on(post_context(app.clone(), body));
+            prop_assert_eq!(status, StatusCode::BAD_REQUEST);
+            let error = body["error"].as_str().unwrap();
+            prop_assert!(matches!(error, "invalid_index" | "unsupported_token_pair"), "{}", error);
+        });
+
+        proptest!(|(body in proptest::collection::vec(any::<u8>(), 0..1024))| {
+            let (status, body) = runtime.block_on(post_context(app.clone(), body));
+            prop_assert_eq!(status, StatusCode::BAD_REQUEST);
+            prop_assert_eq!(body["error"].as_str(), Some("invalid_body"));
+        });
+    }
+
+    #[tokio::test]
+    async fn test_post_order_too_large() {
+        let state = test_state().with_request_limits(order::RequestLimits {
+            max_body_bytes: 64,
+            max_ios: 32,
+        });
+        // Hex is decoded first, so the limit applies to the ABI bytes, not the hex text.
+        let (status, body) =
+            post_context(create_app(state), format!("0x{}", "00".repeat(65))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "order_too_large");
+    }
+
+    #[tokio::test]
+    async fn test_post_invalid_order_version_header() {
+        let request = Request::post("/context")
+            .header(order::ORDER_VERSION_HEADER, "7")
+            .body(Body::from(vec![0u8; 32]))
+            .unwrap();
+        let (status, body) = send(test_app(), request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_order_version");
+    }
+
+    #[tokio::test]
+    async fn test_timestamp_expiry_uses_clock() {
+        let state = test_state().with_clock(Arc::new(clock::FixedClock(
+            std::time::Duration::from_secs(1_700_000_000),
+        )));
+        assert_eq!(
+            compute_expiry(&state, state.expiry_seconds).await.unwrap(),
+            1_700_000_005
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clock_skew_refuses_quotes() {
+        let monitor = Arc::new(ClockSkewMonitor::new(
+            std::time::Duration::from_millis(500),
+            true,
+        ));
+        monitor.record(2000);
+        let state = test_state().with_clock_skew_monitor(monitor);
+
+        let err = issue_quote(
+            &state,
+            PriceDirection::AsIs,
+            0,
+            5,
+            Address::ZERO,
+            None,
+            None,
+            None,
+        )
+        .await
+        .err()
+        .unwrap();
+        let response = err.into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"], "clock_skew");
+    }
+
+    #[test]
+    fn test_price_direction_aliases() {
+        let bridged_weth = Address::repeat_byte(0xee);
+        let usdc_e = Address::repeat_byte(0xcc);
+        let token_pair = TokenPairConfig::new(WETH, USDC)
+            .unwrap()
+            .with_base_aliases(vec![bridged_weth])
+            .with_quote_aliases(vec![usdc_e]);
+        let state = AppState::new(TEST_KEY, "00", 5, token_pair).unwrap();
+        let weth = Address::from_str(WETH).unwrap();
+        let usdc = Address::from_str(USDC).unwrap();
+
+        for (input, output) in [(usdc, bridged_weth), (usdc_e, weth), (usdc_e, bridged_weth)] {
+            assert_eq!(
+                state.price_direction(input, output).unwrap(),
+                PriceDirection::AsIs
+            );
+            assert_eq!(
+                state.price_direction(output, input).unwrap(),
+                PriceDirection::Inverted
+            );
+        }
+        assert!(state.price_direction(usdc_e, usdc).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_startup_checks() {
+        let state =
+            test_state().with_hermes(pyth::Hermes::default().with_base_url("http://127.0.0.1:1"));
+        state.check_signer().await.unwrap();
+        assert!(state.check_feeds().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_decimal_shift() {
+        let usdc = order::Io {
+            token: Address::from_str(USDC).unwrap(),
+            decimals: Some(6),
+            vault_id: B256::ZERO,
+        };
+        let weth = order::Io {
+            token: Address::from_str(WETH).unwrap(),
+            decimals: Some(18),
+            vault_id: B256::ZERO,
+        };
+        let state = |scale| {
+            let token_pair = TokenPairConfig::new(WETH, USDC)
+                .unwrap()
+                .with_decimals_scaling(scale);
+            AppState::new(TEST_KEY, "00", 5, token_pair).unwrap()
+        };
+
+        assert_eq!(state(false).decimal_shift(usdc, weth).await.unwrap(), 0);
+        let scaling = state(true);
+        assert_eq!(scaling.decimal_shift(usdc, weth).await.unwrap(), -12);
+        assert_eq!(scaling.decimal_shift(weth, usdc).await.unwrap(), 12);
+
+        // V4 orders don't declare decimals; without an RPC URL there's no way to look them up.
+        let undeclared = order::Io {
+            decimals: None,
+            ..usdc
+        };
+        let err = scaling.decimal_shift(undeclared, weth).await.unwrap_err();
+        assert!(matches!(
+            err,
+            AppError::BadRequest(OracleRequestError::UnknownTokenDecimals(token)) if token == usdc.token
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_token_decimals_is_a_bad_request() {
+        let token_pair = TokenPairConfig::new(WETH, USDC)
+            .unwrap()
+            .with_decimals_scaling(true)
+            .with_decimals(Some(18), None);
+        let state = Arc::new(AppState::new(TEST_KEY, "00", 5, token_pair).unwrap());
+        let body = order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+        let (status, json) = post_context(create_app(state), body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["error"], "unknown_token_decimals");
+        assert!(json["detail"]
+            .as_str()
+            .unwrap()
+            .contains(&Address::from_str(USDC).unwrap().to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_response_cache_skips_fetch_and_sign() {
+        let state = test_state().with_response_cache(std::time::Duration::from_secs(60));
+        let cached = oracle::OracleResponse {
+            quote_id: uuid::Uuid::new_v4(),
+            signer: state.signer_address(),
+            context: vec![],
+            signature: alloy::primitives::Bytes::from_static(b"sig"),
+            cosignatures: vec![],
+            nonce: None,
+            price: None,
+            debug: None,
+        };
+        let key = cache::CacheKey {
+            base_token: state.token_pair.base_token,
+            quote_token: state.token_pair.quote_token,
+            direction: PriceDirection::AsIs,
+            decimal_shift: 0,
+            expiry_seconds: 5,
+        };
+        state.response_cache.as_ref().unwrap().insert(
+            key,
+            cached.clone(),
+            std::time::Instant::now(),
+        );
+
+        // Feed "00" can't be fetched, so only a cache hit succeeds.
+        let quote = issue_quote(
+            &state,
+            PriceDirection::AsIs,
+            0,
+            5,
+            Address::ZERO,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(quote.quote_id, cached.quote_id);
+        assert!(issue_quote(
+            &state,
+            PriceDirection::Inverted,
+            0,
+            5,
+            Address::ZERO,
+            None,
+            None,
+            None
+        )
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hermes_backoff_is_shared() {
+        let rate_limited = Router::new().route(
+            "/v2/updates/price/latest",
+            get(|| async { (StatusCode::TOO_MANY_REQUESTS, [("retry-after", "30")]) }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, rate_limited).await.unwrap() });
+
+        let storage = Arc::new(storage::MemoryStorage::default());
+        let replica = |hermes: &str, now: u64| {
+            create_app(
+                test_state()
+                    .with_storage(storage.clone())
+                    .with_hermes(pyth::Hermes::default().with_base_url(hermes))
+                    .with_clock(Arc::new(clock::FixedClock(std::time::Duration::from_secs(
+                        now,
+                    )))),
+            )
+        };
+        let encoded = order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+        let (_, body) = post_context(
+            replica(&format!("http://{}", addr), 1700000000),
+            encoded.clone(),
+        )
+        .await;
+        assert_eq!(body["error"], "upstream_rate_limited");
+
+        // Another replica backs off too, without calling its (healthy) Hermes
+        let hermes = mock_hermes().await;
+        let response = replica(&hermes, 1700000010)
+            .oneshot(
+                Request::post("/context")
+                    .body(Body::from(encoded.clone()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()["retry-after"], "20");
+
+        let (status, _) = post_context(replica(&hermes, 1700000030), encoded).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_upstream_errors_map_to_unavailable() {
+        let err = anyhow::Error::from(pyth::HermesError::RateLimited {
+            retry_after: Some(std::time::Duration::from_millis(2500)),
+        });
+        let response = upstream_error(err).into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()["retry-after"], "3");
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"], "upstream_rate_limited");
+
+        let err = anyhow::Error::from(oracle::NonPositivePrice { price: 0, expo: -8 });
+        let response = upstream_error(err).into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"], "invalid_upstream_price");
+
+        let err = anyhow::Error::from(pyth::HermesError::Unverified("no quorum".into()));
+        let response = upstream_error(err).into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"], "unverified_upstream_price");
+
+        let response = upstream_error(anyhow::anyhow!("connection reset")).into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn test_timestamp_expiry() {
+        let now = std::time::Duration::from_millis(1_700_000_000_250);
+        let zero = std::time::Duration::ZERO;
+        assert_eq!(timestamp_expiry(now, 5, 30, zero, None), 1_700_000_005);
+        // The margin extends the expiry
+        let margin = std::time::Duration::from_millis(1750);
+        assert_eq!(timestamp_expiry(now, 5, 30, margin, None), 1_700_000_007);
+        // Aligned to the next 12s block boundary
+        assert_eq!(
+            timestamp_expiry(now, 5, 30, margin, Some(12)),
+            1_700_000_016
+        );
+        assert_eq!(timestamp_expiry(now, 5, 30, margin, Some(0)), 1_700_000_007);
+        // ...unless that would pass the maximum expiry plus the margin
+        assert_eq!(timestamp_expiry(now, 5, 5, margin, Some(12)), 1_700_000_007);
+        assert_eq!(timestamp_expiry(now, 5, 8, margin, Some(12)), 1_700_000_010);
+    }
+
+    #[test]
+    fn test_effective_expiry_seconds() {
+        let state = test_state().with_max_expiry_seconds(30);
+        assert_eq!(state.effective_expiry_seconds(None), 5);
+        assert_eq!(state.effective_expiry_seconds(Some(2)), 2);
+        assert_eq!(state.effective_expiry_seconds(Some(20)), 20);
+        assert_eq!(state.effective_expiry_seconds(Some(3600)), 30);
+        assert_eq!(state.effective_expiry_seconds(Some(0)), 1);
+    }
+
+    #[tokio::test]
+    async fn test_post_invalid_expiry_query() {
+        let request = Request::post("/context?expiry_seconds=soon")
+            .body(Body::from(vec![0u8; 32]))
+            .unwrap();
+        let (status, body) = send(test_app(), request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_query");
+    }
+
+    #[tokio::test]
+    async fn test_debug_float_requires_one_param() {
+        let request = Request::get("/debug/float").body(Body::empty()).unwrap();
+        let (status, body) = send(test_app(), request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_query");
+    }
+
+    #[tokio::test]
+    async fn test_openapi_lists_routes() {
+        let request = Request::get("/openapi.json").body(Body::empty()).unwrap();
+        let (status, body) = send(test_app(), request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["paths"]["/context"]["post"].is_object());
+        assert!(body["paths"]["/debug/float"]["get"].is_object());
+        assert!(body["paths"]["/status"]["get"].is_object());
+        assert!(body["components"]["schemas"]["ErrorResponse"].is_object());
+        assert!(body["components"]["schemas"]["OracleResponse"].is_object());
+    }
+
+    #[tokio::test]
+    async fn test_version() {
+        let request = Request::get("/version").body(Body::empty()).unwrap();
+        let (status, body) = send(test_app(), request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(body["git_commit"], version::GIT_COMMIT);
+        assert!(body["build_timestamp"]
+            .as_str()
+            .unwrap()
+            .parse::<chrono::DateTime<chrono::Utc>>()
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_compresses_json_over_http2() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, test_app()).await.unwrap() });
+
+        let client = reqwest::Client::builder()
+            .http2_prior_knowledge()
+            .build()
+            .unwrap();
+        let url = format!("http://{}/openapi.json", addr);
+        let response = client
+            .get(&url)
+            .header("accept-encoding", "gzip")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.version(), reqwest::Version::HTTP_2);
+        assert_eq!(response.headers()["content-encoding"], "gzip");
+
+        let response = client.get(&url).send().await.unwrap();
+        assert!(response.headers().get("content-encoding").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_price_at_rejects_bad_requests() {
+        let other = "0x0000000000000000000000000000000000000001";
+        let uri = format!("/price/{}-{}/at?timestamp=1700000000", other, USDC);
+        let (status, body) = send(test_app(), Request::get(uri).body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "unsupported_token_pair");
+
+        let future = chrono::Utc::now().timestamp() + 3600;
+        let uri = format!("/price/{}-{}/at?timestamp={}", USDC, WETH, future);
+        let (status, body) = send(test_app(), Request::get(uri).body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_query");
+
+        let uri = format!("/price/{}-{}/at", WETH, USDC);
+        let (status, body) = send(test_app(), Request::get(uri).body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_query");
+    }
+
+    #[tokio::test]
+    async fn test_price_etag() {
+        let state = priced_state().await;
+        let app = create_app(state);
+        let uri = format!("/price/{}-{}", WETH, USDC);
+
+        let response = app
+            .clone()
+            .oneshot(Request::get(&uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers()["etag"].clone();
+        assert_eq!(etag, "\"1700000000-as_is\"");
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["price"], "3100.12345678");
+
+        let request = Request::get(&uri)
+            .header("if-none-match", etag)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert!(response
+            .into_body()
+            .collect()
+            .await
+            .unwrap()
+            .to_bytes()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_price_history() {
+        let state = priced_state().await.with_clock(Arc::new(clock::FixedClock(
+            std::time::Duration::from_secs(1700000030),
+        )));
+        let app = create_app(state);
+        let history = format!("/price/{}-{}/history", USDC, WETH);
+
+        let (status, body) = send(
+            app.clone(),
+            Request::get(&history).body(Body::empty()).unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["samples"], serde_json::json!([]));
+
+        // Fetching the price records it
+        let uri = format!("/price/{}-{}", WETH, USDC);
+        let (status, _) = send(app.clone(), Request::get(uri).body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, body) = send(
+            app.clone(),
+            Request::get(&history).body(Body::empty()).unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["direction"], "inverted");
+        let samples = body["samples"].as_array().unwrap();
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0]["publish_time"], "2023-11-14T22:13:20Z");
+
+        let uri = format!("{}?seconds=10", history);
+        let (_, body) = send(app.clone(), Request::get(uri).body(Body::empty()).unwrap()).await;
+        assert_eq!(body["samples"], serde_json::json!([]));
+
+        let uri = format!("{}?seconds=0", history);
+        let (status, body) = send(app, Request::get(uri).body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_query");
+    }
+
+    #[tokio::test]
+    async fn test_client_verifies_context() {
+        let state = priced_state().await;
+        let signer = state.signer_address();
+        let body = rain_oracle_client::encode_minimal_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+        let (status, json) = post_context(create_app(state), body).await;
+        assert_eq!(status, StatusCode::OK);
+        let response: rain_oracle_client::SignedContext = serde_json::from_value(json).unwrap();
+        let packed = rain_oracle_client::HashScheme::Packed;
+        assert_eq!(response.verify(&[signer], packed).unwrap(), signer);
+        assert!(response.verify(&[Address::ZERO], packed).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_display_decimals() {
+        let token_pair = TokenPairConfig::new(WETH, USDC)
+            .unwrap()
+            .with_display_decimals(Some(6));
+        let state = Arc::new(
+            AppState::new(TEST_KEY, "00", 5, token_pair)
+                .unwrap()
+                .with_hermes(pyth::Hermes::default().with_base_url(&mock_hermes().await)),
+        );
+        let app = create_app(state.clone());
+        let body = order::encode_request(
+            Address::from_str(WETH).unwrap(),
+            Address::from_str(USDC).unwrap(),
+            Address::ZERO,
+        );
+        let (status, json) = post_context(app, body).await;
+        assert_eq!(status, StatusCode::OK);
+        let response: oracle::OracleResponse = serde_json::from_value(json).unwrap();
+        let price = response.price.unwrap();
+        // 1 / 3100.12345678, however the Rain float formats it
+        assert_eq!(price.decimal, "0.000323");
+        let signed = rain_math_float::Float::from(B256::from(response.context[0]));
+        assert_eq!(price.signed, signed.format().unwrap());
+        assert!(state.metrics.render().contains(&format!(
+            "oracle_signed_price{{pair=\"{}/{}\",direction=\"inverted\"}} 0.000323",
+            Address::from_str(WETH).unwrap(),
+            Address::from_str(USDC).unwrap()
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_context_hash_scheme() {
+        // Anvil's second dev key
+        const COSIGNER_KEY: &str =
+            "59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+        let scheme = sign::HashScheme::parse("tagged", Some("rain.oracle.v1")).unwrap();
+        let state = priced_state()
+            .await
+            .with_cosigner(Signer::new(COSIGNER_KEY).unwrap())
+            .with_hash_scheme(scheme);
+        state.check_signer().await.unwrap();
+        let app = create_app(state);
+        let body = order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+        let (status, json) = post_context(app.clone(), body).await;
+        assert_eq!(status, StatusCode::OK);
+        let response: oracle::OracleResponse = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            scheme
+                .recover_signer(&response.context, &response.signature)
+                .unwrap(),
+            response.signer
+        );
+        let cosignature = &response.cosignatures[0];
+        assert_eq!(
+            scheme
+                .recover_signer(&response.context, &cosignature.signature)
+                .unwrap(),
+            cosignature.signer
+        );
+        // Not what a packed verifier would recover
+        assert_ne!(
+            sign::recover_context_signer(&response.context, &response.signature).unwrap(),
+            response.signer
+        );
+
+        let request = Request::get("/signer").body(Body::empty()).unwrap();
+        let (_, body) = send(app, request).await;
+        assert_eq!(body["hash_scheme"], "tagged");
+        assert_eq!(
+            body["hash_tag"],
+            serde_json::to_value(scheme.tag().unwrap()).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_context_cbor() {
+        let state = priced_state().await;
+        let app = create_app(state);
+        let body = order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+        let request = |accept: &str| {
+            Request::post("/context")
+                .header("accept", accept)
+                .body(Body::from(body.clone()))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(request(encoding::CBOR)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], encoding::CBOR);
+        assert_eq!(response.headers()["vary"], "accept");
+        let cbor = response.into_body().collect().await.unwrap().to_bytes();
+        let decoded: oracle::OracleResponse = ciborium::from_reader(cbor.as_ref()).unwrap();
+        assert_eq!(decoded.context.len(), 2);
+        assert_eq!(
+            sign::recover_context_signer(&decoded.context, &decoded.signature).unwrap(),
+            decoded.signer
+        );
+
+        // Same response is smaller than its JSON encoding
+        let response = app.oneshot(request("application/json")).await.unwrap();
+        assert_eq!(response.headers()["content-type"], "application/json");
+        let json = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(cbor.len() < json.len());
+    }
+
+    #[tokio::test]
+    async fn test_json_rpc() {
+        // Off by default
+        let response = test_app()
+            .oneshot(Request::post("/rpc").body(Body::from("{}")).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let state = priced_state().await.with_json_rpc(true);
+        let app = create_app(state);
+        let order = alloy::hex::encode_prefixed(order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        ));
+        let batch = serde_json::json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "oracle_getSignedContext", "params": [order]},
+            {"jsonrpc": "2.0", "id": 2, "method": "oracle_getPrice", "params": [format!("{}-{}", WETH, USDC)]},
+            {"jsonrpc": "2.0", "id": 3, "method": "oracle_listPairs"},
+            {"jsonrpc": "2.0", "id": 4, "method": "oracle_getSignedContext", "params": ["0x00"]},
+            {"jsonrpc": "2.0", "id": 5, "method": "eth_chainId"},
+            {"jsonrpc": "2.0", "method": "oracle_listPairs"},
+        ]);
+        let request = Request::post("/rpc")
+            .body(Body::from(batch.to_string()))
+            .unwrap();
+        let (status, body) = send(app.clone(), request).await;
+        assert_eq!(status, StatusCode::OK);
+        // The notification gets no response
+        assert_eq!(body.as_array().unwrap().len(), 5);
+
+        assert_eq!(body[0]["id"], 1);
+        assert!(body[0]["result"]["signature"].is_string());
+        assert_eq!(body[1]["result"]["price"], "3100.12345678");
+        assert_eq!(
+            body[2]["result"][0]["base_token"],
+            WETH.to_lowercase().as_str()
+        );
+        assert_eq!(body[3]["error"]["code"], -32602);
+        assert_eq!(body[3]["error"]["data"]["error"], "invalid_body");
+        assert_eq!(body[4]["error"]["code"], -32601);
+
+        let request = Request::post("/rpc").body(Body::from("{")).unwrap();
+        let (status, body) = send(app, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["error"]["code"], -32700);
+        assert!(body["id"].is_null());
+    }
+
+    /// Hermes stand-in serving a fixed ETH/USD price.
+    async fn mock_hermes() -> String {
+        let app = Router::new()
+            .route(
+                "/v2/updates/price/latest",
+                get(|| async {
+                    Json(serde_json::json!({
+                        "parsed": [{ "price": { "price": "310012345678", "expo": -8, "publish_time": 1700000000 } }]
+                    }))
+                }),
+            )
+            .route(
+                "/gas",
+                get(|| async { Json(serde_json::json!({ "gasPrice": "20" })) }),
+            )
+            .route(
+                "/ticker",
+                get(|| async { Json(serde_json::json!({ "price": "3090.00" })) }),
+            )
+            .route(
+                "/funding",
+                get(|| async { Json(serde_json::json!({ "lastFundingRate": "0.0001" })) }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    /// JSON-RPC endpoint answering `symbol()` and `decimals()` for WETH (18) and USDC (6).
+    async fn mock_token_rpc() -> String {
+        let app = Router::new().route(
+            "/",
+            axum::routing::post(|Json(request): Json<serde_json::Value>| async move {
+                let call = &request["params"][0];
+                let token = call["to"].as_str().unwrap().to_lowercase();
+                let (symbol, decimals) = if token == WETH.to_lowercase() {
+                    ("WETH", 18)
+                } else {
+                    ("USDC", 6)
+                };
+                let result = match call["data"].as_str().unwrap() {
+                    "0x313ce567" => format!("0x{:064x}", decimals),
+                    _ => format!("0x{:0<64}", hex::encode(symbol)),
+                };
+                Json(serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": result }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_check_tokens_detects_decimals_mismatch() {
+        let rpc_url = mock_token_rpc().await;
+        let state = |quote_decimals| {
+            let token_pair = TokenPairConfig::new(WETH, USDC)
+                .unwrap()
+                .with_decimals(Some(18), Some(quote_decimals));
+            AppState::new(TEST_KEY, "00", 5, token_pair)
+                .unwrap()
+                .with_rpc_url(&rpc_url)
+        };
+
+        let err = state(18).check_tokens().await.unwrap_err();
+        let err = err.downcast_ref::<DecimalsMismatch>().unwrap();
+        assert_eq!(err.symbol, "USDC");
+        assert_eq!((err.configured, err.actual), (18, 6));
+
+        let state = state(6);
+        state.check_tokens().await.unwrap();
+        let usdc = Address::from_str(USDC).unwrap();
+        assert_eq!(state.token_decimals.lock().unwrap().get(&usdc), Some(&6));
+    }
+
+    #[tokio::test]
+    async fn test_gas_compensation_widens_price() {
+        let base_url = mock_hermes().await;
+        let gas = gas::GasCompensation::new(
+            gas::GasPriceSource::Oracle {
+                url: format!("{}/gas", base_url),
+                pointer: "/gasPrice".to_string(),
+            },
+            150_000,
+            "1000",
+        )
+        .unwrap();
+        let state = test_state()
+            .with_hermes(pyth::Hermes::default().with_base_url(&base_url))
+            .with_gas_compensation(gas);
+
+        let priced = price_context(&state, PriceDirection::AsIs, 0, 5)
+            .await
+            .unwrap();
+        let price: f64 =
+            rain_math_float::Float::from(alloy::primitives::B256::from(priced.context[0]))
+                .format()
+                .unwrap()
+                .parse()
+                .unwrap();
+        // 150k gas at 20 gwei = 0.003 ETH, charged against a 1000 USDC fill
+        let mid = 3100.12345678;
+        assert!((price - mid * (1.0 + 0.003 * mid / 1000.0)).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_reference_price_deviation_pauses_pair() {
+        let base_url = mock_hermes().await;
+        let state = |url: &str, max_deviation_percent| {
+            test_state()
+                .with_hermes(pyth::Hermes::default().with_base_url(&base_url))
+                .with_reference_price(
+                    reference::ReferencePrice::new(url, "/price", max_deviation_percent).unwrap(),
+                )
+        };
+        let ticker = format!("{}/ticker", base_url);
+
+        // 3100.12 against 3090 is a 0.33% deviation
+        let priced = price_context(&state(&ticker, 1.0), PriceDirection::AsIs, 0, 5)
+            .await
+            .unwrap();
+        assert!(priced
+            .explanation
+            .guards
+            .contains(&"reference_deviation".to_string()));
+
+        match price_context(&state(&ticker, 0.1), PriceDirection::AsIs, 0, 5).await {
+            Err(err @ AppError::Unavailable(UnavailableError::PriceGuard(_))) => {
+                assert_eq!(err.error_code(), "reference_deviation");
+                assert_eq!(err.guard().as_deref(), Some("reference_deviation"));
+            }
+            other => panic!("expected reference deviation, got {:?}", other.err()),
+        }
+        match price_context(
+            &state("http://127.0.0.1:1", 1.0),
+            PriceDirection::AsIs,
+            0,
+            5,
+        )
+        .await
+        {
+            Err(err @ AppError::Unavailable(UnavailableError::PriceGuard(_))) => {
+                assert_eq!(err.error_code(), "reference_unavailable")
+            }
+            other => panic!("expected reference unavailable, got {:?}", other.err()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pricing_guard_chain_names_rejecting_guard() {
+        let state = priced_state()
+            .await
+            .with_clock(Arc::new(clock::FixedClock(std::time::Duration::from_secs(
+                1700000030,
+            ))))
+            .with_pricing_guard(guard::Staleness::new(std::time::Duration::from_secs(60)).unwrap())
+            .with_pricing_guard(guard::PriceBounds::new(None, Some(3000.0)).unwrap());
+        let app = create_app(state);
+        let encoded = order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+
+        // The price is fresh enough, but above the maximum
+        let (status, body) = post_context(app, encoded).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["error"], "price_guard_rejected");
+        assert_eq!(body["guard"], "bounds");
+        assert_eq!(
+            body["detail"],
+            "Feed price 3100.12345678 is above the maximum 3000; pair paused"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key_replays_response() {
+        let state = Arc::new(
+            priced_state()
+                .await
+                .with_idempotency(std::time::Duration::from_secs(60)),
+        );
+        let app = create_app(state);
+        let body = order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+        let request = |uri: &str, key: &str| {
+            Request::post(uri)
+                .header(idempotency::IDEMPOTENCY_KEY_HEADER, key)
+                .body(Body::from(body.clone()))
+                .unwrap()
+        };
+
+        let first = app
+            .clone()
+            .oneshot(request("/context", "retry-1"))
+            .await
+            .unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        assert!(first
+            .headers()
+            .get(idempotency::IDEMPOTENT_REPLAYED_HEADER)
+            .is_none());
+        let first = first.into_body().collect().await.unwrap().to_bytes();
+
+        let retry = app
+            .clone()
+            .oneshot(request("/context", "retry-1"))
+            .await
+            .unwrap();
+        assert_eq!(
+            retry.headers()[idempotency::IDEMPOTENT_REPLAYED_HEADER],
+            "true"
+        );
+        assert_eq!(retry.into_body().collect().await.unwrap().to_bytes(), first);
+
+        let (_, other) = send(app.clone(), request("/context", "retry-2")).await;
+        let first: serde_json::Value = serde_json::from_slice(&first).unwrap();
+        assert_ne!(other["quote_id"], first["quote_id"]);
+
+        let (status, body) = send(app, request("/context?expiry_seconds=30", "retry-1")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "idempotency_key_reused");
+    }
+
+    #[tokio::test]
+    async fn test_removed_order_is_refused() {
+        let state = Arc::new(test_state());
+        let app = create_app(state.clone());
+        let body = order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+        let order_hash = order::decode_request(&body, None).unwrap().order.hash;
+        state.removed_orders.insert(order_hash);
+
+        let (status, body) = post_context(app, body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "order_removed");
+    }
+
+    #[tokio::test]
+    async fn test_order_nonce_reused() {
+        use storage::Storage;
+
+        let storage = Arc::new(storage::MemoryStorage::default());
+        let state = priced_state()
+            .await
+            .with_storage(storage.clone())
+            .with_clock(Arc::new(clock::FixedClock(std::time::Duration::from_secs(
+                1700000030,
+            ))))
+            .with_order_nonce_window(std::time::Duration::from_secs(60));
+        let app = create_app(Arc::new(state));
+        let body = order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+        let order = order::decode_request(&body, None).unwrap().order;
+        let other_expression = |seen_at| storage::NonceUse {
+            bytecode_hash: B256::repeat_byte(1),
+            seen_at,
+        };
+        let last_use = || storage.order_nonce(order.owner, order.nonce);
+
+        // Another owner's use of the nonce doesn't matter
+        storage
+            .record_order_nonce(
+                Address::repeat_byte(1),
+                order.nonce,
+                &other_expression(1700000000),
+            )
+            .await
+            .unwrap();
+        // Refused requests and dry runs don't record a use
+        let (status, _) = post_context(app.clone(), vec![0u8; 4]).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let request = Request::post("/context/dry-run")
+            .body(Body::from(body.clone()))
+            .unwrap();
+        let (status, _) = send(app.clone(), request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(last_use().await.unwrap(), None);
+
+        // The owner used the nonce with another expression 30s ago
+        storage
+            .record_order_nonce(order.owner, order.nonce, &other_expression(1700000000))
+            .await
+            .unwrap();
+        let (status, response) = post_context(app.clone(), body.clone()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(response["error"], "order_nonce_reused");
+        assert_eq!(
+            last_use().await.unwrap(),
+            Some(other_expression(1700000000))
+        );
+
+        // ...or a full window ago
+        storage
+            .record_order_nonce(order.owner, order.nonce, &other_expression(1699999970))
+            .await
+            .unwrap();
+        let (status, _) = post_context(app.clone(), body.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        let used = last_use().await.unwrap().unwrap();
+        assert_eq!(used.bytecode_hash, order.bytecode_hash);
+        assert_eq!(used.seen_at, 1700000030);
+
+        // The same order can be quoted again
+        let (status, _) = post_context(app, body).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_quote_stream_scales_by_decimals() {
+        let token_pair = TokenPairConfig::new(WETH, USDC)
+            .unwrap()
+            .with_decimals_scaling(true)
+            .with_decimals(Some(18), Some(6));
+        let state = Arc::new(
+            AppState::new(TEST_KEY, "00", 5, token_pair)
+                .unwrap()
+                .with_hermes(pyth::Hermes::default().with_base_url(&mock_hermes().await))
+                .with_quote_stream(
+                    stream::QuoteStream::new(std::time::Duration::from_millis(100)).unwrap(),
+                ),
+        );
+        let quote_stream = state.quote_stream.as_ref().unwrap();
+        let app = create_app(state.clone());
+        for (direction, input, output) in [
+            (PriceDirection::AsIs, USDC, WETH),
+            (PriceDirection::Inverted, WETH, USDC),
+        ] {
+            let (_, mut events) = quote_stream.subscribe(direction);
+            quote_stream.refresh(&state, direction).await;
+            let event = events.try_recv().unwrap();
+            assert_eq!(event.kind, "quote");
+            let streamed: oracle::OracleResponse = serde_json::from_str(&event.data).unwrap();
+
+            let body = order::encode_request(
+                Address::from_str(input).unwrap(),
+                Address::from_str(output).unwrap(),
+                Address::ZERO,
+            );
+            let (status, json) = post_context(app.clone(), body).await;
+            assert_eq!(status, StatusCode::OK);
+            let quoted: oracle::OracleResponse = serde_json::from_value(json).unwrap();
+            assert_eq!(streamed.context[0], quoted.context[0]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quote_stream_signs_once_for_every_subscriber() {
+        use storage::Storage;
+
+        let hermes = mock_hermes().await;
+        let storage = Arc::new(storage::MemoryStorage::default());
+        let state = test_state()
+            .with_hermes(pyth::Hermes::default().with_base_url(&hermes))
+            .with_storage(storage.clone())
+            .with_quote_stream(
+                stream::QuoteStream::new(std::time::Duration::from_millis(100)).unwrap(),
+            );
+        let quote_stream = state.quote_stream.as_ref().unwrap();
+        let signed = || async {
+            storage
+                .quotes(&storage::QuoteFilter::default())
+                .await
+                .unwrap()
+                .len()
+        };
+
+        // Nothing is signed without subscribers
+        quote_stream.refresh(&state, PriceDirection::AsIs).await;
+        assert_eq!(signed().await, 0);
+
+        let (_, mut first) = quote_stream.subscribe(PriceDirection::AsIs);
+        let (_, mut second) = quote_stream.subscribe(PriceDirection::AsIs);
+        quote_stream.refresh(&state, PriceDirection::AsIs).await;
+        let event = first.try_recv().unwrap();
+        assert_eq!(event.kind, "quote");
+        assert_eq!(second.try_recv().unwrap(), event);
+        let response: oracle::OracleResponse = serde_json::from_str(&event.data).unwrap();
+        assert_eq!(
+            sign::recover_context_signer(&response.context, &response.signature).unwrap(),
+            response.signer
+        );
+
+        // The price hasn't moved and the quote is fresh, so it isn't signed again
+        quote_stream.refresh(&state, PriceDirection::AsIs).await;
+        assert!(first.try_recv().is_err());
+        assert_eq!(signed().await, 1);
+        let (latest, _) = quote_stream.subscribe(PriceDirection::AsIs);
+        assert_eq!(latest, Some(event));
+
+        let app = create_app(state);
+        let response = app
+            .oneshot(
+                Request::get("/stream?direction=inverted")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[axum::http::header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+        // Not served unless enabled
+        let response = test_app()
+            .oneshot(Request::get("/stream").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_taker_id_is_recorded_and_verified() {
+        use storage::Storage;
+
+        let hermes = mock_hermes().await;
+        let storage = Arc::new(storage::MemoryStorage::default());
+        let state = test_state()
+            .with_hermes(pyth::Hermes::default().with_base_url(&hermes))
+            .with_storage(storage.clone());
+        let app = create_app(state);
+        let body = order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+        let request = |taker: &str, api_key: Option<&str>| {
+            let mut request = Request::post("/context").header(taker::TAKER_ID_HEADER, taker);
+            if let Some(api_key) = api_key {
+                request = request.header("x-api-key", api_key);
+            }
+            request.body(Body::from(body.clone())).unwrap()
+        };
+
+        // Without auth any well-formed ID is accepted as a label
+        let (status, body) = send(app.clone(), request("desk-1", None)).await;
+        assert_eq!(status, StatusCode::OK);
+        let quote_id = uuid::Uuid::parse_str(body["quote_id"].as_str().unwrap()).unwrap();
+        let record = storage.quote(quote_id).await.unwrap().unwrap();
+        assert_eq!(record.taker_id.as_deref(), Some("desk-1"));
+        let response = app
+            .clone()
+            .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let text = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(
+            text.contains("taker=\"desk-1\",error=\"none\"} 1"),
+            "{}",
+            text
+        );
+        for stage in ["decode", "price_fetch", "pricing", "signing", "recording"] {
+            assert!(text.contains(&format!(
+                "oracle_request_stage_duration_seconds_count{{stage=\"{}\"}} 1",
+                stage
+            )));
+        }
+
+        let (status, body) = send(app, request("desk 1", None)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_taker_id");
+
+        // With auth it must be the counterparty or an authenticated subject
+        let state = test_state()
+            .with_hermes(pyth::Hermes::default().with_base_url(&hermes))
+            .with_quotas(quota::Quotas::new([quota::ApiKey {
+                name: "mm".to_string(),
+                key: "sk_test".to_string(),
+                daily_quota: None,
+            }]));
+        let app = create_app(state);
+        let (status, body) = send(app.clone(), request("desk-1", Some("sk_test"))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "taker_id_mismatch");
+        let (status, _) = send(app.clone(), request("mm", Some("sk_test"))).await;
+        assert_eq!(status, StatusCode::OK);
+        let counterparty = Address::ZERO.to_string();
+        let (status, _) = send(app, request(&counterparty, Some("sk_test"))).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_reports_checks_without_signing() {
+        let state = Arc::new(priced_state().await);
+        let (weth, usdc) = (
+            Address::from_str(WETH).unwrap(),
+            Address::from_str(USDC).unwrap(),
+        );
+
+        let body = order::encode_request(usdc, weth, Address::ZERO);
+        let request = Request::post("/context/dry-run")
+            .body(Body::from(body))
+            .unwrap();
+        let (status, body) = send(create_app(state.clone()), request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["would_sign"], true);
+        assert_eq!(body["checks"][4]["name"], "price");
+        assert_eq!(body["checks"][4]["status"], "passed");
+        assert_eq!(body["context"].as_array().unwrap().len(), 2);
+        assert_eq!(body["debug"]["price"], "3100.12345678");
+        assert!(body.get("signature").is_none());
+        // Nothing was signed, so nothing was recorded
+        assert!(state
+            .storage
+            .quotes(&storage::QuoteFilter::default())
+            .await
+            .unwrap()
+            .is_empty());
+
+        let body = order::encode_request(usdc, usdc, Address::ZERO);
+        let request = Request::post("/context/dry-run")
+            .body(Body::from(body))
+            .unwrap();
+        let (status, body) = send(create_app(state), request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["would_sign"], false);
+        assert_eq!(body["checks"][0]["status"], "failed");
+        assert_eq!(body["checks"][0]["error"], "unsupported_token_pair");
+        assert_eq!(body["checks"][2]["status"], "passed");
+        assert_eq!(body["checks"][4]["status"], "skipped");
+        assert!(body.get("context").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_quote_explanation() {
+        let token_pair = TokenPairConfig::new(WETH, USDC)
+            .unwrap()
+            .with_pipeline("[invert?, mul 0.5]".parse().unwrap());
+        let state = Arc::new(
+            AppState::new(TEST_KEY, "00", 5, token_pair)
+                .unwrap()
+                .with_hermes(pyth::Hermes::default().with_base_url(&mock_hermes().await)),
+        );
+        let body = order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+
+        let request = Request::post("/context")
+            .body(Body::from(body.clone()))
+            .unwrap();
+        let (status, response) = send(create_app(state.clone()), request).await;
+        assert_eq!(status, StatusCode::OK);
+        let quote_id = uuid::Uuid::parse_str(response["quote_id"].as_str().unwrap()).unwrap();
+        let explanation = state
+            .storage
+            .quote(quote_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .explanation
+            .unwrap();
+        assert_eq!(explanation.sources[0].price, "3100.12345678");
+        assert_eq!(explanation.aggregate, "3100.12345678");
+        let steps: Vec<_> = explanation
+            .steps
+            .iter()
+            .map(|s| (s.step.as_str(), s.price.as_str()))
+            .collect();
+        assert_eq!(
+            steps,
+            [("invert?", "3100.12345678"), ("mul 0.5", "1550.06172839")]
+        );
+        assert_eq!(explanation.guards, ["warm_up", "positive_price"]);
+
+        // Only on request in dry runs
+        let request = Request::post("/context/dry-run")
+            .body(Body::from(body.clone()))
+            .unwrap();
+        let (_, response) = send(create_app(state.clone()), request).await;
+        assert!(response.get("explanation").is_none());
+        let request = Request::post("/context/dry-run?explain=true")
+            .body(Body::from(body))
+            .unwrap();
+        let (_, response) = send(create_app(state), request).await;
+        assert_eq!(response["explanation"]["steps"][1]["step"], "mul 0.5");
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_gates_readyz_and_context() {
+        let state = Arc::new(priced_state().await.with_warm_up());
+        let readyz = || Request::get("/readyz").body(Body::empty()).unwrap();
+
+        let (status, body) = send(create_app(state.clone()), readyz()).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["error"], "warming_up");
+        let weth = Address::from_str(WETH).unwrap();
+        let usdc = Address::from_str(USDC).unwrap();
+        let (status, body) = post_context(
+            create_app(state.clone()),
+            order::encode_request(usdc, weth, Address::ZERO),
+        )
+        .await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["error"], "warming_up");
+
+        state
+            .warm_up(std::time::Duration::from_secs(5), false)
+            .await;
+        let response = create_app(state).oneshot(readyz()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_timeout() {
+        let state = || {
+            test_state()
+                .with_hermes(pyth::Hermes::default().with_base_url("http://127.0.0.1:1"))
+                .with_warm_up()
+        };
+        let timeout = std::time::Duration::from_millis(100);
+
+        // Without a successful fetch it stays not ready, still retrying
+        let strict = state();
+        let waited = std::time::Duration::from_millis(500);
+        assert!(tokio::time::timeout(waited, strict.warm_up(timeout, false))
+            .await
+            .is_err());
+        assert!(!strict.is_ready());
+
+        let degraded = state();
+        degraded.warm_up(timeout, true).await;
+        assert!(degraded.is_ready());
+    }
+
+    #[tokio::test]
+    async fn test_metrics_record_context_errors() {
+        let state = Arc::new(test_state());
+        let app = create_app(state.clone());
+        post_context(app.clone(), vec![1u8, 2, 3]).await;
+
+        let response = app
+            .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let text = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(text.contains("error=\"invalid_body\"} 1"), "{}", text);
+    }
+
+    #[tokio::test]
+    async fn test_inventory_throttle_stops_overselling_vault() {
+        // The output vault holds 2.5 tokens
+        let rpc = Router::new().route(
+            "/",
+            axum::routing::post(|| async {
+                let balance: B256 = rain_math_float::Float::parse("2.5".to_string())
+                    .unwrap()
+                    .into();
+                Json(serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": balance }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rpc_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, rpc).await.unwrap() });
+
+        let app = create_app(
+            priced_state()
+                .await
+                .with_response_cache(std::time::Duration::from_secs(60))
+                .with_expiry_safety_margin(std::time::Duration::from_secs(10))
+                .with_inventory_throttle(
+                    inventory::InventoryThrottle::new(&rpc_url, Address::ZERO, 1.0).unwrap(),
+                ),
+        );
+        let body = order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+
+        // Identical requests aren't served from the response cache, so each one counts
+        for _ in 0..2 {
+            let (status, _) = post_context(app.clone(), body.clone()).await;
+            assert_eq!(status, StatusCode::OK);
+        }
+        let request = Request::post("/context/all")
+            .body(Body::from(body.clone()))
+            .unwrap();
+        let (status, error) = send(app.clone(), request).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(error["error"], "inventory_exhausted");
+        let response = app
+            .clone()
+            .oneshot(
+                Request::post("/context")
+                    .body(Body::from(body.clone()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(response.headers().contains_key("retry-after"));
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let error: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(error["error"], "inventory_exhausted");
+
+        // Another order paying from the same vault is counted against it, one paying from
+        // another vault isn't
+        let reorder = |change: fn(&mut OrderV4)| {
+            let (mut order, input, output, counterparty) = <(
+                OrderV4,
+                alloy::primitives::U256,
+                alloy::primitives::U256,
+                Address,
+            )>::abi_decode(&body)
+            .unwrap();
+            change(&mut order);
+            (order, input, output, counterparty).abi_encode()
+        };
+        let same_vault = reorder(|order| order.nonce = B256::repeat_byte(1));
+        let (status, error) = post_context(app.clone(), same_vault).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(error["error"], "inventory_exhausted");
+        let other_vault = reorder(|order| order.validOutputs[0].vaultId = B256::repeat_byte(1));
+        let (status, _) = post_context(app, other_vault).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_market_closed_outside_schedule() {
+        let hermes = mock_hermes().await;
+        let app = |schedule: &str| {
+            create_app(
+                test_state()
+                    .with_hermes(pyth::Hermes::default().with_base_url(&hermes))
+                    // Tuesday 2023-11-14 22:13:20 UTC
+                    .with_clock(Arc::new(clock::FixedClock(std::time::Duration::from_secs(
+                        1_700_000_000,
+                    ))))
+                    .with_schedule(schedule.parse().unwrap()),
+            )
+        };
+        let body = order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+
+        let response = app("mon-fri 13:30-20:00")
+            .oneshot(
+                Request::post("/context")
+                    .body(Body::from(body.clone()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        // Until Wednesday 13:30
+        assert_eq!(response.headers()["retry-after"], "55000");
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let error: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(error["error"], "market_closed");
+
+        let (status, _) = post_context(app("mon-fri 22:00-23:00"), body).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_reset_needs_second_admin() {
+        let approvals = approval::Approvals::new(
+            ["alice:ka".parse().unwrap(), "bob:kb".parse().unwrap()],
+            std::time::Duration::from_secs(60),
+        )
+        .unwrap();
+        let state = test_state()
+            .with_daily_quote_limit(1)
+            .with_approvals(approvals);
+        let app = create_app(state);
+        let admin = |uri: String, key: &str| {
+            Request::post(uri)
+                .header(approval::ADMIN_KEY_HEADER, key)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let (status, _) = send(
+            app.clone(),
+            Request::post("/limits/reset").body(Body::empty()).unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, change) = send(app.clone(), admin("/limits/reset".to_string(), "ka")).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(change["action"], "reset_daily_limit");
+        assert_eq!(change["staged_by"], "alice");
+
+        let approve = format!("/admin/changes/{}/approve", change["id"].as_str().unwrap());
+        let (status, body) = send(app.clone(), admin(approve.clone(), "ka")).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["error"], "self_approval");
+        let (status, _) = send(app.clone(), admin(approve.clone(), "kb")).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send(app, admin(approve, "kb")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_admin_routes_are_separate() {
+        let state = test_state();
+        let (public, admin) = create_apps(state);
+
+        let status = |app: Router| async move {
+            app.oneshot(Request::get("/status").body(Body::empty()).unwrap())
+                .await
+                .unwrap()
+                .status()
+        };
+        assert_eq!(status(public.clone()).await, StatusCode::NOT_FOUND);
+        assert_eq!(status(admin.clone()).await, StatusCode::OK);
+
+        let context = |app: Router| async move {
+            app.oneshot(Request::post("/context").body(Body::empty()).unwrap())
+                .await
+                .unwrap()
+                .status()
+        };
+        assert_eq!(context(public).await, StatusCode::BAD_REQUEST);
+        assert_eq!(context(admin).await, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_shared_listener_needs_two_person_rule_to_reset() {
+        let state = Arc::new(test_state().with_daily_quote_limit(1));
+        let reset = |app: Router| async move {
+            app.oneshot(Request::post("/limits/reset").body(Body::empty()).unwrap())
+                .await
+                .unwrap()
+                .status()
+        };
+
+        assert_eq!(
+            reset(create_app(state.clone())).await,
+            StatusCode::NOT_FOUND
+        );
+        let (_, admin) = create_apps(state);
+        assert_eq!(reset(admin).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_status_reports_config() {
+        let request = Request::get("/status").body(Body::empty()).unwrap();
+        let (status, body) = send(test_app(), request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            body["signers"][0],
+            "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+        );
+        assert_eq!(body["pairs"][0]["feed_id"], "00");
+        assert_eq!(body["pairs"][0]["expiry_mode"], "timestamp");
+        assert_eq!(body["feeds"][0]["feed_id"], "00");
+        assert_eq!(body["feeds"][0]["error_count"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_signer() {
+        // Anvil's second dev key
+        const COSIGNER_KEY: &str =
+            "59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+        let state = test_state().with_cosigner(Signer::new(COSIGNER_KEY).unwrap());
+        let request = Request::get("/signer").body(Body::empty()).unwrap();
+        let (status, body) = send(create_app(state), request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["signing_mode"], "eip191");
+        assert_eq!(body["hash_scheme"], "packed");
+        assert!(body.get("hash_tag").is_none());
+        assert_eq!(
+            body["address"],
+            "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+        );
+        assert_eq!(
+            body["signers"],
+            serde_json::json!(["0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"])
+        );
+        assert_eq!(
+            body["cosigners"],
+            serde_json::json!(["0x70997970c51812dc3a010c7d01b50e0d17dc79c8"])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_erc1271_signer() {
+        const SAFE: &str = "0x5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a";
+        let wallet = Address::from_str(SAFE).unwrap();
+        let signer = Signer::erc1271(
+            wallet,
+            sign::Erc1271Format::Safe { chain_id: 8453 },
+            Signer::new(TEST_KEY).unwrap(),
+        );
+        let token_pair = TokenPairConfig::new(WETH, USDC).unwrap();
+        let state = AppState::from_signer(signer, "00", 5, token_pair)
+            .with_hermes(pyth::Hermes::default().with_base_url(&mock_hermes().await));
+        state.check_signer().await.unwrap();
+        let app = create_app(state);
+
+        let request = Request::get("/signer").body(Body::empty()).unwrap();
+        let (_, body) = send(app.clone(), request).await;
+        assert_eq!(body["signing_mode"], "erc1271");
+        assert_eq!(body["address"], SAFE);
+
+        let body = order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+        let (status, json) = post_context(app, body).await;
+        assert_eq!(status, StatusCode::OK);
+        let response: oracle::OracleResponse = serde_json::from_value(json).unwrap();
+        assert_eq!(response.signer, wallet);
+        assert_eq!(response.signature.len(), 65);
+    }
+
+    #[tokio::test]
+    async fn test_signer_rotation() {
+        const NEXT_KEY: &str = "59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+        const NEXT: &str = "0x70997970c51812dc3a010c7d01b50e0d17dc79c8";
+        let signer_at = |now: u64| async move {
+            let state = test_state()
+                .with_clock(Arc::new(clock::FixedClock(std::time::Duration::from_secs(
+                    now,
+                ))))
+                .with_signer_rotation(sign::Rotation {
+                    next: Signer::new(NEXT_KEY).unwrap(),
+                    starts_at: 1_700_000_000,
+                    ends_at: 1_700_086_400,
+                });
+            let request = Request::get("/signer").body(Body::empty()).unwrap();
+            send(create_app(state), request).await.1
+        };
+
+        let body = signer_at(1_699_999_999).await;
+        assert_eq!(
+            body["address"],
+            "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+        );
+        assert!(body.get("cosigners").is_none());
+        assert_eq!(body["rotation"]["next"], NEXT);
+        assert_eq!(body["rotation"]["phase"], "pending");
+
+        let body = signer_at(1_700_000_000).await;
+        assert_eq!(
+            body["address"],
+            "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+        );
+        assert_eq!(body["cosigners"], serde_json::json!([NEXT]));
+        assert_eq!(body["rotation"]["phase"], "overlap");
+
+        let body = signer_at(1_700_086_400).await;
+        assert_eq!(body["address"], NEXT);
+        assert!(body.get("cosigners").is_none());
+        assert_eq!(body["rotation"]["phase"], "complete");
+    }
+
+    #[tokio::test]
+    async fn test_context_all() {
+        let usdt = Address::repeat_byte(0xdd);
+        let io = |token: Address| order::IOV2 {
+            token,
+            vaultId: Default::default(),
+        };
+        let order = OrderV4 {
+            owner: Address::ZERO,
+            evaluable: EvaluableV4 {
+                interpreter: Address::ZERO,
+                store: Address::ZERO,
+                bytecode: Default::default(),
+            },
+            validInputs: vec![
+                io(Address::from_str(USDC).unwrap()),
+                io(Address::repeat_byte(0xaa)),
+                io(usdt),
+            ],
+            validOutputs: vec![io(Address::from_str(WETH).unwrap())],
+            nonce: Default::default(),
+        };
+        let encoded = (
+            order,
+            alloy::primitives::U256::from(7),
+            alloy::primitives::U256::from(7),
+            Address::ZERO,
+        )
+            .abi_encode();
+        let token_pair = TokenPairConfig::new(WETH, USDC)
+            .unwrap()
+            .with_quote_aliases(vec![usdt]);
+        let hermes = mock_hermes().await;
+        let state = AppState::new(TEST_KEY, "00", 5, token_pair.clone())
+            .unwrap()
+            .with_hermes(pyth::Hermes::default().with_base_url(&hermes));
+        let app = create_app(state);
+        let request = Request::post("/context/all")
+            .body(Body::from(encoded.clone()))
+            .unwrap();
+        let (status, body) = send(app.clone(), request).await;
+        assert_eq!(status, StatusCode::OK);
+        let quotes = body["quotes"].as_array().unwrap();
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(quotes[0]["input_io_index"], 0);
+        assert_eq!(quotes[1]["input_io_index"], 2);
+        assert_eq!(quotes[1]["input_token"], usdt.to_string().to_lowercase());
+        assert_eq!(quotes[1]["output_io_index"], 0);
+        assert_eq!(quotes[1]["context"].as_array().unwrap().len(), 2);
+        assert_ne!(quotes[0]["quote_id"], quotes[1]["quote_id"]);
+
+        // No combination matches
+        let unmatched = order::encode_request(
+            Address::repeat_byte(0xaa),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+        let request = Request::post("/context/all")
+            .body(Body::from(unmatched))
+            .unwrap();
+        let (status, body) = send(app, request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "unsupported_token_pair");
+
+        // Every combination needs quota, so one quote left isn't enough for two
+        let state = AppState::new(TEST_KEY, "00", 5, token_pair)
+            .unwrap()
+            .with_hermes(pyth::Hermes::default().with_base_url(&hermes))
+            .with_quotas(quota::Quotas::new([quota::ApiKey {
+                name: "mm".to_string(),
+                key: "sk_test".to_string(),
+                daily_quota: Some(1),
+            }]));
+        let app = create_app(state);
+        let request = Request::post("/context/all")
+            .header("x-api-key", "sk_test")
+            .body(Body::from(encoded))
+            .unwrap();
+        let (status, body) = send(app.clone(), request).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(body["error"], "quota_exceeded");
+        let request = Request::get("/usage")
+            .header("x-api-key", "sk_test")
+            .body(Body::empty())
+            .unwrap();
+        let (_, body) = send(app, request).await;
+        assert_eq!(body["used_today"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_context_batch() {
+        let state = priced_state().await;
+        let app = create_app(state);
+        let supported = order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+        let unsupported = order::encode_request(
+            Address::repeat_byte(0xaa),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+        let body = serde_json::to_vec(&[
+            alloy::hex::encode_prefixed(&supported),
+            alloy::hex::encode_prefixed(&unsupported),
+        ])
+        .unwrap();
+        let request = Request::post("/context/batch")
+            .body(Body::from(body))
+            .unwrap();
+        let (status, body) = send(app.clone(), request).await;
+        assert_eq!(status, StatusCode::OK);
+        let results = body["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["quote"]["context"].as_array().unwrap().len(), 2);
+        assert!(results[0].get("error").is_none());
+        assert_eq!(results[1]["error"]["error"], "unsupported_token_pair");
+
+        // MessagePack in and out, with the orders and signatures as binary
+        let body = msgpack::to_vec(&[alloy::primitives::Bytes::from(supported)]).unwrap();
+        let request = Request::post("/context/batch")
+            .header(axum::http::header::CONTENT_TYPE, msgpack::MSGPACK)
+            .header(axum::http::header::ACCEPT, msgpack::MSGPACK)
+            .body(Body::from(body))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[axum::http::header::CONTENT_TYPE],
+            msgpack::MSGPACK
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        // {"results": [{"quote": {...}}]}
+        assert_eq!(
+            body[..9],
+            [0x81, 0xa7, b'r', b'e', b's', b'u', b'l', b't', b's']
+        );
+        assert_eq!(body[9..11], [0x91, 0x81]);
+
+        let request = Request::post("/context/batch")
+            .header(idempotency::IDEMPOTENCY_KEY_HEADER, "retry-1")
+            .body(Body::from(r#"["0x00"]"#))
+            .unwrap();
+        let (status, body) = send(app, request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_idempotency_key");
+    }
+
+    #[tokio::test]
+    async fn test_context_layout_mismatch() {
+        // encode_request builds orders with empty bytecode
+        let template = layout::ExpressionTemplate {
+            bytecode_hash: alloy::primitives::KECCAK256_EMPTY,
+            slots: 3,
+        };
+        let check = || layout::LayoutCheck::new([template], layout::LayoutCheckMode::Reject);
+        let order = alloy::hex::encode_prefixed(order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        ));
+
+        let state = test_state().with_layout_check(check());
+        let (status, body) = post_context(create_app(state), order.clone()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "context_layout_mismatch");
+
+        // A nonce makes it three slots
+        let state = priced_state()
+            .await
+            .with_nonces(nonce::NonceGenerator::new(nonce::NonceMode::Counter, 1))
+            .with_layout_check(check());
+        let (status, body) = post_context(create_app(state), order.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["context"].as_array().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_context_sources_follow_nonce() {
+        let hermes = mock_hermes().await;
+        let source: sources::ContextSource = format!("funding={}/funding#/lastFundingRate", hermes)
+            .parse()
+            .unwrap();
+        let state = Arc::new(
+            test_state()
+                .with_hermes(pyth::Hermes::default().with_base_url(&hermes))
+                .with_nonces(nonce::NonceGenerator::new(nonce::NonceMode::Counter, 1))
+                .with_context_sources(sources::ContextSources::new(vec![source])),
+        );
+        let body = order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+        let (status, body) = post_context(create_app(state.clone()), body).await;
+        assert_eq!(status, StatusCode::OK);
+        let context = body["context"].as_array().unwrap();
+        assert_eq!(context.len(), 4);
+        let funding: B256 = rain_math_float::Float::parse("0.0001".to_string())
+            .unwrap()
+            .into();
+        assert_eq!(context[3], funding.to_string());
+
+        let request = Request::get("/pairs").body(Body::empty()).unwrap();
+        let (_, body) = send(create_app(state), request).await;
+        assert_eq!(
+            body["pairs"][0]["context_slots"],
+            serde_json::json!(["price", "expiry", "nonce", "funding"])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pairs() {
+        let state = test_state().with_feed_symbol("ETH/USD");
+        let request = Request::get("/pairs").body(Body::empty()).unwrap();
+        let (status, body) = send(create_app(state), request).await;
+        assert_eq!(status, StatusCode::OK);
+        let pair = &body["pairs"][0];
+        assert_eq!(pair["base_token"], WETH.to_lowercase());
+        assert_eq!(pair["feed_symbol"], "ETH/USD");
+        assert_eq!(pair["expiry"]["mode"], "timestamp");
+        assert_eq!(pair["expiry"]["seconds"], 5);
+        assert_eq!(pair["pricing"]["pipeline"], "[invert?]");
+        assert_eq!(pair["feed_state"], "unknown");
+        assert_eq!(pair["available"], true);
+
+        let state = test_state().with_redacted_pricing(true);
+        let request = Request::get("/pairs").body(Body::empty()).unwrap();
+        let (_, body) = send(create_app(state), request).await;
+        assert!(body["pairs"][0].get("pricing").is_none());
+    }
+
+    fn hmac_app() -> Router {
+        let state = test_state()
+            .with_clock(Arc::new(clock::FixedClock(std::time::Duration::from_secs(
+                1_700_000_000,
+            ))))
+            .with_hmac_auth(auth::HmacAuth::new(b"secret", 30));
+        create_app(state)
+    }
+
+    #[tokio::test]
+    async fn test_hmac_missing_signature() {
+        let (status, body) = post_context(hmac_app(), "0x00").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "missing_auth");
+    }
+
+    #[tokio::test]
+    async fn test_hmac_valid_signature_reaches_decoder() {
+        let signature = auth::HmacAuth::new(b"secret", 30).sign(
+            "1700000000",
+            "POST",
+            "/context?expiry_seconds=5",
+            b"0xzz",
+        );
+        let request = |uri| {
+            Request::post(uri)
+                .header(auth::TIMESTAMP_HEADER, "1700000000")
+                .header(auth::SIGNATURE_HEADER, signature.clone())
+                .body(Body::from("0xzz"))
+                .unwrap()
+        };
+        let (status, body) = send(hmac_app(), request("/context?expiry_seconds=5")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_body");
+
+        // The signature covers the query string
+        let (status, body) = send(hmac_app(), request("/context?expiry_seconds=300")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "invalid_signature");
+    }
+
+    #[tokio::test]
+    async fn test_hmac_bad_signature() {
+        let request = Request::post("/context")
+            .header(auth::TIMESTAMP_HEADER, "1700000000")
+            .header(auth::SIGNATURE_HEADER, "00".repeat(32))
+            .body(Body::from("0xzz"))
+            .unwrap();
+        let (status, body) = send(hmac_app(), request).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "invalid_signature");
+    }
+
+    #[tokio::test]
+    async fn test_hmac_not_required_on_health() {
+        let response = hmac_app()
+            .oneshot(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_jwt_missing_bearer() {
+        let state = test_state().with_jwt_auth(jwt::JwtAuth::with_secret(b"secret"));
+        let (status, body) = post_context(create_app(state), "0x00").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "missing_auth");
+    }
+
+    #[tokio::test]
+    async fn test_jwt_insufficient_scope_is_forbidden() {
+        let token = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &serde_json::json!({
+                "sub": "mm",
+                "scope": "read",
+                "exp": jsonwebtoken::get_current_timestamp() + 60,
+            }),
+            &jsonwebtoken::EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+        let state = test_state()
+            .with_jwt_auth(jwt::JwtAuth::with_secret(b"secret").with_required_scope("quote"));
+        let request = Request::post("/context")
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::from("0x00"))
+            .unwrap();
+        let (status, body) = send(create_app(state), request).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["error"], "insufficient_scope");
+    }
+
+    #[tokio::test]
+    async fn test_auth_any_accepts_either_mechanism() {
+        let state = test_state()
+            .with_jwt_auth(jwt::JwtAuth::with_secret(b"secret"))
+            .with_quotas(quota::Quotas::new([quota::ApiKey {
+                name: "mm".to_string(),
+                key: "sk_test".to_string(),
+                daily_quota: None,
+            }]))
+            .with_auth_mode(auth::AuthMode::Any);
+        let app = create_app(state);
+
+        let request = Request::post("/context")
+            .header("x-api-key", "sk_test")
+            .body(Body::from("0x00"))
+            .unwrap();
+        let (status, body) = send(app.clone(), request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_body");
+
+        let (status, body) = post_context(app.clone(), "0x00").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "missing_auth");
+
+        let request = Request::post("/context")
+            .header("authorization", "Bearer not.a.jwt")
+            .header("x-api-key", "sk_test")
+            .body(Body::from("0x00"))
+            .unwrap();
+        let (status, body) = send(app, request).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "invalid_token");
+    }
+
+    fn quota_app(daily_quota: u64) -> Router {
+        let state = test_state().with_quotas(quota::Quotas::new([quota::ApiKey {
+            name: "mm".to_string(),
+            key: "sk_test".to_string(),
+            daily_quota: Some(daily_quota),
+        }]));
+        create_app(state)
+    }
+
+    #[tokio::test]
+    async fn test_api_key_required() {
+        let (status, body) = post_context(quota_app(10), "0x00").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "missing_auth");
+
+        let request = Request::post("/context")
+            .header("x-api-key", "wrong")
+            .body(Body::from("0x00"))
+            .unwrap();
+        let (status, body) = send(quota_app(10), request).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "invalid_api_key");
+    }
+
+    #[tokio::test]
+    async fn test_quota_exhausted() {
+        let request = Request::post("/context")
+            .header("x-api-key", "sk_test")
+            .body(Body::from("0x00"))
+            .unwrap();
+        let (status, body) = send(quota_app(0), request).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(body["error"], "quota_exceeded");
+    }
+
+    #[tokio::test]
+    async fn test_failed_requests_not_counted() {
+        let app = quota_app(10);
+        let request = Request::post("/context")
+            .header("x-api-key", "sk_test")
+            .body(Body::from("0x00"))
+            .unwrap();
+        let (status, _) = send(app.clone(), request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let request = Request::get("/usage")
+            .header("x-api-key", "sk_test")
+            .body(Body::empty())
+            .unwrap();
+        let (status, body) = send(app, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["name"], "mm");
+        assert_eq!(body["used_today"], 0);
+        assert_eq!(body["remaining_today"], 10);
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_replays_not_counted() {
+        let state = priced_state()
+            .await
+            .with_idempotency(std::time::Duration::from_secs(60))
+            .with_quotas(quota::Quotas::new([quota::ApiKey {
+                name: "mm".to_string(),
+                key: "sk_test".to_string(),
+                daily_quota: Some(10),
+            }]));
+        let app = create_app(state);
+        let body = order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        );
+        for _ in 0..3 {
+            let request = Request::post("/context")
+                .header("x-api-key", "sk_test")
+                .header(idempotency::IDEMPOTENCY_KEY_HEADER, "retry-1")
+                .body(Body::from(body.clone()))
+                .unwrap();
+            let (status, _) = send(app.clone(), request).await;
+            assert_eq!(status, StatusCode::OK);
+        }
+
+        let request = Request::get("/usage")
+            .header("x-api-key", "sk_test")
+            .body(Body::empty())
+            .unwrap();
+        let (_, body) = send(app, request).await;
+        assert_eq!(body["used_today"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_batch_quotes_each_count_against_the_quota() {
+        let state = priced_state()
+            .await
+            .with_quotas(quota::Quotas::new([quota::ApiKey {
+                name: "mm".to_string(),
+                key: "sk_test".to_string(),
+                daily_quota: Some(2),
+            }]));
+        let app = create_app(state);
+        let order = alloy::hex::encode_prefixed(order::encode_request(
+            Address::from_str(USDC).unwrap(),
+            Address::from_str(WETH).unwrap(),
+            Address::ZERO,
+        ));
+        let request = Request::post("/context/batch")
+            .header("x-api-key", "sk_test")
+            .body(Body::from(
+                serde_json::to_vec(&[&order, &order, &order]).unwrap(),
+            ))
+            .unwrap();
+        let (status, body) = send(app.clone(), request).await;
+        assert_eq!(status, StatusCode::OK);
+        let results = body["results"].as_array().unwrap();
+        assert!(results[0].get("quote").is_some());
+        assert!(results[1].get("quote").is_some());
+        assert_eq!(results[2]["error"]["error"], "quota_exceeded");
+
+        let request = Request::get("/usage")
+            .header("x-api-key", "sk_test")
+            .body(Body::empty())
+            .unwrap();
+        let (_, body) = send(app, request).await;
+        assert_eq!(body["used_today"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_quote() {
+        use storage::Storage;
+
+        let storage = Arc::new(storage::MemoryStorage::default());
+        let record = storage::AuditRecord {
+            quote_id: uuid::Uuid::new_v4(),
+            signature_hash: storage::AuditRecord::signature_hash(b"sig"),
+            issued_at: 1700000000,
+            signer: Address::repeat_byte(1),
+            counterparty: Address::repeat_byte(2),
+            api_key: None,
+            taker_id: None,
+            order_hash: None,
+            output_vault: None,
+            base_token: Address::from_str(WETH).unwrap(),
+            quote_token: Address::from_str(USDC).unwrap(),
+            direction: PriceDirection::AsIs,
+            context: vec![],
+            signature: alloy::primitives::Bytes::from_static(b"sig"),
+            explanation: None,
+        };
+        storage.record_quote(&record).await.unwrap();
+
+        let state = test_state().with_storage(storage);
+        let app = create_app(state);
+
+        let get = |path: String| Request::get(path).body(Body::empty()).unwrap();
+        let (status, body) = send(app.clone(), get(format!("/quote/{}", record.quote_id))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["quote_id"], record.quote_id.to_string());
+        assert_eq!(body["direction"], "as_is");
+
+        let (status, body) =
+            send(app.clone(), get(format!("/quote/{}", uuid::Uuid::new_v4()))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "not_found");
+
+        let (status, body) = send(app, get("/quote/not-a-uuid".to_string())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_quote_id");
+    }
+}
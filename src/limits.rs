@@ -0,0 +1,208 @@
+use axum::{extract::State, http::HeaderMap, response::IntoResponse, response::Response, Json};
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::approval::{self, AdminAction};
+use crate::quota::{day, SECONDS_PER_DAY};
+use crate::{AppError, AppState, UnavailableError};
+
+/// Storage usage key counting quotes signed for the configured pair, kept apart from API
+/// key names by its prefix.
+fn usage_key(state: &AppState) -> String {
+    format!(
+        "pair:{}/{}",
+        state.token_pair.base_token, state.token_pair.quote_token
+    )
+}
+
+async fn signed_today(state: &AppState, today: u64) -> anyhow::Result<u64> {
+    Ok(state
+        .storage
+        .usage(&usage_key(state), today)
+        .await?
+        .get(&today)
+        .copied()
+        .unwrap_or(0))
+}
+
+fn limit_reached(limit: u64, now: u64) -> AppError {
+    UnavailableError::DailyLimitReached {
+        limit,
+        retry_after_secs: SECONDS_PER_DAY - now % SECONDS_PER_DAY,
+    }
+    .into()
+}
+
+/// Refuse to sign once the pair's daily limit is used up, until the next UTC day. Only an early
+/// refusal ahead of the price fetch; [`reserve`] is what holds the limit.
+pub async fn check(state: &AppState) -> Result<(), AppError> {
+    let Some(limit) = state.daily_quote_limit else {
+        return Ok(());
+    };
+    let now = state.clock.now().as_secs();
+    if signed_today(state, day(now)).await? >= limit {
+        return Err(limit_reached(limit, now));
+    }
+    Ok(())
+}
+
+/// Count a quote against the pair's daily limit before signing it, so concurrent requests
+/// can't overshoot the limit. Returns the day reserved, for [`release`] if signing fails.
+pub async fn reserve(state: &AppState) -> Result<Option<u64>, AppError> {
+    let Some(limit) = state.daily_quote_limit else {
+        return Ok(None);
+    };
+    let now = state.clock.now().as_secs();
+    let today = day(now);
+    let key = usage_key(state);
+    if state.storage.increment_usage(&key, today).await? > limit {
+        state.storage.release_usage(&key, today).await?;
+        return Err(limit_reached(limit, now));
+    }
+    Ok(Some(today))
+}
+
+/// Give back a [`reserve`]d count for a quote that wasn't signed.
+pub async fn release(state: &AppState, reserved: Option<u64>) {
+    let Some(day) = reserved else {
+        return;
+    };
+    if let Err(e) = state.storage.release_usage(&usage_key(state), day).await {
+        tracing::error!("Failed to release daily signing count: {}", e);
+    }
+}
+
+/// Response for `GET /limits` and `POST /limits/reset`.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct LimitsResponse {
+    /// `base/quote` of the configured pair
+    pub pair: String,
+    /// Quotes allowed per UTC day (absent when unlimited)
+    pub daily_quote_limit: Option<u64>,
+    pub signed_today: u64,
+    /// Absent when unlimited
+    pub remaining_today: Option<u64>,
+    /// Whether signing is paused until the next UTC day or a reset
+    pub paused: bool,
+}
+
+async fn report(state: &AppState) -> anyhow::Result<LimitsResponse> {
+    let signed_today = signed_today(state, day(state.clock.now().as_secs())).await?;
+    let limit = state.daily_quote_limit;
+    Ok(LimitsResponse {
+        pair: format!(
+            "{}/{}",
+            state.token_pair.base_token, state.token_pair.quote_token
+        ),
+        daily_quote_limit: limit,
+        signed_today,
+        remaining_today: limit.map(|l| l.saturating_sub(signed_today)),
+        paused: limit.is_some_and(|l| signed_today >= l),
+    })
+}
+
+/// GET handler — today's signing count against the pair's daily limit.
+#[utoipa::path(
+    get,
+    path = "/limits",
+    responses((status = 200, description = "Daily signing limit usage", body = LimitsResponse)),
+)]
+pub async fn limits(State(state): State<Arc<AppState>>) -> Result<Json<LimitsResponse>, AppError> {
+    Ok(Json(report(&state).await?))
+}
+
+/// Clear today's signing count.
+pub(crate) async fn reset_today(state: &AppState) -> anyhow::Result<()> {
+    let today = day(state.clock.now().as_secs());
+    state.storage.reset_usage(&usage_key(state), today).await?;
+    tracing::warn!("Daily signing count reset by admin");
+    Ok(())
+}
+
+/// POST handler — clear today's signing count, resuming a paused pair. Under the two-person
+/// rule the reset is only staged, and applies once a second admin approves it.
+#[utoipa::path(
+    post,
+    path = "/limits/reset",
+    params(("X-Admin-Key" = Option<String>, Header, description = "Admin API key (two-person rule)")),
+    responses(
+        (status = 200, description = "Daily signing limit usage after the reset", body = LimitsResponse),
+        (status = 202, description = "Reset staged for approval (two-person rule)", body = approval::PendingChange),
+        (status = 401, description = "Missing or unknown admin key (two-person rule)", body = crate::ErrorResponse),
+    ),
+)]
+pub async fn reset(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    if let Some(change) =
+        approval::apply_or_stage(&state, &headers, AdminAction::ResetDailyLimit).await?
+    {
+        return Ok(approval::staged_response(change));
+    }
+    Ok(Json(report(&state).await?).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use crate::tests::test_state;
+
+    #[tokio::test]
+    async fn test_pauses_until_reset() {
+        let state = test_state()
+            .with_clock(Arc::new(FixedClock(std::time::Duration::from_secs(
+                1_700_000_000,
+            ))))
+            .with_daily_quote_limit(2);
+
+        for _ in 0..2 {
+            check(&state).await.unwrap();
+            reserve(&state).await.unwrap();
+        }
+        match reserve(&state).await {
+            Err(AppError::Unavailable(UnavailableError::DailyLimitReached {
+                limit,
+                retry_after_secs,
+            })) => {
+                assert_eq!(limit, 2);
+                assert_eq!(
+                    retry_after_secs,
+                    SECONDS_PER_DAY - 1_700_000_000 % SECONDS_PER_DAY
+                );
+            }
+            other => panic!("expected daily limit error, got {:?}", other),
+        }
+        let paused = report(&state).await.unwrap();
+        assert!(paused.paused);
+        assert_eq!(paused.signed_today, 2);
+
+        reset_today(&state).await.unwrap();
+        let report = report(&state).await.unwrap();
+        assert_eq!(report.signed_today, 0);
+        assert_eq!(report.remaining_today, Some(2));
+        check(&state).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_reservations_stop_at_limit() {
+        let state = Arc::new(test_state().with_daily_quote_limit(3));
+
+        let tasks: Vec<_> = (0..10)
+            .map(|_| {
+                let state = state.clone();
+                tokio::spawn(async move { reserve(&state).await.is_ok() })
+            })
+            .collect();
+        let mut reserved = 0;
+        for task in tasks {
+            reserved += task.await.unwrap() as u64;
+        }
+        assert_eq!(reserved, 3);
+        assert_eq!(report(&state).await.unwrap().signed_today, 3);
+
+        release(&state, Some(day(state.clock.now().as_secs()))).await;
+        assert_eq!(report(&state).await.unwrap().remaining_today, Some(1));
+    }
+}
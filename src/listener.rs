@@ -0,0 +1,123 @@
+use socket2::{Domain, Protocol, SockRef, Socket, TcpKeepalive, Type};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// TCP keep-alive probing for accepted connections.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeepAlive {
+    /// Idle time before the first probe
+    pub time: Duration,
+    /// Time between probes (the OS default if unset)
+    pub interval: Option<Duration>,
+    /// Unanswered probes before the connection is dropped (the OS default if unset)
+    pub retries: Option<u32>,
+}
+
+/// Socket options for the HTTP listeners. Options set on the listening socket are inherited
+/// by the connections it accepts, so they apply to every client connection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListenerOptions {
+    /// Pending connection queue length passed to `listen()`
+    pub backlog: u32,
+    /// Set `SO_REUSEPORT`, so several processes can share a port and the kernel spreads
+    /// connections between them (Unix only)
+    pub reuse_port: bool,
+    /// Disable Nagle's algorithm, so small responses aren't held back waiting for ACKs
+    pub nodelay: bool,
+    pub keepalive: Option<KeepAlive>,
+}
+
+impl Default for ListenerOptions {
+    fn default() -> Self {
+        Self {
+            backlog: 1024,
+            reuse_port: false,
+            nodelay: true,
+            keepalive: None,
+        }
+    }
+}
+
+fn keepalive_params(keepalive: &KeepAlive) -> TcpKeepalive {
+    let mut params = TcpKeepalive::new().with_time(keepalive.time);
+    if let Some(interval) = keepalive.interval {
+        params = params.with_interval(interval);
+    }
+    #[cfg(unix)]
+    if let Some(retries) = keepalive.retries {
+        params = params.with_retries(retries);
+    }
+    params
+}
+
+/// Apply the per-connection options to an accepted (or listening) socket.
+fn configure(socket: SockRef<'_>, options: &ListenerOptions) -> std::io::Result<()> {
+    socket.set_tcp_nodelay(options.nodelay)?;
+    if let Some(keepalive) = &options.keepalive {
+        socket.set_tcp_keepalive(&keepalive_params(keepalive))?;
+    }
+    Ok(())
+}
+
+/// Apply the connection options to a listener bound elsewhere, e.g. inherited from systemd.
+/// Its backlog and `SO_REUSEPORT` were fixed when it was bound.
+pub fn configure_listener(
+    listener: &std::net::TcpListener,
+    options: &ListenerOptions,
+) -> std::io::Result<()> {
+    configure(SockRef::from(listener), options)
+}
+
+/// Bind a non-blocking listener on `addr` with `options`. IPv6 listeners accept IPv6 only,
+/// so `0.0.0.0` and `[::]` can both be bound on the same port.
+pub fn bind(addr: SocketAddr, options: &ListenerOptions) -> anyhow::Result<std::net::TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    socket.set_reuse_address(true)?;
+    if options.reuse_port {
+        #[cfg(unix)]
+        socket.set_reuse_port(true)?;
+        #[cfg(not(unix))]
+        anyhow::bail!("SO_REUSEPORT is not supported on this platform");
+    }
+    if addr.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+    configure(SockRef::from(&socket), options)?;
+    socket.set_nonblocking(true)?;
+    socket
+        .bind(&addr.into())
+        .map_err(|e| anyhow::anyhow!("Failed to bind {}: {}", addr, e))?;
+    socket.listen(options.backlog.try_into().unwrap_or(i32::MAX))?;
+    Ok(socket.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bind_applies_options() {
+        let options = ListenerOptions {
+            reuse_port: true,
+            keepalive: Some(KeepAlive {
+                time: Duration::from_secs(30),
+                interval: Some(Duration::from_secs(5)),
+                retries: Some(3),
+            }),
+            ..ListenerOptions::default()
+        };
+        let listener = bind("127.0.0.1:0".parse().unwrap(), &options).unwrap();
+        let socket = SockRef::from(&listener);
+        assert!(socket.tcp_nodelay().unwrap());
+        assert!(socket.keepalive().unwrap());
+        assert_eq!(
+            socket.tcp_keepalive_time().unwrap(),
+            Duration::from_secs(30)
+        );
+
+        // SO_REUSEPORT lets a second listener share the port
+        let addr = listener.local_addr().unwrap();
+        assert!(bind(addr, &options).is_ok());
+        assert!(bind(addr, &ListenerOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+use alloy::primitives::Address;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::metrics::percentile;
+use crate::order;
+
+/// What to send and how fast.
+#[derive(Debug, Clone)]
+pub struct LoadTestConfig {
+    /// Server base URL; requests go to `{url}/context`
+    pub url: String,
+    /// Requests started per second
+    pub rate: u32,
+    pub duration: Duration,
+    pub base_token: Address,
+    pub quote_token: Address,
+    /// Extra headers sent with every request (e.g. an API key)
+    pub headers: HeaderMap,
+    pub timeout: Duration,
+}
+
+/// Parse a `NAME: VALUE` header.
+pub fn parse_header(s: &str) -> anyhow::Result<(HeaderName, HeaderValue)> {
+    let (name, value) = s
+        .split_once(':')
+        .ok_or_else(|| anyhow::anyhow!("Invalid header '{}' (expected NAME: VALUE)", s))?;
+    Ok((name.trim().parse()?, value.trim().parse()?))
+}
+
+/// Latencies and failures observed during a run.
+#[derive(Debug, Default)]
+pub struct LoadTestReport {
+    pub sent: usize,
+    /// Failed requests by HTTP status, or `timeout` / `connect` / `request` for transport errors
+    pub errors: BTreeMap<String, usize>,
+    /// Milliseconds taken by each successful request (sorted)
+    pub latencies_ms: Vec<f64>,
+    pub elapsed: Duration,
+}
+
+impl std::fmt::Display for LoadTestReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let failed: usize = self.errors.values().sum();
+        write!(
+            f,
+            "{} requests in {:.1}s ({:.1}/s), {} failed ({:.2}%)",
+            self.sent,
+            self.elapsed.as_secs_f64(),
+            self.sent as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON),
+            failed,
+            100.0 * failed as f64 / self.sent.max(1) as f64
+        )?;
+        for (error, count) in &self.errors {
+            write!(f, "\n  {}: {}", error, count)?;
+        }
+        if !self.latencies_ms.is_empty() {
+            write!(f, "\n  latency ms:")?;
+            for p in [50.0, 90.0, 99.0, 100.0] {
+                write!(
+                    f,
+                    " p{} {:.1}",
+                    p,
+                    percentile(&self.latencies_ms, p).unwrap_or(0.0)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of one request: latency on success, or what went wrong.
+type Outcome = Result<Duration, String>;
+
+async fn send(client: reqwest::Client, url: String, body: Vec<u8>) -> Outcome {
+    let started = Instant::now();
+    let request = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+        .body(body);
+    match request.send().await {
+        Ok(response) if response.status().is_success() => {
+            // Latency includes reading the body, as a real client would
+            response.bytes().await.map_err(|_| "request".to_string())?;
+            Ok(started.elapsed())
+        }
+        Ok(response) => Err(response.status().as_u16().to_string()),
+        Err(e) if e.is_timeout() => Err("timeout".to_string()),
+        Err(e) if e.is_connect() => Err("connect".to_string()),
+        Err(_) => Err("request".to_string()),
+    }
+}
+
+/// Send valid `/context` requests at `config.rate` per second for `config.duration`,
+/// alternating directions and using a fresh counterparty each time so responses can't all
+/// be served from cache.
+pub async fn run(config: &LoadTestConfig) -> anyhow::Result<LoadTestReport> {
+    anyhow::ensure!(config.rate > 0, "Load test rate must be positive");
+    let client = reqwest::Client::builder()
+        .timeout(config.timeout)
+        .default_headers(config.headers.clone())
+        .build()?;
+    let url = format!("{}/context", config.url.trim_end_matches('/'));
+
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / f64::from(config.rate)));
+    let started = Instant::now();
+    let mut requests = Vec::new();
+    while started.elapsed() < config.duration {
+        ticker.tick().await;
+        let (input, output) = if requests.len() % 2 == 0 {
+            (config.quote_token, config.base_token)
+        } else {
+            (config.base_token, config.quote_token)
+        };
+        let body = order::encode_request(input, output, Address::random());
+        requests.push(tokio::spawn(send(client.clone(), url.clone(), body)));
+    }
+
+    let mut report = LoadTestReport {
+        sent: requests.len(),
+        ..Default::default()
+    };
+    for request in requests {
+        match request.await? {
+            Ok(latency) => report.latencies_ms.push(latency.as_secs_f64() * 1000.0),
+            Err(error) => *report.errors.entry(error).or_default() += 1,
+        }
+    }
+    report.elapsed = started.elapsed();
+    report.latencies_ms.sort_by(f64::total_cmp);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, routing::post, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_parse_header() {
+        let (name, value) = parse_header("X-Api-Key: secret").unwrap();
+        assert_eq!(name, "x-api-key");
+        assert_eq!(value, "secret");
+        assert!(parse_header("x-api-key").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_reports_errors_and_latency() {
+        // Decodes every body, and fails every third request
+        let count = Arc::new(AtomicUsize::new(0));
+        let app = Router::new().route(
+            "/context",
+            post(move |body: axum::body::Bytes| {
+                let count = count.clone();
+                async move {
+                    order::decode_request(&body, None).unwrap();
+                    if count.fetch_add(1, Ordering::Relaxed) % 3 == 2 {
+                        StatusCode::SERVICE_UNAVAILABLE
+                    } else {
+                        StatusCode::OK
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let report = run(&LoadTestConfig {
+            url: format!("http://{}/", addr),
+            rate: 50,
+            duration: Duration::from_millis(200),
+            base_token: Address::repeat_byte(0x42),
+            quote_token: Address::repeat_byte(0x83),
+            headers: HeaderMap::new(),
+            timeout: Duration::from_secs(5),
+        })
+        .await
+        .unwrap();
+        assert!(report.sent >= 3);
+        assert_eq!(report.errors["503"], report.sent / 3);
+        assert_eq!(report.latencies_ms.len(), report.sent - report.sent / 3);
+        assert!(report.to_string().contains("latency ms: p50"));
+    }
+}
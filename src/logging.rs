@@ -0,0 +1,181 @@
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::{AppError, AppState, QUOTE_ID_HEADER};
+
+/// Largest request body the logging middleware will buffer (matches axum's default body limit).
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Longest prefix of an error response body that is logged.
+const MAX_ERROR_BODY_BYTES: usize = 4096;
+
+/// Byte range of the counterparty in an ABI-encoded `(order, inputIOIndex, outputIOIndex,
+/// counterparty)` body: the fourth head word, address right-aligned.
+const COUNTERPARTY_RANGE: std::ops::Range<usize> = 108..128;
+
+/// Debug logging of `/context` request bodies and response summaries, for diagnosing
+/// integrator encoding problems. Logged under the `rain_oracle_server::logging` target at
+/// debug level; bodies aren't buffered unless that level is enabled.
+#[derive(Debug, Clone, Default)]
+pub struct RequestLogging {
+    redact_counterparty: bool,
+    max_body_bytes: Option<usize>,
+}
+
+impl RequestLogging {
+    /// Zero the counterparty address in logged bodies.
+    pub fn with_redacted_counterparty(mut self, redact: bool) -> Self {
+        self.redact_counterparty = redact;
+        self
+    }
+
+    /// Log at most `max_body_bytes` of each (hex-decoded) body.
+    pub fn with_max_body_bytes(mut self, max_body_bytes: usize) -> Self {
+        self.max_body_bytes = Some(max_body_bytes);
+        self
+    }
+
+    /// Hex of the body as the decoder sees it (`0x` text bodies decoded first), redacted and
+    /// truncated as configured.
+    pub fn format_body(&self, body: &[u8]) -> String {
+        let mut bytes = crate::decode_body(body)
+            .map(|b| b.into_owned())
+            .unwrap_or_else(|_| body.to_vec());
+        if self.redact_counterparty && bytes.len() >= COUNTERPARTY_RANGE.end {
+            bytes[COUNTERPARTY_RANGE].fill(0);
+        }
+        match self.max_body_bytes {
+            Some(max) if bytes.len() > max => format!(
+                "0x{}...({} more bytes)",
+                hex::encode(&bytes[..max]),
+                bytes.len() - max
+            ),
+            _ => format!("0x{}", hex::encode(&bytes)),
+        }
+    }
+}
+
+/// Middleware — logs the request body and a summary of the response.
+/// Passes requests through untouched when request logging is not configured.
+pub async fn log_requests(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let Some(logging) = &state.request_logging else {
+        return Ok(next.run(request).await);
+    };
+    if !tracing::enabled!(tracing::Level::DEBUG) {
+        return Ok(next.run(request).await);
+    }
+
+    let started = Instant::now();
+    let (parts, body) = request.into_parts();
+    // Invalid taker IDs are rejected by the handler; don't echo them into logs.
+    let taker = crate::taker::from_headers(&parts.headers)
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "-".to_string());
+    let bytes = axum::body::to_bytes(body, MAX_BODY_BYTES)
+        .await
+        .map_err(|e| crate::OracleRequestError::InvalidBody(e.to_string()))?;
+    tracing::debug!(
+        "{} {} request ({} bytes, taker {}): {}",
+        parts.method,
+        parts.uri,
+        bytes.len(),
+        taker,
+        logging.format_body(&bytes)
+    );
+
+    let response = next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await;
+    let status = response.status();
+    let elapsed_ms = started.elapsed().as_millis();
+    if status.is_success() {
+        let quote_id = response
+            .headers()
+            .get(QUOTE_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("-");
+        tracing::debug!(
+            "response {} quote {} for taker {} in {}ms",
+            status,
+            quote_id,
+            taker,
+            elapsed_ms
+        );
+        return Ok(response);
+    }
+
+    // Error bodies are short JSON and say why the request was rejected, so log them too.
+    let (parts, body) = response.into_parts();
+    let bytes = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .unwrap_or_default();
+    tracing::debug!(
+        "response {} for taker {} in {}ms: {}",
+        status,
+        taker,
+        elapsed_ms,
+        String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_ERROR_BODY_BYTES)])
+    );
+    Ok(Response::from_parts(parts, Body::from(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body() -> Vec<u8> {
+        // offset, input index, output index, counterparty, then 32 bytes of tail
+        let mut body = vec![0u8; 160];
+        body[31] = 0x80;
+        body[COUNTERPARTY_RANGE].fill(0xcc);
+        body[128..].fill(0xee);
+        body
+    }
+
+    #[test]
+    fn test_format_body_as_is() {
+        let logging = RequestLogging::default();
+        let body = body();
+        assert_eq!(
+            logging.format_body(&body),
+            format!("0x{}", hex::encode(&body))
+        );
+        // Hex text bodies are logged decoded, the same as binary ones
+        let text = format!("0x{}", hex::encode(&body));
+        assert_eq!(
+            logging.format_body(text.as_bytes()),
+            logging.format_body(&body)
+        );
+    }
+
+    #[test]
+    fn test_format_body_redacts_counterparty() {
+        let logging = RequestLogging::default().with_redacted_counterparty(true);
+        let formatted = logging.format_body(&body());
+        assert!(!formatted.contains("cc"));
+        assert!(formatted.ends_with(&"ee".repeat(32)));
+        // Too short to hold a counterparty: nothing to redact
+        assert_eq!(logging.format_body(&[0xcc; 4]), "0xcccccccc");
+    }
+
+    #[test]
+    fn test_format_body_truncates() {
+        let logging = RequestLogging::default().with_max_body_bytes(2);
+        assert_eq!(
+            logging.format_body(&[0xab, 0xcd, 0xef, 0x01]),
+            "0xabcd...(2 more bytes)"
+        );
+        assert_eq!(logging.format_body(&[0xab]), "0xab");
+    }
+}
@@ -1,57 +1,1762 @@
-use clap::Parser;
-use rain_oracle_server::{create_app, AppState, TokenPairConfig};
+use alloy::primitives::Address;
+use axum::http::{HeaderName, HeaderValue};
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use rain_oracle_server::alert::{self, AlertConfig, AlertDestination, Alerter};
+use rain_oracle_server::approval::{AdminKey, Approvals};
+use rain_oracle_server::auth::{AuthMode, HmacAuth, MtlsAuth};
+use rain_oracle_server::backtest;
+use rain_oracle_server::circuit::CircuitBreaker;
+use rain_oracle_server::clock::{ClockSkewMonitor, SystemClock};
+use rain_oracle_server::config;
+use rain_oracle_server::deployment::{self, Deployment};
+use rain_oracle_server::export::{self, ExportFormat};
+use rain_oracle_server::gas::{GasCompensation, GasPriceSource};
+use rain_oracle_server::guard::{PriceBounds, PricingGuard, Staleness};
+use rain_oracle_server::inventory::InventoryThrottle;
+use rain_oracle_server::jitter::PriceJitter;
+use rain_oracle_server::jwt::JwtAuth;
+use rain_oracle_server::layout::{ExpressionTemplate, LayoutCheck, LayoutCheckMode};
+use rain_oracle_server::leader::{self, LeaderElection};
+use rain_oracle_server::listener::{self, KeepAlive, ListenerOptions};
+use rain_oracle_server::loadtest::{self, LoadTestConfig};
+use rain_oracle_server::logging::RequestLogging;
+use rain_oracle_server::nonce::{NonceGenerator, NonceMode};
+use rain_oracle_server::order::RequestLimits;
+use rain_oracle_server::pipeline::Pipeline;
+use rain_oracle_server::pyth::{self, ApiKeyPlacement, Hermes};
+use rain_oracle_server::quota::{ApiKey, Quotas};
+use rain_oracle_server::reference::ReferencePrice;
+use rain_oracle_server::relay::{self, RelayConfig};
+use rain_oracle_server::remote::{self, RemoteSigner};
+use rain_oracle_server::rounding::{PriceRounding, RoundingDirection};
+use rain_oracle_server::schedule::Schedule;
+use rain_oracle_server::secrets;
+use rain_oracle_server::shared_price::{self, SharedPriceCache};
+use rain_oracle_server::sign::{self, Signer};
+use rain_oracle_server::sources::{ContextSource, ContextSources};
+use rain_oracle_server::storage::{self, QuoteFilter};
+use rain_oracle_server::stream;
+use rain_oracle_server::systemd;
+use rain_oracle_server::version::VersionResponse;
+use rain_oracle_server::watcher::{self, WatcherConfig};
+use rain_oracle_server::wormhole::GuardianSet;
+use rain_oracle_server::{
+    create_app, create_apps, AppState, DecimalsMismatch, ExpiryMode, PriceDirection,
+    TokenPairConfig,
+};
 use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use tracing_subscriber::EnvFilter;
+use zeroize::Zeroizing;
 
 /// WETH on Base
 const BASE_TOKEN: &str = "0x4200000000000000000000000000000000000006";
 /// USDC on Base
 const QUOTE_TOKEN: &str = "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913";
 /// ETH/USD Pyth price feed ID
-const PYTH_PRICE_FEED_ID: &str = "ff61491a931112ddf1bd8147cd1b641375f79f5825126d665480874634fd0ace";
+const DEFAULT_PYTH_PRICE_FEED_ID: &str =
+    "ff61491a931112ddf1bd8147cd1b641375f79f5825126d665480874634fd0ace";
 
-#[derive(Parser)]
+#[derive(Parser, Clone)]
 #[command(name = "rain-oracle-server")]
 #[command(about = "Reference signed context oracle server for Raindex")]
+#[command(subcommand_negates_reqs = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// JSON file of settings by environment variable name, e.g. `{"PORT": 8080}`. Flags and
+    /// environment variables take precedence over it
+    #[arg(long, env = "CONFIG_FILE")]
+    config_file: Option<PathBuf>,
+
     /// Port to listen on
     #[arg(short, long, default_value = "3000", env = "PORT")]
     port: u16,
 
+    /// Addresses to listen on instead of 0.0.0.0:PORT, comma-separated (e.g.
+    /// `0.0.0.0:3000,[::]:3000` for IPv4 and IPv6)
+    #[arg(long, env = "LISTEN_ADDRS", value_delimiter = ',')]
+    listen_addrs: Vec<SocketAddr>,
+
+    /// Pending connection queue length for each listener
+    #[arg(long, default_value = "1024", env = "LISTEN_BACKLOG")]
+    listen_backlog: u32,
+
+    /// Set SO_REUSEPORT on the listeners, so several server processes can share a port
+    #[arg(long, env = "SO_REUSEPORT")]
+    reuse_port: bool,
+
+    /// Set TCP_NODELAY on client connections
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set, env = "TCP_NODELAY")]
+    tcp_nodelay: bool,
+
+    /// Idle seconds before TCP keep-alive probes are sent on client connections; unset
+    /// leaves keep-alive off
+    #[arg(long, env = "TCP_KEEPALIVE_SECONDS")]
+    tcp_keepalive_seconds: Option<u64>,
+
+    /// Seconds between TCP keep-alive probes (OS default if unset)
+    #[arg(
+        long,
+        env = "TCP_KEEPALIVE_INTERVAL_SECONDS",
+        requires = "tcp_keepalive_seconds"
+    )]
+    tcp_keepalive_interval_seconds: Option<u64>,
+
+    /// Unanswered TCP keep-alive probes before a connection is dropped (OS default if unset)
+    #[arg(
+        long,
+        env = "TCP_KEEPALIVE_RETRIES",
+        requires = "tcp_keepalive_seconds"
+    )]
+    tcp_keepalive_retries: Option<u32>,
+
+    /// Serve admin routes (status, metrics) on this separate address (e.g. 127.0.0.1:9000)
+    /// instead of the public port
+    #[arg(long, env = "ADMIN_ADDR")]
+    admin_addr: Option<SocketAddr>,
+
+    /// PEM certificate chain for terminating HTTPS directly (requires --tls-key)
+    #[arg(long, env = "TLS_CERT", requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM private key for the TLS certificate (requires --tls-cert)
+    #[arg(long, env = "TLS_KEY", requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+
     /// Private key for EIP-191 signing (hex, with or without 0x prefix)
-    #[arg(long, env = "SIGNER_PRIVATE_KEY")]
-    signer_private_key: String,
+    #[arg(
+        long,
+        env = "SIGNER_PRIVATE_KEY",
+        hide_env_values = true,
+        required_unless_present_any = ["signer_private_key_file", "remote_signer_urls"]
+    )]
+    signer_private_key: Option<String>,
+
+    /// Read the signing key from this file (e.g. a Docker secret) instead of --signer-private-key
+    #[arg(
+        long,
+        env = "SIGNER_PRIVATE_KEY_FILE",
+        conflicts_with = "signer_private_key"
+    )]
+    signer_private_key_file: Option<PathBuf>,
+
+    /// Sign through remote `eth_sign` services (e.g. Web3Signer) instead of a local key,
+    /// failing over between these URLs in order (comma-separated)
+    #[arg(
+        long,
+        env = "REMOTE_SIGNER_URLS",
+        value_delimiter = ',',
+        conflicts_with_all = ["signer_private_key", "signer_private_key_file"],
+        requires = "remote_signer_address"
+    )]
+    remote_signer_urls: Vec<String>,
+
+    /// Address of the remote signer's key
+    #[arg(long, env = "REMOTE_SIGNER_ADDRESS")]
+    remote_signer_address: Option<Address>,
+
+    /// Signing requests sent to each remote signer at once
+    #[arg(long, default_value = "16", env = "REMOTE_SIGNER_CONCURRENCY")]
+    remote_signer_concurrency: usize,
+
+    /// Signing requests that may wait for each remote signer; further requests get a 503
+    #[arg(long, default_value = "256", env = "REMOTE_SIGNER_QUEUE")]
+    remote_signer_queue: usize,
+
+    /// Additional local keys that co-sign every context (comma-separated)
+    #[arg(
+        long,
+        env = "COSIGNER_KEYS",
+        value_delimiter = ',',
+        hide_env_values = true
+    )]
+    cosigner_keys: Vec<String>,
+
+    /// Key of the signer replacing the default signer: it co-signs from
+    /// SIGNER_ROTATION_START and signs alone from SIGNER_ROTATION_END
+    #[arg(
+        long,
+        env = "NEXT_SIGNER_PRIVATE_KEY",
+        hide_env_values = true,
+        requires_all = ["signer_rotation_start", "signer_rotation_end"]
+    )]
+    next_signer_private_key: Option<String>,
+
+    /// Start of the signer rotation overlap window (unix seconds or RFC 3339)
+    #[arg(long, env = "SIGNER_ROTATION_START", value_parser = export::parse_time, requires = "next_signer_private_key")]
+    signer_rotation_start: Option<i64>,
+
+    /// End of the signer rotation overlap window (unix seconds or RFC 3339)
+    #[arg(long, env = "SIGNER_ROTATION_END", value_parser = export::parse_time, requires = "next_signer_private_key")]
+    signer_rotation_end: Option<i64>,
+
+    /// Remote co-signers as ADDRESS=URL, with `|`-separated failover URLs (comma-separated)
+    #[arg(long, env = "REMOTE_COSIGNERS", value_delimiter = ',', value_parser = remote::parse_remote_signer)]
+    remote_cosigners: Vec<(Address, Vec<String>)>,
+
+    /// Backup signers used in priority order when the primary signer fails, each a hex
+    /// private key or a remote ADDRESS=URL[|URL...] (comma-separated)
+    #[arg(
+        long,
+        env = "FALLBACK_SIGNERS",
+        value_delimiter = ',',
+        hide_env_values = true
+    )]
+    fallback_signers: Vec<String>,
+
+    /// Seconds between health checks of the primary and fallback signers
+    #[arg(long, default_value = "30", env = "SIGNER_HEALTH_CHECK_SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    signer_health_check_seconds: u64,
+
+    /// Per-pair signing keys overriding --signer-private-key, as BASE/QUOTE=PRIVATE_KEY
+    /// (comma-separated)
+    #[arg(long, env = "PAIR_SIGNER_KEYS", value_delimiter = ',', value_parser = sign::parse_pair_key, hide_env_values = true)]
+    pair_signer_keys: Vec<(Address, Address, String)>,
+
+    /// Smart contract wallet (e.g. a Safe) to advertise as the signer, with the signing key
+    /// (or remote signer) acting as one of its owners
+    #[arg(long, env = "ERC1271_WALLET")]
+    erc1271_wallet: Option<Address>,
+
+    /// How ERC-1271 signatures are laid out: safe (an owner's signature over the Safe's
+    /// SafeMessage, needs CHAIN_ID) or plain (the owner's signature as is)
+    #[arg(long, default_value = "safe", env = "ERC1271_FORMAT")]
+    erc1271_format: String,
+
+    /// How contexts are packed into the signed hash: packed (keccak256(abi.encodePacked(context)),
+    /// as the orderbook checks it), abi_encoded or tagged
+    #[arg(long, default_value = "packed", env = "SIGNED_HASH_SCHEME")]
+    signed_hash_scheme: String,
+
+    /// Domain tag for the tagged hash scheme; keccak256 of it is prefixed to the context
+    #[arg(long, env = "SIGNED_HASH_TAG")]
+    signed_hash_tag: Option<String>,
 
     /// Signed context expiry in seconds
     #[arg(long, default_value = "5", env = "EXPIRY_SECONDS")]
     expiry_seconds: u64,
+
+    /// Maximum expiry in seconds a client may request via `?expiry_seconds=` (defaults to --expiry-seconds)
+    #[arg(long, env = "MAX_EXPIRY_SECONDS")]
+    max_expiry_seconds: Option<u64>,
+
+    /// Milliseconds added to timestamp expiries (extending them) to cover network and
+    /// block-inclusion delay
+    #[arg(long, default_value = "0", env = "EXPIRY_SAFETY_MARGIN_MS")]
+    expiry_safety_margin_ms: u64,
+
+    /// Chain block time in seconds; timestamp expiries are rounded up to the next block boundary,
+    /// unless that would take them past --max-expiry-seconds plus the safety margin
+    #[arg(long, env = "EXPIRY_BLOCK_TIME_SECONDS")]
+    expiry_block_time_seconds: Option<u64>,
+
+    /// Other addresses accepted as the base token, e.g. bridged variants (comma-separated)
+    #[arg(long, env = "BASE_TOKEN_ALIASES", value_delimiter = ',')]
+    base_token_aliases: Vec<Address>,
+
+    /// Token priced by the feed
+    #[arg(long, default_value = BASE_TOKEN, env = "BASE_TOKEN")]
+    base_token: Address,
+
+    /// Token the feed price is denominated in
+    #[arg(long, default_value = QUOTE_TOKEN, env = "QUOTE_TOKEN")]
+    quote_token: Address,
+
+    /// Chain ID served, checked against `eth_chainId` at startup when --rpc-url is set
+    #[arg(long, env = "CHAIN_ID")]
+    chain_id: Option<u64>,
+
+    /// JSON file listing several deployments (chain, tokens, signer, feed) to serve from one
+    /// process, each under its own path prefix
+    #[arg(long, env = "DEPLOYMENTS_FILE")]
+    deployments_file: Option<PathBuf>,
+
+    /// Profile to read from a deployments file that defines several (e.g. `dev`, `prod`)
+    #[arg(long, env = "PROFILE", requires = "deployments_file")]
+    profile: Option<String>,
+
+    /// Expected decimals of the base token: used instead of looking them up, and checked
+    /// against the chain at startup when --rpc-url is set
+    #[arg(long, env = "BASE_TOKEN_DECIMALS")]
+    base_token_decimals: Option<u8>,
+
+    /// Expected decimals of the quote token
+    #[arg(long, env = "QUOTE_TOKEN_DECIMALS")]
+    quote_token_decimals: Option<u8>,
+
+    /// Other addresses accepted as the quote token, e.g. USDC.e (comma-separated)
+    #[arg(long, env = "QUOTE_TOKEN_ALIASES", value_delimiter = ',')]
+    quote_token_aliases: Vec<Address>,
+
+    /// Pyth price feed ID (hex without 0x)
+    #[arg(long, default_value = DEFAULT_PYTH_PRICE_FEED_ID, env = "PYTH_PRICE_FEED_ID")]
+    pyth_price_feed_id: String,
+
+    /// Pyth feed symbol (e.g. `ETH/USD`), resolved to a feed ID via Hermes at startup;
+    /// overrides --pyth-price-feed-id
+    #[arg(long, env = "PYTH_PRICE_FEED_SYMBOL")]
+    pyth_price_feed_symbol: Option<String>,
+
+    /// The Pyth feed is quoted the other way round (e.g. USD/JPY for a JPY-quoted pair), so
+    /// flip it before pricing
+    #[arg(long, env = "FEED_INVERTED")]
+    feed_inverted: bool,
+
+    /// Also render the signed price with this many decimal places in responses, debug logs and
+    /// metrics, alongside the exact Rain float
+    #[arg(long, env = "PRICE_DISPLAY_DECIMALS")]
+    price_display_decimals: Option<u8>,
+
+    /// Scale the signed price by the decimal difference between the order's input and output
+    /// tokens (decimals from the order, or looked up via --rpc-url)
+    #[arg(long, env = "SCALE_BY_DECIMALS")]
+    scale_by_decimals: bool,
+
+    /// Round the feed price to this many significant digits, before the pipeline, inversion and
+    /// decimal scaling
+    #[arg(
+        long,
+        env = "PRICE_SIGNIFICANT_DIGITS",
+        conflicts_with = "price_tick_size"
+    )]
+    price_significant_digits: Option<u32>,
+
+    /// Round the feed price to a multiple of this tick size in quote-per-base units (e.g. `0.01`)
+    #[arg(long, env = "PRICE_TICK_SIZE")]
+    price_tick_size: Option<String>,
+
+    /// Which way to round the signed price; `up` is against the taker (the feed price is
+    /// rounded the other way for inverted orders)
+    #[arg(long, value_enum, default_value = "up", env = "PRICE_ROUNDING")]
+    price_rounding: RoundingDirection,
+
+    /// Transformations from the feed price to the signed price, applied in order, e.g.
+    /// `[clamp 1500..4000, invert?, mul 1.001, round 6sf]`
+    #[arg(long, default_value = "[invert?]", env = "PRICE_PIPELINE")]
+    price_pipeline: Pipeline,
+
+    /// Widen the signed price by a fill's gas cost at the current gas price: gas used per fill
+    /// (gas price from --gas-oracle-url, or `eth_gasPrice` on --rpc-url)
+    #[arg(long, env = "GAS_FILL_UNITS", requires = "gas_reference_fill")]
+    gas_fill_units: Option<u64>,
+
+    /// Fill size, in quote tokens, the gas cost is charged against
+    #[arg(long, env = "GAS_REFERENCE_FILL", requires = "gas_fill_units")]
+    gas_reference_fill: Option<String>,
+
+    /// Gas oracle API returning the gas price in gwei as JSON, instead of `eth_gasPrice`
+    #[arg(long, env = "GAS_ORACLE_URL")]
+    gas_oracle_url: Option<String>,
+
+    /// JSON pointer to the gwei gas price in the gas oracle response
+    #[arg(long, default_value = "/gasPrice", env = "GAS_ORACLE_POINTER")]
+    gas_oracle_pointer: String,
+
+    /// Raise every signed price by a random amount of up to this many basis points, so takers
+    /// can't infer our exact feed price and timing
+    #[arg(long, env = "PRICE_JITTER_BPS")]
+    price_jitter_bps: Option<u32>,
+
+    /// JSON API (e.g. a CEX ticker) whose price the feed price is checked against, quoted the
+    /// same way round as the feed; never signed
+    #[arg(long, env = "REFERENCE_PRICE_URL")]
+    reference_price_url: Option<String>,
+
+    /// JSON pointer to the price in the reference API response
+    #[arg(long, default_value = "/price", env = "REFERENCE_PRICE_POINTER")]
+    reference_price_pointer: String,
+
+    /// Pause the pair while the feed price deviates from the reference price by more than
+    /// this percentage
+    #[arg(long, default_value = "2", env = "REFERENCE_MAX_DEVIATION_PERCENT")]
+    reference_max_deviation_percent: f64,
+
+    /// Refuse feed prices published more than this many seconds ago
+    #[arg(long, env = "MAX_PRICE_AGE_SECONDS")]
+    max_price_age_seconds: Option<u64>,
+
+    /// Refuse feed prices below this, quoted the same way round as the feed
+    #[arg(long, env = "MIN_PRICE")]
+    min_price: Option<f64>,
+
+    /// Refuse feed prices above this, quoted the same way round as the feed
+    #[arg(long, env = "MAX_PRICE")]
+    max_price: Option<f64>,
+
+    /// Output tokens each unexpired quote may take; enables inventory throttling against the
+    /// order's output vault on ORDERBOOK_ADDRESS (requires RPC_URL; not with QUOTE_STREAM or
+    /// WEBHOOK_URLS)
+    #[arg(long, env = "INVENTORY_QUOTE_SIZE", requires = "rpc_url")]
+    inventory_quote_size: Option<f64>,
+
+    /// Largest ABI-encoded /context body accepted, in bytes
+    #[arg(long, default_value = "65536", env = "MAX_ORDER_BYTES")]
+    max_order_bytes: usize,
+
+    /// Largest validInputs / validOutputs array accepted in a /context order
+    #[arg(long, default_value = "32", env = "MAX_ORDER_IOS")]
+    max_order_ios: usize,
+
+    /// Express expiry as "current block + N" instead of a unix timestamp (requires --rpc-url)
+    #[arg(long, env = "EXPIRY_BLOCKS", requires = "rpc_url")]
+    expiry_blocks: Option<u64>,
+
+    /// Ethereum JSON-RPC endpoint used to look up the head block for block-number expiry
+    #[arg(long, env = "RPC_URL")]
+    rpc_url: Option<String>,
+
+    /// NTP server (host:port) to check local clock skew against; unset disables the check
+    #[arg(long, env = "NTP_SERVER")]
+    ntp_server: Option<String>,
+
+    /// Maximum tolerated local clock skew in milliseconds
+    #[arg(long, default_value = "1000", env = "MAX_CLOCK_SKEW_MS")]
+    max_clock_skew_ms: u64,
+
+    /// Seconds between periodic NTP clock checks
+    #[arg(long, default_value = "300", env = "NTP_CHECK_INTERVAL_SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    ntp_check_interval_seconds: u64,
+
+    /// Refuse to sign quotes (503) while clock skew exceeds the limit, instead of only logging
+    #[arg(long, env = "REFUSE_ON_CLOCK_SKEW")]
+    refuse_on_clock_skew: bool,
+
+    /// Shared secret for HMAC-SHA256 request signing on /context; unset disables HMAC auth
+    #[arg(long, env = "HMAC_SECRET", hide_env_values = true)]
+    hmac_secret: Option<String>,
+
+    /// Read the HMAC secret from this file instead of --hmac-secret
+    #[arg(long, env = "HMAC_SECRET_FILE", conflicts_with = "hmac_secret")]
+    hmac_secret_file: Option<PathBuf>,
+
+    /// Maximum age (either direction) of a signed request's X-Timestamp, in seconds
+    #[arg(long, default_value = "30", env = "HMAC_MAX_AGE_SECONDS")]
+    hmac_max_age_seconds: u64,
+
+    /// Shared secret for validating HS256 JWT bearer tokens on /context
+    #[arg(
+        long,
+        env = "JWT_SECRET",
+        hide_env_values = true,
+        conflicts_with = "jwks_url"
+    )]
+    jwt_secret: Option<String>,
+
+    /// Read the JWT secret from this file instead of --jwt-secret
+    #[arg(long, env = "JWT_SECRET_FILE", conflicts_with_all = ["jwt_secret", "jwks_url"])]
+    jwt_secret_file: Option<PathBuf>,
+
+    /// JWKS URL for validating asymmetric JWT bearer tokens on /context
+    #[arg(long, env = "JWKS_URL")]
+    jwks_url: Option<String>,
+
+    /// Required JWT `iss` claim
+    #[arg(long, env = "JWT_ISSUER")]
+    jwt_issuer: Option<String>,
+
+    /// Required JWT `aud` claim
+    #[arg(long, env = "JWT_AUDIENCE")]
+    jwt_audience: Option<String>,
+
+    /// Scope that must be present in the JWT `scope` claim
+    #[arg(long, env = "JWT_REQUIRED_SCOPE")]
+    jwt_required_scope: Option<String>,
+
+    /// API keys required on /context, as comma-separated `name:key` or `name:key:daily_quota`
+    #[arg(long, env = "API_KEYS", value_delimiter = ',', hide_env_values = true)]
+    api_keys: Vec<ApiKey>,
+
+    /// Read additional API keys from this file, one `name:key[:daily_quota]` per line or
+    /// comma-separated
+    #[arg(long, env = "API_KEYS_FILE")]
+    api_keys_file: Option<PathBuf>,
+
+    /// Whether `/context` requests must pass every configured auth mechanism or any one of them
+    #[arg(long, value_enum, default_value = "all", env = "AUTH_MODE")]
+    auth_mode: AuthMode,
+
+    /// Header in which a TLS-terminating proxy forwards the verified client certificate
+    /// identity; enables mTLS auth on /context
+    #[arg(long, env = "MTLS_IDENTITY_HEADER")]
+    mtls_identity_header: Option<String>,
+
+    /// Client certificate identities accepted by mTLS auth (comma-separated); unset accepts any
+    #[arg(long, env = "MTLS_ALLOWED_IDENTITIES", value_delimiter = ',')]
+    mtls_allowed_identities: Vec<String>,
+
+    /// Storage for audit records and usage counters (`sqlite://...`, `postgres://...` or
+    /// `redis://...`); unset keeps them in process memory
+    #[arg(long, env = "DATABASE_URL", hide_env_values = true)]
+    database_url: Option<String>,
+
+    /// Read the storage URL (which may embed a password) from this file instead of --database-url
+    #[arg(long, env = "DATABASE_URL_FILE", conflicts_with = "database_url")]
+    database_url_file: Option<PathBuf>,
+
+    /// Webhook URLs to push freshly signed quotes to (comma-separated); unset disables relay mode
+    #[arg(long, env = "WEBHOOK_URLS", value_delimiter = ',')]
+    webhook_urls: Vec<String>,
+
+    /// Seconds between relay pushes
+    #[arg(long, default_value = "5", env = "WEBHOOK_INTERVAL_SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    webhook_interval_seconds: u64,
+
+    /// Serve identical quote requests from a cache of signed responses for this many
+    /// milliseconds (0 disables; ignored with --nonce-mode)
+    #[arg(long, default_value = "0", env = "RESPONSE_CACHE_MS")]
+    response_cache_ms: u64,
+
+    /// Replay the signed response to `/context` retries carrying the same `Idempotency-Key`
+    /// for this many seconds (0 ignores the header)
+    #[arg(long, default_value = "60", env = "IDEMPOTENCY_TTL_SECONDS")]
+    idempotency_ttl_seconds: u64,
+
+    /// Pause signing once this many quotes have been signed for the pair in a UTC day, until
+    /// the next day or `POST /limits/reset`; unset is unlimited
+    #[arg(long, env = "DAILY_QUOTE_LIMIT")]
+    daily_quote_limit: Option<u64>,
+
+    /// The pair is a derivative: sign the CONTEXT_SOURCES values into its contexts
+    #[arg(long, env = "DERIVATIVE_PAIR")]
+    derivative_pair: bool,
+
+    /// Extra context slots for derivative pairs, as comma-separated `NAME=URL#POINTER` (a
+    /// JSON API and the pointer to the value), signed in order after the nonce
+    #[arg(long, env = "CONTEXT_SOURCES", value_delimiter = ',')]
+    context_sources: Vec<ContextSource>,
+
+    /// Weekly UTC quoting windows and closed dates, e.g.
+    /// `mon-fri 13:30-20:00, closed 2026-12-25`; unset quotes around the clock
+    #[arg(long, env = "SCHEDULE")]
+    schedule: Option<Schedule>,
+
+    /// Admin keys (comma-separated `name:key`) enabling the two-person rule: admin actions
+    /// that change signing are staged by one key and applied once another approves them
+    #[arg(
+        long,
+        env = "ADMIN_API_KEYS",
+        value_delimiter = ',',
+        hide_env_values = true
+    )]
+    admin_api_keys: Vec<AdminKey>,
+
+    /// Seconds a staged admin change can be approved for
+    #[arg(long, default_value = "3600", env = "ADMIN_APPROVAL_TTL_SECONDS")]
+    admin_approval_ttl_seconds: u64,
+
+    /// Log `/context` request bodies (hex) and response summaries at debug level
+    #[arg(long, env = "LOG_REQUESTS")]
+    log_requests: bool,
+
+    /// Zero the counterparty address in logged request bodies
+    #[arg(long, env = "LOG_REDACT_COUNTERPARTY")]
+    log_redact_counterparty: bool,
+
+    /// Log at most this many bytes of each request body
+    #[arg(long, env = "LOG_MAX_BODY_BYTES")]
+    log_max_body_bytes: Option<usize>,
+
+    /// Include internal error messages in 500 responses instead of only a correlation ID
+    #[arg(long, env = "VERBOSE_ERRORS")]
+    verbose_errors: bool,
+
+    /// Fetched prices to keep for GET /price/{pair}/history (0 to keep none)
+    #[arg(long, default_value = "3600", env = "PRICE_HISTORY_SIZE")]
+    price_history_size: usize,
+
+    /// Log a per-stage timing breakdown of /context requests taking at least this many
+    /// milliseconds (0 to disable)
+    #[arg(long, default_value = "1000", env = "SLOW_REQUEST_MS")]
+    slow_request_ms: u64,
+
+    /// Leave the price pipeline, rounding and gas settings out of GET /pairs
+    #[arg(long, env = "PAIRS_REDACT_PRICING")]
+    pairs_redact_pricing: bool,
+
+    /// Serve a JSON-RPC 2.0 facade at POST /rpc
+    #[arg(long, env = "JSON_RPC")]
+    json_rpc: bool,
+
+    /// Push signed quotes to Server-Sent Events subscribers at GET /stream
+    #[arg(long, env = "QUOTE_STREAM")]
+    quote_stream: bool,
+
+    /// Milliseconds between checks for a new price to sign for /stream subscribers
+    #[arg(long, default_value = "500", env = "QUOTE_STREAM_INTERVAL_MS")]
+    quote_stream_interval_ms: u64,
+
+    /// Start even if the configured price feed fails its startup check, or become ready when
+    /// warm-up times out (the signer check always has to pass)
+    #[arg(long, env = "ALLOW_DEGRADED_START")]
+    allow_degraded_start: bool,
+
+    /// Start serving immediately and fetch the first price in the background, reporting not
+    /// ready on /readyz (and refusing /context) until it arrives. After this many seconds
+    /// without one, logs an error and keeps retrying, or serves anyway with
+    /// --allow-degraded-start
+    #[arg(long, env = "WARMUP_TIMEOUT_SECONDS")]
+    warmup_timeout_seconds: Option<u64>,
+
+    /// API key for Hermes providers that require one
+    #[arg(long, env = "HERMES_API_KEY", hide_env_values = true)]
+    hermes_api_key: Option<String>,
+
+    /// Read the Hermes API key from this file instead of --hermes-api-key
+    #[arg(long, env = "HERMES_API_KEY_FILE", conflicts_with = "hermes_api_key")]
+    hermes_api_key_file: Option<PathBuf>,
+
+    /// Header to send the Hermes API key in
+    #[arg(long, default_value = pyth::DEFAULT_API_KEY_HEADER, env = "HERMES_API_KEY_HEADER")]
+    hermes_api_key_header: String,
+
+    /// Send the Hermes API key as this query parameter instead of a header
+    #[arg(long, env = "HERMES_API_KEY_QUERY")]
+    hermes_api_key_query: Option<String>,
+
+    /// Hermes base URL, for hosted providers
+    #[arg(long, env = "HERMES_URL")]
+    hermes_url: Option<String>,
+
+    /// Wormhole guardian addresses, in guardian set order (comma-separated); when set, the
+    /// guardian signatures on every Hermes update are verified before its price is used
+    #[arg(
+        long,
+        env = "HERMES_GUARDIAN_SET",
+        value_delimiter = ',',
+        requires = "hermes_guardian_set_index"
+    )]
+    hermes_guardian_set: Vec<Address>,
+
+    /// Index of the Wormhole guardian set in HERMES_GUARDIAN_SET
+    #[arg(
+        long,
+        env = "HERMES_GUARDIAN_SET_INDEX",
+        requires = "hermes_guardian_set"
+    )]
+    hermes_guardian_set_index: Option<u32>,
+
+    /// Consecutive Hermes failures after which requests fail fast with 503 circuit_open;
+    /// unset to always try Hermes
+    #[arg(long, env = "CIRCUIT_BREAKER_FAILURES")]
+    circuit_breaker_failures: Option<u32>,
+
+    /// Seconds requests fail fast once the circuit opens, before Hermes is tried again
+    #[arg(long, default_value = "30", env = "CIRCUIT_BREAKER_COOLDOWN_SECONDS")]
+    circuit_breaker_cooldown_seconds: u64,
+
+    /// Seconds within which a guard tripping again isn't recorded as another incident
+    #[arg(long, default_value = "300", env = "INCIDENT_COOLDOWN_SECONDS")]
+    incident_cooldown_seconds: u64,
+
+    /// Retries per webhook delivery, with exponential backoff
+    #[arg(long, default_value = "3", env = "WEBHOOK_MAX_RETRIES")]
+    webhook_max_retries: u32,
+
+    /// Orderbook to watch for takes of issued quotes (fill-rate and slippage metrics); unset
+    /// disables the watcher
+    #[arg(long, env = "ORDERBOOK_ADDRESS")]
+    orderbook_address: Option<Address>,
+
+    /// JSON-RPC endpoint the orderbook watcher polls for events (defaults to RPC_URL)
+    #[arg(long, env = "WATCHER_RPC_URL")]
+    watcher_rpc_url: Option<String>,
+
+    /// Seconds between orderbook watcher polls
+    #[arg(long, default_value = "5", env = "WATCHER_POLL_SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    watcher_poll_seconds: u64,
+
+    /// Elect one replica sharing DATABASE_URL to run the orderbook watcher and webhook relay;
+    /// every replica still serves quotes
+    #[arg(long, env = "LEADER_ELECTION", requires = "database_url")]
+    leader_election: bool,
+
+    /// Seconds the leader's lease lasts without renewal before another replica takes over
+    #[arg(long, default_value = "15", env = "LEADER_LEASE_SECONDS")]
+    leader_lease_seconds: u64,
+
+    /// Have the leader fetch the feed every this many milliseconds and publish it to storage for
+    /// every replica to quote from; unset fetches per request
+    #[arg(long, env = "SHARED_PRICE_INTERVAL_MS", requires = "database_url")]
+    shared_price_interval_ms: Option<u64>,
+
+    /// Oldest published price (in milliseconds) replicas quote from before fetching themselves
+    #[arg(long, default_value = "2000", env = "SHARED_PRICE_MAX_AGE_MS")]
+    shared_price_max_age_ms: u64,
+
+    /// Alert destinations (comma-separated `slack:<webhook url>` or `pagerduty:<routing key>`);
+    /// unset disables alerting
+    #[arg(
+        long,
+        env = "ALERT_DESTINATIONS",
+        value_delimiter = ',',
+        hide_env_values = true
+    )]
+    alert_destinations: Vec<AlertDestination>,
+
+    /// Alert when a price feed has been failing for this many seconds
+    #[arg(long, default_value = "60", env = "ALERT_FEED_DOWN_SECONDS")]
+    alert_feed_down_seconds: u64,
+
+    /// Alert when this many quotes fail with internal errors within the alert window
+    #[arg(long, default_value = "5", env = "ALERT_SIGNING_ERRORS")]
+    alert_signing_errors: u64,
+
+    /// Window for counting signing errors, in seconds
+    #[arg(long, default_value = "60", env = "ALERT_WINDOW_SECONDS")]
+    alert_window_seconds: u64,
+
+    /// Append a replay-protection nonce to every context as slot [2]
+    #[arg(long, value_enum, env = "NONCE_MODE")]
+    nonce_mode: Option<NonceMode>,
+
+    /// Known expression templates as <bytecode keccak256>:<signed-context slots read>, checked
+    /// against the context layout we sign
+    #[arg(long, env = "EXPRESSION_TEMPLATES", value_delimiter = ',')]
+    expression_templates: Vec<ExpressionTemplate>,
+
+    /// What to do when an order's template reads more slots than we sign
+    #[arg(long, value_enum, default_value = "warn", env = "LAYOUT_CHECK_MODE")]
+    layout_check_mode: LayoutCheckMode,
+
+    /// Refuse orders whose owner quoted the same nonce with a different expression within this
+    /// many seconds
+    #[arg(long, env = "ORDER_NONCE_WINDOW_SECONDS")]
+    order_nonce_window_seconds: Option<u64>,
+
+    /// Include a human-readable `debug` block (decimal price, direction, feed, times) in responses
+    #[arg(long, env = "RESPONSE_DIAGNOSTICS")]
+    response_diagnostics: bool,
+}
+
+#[derive(Subcommand, Clone)]
+enum Command {
+    /// Dump issued quotes from the audit database as CSV or JSONL
+    Export {
+        /// Storage to read from (`sqlite://...`, `postgres://...` or `redis://...`)
+        #[arg(long, env = "DATABASE_URL", hide_env_values = true)]
+        database_url: String,
+
+        #[arg(long, value_enum, default_value = "csv")]
+        format: ExportFormat,
+
+        /// Only quotes issued at or after this time (unix seconds or RFC 3339)
+        #[arg(long, value_parser = export::parse_time)]
+        since: Option<i64>,
+
+        /// Only quotes issued before this time (unix seconds or RFC 3339)
+        #[arg(long, value_parser = export::parse_time)]
+        until: Option<i64>,
+
+        /// Only quotes for this pair, as `BASE/QUOTE` token addresses
+        #[arg(long, value_parser = export::parse_pair)]
+        pair: Option<(Address, Address)>,
+
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Replay historical prices through the pricing pipeline and report refusals and markups
+    Backtest {
+        /// Read `timestamp,price` rows from this CSV instead of fetching from Pyth
+        #[arg(long, conflicts_with_all = ["from", "until"])]
+        csv: Option<PathBuf>,
+
+        /// Fetch prices from this time on (unix seconds or RFC 3339)
+        #[arg(long, value_parser = export::parse_time, requires = "until")]
+        from: Option<i64>,
+
+        /// Fetch prices before this time (unix seconds or RFC 3339)
+        #[arg(long, value_parser = export::parse_time, requires = "from")]
+        until: Option<i64>,
+
+        /// Seconds between fetched prices
+        #[arg(long, default_value = "3600", value_parser = clap::value_parser!(u64).range(1..))]
+        step_seconds: u64,
+    },
+    /// Send valid /context requests to a server at a fixed rate and report latency and errors
+    Loadtest {
+        /// Base URL of the server to test
+        #[arg(long)]
+        url: String,
+
+        /// Requests per second
+        #[arg(long, default_value = "10", value_parser = clap::value_parser!(u32).range(1..))]
+        rate: u32,
+
+        /// How long to send requests for
+        #[arg(long, default_value = "30")]
+        duration_seconds: u64,
+
+        /// Header to send with every request, e.g. `x-api-key: ...` (repeatable)
+        #[arg(long = "header", value_parser = loadtest::parse_header)]
+        headers: Vec<(HeaderName, HeaderValue)>,
+
+        /// Per-request timeout
+        #[arg(long, default_value = "10")]
+        timeout_seconds: u64,
+
+        /// Base token of the pair to request
+        #[arg(long, default_value = BASE_TOKEN)]
+        base_token: Address,
+
+        /// Quote token of the pair to request
+        #[arg(long, default_value = QUOTE_TOKEN)]
+        quote_token: Address,
+    },
+}
+
+/// Build the Hermes client and resolve the feed ID from the Pyth flags.
+async fn hermes_feed(cli: &mut Cli) -> anyhow::Result<(Hermes, String)> {
+    let hermes = hermes(cli)?;
+    let feed_id = match &cli.pyth_price_feed_symbol {
+        Some(symbol) => {
+            let feed_id = hermes.feed_id_for_symbol(symbol).await.map_err(|e| {
+                anyhow::anyhow!("Failed to resolve Pyth feed symbol '{}': {:#}", symbol, e)
+            })?;
+            tracing::info!("Resolved {} to Pyth feed {}", symbol, feed_id);
+            feed_id
+        }
+        None => cli.pyth_price_feed_id.trim_start_matches("0x").to_string(),
+    };
+    Ok((hermes, feed_id))
+}
+
+/// The configured pricing guards that only look at the feed price, so `backtest` can check
+/// historical prices with them. The reference price guard compares against a live price and
+/// isn't included.
+fn replayed_guards(cli: &Cli) -> anyhow::Result<Vec<Arc<dyn PricingGuard>>> {
+    let mut guards: Vec<Arc<dyn PricingGuard>> = Vec::new();
+    if let Some(max_age) = cli.max_price_age_seconds {
+        guards.push(Arc::new(Staleness::new(Duration::from_secs(max_age))?));
+    }
+    if cli.min_price.is_some() || cli.max_price.is_some() {
+        guards.push(Arc::new(PriceBounds::new(cli.min_price, cli.max_price)?));
+    }
+    Ok(guards)
+}
+
+/// Build the Hermes client from the Hermes flags.
+fn hermes(cli: &mut Cli) -> anyhow::Result<Hermes> {
+    let mut hermes = Hermes::default();
+    if let Some(url) = &cli.hermes_url {
+        hermes = hermes.with_base_url(url);
+    }
+    let hermes_api_key = secrets::resolve(
+        cli.hermes_api_key.take(),
+        cli.hermes_api_key_file.as_deref(),
+    )?;
+    if let Some(key) = &hermes_api_key {
+        let placement = match &cli.hermes_api_key_query {
+            Some(param) => ApiKeyPlacement::Query(param.clone()),
+            None => ApiKeyPlacement::Header(cli.hermes_api_key_header.clone()),
+        };
+        hermes = hermes.with_api_key(placement, key);
+    }
+    if let Some(index) = cli.hermes_guardian_set_index {
+        tracing::info!(
+            "Verifying Hermes updates against guardian set {} ({} guardians)",
+            index,
+            cli.hermes_guardian_set.len()
+        );
+        hermes = hermes.with_guardian_set(GuardianSet {
+            index,
+            keys: cli.hermes_guardian_set.clone(),
+        });
+    }
+    Ok(hermes)
+}
+
+/// The configured token pair and how its price is signed.
+fn token_pair(cli: &Cli) -> anyhow::Result<TokenPairConfig> {
+    anyhow::ensure!(
+        cli.price_display_decimals
+            .is_none_or(|decimals| decimals <= 36),
+        "Price display decimals must be at most 36"
+    );
+    let mut token_pair =
+        TokenPairConfig::new(&cli.base_token.to_string(), &cli.quote_token.to_string())?
+            .with_base_aliases(cli.base_token_aliases.clone())
+            .with_quote_aliases(cli.quote_token_aliases.clone())
+            .with_decimals_scaling(cli.scale_by_decimals)
+            .with_decimals(cli.base_token_decimals, cli.quote_token_decimals)
+            .with_feed_inverted(cli.feed_inverted)
+            .with_display_decimals(cli.price_display_decimals)
+            .with_rounding(price_rounding(cli)?)
+            .with_pipeline(cli.price_pipeline.clone());
+    let quote_tokens = [&[token_pair.quote_token][..], &token_pair.quote_aliases].concat();
+    if let Some(both) = [&[token_pair.base_token][..], &token_pair.base_aliases]
+        .concat()
+        .into_iter()
+        .find(|t| quote_tokens.contains(t))
+    {
+        anyhow::bail!("Token {} is configured as both base and quote", both);
+    }
+    if let Some(blocks) = cli.expiry_blocks {
+        token_pair = token_pair.with_expiry(ExpiryMode::BlockNumber { blocks });
+    }
+    Ok(token_pair)
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
+/// The configured signed-price rounding, if any.
+fn price_rounding(cli: &Cli) -> anyhow::Result<Option<PriceRounding>> {
+    match (cli.price_significant_digits, &cli.price_tick_size) {
+        (Some(digits), _) => {
+            PriceRounding::significant_digits(digits, cli.price_rounding).map(Some)
+        }
+        (None, Some(tick_size)) => {
+            PriceRounding::tick_size(tick_size, cli.price_rounding).map(Some)
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt()
         .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
         .init();
+    tracing::info!("{}", VersionResponse::current());
+
+    // Precedence: flags > environment (then .env) > config file > defaults. The .env file is
+    // loaded and the configuration resolved before the runtime starts any threads.
+    if cfg!(debug_assertions) {
+        if let Some(path) = config::load_dotenv()? {
+            tracing::info!("Loaded environment from {}", path.display());
+        }
+    }
+    let mut command = Cli::command();
+    if let Some(path) = config_file() {
+        let (with_settings, applied) = config::apply(command, config::load(&path)?, |name| {
+            std::env::var_os(name).is_some()
+        })?;
+        tracing::info!(
+            "Applied {} setting(s) from {}",
+            applied.len(),
+            path.display()
+        );
+        command = with_settings;
+    }
+    let cli = Cli::from_arg_matches(&command.get_matches()).unwrap_or_else(|e| e.exit());
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(run(cli))
+}
+
+async fn run(mut cli: Cli) -> anyhow::Result<()> {
+    match cli.command.take() {
+        Some(Command::Export {
+            database_url,
+            format,
+            since,
+            until,
+            pair,
+            output,
+        }) => {
+            let storage = storage::connect(&database_url).await?;
+            let filter = QuoteFilter { since, until, pair };
+            let rows = match output {
+                Some(path) => {
+                    let mut file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+                    export::export(storage.as_ref(), &filter, format, &mut file).await?
+                }
+                None => {
+                    export::export(
+                        storage.as_ref(),
+                        &filter,
+                        format,
+                        &mut std::io::stdout().lock(),
+                    )
+                    .await?
+                }
+            };
+            // stdout may be the export itself, so report on stderr
+            eprintln!("Exported {} quote(s)", rows);
+            return Ok(());
+        }
+        Some(Command::Backtest {
+            csv,
+            from,
+            until,
+            step_seconds,
+        }) => {
+            let samples = match (csv, from, until) {
+                (Some(path), _, _) => {
+                    let file = std::io::BufReader::new(std::fs::File::open(&path)?);
+                    backtest::read_csv(file)?.into_iter().map(Ok).collect()
+                }
+                (None, Some(from), Some(until)) => {
+                    let (hermes, feed_id) = hermes_feed(&mut cli).await?;
+                    backtest::fetch_samples(&hermes, &feed_id, from, until, step_seconds).await
+                }
+                _ => anyhow::bail!("backtest needs --csv, or --from and --until"),
+            };
+            let pair = token_pair(&cli)?;
+            let feed_id = cli.pyth_price_feed_id.trim_start_matches("0x");
+            let guards = replayed_guards(&cli)?;
+            for direction in [PriceDirection::AsIs, PriceDirection::Inverted] {
+                let report = backtest::replay(&samples, direction, &pair, feed_id, &guards).await;
+                println!("{}", report);
+            }
+            let mut not_replayed = vec!["decimal scaling"];
+            if cli.reference_price_url.is_some() {
+                not_replayed.push("reference price guard");
+            }
+            if cli.gas_fill_units.is_some() {
+                not_replayed.push("gas compensation");
+            }
+            if cli.price_jitter_bps.is_some() {
+                not_replayed.push("price jitter");
+            }
+            println!("not replayed: {}", not_replayed.join(", "));
+            return Ok(());
+        }
+        Some(Command::Loadtest {
+            url,
+            rate,
+            duration_seconds,
+            headers,
+            timeout_seconds,
+            base_token,
+            quote_token,
+        }) => {
+            let config = LoadTestConfig {
+                url,
+                rate,
+                duration: Duration::from_secs(duration_seconds),
+                base_token,
+                quote_token,
+                headers: headers.into_iter().collect(),
+                timeout: Duration::from_secs(timeout_seconds),
+            };
+            eprintln!(
+                "Sending {} request(s)/s to {} for {}s",
+                rate, config.url, duration_seconds
+            );
+            println!("{}", loadtest::run(&config).await?);
+            return Ok(());
+        }
+        None => {}
+    }
+    let deployments = match &cli.deployments_file {
+        Some(path) => deployment::load(path, cli.profile.as_deref())?,
+        None => Vec::new(),
+    };
+    // Every deployment must finish warming up before systemd is told the server is ready
+    let mut states = Vec::new();
+    let mut named = Vec::new();
+    let config = if deployments.is_empty() {
+        states.push(build_state(cli.clone()).await?);
+        None
+    } else {
+        for deployment in &deployments {
+            tracing::info!(
+                "Deployment {} (chain {}) at /{}",
+                deployment.name,
+                deployment.chain_id,
+                deployment.name
+            );
+            let state = build_state(with_deployment(cli.clone(), deployment))
+                .await
+                .map_err(|e| e.context(format!("Deployment {}", deployment.name)))?;
+            states.push(state.clone());
+            named.push((deployment.name.clone(), state));
+        }
+        let running = match &cli.deployments_file {
+            Some(path) => deployment::load_raw(path, cli.profile.as_deref())?,
+            None => deployments,
+        };
+        Some(deployment::ConfigState {
+            running,
+            profile: cli.profile.clone(),
+            hermes: hermes(&mut cli.clone())?,
+        })
+    };
+    let single_state = states.first().cloned();
 
-    let cli = Cli::parse();
+    let addrs = if cli.listen_addrs.is_empty() {
+        vec![SocketAddr::from(([0, 0, 0, 0], cli.port))]
+    } else {
+        cli.listen_addrs.clone()
+    };
+    let options = ListenerOptions {
+        backlog: cli.listen_backlog,
+        reuse_port: cli.reuse_port,
+        nodelay: cli.tcp_nodelay,
+        keepalive: cli.tcp_keepalive_seconds.map(|seconds| KeepAlive {
+            time: Duration::from_secs(seconds),
+            interval: cli.tcp_keepalive_interval_seconds.map(Duration::from_secs),
+            retries: cli.tcp_keepalive_retries,
+        }),
+    };
 
-    let token_pair = TokenPairConfig::new(BASE_TOKEN, QUOTE_TOKEN)?;
+    let bind_admin = || {
+        cli.admin_addr
+            .map(|addr| bind_all(&[addr], &options))
+            .transpose()
+    };
+    let inherited = systemd::listen_fds()?;
+    let (public_listeners, admin_listeners) = if inherited.is_empty() {
+        (bind_all(&addrs, &options)?, bind_admin()?)
+    } else {
+        tracing::info!("Serving on {} socket(s) from systemd", inherited.len());
+        for inherited in &inherited {
+            listener::configure_listener(&inherited.listener, &options)?;
+        }
+        // Sockets named `admin` (FileDescriptorName=admin) serve the admin routes
+        let (admin, public): (Vec<_>, Vec<_>) = inherited
+            .into_iter()
+            .partition(|inherited| inherited.name.as_deref() == Some("admin"));
+        let admin = if admin.is_empty() {
+            bind_admin()?
+        } else {
+            Some(admin.into_iter().map(|a| a.listener).collect())
+        };
+        (public.into_iter().map(|p| p.listener).collect(), admin)
+    };
 
-    let state = AppState::new(
-        &cli.signer_private_key,
-        PYTH_PRICE_FEED_ID,
-        cli.expiry_seconds,
-        token_pair,
+    tokio::spawn(async move {
+        while !states.iter().all(|state| state.is_ready()) {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        match systemd::notify("READY=1") {
+            Ok(true) => tracing::info!("Notified systemd of readiness"),
+            Ok(false) => {}
+            Err(e) => tracing::warn!("Failed to notify systemd of readiness: {}", e),
+        }
+    });
+
+    let tls = match (&cli.tls_cert, &cli.tls_key) {
+        (Some(cert), Some(key)) => Some(RustlsConfig::from_pem_file(cert, key).await?),
+        _ => None,
+    };
+
+    // Without an admin listener the admin routes share the public one, so they are built
+    // for it (leaving out `POST /limits/reset` unless the two-person rule authenticates it)
+    let separate_admin = admin_listeners.is_some();
+    let (public, admin) = match config {
+        None if separate_admin => create_apps(single_state.expect("state is built")),
+        None => (
+            create_app(single_state.expect("state is built")),
+            Router::new(),
+        ),
+        Some(config) => {
+            let (public, admin) = if separate_admin {
+                deployment::create_apps(named)
+            } else {
+                (deployment::create_app(named), Router::new())
+            };
+            (public, admin.merge(deployment::config_router(config)))
+        }
+    };
+
+    match admin_listeners {
+        Some(admin_listeners) => {
+            tokio::try_join!(
+                serve(public_listeners, public, tls.clone()),
+                serve(admin_listeners, admin, tls),
+            )?;
+        }
+        None => serve(public_listeners, public.merge(admin), tls).await?,
+    }
+
+    Ok(())
+}
+
+/// `--config-file` (or `CONFIG_FILE`), found before the full parse since that needs the
+/// file's settings applied first.
+fn config_file() -> Option<PathBuf> {
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        let arg = arg.to_string_lossy();
+        if arg == "--config-file" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix("--config-file=") {
+            return Some(PathBuf::from(path));
+        }
+    }
+    std::env::var_os("CONFIG_FILE").map(PathBuf::from)
+}
+
+/// Overlay a deployment's settings on the top-level configuration.
+fn with_deployment(mut cli: Cli, deployment: &Deployment) -> Cli {
+    cli.chain_id = Some(deployment.chain_id);
+    cli.base_token = deployment.base_token;
+    cli.quote_token = deployment.quote_token;
+    cli.base_token_aliases = deployment.base_token_aliases.clone();
+    cli.quote_token_aliases = deployment.quote_token_aliases.clone();
+    cli.base_token_decimals = deployment.base_token_decimals;
+    cli.quote_token_decimals = deployment.quote_token_decimals;
+    if let Some(path) = &deployment.signer_private_key_file {
+        cli.signer_private_key = None;
+        cli.signer_private_key_file = Some(path.clone());
+        cli.remote_signer_urls.clear();
+    }
+    if let Some(feed_id) = &deployment.pyth_price_feed_id {
+        cli.pyth_price_feed_id = feed_id.clone();
+        cli.pyth_price_feed_symbol = None;
+    }
+    if let Some(symbol) = &deployment.pyth_price_feed_symbol {
+        cli.pyth_price_feed_symbol = Some(symbol.clone());
+    }
+    if let Some(feed_inverted) = deployment.feed_inverted {
+        cli.feed_inverted = feed_inverted;
+    }
+    if let Some(decimals) = deployment.price_display_decimals {
+        cli.price_display_decimals = Some(decimals);
+    }
+    if let Some(rpc_url) = &deployment.rpc_url {
+        cli.rpc_url = Some(rpc_url.clone());
+    }
+    if let Some(url) = &deployment.reference_price_url {
+        cli.reference_price_url = Some(url.clone());
+    }
+    if let Some(pointer) = &deployment.reference_price_pointer {
+        cli.reference_price_pointer = pointer.clone();
+    }
+    if let Some(max_age) = deployment.max_price_age_seconds {
+        cli.max_price_age_seconds = Some(max_age);
+    }
+    if let Some(min) = deployment.min_price {
+        cli.min_price = Some(min);
+    }
+    if let Some(max) = deployment.max_price {
+        cli.max_price = Some(max);
+    }
+    if let Some(schedule) = &deployment.schedule {
+        cli.schedule = Some(schedule.clone());
+    }
+    if let Some(derivative) = deployment.derivative {
+        cli.derivative_pair = derivative;
+    }
+    if let Some(sources) = &deployment.context_sources {
+        cli.context_sources = sources.clone();
+    }
+    if let Some(scheme) = &deployment.signed_hash_scheme {
+        // The scheme and its tag go together, so the top-level tag isn't inherited
+        cli.signed_hash_scheme = scheme.clone();
+        cli.signed_hash_tag = deployment.signed_hash_tag.clone();
+    } else if let Some(tag) = &deployment.signed_hash_tag {
+        cli.signed_hash_tag = Some(tag.clone());
+    }
+    // Per-pair signers only apply to the deployment serving that pair
+    cli.pair_signer_keys.retain(|(base, quote, _)| {
+        (*base, *quote) == (deployment.base_token, deployment.quote_token)
+    });
+    cli
+}
+
+/// Build the state for one deployment from its configuration, and start its background
+/// tasks (warm-up, signer health checks, alerts, webhook relay, orderbook watcher).
+async fn build_state(mut cli: Cli) -> anyhow::Result<Arc<AppState>> {
+    let signer_private_key = secrets::resolve(
+        cli.signer_private_key.take(),
+        cli.signer_private_key_file.as_deref(),
     )?;
+    let signing_limits = remote::SigningLimits {
+        concurrency: cli.remote_signer_concurrency,
+        queue: cli.remote_signer_queue,
+    };
+    let remote_signer = |urls: Vec<String>, address: Address| -> anyhow::Result<Signer> {
+        Ok(Signer::remote(
+            RemoteSigner::new(urls, address)?.with_limits(signing_limits)?,
+        ))
+    };
+    let signer = match (signer_private_key, cli.remote_signer_address) {
+        (Some(key), _) => Signer::new(&key)?,
+        (None, Some(address)) if !cli.remote_signer_urls.is_empty() => {
+            remote_signer(cli.remote_signer_urls.clone(), address)?
+        }
+        _ => anyhow::bail!("--signer-private-key or --remote-signer-urls is required"),
+    };
+    let fallback_signers = std::mem::take(&mut cli.fallback_signers);
+    let has_fallback_signers = !fallback_signers.is_empty();
+    let signer = if !has_fallback_signers {
+        signer
+    } else {
+        let mut chain = vec![signer];
+        for entry in fallback_signers {
+            let entry = Zeroizing::new(entry);
+            chain.push(if entry.contains('=') {
+                let (address, urls) = remote::parse_remote_signer(&entry)?;
+                remote_signer(urls, address)?
+            } else {
+                Signer::new(&entry)?
+            });
+        }
+        tracing::info!("Signing with {} fallback signer(s)", chain.len() - 1);
+        Signer::failover(chain)?
+    };
+    let signer = match cli.erc1271_wallet {
+        Some(wallet) => {
+            let format = sign::Erc1271Format::parse(&cli.erc1271_format, cli.chain_id)?;
+            tracing::info!(
+                "Signing for ERC-1271 wallet {} with owner {} ({:?})",
+                wallet,
+                signer.address(),
+                format
+            );
+            Signer::erc1271(wallet, format, signer)
+        }
+        None => signer,
+    };
+
+    let token_pair = token_pair(&cli)?;
+    let lease_name = format!(
+        "pollers:{}/{}",
+        token_pair.base_token, token_pair.quote_token
+    );
+    let (hermes, feed_id) = hermes_feed(&mut cli).await?;
+
+    let mut pair_signers = Vec::new();
+    for (base, quote, key) in std::mem::take(&mut cli.pair_signer_keys) {
+        let key = Zeroizing::new(key);
+        anyhow::ensure!(
+            (base, quote) == (token_pair.base_token, token_pair.quote_token),
+            "Pair signer configured for {}/{}, which is not a configured pair",
+            base,
+            quote
+        );
+        pair_signers.push((base, quote, Signer::new(&key)?));
+    }
+
+    let mut state = AppState::from_signer(signer, &feed_id, cli.expiry_seconds, token_pair)
+        .with_diagnostics(cli.response_diagnostics)
+        .with_request_limits(RequestLimits {
+            max_body_bytes: cli.max_order_bytes,
+            max_ios: cli.max_order_ios,
+        })
+        .with_hermes(hermes)
+        .with_redacted_pricing(cli.pairs_redact_pricing)
+        .with_hash_scheme(sign::HashScheme::parse(
+            &cli.signed_hash_scheme,
+            cli.signed_hash_tag.as_deref(),
+        )?);
+    if let Some(symbol) = &cli.pyth_price_feed_symbol {
+        state = state.with_feed_symbol(symbol);
+    }
+    for key in std::mem::take(&mut cli.cosigner_keys) {
+        let key = Zeroizing::new(key);
+        let signer = Signer::new(&key)?;
+        tracing::info!("Co-signer: {}", signer.address());
+        state = state.with_cosigner(signer);
+    }
+    if let (Some(key), Some(starts_at), Some(ends_at)) = (
+        cli.next_signer_private_key.take().map(Zeroizing::new),
+        cli.signer_rotation_start,
+        cli.signer_rotation_end,
+    ) {
+        anyhow::ensure!(
+            0 <= starts_at && starts_at < ends_at,
+            "SIGNER_ROTATION_START must be before SIGNER_ROTATION_END"
+        );
+        let next = Signer::new(&key)?;
+        tracing::info!(
+            "Signer rotation to {}: co-signing from {}, signing alone from {}",
+            next.address(),
+            starts_at,
+            ends_at
+        );
+        state = state.with_signer_rotation(sign::Rotation {
+            next,
+            starts_at: starts_at as u64,
+            ends_at: ends_at as u64,
+        });
+    }
+    for (address, urls) in &cli.remote_cosigners {
+        tracing::info!("Remote co-signer: {}", address);
+        state = state.with_cosigner(remote_signer(urls.clone(), *address)?);
+    }
+    for (base, quote, signer) in pair_signers {
+        tracing::info!("Signer for {}/{}: {}", base, quote, signer.address());
+        state = state.with_pair_signer(base, quote, signer);
+    }
+    if let Some(max_expiry_seconds) = cli.max_expiry_seconds {
+        anyhow::ensure!(
+            max_expiry_seconds >= cli.expiry_seconds,
+            "--max-expiry-seconds ({}) must be at least --expiry-seconds ({})",
+            max_expiry_seconds,
+            cli.expiry_seconds
+        );
+        state = state.with_max_expiry_seconds(max_expiry_seconds);
+    }
+    if cli.expiry_safety_margin_ms > 0 {
+        state = state.with_expiry_safety_margin(Duration::from_millis(cli.expiry_safety_margin_ms));
+    }
+    if let Some(block_time) = cli.expiry_block_time_seconds {
+        anyhow::ensure!(
+            block_time > 0,
+            "--expiry-block-time-seconds must be positive"
+        );
+        state = state.with_expiry_block_time(block_time);
+    }
+    if let Some(mode) = cli.nonce_mode {
+        let start = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_micros() as u64;
+        state = state.with_nonces(NonceGenerator::new(mode, start));
+    }
+    if !cli.expression_templates.is_empty() {
+        state = state.with_layout_check(LayoutCheck::new(
+            std::mem::take(&mut cli.expression_templates),
+            cli.layout_check_mode,
+        ));
+    }
+    if let Some(window) = cli.order_nonce_window_seconds {
+        state = state.with_order_nonce_window(Duration::from_secs(window));
+    }
+    if cli.response_cache_ms > 0 {
+        if cli.nonce_mode.is_some() {
+            tracing::warn!("RESPONSE_CACHE_MS is ignored because nonces are enabled");
+        }
+        state = state.with_response_cache(Duration::from_millis(cli.response_cache_ms));
+    }
+    if let Some(limit) = cli.daily_quote_limit {
+        state = state.with_daily_quote_limit(limit);
+    }
+    if let Some(schedule) = &cli.schedule {
+        tracing::info!("Quoting schedule: {}", schedule);
+        state = state.with_schedule(schedule.clone());
+    }
+    if cli.derivative_pair {
+        for source in &cli.context_sources {
+            tracing::info!("Context slot '{}' from {}", source.name, source.url);
+        }
+        state = state.with_context_sources(ContextSources::new(cli.context_sources.clone()));
+    } else if !cli.context_sources.is_empty() {
+        tracing::info!("Pair is not a derivative; CONTEXT_SOURCES are not signed");
+    }
+    if !cli.admin_api_keys.is_empty() {
+        state = state.with_approvals(Approvals::new(
+            cli.admin_api_keys,
+            Duration::from_secs(cli.admin_approval_ttl_seconds),
+        )?);
+    }
+    if cli.idempotency_ttl_seconds > 0 {
+        state = state.with_idempotency(Duration::from_secs(cli.idempotency_ttl_seconds));
+    }
+    if cli.log_requests {
+        let mut logging =
+            RequestLogging::default().with_redacted_counterparty(cli.log_redact_counterparty);
+        if let Some(max) = cli.log_max_body_bytes {
+            logging = logging.with_max_body_bytes(max);
+        }
+        if !tracing::enabled!(tracing::Level::DEBUG) {
+            tracing::warn!("LOG_REQUESTS has no effect unless RUST_LOG enables debug logging");
+        }
+        state = state.with_request_logging(logging);
+    }
+    state = state.with_price_history_size(cli.price_history_size);
+    if cli.slow_request_ms > 0 {
+        state = state.with_slow_request_threshold(Duration::from_millis(cli.slow_request_ms));
+    }
+    state = state
+        .with_verbose_errors(cli.verbose_errors)
+        .with_json_rpc(cli.json_rpc);
+    if cli.quote_stream {
+        state = state.with_quote_stream(stream::QuoteStream::new(Duration::from_millis(
+            cli.quote_stream_interval_ms,
+        ))?);
+    }
+    if let (Some(fill_gas), Some(reference_fill)) = (cli.gas_fill_units, &cli.gas_reference_fill) {
+        let source = match (&cli.gas_oracle_url, &cli.rpc_url) {
+            (Some(url), _) => GasPriceSource::Oracle {
+                url: url.clone(),
+                pointer: cli.gas_oracle_pointer.clone(),
+            },
+            (None, Some(rpc_url)) => GasPriceSource::Rpc(rpc_url.clone()),
+            (None, None) => anyhow::bail!("--gas-fill-units needs --gas-oracle-url or --rpc-url"),
+        };
+        state =
+            state.with_gas_compensation(GasCompensation::new(source, fill_gas, reference_fill)?);
+    }
+    if let Some(bps) = cli.price_jitter_bps {
+        state = state.with_price_jitter(PriceJitter::new(bps)?);
+    }
+    if let Some(failures) = cli.circuit_breaker_failures {
+        state = state.with_circuit_breaker(CircuitBreaker::new(
+            failures,
+            Duration::from_secs(cli.circuit_breaker_cooldown_seconds),
+        )?);
+    }
+    state = state.with_incident_cooldown(Duration::from_secs(cli.incident_cooldown_seconds));
+    if let Some(max_age) = cli.max_price_age_seconds {
+        state = state.with_pricing_guard(Staleness::new(Duration::from_secs(max_age))?);
+    }
+    if cli.min_price.is_some() || cli.max_price.is_some() {
+        state = state.with_pricing_guard(PriceBounds::new(cli.min_price, cli.max_price)?);
+    }
+    if let Some(url) = &cli.reference_price_url {
+        state = state.with_reference_price(ReferencePrice::new(
+            url,
+            &cli.reference_price_pointer,
+            cli.reference_max_deviation_percent,
+        )?);
+    }
+    if let Some(rpc_url) = &cli.rpc_url {
+        state = state.with_rpc_url(rpc_url);
+    }
+    if let (Some(quote_size), Some(rpc_url)) = (cli.inventory_quote_size, &cli.rpc_url) {
+        let orderbook = cli
+            .orderbook_address
+            .ok_or_else(|| anyhow::anyhow!("INVENTORY_QUOTE_SIZE requires ORDERBOOK_ADDRESS"))?;
+        // Streamed and pushed quotes aren't signed for an order, so there's no vault to count
+        // them against
+        anyhow::ensure!(
+            !cli.quote_stream && cli.webhook_urls.is_empty(),
+            "INVENTORY_QUOTE_SIZE can't be combined with QUOTE_STREAM or WEBHOOK_URLS"
+        );
+        tracing::info!(
+            "Throttling quotes to output vault balances on {} ({} per quote)",
+            orderbook,
+            quote_size
+        );
+        state =
+            state.with_inventory_throttle(InventoryThrottle::new(rpc_url, orderbook, quote_size)?);
+    }
+    if let Some(chain_id) = cli.chain_id {
+        state = state.with_chain_id(chain_id);
+        state
+            .check_chain_id()
+            .await
+            .map_err(|e| e.context("Chain ID check failed"))?;
+    }
+    if let Err(e) = state.check_tokens().await {
+        if !cli.allow_degraded_start || e.is::<DecimalsMismatch>() {
+            return Err(e.context("Token metadata check failed"));
+        }
+        tracing::warn!("Token metadata check failed: {:#}", e);
+    }
+    state = state.with_auth_mode(cli.auth_mode);
+    if let Some(secret) = secrets::resolve(cli.hmac_secret.take(), cli.hmac_secret_file.as_deref())?
+    {
+        state = state.with_hmac_auth(HmacAuth::new(secret.as_bytes(), cli.hmac_max_age_seconds));
+    }
+
+    let jwt_secret = secrets::resolve(cli.jwt_secret.take(), cli.jwt_secret_file.as_deref())?;
+    let jwt_auth = match (&jwt_secret, &cli.jwks_url) {
+        (Some(secret), _) => Some(JwtAuth::with_secret(secret.as_bytes())),
+        (None, Some(url)) => Some(JwtAuth::with_jwks_url(url).await?),
+        (None, None) => None,
+    };
+    if let Some(mut jwt_auth) = jwt_auth {
+        if let Some(issuer) = &cli.jwt_issuer {
+            jwt_auth = jwt_auth.with_issuer(issuer);
+        }
+        if let Some(audience) = &cli.jwt_audience {
+            jwt_auth = jwt_auth.with_audience(audience);
+        }
+        if let Some(scope) = &cli.jwt_required_scope {
+            jwt_auth = jwt_auth.with_required_scope(scope);
+        }
+        state = state.with_jwt_auth(jwt_auth);
+    }
+    if let Some(header) = &cli.mtls_identity_header {
+        state = state.with_authenticator(Arc::new(MtlsAuth::new(
+            header,
+            cli.mtls_allowed_identities.clone(),
+        )));
+    }
+    if let Some(database_url) =
+        secrets::resolve(cli.database_url.take(), cli.database_url_file.as_deref())?
+    {
+        state = state.with_storage(storage::connect(&database_url).await?);
+        tracing::info!("Connected to storage database");
+    }
+    if cli.leader_election {
+        let election =
+            LeaderElection::new(&lease_name, Duration::from_secs(cli.leader_lease_seconds))?;
+        tracing::info!(
+            "Competing for leader lease '{}' as {}",
+            lease_name,
+            election.holder()
+        );
+        state = state.with_leader_election(election);
+    }
+    if let Some(interval) = cli.shared_price_interval_ms {
+        anyhow::ensure!(interval > 0, "SHARED_PRICE_INTERVAL_MS must be positive");
+        state = state.with_shared_price_cache(SharedPriceCache {
+            interval: Duration::from_millis(interval),
+            max_age: Duration::from_millis(cli.shared_price_max_age_ms),
+        });
+    }
+    let mut api_keys = cli.api_keys;
+    if let Some(path) = &cli.api_keys_file {
+        for entry in secrets::read_secret_file(path)?
+            .split([',', '\n'])
+            .filter(|entry| !entry.trim().is_empty())
+        {
+            api_keys.push(
+                entry
+                    .parse()
+                    .map_err(|e| anyhow::anyhow!("Invalid API key in {}: {}", path.display(), e))?,
+            );
+        }
+    }
+    if !api_keys.is_empty() {
+        tracing::info!("{} API key(s) configured", api_keys.len());
+        state = state.with_quotas(Quotas::new(api_keys));
+    }
+
+    if let Some(ntp_server) = cli.ntp_server {
+        let monitor = Arc::new(ClockSkewMonitor::new(
+            Duration::from_millis(cli.max_clock_skew_ms),
+            cli.refuse_on_clock_skew,
+        ));
+        match monitor.check(&ntp_server, &SystemClock).await {
+            Ok(skew) => tracing::info!("Clock skew vs {}: {}ms", ntp_server, skew),
+            Err(e) => tracing::warn!("Startup NTP clock check failed: {}", e),
+        }
+        monitor.clone().spawn_periodic(
+            ntp_server,
+            Arc::new(SystemClock),
+            Duration::from_secs(cli.ntp_check_interval_seconds),
+        );
+        state = state.with_clock_skew_monitor(monitor);
+    }
 
     tracing::info!("Signer address: {}", state.signer_address());
+    state
+        .check_signer()
+        .await
+        .map_err(|e| e.context("Signing self-test failed"))?;
+    if cli.warmup_timeout_seconds.is_some() {
+        state = state.with_warm_up();
+    } else if let Err(e) = state.check_feeds().await {
+        if !cli.allow_degraded_start {
+            return Err(
+                e.context("Startup feed check failed (set ALLOW_DEGRADED_START to start anyway)")
+            );
+        }
+        tracing::warn!("Starting degraded: {:#}", e);
+    }
+    let state = Arc::new(state);
+
+    leader::spawn(state.clone());
+    shared_price::spawn(state.clone());
+    stream::spawn(state.clone());
+
+    if let Some(timeout) = cli.warmup_timeout_seconds {
+        let state = state.clone();
+        let allow_degraded = cli.allow_degraded_start;
+        tokio::spawn(async move {
+            state
+                .warm_up(Duration::from_secs(timeout), allow_degraded)
+                .await
+        });
+    }
 
-    let app = create_app(state);
-    let addr = SocketAddr::from(([0, 0, 0, 0], cli.port));
-    tracing::info!("Listening on {}", addr);
+    if has_fallback_signers {
+        let state = state.clone();
+        let interval = Duration::from_secs(cli.signer_health_check_seconds);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                state.check_signer_health().await;
+            }
+        });
+    }
+    if !cli.alert_destinations.is_empty() {
+        tracing::info!(
+            "Sending alerts to {} destination(s)",
+            cli.alert_destinations.len()
+        );
+        alert::spawn(
+            state.clone(),
+            AlertConfig {
+                feed_down_after: Duration::from_secs(cli.alert_feed_down_seconds),
+                signing_errors: cli.alert_signing_errors,
+                window: Duration::from_secs(cli.alert_window_seconds),
+                check_interval: Duration::from_secs(10),
+            },
+            Alerter::new(cli.alert_destinations),
+        );
+    }
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    if !cli.webhook_urls.is_empty() {
+        tracing::info!(
+            "Relaying quotes to {} webhook(s) every {}s",
+            cli.webhook_urls.len(),
+            cli.webhook_interval_seconds
+        );
+        relay::spawn(
+            state.clone(),
+            RelayConfig {
+                urls: cli.webhook_urls,
+                interval: Duration::from_secs(cli.webhook_interval_seconds),
+                max_retries: cli.webhook_max_retries,
+            },
+        );
+    }
+
+    if let Some(orderbook) = cli.orderbook_address {
+        let rpc_url = cli.watcher_rpc_url.or(cli.rpc_url).ok_or_else(|| {
+            anyhow::anyhow!("ORDERBOOK_ADDRESS requires WATCHER_RPC_URL or RPC_URL")
+        })?;
+        tracing::info!("Watching orderbook {} for fills", orderbook);
+        watcher::spawn(
+            state.clone(),
+            WatcherConfig {
+                rpc_url,
+                orderbook,
+                poll_interval: Duration::from_secs(cli.watcher_poll_seconds),
+            },
+        );
+    }
+
+    Ok(state)
+}
+
+/// Bind a listener on each of `addrs`.
+fn bind_all(
+    addrs: &[SocketAddr],
+    options: &ListenerOptions,
+) -> anyhow::Result<Vec<std::net::TcpListener>> {
+    addrs
+        .iter()
+        .map(|&addr| listener::bind(addr, options))
+        .collect()
+}
 
+/// Serve `app` on each of `listeners`, over HTTPS when a TLS config is given, until one of
+/// them fails.
+async fn serve(
+    listeners: Vec<std::net::TcpListener>,
+    app: Router,
+    tls: Option<RustlsConfig>,
+) -> anyhow::Result<()> {
+    let mut servers = tokio::task::JoinSet::new();
+    for listener in listeners {
+        let addr = listener.local_addr()?;
+        let (app, tls) = (app.clone(), tls.clone());
+        servers.spawn(async move {
+            match tls {
+                Some(tls) => {
+                    tracing::info!("Listening on https://{}", addr);
+                    axum_server::from_tcp_rustls(listener, tls)
+                        .serve(app.into_make_service())
+                        .await?;
+                }
+                None => {
+                    tracing::info!("Listening on http://{}", addr);
+                    let listener = tokio::net::TcpListener::from_std(listener)?;
+                    axum::serve(listener, app).await?;
+                }
+            }
+            anyhow::Ok(())
+        });
+    }
+    while let Some(result) = servers.join_next().await {
+        result??;
+    }
     Ok(())
 }
@@ -0,0 +1,478 @@
+use alloy::primitives::Address;
+use axum::{extract::State, http::header, response::IntoResponse};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::remote::SigningQueueStats;
+use crate::sign::SignerHealth;
+use crate::timing::Stage;
+use crate::{AppState, PriceDirection};
+
+/// Upper bounds (seconds) of the request duration histogram buckets.
+const DURATION_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Distinct counterparties tracked as their own label value; later ones are counted as
+/// `other` so a flood of fresh addresses can't grow the metrics without bound.
+const MAX_COUNTERPARTIES: usize = 1000;
+
+/// Distinct `X-Taker-Id` values tracked as their own label value, capped like counterparties.
+const MAX_TAKERS: usize = 1000;
+
+/// Labels for a `/context` request, filled in as far as the request got before it
+/// succeeded or failed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RequestLabels {
+    /// `base/quote` of the resolved pair, empty if the request failed before resolving it
+    pub pair: String,
+    pub direction: String,
+    pub counterparty: String,
+    /// Self-reported `X-Taker-Id`, empty when the request didn't send one
+    pub taker: String,
+}
+
+impl RequestLabels {
+    pub fn set_pair(&mut self, base: Address, quote: Address, direction: PriceDirection) {
+        self.pair = format!("{}/{}", base, quote);
+        self.direction = match direction {
+            PriceDirection::AsIs => "as_is",
+            PriceDirection::Inverted => "inverted",
+        }
+        .to_string();
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    buckets: [u64; DURATION_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, secs: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(DURATION_BUCKETS) {
+            if secs <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += secs;
+    }
+}
+
+/// The value at percentile `p` (0-100) of already sorted `values`, by nearest rank.
+pub fn percentile(values: &[f64], p: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let rank = (p / 100.0 * values.len() as f64).ceil() as usize;
+    Some(values[rank.clamp(1, values.len()) - 1])
+}
+
+/// `/context` request counters and latency histograms, rendered in the Prometheus text format.
+#[derive(Default)]
+pub struct Metrics {
+    /// Keyed by labels plus error code (`none` on success)
+    requests: Mutex<BTreeMap<(RequestLabels, String), u64>>,
+    /// Keyed by (pair, direction)
+    durations: Mutex<BTreeMap<(String, String), Histogram>>,
+    /// Time spent in each stage of quoting, across requests
+    stages: Mutex<BTreeMap<Stage, Histogram>>,
+    counterparties: Mutex<HashSet<String>>,
+    takers: Mutex<HashSet<String>>,
+    /// Last signed price at the pair's display decimals, keyed by (pair, direction)
+    signed_prices: Mutex<BTreeMap<(String, String), String>>,
+}
+
+/// Replace `value` with `other` once `max` distinct values have been seen.
+fn cap_cardinality(seen: &Mutex<HashSet<String>>, value: &mut String, max: usize) {
+    if value.is_empty() {
+        return;
+    }
+    let mut seen = seen.lock().unwrap();
+    if !seen.contains(value) {
+        if seen.len() < max {
+            seen.insert(value.clone());
+        } else {
+            *value = "other".to_string();
+        }
+    }
+}
+
+impl Metrics {
+    /// Record a finished `/context` request.
+    pub fn record(&self, mut labels: RequestLabels, error_code: Option<&str>, elapsed: Duration) {
+        cap_cardinality(
+            &self.counterparties,
+            &mut labels.counterparty,
+            MAX_COUNTERPARTIES,
+        );
+        cap_cardinality(&self.takers, &mut labels.taker, MAX_TAKERS);
+
+        self.durations
+            .lock()
+            .unwrap()
+            .entry((labels.pair.clone(), labels.direction.clone()))
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+        *self
+            .requests
+            .lock()
+            .unwrap()
+            .entry((labels, error_code.unwrap_or("none").to_string()))
+            .or_default() += 1;
+    }
+
+    /// Record the time a request spent in each stage.
+    pub fn record_stages(&self, timings: &[(Stage, Duration)]) {
+        let mut stages = self.stages.lock().unwrap();
+        for (stage, elapsed) in timings {
+            stages
+                .entry(*stage)
+                .or_default()
+                .observe(elapsed.as_secs_f64());
+        }
+    }
+
+    /// Record the price just signed for `base`/`quote` in `direction`, already rendered with
+    /// the pair's display decimals.
+    pub fn record_signed_price(
+        &self,
+        base: Address,
+        quote: Address,
+        direction: PriceDirection,
+        price: &str,
+    ) {
+        let mut labels = RequestLabels::default();
+        labels.set_pair(base, quote, direction);
+        self.signed_prices
+            .lock()
+            .unwrap()
+            .insert((labels.pair, labels.direction), price.to_string());
+    }
+
+    /// Total requests that failed with `error_code`, across all labels.
+    pub fn error_count(&self, error_code: &str) -> u64 {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|((_, error), _)| error == error_code)
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// Render every metric in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP oracle_requests_total /context requests by pair, direction, counterparty, taker and error code.\n");
+        out.push_str("# TYPE oracle_requests_total counter\n");
+        for ((labels, error), count) in self.requests.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "oracle_requests_total{{pair=\"{}\",direction=\"{}\",counterparty=\"{}\",taker=\"{}\",error=\"{}\"}} {}",
+                labels.pair, labels.direction, labels.counterparty, labels.taker, error, count
+            );
+        }
+
+        out.push_str("# HELP oracle_request_duration_seconds /context request latency by pair and direction.\n");
+        out.push_str("# TYPE oracle_request_duration_seconds histogram\n");
+        for ((pair, direction), histogram) in self.durations.lock().unwrap().iter() {
+            let labels = format!("pair=\"{}\",direction=\"{}\"", pair, direction);
+            for (bound, count) in DURATION_BUCKETS.iter().zip(histogram.buckets) {
+                let _ = writeln!(
+                    out,
+                    "oracle_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, bound, count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "oracle_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "oracle_request_duration_seconds_sum{{{}}} {}",
+                labels, histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "oracle_request_duration_seconds_count{{{}}} {}",
+                labels, histogram.count
+            );
+        }
+
+        out.push_str("# HELP oracle_request_stage_duration_seconds Time /context requests spent in each stage (decode, price_fetch, pricing, signing, ...).\n");
+        out.push_str("# TYPE oracle_request_stage_duration_seconds histogram\n");
+        for (stage, histogram) in self.stages.lock().unwrap().iter() {
+            let stage = stage.as_str();
+            for (bound, count) in DURATION_BUCKETS.iter().zip(histogram.buckets) {
+                let _ = writeln!(
+                    out,
+                    "oracle_request_stage_duration_seconds_bucket{{stage=\"{}\",le=\"{}\"}} {}",
+                    stage, bound, count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "oracle_request_stage_duration_seconds_bucket{{stage=\"{}\",le=\"+Inf\"}} {}",
+                stage, histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "oracle_request_stage_duration_seconds_sum{{stage=\"{}\"}} {}",
+                stage, histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "oracle_request_stage_duration_seconds_count{{stage=\"{}\"}} {}",
+                stage, histogram.count
+            );
+        }
+
+        let signed_prices = self.signed_prices.lock().unwrap();
+        if !signed_prices.is_empty() {
+            out.push_str("# HELP oracle_signed_price Last signed price by pair and direction, at the pair's display decimals.\n");
+            out.push_str("# TYPE oracle_signed_price gauge\n");
+            for ((pair, direction), price) in signed_prices.iter() {
+                let _ = writeln!(
+                    out,
+                    "oracle_signed_price{{pair=\"{}\",direction=\"{}\"}} {}",
+                    pair, direction, price
+                );
+            }
+        }
+        out
+    }
+}
+
+/// Render failover signer health in the Prometheus text format (nothing without failover).
+pub fn render_signer_health(health: &[SignerHealth]) -> String {
+    let mut out = String::new();
+    if health.is_empty() {
+        return out;
+    }
+    out.push_str("# HELP oracle_signer_healthy Whether each failover chain signer is healthy.\n");
+    out.push_str("# TYPE oracle_signer_healthy gauge\n");
+    for signer in health {
+        let _ = writeln!(
+            out,
+            "oracle_signer_healthy{{signer=\"{}\"}} {}",
+            signer.address,
+            u8::from(signer.healthy)
+        );
+    }
+    out.push_str("# HELP oracle_signer_fallback_total Signatures made by a fallback signer while a higher-priority one was unavailable.\n");
+    out.push_str("# TYPE oracle_signer_fallback_total counter\n");
+    for signer in health {
+        let _ = writeln!(
+            out,
+            "oracle_signer_fallback_total{{signer=\"{}\"}} {}",
+            signer.address, signer.fallback_signatures
+        );
+    }
+    out
+}
+
+/// Render remote signer pool load in the Prometheus text format (nothing without remote
+/// signers).
+pub fn render_signing_queues(queues: &[SigningQueueStats]) -> String {
+    let mut out = String::new();
+    if queues.is_empty() {
+        return out;
+    }
+    out.push_str(
+        "# HELP oracle_signing_in_flight Signing requests a remote signer is working on.\n",
+    );
+    out.push_str("# TYPE oracle_signing_in_flight gauge\n");
+    for queue in queues {
+        let _ = writeln!(
+            out,
+            "oracle_signing_in_flight{{signer=\"{}\"}} {}",
+            queue.address, queue.in_flight
+        );
+    }
+    out.push_str(
+        "# HELP oracle_signing_concurrency Signing requests a remote signer may work on at once.\n",
+    );
+    out.push_str("# TYPE oracle_signing_concurrency gauge\n");
+    for queue in queues {
+        let _ = writeln!(
+            out,
+            "oracle_signing_concurrency{{signer=\"{}\"}} {}",
+            queue.address, queue.concurrency
+        );
+    }
+    out.push_str(
+        "# HELP oracle_signing_queue_depth Signing requests waiting for a remote signer.\n",
+    );
+    out.push_str("# TYPE oracle_signing_queue_depth gauge\n");
+    for queue in queues {
+        let _ = writeln!(
+            out,
+            "oracle_signing_queue_depth{{signer=\"{}\"}} {}",
+            queue.address, queue.queued
+        );
+    }
+    out.push_str("# HELP oracle_signing_rejected_total Signing requests refused because a remote signer's queue was full.\n");
+    out.push_str("# TYPE oracle_signing_rejected_total counter\n");
+    for queue in queues {
+        let _ = writeln!(
+            out,
+            "oracle_signing_rejected_total{{signer=\"{}\"}} {}",
+            queue.address, queue.rejected
+        );
+    }
+    out
+}
+
+/// GET handler — Prometheus scrape endpoint.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    responses((status = 200, description = "Prometheus text format metrics", body = String, content_type = "text/plain")),
+)]
+pub async fn metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render()
+            + &render_signer_health(&state.signers.health())
+            + &render_signing_queues(&state.signers.queue_stats())
+            + &state.fills.render()
+            + &state
+                .circuit_breaker
+                .as_ref()
+                .map(|breaker| breaker.render())
+                .unwrap_or_default()
+            + &state
+                .quote_stream
+                .as_ref()
+                .map(|stream| stream.render())
+                .unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(counterparty: &str) -> RequestLabels {
+        let mut labels = RequestLabels {
+            counterparty: counterparty.to_string(),
+            ..Default::default()
+        };
+        labels.set_pair(
+            Address::repeat_byte(1),
+            Address::repeat_byte(2),
+            PriceDirection::AsIs,
+        );
+        labels
+    }
+
+    #[test]
+    fn test_render_counters_and_histogram() {
+        let metrics = Metrics::default();
+        metrics.record(labels("0xabc"), None, Duration::from_millis(20));
+        metrics.record(labels("0xabc"), None, Duration::from_millis(300));
+        metrics.record(
+            RequestLabels::default(),
+            Some("invalid_body"),
+            Duration::from_millis(1),
+        );
+
+        let out = metrics.render();
+        let pair = format!("{}/{}", Address::repeat_byte(1), Address::repeat_byte(2));
+        assert!(out.contains(&format!(
+            "oracle_requests_total{{pair=\"{}\",direction=\"as_is\",counterparty=\"0xabc\",taker=\"\",error=\"none\"}} 2",
+            pair
+        )));
+        assert!(out.contains(
+            "oracle_requests_total{pair=\"\",direction=\"\",counterparty=\"\",taker=\"\",error=\"invalid_body\"} 1"
+        ));
+        assert_eq!(metrics.error_count("invalid_body"), 1);
+        assert_eq!(metrics.error_count("internal_error"), 0);
+        assert!(out.contains(&format!(
+            "oracle_request_duration_seconds_bucket{{pair=\"{}\",direction=\"as_is\",le=\"0.025\"}} 1",
+            pair
+        )));
+        assert!(out.contains(&format!(
+            "oracle_request_duration_seconds_count{{pair=\"{}\",direction=\"as_is\"}} 2",
+            pair
+        )));
+    }
+
+    #[test]
+    fn test_render_signed_price() {
+        let metrics = Metrics::default();
+        assert!(!metrics.render().contains("oracle_signed_price"));
+        let (base, quote) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        metrics.record_signed_price(base, quote, PriceDirection::AsIs, "0.000400");
+        metrics.record_signed_price(base, quote, PriceDirection::AsIs, "0.000500");
+        assert!(metrics.render().contains(&format!(
+            "oracle_signed_price{{pair=\"{}/{}\",direction=\"as_is\"}} 0.000500",
+            base, quote
+        )));
+    }
+
+    #[test]
+    fn test_percentile() {
+        let values = [1.0, 2.0, 3.0, 4.0];
+        assert_eq!(percentile(&values, 50.0), Some(2.0));
+        assert_eq!(percentile(&values, 99.0), Some(4.0));
+        assert_eq!(percentile(&values, 0.0), Some(1.0));
+        assert_eq!(percentile(&[], 50.0), None);
+    }
+
+    #[test]
+    fn test_render_signer_health() {
+        assert!(render_signer_health(&[]).is_empty());
+        let out = render_signer_health(&[SignerHealth {
+            address: Address::repeat_byte(1),
+            healthy: false,
+            fallback_signatures: 0,
+        }]);
+        assert!(out.contains(&format!(
+            "oracle_signer_healthy{{signer=\"{}\"}} 0",
+            Address::repeat_byte(1)
+        )));
+        assert!(out.contains("# TYPE oracle_signer_fallback_total counter"));
+    }
+
+    #[test]
+    fn test_render_signing_queues() {
+        assert!(render_signing_queues(&[]).is_empty());
+        let out = render_signing_queues(&[SigningQueueStats {
+            address: Address::repeat_byte(1),
+            concurrency: 16,
+            in_flight: 16,
+            queued: 3,
+            rejected: 2,
+        }]);
+        let signer = Address::repeat_byte(1);
+        assert!(out.contains(&format!(
+            "oracle_signing_queue_depth{{signer=\"{}\"}} 3",
+            signer
+        )));
+        assert!(out.contains(&format!(
+            "oracle_signing_rejected_total{{signer=\"{}\"}} 2",
+            signer
+        )));
+    }
+
+    #[test]
+    fn test_counterparty_cardinality_is_capped() {
+        let metrics = Metrics::default();
+        for i in 0..=MAX_COUNTERPARTIES {
+            metrics.record(labels(&i.to_string()), None, Duration::ZERO);
+        }
+        let out = metrics.render();
+        assert!(out.contains("counterparty=\"0\""));
+        assert!(out.contains("counterparty=\"other\""));
+        assert!(!out.contains(&format!("counterparty=\"{}\"", MAX_COUNTERPARTIES)));
+    }
+}
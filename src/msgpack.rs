@@ -0,0 +1,581 @@
+use serde::ser::{self, Serialize};
+
+/// Media type for MessagePack.
+pub const MSGPACK: &str = "application/msgpack";
+
+/// Older, unregistered media type for MessagePack, still sent by many clients.
+pub const X_MSGPACK: &str = "application/x-msgpack";
+
+/// Whether `media` (a `Content-Type` or `Accept` media range, without parameters) is
+/// MessagePack.
+pub fn is_msgpack(media: &str) -> bool {
+    media.eq_ignore_ascii_case(MSGPACK) || media.eq_ignore_ascii_case(X_MSGPACK)
+}
+
+/// Why a value couldn't be encoded or a body couldn't be decoded.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct Error(String);
+
+impl ser::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+/// Encode `value` as MessagePack. Like CBOR responses, the encoding is not human-readable,
+/// so addresses, bytes and IDs are binary rather than hex strings.
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, Error> {
+    let mut serializer = Serializer::default();
+    value.serialize(&mut serializer)?;
+    Ok(serializer.out)
+}
+
+/// Decode a MessagePack array of binary (or string) values, e.g. the ABI-encoded request
+/// bodies of a `/context/batch` request.
+pub fn decode_binary_array(data: &[u8]) -> Result<Vec<Vec<u8>>, Error> {
+    let mut reader = Reader { data };
+    let len = reader.array_len()?;
+    let mut items = Vec::with_capacity(len.min(data.len()));
+    for _ in 0..len {
+        items.push(reader.binary()?.to_vec());
+    }
+    if !reader.data.is_empty() {
+        return Err(Error(format!(
+            "{} trailing bytes after the array",
+            reader.data.len()
+        )));
+    }
+    Ok(items)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.data.len() < n {
+            return Err(Error("Unexpected end of MessagePack data".to_string()));
+        }
+        let (taken, rest) = self.data.split_at(n);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn uint(&mut self, bytes: usize) -> Result<usize, Error> {
+        Ok(self
+            .take(bytes)?
+            .iter()
+            .fold(0, |n, byte| (n << 8) | usize::from(*byte)))
+    }
+
+    fn array_len(&mut self) -> Result<usize, Error> {
+        match self.take(1)?[0] {
+            marker @ 0x90..=0x9f => Ok(usize::from(marker & 0x0f)),
+            0xdc => self.uint(2),
+            0xdd => self.uint(4),
+            marker => Err(Error(format!("Expected an array, found 0x{:02x}", marker))),
+        }
+    }
+
+    fn binary(&mut self) -> Result<&'a [u8], Error> {
+        let len = match self.take(1)?[0] {
+            marker @ 0xa0..=0xbf => usize::from(marker & 0x1f),
+            0xc4 | 0xd9 => self.uint(1)?,
+            0xc5 | 0xda => self.uint(2)?,
+            0xc6 | 0xdb => self.uint(4)?,
+            marker => {
+                return Err(Error(format!(
+                    "Expected binary or a string, found 0x{:02x}",
+                    marker
+                )))
+            }
+        };
+        self.take(len)
+    }
+}
+
+/// A MessagePack serializer writing into a buffer. Arrays and maps are buffered until they
+/// end, so lengths serde doesn't know up front (e.g. flattened structs) are still written in
+/// the header.
+#[derive(Default)]
+struct Serializer {
+    out: Vec<u8>,
+}
+
+impl Serializer {
+    fn uint(&mut self, v: u64) {
+        match v {
+            0..=0x7f => self.out.push(v as u8),
+            0x80..=0xff => self.out.extend([0xcc, v as u8]),
+            0x100..=0xffff => {
+                self.out.push(0xcd);
+                self.out.extend((v as u16).to_be_bytes());
+            }
+            0x1_0000..=0xffff_ffff => {
+                self.out.push(0xce);
+                self.out.extend((v as u32).to_be_bytes());
+            }
+            _ => {
+                self.out.push(0xcf);
+                self.out.extend(v.to_be_bytes());
+            }
+        }
+    }
+
+    fn int(&mut self, v: i64) {
+        if v >= 0 {
+            return self.uint(v as u64);
+        }
+        match v {
+            -32..=-1 => self.out.push(v as u8),
+            -0x80..=-33 => self.out.extend([0xd0, v as u8]),
+            -0x8000..=-0x81 => {
+                self.out.push(0xd1);
+                self.out.extend((v as i16).to_be_bytes());
+            }
+            -0x8000_0000..=-0x8001 => {
+                self.out.push(0xd2);
+                self.out.extend((v as i32).to_be_bytes());
+            }
+            _ => {
+                self.out.push(0xd3);
+                self.out.extend(v.to_be_bytes());
+            }
+        }
+    }
+
+    /// A length-prefixed header: the fix form for short lengths, then 8/16/32-bit forms.
+    fn header(&mut self, len: usize, fix: Option<(u8, usize)>, wide: [Option<u8>; 3]) {
+        if let Some((marker, _)) = fix.filter(|&(_, max)| len <= max) {
+            self.out.push(marker | len as u8);
+        } else if let Some(marker) = wide[0].filter(|_| len <= 0xff) {
+            self.out.extend([marker, len as u8]);
+        } else if let Some(marker) = wide[1].filter(|_| len <= 0xffff) {
+            self.out.push(marker);
+            self.out.extend((len as u16).to_be_bytes());
+        } else if let Some(marker) = wide[2] {
+            self.out.push(marker);
+            self.out.extend((len as u32).to_be_bytes());
+        }
+    }
+
+    fn str(&mut self, v: &str) {
+        self.header(
+            v.len(),
+            Some((0xa0, 31)),
+            [Some(0xd9), Some(0xda), Some(0xdb)],
+        );
+        self.out.extend(v.as_bytes());
+    }
+
+    fn bin(&mut self, v: &[u8]) {
+        self.header(v.len(), None, [Some(0xc4), Some(0xc5), Some(0xc6)]);
+        self.out.extend(v);
+    }
+
+    fn array_header(&mut self, len: usize) {
+        self.header(len, Some((0x90, 15)), [None, Some(0xdc), Some(0xdd)]);
+    }
+
+    fn map_header(&mut self, len: usize) {
+        self.header(len, Some((0x80, 15)), [None, Some(0xde), Some(0xdf)]);
+    }
+
+    fn compound(&mut self, map: bool) -> Compound<'_> {
+        Compound {
+            parent: self,
+            body: Serializer::default(),
+            len: 0,
+            map,
+        }
+    }
+
+    /// `{variant: ...}`, the externally tagged form serde_json uses for data-carrying variants.
+    fn variant(&mut self, variant: &str) {
+        self.map_header(1);
+        self.str(variant);
+    }
+}
+
+/// An array or map being serialized: its entries are counted into `body` and written to
+/// `parent` behind the header when it ends.
+struct Compound<'a> {
+    parent: &'a mut Serializer,
+    body: Serializer,
+    len: usize,
+    map: bool,
+}
+
+impl Compound<'_> {
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut self.body)
+    }
+
+    fn field<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), Error> {
+        self.len += 1;
+        self.body.str(key);
+        value.serialize(&mut self.body)
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        if self.map {
+            self.parent.map_header(self.len);
+        } else {
+            self.parent.array_header(self.len);
+        }
+        self.parent.out.extend(self.body.out);
+        Ok(())
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut Serializer {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.out.push(if v { 0xc3 } else { 0xc2 });
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        self.int(v);
+        Ok(())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), Error> {
+        let v = i64::try_from(v).map_err(|_| Error(format!("{} is out of range", v)))?;
+        self.serialize_i64(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        self.uint(v);
+        Ok(())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), Error> {
+        let v = u64::try_from(v).map_err(|_| Error(format!("{} is out of range", v)))?;
+        self.serialize_u64(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        self.out.push(0xca);
+        self.out.extend(v.to_be_bytes());
+        Ok(())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        self.out.push(0xcb);
+        self.out.extend(v.to_be_bytes());
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.str(v.encode_utf8(&mut [0; 4]));
+        Ok(())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        self.str(v);
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        self.bin(v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.out.push(0xc0);
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.variant(variant);
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'a>, Error> {
+        Ok(self.compound(false))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, Error> {
+        self.variant(variant);
+        Ok(self.compound(false))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'a>, Error> {
+        Ok(self.compound(true))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Compound<'a>, Error> {
+        Ok(self.compound(true))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, Error> {
+        self.variant(variant);
+        Ok(self.compound(true))
+    }
+}
+
+impl ser::SerializeSeq for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.len += 1;
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeMap for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.len += 1;
+        self.element(key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{Address, Bytes};
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Quote {
+        signer: Address,
+        signature: Bytes,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        nonce: Option<String>,
+        expiry: u64,
+    }
+
+    #[test]
+    fn test_to_vec() {
+        let quote = Quote {
+            signer: Address::repeat_byte(1),
+            signature: Bytes::from(vec![2; 3]),
+            nonce: None,
+            expiry: 1700000005,
+        };
+        let mut expected = vec![0x83, 0xa6];
+        expected.extend(b"signer");
+        expected.extend([0xc4, 20]);
+        expected.extend([1; 20]);
+        expected.push(0xa9);
+        expected.extend(b"signature");
+        expected.extend([0xc4, 3, 2, 2, 2]);
+        expected.push(0xa6);
+        expected.extend(b"expiry");
+        expected.push(0xce);
+        expected.extend(1700000005u32.to_be_bytes());
+        assert_eq!(to_vec(&quote).unwrap(), expected);
+
+        assert_eq!(to_vec(&-1i64).unwrap(), [0xff]);
+        assert_eq!(to_vec(&-200i64).unwrap(), [0xd1, 0xff, 0x38]);
+        assert_eq!(to_vec(&vec![0u8; 16]).unwrap()[..3], [0xdc, 0, 16]);
+        let long = "x".repeat(40);
+        assert_eq!(to_vec(&long).unwrap()[..2], [0xd9, 40]);
+    }
+
+    #[test]
+    fn test_decode_binary_array() {
+        let bodies = vec![Bytes::from(vec![1, 2, 3]), Bytes::from(vec![0; 300])];
+        let encoded = to_vec(&bodies).unwrap();
+        assert_eq!(
+            decode_binary_array(&encoded).unwrap(),
+            [vec![1, 2, 3], vec![0; 300]]
+        );
+        // Strings are accepted too, e.g. 0x-prefixed hex
+        assert_eq!(
+            decode_binary_array(&to_vec(&["0x01"]).unwrap()).unwrap(),
+            [b"0x01".to_vec()]
+        );
+        assert!(decode_binary_array(&[0x92, 0xc4, 1, 0]).is_err());
+        assert!(decode_binary_array(&[0x91, 0xc4, 1, 0, 0]).is_err());
+        assert!(decode_binary_array(&[0x81]).is_err());
+    }
+}
@@ -0,0 +1,73 @@
+use alloy::primitives::{FixedBytes, B256};
+use rain_math_float::Float;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// How replay-protection nonces are generated.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum NonceMode {
+    /// Strictly increasing integer, encoded as a Rain DecimalFloat
+    Counter,
+    /// 32 random bytes, for deployments with several replicas signing concurrently
+    Random,
+}
+
+/// Generates the nonce placed in context slot [2].
+pub struct NonceGenerator {
+    mode: NonceMode,
+    counter: AtomicU64,
+}
+
+impl NonceGenerator {
+    /// `start` seeds the counter; passing the startup time in microseconds keeps counter
+    /// nonces increasing across restarts.
+    pub fn new(mode: NonceMode, start: u64) -> Self {
+        Self {
+            mode,
+            counter: AtomicU64::new(start),
+        }
+    }
+
+    /// Next nonce as a context value, plus its display form for the JSON response
+    /// (a decimal string for counters, 0x-hex for random nonces).
+    pub fn next(&self) -> anyhow::Result<(FixedBytes<32>, String)> {
+        match self.mode {
+            NonceMode::Counter => {
+                let n = self.counter.fetch_add(1, Ordering::Relaxed).to_string();
+                let float = Float::parse(n.clone()).map_err(|e| {
+                    anyhow::anyhow!("Failed to parse nonce '{}' as Rain float: {:?}", n, e)
+                })?;
+                let bytes: B256 = float.into();
+                Ok((bytes, n))
+            }
+            NonceMode::Random => {
+                let bytes = B256::random();
+                Ok((bytes, bytes.to_string()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counter_increments() {
+        let nonces = NonceGenerator::new(NonceMode::Counter, 41);
+        let (first, first_str) = nonces.next().unwrap();
+        let (second, second_str) = nonces.next().unwrap();
+        assert_eq!(first_str, "41");
+        assert_eq!(second_str, "42");
+        assert_ne!(first, second);
+        assert_eq!(Float::from(first).format().unwrap(), "41");
+    }
+
+    #[test]
+    fn test_random_nonces_differ() {
+        let nonces = NonceGenerator::new(NonceMode::Random, 0);
+        let (a, a_str) = nonces.next().unwrap();
+        let (b, _) = nonces.next().unwrap();
+        assert_ne!(a, b);
+        assert_eq!(a_str, a.to_string());
+    }
+}
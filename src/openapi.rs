@@ -0,0 +1,98 @@
+use axum::Json;
+use utoipa::OpenApi;
+
+/// OpenAPI description of every route, served at `/openapi.json`.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "rain-oracle-server",
+        description = "Reference signed context oracle server for Raindex orders"
+    ),
+    paths(
+        crate::health,
+        crate::readyz,
+        crate::version::version,
+        crate::status::status,
+        crate::status::signer,
+        crate::pairs::pairs,
+        crate::errors::errors,
+        crate::post_signed_context,
+        crate::jsonrpc::rpc,
+        crate::stream::subscribe,
+        crate::dry_run::dry_run,
+        crate::combos::context_all,
+        crate::batch::context_batch,
+        crate::debug::float,
+        crate::history::price,
+        crate::history::price_at,
+        crate::history::price_history,
+        crate::quota::usage,
+        crate::status::feed_health,
+        crate::get_quote,
+        crate::metrics::metrics,
+        crate::limits::limits,
+        crate::limits::reset,
+        crate::incident::incidents,
+        crate::approval::pending,
+        crate::approval::approve,
+        crate::deployment::validate_config,
+        openapi_json
+    ),
+    components(schemas(
+        crate::ErrorResponse,
+        crate::version::VersionResponse,
+        crate::PriceDirection,
+        crate::oracle::OracleResponse,
+        crate::oracle::CoSignature,
+        crate::oracle::QuotePrice,
+        crate::oracle::Diagnostics,
+        crate::dry_run::DryRunResponse,
+        crate::explain::Explanation,
+        crate::explain::SourcePrice,
+        crate::explain::PricingStep,
+        crate::combos::ComboQuotesResponse,
+        crate::combos::ComboQuote,
+        crate::batch::BatchResponse,
+        crate::batch::BatchResult,
+        crate::dry_run::DryRunCheck,
+        crate::dry_run::CheckStatus,
+        crate::debug::FloatResponse,
+        crate::history::PairPrice,
+        crate::history::PriceHistoryResponse,
+        crate::history::PriceSample,
+        crate::status::StatusResponse,
+        crate::status::SignerResponse,
+        crate::status::RotationStatus,
+        crate::sign::RotationPhase,
+        crate::pairs::PairsResponse,
+        crate::pairs::PairInfo,
+        crate::pairs::ExpiryInfo,
+        crate::pairs::PricingInfo,
+        crate::errors::ErrorCatalog,
+        crate::errors::ErrorInfo,
+        crate::sign::SigningMode,
+        crate::status::FeedHealthResponse,
+        crate::status::FeedHealth,
+        crate::status::FeedState,
+        crate::status::LatencyStats,
+        crate::quota::UsageResponse,
+        crate::quota::DailyUsage,
+        crate::storage::AuditRecord,
+        crate::limits::LimitsResponse,
+        crate::incident::IncidentsResponse,
+        crate::storage::Incident,
+        crate::approval::PendingChange,
+        crate::approval::AdminAction,
+        crate::deployment::ValidationResponse,
+        crate::deployment::ConfigDiff,
+        crate::deployment::DeploymentChange,
+        crate::deployment::FieldChange,
+    ))
+)]
+pub struct ApiDoc;
+
+/// GET handler — the OpenAPI document for this server.
+#[utoipa::path(get, path = "/openapi.json", responses((status = 200, description = "OpenAPI document")))]
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
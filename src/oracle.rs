@@ -2,47 +2,181 @@ use alloy::primitives::{Address, Bytes, FixedBytes};
 use rain_math_float::Float;
 use serde::{Deserialize, Serialize};
 
-use crate::PriceDirection;
+use crate::pipeline::Pipeline;
+use crate::rounding::PriceRounding;
+use crate::{PriceDirection, TokenPairConfig};
 
 /// Oracle response matching the SDK's expected format.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
 pub struct OracleResponse {
+    /// ID for looking the quote up later (also sent as `X-Quote-Id`)
+    #[schema(value_type = String, format = "uuid")]
+    #[cfg_attr(feature = "ts", ts(type = "string"))]
+    pub quote_id: uuid::Uuid,
     /// The signer address (EIP-191 signer of the context data)
+    #[schema(value_type = String, example = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266")]
+    #[cfg_attr(feature = "ts", ts(type = "string"))]
     pub signer: Address,
     /// The signed context data as bytes32[] values (Rain DecimalFloats)
+    #[schema(value_type = Vec<String>)]
+    #[cfg_attr(feature = "ts", ts(type = "Array<string>"))]
     pub context: Vec<FixedBytes<32>>,
     /// The EIP-191 signature over keccak256(abi.encodePacked(context))
+    #[schema(value_type = String)]
+    #[cfg_attr(feature = "ts", ts(type = "string"))]
     pub signature: Bytes,
+    /// Signatures over the same context from the configured co-signers (only when co-signers
+    /// are configured), for schemes requiring M-of-N oracle signatures
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[cfg_attr(feature = "ts", ts(as = "Option<Vec<CoSignature>>", optional))]
+    pub cosignatures: Vec<CoSignature>,
+    /// Replay-protection nonce signed as context[2] (only when nonces are enabled)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub nonce: Option<String>,
+    /// context[0] as signed and at the pair's display decimals (only when the pair has display
+    /// decimals configured)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub price: Option<QuotePrice>,
+    /// Human-readable summary of what was signed (only when diagnostics are enabled)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub debug: Option<Diagnostics>,
+}
+
+/// A co-signer's EIP-191 signature over the response's context.
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct CoSignature {
+    #[schema(value_type = String)]
+    #[cfg_attr(feature = "ts", ts(type = "string"))]
+    pub signer: Address,
+    #[schema(value_type = String)]
+    #[cfg_attr(feature = "ts", ts(type = "string"))]
+    pub signature: Bytes,
+}
+
+/// The signed price two ways, so reconciliation doesn't depend on how Rain floats format.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct QuotePrice {
+    /// context[0] exactly as signed, formatted as a Rain float (e.g. `5e-4`)
+    #[schema(example = "5e-4")]
+    pub signed: String,
+    /// context[0] with the pair's fixed number of decimal places, halves rounding up
+    /// (e.g. `0.000500`)
+    #[schema(example = "0.000500")]
+    pub decimal: String,
+}
+
+impl QuotePrice {
+    /// Render a context price slot as signed and with `decimals` decimal places.
+    pub fn new(price: FixedBytes<32>, decimals: u8) -> anyhow::Result<Self> {
+        let signed = Float::from(alloy::primitives::B256::from(price))
+            .format()
+            .map_err(|e| anyhow::anyhow!("Failed to format signed price: {:?}", e))?;
+        let decimal = crate::rounding::format_fixed(&signed, decimals)?;
+        Ok(Self { signed, decimal })
+    }
+}
+
+/// Human-readable view of a signed context, so integrators can see what was signed
+/// without decoding Rain floats.
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct Diagnostics {
+    /// context[0] decoded back to a decimal string
+    pub price: String,
+    pub direction: PriceDirection,
+    pub feed_id: String,
+    /// Pyth publish time as an ISO 8601 timestamp
+    pub publish_time: String,
+    /// context[1] as an ISO 8601 timestamp (timestamp expiry)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ts", ts(optional))]
+    pub expiry: Option<String>,
+    /// context[1] as a block number (block-number expiry)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ts", ts(as = "Option<u32>", optional))]
+    pub expiry_block: Option<u64>,
+}
+
+/// Format a unix timestamp (seconds) as an ISO 8601 UTC string.
+pub fn format_unix_timestamp(secs: i64) -> String {
+    chrono::DateTime::from_timestamp(secs, 0)
+        .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+        .unwrap_or_else(|| secs.to_string())
+}
+
+impl Diagnostics {
+    /// Build diagnostics from a context produced by [`build_context`].
+    pub fn new(
+        context: &[FixedBytes<32>],
+        direction: PriceDirection,
+        feed_id: &str,
+        publish_time: i64,
+        expiry: u64,
+        expiry_is_block: bool,
+    ) -> anyhow::Result<Self> {
+        let price_bytes = context
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("Context has no price slot"))?;
+        let price = Float::from(alloy::primitives::B256::from(*price_bytes))
+            .format()
+            .map_err(|e| anyhow::anyhow!("Failed to format signed price: {:?}", e))?;
+
+        Ok(Self {
+            price,
+            direction,
+            feed_id: feed_id.to_string(),
+            publish_time: format_unix_timestamp(publish_time),
+            expiry: (!expiry_is_block).then(|| format_unix_timestamp(expiry as i64)),
+            expiry_block: expiry_is_block.then_some(expiry),
+        })
+    }
 }
 
 /// Format a Pyth price (coefficient * 10^expo) as a decimal string for Float::parse.
 ///
 /// e.g. price=310012345678, expo=-8 => "3100.12345678"
-fn format_pyth_price(price: i64, expo: i32) -> String {
+///
+/// Runs on every quote, so it writes straight into one buffer sized up front.
+pub fn format_pyth_price(price: i128, expo: i32) -> String {
+    let digits = price.unsigned_abs().to_string();
+    let abs_expo = expo.unsigned_abs() as usize;
+    // Sign, digits, and up to "0." plus padding zeros or the trailing zeros
+    let mut s = String::with_capacity(digits.len() + abs_expo + 3);
+    if price < 0 {
+        s.push('-');
+    }
     if expo >= 0 {
-        let mut s = price.to_string();
-        for _ in 0..expo {
-            s.push('0');
-        }
-        s
+        s.push_str(&digits);
+        s.extend(std::iter::repeat_n('0', abs_expo));
+    } else if digits.len() <= abs_expo {
+        s.push_str("0.");
+        s.extend(std::iter::repeat_n('0', abs_expo - digits.len()));
+        s.push_str(&digits);
     } else {
-        let abs_expo = (-expo) as usize;
-        let is_negative = price < 0;
-        let digits = price.unsigned_abs().to_string();
-
-        if digits.len() <= abs_expo {
-            let zeros = abs_expo - digits.len();
-            let prefix = if is_negative { "-0." } else { "0." };
-            format!("{}{}{}", prefix, "0".repeat(zeros), digits)
-        } else {
-            let split_pos = digits.len() - abs_expo;
-            let prefix = if is_negative { "-" } else { "" };
-            format!("{}{}.{}", prefix, &digits[..split_pos], &digits[split_pos..])
-        }
+        let split_pos = digits.len() - abs_expo;
+        s.push_str(&digits[..split_pos]);
+        s.push('.');
+        s.push_str(&digits[split_pos..]);
     }
+    s
 }
 
-/// Build the context array from a Pyth price and expiry timestamp.
+/// The feed returned a price that can't be signed: zero would make the inverted price a
+/// division by zero, and a negative exchange rate is meaningless.
+#[derive(Debug, thiserror::Error)]
+#[error("Upstream price {} is not positive", format_pyth_price(*.price, *.expo))]
+pub struct NonPositivePrice {
+    pub price: i128,
+    pub expo: i32,
+}
+
+/// Build the context array from a Pyth price and expiry.
 ///
 /// All values are encoded as Rain DecimalFloats (bytes32) via Float::parse.
 ///
@@ -52,27 +186,109 @@ fn format_pyth_price(price: i64, expo: i32) -> String {
 ///
 /// Context layout:
 /// - [0]: price as Rain DecimalFloat
-/// - [1]: expiry timestamp as Rain DecimalFloat
+/// - [1]: expiry (unix timestamp, or block number for block-based expiry) as Rain DecimalFloat
+///
+/// Fails with [`NonPositivePrice`] if the price is zero or negative.
 pub fn build_context(
-    price: i64,
+    price: i128,
+    expo: i32,
+    expiry: u64,
+    direction: PriceDirection,
+) -> Result<Vec<FixedBytes<32>>, anyhow::Error> {
+    Ok(context_from_feed(
+        price,
+        expo,
+        expiry,
+        direction,
+        false,
+        None,
+        &Pipeline::default(),
+    )?
+    .0)
+}
+
+/// [`build_context`] for a configured pair: the feed price is flipped first if the pair's
+/// feed is quoted the other way round, rounded to the pair's precision, then run through the
+/// pair's pipeline (which does any inversion).
+pub fn build_pair_context(
+    price: i128,
     expo: i32,
     expiry: u64,
     direction: PriceDirection,
+    pair: &TokenPairConfig,
 ) -> Result<Vec<FixedBytes<32>>, anyhow::Error> {
+    Ok(build_pair_context_explained(price, expo, expiry, direction, pair)?.0)
+}
+
+/// Pricing steps with the price after each, e.g. `("mul 0.999", 3097.02)`.
+pub type PriceSteps = Vec<(String, Float)>;
+
+/// [`build_pair_context`], also returning the price after the feed flip and each pipeline
+/// stage.
+pub fn build_pair_context_explained(
+    price: i128,
+    expo: i32,
+    expiry: u64,
+    direction: PriceDirection,
+    pair: &TokenPairConfig,
+) -> Result<(Vec<FixedBytes<32>>, PriceSteps), anyhow::Error> {
+    context_from_feed(
+        price,
+        expo,
+        expiry,
+        direction,
+        pair.feed_inverted,
+        pair.rounding.as_ref(),
+        &pair.pipeline,
+    )
+}
+
+/// A Pyth price as a Rain float in quote-per-base terms: flipped (1/price) when the feed is
+/// quoted the other way round (e.g. USD/JPY for a JPY-quoted pair).
+///
+/// Fails with [`NonPositivePrice`] if the price is zero or negative.
+pub fn feed_price(price: i128, expo: i32, feed_inverted: bool) -> anyhow::Result<Float> {
+    if price <= 0 {
+        return Err(NonPositivePrice { price, expo }.into());
+    }
+
     let price_str = format_pyth_price(price, expo);
     let price_float = Float::parse(price_str.clone())
         .map_err(|e| anyhow::anyhow!("Failed to parse price '{}' as Rain float: {:?}", price_str, e))?;
+    if !feed_inverted {
+        return Ok(price_float);
+    }
+    price_float
+        .inv()
+        .map_err(|e| anyhow::anyhow!("Failed to flip feed price: {:?}", e))
+}
 
-    // Apply direction — invert if needed
-    let final_price = match direction {
-        PriceDirection::AsIs => price_float,
-        PriceDirection::Inverted => {
-            let one = Float::parse("1".to_string())
-                .map_err(|e| anyhow::anyhow!("Failed to parse '1' as Rain float: {:?}", e))?;
-            (one / price_float)
-                .map_err(|e| anyhow::anyhow!("Failed to invert price: {:?}", e))?
-        }
-    };
+fn context_from_feed(
+    price: i128,
+    expo: i32,
+    expiry: u64,
+    direction: PriceDirection,
+    feed_inverted: bool,
+    rounding: Option<&PriceRounding>,
+    pipeline: &Pipeline,
+) -> Result<(Vec<FixedBytes<32>>, PriceSteps), anyhow::Error> {
+    let mut price_float = feed_price(price, expo, feed_inverted)?;
+    let mut steps = Vec::new();
+    if feed_inverted {
+        steps.push(("flip feed".to_string(), price_float));
+    }
+    // Ticks and significant digits are meant in the feed's terms (e.g. 0.05 USD per ETH), so
+    // round before inversion and decimal scaling, which would make them meaningless
+    if let Some(rounding) = rounding {
+        price_float = rounding.for_direction(direction).round(price_float)?;
+        steps.push((format!("round {}", rounding), price_float));
+    }
+    let (final_price, stages) = pipeline.trace(price_float, direction)?;
+    steps.extend(
+        stages
+            .into_iter()
+            .map(|(stage, price)| (stage.to_string(), price)),
+    );
 
     let expiry_str = expiry.to_string();
     let expiry_float = Float::parse(expiry_str.clone())
@@ -81,16 +297,150 @@ pub fn build_context(
     let price_bytes: alloy::primitives::B256 = final_price.into();
     let expiry_bytes: alloy::primitives::B256 = expiry_float.into();
 
-    Ok(vec![
-        FixedBytes::from(price_bytes),
-        FixedBytes::from(expiry_bytes),
-    ])
+    Ok((
+        vec![
+            FixedBytes::from(price_bytes),
+            FixedBytes::from(expiry_bytes),
+        ],
+        steps,
+    ))
+}
+
+/// Multiply a context price by `10^decimal_shift`, turning a human price into a ratio of
+/// raw token amounts (e.g. a shift of -12 for USDC (6) per WETH (18)).
+pub fn scale_price(price: FixedBytes<32>, decimal_shift: i32) -> anyhow::Result<FixedBytes<32>> {
+    if decimal_shift == 0 {
+        return Ok(price);
+    }
+    let factor_str = format_pyth_price(1, decimal_shift);
+    let factor = Float::parse(factor_str.clone()).map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to parse scale '{}' as Rain float: {:?}",
+            factor_str,
+            e
+        )
+    })?;
+    let scaled = (Float::from(alloy::primitives::B256::from(price)) * factor)
+        .map_err(|e| anyhow::anyhow!("Failed to scale price: {:?}", e))?;
+    let bytes: alloy::primitives::B256 = scaled.into();
+    Ok(FixedBytes::from(bytes))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_build_context_rejects_non_positive_price() {
+        for price in [0, -310012345678] {
+            for direction in [PriceDirection::AsIs, PriceDirection::Inverted] {
+                let err = build_context(price, -8, 1700000005, direction).unwrap_err();
+                let err = err.downcast_ref::<NonPositivePrice>().unwrap();
+                assert_eq!(err.price, price);
+            }
+        }
+        let err = build_context(-31, -5, 1700000005, PriceDirection::AsIs).unwrap_err();
+        assert_eq!(err.to_string(), "Upstream price -0.00031 is not positive");
+    }
+
+    #[test]
+    fn test_build_pair_context_feed_inverted() {
+        // A USD/JPY feed at 150 for a pair quoted in USD: 1/150 USD per JPY as-is
+        let pair = TokenPairConfig::new(
+            "0x4200000000000000000000000000000000000006",
+            "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+        )
+        .unwrap()
+        .with_feed_inverted(true);
+        let price = |direction| {
+            let context = build_pair_context(150, 0, 1700000005, direction, &pair).unwrap();
+            Float::from(alloy::primitives::B256::from(context[0]))
+        };
+        let expected = (Float::parse("1".to_string()).unwrap()
+            / Float::parse("150".to_string()).unwrap())
+        .unwrap();
+        assert!(price(PriceDirection::AsIs).eq(expected).unwrap());
+        assert!(price(PriceDirection::Inverted)
+            .eq(Float::parse("150".to_string()).unwrap())
+            .unwrap());
+        assert!(build_pair_context(0, 0, 1700000005, PriceDirection::AsIs, &pair).is_err());
+    }
+
+    #[test]
+    fn test_build_pair_context_rounding() {
+        // A 0.05 tick is in USD per ETH whichever way the order trades
+        let rounding = crate::rounding::PriceRounding::tick_size(
+            "0.05",
+            crate::rounding::RoundingDirection::Up,
+        )
+        .unwrap();
+        let pair = TokenPairConfig::new(
+            "0x4200000000000000000000000000000000000006",
+            "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+        )
+        .unwrap()
+        .with_rounding(Some(rounding));
+        let price = |direction| {
+            let context =
+                build_pair_context(310012345678, -8, 1700000005, direction, &pair).unwrap();
+            Float::from(alloy::primitives::B256::from(context[0]))
+        };
+        let float = |s: &str| Float::parse(s.to_string()).unwrap();
+        assert!(price(PriceDirection::AsIs).eq(float("3100.15")).unwrap());
+        // Rounded down before inverting, so the inverted price still rounds against the taker
+        let expected = (float("1") / float("3100.1")).unwrap();
+        assert!(price(PriceDirection::Inverted).eq(expected).unwrap());
+    }
+
+    #[test]
+    fn test_scale_price() {
+        let price: alloy::primitives::B256 = Float::parse("1900".to_string()).unwrap().into();
+        let price = FixedBytes::from(price);
+        assert_eq!(scale_price(price, 0).unwrap(), price);
+
+        let down = Float::from(alloy::primitives::B256::from(
+            scale_price(price, -12).unwrap(),
+        ));
+        let expected = Float::parse("0.0000000019".to_string()).unwrap();
+        assert!(down.eq(expected).unwrap());
+
+        let up = Float::from(alloy::primitives::B256::from(
+            scale_price(price, 12).unwrap(),
+        ));
+        let expected = Float::parse("1900000000000000".to_string()).unwrap();
+        assert!(up.eq(expected).unwrap());
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_format_pyth_price_preserves_digits(price: i128, expo in -40i32..40) {
+            let s = format_pyth_price(price, expo);
+            proptest::prop_assert_eq!(s.starts_with('-'), price < 0);
+
+            let unsigned = s.trim_start_matches('-');
+            let (int_part, frac_part) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+            proptest::prop_assert_eq!(frac_part.len(), if expo < 0 { (-expo) as usize } else { 0 });
+
+            // Dropping the decimal point gives back the coefficient, scaled up for positive expos.
+            let joined = format!("{}{}", int_part, frac_part);
+            let mut expected = price.unsigned_abs().to_string();
+            if expo > 0 {
+                expected.push_str(&"0".repeat(expo as usize));
+            }
+            proptest::prop_assert_eq!(joined.trim_start_matches('0'), expected.trim_start_matches('0'));
+        }
+    }
+
+    #[test]
+    fn test_format_pyth_price_beyond_i64() {
+        let price = i64::MAX as i128 * 1000;
+        assert_eq!(format_pyth_price(price, -3), i64::MAX.to_string() + ".000");
+        assert_eq!(
+            format_pyth_price(i128::MIN, -2),
+            "-1701411834604692317316873037158841057.28"
+        );
+    }
+
     #[test]
     fn test_format_pyth_price_typical() {
         assert_eq!(format_pyth_price(310012345678, -8), "3100.12345678");
@@ -133,6 +483,30 @@ mod tests {
         assert_eq!(formatted, "5e-4"); // 1/2000 = 0.0005
     }
 
+    #[test]
+    fn test_format_unix_timestamp() {
+        assert_eq!(format_unix_timestamp(1700000000), "2023-11-14T22:13:20Z");
+        assert_eq!(format_unix_timestamp(0), "1970-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn test_diagnostics_from_context() {
+        let ctx = build_context(310012345678, -8, 1700000000, PriceDirection::AsIs).unwrap();
+        let diagnostics = Diagnostics::new(
+            &ctx,
+            PriceDirection::AsIs,
+            "ff61",
+            1699999999,
+            1700000000,
+            false,
+        )
+        .unwrap();
+        assert_eq!(diagnostics.price, "3100.12345678");
+        assert_eq!(diagnostics.publish_time, "2023-11-14T22:13:19Z");
+        assert_eq!(diagnostics.expiry.as_deref(), Some("2023-11-14T22:13:20Z"));
+        assert_eq!(diagnostics.expiry_block, None);
+    }
+
     #[test]
     fn test_build_context_expiry_roundtrip() {
         let ctx = build_context(310012345678, -8, 1700000000, PriceDirection::AsIs).unwrap();
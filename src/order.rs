@@ -0,0 +1,502 @@
+use alloy::primitives::{keccak256, Address, B256, U256};
+use alloy::sol;
+use alloy::sol_types::{SolType, SolValue};
+use std::str::FromStr;
+
+use crate::OracleRequestError;
+
+/// Request header clients can set to pin the order ABI version instead of relying on detection.
+pub const ORDER_VERSION_HEADER: &str = "x-order-version";
+
+// Minimal OrderV4/OrderV3 definitions and orderbook events for ABI decoding — avoids pulling
+// in rain_orderbook_bindings.
+sol! {
+    struct IOV2 {
+        address token;
+        bytes32 vaultId;
+    }
+
+    struct EvaluableV4 {
+        address interpreter;
+        address store;
+        bytes bytecode;
+    }
+
+    struct OrderV4 {
+        address owner;
+        EvaluableV4 evaluable;
+        IOV2[] validInputs;
+        IOV2[] validOutputs;
+        bytes32 nonce;
+    }
+
+    struct IO {
+        address token;
+        uint8 decimals;
+        uint256 vaultId;
+    }
+
+    struct EvaluableV3 {
+        address interpreter;
+        address store;
+        bytes bytecode;
+    }
+
+    struct OrderV3 {
+        address owner;
+        EvaluableV3 evaluable;
+        IO[] validInputs;
+        IO[] validOutputs;
+        bytes32 nonce;
+    }
+
+    struct SignedContextV1 {
+        address signer;
+        bytes32[] context;
+        bytes signature;
+    }
+
+    struct TakeOrderConfigV4 {
+        OrderV4 order;
+        uint256 inputIOIndex;
+        uint256 outputIOIndex;
+        SignedContextV1[] signedContext;
+    }
+
+    struct TakeOrderConfigV3 {
+        OrderV3 order;
+        uint256 inputIOIndex;
+        uint256 outputIOIndex;
+        SignedContextV1[] signedContext;
+    }
+
+    /// OrderBookV5: `input`/`output` are Rain floats, from the taker's perspective.
+    event TakeOrderV3(address sender, TakeOrderConfigV4 config, bytes32 input, bytes32 output);
+
+    /// OrderBookV4: `input`/`output` are token amounts, from the taker's perspective.
+    event TakeOrderV2(address sender, TakeOrderConfigV3 config, uint256 input, uint256 output);
+
+    event AddOrderV3(address sender, bytes32 orderHash, OrderV4 order);
+    event AddOrderV2(address sender, bytes32 orderHash, OrderV3 order);
+    event RemoveOrderV3(address sender, bytes32 orderHash, OrderV4 order);
+    event RemoveOrderV2(address sender, bytes32 orderHash, OrderV3 order);
+}
+
+/// Decoded POST body: (OrderV4, uint256 inputIOIndex, uint256 outputIOIndex, address counterparty)
+type OracleRequestBodyV4 = (OrderV4, U256, U256, Address);
+
+/// Decoded POST body for orderbooks still on the previous ABI:
+/// (OrderV3, uint256 inputIOIndex, uint256 outputIOIndex, address counterparty)
+type OracleRequestBodyV3 = (OrderV3, U256, U256, Address);
+
+/// Size limits enforced on `/context` requests, so pathological orders can't burn CPU in
+/// ABI decoding or downstream processing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RequestLimits {
+    /// Maximum ABI-encoded body size, checked before decoding
+    pub max_body_bytes: usize,
+    /// Maximum length of each of `validInputs` and `validOutputs`
+    pub max_ios: usize,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: 64 * 1024,
+            max_ios: 32,
+        }
+    }
+}
+
+impl RequestLimits {
+    /// Reject an encoded body before it is decoded.
+    pub fn check_body(&self, body: &[u8]) -> Result<(), OracleRequestError> {
+        if body.len() > self.max_body_bytes {
+            return Err(OracleRequestError::OrderTooLarge(format!(
+                "body is {} bytes (limit {})",
+                body.len(),
+                self.max_body_bytes
+            )));
+        }
+        Ok(())
+    }
+
+    /// Reject a decoded order with too many IOs.
+    pub fn check_order(&self, order: &Order) -> Result<(), OracleRequestError> {
+        for (kind, len) in [
+            ("validInputs", order.valid_inputs.len()),
+            ("validOutputs", order.valid_outputs.len()),
+        ] {
+            if len > self.max_ios {
+                return Err(OracleRequestError::OrderTooLarge(format!(
+                    "{} has {} entries (limit {})",
+                    kind, len, self.max_ios
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Orderbook ABI version of the order in a request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrderVersion {
+    V3,
+    V4,
+}
+
+impl FromStr for OrderVersion {
+    type Err = OracleRequestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "3" | "v3" => Ok(Self::V3),
+            "4" | "v4" => Ok(Self::V4),
+            _ => Err(OracleRequestError::InvalidOrderVersion(s.to_string())),
+        }
+    }
+}
+
+/// An order IO, normalised across ABI versions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Io {
+    pub token: Address,
+    /// Token decimals as declared in the order (OrderV3 only — V4 dropped the field).
+    pub decimals: Option<u8>,
+    /// The owner's vault the order moves this token in or out of
+    pub vault_id: B256,
+}
+
+/// The parts of an order the oracle cares about, normalised across ABI versions.
+#[derive(Debug, Clone)]
+pub struct Order {
+    pub version: OrderVersion,
+    pub owner: Address,
+    /// keccak256 of the ABI-encoded order, as the orderbook identifies it
+    pub hash: B256,
+    /// keccak256 of the order's expression bytecode
+    pub bytecode_hash: B256,
+    pub valid_inputs: Vec<Io>,
+    pub valid_outputs: Vec<Io>,
+    /// The owner's nonce distinguishing otherwise identical orders
+    pub nonce: B256,
+}
+
+impl From<OrderV4> for Order {
+    fn from(order: OrderV4) -> Self {
+        let io = |io: &IOV2| Io {
+            token: io.token,
+            decimals: None,
+            vault_id: io.vaultId,
+        };
+        Self {
+            version: OrderVersion::V4,
+            owner: order.owner,
+            hash: keccak256(order.abi_encode()),
+            bytecode_hash: keccak256(&order.evaluable.bytecode),
+            valid_inputs: order.validInputs.iter().map(io).collect(),
+            valid_outputs: order.validOutputs.iter().map(io).collect(),
+            nonce: order.nonce,
+        }
+    }
+}
+
+impl From<OrderV3> for Order {
+    fn from(order: OrderV3) -> Self {
+        let io = |io: &IO| Io {
+            token: io.token,
+            decimals: Some(io.decimals),
+            vault_id: io.vaultId.into(),
+        };
+        Self {
+            version: OrderVersion::V3,
+            owner: order.owner,
+            hash: keccak256(order.abi_encode()),
+            bytecode_hash: keccak256(&order.evaluable.bytecode),
+            valid_inputs: order.validInputs.iter().map(io).collect(),
+            valid_outputs: order.validOutputs.iter().map(io).collect(),
+            nonce: order.nonce,
+        }
+    }
+}
+
+/// A decoded `/context` request.
+#[derive(Debug, Clone)]
+pub struct OracleRequest {
+    pub order: Order,
+    pub input_io_index: U256,
+    pub output_io_index: U256,
+    pub counterparty: Address,
+}
+
+impl From<OracleRequestBodyV4> for OracleRequest {
+    fn from((order, input_io_index, output_io_index, counterparty): OracleRequestBodyV4) -> Self {
+        Self {
+            order: order.into(),
+            input_io_index,
+            output_io_index,
+            counterparty,
+        }
+    }
+}
+
+impl From<OracleRequestBodyV3> for OracleRequest {
+    fn from((order, input_io_index, output_io_index, counterparty): OracleRequestBodyV3) -> Self {
+        Self {
+            order: order.into(),
+            input_io_index,
+            output_io_index,
+            counterparty,
+        }
+    }
+}
+
+/// Decode `body` as `T` only if it is exactly the canonical encoding of the result.
+///
+/// Lenient decoding ignores trailing data and tolerates inconsistent offsets, so a body
+/// in one layout can "succeed" as another with garbage fields; re-encoding catches that.
+fn decode_exact<T>(body: &[u8]) -> Option<T>
+where
+    T: SolValue + From<<T::SolType as SolType>::RustType>,
+{
+    T::abi_decode(body)
+        .ok()
+        .filter(|value| value.abi_encode() == body)
+}
+
+/// Decode an ABI-encoded request body.
+///
+/// With an explicit version hint only that layout is tried. Without one, the body must be
+/// the exact encoding of a V4 or else a V3 request — a V3 body can leniently decode as V4
+/// with garbage token addresses since the IO structs differ in width. If neither matches
+/// exactly, fall back to a lenient V4 decode so errors look the same as before V3 support.
+pub fn decode_request(
+    body: &[u8],
+    version: Option<OrderVersion>,
+) -> Result<OracleRequest, OracleRequestError> {
+    let invalid = |e: alloy::sol_types::Error| OracleRequestError::InvalidBody(e.to_string());
+    match version {
+        Some(OrderVersion::V4) => OracleRequestBodyV4::abi_decode(body)
+            .map(Into::into)
+            .map_err(invalid),
+        Some(OrderVersion::V3) => OracleRequestBodyV3::abi_decode(body)
+            .map(Into::into)
+            .map_err(invalid),
+        None => {
+            if let Some(decoded) = decode_exact::<OracleRequestBodyV4>(body) {
+                return Ok(decoded.into());
+            }
+            if let Some(decoded) = decode_exact::<OracleRequestBodyV3>(body) {
+                return Ok(decoded.into());
+            }
+            OracleRequestBodyV4::abi_decode(body)
+                .map(Into::into)
+                .map_err(invalid)
+        }
+    }
+}
+
+/// ABI-encode a V4 `/context` body for a minimal order taking `input_token` and giving
+/// `output_token`, e.g. to exercise a server without a real order.
+pub fn encode_request(
+    input_token: Address,
+    output_token: Address,
+    counterparty: Address,
+) -> Vec<u8> {
+    rain_oracle_client::encode_minimal_request(input_token, output_token, counterparty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{Bytes, FixedBytes};
+    use proptest::prelude::*;
+
+    const WETH: Address = Address::new([0x42; 20]);
+    const USDC: Address = Address::new([0x83; 20]);
+
+    fn order_v4() -> OrderV4 {
+        OrderV4 {
+            owner: Address::ZERO,
+            evaluable: EvaluableV4 {
+                interpreter: Address::ZERO,
+                store: Address::ZERO,
+                bytecode: Bytes::from(vec![1, 2, 3]),
+            },
+            validInputs: vec![IOV2 {
+                token: USDC,
+                vaultId: FixedBytes::ZERO,
+            }],
+            validOutputs: vec![IOV2 {
+                token: WETH,
+                vaultId: FixedBytes::ZERO,
+            }],
+            nonce: FixedBytes::ZERO,
+        }
+    }
+
+    fn order_v3() -> OrderV3 {
+        OrderV3 {
+            owner: Address::ZERO,
+            evaluable: EvaluableV3 {
+                interpreter: Address::ZERO,
+                store: Address::ZERO,
+                bytecode: Bytes::from(vec![1, 2, 3]),
+            },
+            validInputs: vec![IO {
+                token: USDC,
+                decimals: 6,
+                vaultId: U256::from(1),
+            }],
+            validOutputs: vec![IO {
+                token: WETH,
+                decimals: 18,
+                vaultId: U256::from(1),
+            }],
+            nonce: FixedBytes::ZERO,
+        }
+    }
+
+    fn encode_v4() -> Vec<u8> {
+        (order_v4(), U256::ZERO, U256::ZERO, Address::ZERO).abi_encode()
+    }
+
+    fn encode_v3() -> Vec<u8> {
+        (order_v3(), U256::ZERO, U256::ZERO, Address::ZERO).abi_encode()
+    }
+
+    #[test]
+    fn test_order_version_from_str() {
+        assert_eq!("4".parse::<OrderVersion>().unwrap(), OrderVersion::V4);
+        assert_eq!("V3".parse::<OrderVersion>().unwrap(), OrderVersion::V3);
+        assert!("5".parse::<OrderVersion>().is_err());
+    }
+
+    #[test]
+    fn test_decode_v4_detected() {
+        let req = decode_request(&encode_v4(), None).unwrap();
+        assert_eq!(req.order.version, OrderVersion::V4);
+        assert_eq!(req.order.valid_inputs[0].token, USDC);
+        assert_eq!(req.order.valid_outputs[0].token, WETH);
+        assert_eq!(req.order.valid_inputs[0].decimals, None);
+        assert_eq!(req.order.hash, keccak256(order_v4().abi_encode()));
+    }
+
+    #[test]
+    fn test_decode_v3_detected() {
+        let req = decode_request(&encode_v3(), None).unwrap();
+        assert_eq!(req.order.version, OrderVersion::V3);
+        assert_eq!(req.order.valid_inputs[0].token, USDC);
+        assert_eq!(req.order.valid_outputs[0].token, WETH);
+        assert_eq!(req.order.valid_inputs[0].decimals, Some(6));
+        assert_eq!(req.order.valid_outputs[0].decimals, Some(18));
+    }
+
+    #[test]
+    fn test_decode_with_version_hint() {
+        let req = decode_request(&encode_v3(), Some(OrderVersion::V3)).unwrap();
+        assert_eq!(req.order.version, OrderVersion::V3);
+
+        let req = decode_request(&encode_v4(), Some(OrderVersion::V4)).unwrap();
+        assert_eq!(req.order.version, OrderVersion::V4);
+    }
+
+    #[test]
+    fn test_encode_request() {
+        let request =
+            decode_request(&encode_request(USDC, WETH, Address::repeat_byte(7)), None).unwrap();
+        assert_eq!(request.order.version, OrderVersion::V4);
+        assert_eq!(request.order.valid_inputs[0].token, USDC);
+        assert_eq!(request.order.valid_outputs[0].token, WETH);
+        assert_eq!(request.counterparty, Address::repeat_byte(7));
+    }
+
+    #[test]
+    fn test_decode_garbage() {
+        let err = decode_request(&[1, 2, 3], None).unwrap_err();
+        assert!(matches!(err, OracleRequestError::InvalidBody(_)));
+    }
+
+    #[test]
+    fn test_request_limits() {
+        let limits = RequestLimits {
+            max_body_bytes: encode_v4().len(),
+            max_ios: 1,
+        };
+        let body = encode_v4();
+        limits.check_body(&body).unwrap();
+        let order = decode_request(&body, None).unwrap().order;
+        limits.check_order(&order).unwrap();
+
+        let mut two_inputs = order_v4();
+        two_inputs
+            .validInputs
+            .push(two_inputs.validInputs[0].clone());
+        let body = (two_inputs, U256::ZERO, U256::ZERO, Address::ZERO).abi_encode();
+        assert!(matches!(
+            limits.check_body(&body),
+            Err(OracleRequestError::OrderTooLarge(_))
+        ));
+        let order = decode_request(&body, None).unwrap().order;
+        let err = limits.check_order(&order).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Order too large: validInputs has 2 entries (limit 1)"
+        );
+    }
+
+    fn version_hint() -> impl Strategy<Value = Option<OrderVersion>> {
+        proptest::option::of(proptest::sample::select(vec![
+            OrderVersion::V3,
+            OrderVersion::V4,
+        ]))
+    }
+
+    proptest! {
+        #[test]
+        fn prop_decode_arbitrary_bytes(body in proptest::collection::vec(any::<u8>(), 0..2048), version in version_hint()) {
+            if let Err(err) = decode_request(&body, version) {
+                prop_assert!(matches!(err, OracleRequestError::InvalidBody(_)));
+            }
+        }
+
+        /// Valid encodings with individual bytes overwritten and/or truncated.
+        #[test]
+        fn prop_decode_corrupted_encoding(
+            v3 in any::<bool>(),
+            patches in proptest::collection::vec((any::<usize>(), any::<u8>()), 0..16),
+            truncate in proptest::option::of(any::<usize>()),
+            version in version_hint(),
+        ) {
+            let mut body = if v3 { encode_v3() } else { encode_v4() };
+            for (index, byte) in patches {
+                let len = body.len();
+                body[index % len] = byte;
+            }
+            if let Some(len) = truncate {
+                body.truncate(len % (body.len() + 1));
+            }
+            let _ = decode_request(&body, version);
+        }
+
+        /// Valid encodings with a whole word replaced, e.g. an offset or length pointing far
+        /// past the end of the body.
+        #[test]
+        fn prop_decode_adversarial_words(
+            v3 in any::<bool>(),
+            word in any::<usize>(),
+            value in prop_oneof![
+                Just(U256::MAX),
+                Just(U256::from(u64::MAX)),
+                Just(U256::from(u32::MAX)),
+                any::<u64>().prop_map(U256::from),
+            ],
+            version in version_hint(),
+        ) {
+            let mut body = if v3 { encode_v3() } else { encode_v4() };
+            let start = (word % (body.len() / 32)) * 32;
+            body[start..start + 32].copy_from_slice(&value.to_be_bytes::<32>());
+            let _ = decode_request(&body, version);
+        }
+    }
+}
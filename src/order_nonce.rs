@@ -0,0 +1,122 @@
+use alloy::primitives::{Address, B256};
+use std::time::Duration;
+
+use crate::order::Order;
+use crate::storage::NonceUse;
+use crate::{AppError, AppState, OracleRequestError};
+
+/// An order's owner, nonce and expression, as checked by the nonce freshness policy.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct OrderNonce {
+    owner: Address,
+    nonce: B256,
+    bytecode_hash: B256,
+}
+
+impl From<&Order> for OrderNonce {
+    fn from(order: &Order) -> Self {
+        Self {
+            owner: order.owner,
+            nonce: order.nonce,
+            bytecode_hash: order.bytecode_hash,
+        }
+    }
+}
+
+/// Whether `used` is the owner's nonce turning up with a different expression within `window`
+/// of its `previous` use.
+fn conflicts(previous: &NonceUse, used: &NonceUse, window: Duration) -> bool {
+    let recent = used.seen_at - previous.seen_at < window.as_secs() as i64;
+    recent && previous.bytecode_hash != used.bytecode_hash
+}
+
+fn nonce_use(state: &AppState, order: &OrderNonce) -> NonceUse {
+    NonceUse {
+        bytecode_hash: order.bytecode_hash,
+        seen_at: state.clock.now().as_secs() as i64,
+    }
+}
+
+/// Refuse `order` if its owner used the same nonce with a different expression within the
+/// configured window. Owners pick nonces to tell their own orders apart, so one of their
+/// nonces turning up with other bytecode points at a doctored order payload being used to
+/// farm signatures. Only reads: uses are recorded by [`record`] once a quote is signed.
+pub(crate) async fn check(state: &AppState, order: &OrderNonce) -> Result<(), AppError> {
+    let Some(window) = state.order_nonce_window else {
+        return Ok(());
+    };
+    let used = nonce_use(state, order);
+    if let Some(previous) = state.storage.order_nonce(order.owner, order.nonce).await? {
+        if conflicts(&previous, &used, window) {
+            tracing::warn!(
+                "Refusing order nonce {} of {}: seen {}s ago with expression {}, now with {}",
+                order.nonce,
+                order.owner,
+                used.seen_at - previous.seen_at,
+                previous.bytecode_hash,
+                used.bytecode_hash
+            );
+            return Err(OracleRequestError::OrderNonceReused(order.nonce).into());
+        }
+    }
+    Ok(())
+}
+
+/// Record that a quote was signed for `order`. A live use with a different expression (e.g.
+/// recorded by a concurrent request since [`check`]) is left in place rather than replaced.
+/// Failures are logged rather than returned, since the quote is already signed.
+pub(crate) async fn record(state: &AppState, order: &OrderNonce) {
+    let Some(window) = state.order_nonce_window else {
+        return;
+    };
+    let used = nonce_use(state, order);
+    let result = async {
+        if let Some(previous) = state.storage.order_nonce(order.owner, order.nonce).await? {
+            if conflicts(&previous, &used, window) {
+                return Ok(());
+            }
+        }
+        state
+            .storage
+            .record_order_nonce(order.owner, order.nonce, &used)
+            .await
+    }
+    .await;
+    if let Err(e) = result {
+        tracing::warn!(
+            "Failed to record order nonce {} of {}: {:#}",
+            order.nonce,
+            order.owner,
+            e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conflicts() {
+        let window = Duration::from_secs(60);
+        let previous = NonceUse {
+            bytecode_hash: B256::repeat_byte(2),
+            seen_at: 1700000000,
+        };
+        let same = NonceUse {
+            seen_at: 1700000030,
+            ..previous
+        };
+        assert!(!conflicts(&previous, &same, window));
+        let other_bytecode = NonceUse {
+            bytecode_hash: B256::repeat_byte(4),
+            ..same
+        };
+        assert!(conflicts(&previous, &other_bytecode, window));
+        let later = NonceUse {
+            seen_at: 1700000060,
+            ..other_bytecode
+        };
+        assert!(!conflicts(&previous, &later, window));
+    }
+}
@@ -0,0 +1,188 @@
+use alloy::primitives::Address;
+use axum::{extract::State, Json};
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::status::FeedState;
+use crate::{check_clock_skew, check_ready, check_schedule, limits, AppState, ExpiryMode};
+
+/// How long signed contexts stay valid.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ExpiryInfo {
+    /// "timestamp" or "block_number"
+    pub mode: &'static str,
+    /// Default expiry (timestamp expiry only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seconds: Option<u64>,
+    /// Longest expiry a client may request with `?expiry_seconds=` (timestamp expiry only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_seconds: Option<u64>,
+    /// Blocks until expiry (block number expiry only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocks: Option<u64>,
+}
+
+/// How the feed price is turned into the signed price.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct PricingInfo {
+    /// Price pipeline, e.g. `[clamp 1500..4000, invert?, mul 1.001, round 6sf]`
+    pub pipeline: String,
+    /// Rounding applied to the signed price, e.g. `6sf up`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rounding: Option<String>,
+    /// Whether gas costs are priced into the ratio
+    pub gas_compensation: bool,
+    /// Largest random markup on the signed price, in basis points
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_jitter_bps: Option<u32>,
+    /// Whether the price is scaled by the tokens' decimal difference
+    pub scale_by_decimals: bool,
+}
+
+/// A pair the oracle quotes, for frontends building orders.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct PairInfo {
+    /// Deployment serving the pair (with a deployments file)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deployment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    #[schema(value_type = String)]
+    pub base_token: Address,
+    #[schema(value_type = String)]
+    pub quote_token: Address,
+    /// Other addresses accepted as the base token
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<String>)]
+    pub base_aliases: Vec<Address>,
+    /// Other addresses accepted as the quote token
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<String>)]
+    pub quote_aliases: Vec<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_decimals: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quote_decimals: Option<u8>,
+    /// Decimal places `/context` renders the signed price with, alongside the exact Rain float
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_decimals: Option<u8>,
+    pub feed_id: String,
+    /// Symbol the feed was configured by, e.g. `ETH/USD`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed_symbol: Option<String>,
+    /// The feed is quoted the other way round for this pair
+    pub feed_inverted: bool,
+    pub expiry: ExpiryInfo,
+    /// Names of the signed context slots in order, e.g. `[price, expiry, nonce, funding]`
+    pub context_slots: Vec<String>,
+    /// Absent when pricing details are redacted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<PricingInfo>,
+    pub feed_state: FeedState,
+    /// Whether `/context` would currently sign for the pair
+    pub available: bool,
+    /// Error code `/context` currently refuses with, when unavailable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unavailable_reason: Option<String>,
+}
+
+/// Response for `GET /pairs`.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct PairsResponse {
+    pub pairs: Vec<PairInfo>,
+}
+
+/// How `state` prices the pair, regardless of redaction.
+pub fn pricing_info(state: &AppState) -> PricingInfo {
+    let pair = &state.token_pair;
+    PricingInfo {
+        pipeline: pair.pipeline.to_string(),
+        rounding: pair.rounding.as_ref().map(ToString::to_string),
+        gas_compensation: state.gas_compensation.is_some(),
+        price_jitter_bps: state.price_jitter.map(|j| j.max_bps()),
+        scale_by_decimals: pair.scale_by_decimals,
+    }
+}
+
+/// The pair served by `state`.
+pub async fn pair_info(state: &AppState) -> PairInfo {
+    let pair = &state.token_pair;
+    let expiry = match pair.expiry {
+        ExpiryMode::Timestamp => ExpiryInfo {
+            mode: "timestamp",
+            seconds: Some(state.expiry_seconds),
+            max_seconds: Some(state.max_expiry_seconds),
+            blocks: None,
+        },
+        ExpiryMode::BlockNumber { blocks } => ExpiryInfo {
+            mode: "block_number",
+            seconds: None,
+            max_seconds: None,
+            blocks: Some(blocks),
+        },
+    };
+    let pricing = (!state.redact_pricing).then(|| pricing_info(state));
+    let feed_state = state
+        .feed_stats
+        .snapshot()
+        .into_iter()
+        .find(|feed| feed.feed_id == state.pyth_price_feed_id)
+        .map(|feed| feed.state())
+        .unwrap_or(FeedState::Unknown);
+    let refusal = match check_ready(state)
+        .and_then(|()| check_clock_skew(state))
+        .and_then(|()| check_schedule(state))
+    {
+        Err(e) => Some(e),
+        Ok(()) => limits::check(state).await.err(),
+    };
+    let unavailable_reason = match refusal {
+        Some(e) => Some(e.error_code().to_string()),
+        None if feed_state == FeedState::Down => Some("feed_down".to_string()),
+        None => None,
+    };
+    PairInfo {
+        deployment: None,
+        chain_id: state.chain_id,
+        base_token: pair.base_token,
+        quote_token: pair.quote_token,
+        base_aliases: pair.base_aliases.clone(),
+        quote_aliases: pair.quote_aliases.clone(),
+        base_decimals: pair.base_decimals,
+        quote_decimals: pair.quote_decimals,
+        display_decimals: pair.display_decimals,
+        feed_id: state.pyth_price_feed_id.clone(),
+        feed_symbol: state.pyth_price_feed_symbol.clone(),
+        feed_inverted: pair.feed_inverted,
+        expiry,
+        context_slots: state.context_slots(),
+        pricing,
+        feed_state,
+        available: unavailable_reason.is_none(),
+        unavailable_reason,
+    }
+}
+
+/// GET handler — every pair the oracle quotes and whether it is quoting it right now.
+#[utoipa::path(
+    get,
+    path = "/pairs",
+    responses((status = 200, description = "Configured pairs", body = PairsResponse)),
+)]
+pub async fn pairs(State(state): State<Arc<AppState>>) -> Json<PairsResponse> {
+    Json(PairsResponse {
+        pairs: vec![pair_info(&state).await],
+    })
+}
+
+/// Pairs of every deployment, labelled with the deployment serving them.
+pub async fn deployment_pairs(deployments: &[(String, Arc<AppState>)]) -> PairsResponse {
+    let mut pairs = Vec::with_capacity(deployments.len());
+    for (name, state) in deployments {
+        pairs.push(PairInfo {
+            deployment: Some(name.clone()),
+            ..pair_info(state).await
+        });
+    }
+    PairsResponse { pairs }
+}
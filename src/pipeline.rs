@@ -0,0 +1,263 @@
+use rain_math_float::Float;
+
+use crate::rounding::{PriceRounding, RoundingDirection};
+use crate::PriceDirection;
+
+/// One step of a [`Pipeline`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stage {
+    /// `invert?`: take the reciprocal when the order needs the inverted price
+    InvertIfNeeded,
+    /// `mul X`: multiply by a decimal factor, e.g. `mul 1.001` for a 10 bps markup
+    Mul(String),
+    /// `clamp MIN..MAX`: limit the price to a range
+    Clamp { min: String, max: String },
+    /// `round 6sf` or `round 0.01`, optionally followed by `up`, `down` or `nearest`
+    Round(PriceRounding),
+}
+
+/// Transformations applied in order to the feed price to produce the signed price, parsed
+/// from e.g. `[clamp 1500..4000, invert?, mul 1.001, round 6sf]`.
+///
+/// Must contain `invert?` exactly once, so stages before it see the feed price and stages
+/// after it see the price in the order's direction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pipeline {
+    stages: Vec<Stage>,
+}
+
+impl Default for Pipeline {
+    /// `[invert?]`: the feed price, inverted when needed.
+    fn default() -> Self {
+        Self {
+            stages: vec![Stage::InvertIfNeeded],
+        }
+    }
+}
+
+fn parse_float(s: &str) -> anyhow::Result<Float> {
+    Float::parse(s.to_string())
+        .map_err(|e| anyhow::anyhow!("Failed to parse '{}' as Rain float: {:?}", s, e))
+}
+
+fn parse_stage(s: &str) -> anyhow::Result<Stage> {
+    let mut words = s.split_whitespace();
+    let op = words
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Empty pipeline stage"))?;
+    let args: Vec<&str> = words.collect();
+    let stage = match (op, args.as_slice()) {
+        ("invert?", []) => Stage::InvertIfNeeded,
+        ("mul", [factor]) => {
+            parse_float(factor)?;
+            Stage::Mul(factor.to_string())
+        }
+        ("clamp", [range]) => {
+            let (min, max) = range
+                .split_once("..")
+                .ok_or_else(|| anyhow::anyhow!("Expected clamp MIN..MAX, got '{}'", range))?;
+            anyhow::ensure!(
+                !parse_float(max)?.lt(parse_float(min)?).unwrap_or(true),
+                "Clamp range '{}' is empty",
+                range
+            );
+            Stage::Clamp {
+                min: min.to_string(),
+                max: max.to_string(),
+            }
+        }
+        ("round", [precision, rest @ ..]) if rest.len() <= 1 => {
+            let direction = match rest.first() {
+                Some(direction) => <RoundingDirection as clap::ValueEnum>::from_str(
+                    direction, true,
+                )
+                .map_err(|_| anyhow::anyhow!("Unknown rounding direction '{}'", direction))?,
+                None => RoundingDirection::default(),
+            };
+            Stage::Round(match precision.strip_suffix("sf") {
+                Some(digits) => PriceRounding::significant_digits(
+                    digits.parse().map_err(|_| {
+                        anyhow::anyhow!("Invalid significant digits '{}'", precision)
+                    })?,
+                    direction,
+                )?,
+                None => PriceRounding::tick_size(precision, direction)?,
+            })
+        }
+        _ => anyhow::bail!("Invalid pipeline stage '{}'", s),
+    };
+    Ok(stage)
+}
+
+impl std::str::FromStr for Pipeline {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let s = s.trim();
+        let s = s
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .unwrap_or(s);
+        let stages = s
+            .split(',')
+            .map(|stage| parse_stage(stage.trim()))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let inverts = stages
+            .iter()
+            .filter(|stage| **stage == Stage::InvertIfNeeded)
+            .count();
+        anyhow::ensure!(
+            inverts == 1,
+            "Pipeline must contain `invert?` exactly once, found {}",
+            inverts
+        );
+        Ok(Self { stages })
+    }
+}
+
+/// In the syntax it is parsed from, e.g. `[clamp 1500..4000, invert?, mul 1.001, round 6sf]`.
+impl std::fmt::Display for Pipeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let stages: Vec<String> = self.stages.iter().map(ToString::to_string).collect();
+        write!(f, "[{}]", stages.join(", "))
+    }
+}
+
+/// In the syntax it is parsed from, e.g. `mul 0.999`.
+impl std::fmt::Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stage::InvertIfNeeded => write!(f, "invert?"),
+            Stage::Mul(factor) => write!(f, "mul {}", factor),
+            Stage::Clamp { min, max } => write!(f, "clamp {}..{}", min, max),
+            Stage::Round(rounding) => write!(f, "round {}", rounding),
+        }
+    }
+}
+
+impl Pipeline {
+    /// Run `price` through each stage in order.
+    pub fn apply(&self, price: Float, direction: PriceDirection) -> anyhow::Result<Float> {
+        Ok(self.trace(price, direction)?.0)
+    }
+
+    /// [`Pipeline::apply`], also returning each stage with the price after it.
+    pub fn trace(
+        &self,
+        price: Float,
+        direction: PriceDirection,
+    ) -> anyhow::Result<(Float, Vec<(&Stage, Float)>)> {
+        let mut price = price;
+        let mut steps = Vec::with_capacity(self.stages.len());
+        for stage in &self.stages {
+            price = match stage {
+                Stage::InvertIfNeeded => match direction {
+                    PriceDirection::AsIs => price,
+                    PriceDirection::Inverted => price
+                        .inv()
+                        .map_err(|e| anyhow::anyhow!("Failed to invert price: {:?}", e))?,
+                },
+                Stage::Mul(factor) => (price * parse_float(factor)?).map_err(|e| {
+                    anyhow::anyhow!("Failed to multiply price by {}: {:?}", factor, e)
+                })?,
+                Stage::Clamp { min, max } => {
+                    let (min, max) = (parse_float(min)?, parse_float(max)?);
+                    let compare = |e| anyhow::anyhow!("Failed to compare price: {:?}", e);
+                    if price.lt(min).map_err(compare)? {
+                        min
+                    } else if price.gt(max).map_err(compare)? {
+                        max
+                    } else {
+                        price
+                    }
+                }
+                Stage::Round(rounding) => rounding.round(price)?,
+            };
+            steps.push((stage, price));
+        }
+        Ok((price, steps))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(pipeline: &str, price: &str, direction: PriceDirection) -> String {
+        pipeline
+            .parse::<Pipeline>()
+            .unwrap()
+            .apply(parse_float(price).unwrap(), direction)
+            .unwrap()
+            .format()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let pipeline: Pipeline = "[invert?, mul 0.999, clamp 1500..4000, round 6sf down]"
+            .parse()
+            .unwrap();
+        assert_eq!(pipeline.stages.len(), 4);
+        assert_eq!(
+            pipeline.stages[2],
+            Stage::Clamp {
+                min: "1500".to_string(),
+                max: "4000".to_string()
+            }
+        );
+        assert_eq!("invert?".parse::<Pipeline>().unwrap(), Pipeline::default());
+        // Displays as it parses
+        assert_eq!(
+            pipeline.to_string(),
+            "[invert?, mul 0.999, clamp 1500..4000, round 6sf down]"
+        );
+        let ticks: Pipeline = "invert?, round 0.01".parse().unwrap();
+        assert_eq!(ticks.to_string(), "[invert?, round 0.01 up]");
+
+        for invalid in [
+            "mul 0.999",
+            "invert?, invert?",
+            "invert?, mul",
+            "invert?, clamp 4000..1500",
+            "invert?, round 6sf sideways",
+            "invert?, add 1",
+        ] {
+            assert!(invalid.parse::<Pipeline>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_apply_in_order() {
+        let pipeline = "[clamp 1500..4000, invert?, mul 1.001, round 6sf]";
+        assert_eq!(apply(pipeline, "3000", PriceDirection::AsIs), "3003");
+        assert_eq!(apply(pipeline, "5000", PriceDirection::AsIs), "4004");
+        assert_eq!(apply(pipeline, "1000", PriceDirection::AsIs), "1501.5");
+        // The clamp bounds the feed price either way; the markup is in the order's direction
+        assert_eq!(
+            apply(pipeline, "3000", PriceDirection::Inverted),
+            "0.000333667"
+        );
+        assert_eq!(
+            apply(pipeline, "5000", PriceDirection::Inverted),
+            "0.00025025"
+        );
+        assert_eq!(
+            apply(pipeline, "1000", PriceDirection::Inverted),
+            "0.000667334"
+        );
+        // Stages before invert? see the feed price
+        assert_eq!(
+            apply(
+                "[clamp 1500..4000, invert?]",
+                "5000",
+                PriceDirection::Inverted
+            ),
+            "0.00025"
+        );
+        assert_eq!(
+            apply("[invert?, round 2sf up]", "3000", PriceDirection::Inverted),
+            "0.00034"
+        );
+    }
+}
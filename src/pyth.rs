@@ -1,18 +1,62 @@
 use serde::Deserialize;
+use std::time::Duration;
+
+use crate::wormhole::GuardianSet;
 
 const HERMES_BASE_URL: &str = "https://hermes.pyth.network";
 
-#[derive(Debug)]
+/// Largest price exponent magnitude accepted from Hermes. Real feeds stay within a few
+/// dozen; anything beyond this is corrupt data, not a price.
+const MAX_ABS_EXPO: u32 = 128;
+
+/// Header the Hermes API key is sent in unless configured otherwise.
+pub const DEFAULT_API_KEY_HEADER: &str = "x-api-key";
+
+/// Where to put the Hermes API key on each request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiKeyPlacement {
+    /// Request header with this name
+    Header(String),
+    /// Query parameter with this name
+    Query(String),
+}
+
+/// Errors from Hermes that callers handle differently from a generic failure.
+#[derive(Debug, thiserror::Error)]
+pub enum HermesError {
+    #[error("Hermes rate limit exceeded")]
+    RateLimited {
+        /// Delay Hermes asked for in its `Retry-After` header, if any
+        retry_after: Option<Duration>,
+    },
+
+    #[error("Hermes update failed verification: {0}")]
+    Unverified(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct PriceData {
-    pub price: i64,
+    /// Price coefficient; wider than Hermes' nominal i64 so extreme feeds can't overflow.
+    pub price: i128,
     pub expo: i32,
+    /// Unix timestamp (seconds) at which Pyth published this price
+    pub publish_time: i64,
 }
 
 #[derive(Deserialize)]
 struct HermesResponse {
+    #[serde(default)]
+    binary: Option<BinaryUpdate>,
     parsed: Vec<ParsedPriceFeed>,
 }
 
+/// Signed update data Hermes returns alongside the parsed prices.
+#[derive(Deserialize)]
+struct BinaryUpdate {
+    encoding: String,
+    data: Vec<String>,
+}
+
 #[derive(Deserialize)]
 struct ParsedPriceFeed {
     price: PriceInfo,
@@ -22,33 +66,477 @@ struct ParsedPriceFeed {
 struct PriceInfo {
     price: String,
     expo: i32,
+    publish_time: i64,
+}
+
+/// Hermes API client. The public endpoint needs no key; hosted providers usually do.
+#[derive(Clone)]
+pub struct Hermes {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: Option<(ApiKeyPlacement, String)>,
+    guardians: Option<GuardianSet>,
+}
+
+/// Shows where the API key goes but never the key itself.
+impl std::fmt::Debug for Hermes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Hermes")
+            .field("base_url", &self.base_url)
+            .field(
+                "api_key",
+                &self
+                    .api_key
+                    .as_ref()
+                    .map(|(placement, _)| (placement, "(redacted)")),
+            )
+            .field("guardians", &self.guardians)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Hermes {
+    fn default() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: HERMES_BASE_URL.to_string(),
+            api_key: None,
+            guardians: None,
+        }
+    }
+}
+
+impl Hermes {
+    /// Use a different Hermes deployment (e.g. a paid provider).
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Send `key` with every request.
+    pub fn with_api_key(mut self, placement: ApiKeyPlacement, key: &str) -> Self {
+        self.api_key = Some((placement, key.to_string()));
+        self
+    }
+
+    /// Verify the Wormhole guardian signatures on every update against `guardians` before
+    /// trusting the parsed price, so a compromised Hermes endpoint can't forge prices.
+    pub fn with_guardian_set(mut self, guardians: GuardianSet) -> Self {
+        self.guardians = Some(guardians);
+        self
+    }
+
+    /// Attach the API key, if configured.
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.api_key {
+            Some((ApiKeyPlacement::Header(name), key)) => request.header(name, key),
+            Some((ApiKeyPlacement::Query(name), key)) => request.query(&[(name, key)]),
+            None => request,
+        }
+    }
+
+    /// Drop the request URL from `e` if it carries the API key as a query parameter, so the
+    /// key doesn't end up in logs with the error.
+    fn redact(&self, e: reqwest::Error) -> reqwest::Error {
+        match &self.api_key {
+            Some((ApiKeyPlacement::Query(_), _)) => e.without_url(),
+            _ => e,
+        }
+    }
+
+    /// Resolve a feed symbol (`ETH/USD` or `Crypto.ETH/USD`) to its feed ID (hex without 0x).
+    /// Fails if no feed, or more than one, has that symbol.
+    pub async fn feed_id_for_symbol(&self, symbol: &str) -> anyhow::Result<String> {
+        let url = format!("{}/v2/price_feeds", self.base_url);
+        let request = self.client.get(&url).query(&[("query", symbol.trim())]);
+        let feeds: Vec<FeedMetadata> = self
+            .authorize(request)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| self.redact(e))?
+            .json()
+            .await
+            .map_err(|e| self.redact(e))?;
+        resolve_feed(&feeds, symbol)
+    }
+
+    /// Fetch the latest price for `feed_id`. A `429` from Hermes is returned as
+    /// [`HermesError::RateLimited`] so callers can tell it apart from other failures.
+    pub async fn fetch_price(&self, feed_id: &str) -> anyhow::Result<PriceData> {
+        let url = format!(
+            "{}/v2/updates/price/latest?ids[]=0x{}",
+            self.base_url, feed_id
+        );
+        self.fetch_update(&url, feed_id).await
+    }
+
+    /// Fetch the price for `feed_id` as published at unix time `timestamp`, from Hermes'
+    /// historical updates endpoint (the same data Pyth Benchmarks serves).
+    pub async fn fetch_price_at(&self, feed_id: &str, timestamp: i64) -> anyhow::Result<PriceData> {
+        let url = format!(
+            "{}/v2/updates/price/{}?ids[]=0x{}&parsed=true",
+            self.base_url, timestamp, feed_id
+        );
+        self.fetch_update(&url, feed_id).await
+    }
+
+    async fn fetch_update(&self, url: &str, feed_id: &str) -> anyhow::Result<PriceData> {
+        let resp = self
+            .authorize(self.client.get(url))
+            .send()
+            .await
+            .map_err(|e| self.redact(e))?;
+        if resp.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = resp
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| parse_retry_after(v, chrono::Utc::now().timestamp()));
+            return Err(HermesError::RateLimited { retry_after }.into());
+        }
+        let resp: HermesResponse = resp
+            .error_for_status()
+            .map_err(|e| self.redact(e))?
+            .json()
+            .await
+            .map_err(|e| self.redact(e))?;
+        let verified = match &self.guardians {
+            Some(guardians) => Some(
+                verify_binary(&resp, guardians, feed_id)
+                    .map_err(|e| HermesError::Unverified(e.to_string()))?,
+            ),
+            None => None,
+        };
+        let data = parse_response(resp)?;
+        if let Some(verified) = verified {
+            let signed = (verified.price as i128, verified.expo, verified.publish_time);
+            if (data.price, data.expo, data.publish_time) != signed {
+                return Err(HermesError::Unverified(
+                    "parsed price does not match the signed update data".to_string(),
+                )
+                .into());
+            }
+        }
+        Ok(data)
+    }
+}
+
+/// Entry from Hermes' `/v2/price_feeds` metadata endpoint.
+#[derive(Deserialize)]
+struct FeedMetadata {
+    id: String,
+    attributes: FeedAttributes,
+}
+
+#[derive(Deserialize)]
+struct FeedAttributes {
+    /// e.g. "Crypto.ETH/USD"
+    symbol: String,
+}
+
+/// Whether a Hermes symbol like `Crypto.ETH/USD` is the requested `ETH/USD` (or the
+/// fully qualified form).
+fn symbol_matches(hermes_symbol: &str, symbol: &str) -> bool {
+    let hermes_symbol = hermes_symbol.to_ascii_uppercase();
+    let symbol = symbol.trim().to_ascii_uppercase();
+    hermes_symbol == symbol
+        || hermes_symbol
+            .split_once('.')
+            .is_some_and(|(_, bare)| bare == symbol)
 }
 
-/// Fetch the latest price from Pyth Hermes API.
+/// Pick the single feed whose symbol matches, failing on none or several.
+fn resolve_feed(feeds: &[FeedMetadata], symbol: &str) -> anyhow::Result<String> {
+    let matches: Vec<_> = feeds
+        .iter()
+        .filter(|f| symbol_matches(&f.attributes.symbol, symbol))
+        .collect();
+    match matches.as_slice() {
+        [feed] => Ok(feed.id.trim_start_matches("0x").to_string()),
+        [] => anyhow::bail!("No Pyth price feed with symbol '{}'", symbol),
+        _ => anyhow::bail!(
+            "Symbol '{}' matches several Pyth feeds ({}); use the fully qualified symbol",
+            symbol,
+            matches
+                .iter()
+                .map(|f| f.attributes.symbol.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Parse a `Retry-After` value given as delay seconds or an HTTP date.
+fn parse_retry_after(value: &str, now: i64) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value)
+        .ok()?
+        .timestamp();
+    Some(Duration::from_secs(at.saturating_sub(now).max(0) as u64))
+}
+
+/// Fetch the latest price from the public Pyth Hermes API.
 pub async fn fetch_price(feed_id: &str) -> anyhow::Result<PriceData> {
-    let url = format!(
-        "{}/v2/updates/price/latest?ids[]=0x{}",
-        HERMES_BASE_URL, feed_id
-    );
+    Hermes::default().fetch_price(feed_id).await
+}
 
-    let resp: HermesResponse = reqwest::get(&url).await?.error_for_status()?.json().await?;
+/// The price message for `feed_id` from the response's signed update data, verified against
+/// the guardian set.
+fn verify_binary(
+    resp: &HermesResponse,
+    guardians: &GuardianSet,
+    feed_id: &str,
+) -> anyhow::Result<crate::wormhole::PriceFeedMessage> {
+    let binary = resp
+        .binary
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("Hermes returned no update data to verify"))?;
+    anyhow::ensure!(
+        binary.encoding == "hex",
+        "Unsupported Hermes update data encoding '{}'",
+        binary.encoding
+    );
+    let feed_id = feed_id
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Invalid feed ID '{}': {}", feed_id, e))?;
+    let mut last_err = anyhow::anyhow!("Hermes returned no update data to verify");
+    for data in &binary.data {
+        let data = hex::decode(data.trim_start_matches("0x"))?;
+        match guardians.verify_update(&data, feed_id) {
+            Ok(message) => return Ok(message),
+            Err(e) => last_err = e,
+        }
+    }
+    Err(last_err)
+}
 
+fn parse_response(resp: HermesResponse) -> anyhow::Result<PriceData> {
     let feed = resp
         .parsed
         .first()
         .ok_or_else(|| anyhow::anyhow!("No price feed returned from Hermes"))?;
 
-    let price: i64 = feed.price.price.parse()?;
+    let price: i128 = feed
+        .price
+        .price
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Invalid Hermes price '{}': {}", feed.price.price, e))?;
+    anyhow::ensure!(
+        feed.price.expo.unsigned_abs() <= MAX_ABS_EXPO,
+        "Hermes price exponent {} is out of range",
+        feed.price.expo
+    );
 
     Ok(PriceData {
         price,
         expo: feed.price.expo,
+        publish_time: feed.price.publish_time,
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use axum::{http::HeaderMap, response::IntoResponse, routing::get, Router};
+
+    /// Local Hermes stand-in that rate-limits requests without the expected key.
+    async fn mock_hermes() -> String {
+        let app = Router::new().route(
+            "/v2/updates/price/latest",
+            get(
+                |headers: HeaderMap, query: axum::extract::RawQuery| async move {
+                    let query = query.0.unwrap_or_default();
+                    let authorized = headers.get("x-api-key").is_some_and(|v| v == "secret")
+                        || query.contains("api-key=secret");
+                    if !authorized {
+                        return (
+                            reqwest::StatusCode::TOO_MANY_REQUESTS,
+                            [("retry-after", "7")],
+                        )
+                            .into_response();
+                    }
+                    axum::Json(serde_json::json!({
+                        "parsed": [{ "price": { "price": "310012345678", "expo": -8, "publish_time": 1700000000 } }]
+                    }))
+                    .into_response()
+                },
+            ),
+        )
+        .route("/v2/updates/price/{timestamp}", get(historical_update));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    /// Hermes' historical endpoint: a fixed price published at the requested time.
+    async fn historical_update(
+        axum::extract::Path(timestamp): axum::extract::Path<i64>,
+    ) -> axum::Json<serde_json::Value> {
+        axum::Json(serde_json::json!({
+            "parsed": [{ "price": { "price": "200000000000", "expo": -8, "publish_time": timestamp } }]
+        }))
+    }
+
+    fn hermes_response(price: &str, expo: i32) -> HermesResponse {
+        serde_json::from_value(serde_json::json!({
+            "parsed": [{ "price": { "price": price, "expo": expo, "publish_time": 1700000000 } }]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_response_wide_price() {
+        let beyond_i64 = "92233720368547758070";
+        let data = parse_response(hermes_response(beyond_i64, -8)).unwrap();
+        assert_eq!(data.price.to_string(), beyond_i64);
+
+        assert!(parse_response(hermes_response("3.1", -8)).is_err());
+        assert!(parse_response(hermes_response("1", i32::MIN)).is_err());
+    }
+
+    #[test]
+    fn test_resolve_feed() {
+        let feeds: Vec<FeedMetadata> = serde_json::from_value(serde_json::json!([
+            { "id": "ff61", "attributes": { "symbol": "Crypto.ETH/USD" } },
+            { "id": "0xaa01", "attributes": { "symbol": "Crypto.ETH/USDT" } },
+            { "id": "bb02", "attributes": { "symbol": "Equity.US.AAPL/USD" } },
+            { "id": "cc03", "attributes": { "symbol": "Crypto.AAPL/USD" } },
+        ]))
+        .unwrap();
+
+        assert_eq!(resolve_feed(&feeds, "ETH/USD").unwrap(), "ff61");
+        assert_eq!(resolve_feed(&feeds, "eth/usdt").unwrap(), "aa01");
+        assert_eq!(resolve_feed(&feeds, "Crypto.ETH/USD").unwrap(), "ff61");
+        assert!(resolve_feed(&feeds, "BTC/USD").is_err());
+        assert_eq!(resolve_feed(&feeds, "Equity.US.AAPL/USD").unwrap(), "bb02");
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("30", 0), Some(Duration::from_secs(30)));
+        assert_eq!(
+            parse_retry_after("Tue, 14 Nov 2023 22:13:30 GMT", 1700000000),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(parse_retry_after("soon", 0), None);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited() {
+        let hermes = Hermes::default().with_base_url(&mock_hermes().await);
+        let err = hermes.fetch_price("00").await.unwrap_err();
+        match err.downcast_ref::<HermesError>() {
+            Some(HermesError::RateLimited { retry_after }) => {
+                assert_eq!(*retry_after, Some(Duration::from_secs(7)))
+            }
+            _ => panic!("expected rate limit error, got {:?}", err),
+        }
+    }
+
+    /// Local Hermes serving `binary` update data alongside a parsed price of `parsed`.
+    async fn signed_hermes(binary: Vec<u8>, parsed: &str) -> String {
+        let body = serde_json::json!({
+            "binary": { "encoding": "hex", "data": [hex::encode(binary)] },
+            "parsed": [{ "price": { "price": parsed, "expo": -8, "publish_time": 1700000000 } }]
+        });
+        let app = Router::new().route(
+            "/v2/updates/price/latest",
+            get(move || async move { axum::Json(body) }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_guardian_verification() {
+        use crate::wormhole::{encode_update, PriceFeedMessage};
+        use alloy::primitives::B256;
+        use alloy::signers::local::PrivateKeySigner;
+
+        let feed_id = "ff".repeat(32);
+        let guardian = PrivateKeySigner::random();
+        let guardians = GuardianSet {
+            index: 4,
+            keys: vec![guardian.address()],
+        };
+        let message = PriceFeedMessage {
+            feed_id: B256::repeat_byte(0xff),
+            price: 310012345678,
+            conf: 1_000,
+            expo: -8,
+            publish_time: 1700000000,
+        };
+        let binary = encode_update(4, &[guardian], &[message]);
+
+        let hermes = Hermes::default()
+            .with_base_url(&signed_hermes(binary.clone(), "310012345678").await)
+            .with_guardian_set(guardians.clone());
+        assert_eq!(
+            hermes.fetch_price(&feed_id).await.unwrap().price,
+            310012345678
+        );
+
+        // Hermes reporting a different price than it can prove
+        let hermes = Hermes::default()
+            .with_base_url(&signed_hermes(binary.clone(), "999912345678").await)
+            .with_guardian_set(guardians.clone());
+        let err = hermes.fetch_price(&feed_id).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<HermesError>(),
+            Some(HermesError::Unverified(_))
+        ));
+
+        // Update data signed by someone outside the guardian set
+        let forged = encode_update(4, &[PrivateKeySigner::random()], &[message]);
+        let hermes = Hermes::default()
+            .with_base_url(&signed_hermes(forged, "310012345678").await)
+            .with_guardian_set(guardians);
+        let err = hermes.fetch_price(&feed_id).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<HermesError>(),
+            Some(HermesError::Unverified(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_api_key_placement() {
+        let base_url = mock_hermes().await;
+        for placement in [
+            ApiKeyPlacement::Header(DEFAULT_API_KEY_HEADER.to_string()),
+            ApiKeyPlacement::Query("api-key".to_string()),
+        ] {
+            let hermes = Hermes::default()
+                .with_base_url(&base_url)
+                .with_api_key(placement.clone(), "secret");
+            assert!(!format!("{:?}", hermes).contains("secret"));
+            let data = hermes.fetch_price("00").await.unwrap();
+            assert_eq!(data.price, 310012345678);
+            assert_eq!(data.expo, -8);
+
+            let err = Hermes::default()
+                .with_base_url("http://127.0.0.1:1")
+                .with_api_key(placement, "secret")
+                .fetch_price("00")
+                .await
+                .unwrap_err();
+            assert!(!format!("{:#}", err).contains("secret"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_price_at() {
+        let hermes = Hermes::default().with_base_url(&mock_hermes().await);
+        let data = hermes.fetch_price_at("00", 1690000000).await.unwrap();
+        assert_eq!(data.price, 200000000000);
+        assert_eq!(data.publish_time, 1690000000);
+    }
 
     #[tokio::test]
     async fn test_fetch_eth_price() {
@@ -60,6 +548,7 @@ mod tests {
         let data = result.unwrap();
         assert!(data.price > 0, "Price should be positive");
         assert!(data.expo < 0, "Expo should be negative for USD prices");
+        assert!(data.publish_time > 0, "Publish time should be set");
         tracing::info!("ETH/USD: {} * 10^{}", data.price, data.expo);
     }
 }
@@ -0,0 +1,429 @@
+use async_trait::async_trait;
+use axum::{
+    extract::{Request, State},
+    http::{request::Parts, HeaderMap},
+    middleware::Next,
+    response::Response,
+    Extension, Json,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::auth::{AuthError, Authenticator, Identity};
+use crate::storage::Storage;
+use crate::{AppError, AppState};
+
+/// Header carrying the client's API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Days of per-key usage history reported by `GET /usage`.
+const HISTORY_DAYS: u64 = 30;
+
+pub(crate) const SECONDS_PER_DAY: u64 = 86_400;
+
+/// A configured API key and its daily signed-quote allowance.
+#[derive(Clone)]
+pub struct ApiKey {
+    /// Label used in logs and usage reports (never the key itself)
+    pub name: String,
+    pub key: String,
+    /// Signed quotes allowed per UTC day; `None` is unlimited
+    pub daily_quota: Option<u64>,
+}
+
+/// Shows the name and quota only, never the key.
+impl std::fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiKey")
+            .field("name", &self.name)
+            .field("daily_quota", &self.daily_quota)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FromStr for ApiKey {
+    type Err = String;
+
+    /// Parse `name:key` or `name:key:daily_quota`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().splitn(3, ':');
+        let name = parts.next().unwrap_or_default();
+        let key = parts.next().unwrap_or_default();
+        if name.is_empty() || key.is_empty() {
+            return Err("expected name:key or name:key:daily_quota".to_string());
+        }
+        let daily_quota = parts
+            .next()
+            .map(|q| {
+                q.parse()
+                    .map_err(|_| format!("invalid daily quota '{}' for '{}'", q, name))
+            })
+            .transpose()?;
+        Ok(Self {
+            name: name.to_string(),
+            key: key.to_string(),
+            daily_quota,
+        })
+    }
+}
+
+/// Name of the API key a request was authenticated with, attached as a request extension.
+#[derive(Debug, Clone)]
+pub struct ApiKeyName(pub String);
+
+/// Quota failures (returned as 429).
+#[derive(Debug, thiserror::Error)]
+pub enum QuotaError {
+    #[error("Daily quota of {quota} signed quotes exhausted for '{name}'")]
+    Exceeded { name: String, quota: u64 },
+}
+
+impl QuotaError {
+    pub(crate) fn error_code(&self) -> &'static str {
+        match self {
+            Self::Exceeded { .. } => "quota_exceeded",
+        }
+    }
+
+    /// What the error code means, for the `/errors` catalog.
+    pub(crate) fn description(&self) -> &'static str {
+        match self {
+            Self::Exceeded { .. } => "The API key has used up its daily quota of signed quotes",
+        }
+    }
+
+    /// One of each variant, for the `/errors` catalog.
+    pub(crate) fn samples() -> Vec<Self> {
+        vec![Self::Exceeded {
+            name: String::new(),
+            quota: 0,
+        }]
+    }
+}
+
+/// Signed quotes issued on one UTC day.
+#[derive(Debug, Clone, PartialEq, Serialize, utoipa::ToSchema)]
+pub struct DailyUsage {
+    /// UTC date, e.g. "2023-11-14"
+    pub date: String,
+    pub count: u64,
+}
+
+/// Response for `GET /usage`.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct UsageResponse {
+    pub name: String,
+    /// Signed quotes allowed per UTC day (absent when unlimited)
+    pub daily_quota: Option<u64>,
+    pub used_today: u64,
+    /// Absent when unlimited
+    pub remaining_today: Option<u64>,
+    /// Recent history, oldest first
+    pub days: Vec<DailyUsage>,
+}
+
+/// Configured API keys. Usage counts live in [`Storage`] so replicas sharing a
+/// database enforce one quota between them.
+pub struct Quotas {
+    /// Keyed by API key
+    keys: HashMap<String, ApiKey>,
+}
+
+pub(crate) fn day(now_secs: u64) -> u64 {
+    now_secs / SECONDS_PER_DAY
+}
+
+fn format_day(day: u64) -> String {
+    chrono::DateTime::from_timestamp((day * SECONDS_PER_DAY) as i64, 0)
+        .map(|dt| dt.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| day.to_string())
+}
+
+impl Quotas {
+    pub fn new(keys: impl IntoIterator<Item = ApiKey>) -> Self {
+        Self {
+            keys: keys.into_iter().map(|k| (k.key.clone(), k)).collect(),
+        }
+    }
+
+    /// Look up the API key sent in the request headers.
+    pub fn api_key(&self, headers: &HeaderMap) -> Result<&ApiKey, AuthError> {
+        let key = headers
+            .get(API_KEY_HEADER)
+            .ok_or(AuthError::MissingHeader(API_KEY_HEADER))?
+            .to_str()
+            .map_err(|_| AuthError::MalformedHeader(API_KEY_HEADER))?;
+        self.keys.get(key.trim()).ok_or(AuthError::InvalidApiKey)
+    }
+
+    /// Look up a configured API key by its name.
+    pub fn by_name(&self, name: &str) -> Option<&ApiKey> {
+        self.keys.values().find(|key| key.name == name)
+    }
+}
+
+/// Authenticates the `X-Api-Key` header and attaches its [`ApiKeyName`]; quotas are
+/// enforced separately by [`enforce_quota`].
+#[async_trait]
+impl Authenticator for Quotas {
+    fn name(&self) -> &'static str {
+        "api_key"
+    }
+
+    async fn authenticate(
+        &self,
+        parts: &mut Parts,
+        _body: &[u8],
+        _now: u64,
+    ) -> Result<Option<Identity>, AuthError> {
+        if !parts.headers.contains_key(API_KEY_HEADER) {
+            return Ok(None);
+        }
+        let name = self.api_key(&parts.headers)?.name.clone();
+        parts.extensions.insert(ApiKeyName(name.clone()));
+        Ok(Some(Identity {
+            mechanism: self.name(),
+            subject: Some(name),
+        }))
+    }
+}
+
+/// Reserve one of `key`'s signed quotes for the day containing `now_secs`, returning the day
+/// reserved against, or fail if its allowance is used up. The count is incremented before it
+/// is compared, so concurrent requests can't all see the last quote as free; a request over
+/// the quota gives its reservation back. Keys without a quota reserve nothing.
+pub async fn reserve(
+    storage: &dyn Storage,
+    key: &ApiKey,
+    now_secs: u64,
+) -> Result<Option<u64>, AppError> {
+    let Some(quota) = key.daily_quota else {
+        return Ok(None);
+    };
+    let today = day(now_secs);
+    if storage.increment_usage(&key.name, today).await? > quota {
+        storage.release_usage(&key.name, today).await?;
+        return Err(QuotaError::Exceeded {
+            name: key.name.clone(),
+            quota,
+        }
+        .into());
+    }
+    Ok(Some(today))
+}
+
+/// Usage for `key` over the retained history, ending on the day containing `now_secs`.
+pub async fn usage_report(
+    storage: &dyn Storage,
+    key: &ApiKey,
+    now_secs: u64,
+) -> anyhow::Result<UsageResponse> {
+    let today = day(now_secs);
+    let days = storage
+        .usage(&key.name, (today + 1).saturating_sub(HISTORY_DAYS))
+        .await?;
+    let used_today = days.get(&today).copied().unwrap_or(0);
+    Ok(UsageResponse {
+        name: key.name.clone(),
+        daily_quota: key.daily_quota,
+        used_today,
+        remaining_today: key.daily_quota.map(|q| q.saturating_sub(used_today)),
+        days: days
+            .into_iter()
+            .map(|(d, count)| DailyUsage {
+                date: format_day(d),
+                count,
+            })
+            .collect(),
+    })
+}
+
+/// Number of signed quotes in a response carrying several, attached as a response extension so
+/// [`enforce_quota`] knows whether its reservation was used. Handlers signing several quotes
+/// reserve each one beyond the first with [`reserve_extra`].
+#[derive(Debug, Clone, Copy)]
+pub struct QuotesIssued(pub u64);
+
+/// Reserve a quote for `api_key` beyond the one [`enforce_quota`] reserved for the request,
+/// for handlers signing several. Returns the day to [`release`] it from if the quote
+/// isn't issued, or `None` when the key has no quota (or there are no API keys).
+pub(crate) async fn reserve_extra(
+    state: &AppState,
+    api_key: Option<&str>,
+) -> Result<Option<u64>, AppError> {
+    let Some(key) = api_key.and_then(|name| state.quotas.as_ref()?.by_name(name)) else {
+        return Ok(None);
+    };
+    reserve(state.storage.as_ref(), key, state.clock.now().as_secs()).await
+}
+
+/// Give back a reserved quote (e.g. from [`reserve_extra`]) that wasn't issued.
+pub(crate) async fn release(state: &AppState, api_key: Option<&str>, reserved: Option<u64>) {
+    let (Some(name), Some(today)) = (api_key, reserved) else {
+        return;
+    };
+    if let Err(e) = state.storage.release_usage(name, today).await {
+        tracing::error!("Failed to release reserved usage for '{}': {}", name, e);
+    }
+}
+
+/// Middleware — reserves a quote from the API key the request was authenticated with, then
+/// gives the reservation back once the response is known if no signed quote was returned.
+/// Passes requests through untouched when no API keys are configured or the request was
+/// authenticated another way.
+pub async fn enforce_quota(
+    State(state): State<Arc<AppState>>,
+    api_key: Option<Extension<ApiKeyName>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let (Some(quotas), Some(Extension(ApiKeyName(name)))) = (&state.quotas, api_key) else {
+        return Ok(next.run(request).await);
+    };
+    let key = quotas
+        .by_name(&name)
+        .ok_or_else(|| anyhow::anyhow!("API key '{}' is not configured", name))?;
+    let reserved = reserve(state.storage.as_ref(), key, state.clock.now().as_secs()).await?;
+
+    let response = next.run(request).await;
+    // Only issued quotes count; dry runs return no quote ID, and idempotent replays hand back
+    // a quote that was already counted
+    let replayed = response
+        .headers()
+        .get(crate::idempotency::IDEMPOTENT_REPLAYED_HEADER)
+        .is_some_and(|value| value == "true");
+    let issued = match response.extensions().get::<QuotesIssued>() {
+        _ if replayed => 0,
+        Some(QuotesIssued(n)) if response.status().is_success() => *n,
+        None if response.status().is_success() => {
+            u64::from(response.headers().contains_key(crate::QUOTE_ID_HEADER))
+        }
+        _ => 0,
+    };
+    if issued == 0 {
+        release(&state, Some(&key.name), reserved).await;
+    }
+    Ok(response)
+}
+
+/// GET handler — usage and remaining quota for the calling API key.
+#[utoipa::path(
+    get,
+    path = "/usage",
+    params(("X-Api-Key" = String, Header, description = "API key")),
+    responses(
+        (status = 200, description = "Usage for the calling API key", body = UsageResponse),
+        (status = 401, description = "Missing or unknown API key", body = crate::ErrorResponse),
+    ),
+)]
+pub async fn usage(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<UsageResponse>, AppError> {
+    let quotas = state
+        .quotas
+        .as_ref()
+        .ok_or(AuthError::MissingHeader(API_KEY_HEADER))?;
+    let key = quotas.api_key(&headers)?;
+    let report = usage_report(state.storage.as_ref(), key, state.clock.now().as_secs()).await?;
+    Ok(Json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn key(quota: Option<u64>) -> ApiKey {
+        ApiKey {
+            name: "mm".to_string(),
+            key: "sk_test".to_string(),
+            daily_quota: quota,
+        }
+    }
+
+    #[test]
+    fn test_parse_api_key() {
+        let k: ApiKey = "mm:sk_test:100".parse().unwrap();
+        assert_eq!(k.name, "mm");
+        assert_eq!(k.key, "sk_test");
+        assert_eq!(k.daily_quota, Some(100));
+
+        let k: ApiKey = "mm:sk_test".parse().unwrap();
+        assert_eq!(k.daily_quota, None);
+
+        assert!("mm".parse::<ApiKey>().is_err());
+        assert!("mm:sk_test:lots".parse::<ApiKey>().is_err());
+        assert!(!format!("{:?}", k).contains("sk_test"));
+    }
+
+    #[test]
+    fn test_authenticate() {
+        let quotas = Quotas::new([key(None)]);
+        let mut headers = HeaderMap::new();
+        assert!(matches!(
+            quotas.api_key(&headers).unwrap_err(),
+            AuthError::MissingHeader(API_KEY_HEADER)
+        ));
+        headers.insert(API_KEY_HEADER, "nope".parse().unwrap());
+        assert!(matches!(
+            quotas.api_key(&headers).unwrap_err(),
+            AuthError::InvalidApiKey
+        ));
+        headers.insert(API_KEY_HEADER, "sk_test".parse().unwrap());
+        assert_eq!(quotas.api_key(&headers).unwrap().name, "mm");
+        assert_eq!(quotas.by_name("mm").unwrap().key, "sk_test");
+    }
+
+    #[tokio::test]
+    async fn test_quota_enforced_and_resets_daily() {
+        let k = key(Some(2));
+        let storage = MemoryStorage::default();
+        assert_eq!(reserve(&storage, &k, NOW).await.unwrap(), Some(day(NOW)));
+        reserve(&storage, &k, NOW).await.unwrap();
+        assert!(matches!(
+            reserve(&storage, &k, NOW).await.unwrap_err(),
+            AppError::TooManyRequests(QuotaError::Exceeded { quota: 2, .. })
+        ));
+        // The refused reservation was given back
+        let usage = usage_report(&storage, &k, NOW).await.unwrap();
+        assert_eq!(usage.used_today, 2);
+        assert_eq!(reserve(&storage, &key(None), NOW).await.unwrap(), None);
+
+        let tomorrow = NOW + SECONDS_PER_DAY;
+        reserve(&storage, &k, tomorrow).await.unwrap();
+
+        let usage = usage_report(&storage, &k, tomorrow).await.unwrap();
+        assert_eq!(usage.used_today, 1);
+        assert_eq!(usage.remaining_today, Some(1));
+        assert_eq!(
+            usage.days,
+            vec![
+                DailyUsage {
+                    date: "2023-11-14".to_string(),
+                    count: 2
+                },
+                DailyUsage {
+                    date: "2023-11-15".to_string(),
+                    count: 1
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_history_window() {
+        let k = key(None);
+        let storage = MemoryStorage::default();
+        let later = NOW + HISTORY_DAYS * SECONDS_PER_DAY;
+        storage.increment_usage("mm", day(NOW)).await.unwrap();
+        storage.increment_usage("mm", day(later)).await.unwrap();
+        let usage = usage_report(&storage, &k, later).await.unwrap();
+        assert_eq!(usage.days.len(), 1);
+        assert_eq!(usage.remaining_today, None);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::guard::{GuardInput, GuardRejection, PricingGuard};
+
+/// A secondary price source that is never signed, only compared against the feed: when the
+/// two disagree by more than `max_deviation_percent` the pair is paused, as a cheap guard
+/// against a bad primary price without full multi-source aggregation.
+///
+/// The source is any JSON API (e.g. a CEX ticker) with the price at a JSON pointer, quoted
+/// the same way round as the Pyth feed (e.g. ETH/USD).
+#[derive(Debug, Clone)]
+pub struct ReferencePrice {
+    url: String,
+    pointer: String,
+    max_deviation_percent: f64,
+    client: reqwest::Client,
+}
+
+/// The feed price disagrees with the reference price by more than the configured limit.
+#[derive(Debug, Clone, Copy, PartialEq, thiserror::Error)]
+#[error("Feed price {primary} deviates {deviation_percent:.2}% from reference price {reference} (limit {max_percent}%)")]
+pub struct ReferenceDeviation {
+    pub primary: f64,
+    pub reference: f64,
+    pub deviation_percent: f64,
+    pub max_percent: f64,
+}
+
+/// How far `primary` is from `reference`, as a percentage of `reference`.
+pub fn deviation_percent(primary: f64, reference: f64) -> f64 {
+    (primary - reference).abs() / reference * 100.0
+}
+
+/// Read a positive price from a reference API response.
+fn parse_price(body: &serde_json::Value, pointer: &str) -> anyhow::Result<f64> {
+    let value = body
+        .pointer(pointer)
+        .ok_or_else(|| anyhow::anyhow!("Reference response has no value at {}", pointer))?;
+    match value {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+    .filter(|price: &f64| price.is_finite() && *price > 0.0)
+    .ok_or_else(|| anyhow::anyhow!("Invalid reference price {} at {}", value, pointer))
+}
+
+impl ReferencePrice {
+    /// `pointer` is a JSON pointer such as `/price`; `max_deviation_percent` must be positive.
+    pub fn new(url: &str, pointer: &str, max_deviation_percent: f64) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            max_deviation_percent.is_finite() && max_deviation_percent > 0.0,
+            "Reference deviation limit must be a positive percentage"
+        );
+        Ok(Self {
+            url: url.to_string(),
+            pointer: pointer.to_string(),
+            max_deviation_percent,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// The current reference price.
+    pub async fn fetch(&self) -> anyhow::Result<f64> {
+        let body: serde_json::Value = self
+            .client
+            .get(&self.url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        parse_price(&body, &self.pointer)
+    }
+
+    /// Check the feed price `primary` against `reference`.
+    pub fn check(&self, primary: f64, reference: f64) -> Result<(), ReferenceDeviation> {
+        let deviation = deviation_percent(primary, reference);
+        if deviation > self.max_deviation_percent {
+            return Err(ReferenceDeviation {
+                primary,
+                reference,
+                deviation_percent: deviation,
+                max_percent: self.max_deviation_percent,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl PricingGuard for ReferencePrice {
+    fn name(&self) -> &str {
+        "reference_deviation"
+    }
+
+    /// Failing to fetch the reference price refuses too, since the feed price can't be checked.
+    async fn check(&self, input: GuardInput<'_>) -> Result<(), GuardRejection> {
+        let reference = self.fetch().await.map_err(|e| {
+            GuardRejection::new(self.name(), format!("Reference price unavailable ({})", e))
+                .with_code("reference_unavailable")
+        })?;
+        let rejected = |reason: String| {
+            GuardRejection::new(self.name(), reason)
+                .with_code("reference_deviation")
+                .with_source("reference", reference)
+        };
+        let primary = input.feed_price().map_err(|e| rejected(e.to_string()))?;
+        ReferencePrice::check(self, primary, reference).map_err(|e| rejected(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_price() {
+        let body = serde_json::json!({ "symbol": "ETHUSDC", "price": "3100.50", "last": 3099.5 });
+        assert_eq!(parse_price(&body, "/price").unwrap(), 3100.5);
+        assert_eq!(parse_price(&body, "/last").unwrap(), 3099.5);
+        assert!(parse_price(&body, "/symbol").is_err());
+        assert!(parse_price(&body, "/missing").is_err());
+        assert!(parse_price(&serde_json::json!({ "price": 0 }), "/price").is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let reference = ReferencePrice::new("http://127.0.0.1:1", "/price", 2.0).unwrap();
+        assert!(reference.check(3100.0, 3050.0).is_ok());
+        let err = reference.check(3100.0, 3000.0).unwrap_err();
+        assert!((err.deviation_percent - 3.333).abs() < 0.001);
+        assert_eq!(err.max_percent, 2.0);
+        assert!(ReferencePrice::new("http://127.0.0.1:1", "/price", 0.0).is_err());
+    }
+}
@@ -0,0 +1,221 @@
+use alloy::primitives::{Address, B256};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::oracle::OracleResponse;
+use crate::order::Io;
+use crate::{issue_quote, AppState, PriceDirection};
+
+/// Delay before the first retry; doubles on each further attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Timeout for a single webhook POST.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Push-mode configuration: where to send quotes and how often.
+#[derive(Debug, Clone)]
+pub struct RelayConfig {
+    pub urls: Vec<String>,
+    pub interval: Duration,
+    /// Retries per delivery after the first attempt
+    pub max_retries: u32,
+}
+
+/// Body POSTed to each webhook — the same fields `/context` returns, plus the pair and
+/// direction the quote is for.
+#[derive(Debug, Serialize)]
+pub struct RelayPayload<'a> {
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub direction: PriceDirection,
+    #[serde(flatten)]
+    pub quote: &'a OracleResponse,
+}
+
+fn backoff(attempt: u32) -> Duration {
+    INITIAL_BACKOFF * 2u32.saturating_pow(attempt.min(16))
+}
+
+/// POST `body` to `url`, retrying with exponential backoff on network errors, 429 and
+/// 5xx responses. Other 4xx responses are not retried.
+pub async fn deliver(
+    client: &reqwest::Client,
+    url: &str,
+    body: &serde_json::Value,
+    max_retries: u32,
+) -> anyhow::Result<()> {
+    let mut attempt = 0;
+    loop {
+        let err = match client.post(url).json(body).send().await {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => {
+                let status = response.status();
+                if status.is_client_error() && status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    anyhow::bail!("Webhook {} rejected quote with {}", url, status);
+                }
+                anyhow::anyhow!("Webhook {} returned {}", url, status)
+            }
+            Err(e) => anyhow::anyhow!("Webhook {} unreachable: {}", url, e),
+        };
+
+        if attempt >= max_retries {
+            return Err(err.context(format!("giving up after {} attempts", attempt + 1)));
+        }
+        tracing::debug!("{}; retrying", err);
+        tokio::time::sleep(backoff(attempt)).await;
+        attempt += 1;
+    }
+}
+
+/// Sign a quote for each direction of the configured pair and fan it out to every webhook.
+async fn relay_once(state: &AppState, client: &reqwest::Client, config: &RelayConfig) {
+    let pair = &state.token_pair;
+    for direction in [PriceDirection::AsIs, PriceDirection::Inverted] {
+        let (input, output) = match direction {
+            PriceDirection::AsIs => (pair.quote_token, pair.base_token),
+            PriceDirection::Inverted => (pair.base_token, pair.quote_token),
+        };
+        let io = |token| Io {
+            token,
+            decimals: None,
+            vault_id: B256::ZERO,
+        };
+        let decimal_shift = match state.decimal_shift(io(input), io(output)).await {
+            Ok(shift) => shift,
+            Err(e) => {
+                tracing::warn!("Relay failed to resolve token decimals: {}", e.detail());
+                continue;
+            }
+        };
+        // Pushed quotes aren't addressed to a particular counterparty.
+        let quote = match issue_quote(
+            state,
+            direction,
+            decimal_shift,
+            state.expiry_seconds,
+            Address::ZERO,
+            None,
+            None,
+            None,
+        )
+        .await
+        {
+            Ok(quote) => quote,
+            Err(e) => {
+                tracing::warn!("Relay failed to issue {:?} quote: {:?}", direction, e);
+                continue;
+            }
+        };
+        let payload = RelayPayload {
+            base_token: state.token_pair.base_token,
+            quote_token: state.token_pair.quote_token,
+            direction,
+            quote: &quote,
+        };
+        let body = match serde_json::to_value(&payload) {
+            Ok(body) => Arc::new(body),
+            Err(e) => {
+                tracing::error!("Failed to serialize relay payload: {}", e);
+                continue;
+            }
+        };
+
+        // Deliver concurrently so one slow webhook doesn't delay the others.
+        for url in &config.urls {
+            let (client, url, body) = (client.clone(), url.clone(), body.clone());
+            let max_retries = config.max_retries;
+            tokio::spawn(async move {
+                if let Err(e) = deliver(&client, &url, &body, max_retries).await {
+                    tracing::warn!("{:#}", e);
+                }
+            });
+        }
+    }
+}
+
+/// Start pushing freshly signed quotes to the configured webhooks every `interval`.
+pub fn spawn(state: Arc<AppState>, config: RelayConfig) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("reqwest client builds with a timeout");
+        let mut ticker = tokio::time::interval(config.interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            if state.is_leader() {
+                relay_once(&state, &client, &config).await;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, routing::post, Router};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Webhook that fails with `status` for the first `failures` calls, then accepts.
+    async fn flaky_webhook(failures: u32, status: StatusCode) -> (String, Arc<AtomicU32>) {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let app = Router::new().route(
+            "/hook",
+            post(move || {
+                let counter = counter.clone();
+                async move {
+                    if counter.fetch_add(1, Ordering::SeqCst) < failures {
+                        status
+                    } else {
+                        StatusCode::OK
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{}/hook", addr), calls)
+    }
+
+    #[test]
+    fn test_backoff_doubles() {
+        assert_eq!(backoff(0), Duration::from_millis(250));
+        assert_eq!(backoff(1), Duration::from_millis(500));
+        assert_eq!(backoff(3), Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_retries_server_errors() {
+        let (url, calls) = flaky_webhook(2, StatusCode::SERVICE_UNAVAILABLE).await;
+        let client = reqwest::Client::new();
+        deliver(&client, &url, &serde_json::json!({}), 3)
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries() {
+        let (url, calls) = flaky_webhook(10, StatusCode::BAD_GATEWAY).await;
+        let client = reqwest::Client::new();
+        assert!(deliver(&client, &url, &serde_json::json!({}), 2)
+            .await
+            .is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_not_retried() {
+        let (url, calls) = flaky_webhook(10, StatusCode::BAD_REQUEST).await;
+        let client = reqwest::Client::new();
+        assert!(deliver(&client, &url, &serde_json::json!({}), 3)
+            .await
+            .is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
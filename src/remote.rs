@@ -0,0 +1,310 @@
+use alloy::primitives::{Address, Bytes, Signature, B256};
+use serde_json::json;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::rpc;
+
+/// Timeout for a single signing request, so a hung signer fails over quickly.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Bounds on the signing requests one remote signer works on at once, so a slow backend
+/// (a KMS or Vault under load) backs requests up in a bounded queue and then sheds them,
+/// instead of piling up tasks and connections without limit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SigningLimits {
+    /// Requests sent to the backend at once
+    pub concurrency: usize,
+    /// Requests waiting for one of those slots; any more are refused
+    pub queue: usize,
+}
+
+impl Default for SigningLimits {
+    fn default() -> Self {
+        Self {
+            concurrency: 16,
+            queue: 256,
+        }
+    }
+}
+
+/// A remote signer's queue was full, so the request was refused without waiting.
+#[derive(Debug, Clone, Copy, PartialEq, thiserror::Error)]
+#[error("Signing queue for {signer} is full ({queued} waiting)")]
+pub struct SigningQueueFull {
+    pub signer: Address,
+    pub queued: usize,
+}
+
+/// Load on one remote signer's pool, for `/metrics`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SigningQueueStats {
+    pub address: Address,
+    pub concurrency: usize,
+    pub in_flight: usize,
+    pub queued: usize,
+    pub rejected: u64,
+}
+
+/// Counts a request as queued until it's dropped, including when the caller gives up.
+struct Queued<'a>(&'a AtomicUsize);
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Signs through remote services speaking the Ethereum JSON-RPC `eth_sign` method
+/// (e.g. Web3Signer in eth1 mode), failing over between URLs in order.
+pub struct RemoteSigner {
+    client: reqwest::Client,
+    urls: Vec<String>,
+    address: Address,
+    /// Index of the URL that last succeeded, tried first next time
+    preferred: AtomicUsize,
+    limits: SigningLimits,
+    /// One permit per request the backend may work on at once
+    permits: Semaphore,
+    queued: AtomicUsize,
+    rejected: AtomicU64,
+}
+
+impl RemoteSigner {
+    /// Sign as `address` through the signer at any of `urls`, preferring earlier ones.
+    pub fn new(urls: Vec<String>, address: Address) -> anyhow::Result<Self> {
+        anyhow::ensure!(!urls.is_empty(), "Remote signer needs at least one URL");
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()?,
+            urls,
+            address,
+            preferred: AtomicUsize::new(0),
+            limits: SigningLimits::default(),
+            permits: Semaphore::new(SigningLimits::default().concurrency),
+            queued: AtomicUsize::new(0),
+            rejected: AtomicU64::new(0),
+        })
+    }
+
+    /// Replace the default [`SigningLimits`]. Concurrency must be at least 1.
+    pub fn with_limits(mut self, limits: SigningLimits) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            limits.concurrency > 0,
+            "Remote signer concurrency must be at least 1"
+        );
+        self.permits = Semaphore::new(limits.concurrency);
+        self.limits = limits;
+        Ok(self)
+    }
+
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    pub fn queue_stats(&self) -> SigningQueueStats {
+        SigningQueueStats {
+            address: self.address,
+            concurrency: self.limits.concurrency,
+            in_flight: self.limits.concurrency - self.permits.available_permits(),
+            queued: self.queued.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Wait for a free slot, or refuse straight away if the queue is already full.
+    async fn acquire(&self) -> Result<SemaphorePermit<'_>, SigningQueueFull> {
+        if let Ok(permit) = self.permits.try_acquire() {
+            return Ok(permit);
+        }
+        let queued = self.queued.fetch_add(1, Ordering::Relaxed);
+        let _queued = Queued(&self.queued);
+        if queued >= self.limits.queue {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(SigningQueueFull {
+                signer: self.address,
+                queued,
+            });
+        }
+        Ok(self
+            .permits
+            .acquire()
+            .await
+            .expect("signing semaphore is never closed"))
+    }
+
+    /// EIP-191 sign `hash` with `eth_sign`, trying each URL in turn (starting from the one
+    /// that last worked) until one returns a signature that recovers to our address.
+    pub async fn sign_hash(&self, hash: B256) -> anyhow::Result<Signature> {
+        let _permit = self.acquire().await?;
+        let start = self.preferred.load(Ordering::Relaxed);
+        let mut last_error = None;
+        for i in 0..self.urls.len() {
+            let index = (start + i) % self.urls.len();
+            let url = &self.urls[index];
+            match self.sign_with(url, hash).await {
+                Ok(signature) => {
+                    if index != start {
+                        tracing::warn!("Remote signer failed over to {}", url);
+                    }
+                    self.preferred.store(index, Ordering::Relaxed);
+                    return Ok(signature);
+                }
+                Err(e) => {
+                    tracing::warn!("Remote signer {} failed: {:#}", url, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error
+            .expect("at least one URL")
+            .context(format!("All {} remote signer(s) failed", self.urls.len())))
+    }
+
+    async fn sign_with(&self, url: &str, hash: B256) -> anyhow::Result<Signature> {
+        let signature: Bytes =
+            rpc::call_with(&self.client, url, "eth_sign", json!([self.address, hash])).await?;
+        let signature = Signature::from_raw(&signature)?;
+        let recovered = signature.recover_address_from_msg(hash.as_slice())?;
+        anyhow::ensure!(
+            recovered == self.address,
+            "Signature recovers to {} instead of {}",
+            recovered,
+            self.address
+        );
+        Ok(signature)
+    }
+}
+
+/// Parse an `ADDRESS=URL[|URL...]` remote signer setting.
+pub fn parse_remote_signer(s: &str) -> anyhow::Result<(Address, Vec<String>)> {
+    let (address, urls) = s.split_once('=').ok_or_else(|| {
+        anyhow::anyhow!(
+            "Invalid remote signer '{}' (expected ADDRESS=URL[|URL...])",
+            s
+        )
+    })?;
+    Ok((
+        address.trim().parse()?,
+        urls.split('|').map(|url| url.trim().to_string()).collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TEST_KEY;
+    use alloy::signers::local::PrivateKeySigner;
+    use alloy::signers::Signer as _;
+    use axum::{routing::post, Json, Router};
+
+    /// Serve `eth_sign` for the test key, like Web3Signer would.
+    async fn mock_signer() -> String {
+        let app = Router::new().route(
+            "/",
+            post(|Json(request): Json<serde_json::Value>| async move {
+                let signer: PrivateKeySigner = TEST_KEY.parse().unwrap();
+                let data: Bytes = serde_json::from_value(request["params"][1].clone()).unwrap();
+                let signature = signer.sign_message(&data).await.unwrap();
+                Json(json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "result": Bytes::from(signature.as_bytes().to_vec()),
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    fn address() -> Address {
+        TEST_KEY.parse::<PrivateKeySigner>().unwrap().address()
+    }
+
+    #[tokio::test]
+    async fn test_sign_hash_fails_over() {
+        let remote = RemoteSigner::new(
+            vec!["http://127.0.0.1:1".to_string(), mock_signer().await],
+            address(),
+        )
+        .unwrap();
+        let hash = B256::repeat_byte(0x11);
+        let signature = remote.sign_hash(hash).await.unwrap();
+        assert_eq!(
+            signature.recover_address_from_msg(hash.as_slice()).unwrap(),
+            address()
+        );
+        // The working URL is now tried first
+        assert_eq!(remote.preferred.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sign_hash_rejects_wrong_signer() {
+        let remote = RemoteSigner::new(vec![mock_signer().await], Address::repeat_byte(1)).unwrap();
+        let err = remote.sign_hash(B256::repeat_byte(0x11)).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("recovers to"));
+    }
+
+    #[test]
+    fn test_parse_remote_signer() {
+        let (signer, urls) =
+            parse_remote_signer(&format!("{}=http://a:9000|http://b:9000", address())).unwrap();
+        assert_eq!(signer, address());
+        assert_eq!(urls, ["http://a:9000", "http://b:9000"]);
+        assert!(parse_remote_signer("http://a:9000").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_queue_is_bounded() {
+        let remote = RemoteSigner::new(vec![mock_signer().await], address())
+            .unwrap()
+            .with_limits(SigningLimits {
+                concurrency: 1,
+                queue: 1,
+            })
+            .unwrap();
+        let busy = remote.acquire().await.unwrap();
+        // One request may wait for the busy slot; the next is refused
+        let waiting = remote.acquire();
+        tokio::pin!(waiting);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(20), &mut waiting)
+                .await
+                .is_err()
+        );
+        let err = remote.acquire().await.unwrap_err();
+        assert_eq!(err.queued, 1);
+        assert_eq!(
+            remote.queue_stats(),
+            SigningQueueStats {
+                address: address(),
+                concurrency: 1,
+                in_flight: 1,
+                queued: 1,
+                rejected: 1,
+            }
+        );
+
+        drop(busy);
+        drop(waiting.await.unwrap());
+        assert!(remote.sign_hash(B256::repeat_byte(0x11)).await.is_ok());
+        assert_eq!(remote.queue_stats().queued, 0);
+        assert_eq!(remote.queue_stats().in_flight, 0);
+        assert!(RemoteSigner::new(vec![mock_signer().await], address())
+            .unwrap()
+            .with_limits(SigningLimits {
+                concurrency: 0,
+                queue: 1,
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn test_requires_url() {
+        assert!(RemoteSigner::new(vec![], address()).is_err());
+    }
+}
@@ -0,0 +1,328 @@
+use alloy::primitives::{FixedBytes, U256};
+use rain_math_float::Float;
+use std::str::FromStr;
+
+use crate::PriceDirection;
+
+/// Which way to round the signed price.
+///
+/// context[0] is the order's IO ratio (input per output), so rounding up makes the taker pay
+/// more and is biased against the taker.
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum RoundingDirection {
+    /// Away from zero, against the taker
+    #[default]
+    Up,
+    /// Towards zero, in the taker's favour
+    Down,
+    /// To the nearest step, halves rounding up
+    Nearest,
+}
+
+/// The precision a price is rounded to.
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    SignificantDigits(u32),
+    /// A multiple of `coefficient * 10^exponent`
+    TickSize {
+        coefficient: U256,
+        exponent: i32,
+    },
+}
+
+/// Rounds the signed price to a number of significant digits or a tick size, so signed prices
+/// don't carry the feed's full precision.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceRounding {
+    step: Step,
+    direction: RoundingDirection,
+}
+
+/// A non-negative decimal as `coefficient * 10^exponent`.
+#[derive(Debug, Clone, PartialEq)]
+struct Decimal {
+    coefficient: U256,
+    exponent: i32,
+}
+
+impl Decimal {
+    /// Parse a plain (`3100.12`) or scientific (`3.10012e3`) non-negative decimal.
+    fn parse(s: &str) -> anyhow::Result<Self> {
+        let invalid = || anyhow::anyhow!("Invalid decimal '{}'", s);
+        let (mantissa, exp) = match s.split_once(['e', 'E']) {
+            Some((mantissa, exp)) => (mantissa, exp.parse::<i32>().map_err(|_| invalid())?),
+            None => (s, 0),
+        };
+        let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let digits = format!("{}{}", int_part, frac_part);
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        Ok(Self {
+            coefficient: U256::from_str(&digits).map_err(|_| invalid())?,
+            exponent: exp - frac_part.len() as i32,
+        })
+    }
+
+    fn digits(&self) -> u32 {
+        self.coefficient.to_string().trim_start_matches('0').len() as u32
+    }
+
+    /// Round to a multiple of `tick`.
+    fn round_to(&self, tick: &Decimal, direction: RoundingDirection) -> anyhow::Result<Self> {
+        let overflow = || anyhow::anyhow!("Price precision out of range for rounding");
+        let exponent = self.exponent.min(tick.exponent);
+        let scale = |d: &Decimal| {
+            U256::from(10)
+                .checked_pow(U256::from((d.exponent - exponent) as u32))
+                .and_then(|factor| d.coefficient.checked_mul(factor))
+                .ok_or_else(overflow)
+        };
+        let (value, step) = (scale(self)?, scale(tick)?);
+        let (mut ticks, remainder) = value.div_rem(step);
+        let round_up = match direction {
+            RoundingDirection::Up => !remainder.is_zero(),
+            RoundingDirection::Down => false,
+            RoundingDirection::Nearest => {
+                remainder.checked_mul(U256::from(2)).ok_or_else(overflow)? >= step
+            }
+        };
+        if round_up {
+            ticks += U256::from(1);
+        }
+        Ok(Self {
+            coefficient: ticks.checked_mul(tick.coefficient).ok_or_else(overflow)?,
+            exponent: tick.exponent,
+        })
+    }
+}
+
+impl std::fmt::Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let digits = self.coefficient.to_string();
+        if self.exponent >= 0 {
+            return write!(f, "{}{}", digits, "0".repeat(self.exponent as usize));
+        }
+        let frac_len = self.exponent.unsigned_abs() as usize;
+        if digits.len() <= frac_len {
+            write!(f, "0.{}{}", "0".repeat(frac_len - digits.len()), digits)
+        } else {
+            let (int_part, frac_part) = digits.split_at(digits.len() - frac_len);
+            write!(f, "{}.{}", int_part, frac_part)
+        }
+    }
+}
+
+/// As written in a pipeline's `round` stage, e.g. `6sf up` or `0.01 nearest`.
+impl std::fmt::Display for PriceRounding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.step {
+            Step::SignificantDigits(digits) => write!(f, "{}sf", digits)?,
+            Step::TickSize {
+                coefficient,
+                exponent,
+            } => write!(
+                f,
+                "{}",
+                Decimal {
+                    coefficient: *coefficient,
+                    exponent: *exponent,
+                }
+            )?,
+        }
+        let direction = match self.direction {
+            RoundingDirection::Up => "up",
+            RoundingDirection::Down => "down",
+            RoundingDirection::Nearest => "nearest",
+        };
+        write!(f, " {}", direction)
+    }
+}
+
+impl PriceRounding {
+    /// Round to `digits` significant digits.
+    pub fn significant_digits(digits: u32, direction: RoundingDirection) -> anyhow::Result<Self> {
+        anyhow::ensure!(digits > 0, "Significant digits must be positive");
+        Ok(Self {
+            step: Step::SignificantDigits(digits),
+            direction,
+        })
+    }
+
+    /// Round to a multiple of `tick_size` (a positive decimal, e.g. `0.01`).
+    pub fn tick_size(tick_size: &str, direction: RoundingDirection) -> anyhow::Result<Self> {
+        let tick = Decimal::parse(tick_size.trim())?;
+        anyhow::ensure!(!tick.coefficient.is_zero(), "Tick size must be positive");
+        Ok(Self {
+            step: Step::TickSize {
+                coefficient: tick.coefficient,
+                exponent: tick.exponent,
+            },
+            direction,
+        })
+    }
+
+    /// This rounding as applied to the price in the feed's orientation for an order priced in
+    /// `direction`. Inverting reverses the order of prices, so rounding the feed price up makes
+    /// an inverted price smaller: up and down swap to keep the same bias towards the taker.
+    pub fn for_direction(&self, direction: PriceDirection) -> Self {
+        let direction = match (direction, self.direction) {
+            (PriceDirection::Inverted, RoundingDirection::Up) => RoundingDirection::Down,
+            (PriceDirection::Inverted, RoundingDirection::Down) => RoundingDirection::Up,
+            (_, direction) => direction,
+        };
+        Self {
+            step: self.step.clone(),
+            direction,
+        }
+    }
+
+    /// Round a positive decimal price string.
+    fn round_str(&self, price: &str) -> anyhow::Result<String> {
+        let price = Decimal::parse(price)?;
+        let tick = match self.step {
+            Step::SignificantDigits(digits) => {
+                let excess = price.digits().saturating_sub(digits);
+                if excess == 0 {
+                    return Ok(price.to_string());
+                }
+                Decimal {
+                    coefficient: U256::from(1),
+                    exponent: price.exponent + excess as i32,
+                }
+            }
+            Step::TickSize {
+                coefficient,
+                exponent,
+            } => Decimal {
+                coefficient,
+                exponent,
+            },
+        };
+        let rounded = price.round_to(&tick, self.direction)?;
+        anyhow::ensure!(
+            !rounded.coefficient.is_zero(),
+            "Price {} rounds to zero at tick size {}",
+            price,
+            tick
+        );
+        Ok(rounded.to_string())
+    }
+
+    /// Round a price.
+    pub fn round(&self, price: Float) -> anyhow::Result<Float> {
+        let formatted = price
+            .format()
+            .map_err(|e| anyhow::anyhow!("Failed to format price: {:?}", e))?;
+        let rounded = self.round_str(&formatted)?;
+        Float::parse(rounded.clone()).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to parse rounded price '{}' as Rain float: {:?}",
+                rounded,
+                e
+            )
+        })
+    }
+
+    /// Round a context price slot.
+    pub fn apply(&self, price: FixedBytes<32>) -> anyhow::Result<FixedBytes<32>> {
+        let rounded = self.round(Float::from(alloy::primitives::B256::from(price)))?;
+        let bytes: alloy::primitives::B256 = rounded.into();
+        Ok(FixedBytes::from(bytes))
+    }
+}
+
+/// Render a non-negative decimal, plain (`3100.12`) or scientific the way Rain floats format
+/// small values (`5e-4`), with exactly `decimals` decimal places, halves rounding up: `5e-4` at
+/// 6 places is `0.000500`.
+pub fn format_fixed(price: &str, decimals: u8) -> anyhow::Result<String> {
+    let step = Decimal {
+        coefficient: U256::from(1),
+        exponent: -i32::from(decimals),
+    };
+    Ok(Decimal::parse(price)?
+        .round_to(&step, RoundingDirection::Nearest)?
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_significant_digits() {
+        let round = |digits, direction, price| {
+            PriceRounding::significant_digits(digits, direction)
+                .unwrap()
+                .round_str(price)
+                .unwrap()
+        };
+        assert_eq!(round(6, RoundingDirection::Up, "3100.12345678"), "3100.13");
+        assert_eq!(
+            round(6, RoundingDirection::Down, "3100.12345678"),
+            "3100.12"
+        );
+        assert_eq!(
+            round(6, RoundingDirection::Nearest, "3100.12345678"),
+            "3100.12"
+        );
+        assert_eq!(round(3, RoundingDirection::Up, "0.000322567"), "0.000323");
+        assert_eq!(round(2, RoundingDirection::Up, "3.1e5"), "310000");
+        assert_eq!(round(2, RoundingDirection::Up, "1999"), "2000");
+        // Already within precision
+        assert_eq!(round(6, RoundingDirection::Up, "1900"), "1900");
+        assert!(PriceRounding::significant_digits(0, RoundingDirection::Up).is_err());
+    }
+
+    #[test]
+    fn test_tick_size() {
+        let round = |tick, direction, price| {
+            PriceRounding::tick_size(tick, direction)
+                .unwrap()
+                .round_str(price)
+        };
+        assert_eq!(
+            round("0.05", RoundingDirection::Up, "3100.12").unwrap(),
+            "3100.15"
+        );
+        assert_eq!(
+            round("0.05", RoundingDirection::Down, "3100.12").unwrap(),
+            "3100.10"
+        );
+        assert_eq!(
+            round("0.05", RoundingDirection::Nearest, "3100.125").unwrap(),
+            "3100.15"
+        );
+        assert_eq!(
+            round("25", RoundingDirection::Down, "3110").unwrap(),
+            "3100"
+        );
+        assert!(round("0.01", RoundingDirection::Down, "0.000526").is_err());
+        assert!(PriceRounding::tick_size("0", RoundingDirection::Up).is_err());
+        assert!(PriceRounding::tick_size("-1", RoundingDirection::Up).is_err());
+    }
+
+    #[test]
+    fn test_format_fixed() {
+        assert_eq!(format_fixed("5e-4", 6).unwrap(), "0.000500");
+        assert_eq!(format_fixed("3100.12345678", 2).unwrap(), "3100.12");
+        assert_eq!(format_fixed("3100.125", 2).unwrap(), "3100.13");
+        assert_eq!(format_fixed("3.1e3", 2).unwrap(), "3100.00");
+        assert_eq!(format_fixed("1999.5", 0).unwrap(), "2000");
+        assert_eq!(format_fixed("1e-9", 4).unwrap(), "0.0000");
+        assert!(format_fixed("-1", 2).is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let rounding = PriceRounding::significant_digits(4, RoundingDirection::Up).unwrap();
+        let price: alloy::primitives::B256 =
+            Float::parse("3100.12345678".to_string()).unwrap().into();
+        let rounded = Float::from(alloy::primitives::B256::from(
+            rounding.apply(FixedBytes::from(price)).unwrap(),
+        ));
+        assert!(rounded
+            .eq(Float::parse("3101".to_string()).unwrap())
+            .unwrap());
+    }
+}
@@ -0,0 +1,221 @@
+use alloy::primitives::{Address, Bytes, B256, U256};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+
+/// Selector of ERC-20 `decimals()`.
+const DECIMALS_SELECTOR: &str = "0x313ce567";
+
+/// Selector of ERC-20 `symbol()`.
+const SYMBOL_SELECTOR: &str = "0x95d89b41";
+
+#[derive(Deserialize)]
+struct JsonRpcResponse<T> {
+    result: Option<T>,
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Deserialize, Debug)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+/// Parse a JSON-RPC hex quantity (e.g. "0x1b4") into a u64.
+fn parse_quantity(quantity: &str) -> anyhow::Result<u64> {
+    let digits = quantity
+        .strip_prefix("0x")
+        .ok_or_else(|| anyhow::anyhow!("RPC quantity '{}' is missing 0x prefix", quantity))?;
+    Ok(u64::from_str_radix(digits, 16)?)
+}
+
+/// Send a single JSON-RPC request and return its result.
+async fn call<T: DeserializeOwned>(
+    rpc_url: &str,
+    method: &str,
+    params: serde_json::Value,
+) -> anyhow::Result<T> {
+    call_with(&reqwest::Client::new(), rpc_url, method, params).await
+}
+
+/// Send a single JSON-RPC request through `client` (reusing its pooled connections) and
+/// return its result.
+pub(crate) async fn call_with<T: DeserializeOwned>(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    method: &str,
+    params: serde_json::Value,
+) -> anyhow::Result<T> {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params,
+    });
+
+    let resp: JsonRpcResponse<T> = client
+        .post(rpc_url)
+        .json(&request)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    if let Some(err) = resp.error {
+        anyhow::bail!("{} failed: {} (code {})", method, err.message, err.code);
+    }
+
+    resp.result
+        .ok_or_else(|| anyhow::anyhow!("{} returned no result", method))
+}
+
+/// Fetch the current head block number from an Ethereum JSON-RPC endpoint.
+pub async fn block_number(rpc_url: &str) -> anyhow::Result<u64> {
+    block_number_with(&reqwest::Client::new(), rpc_url).await
+}
+
+/// [`block_number`] through a shared `client`.
+pub async fn block_number_with(client: &reqwest::Client, rpc_url: &str) -> anyhow::Result<u64> {
+    let result: String = call_with(client, rpc_url, "eth_blockNumber", json!([])).await?;
+    parse_quantity(&result)
+}
+
+/// An event log as returned by `eth_getLogs`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Log {
+    pub address: Address,
+    pub topics: Vec<B256>,
+    pub data: Bytes,
+}
+
+/// Fetch logs emitted by `address` in blocks `from..=to` whose first topic is one of `topics`.
+pub async fn logs_with(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    address: Address,
+    topics: &[B256],
+    from: u64,
+    to: u64,
+) -> anyhow::Result<Vec<Log>> {
+    call_with(
+        client,
+        rpc_url,
+        "eth_getLogs",
+        json!([{
+            "address": address,
+            "topics": [topics],
+            "fromBlock": format!("0x{:x}", from),
+            "toBlock": format!("0x{:x}", to),
+        }]),
+    )
+    .await
+}
+
+/// Fetch the chain ID with `eth_chainId`.
+pub async fn chain_id(rpc_url: &str) -> anyhow::Result<u64> {
+    let result: String = call(rpc_url, "eth_chainId", json!([])).await?;
+    parse_quantity(&result)
+}
+
+/// Fetch the current gas price in wei with `eth_gasPrice`.
+pub async fn gas_price_with(client: &reqwest::Client, rpc_url: &str) -> anyhow::Result<u64> {
+    let result: String = call_with(client, rpc_url, "eth_gasPrice", json!([])).await?;
+    parse_quantity(&result)
+}
+
+/// Parse the ABI-encoded return value of `decimals()`.
+fn parse_decimals(word: &str) -> anyhow::Result<u8> {
+    let digits = word.trim_start_matches("0x");
+    anyhow::ensure!(
+        !digits.is_empty(),
+        "decimals() returned no data (not an ERC-20?)"
+    );
+    let value = U256::from_str_radix(digits, 16)
+        .map_err(|e| anyhow::anyhow!("Invalid decimals() return value '{}': {}", word, e))?;
+    u8::try_from(value)
+        .map_err(|_| anyhow::anyhow!("decimals() returned {}, expected uint8", value))
+}
+
+/// Look up an ERC-20 token's `decimals()` with `eth_call` at the latest block.
+pub async fn token_decimals(rpc_url: &str, token: Address) -> anyhow::Result<u8> {
+    let result: String = call(
+        rpc_url,
+        "eth_call",
+        json!([{ "to": token, "data": DECIMALS_SELECTOR }, "latest"]),
+    )
+    .await?;
+    parse_decimals(&result).map_err(|e| anyhow::anyhow!("Token {}: {}", token, e))
+}
+
+/// Parse the return value of `symbol()`: an ABI-encoded `string`, or a `bytes32` for older
+/// tokens such as MKR.
+fn parse_symbol(data: &str) -> anyhow::Result<String> {
+    let bytes = hex::decode(data.trim_start_matches("0x"))
+        .map_err(|e| anyhow::anyhow!("Invalid symbol() return value '{}': {}", data, e))?;
+    let symbol = if bytes.len() == 32 {
+        bytes.into_iter().take_while(|b| *b != 0).collect()
+    } else {
+        let word = |i: usize| {
+            bytes
+                .get(i..i + 32)
+                .map(|w| U256::from_be_slice(w))
+                .and_then(|w| usize::try_from(w).ok())
+                .ok_or_else(|| anyhow::anyhow!("symbol() returned malformed data"))
+        };
+        let offset = word(0)?;
+        let len = word(offset)?;
+        bytes
+            .get(offset + 32..offset + 32 + len)
+            .ok_or_else(|| anyhow::anyhow!("symbol() returned malformed data"))?
+            .to_vec()
+    };
+    String::from_utf8(symbol).map_err(|_| anyhow::anyhow!("symbol() returned non-UTF-8 data"))
+}
+
+/// Look up an ERC-20 token's `symbol()` with `eth_call` at the latest block.
+pub async fn token_symbol(rpc_url: &str, token: Address) -> anyhow::Result<String> {
+    let result: String = call(
+        rpc_url,
+        "eth_call",
+        json!([{ "to": token, "data": SYMBOL_SELECTOR }, "latest"]),
+    )
+    .await?;
+    parse_symbol(&result).map_err(|e| anyhow::anyhow!("Token {}: {}", token, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quantity() {
+        assert_eq!(parse_quantity("0x0").unwrap(), 0);
+        assert_eq!(parse_quantity("0x1b4").unwrap(), 436);
+        assert_eq!(parse_quantity("0x1fa0e4c").unwrap(), 33164876);
+    }
+
+    #[test]
+    fn test_parse_decimals() {
+        let six = format!("0x{:064x}", 6);
+        assert_eq!(parse_decimals(&six).unwrap(), 6);
+        assert!(parse_decimals(&format!("0x{:064x}", 256)).is_err());
+        assert!(parse_decimals("0x").is_err());
+    }
+
+    #[test]
+    fn test_parse_symbol() {
+        let string = format!("0x{:064x}{:064x}{:0<64}", 32, 4, hex::encode("WETH"));
+        assert_eq!(parse_symbol(&string).unwrap(), "WETH");
+        let bytes32 = format!("0x{:0<64}", hex::encode("MKR"));
+        assert_eq!(parse_symbol(&bytes32).unwrap(), "MKR");
+        assert!(parse_symbol("0x").is_err());
+        assert!(parse_symbol(&format!("0x{:064x}{:064x}", 32, 4)).is_err());
+    }
+
+    #[test]
+    fn test_parse_quantity_invalid() {
+        assert!(parse_quantity("1b4").is_err());
+        assert!(parse_quantity("0xzz").is_err());
+    }
+}
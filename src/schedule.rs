@@ -0,0 +1,305 @@
+use chrono::NaiveDate;
+use std::fmt;
+use std::str::FromStr;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+const DAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// A weekly quoting window: from `start` to `end` (minutes after midnight UTC) on each of
+/// `days` (Monday first). An `end` at or before `start` runs past midnight into the next day.
+#[derive(Debug, Clone, PartialEq)]
+struct Window {
+    days: [bool; 7],
+    start: u32,
+    end: u32,
+}
+
+/// When quoting is enabled for a pair, e.g. to pause tokenized-equity or FX pairs outside
+/// market hours when their feeds go stale. Parsed from comma-separated entries, each either
+/// a weekly window (`mon-fri 13:30-20:00`, `daily 00:00-24:00`, `sun-thu 22:00-05:00`) or a
+/// closed date (`closed 2026-12-25`), all in UTC. A window belongs to the day it starts on,
+/// so a closed date cancels the windows starting that day.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Schedule {
+    windows: Vec<Window>,
+    closed: Vec<NaiveDate>,
+}
+
+fn parse_day(s: &str) -> Result<usize, String> {
+    DAYS.iter()
+        .position(|day| s.eq_ignore_ascii_case(day))
+        .ok_or_else(|| format!("unknown day '{}'", s))
+}
+
+/// Parse `daily` or `+`-joined days and day ranges, e.g. `mon-fri` or `mon+wed+fri`.
+fn parse_days(s: &str) -> Result<[bool; 7], String> {
+    if s.eq_ignore_ascii_case("daily") {
+        return Ok([true; 7]);
+    }
+    let mut days = [false; 7];
+    for part in s.split('+') {
+        match part.split_once('-') {
+            // Ranges wrap through Sunday, e.g. `sun-thu`
+            Some((from, to)) => {
+                let (mut day, to) = (parse_day(from)?, parse_day(to)?);
+                days[day] = true;
+                while day != to {
+                    day = (day + 1) % 7;
+                    days[day] = true;
+                }
+            }
+            None => days[parse_day(part)?] = true,
+        }
+    }
+    Ok(days)
+}
+
+/// Format days as `+`-joined runs, e.g. `mon-thu+sun`.
+fn format_days(days: &[bool; 7]) -> String {
+    if *days == [true; 7] {
+        return "daily".to_string();
+    }
+    let mut parts = Vec::new();
+    let mut day = 0;
+    while day < 7 {
+        if !days[day] {
+            day += 1;
+            continue;
+        }
+        let start = day;
+        while day + 1 < 7 && days[day + 1] {
+            day += 1;
+        }
+        parts.push(if start == day {
+            DAYS[day].to_string()
+        } else {
+            format!("{}-{}", DAYS[start], DAYS[day])
+        });
+        day += 1;
+    }
+    parts.join("+")
+}
+
+/// Parse `HH:MM` (up to `24:00`) as minutes after midnight.
+fn parse_time(s: &str) -> Result<u32, String> {
+    let invalid = || format!("invalid time '{}', expected HH:MM", s);
+    let (hours, minutes) = s.split_once(':').ok_or_else(invalid)?;
+    let (hours, minutes): (u32, u32) = (
+        hours.parse().map_err(|_| invalid())?,
+        minutes.parse().map_err(|_| invalid())?,
+    );
+    let time = hours * 60 + minutes;
+    if minutes >= 60 || time > 24 * 60 {
+        return Err(invalid());
+    }
+    Ok(time)
+}
+
+fn format_time(minutes: u32) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+impl FromStr for Window {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (days, times) = s
+            .split_once(' ')
+            .ok_or_else(|| format!("expected '<days> HH:MM-HH:MM', got '{}'", s))?;
+        let (start, end) = times
+            .trim()
+            .split_once('-')
+            .ok_or_else(|| format!("expected HH:MM-HH:MM, got '{}'", times.trim()))?;
+        let window = Self {
+            days: parse_days(days)?,
+            start: parse_time(start)?,
+            end: parse_time(end)?,
+        };
+        if window.start == window.end {
+            return Err(format!("empty window '{}'", s));
+        }
+        Ok(window)
+    }
+}
+
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}-{}",
+            format_days(&self.days),
+            format_time(self.start),
+            format_time(self.end)
+        )
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut schedule = Self {
+            windows: Vec::new(),
+            closed: Vec::new(),
+        };
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry.strip_prefix("closed ") {
+                Some(date) => schedule.closed.push(
+                    date.trim()
+                        .parse()
+                        .map_err(|e| format!("invalid closed date '{}': {}", date.trim(), e))?,
+                ),
+                None => schedule.windows.push(entry.parse()?),
+            }
+        }
+        if schedule.windows.is_empty() {
+            return Err("schedule has no quoting windows".to_string());
+        }
+        Ok(schedule)
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries: Vec<String> = self
+            .windows
+            .iter()
+            .map(ToString::to_string)
+            .chain(self.closed.iter().map(|date| format!("closed {}", date)))
+            .collect();
+        write!(f, "{}", entries.join(", "))
+    }
+}
+
+impl TryFrom<String> for Schedule {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Schedule> for String {
+    fn from(schedule: Schedule) -> Self {
+        schedule.to_string()
+    }
+}
+
+impl Schedule {
+    fn is_closed(&self, day: u64) -> bool {
+        let Some(date) = chrono::DateTime::from_timestamp((day * SECONDS_PER_DAY) as i64, 0) else {
+            return false;
+        };
+        self.closed.contains(&date.date_naive())
+    }
+
+    /// `[start, end)` unix seconds of each session starting on `day` (days since the epoch).
+    fn sessions(&self, day: u64) -> impl Iterator<Item = (u64, u64)> + '_ {
+        // 1970-01-01 was a Thursday
+        let weekday = ((day + 3) % 7) as usize;
+        let closed = self.is_closed(day);
+        let midnight = day * SECONDS_PER_DAY;
+        self.windows
+            .iter()
+            .filter(move |w| w.days[weekday] && !closed)
+            .map(move |w| {
+                let end_day = if w.end > w.start { 0 } else { SECONDS_PER_DAY };
+                (
+                    midnight + u64::from(w.start) * 60,
+                    midnight + end_day + u64::from(w.end) * 60,
+                )
+            })
+    }
+
+    /// Whether quoting is enabled at unix time `now`.
+    pub fn is_open(&self, now: u64) -> bool {
+        let today = now / SECONDS_PER_DAY;
+        (today.saturating_sub(1)..=today)
+            .flat_map(|day| self.sessions(day))
+            .any(|(start, end)| start <= now && now < end)
+    }
+
+    /// When quoting next opens after unix time `now`, if within a year.
+    pub fn next_open(&self, now: u64) -> Option<u64> {
+        let today = now / SECONDS_PER_DAY;
+        (today..=today + 366).find_map(|day| {
+            self.sessions(day)
+                .map(|(start, _)| start)
+                .filter(|&start| start > now)
+                .min()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Monday 2023-11-13 00:00 UTC
+    const MONDAY: u64 = 1_699_833_600;
+    const HOUR: u64 = 3600;
+
+    #[test]
+    fn test_parse() {
+        let schedule: Schedule = "mon-fri 13:30-20:00, closed 2023-11-23".parse().unwrap();
+        assert_eq!(
+            schedule.to_string(),
+            "mon-fri 13:30-20:00, closed 2023-11-23"
+        );
+        assert_eq!(schedule.to_string().parse::<Schedule>().unwrap(), schedule);
+        assert_eq!(
+            "daily 00:00-24:00".parse::<Schedule>().unwrap().to_string(),
+            "daily 00:00-24:00"
+        );
+        assert_eq!(
+            "sun-thu 22:00-05:00, sat 10:00-12:00"
+                .parse::<Schedule>()
+                .unwrap()
+                .to_string(),
+            "mon-thu+sun 22:00-05:00, sat 10:00-12:00"
+        );
+        assert!("mon+wed+fri 09:00-17:00".parse::<Schedule>().is_ok());
+        assert!("mon-fri 13:30".parse::<Schedule>().is_err());
+        assert!("funday 09:00-17:00".parse::<Schedule>().is_err());
+        assert!("mon 09:00-24:01".parse::<Schedule>().is_err());
+        assert!("mon 09:00-09:00".parse::<Schedule>().is_err());
+        assert!("closed 2023-11-23".parse::<Schedule>().is_err());
+    }
+
+    #[test]
+    fn test_weekly_window() {
+        let schedule: Schedule = "mon-fri 13:30-20:00, closed 2023-11-14".parse().unwrap();
+        assert!(!schedule.is_open(MONDAY + 13 * HOUR));
+        assert!(schedule.is_open(MONDAY + 14 * HOUR));
+        assert!(!schedule.is_open(MONDAY + 20 * HOUR));
+        assert_eq!(
+            schedule.next_open(MONDAY + 13 * HOUR),
+            Some(MONDAY + 13 * HOUR + 1800)
+        );
+        // Tuesday is closed, so Monday evening waits for Wednesday
+        assert!(!schedule.is_open(MONDAY + 24 * HOUR + 14 * HOUR));
+        assert_eq!(
+            schedule.next_open(MONDAY + 21 * HOUR),
+            Some(MONDAY + 48 * HOUR + 13 * HOUR + 1800)
+        );
+        // Friday evening waits for Monday
+        assert_eq!(
+            schedule.next_open(MONDAY + 4 * 24 * HOUR + 21 * HOUR),
+            Some(MONDAY + 7 * 24 * HOUR + 13 * HOUR + 1800)
+        );
+    }
+
+    #[test]
+    fn test_overnight_window() {
+        // FX-style: Sunday evening through Friday evening
+        let schedule: Schedule = "sun-thu 22:00-21:00".parse().unwrap();
+        let sunday = MONDAY - 24 * HOUR;
+        assert!(!schedule.is_open(sunday + 21 * HOUR));
+        assert!(schedule.is_open(sunday + 23 * HOUR));
+        assert!(schedule.is_open(MONDAY + 12 * HOUR));
+        assert!(schedule.is_open(MONDAY + 4 * 24 * HOUR + 20 * HOUR));
+        assert!(!schedule.is_open(MONDAY + 4 * 24 * HOUR + 21 * HOUR));
+        assert!(!schedule.is_open(MONDAY + 5 * 24 * HOUR + 12 * HOUR));
+    }
+}
@@ -0,0 +1,82 @@
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// Read a secret from a file (e.g. a Docker secret under `/run/secrets`), trimming
+/// surrounding whitespace. The file contents are zeroed once the secret is copied out, and
+/// the returned secret is zeroed when dropped.
+pub fn read_secret_file(path: &Path) -> anyhow::Result<Zeroizing<String>> {
+    let bytes =
+        Zeroizing::new(std::fs::read(path).map_err(|e| {
+            anyhow::anyhow!("Failed to read secret file {}: {}", path.display(), e)
+        })?);
+    let secret = std::str::from_utf8(&bytes)
+        .map_err(|_| anyhow::anyhow!("Secret file {} is not UTF-8", path.display()))?
+        .trim();
+    anyhow::ensure!(
+        !secret.is_empty(),
+        "Secret file {} is empty",
+        path.display()
+    );
+    Ok(Zeroizing::new(secret.to_string()))
+}
+
+/// A secret given either directly (flag or env var) or as a file to read it from. A direct
+/// value is moved (not copied) into the returned secret, so no plain copy is left behind.
+pub fn resolve(
+    value: Option<String>,
+    file: Option<&Path>,
+) -> anyhow::Result<Option<Zeroizing<String>>> {
+    match (value, file) {
+        (Some(value), _) => Ok(Some(Zeroizing::new(value))),
+        (None, Some(path)) => read_secret_file(path).map(Some),
+        (None, None) => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secret_file(name: &str, contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_read_secret_file_trims() {
+        let path = secret_file("secret-trim", "0xabc\n");
+        assert_eq!(read_secret_file(&path).unwrap().as_str(), "0xabc");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_read_secret_file_errors() {
+        let path = secret_file("secret-empty", " \n");
+        assert!(read_secret_file(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+        let err = read_secret_file(&path).unwrap_err();
+        assert!(err.to_string().contains("Failed to read secret file"));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let path = secret_file("secret-resolve", "from-file");
+        assert_eq!(
+            resolve(Some("direct".to_string()), None)
+                .unwrap()
+                .as_deref()
+                .map(String::as_str),
+            Some("direct")
+        );
+        assert_eq!(
+            resolve(None, Some(&path))
+                .unwrap()
+                .as_deref()
+                .map(String::as_str),
+            Some("from-file")
+        );
+        assert!(resolve(None, None).unwrap().is_none());
+        std::fs::remove_file(path).unwrap();
+    }
+}
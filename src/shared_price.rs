@@ -0,0 +1,126 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::pyth::PriceData;
+use crate::storage::SharedPrice;
+use crate::{timing, AppState};
+
+/// Lets replicas sharing a storage backend quote from one Hermes poller instead of each
+/// calling Hermes per request: the leader fetches the feed every `interval` and publishes it
+/// to storage, and every replica prices from the published price while it is at most
+/// `max_age` old, falling back to Hermes itself otherwise.
+#[derive(Debug, Clone, Copy)]
+pub struct SharedPriceCache {
+    pub interval: Duration,
+    pub max_age: Duration,
+}
+
+/// The published price for the configured feed, if it is fresh enough to quote from. Storage
+/// errors are logged and treated as a miss, so requests fall back to Hermes.
+pub(crate) async fn latest(state: &AppState) -> Option<PriceData> {
+    let cache = state.shared_prices?;
+    let started = std::time::Instant::now();
+    let shared = match state.storage.shared_price(&state.pyth_price_feed_id).await {
+        Ok(shared) => shared?,
+        Err(e) => {
+            tracing::warn!("Failed to read the shared price: {:#}", e);
+            return None;
+        }
+    };
+    let age = state.clock.now().as_secs() as i64 - shared.fetched_at;
+    if age > cache.max_age.as_secs() as i64 {
+        tracing::debug!("Shared price is {}s old; fetching from Hermes", age);
+        return None;
+    }
+    timing::record(timing::Stage::PriceFetch, started.elapsed());
+    Some(PriceData {
+        price: shared.price,
+        expo: shared.expo,
+        publish_time: shared.publish_time,
+    })
+}
+
+/// Fetch the feed and publish it, if this replica is the leader.
+async fn poll(state: &AppState) {
+    if !state.is_leader() {
+        return;
+    }
+    let data = match crate::fetch_hermes_price(state).await {
+        Ok(data) => data,
+        Err(e) => {
+            tracing::warn!("Failed to fetch the price to share: {:#}", e);
+            return;
+        }
+    };
+    let shared = SharedPrice {
+        price: data.price,
+        expo: data.expo,
+        publish_time: data.publish_time,
+        fetched_at: state.clock.now().as_secs() as i64,
+    };
+    if let Err(e) = state
+        .storage
+        .set_shared_price(&state.pyth_price_feed_id, &shared)
+        .await
+    {
+        tracing::warn!("Failed to publish the shared price: {:#}", e);
+    }
+}
+
+/// Poll and publish the feed while leading. Does nothing without a shared price cache.
+pub fn spawn(state: Arc<AppState>) -> Option<JoinHandle<()>> {
+    let interval = state.shared_prices?.interval;
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            poll(&state).await;
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use crate::leader::LeaderElection;
+    use crate::storage::{MemoryStorage, Storage};
+    use crate::tests::{TEST_KEY, USDC, WETH};
+    use crate::{pyth, TokenPairConfig};
+
+    fn replica(storage: Arc<dyn Storage>, hermes: &str, now: u64) -> AppState {
+        let token_pair = TokenPairConfig::new(WETH, USDC).unwrap();
+        AppState::new(TEST_KEY, "ff61", 5, token_pair)
+            .unwrap()
+            .with_storage(storage)
+            .with_hermes(pyth::Hermes::default().with_base_url(hermes))
+            .with_clock(Arc::new(FixedClock(Duration::from_secs(now))))
+            .with_leader_election(LeaderElection::new("pollers", Duration::from_secs(15)).unwrap())
+            .with_shared_price_cache(SharedPriceCache {
+                interval: Duration::from_secs(1),
+                max_age: Duration::from_secs(5),
+            })
+    }
+
+    #[tokio::test]
+    async fn test_only_the_leader_publishes() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::default());
+        let shared = SharedPrice {
+            price: 200000000000,
+            expo: -8,
+            publish_time: 1700000000,
+            fetched_at: 1700000000,
+        };
+        storage.set_shared_price("ff61", &shared).await.unwrap();
+        // Hermes is unreachable, so any price comes from storage
+        let follower = replica(storage.clone(), "http://127.0.0.1:1", 1700000005);
+        assert_eq!(latest(&follower).await.unwrap().price, 200000000000);
+        poll(&follower).await;
+        assert_eq!(storage.shared_price("ff61").await.unwrap(), Some(shared));
+
+        let stale = replica(storage, "http://127.0.0.1:1", 1700000006);
+        assert!(latest(&stale).await.is_none());
+    }
+}
@@ -1,24 +1,402 @@
-use alloy::primitives::{Address, Bytes, FixedBytes};
-use alloy::signers::Signer as AlloySigner;
+use alloy::primitives::{
+    eip191_hash_message, keccak256, Address, Bytes, FixedBytes, Signature, B256, U256,
+};
 use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::SignerSync;
+use alloy::sol_types::SolValue;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use zeroize::Zeroizing;
 // EIP-191 signing for Rain signed context
 
+use crate::remote::{RemoteSigner, SigningQueueFull, SigningQueueStats};
+
+/// Where signing keys live.
+enum Backend {
+    Local(PrivateKeySigner),
+    Remote(RemoteSigner),
+    Failover(Failover),
+    Erc1271(Erc1271),
+}
+
+/// A smart contract wallet advertised as the signer, with one of its owners signing for it.
+struct Erc1271 {
+    wallet: Address,
+    format: Erc1271Format,
+    owner: Box<Signer>,
+}
+
+/// How a signature is laid out for the wallet's ERC-1271 `isValidSignature(hash, signature)`,
+/// where `hash` is the EIP-191 hash the orderbook checks an EOA signature against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Erc1271Format {
+    /// The owner's signature over `hash` itself, for wallets that `ecrecover` it directly
+    Plain,
+    /// A Safe owner's `eth_sign` signature over the Safe's EIP-712 `SafeMessage` wrapping of
+    /// `hash` (Safes with a threshold of 1)
+    Safe { chain_id: u64 },
+}
+
+impl Erc1271Format {
+    /// Parse `plain` or `safe`; Safe messages are bound to `chain_id`, so `safe` needs it.
+    pub fn parse(format: &str, chain_id: Option<u64>) -> anyhow::Result<Self> {
+        match format {
+            "plain" => Ok(Self::Plain),
+            "safe" => Ok(Self::Safe {
+                chain_id: chain_id
+                    .ok_or_else(|| anyhow::anyhow!("The safe ERC-1271 format needs a chain ID"))?,
+            }),
+            other => anyhow::bail!(
+                "Unknown ERC-1271 format {:?} (expected plain or safe)",
+                other
+            ),
+        }
+    }
+}
+
+/// The hash a Safe's owners sign for `isValidSignature(hash, ...)`: the EIP-712 hash of a
+/// `SafeMessage` holding `abi.encode(hash)`, in the domain of `safe` on `chain_id`.
+pub fn safe_message_hash(chain_id: u64, safe: Address, hash: B256) -> B256 {
+    let domain_separator = keccak256(
+        (
+            keccak256("EIP712Domain(uint256 chainId,address verifyingContract)"),
+            U256::from(chain_id),
+            safe,
+        )
+            .abi_encode(),
+    );
+    let message =
+        keccak256((keccak256("SafeMessage(bytes message)"), keccak256(hash)).abi_encode());
+    keccak256(
+        [
+            &[0x19, 0x01],
+            domain_separator.as_slice(),
+            message.as_slice(),
+        ]
+        .concat(),
+    )
+}
+
+/// Safe's signature type for an owner's `eth_sign` signature: `v` offset by 4.
+const SAFE_ETH_SIGN_V_OFFSET: u8 = 4;
+
+impl Erc1271 {
+    /// What the owner EIP-191 signs for a context hash, so the wallet accepts the result for
+    /// the hash the orderbook asks it about.
+    fn owner_message(&self, hash: B256) -> B256 {
+        match self.format {
+            // An EIP-191 signature over `hash` is a plain signature over its EIP-191 hash
+            Erc1271Format::Plain => hash,
+            Erc1271Format::Safe { chain_id } => {
+                safe_message_hash(chain_id, self.wallet, eip191_hash_message(hash))
+            }
+        }
+    }
+
+    async fn sign_hash(&self, hash: B256) -> anyhow::Result<(Bytes, Address)> {
+        // Boxed because the owner may itself be a failover chain
+        let (signature, _) = Box::pin(self.owner.sign_hash(self.owner_message(hash))).await?;
+        let mut signature = signature.to_vec();
+        if let Erc1271Format::Safe { .. } = self.format {
+            signature[64] += SAFE_ETH_SIGN_V_OFFSET;
+        }
+        Ok((signature.into(), self.wallet))
+    }
+
+    /// Check `signature` over a context hash was made by one of the owner's keys. Whether
+    /// the wallet accepts that owner can only be checked on-chain.
+    fn check(&self, hash: B256, signature: &[u8]) -> anyhow::Result<Address> {
+        let mut signature = signature.to_vec();
+        if let Erc1271Format::Safe { .. } = self.format {
+            anyhow::ensure!(signature.len() == 65, "Safe signature must be 65 bytes");
+            signature[64] = signature[64]
+                .checked_sub(SAFE_ETH_SIGN_V_OFFSET)
+                .ok_or_else(|| anyhow::anyhow!("Safe signature isn't an eth_sign signature"))?;
+        }
+        let owner = Signature::from_raw(&signature)?
+            .recover_address_from_msg(self.owner_message(hash).as_slice())?;
+        anyhow::ensure!(
+            self.owner.members().iter().any(|m| m.address() == owner),
+            "Signature recovers to {}, not an owner key of wallet {}",
+            owner,
+            self.wallet
+        );
+        Ok(self.wallet)
+    }
+}
+
+/// Signers in priority order; each signature comes from the first healthy one.
+struct Failover {
+    signers: Vec<Signer>,
+    healthy: Vec<AtomicBool>,
+    /// Signatures made by each signer while a higher-priority one was unavailable
+    fallback_signatures: Vec<AtomicU64>,
+}
+
+/// How contexts are hashed and signed, which the orderbook must verify the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SigningMode {
+    /// `personal_sign` over `keccak256(abi.encodePacked(context))`, as `LibContext.build`
+    /// checks it
+    Eip191,
+    /// Signed by an owner of a smart contract wallet for the wallet to validate with
+    /// ERC-1271, which `SignatureChecker` calls for contract signers
+    Erc1271,
+}
+
+/// How a context is packed into the hash that is signed, defined in the client crate so clients
+/// verify with the same scheme the server signs with.
+pub use rain_oracle_client::HashScheme;
+
+/// Health of one signer in a failover chain, for `/metrics`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignerHealth {
+    pub address: Address,
+    pub healthy: bool,
+    pub fallback_signatures: u64,
+}
+
+/// Hash signed by failover health checks (not the hash of any context this server signs).
+const HEALTH_PROBE: B256 = B256::repeat_byte(0x01);
+
+impl Failover {
+    /// Index of the signer currently preferred: the first healthy one.
+    fn active(&self) -> usize {
+        self.healthy
+            .iter()
+            .position(|h| h.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    fn set_healthy(&self, index: usize, healthy: bool) {
+        let was = self.healthy[index].swap(healthy, Ordering::Relaxed);
+        let address = self.signers[index].address();
+        match (was, healthy) {
+            (true, false) => tracing::warn!("Signer {} marked unhealthy", address),
+            (false, true) => tracing::info!("Signer {} healthy again", address),
+            _ => {}
+        }
+    }
+
+    /// Try healthy signers in priority order, then unhealthy ones as a last resort.
+    async fn sign_hash(&self, hash: B256) -> anyhow::Result<(Bytes, Address)> {
+        let (healthy, unhealthy): (Vec<usize>, Vec<usize>) =
+            (0..self.signers.len()).partition(|&i| self.healthy[i].load(Ordering::Relaxed));
+        let mut last_error = None;
+        for index in healthy.into_iter().chain(unhealthy) {
+            // Boxed because chains recurse into their members
+            match Box::pin(self.signers[index].sign_hash(hash)).await {
+                Ok(signed) => {
+                    self.set_healthy(index, true);
+                    if index > 0 {
+                        tracing::warn!("Signed with fallback signer {}", signed.1);
+                        self.fallback_signatures[index].fetch_add(1, Ordering::Relaxed);
+                    }
+                    return Ok(signed);
+                }
+                Err(e) => {
+                    // A full queue means busy, not broken: pass over it without marking it
+                    // unhealthy
+                    if e.downcast_ref::<SigningQueueFull>().is_none() {
+                        self.set_healthy(index, false);
+                    }
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error
+            .expect("failover chain is never empty")
+            .context("Every signer in the failover chain failed"))
+    }
+
+    /// Probe every signer, so a recovered primary is preferred again.
+    async fn check_health(&self) {
+        for (index, signer) in self.signers.iter().enumerate() {
+            let healthy = match signer.sign_hash(HEALTH_PROBE).await {
+                Ok(_) => true,
+                Err(e) => {
+                    tracing::warn!("Signer {} health check failed: {:#}", signer.address(), e);
+                    false
+                }
+            };
+            self.set_healthy(index, healthy);
+        }
+    }
+}
+
 /// EIP-191 signer for Rain signed context.
 pub struct Signer {
-    inner: PrivateKeySigner,
+    backend: Backend,
+}
+
+/// Shows the address and backend only, never key material.
+impl std::fmt::Debug for Signer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let backend = match self.backend {
+            Backend::Local(_) => "local",
+            Backend::Remote(_) => "remote",
+            Backend::Failover(_) => "failover",
+            Backend::Erc1271(_) => "erc1271",
+        };
+        f.debug_struct("Signer")
+            .field("address", &self.address())
+            .field("backend", &backend)
+            .finish()
+    }
 }
 
 impl Signer {
     /// Create a new signer from a hex private key (with or without 0x prefix).
+    ///
+    /// The key is decoded into a buffer that is zeroed afterwards, and the resulting signing
+    /// key zeroes itself on drop. Callers should zero their copy of the hex string too.
     pub fn new(private_key: &str) -> anyhow::Result<Self> {
-        let key = private_key.strip_prefix("0x").unwrap_or(private_key);
-        let signer: PrivateKeySigner = key.parse()?;
-        Ok(Self { inner: signer })
+        let key = private_key.trim();
+        let key = key.strip_prefix("0x").unwrap_or(key);
+        let mut bytes = Zeroizing::new([0u8; 32]);
+        // Don't echo the input in the error: it may be (most of) a key.
+        hex::decode_to_slice(key, bytes.as_mut_slice())
+            .map_err(|_| anyhow::anyhow!("Private key must be 32 bytes of hex"))?;
+        let signer = PrivateKeySigner::from_slice(bytes.as_slice())?;
+        Ok(Self {
+            backend: Backend::Local(signer),
+        })
     }
 
-    /// Get the signer's address.
+    /// Create a signer that delegates to a remote signing service.
+    pub fn remote(remote: RemoteSigner) -> Self {
+        Self {
+            backend: Backend::Remote(remote),
+        }
+    }
+
+    /// Create a signer that uses the first healthy of `signers`, in priority order (e.g. a
+    /// remote signer with a local key as backup). Each keeps its own address, so contracts
+    /// must accept every address in the chain.
+    pub fn failover(signers: Vec<Signer>) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            !signers.is_empty(),
+            "Failover chain needs at least one signer"
+        );
+        Ok(Self {
+            backend: Backend::Failover(Failover {
+                healthy: signers.iter().map(|_| AtomicBool::new(true)).collect(),
+                fallback_signatures: signers.iter().map(|_| AtomicU64::new(0)).collect(),
+                signers,
+            }),
+        })
+    }
+
+    /// Sign as the smart contract wallet `wallet`, with `owner` (one of its owners' keys)
+    /// making signatures laid out as `format` for the wallet to validate with ERC-1271.
+    pub fn erc1271(wallet: Address, format: Erc1271Format, owner: Signer) -> Self {
+        Self {
+            backend: Backend::Erc1271(Erc1271 {
+                wallet,
+                format,
+                owner: Box::new(owner),
+            }),
+        }
+    }
+
+    /// Get the signer's address (for a failover chain, the currently preferred signer's).
     pub fn address(&self) -> Address {
-        self.inner.address()
+        match &self.backend {
+            Backend::Local(signer) => signer.address(),
+            Backend::Remote(remote) => remote.address(),
+            Backend::Failover(chain) => chain.signers[chain.active()].address(),
+            Backend::Erc1271(wallet) => wallet.wallet,
+        }
+    }
+
+    /// The individual signers behind this one: itself, or every member of a failover chain.
+    pub fn members(&self) -> Vec<&Signer> {
+        match &self.backend {
+            Backend::Failover(chain) => chain.signers.iter().flat_map(Signer::members).collect(),
+            _ => vec![self],
+        }
+    }
+
+    /// Health of each member of a failover chain (empty for other signers).
+    pub fn health(&self) -> Vec<SignerHealth> {
+        match &self.backend {
+            Backend::Failover(chain) => chain
+                .signers
+                .iter()
+                .enumerate()
+                .map(|(i, signer)| SignerHealth {
+                    address: signer.address(),
+                    healthy: chain.healthy[i].load(Ordering::Relaxed),
+                    fallback_signatures: chain.fallback_signatures[i].load(Ordering::Relaxed),
+                })
+                .collect(),
+            Backend::Erc1271(wallet) => wallet.owner.health(),
+            _ => vec![],
+        }
+    }
+
+    /// Signing pool load of each remote signer among [`Signer::members`] (and the owner keys
+    /// of an ERC-1271 wallet).
+    pub fn queue_stats(&self) -> Vec<SigningQueueStats> {
+        self.members()
+            .into_iter()
+            .flat_map(|signer| match &signer.backend {
+                Backend::Remote(remote) => vec![remote.queue_stats()],
+                Backend::Erc1271(wallet) => wallet.owner.queue_stats(),
+                _ => vec![],
+            })
+            .collect()
+    }
+
+    /// Re-probe the members of a failover chain (a no-op for other signers).
+    pub async fn check_health(&self) {
+        match &self.backend {
+            Backend::Failover(chain) => chain.check_health().await,
+            Backend::Erc1271(wallet) => Box::pin(wallet.owner.check_health()).await,
+            _ => {}
+        }
+    }
+
+    pub fn signing_mode(&self) -> SigningMode {
+        match self.backend {
+            Backend::Erc1271(_) => SigningMode::Erc1271,
+            _ => SigningMode::Eip191,
+        }
+    }
+
+    /// Whether this signer holds its key in process, so it can sign without awaiting.
+    pub fn is_local(&self) -> bool {
+        matches!(self.backend, Backend::Local(_))
+    }
+
+    /// EIP-191 sign a context hash, returning the signature and the address that made it.
+    async fn sign_hash(&self, hash: B256) -> anyhow::Result<(Bytes, Address)> {
+        let bytes = |signature: Signature| Bytes::copy_from_slice(&signature.as_bytes());
+        match &self.backend {
+            Backend::Local(signer) => Ok((
+                bytes(signer.sign_message_sync(hash.as_slice())?),
+                signer.address(),
+            )),
+            Backend::Remote(remote) => Ok((bytes(remote.sign_hash(hash).await?), remote.address())),
+            Backend::Failover(chain) => chain.sign_hash(hash).await,
+            Backend::Erc1271(wallet) => wallet.sign_hash(hash).await,
+        }
+    }
+
+    /// The address `signature` over `context` is valid for: the address it recovers to, or
+    /// for an ERC-1271 wallet, the wallet once the signature checks out against its owner.
+    pub fn recover(
+        &self,
+        context: &[FixedBytes<32>],
+        signature: &[u8],
+        scheme: HashScheme,
+    ) -> anyhow::Result<Address> {
+        match &self.backend {
+            Backend::Erc1271(wallet) => wallet.check(scheme.hash(context), signature),
+            _ => Ok(scheme.recover_signer(context, signature)?),
+        }
     }
 
     /// Sign a context array using EIP-191.
@@ -30,29 +408,247 @@ impl Signer {
         &self,
         context: &[FixedBytes<32>],
     ) -> anyhow::Result<(Bytes, Address)> {
-        // abi.encodePacked(bytes32[]) — just concatenate the raw bytes
-        let packed: Vec<u8> = context.iter().flat_map(|b| b.as_slice().to_vec()).collect();
-
-        // keccak256 of the packed data
-        let hash = alloy::primitives::keccak256(&packed);
+        self.sign_context_with(context, HashScheme::Packed).await
+    }
 
+    /// [`Signer::sign_context`] over the hash `scheme` packs the context into.
+    pub async fn sign_context_with(
+        &self,
+        context: &[FixedBytes<32>],
+        scheme: HashScheme,
+    ) -> anyhow::Result<(Bytes, Address)> {
         // Sign with EIP-191 prefix: the Rain orderbook contract applies
         // toEthSignedMessageHash(hash) before ecrecover, so we must sign
         // the raw hash using sign_message (which internally prefixes with
         // "\x19Ethereum Signed Message:\n32" before signing).
-        let signature = self.inner.sign_message(hash.as_slice()).await?;
+        self.sign_hash(scheme.hash(context)).await
+    }
 
-        Ok((Bytes::from(signature.as_bytes().to_vec()), self.address()))
+    /// [`Signer::sign_context_with`] without awaiting, for callers off the async runtime (e.g.
+    /// a thread pool). Only local keys can sign this way; remote signers and failover chains
+    /// need the network.
+    pub fn sign_context_sync(
+        &self,
+        context: &[FixedBytes<32>],
+        scheme: HashScheme,
+    ) -> anyhow::Result<(Bytes, Address)> {
+        let Backend::Local(signer) = &self.backend else {
+            anyhow::bail!(
+                "Signer {} can't sign synchronously: its key isn't held locally",
+                self.address()
+            );
+        };
+        let signature = signer.sign_message_sync(scheme.hash(context).as_slice())?;
+        Ok((
+            Bytes::copy_from_slice(&signature.as_bytes()),
+            signer.address(),
+        ))
+    }
+}
+
+/// Where a [`Rotation`] is at a given time.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationPhase {
+    /// Only the current signer signs
+    Pending,
+    /// The next signer co-signs alongside the current one
+    Overlap,
+    /// Only the next signer signs
+    Complete,
+}
+
+/// Blue/green rotation of the default signer: from `starts_at` the next signer co-signs
+/// every context alongside the current one, so on-chain allowlists can be updated without a
+/// quoting gap, and from `ends_at` it signs alone.
+pub struct Rotation {
+    pub next: Signer,
+    /// Unix seconds
+    pub starts_at: u64,
+    /// Unix seconds
+    pub ends_at: u64,
+}
+
+impl Rotation {
+    pub fn phase(&self, now: u64) -> RotationPhase {
+        if now >= self.ends_at {
+            RotationPhase::Complete
+        } else if now >= self.starts_at {
+            RotationPhase::Overlap
+        } else {
+            RotationPhase::Pending
+        }
     }
 }
 
+/// Signers keyed by (base, quote) pair, falling back to a default signer for pairs without
+/// their own key, so one market's key can be rotated without touching the others.
+///
+/// Co-signers sign every context in addition to the pair's signer, for on-chain schemes
+/// requiring M-of-N oracle signatures.
+pub struct SignerRegistry {
+    default: Signer,
+    pairs: HashMap<(Address, Address), Signer>,
+    cosigners: Vec<Signer>,
+    rotation: Option<Rotation>,
+    hash_scheme: HashScheme,
+}
+
+impl SignerRegistry {
+    pub fn new(default: Signer) -> Self {
+        Self {
+            default,
+            pairs: HashMap::new(),
+            cosigners: Vec::new(),
+            rotation: None,
+            hash_scheme: HashScheme::Packed,
+        }
+    }
+
+    /// Hash every context `scheme`'s way before signing it.
+    pub fn set_hash_scheme(&mut self, scheme: HashScheme) {
+        self.hash_scheme = scheme;
+    }
+
+    pub fn hash_scheme(&self) -> HashScheme {
+        self.hash_scheme
+    }
+
+    /// Rotate the default signer to `rotation.next` (pairs with their own key are unaffected).
+    pub fn set_rotation(&mut self, rotation: Rotation) {
+        self.rotation = Some(rotation);
+    }
+
+    pub fn rotation(&self) -> Option<&Rotation> {
+        self.rotation.as_ref()
+    }
+
+    /// Also sign every context with `signer`.
+    pub fn add_cosigner(&mut self, signer: Signer) {
+        self.cosigners.push(signer);
+    }
+
+    /// Sign `context` with every co-signer at unix time `now`, in configured order, followed
+    /// by the next signer during a rotation's overlap window.
+    pub async fn cosign(
+        &self,
+        context: &[FixedBytes<32>],
+        now: u64,
+    ) -> anyhow::Result<Vec<(Bytes, Address)>> {
+        let mut signatures = Vec::with_capacity(self.cosigners.len() + 1);
+        for signer in self.cosigners_at(now) {
+            signatures.push(signer.sign_context_with(context, self.hash_scheme).await?);
+        }
+        Ok(signatures)
+    }
+
+    /// Co-signers at unix time `now`, including the next signer during a rotation's overlap
+    /// window.
+    pub fn cosigners_at(&self, now: u64) -> Vec<&Signer> {
+        let overlapping = self
+            .rotation
+            .as_ref()
+            .filter(|rotation| rotation.phase(now) == RotationPhase::Overlap)
+            .map(|rotation| &rotation.next);
+        self.cosigners.iter().chain(overlapping).collect()
+    }
+
+    /// Sign quotes for the `base`/`quote` pair with `signer` instead of the default.
+    pub fn insert(&mut self, base: Address, quote: Address, signer: Signer) {
+        self.pairs.insert((base, quote), signer);
+    }
+
+    /// The signer for a pair at unix time `now`.
+    pub fn for_pair(&self, base: Address, quote: Address, now: u64) -> &Signer {
+        self.pairs
+            .get(&(base, quote))
+            .unwrap_or_else(|| self.default_at(now))
+    }
+
+    /// The default signer at unix time `now`: the next signer once a rotation is complete.
+    fn default_at(&self, now: u64) -> &Signer {
+        match &self.rotation {
+            Some(rotation) if rotation.phase(now) == RotationPhase::Complete => &rotation.next,
+            _ => &self.default,
+        }
+    }
+
+    pub fn default_signer(&self) -> &Signer {
+        &self.default
+    }
+
+    pub fn cosigners(&self) -> &[Signer] {
+        &self.cosigners
+    }
+
+    /// Health of every failover chain member.
+    pub fn health(&self) -> Vec<SignerHealth> {
+        self.configured().flat_map(Signer::health).collect()
+    }
+
+    /// Signing pool load of every remote signer.
+    pub fn queue_stats(&self) -> Vec<SigningQueueStats> {
+        self.configured().flat_map(Signer::queue_stats).collect()
+    }
+
+    /// Re-probe every failover chain.
+    pub async fn check_health(&self) {
+        for signer in self.configured() {
+            signer.check_health().await;
+        }
+    }
+
+    /// Each configured signer (default, per-pair, co-signers, the next signer of a
+    /// rotation), with failover chains as one.
+    pub(crate) fn configured(&self) -> impl Iterator<Item = &Signer> {
+        std::iter::once(&self.default)
+            .chain(self.pairs.values())
+            .chain(&self.cosigners)
+            .chain(self.rotation.as_ref().map(|rotation| &rotation.next))
+    }
+
+    /// Every distinct signer (including co-signers and failover chain members), default first.
+    pub fn signers(&self) -> Vec<&Signer> {
+        let mut signers: Vec<&Signer> = Vec::new();
+        for signer in self.configured().flat_map(Signer::members) {
+            if !signers.iter().any(|s| s.address() == signer.address()) {
+                signers.push(signer);
+            }
+        }
+        signers
+    }
+}
+
+/// Parse a `BASE/QUOTE=PRIVATE_KEY` per-pair signer setting.
+pub fn parse_pair_key(s: &str) -> anyhow::Result<(Address, Address, String)> {
+    let (pair, key) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("Invalid pair signer (expected BASE/QUOTE=PRIVATE_KEY)"))?;
+    let (base, quote) = crate::export::parse_pair(pair)?;
+    Ok((base, quote, key.trim().to_string()))
+}
+
+/// `keccak256(abi.encodePacked(context))`, the hash signed for a context, computed by the
+/// client crate so clients verify against exactly what is signed here.
+pub fn context_hash(context: &[FixedBytes<32>]) -> B256 {
+    rain_oracle_client::context_hash(context)
+}
+
+/// Recover the address that produced `signature` over `context`, as the orderbook does.
+pub fn recover_context_signer(
+    context: &[FixedBytes<32>],
+    signature: &[u8],
+) -> anyhow::Result<Address> {
+    Ok(rain_oracle_client::recover_context_signer(
+        context, signature,
+    )?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use alloy::primitives::U256;
-
-    // Test private key — DO NOT use in production
-    const TEST_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    use crate::tests::TEST_KEY;
+    use alloy::primitives::{b256, U256};
 
     #[test]
     fn test_signer_from_key() {
@@ -77,6 +673,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_invalid_key_and_debug_hide_key_material() {
+        let err = Signer::new(&TEST_KEY[..62]).unwrap_err();
+        assert!(!err.to_string().contains(&TEST_KEY[..62]));
+        assert!(Signer::new("").is_err());
+
+        let debug = format!("{:?}", Signer::new(TEST_KEY).unwrap());
+        assert!(debug.contains("0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"));
+        assert!(!debug.contains(&TEST_KEY[..16]));
+    }
+
     #[tokio::test]
     async fn test_sign_context_deterministic() {
         let signer = Signer::new(TEST_KEY).unwrap();
@@ -93,6 +700,270 @@ mod tests {
         assert_eq!(sig1.len(), 65, "EIP-191 signature should be 65 bytes");
     }
 
+    #[tokio::test]
+    async fn test_sign_context_sync() {
+        let signer = Signer::new(TEST_KEY).unwrap();
+        let context = vec![
+            FixedBytes::<32>::from(U256::from(1000u64)),
+            FixedBytes::<32>::from(U256::from(2000u64)),
+        ];
+        let packed: Vec<u8> = context.iter().flat_map(|b| b.to_vec()).collect();
+        assert_eq!(
+            context_hash(&context),
+            alloy::primitives::keccak256(&packed)
+        );
+
+        assert!(signer.is_local());
+        assert_eq!(
+            signer
+                .sign_context_sync(&context, HashScheme::Packed)
+                .unwrap(),
+            signer.sign_context(&context).await.unwrap()
+        );
+        let tagged = HashScheme::parse("tagged", Some("rain.oracle.v1")).unwrap();
+        assert_eq!(
+            signer.sign_context_sync(&context, tagged).unwrap(),
+            signer.sign_context_with(&context, tagged).await.unwrap()
+        );
+        let remote = Signer::remote(
+            RemoteSigner::new(
+                vec!["http://127.0.0.1:1".to_string()],
+                Address::repeat_byte(1),
+            )
+            .unwrap(),
+        );
+        assert!(!remote.is_local());
+        assert!(remote
+            .sign_context_sync(&context, HashScheme::Packed)
+            .is_err());
+    }
+
+    #[test]
+    fn test_hash_scheme_vectors() {
+        let context = vec![
+            FixedBytes::<32>::from(U256::from(1000u64)),
+            FixedBytes::<32>::from(U256::from(2000u64)),
+        ];
+        let words = |words: &[u64]| -> Vec<u8> {
+            words
+                .iter()
+                .flat_map(|w| U256::from(*w).to_be_bytes::<32>())
+                .collect()
+        };
+
+        // abi.encodePacked: the slots back to back
+        let packed = HashScheme::parse("packed", None).unwrap();
+        assert_eq!(packed.hash(&context), keccak256(words(&[1000, 2000])));
+        assert_eq!(
+            packed.hash(&context),
+            b256!("ae87574c34cf1bc6f63e6bf0ff2252c57a6f6f4689b073c120c4bdcc2d7e1b70")
+        );
+
+        // abi.encode: the array's offset and length ahead of the slots
+        let abi_encoded = HashScheme::parse("abi_encoded", None).unwrap();
+        assert_eq!(
+            abi_encoded.hash(&context),
+            keccak256(words(&[0x20, 2, 1000, 2000]))
+        );
+        assert_eq!(
+            abi_encoded.hash(&context),
+            b256!("b3a63b19992b41acbe0229ab2c74ebedfbdd8c253c14655a25aabdbb501557ff")
+        );
+
+        // Tagged: keccak256(tag) ahead of the slots
+        let tagged = HashScheme::parse("tagged", Some("rain.oracle.v1")).unwrap();
+        let tag = keccak256("rain.oracle.v1");
+        assert_eq!(tagged.tag(), Some(tag));
+        assert_eq!(
+            tagged.hash(&context),
+            keccak256([tag.as_slice(), &words(&[1000, 2000])].concat())
+        );
+        assert_eq!(
+            tagged.hash(&context),
+            b256!("3aced416a46ebf369fe3370896bba89bf4f472edb7e8b66dc69faba18be8021d")
+        );
+
+        assert!(HashScheme::parse("tagged", None).is_err());
+        assert!(HashScheme::parse("packed", Some("tag")).is_err());
+        assert!(HashScheme::parse("eip712", None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sign_context_with_scheme() {
+        let signer = Signer::new(TEST_KEY).unwrap();
+        let context = vec![FixedBytes::<32>::from(U256::from(1000u64))];
+        let scheme = HashScheme::AbiEncoded;
+        let (sig, addr) = signer.sign_context_with(&context, scheme).await.unwrap();
+
+        assert_eq!(scheme.recover_signer(&context, &sig).unwrap(), addr);
+        assert_ne!(recover_context_signer(&context, &sig).unwrap(), addr);
+        assert_eq!(
+            signer
+                .sign_context_with(&context, HashScheme::Packed)
+                .await
+                .unwrap(),
+            signer.sign_context(&context).await.unwrap()
+        );
+    }
+
+    #[test]
+    fn test_safe_message_typehashes() {
+        // As declared in Safe's contracts
+        assert_eq!(
+            keccak256("EIP712Domain(uint256 chainId,address verifyingContract)"),
+            b256!("47e79534a245952e8b16893a336b85a3d9ea9fa8c573f3d803afb92a79469218")
+        );
+        assert_eq!(
+            keccak256("SafeMessage(bytes message)"),
+            b256!("60b3cbf8b4a223d68d641b3b6ddf9a298e7f33710cf3d3a9d1146b5a6150fbca")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_erc1271_signer() {
+        let owner = Signer::new(TEST_KEY).unwrap().address();
+        let wallet = Address::repeat_byte(0x5a);
+        let context = vec![FixedBytes::<32>::from(U256::from(1000u64))];
+        let hash = context_hash(&context);
+
+        // Plain: the owner's own signature, advertised as the wallet's
+        let plain = Signer::erc1271(wallet, Erc1271Format::Plain, Signer::new(TEST_KEY).unwrap());
+        assert_eq!(plain.address(), wallet);
+        let (sig, addr) = plain.sign_context(&context).await.unwrap();
+        assert_eq!(addr, wallet);
+        assert_eq!(recover_context_signer(&context, &sig).unwrap(), owner);
+        assert_eq!(
+            plain.recover(&context, &sig, HashScheme::Packed).unwrap(),
+            wallet
+        );
+
+        // Safe: an eth_sign signature (v + 4) over the SafeMessage for the EIP-191 hash
+        let format = Erc1271Format::parse("safe", Some(8453)).unwrap();
+        let safe = Signer::erc1271(wallet, format, Signer::new(TEST_KEY).unwrap());
+        let (sig, addr) = safe.sign_context(&context).await.unwrap();
+        assert_eq!(addr, wallet);
+        assert!(matches!(sig[64], 31 | 32));
+        let mut owner_sig = sig.to_vec();
+        owner_sig[64] -= 4;
+        let message = safe_message_hash(8453, wallet, eip191_hash_message(hash));
+        assert_eq!(
+            Signature::from_raw(&owner_sig)
+                .unwrap()
+                .recover_address_from_msg(message.as_slice())
+                .unwrap(),
+            owner
+        );
+        assert_eq!(
+            safe.recover(&context, &sig, HashScheme::Packed).unwrap(),
+            wallet
+        );
+        let other = vec![FixedBytes::<32>::from(U256::from(2000u64))];
+        assert!(safe.recover(&other, &sig, HashScheme::Packed).is_err());
+        // Bound to the chain
+        assert_ne!(
+            safe_message_hash(1, wallet, hash),
+            safe_message_hash(8453, wallet, hash)
+        );
+
+        assert!(Erc1271Format::parse("safe", None).is_err());
+        assert!(Erc1271Format::parse("argent", Some(1)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_recover_context_signer() {
+        let signer = Signer::new(TEST_KEY).unwrap();
+        let context = vec![FixedBytes::<32>::from(U256::from(1000u64))];
+        let (sig, addr) = signer.sign_context(&context).await.unwrap();
+
+        assert_eq!(recover_context_signer(&context, &sig).unwrap(), addr);
+        let other = vec![FixedBytes::<32>::from(U256::from(2000u64))];
+        assert_ne!(recover_context_signer(&other, &sig).unwrap(), addr);
+    }
+
+    #[test]
+    fn test_signer_registry_per_pair() {
+        // Hardhat account #1
+        const OTHER_KEY: &str = "59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+        let (weth, usdc) = (Address::repeat_byte(0x42), Address::repeat_byte(0x83));
+        let mut registry = SignerRegistry::new(Signer::new(TEST_KEY).unwrap());
+        registry.insert(weth, usdc, Signer::new(OTHER_KEY).unwrap());
+
+        let default = registry.default_signer().address();
+        let pair = registry.for_pair(weth, usdc, 0).address();
+        assert_ne!(pair, default);
+        assert_eq!(registry.for_pair(usdc, weth, 0).address(), default);
+        assert_eq!(registry.signers().len(), 2);
+
+        // A pair reusing the default key isn't listed twice
+        registry.insert(usdc, weth, Signer::new(TEST_KEY).unwrap());
+        assert_eq!(registry.signers().len(), 2);
+    }
+
+    #[test]
+    fn test_rotation() {
+        const NEXT_KEY: &str = "59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+        let mut registry = SignerRegistry::new(Signer::new(TEST_KEY).unwrap());
+        let current = registry.default_signer().address();
+        let next = Signer::new(NEXT_KEY).unwrap();
+        let next_address = next.address();
+        registry.set_rotation(Rotation {
+            next,
+            starts_at: 100,
+            ends_at: 200,
+        });
+        let signer_at = |now| {
+            registry
+                .for_pair(Address::ZERO, Address::ZERO, now)
+                .address()
+        };
+        let cosigners_at = |now| {
+            registry
+                .cosigners_at(now)
+                .iter()
+                .map(|s| s.address())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(signer_at(99), current);
+        assert!(cosigners_at(99).is_empty());
+        assert_eq!(signer_at(100), current);
+        assert_eq!(cosigners_at(100), vec![next_address]);
+        assert_eq!(signer_at(200), next_address);
+        assert!(cosigners_at(200).is_empty());
+        assert_eq!(registry.signers().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cosign() {
+        const OTHER_KEY: &str = "59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+        let mut registry = SignerRegistry::new(Signer::new(TEST_KEY).unwrap());
+        let context = vec![FixedBytes::<32>::from(U256::from(1000u64))];
+        assert!(registry.cosign(&context, 0).await.unwrap().is_empty());
+
+        registry.add_cosigner(Signer::new(OTHER_KEY).unwrap());
+        let signatures = registry.cosign(&context, 0).await.unwrap();
+        assert_eq!(signatures.len(), 1);
+        let (sig, addr) = &signatures[0];
+        assert_ne!(*addr, registry.default_signer().address());
+        assert_eq!(recover_context_signer(&context, sig).unwrap(), *addr);
+        assert_eq!(registry.signers().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_pair_key() {
+        let (base, quote, key) = parse_pair_key(&format!(
+            "{}/{}=0x{}",
+            Address::repeat_byte(0x42),
+            Address::repeat_byte(0x83),
+            TEST_KEY
+        ))
+        .unwrap();
+        assert_eq!(base, Address::repeat_byte(0x42));
+        assert_eq!(quote, Address::repeat_byte(0x83));
+        assert_eq!(key, format!("0x{}", TEST_KEY));
+        assert!(parse_pair_key(TEST_KEY).is_err());
+    }
+
     #[tokio::test]
     async fn test_sign_context_different_data() {
         let signer = Signer::new(TEST_KEY).unwrap();
@@ -108,4 +979,49 @@ mod tests {
             "Different context should produce different signatures"
         );
     }
+
+    #[tokio::test]
+    async fn test_failover_to_backup_signer() {
+        let backup = Signer::new(TEST_KEY).unwrap();
+        let backup_address = backup.address();
+        let primary = Signer::remote(
+            RemoteSigner::new(
+                vec!["http://127.0.0.1:1".to_string()],
+                Address::repeat_byte(1),
+            )
+            .unwrap(),
+        );
+        let chain = Signer::failover(vec![primary, backup]).unwrap();
+        assert_eq!(chain.members().len(), 2);
+
+        let context = vec![FixedBytes::<32>::from(U256::from(1000u64))];
+        let (sig, addr) = chain.sign_context(&context).await.unwrap();
+        assert_eq!(addr, backup_address);
+        assert_eq!(
+            recover_context_signer(&context, &sig).unwrap(),
+            backup_address
+        );
+        assert_eq!(chain.address(), backup_address);
+        assert_eq!(
+            chain.health(),
+            vec![
+                SignerHealth {
+                    address: Address::repeat_byte(1),
+                    healthy: false,
+                    fallback_signatures: 0,
+                },
+                SignerHealth {
+                    address: backup_address,
+                    healthy: true,
+                    fallback_signatures: 1,
+                },
+            ]
+        );
+
+        // The primary is still down, so health checks leave it unhealthy
+        chain.check_health().await;
+        assert!(!chain.health()[0].healthy);
+        assert!(Signer::new(TEST_KEY).unwrap().health().is_empty());
+        assert!(Signer::failover(vec![]).is_err());
+    }
 }
@@ -0,0 +1,173 @@
+use alloy::primitives::{FixedBytes, B256};
+use rain_math_float::Float;
+use std::str::FromStr;
+
+/// An extra value signed into the context after the price and expiry (and nonce), e.g. a
+/// funding or borrow rate for perp-like strategies that need more than the spot price.
+///
+/// The source is any JSON API with the value at a JSON pointer, as a number or a decimal
+/// string, signed as a Rain float.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ContextSource {
+    /// Label for the slot in `/pairs` and errors, e.g. `funding`
+    pub name: String,
+    pub url: String,
+    pub pointer: String,
+}
+
+impl FromStr for ContextSource {
+    type Err = String;
+
+    /// Parse `NAME=URL#POINTER`, e.g.
+    /// `funding=https://fapi.binance.com/fapi/v1/premiumIndex?symbol=ETHUSDT#/lastFundingRate`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, rest) = s
+            .trim()
+            .split_once('=')
+            .ok_or_else(|| "expected NAME=URL#POINTER".to_string())?;
+        let (url, pointer) = rest
+            .rsplit_once('#')
+            .ok_or_else(|| format!("missing #POINTER in '{}'", rest))?;
+        if name.is_empty() || url.is_empty() {
+            return Err("expected NAME=URL#POINTER".to_string());
+        }
+        if !pointer.starts_with('/') {
+            return Err(format!("invalid JSON pointer '{}'", pointer));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            url: url.to_string(),
+            pointer: pointer.to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for ContextSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}#{}", self.name, self.url, self.pointer)
+    }
+}
+
+impl TryFrom<String> for ContextSource {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<ContextSource> for String {
+    fn from(source: ContextSource) -> Self {
+        source.to_string()
+    }
+}
+
+/// Read a decimal value from a source response.
+fn parse_value(body: &serde_json::Value, pointer: &str) -> anyhow::Result<Float> {
+    let value = body
+        .pointer(pointer)
+        .ok_or_else(|| anyhow::anyhow!("Response has no value at {}", pointer))?;
+    let decimal = match value {
+        serde_json::Value::Number(n) => n.to_string(),
+        serde_json::Value::String(s) => s.trim().to_string(),
+        _ => anyhow::bail!("Invalid value {} at {}", value, pointer),
+    };
+    Float::parse(decimal.clone())
+        .map_err(|e| anyhow::anyhow!("Invalid value '{}' at {}: {:?}", decimal, pointer, e))
+}
+
+/// The sources signed into a derivative pair's context, in slot order.
+#[derive(Debug, Clone)]
+pub struct ContextSources {
+    sources: Vec<ContextSource>,
+    client: reqwest::Client,
+}
+
+impl ContextSources {
+    pub fn new(sources: Vec<ContextSource>) -> Self {
+        Self {
+            sources,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Slot names, in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.sources.iter().map(|s| s.name.as_str())
+    }
+
+    async fn fetch(&self, source: &ContextSource) -> anyhow::Result<Float> {
+        let body: serde_json::Value = self
+            .client
+            .get(&source.url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        parse_value(&body, &source.pointer)
+    }
+
+    /// Fetch every source, returning the context slots in order. Fails if any source does,
+    /// rather than signing a context with fewer slots than the strategy reads.
+    pub async fn slots(&self) -> anyhow::Result<Vec<FixedBytes<32>>> {
+        let mut slots = Vec::with_capacity(self.sources.len());
+        for source in &self.sources {
+            let value = self
+                .fetch(source)
+                .await
+                .map_err(|e| e.context(format!("Context source '{}' failed", source.name)))?;
+            let bytes: B256 = value.into();
+            slots.push(FixedBytes::from(bytes));
+        }
+        Ok(slots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_source() {
+        let source: ContextSource =
+            "funding=https://example.com/premium?symbol=ETH#/lastFundingRate"
+                .parse()
+                .unwrap();
+        assert_eq!(source.name, "funding");
+        assert_eq!(source.url, "https://example.com/premium?symbol=ETH");
+        assert_eq!(source.pointer, "/lastFundingRate");
+        assert!("https://example.com#/rate"
+            .parse::<ContextSource>()
+            .is_err());
+        assert!("funding=https://example.com"
+            .parse::<ContextSource>()
+            .is_err());
+        assert!("funding=https://example.com#rate"
+            .parse::<ContextSource>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_value() {
+        let body = serde_json::json!({ "rate": "-0.000125", "borrow": 0.05, "name": null });
+        let slot = |value: Float| B256::from(value);
+        let float = |s: &str| slot(Float::parse(s.to_string()).unwrap());
+        assert_eq!(
+            slot(parse_value(&body, "/rate").unwrap()),
+            float("-0.000125")
+        );
+        assert_eq!(slot(parse_value(&body, "/borrow").unwrap()), float("0.05"));
+        assert!(parse_value(&body, "/name").is_err());
+        assert!(parse_value(&body, "/missing").is_err());
+    }
+}
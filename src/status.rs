@@ -0,0 +1,470 @@
+use alloy::primitives::{Address, B256};
+use axum::{extract::State, Json};
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::metrics::percentile;
+use crate::oracle::format_unix_timestamp;
+use crate::pyth::PriceData;
+use crate::sign::{RotationPhase, Signer, SigningMode};
+use crate::{AppState, ExpiryMode};
+
+/// Freshness and error counters for a single price feed.
+#[derive(Debug, Clone, Default, Serialize, utoipa::ToSchema)]
+pub struct FeedStatus {
+    pub feed_id: String,
+    /// When the last successful fetch completed (ISO 8601)
+    pub last_success: Option<String>,
+    /// Last fetched price as a decimal string (as published, before any inversion)
+    pub last_price: Option<String>,
+    /// Pyth publish time of the last fetched price (ISO 8601)
+    pub last_publish_time: Option<String>,
+    pub success_count: u64,
+    pub error_count: u64,
+    pub consecutive_errors: u64,
+    pub last_error: Option<String>,
+    /// When the last failed fetch completed (ISO 8601)
+    pub last_error_at: Option<String>,
+    /// Start of the current run of consecutive errors (ISO 8601), if the feed is failing
+    pub failing_since: Option<String>,
+    #[serde(skip)]
+    pub(crate) failing_since_unix: Option<i64>,
+    /// Durations of the most recent fetches, oldest first
+    #[serde(skip)]
+    pub(crate) recent_latencies_ms: VecDeque<f64>,
+}
+
+/// Fetches kept for `/health/feeds` latency stats.
+const LATENCY_WINDOW: usize = 100;
+
+/// Per-feed fetch statistics, keyed by feed ID.
+#[derive(Default)]
+pub struct FeedStats {
+    feeds: Mutex<BTreeMap<String, FeedStatus>>,
+}
+
+impl FeedStats {
+    /// Create stats pre-populated with the configured feeds, so feeds that have never been
+    /// fetched still show up.
+    pub fn new<'a>(feed_ids: impl IntoIterator<Item = &'a str>) -> Self {
+        let feeds = feed_ids
+            .into_iter()
+            .map(|id| {
+                (
+                    id.to_string(),
+                    FeedStatus {
+                        feed_id: id.to_string(),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect();
+        Self {
+            feeds: Mutex::new(feeds),
+        }
+    }
+
+    fn update(&self, feed_id: &str, f: impl FnOnce(&mut FeedStatus)) {
+        let mut feeds = self.feeds.lock().unwrap();
+        let status = feeds
+            .entry(feed_id.to_string())
+            .or_insert_with(|| FeedStatus {
+                feed_id: feed_id.to_string(),
+                ..Default::default()
+            });
+        f(status);
+    }
+
+    pub fn record_success(&self, feed_id: &str, now: i64, data: &PriceData) {
+        self.update(feed_id, |status| {
+            status.last_success = Some(format_unix_timestamp(now));
+            status.last_price = Some(crate::oracle::format_pyth_price(data.price, data.expo));
+            status.last_publish_time = Some(format_unix_timestamp(data.publish_time));
+            status.success_count += 1;
+            status.consecutive_errors = 0;
+            status.failing_since = None;
+            status.failing_since_unix = None;
+        });
+    }
+
+    pub fn record_error(&self, feed_id: &str, now: i64, err: &anyhow::Error) {
+        self.update(feed_id, |status| {
+            status.error_count += 1;
+            status.consecutive_errors += 1;
+            status.last_error = Some(err.to_string());
+            status.last_error_at = Some(format_unix_timestamp(now));
+            if status.failing_since_unix.is_none() {
+                status.failing_since = Some(format_unix_timestamp(now));
+                status.failing_since_unix = Some(now);
+            }
+        });
+    }
+
+    /// Record how long a fetch (successful or not) took.
+    pub fn record_latency(&self, feed_id: &str, latency: Duration) {
+        self.update(feed_id, |status| {
+            if status.recent_latencies_ms.len() == LATENCY_WINDOW {
+                status.recent_latencies_ms.pop_front();
+            }
+            status
+                .recent_latencies_ms
+                .push_back(latency.as_secs_f64() * 1000.0);
+        });
+    }
+
+    pub fn snapshot(&self) -> Vec<FeedStatus> {
+        self.feeds.lock().unwrap().values().cloned().collect()
+    }
+}
+
+/// Process start time, for uptime reporting.
+pub struct StartTime {
+    instant: Instant,
+    unix: i64,
+}
+
+impl StartTime {
+    pub fn now(unix: i64) -> Self {
+        Self {
+            instant: Instant::now(),
+            unix,
+        }
+    }
+}
+
+/// Configured token pair as reported by `/status`.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct PairStatus {
+    #[schema(value_type = String)]
+    pub base_token: Address,
+    #[schema(value_type = String)]
+    pub quote_token: Address,
+    /// Other addresses accepted as the base token
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<String>)]
+    pub base_aliases: Vec<Address>,
+    /// Other addresses accepted as the quote token
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<String>)]
+    pub quote_aliases: Vec<Address>,
+    pub feed_id: String,
+    /// "timestamp" or "block_number"
+    pub expiry_mode: &'static str,
+}
+
+/// Response for `GET /status`.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct StatusResponse {
+    pub version: &'static str,
+    /// Git commit the binary was built from, if known at build time
+    pub commit: Option<&'static str>,
+    pub started_at: String,
+    pub uptime_seconds: u64,
+    /// Chain the deployment serves, when configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    pub pairs: Vec<PairStatus>,
+    #[schema(value_type = Vec<String>)]
+    pub signers: Vec<Address>,
+    pub feeds: Vec<FeedStatus>,
+    /// Whether this replica runs the background pollers, when leader election is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leader: Option<bool>,
+}
+
+/// The configured token pair and how it is priced.
+pub(crate) fn pair_status(state: &AppState) -> PairStatus {
+    let pair = &state.token_pair;
+    PairStatus {
+        base_token: pair.base_token,
+        quote_token: pair.quote_token,
+        base_aliases: pair.base_aliases.clone(),
+        quote_aliases: pair.quote_aliases.clone(),
+        feed_id: state.pyth_price_feed_id.clone(),
+        expiry_mode: match pair.expiry {
+            ExpiryMode::Timestamp => "timestamp",
+            ExpiryMode::BlockNumber { .. } => "block_number",
+        },
+    }
+}
+
+/// GET handler — one endpoint for ops dashboards to scrape.
+#[utoipa::path(
+    get,
+    path = "/status",
+    responses((status = 200, description = "Server status", body = StatusResponse)),
+)]
+pub async fn status(State(state): State<Arc<AppState>>) -> Json<StatusResponse> {
+    Json(StatusResponse {
+        version: crate::version::VERSION,
+        commit: (crate::version::GIT_COMMIT != "unknown").then_some(crate::version::GIT_COMMIT),
+        started_at: format_unix_timestamp(state.started_at.unix),
+        uptime_seconds: state.started_at.instant.elapsed().as_secs(),
+        chain_id: state.chain_id,
+        pairs: vec![pair_status(&state)],
+        signers: state.signer_addresses(),
+        feeds: state.feed_stats.snapshot(),
+        leader: state.leader.as_ref().map(|leader| leader.is_leader()),
+    })
+}
+
+/// Response for `GET /signer`.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct SignerResponse {
+    pub signing_mode: SigningMode,
+    /// How the context is packed into the signed hash: `packed`, `abi_encoded` or `tagged`
+    pub hash_scheme: &'static str,
+    /// Domain tag prefixed to the context under the `tagged` scheme
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub hash_tag: Option<B256>,
+    /// Address currently signing quotes for the configured pair
+    #[schema(value_type = String)]
+    pub address: Address,
+    /// Every address that may sign for the pair (each member of a failover chain), in
+    /// priority order; all of them need registering on-chain
+    #[schema(value_type = Vec<String>)]
+    pub signers: Vec<Address>,
+    /// Addresses co-signing every context, in configured order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<String>)]
+    pub cosigners: Vec<Address>,
+    /// Blue/green rotation of the default signer, when configured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<RotationStatus>,
+}
+
+/// A configured signer rotation, announced so the next address can be registered on-chain
+/// before it signs alone.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct RotationStatus {
+    #[schema(value_type = String)]
+    pub next: Address,
+    /// When the next signer starts co-signing (ISO 8601)
+    pub starts_at: String,
+    /// When the next signer takes over alone (ISO 8601)
+    pub ends_at: String,
+    pub phase: RotationPhase,
+}
+
+/// GET handler — the addresses signing quotes, for deployment scripts registering them
+/// on-chain.
+#[utoipa::path(
+    get,
+    path = "/signer",
+    responses((status = 200, description = "Signer addresses and signing mode", body = SignerResponse)),
+)]
+pub async fn signer(State(state): State<Arc<AppState>>) -> Json<SignerResponse> {
+    let pair = &state.token_pair;
+    let now = state.clock.now().as_secs();
+    let signer = state
+        .signers
+        .for_pair(pair.base_token, pair.quote_token, now);
+    let addresses = |signer: &Signer| -> Vec<Address> {
+        signer.members().iter().map(|s| s.address()).collect()
+    };
+    Json(SignerResponse {
+        signing_mode: signer.signing_mode(),
+        hash_scheme: state.signers.hash_scheme().name(),
+        hash_tag: state.signers.hash_scheme().tag(),
+        address: signer.address(),
+        signers: addresses(signer),
+        cosigners: state
+            .signers
+            .cosigners_at(now)
+            .into_iter()
+            .flat_map(addresses)
+            .collect(),
+        rotation: state.signers.rotation().map(|rotation| RotationStatus {
+            next: rotation.next.address(),
+            starts_at: format_unix_timestamp(rotation.starts_at as i64),
+            ends_at: format_unix_timestamp(rotation.ends_at as i64),
+            phase: rotation.phase(now),
+        }),
+    })
+}
+
+/// Health of a feed as reported by `/health/feeds`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedState {
+    /// Not fetched yet
+    Unknown,
+    /// The last fetch succeeded
+    Up,
+    /// The last fetch failed
+    Down,
+}
+
+/// Fetch latency over the most recent fetches, in milliseconds.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct LatencyStats {
+    pub samples: usize,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl LatencyStats {
+    fn new(latencies_ms: &VecDeque<f64>) -> Option<Self> {
+        let mut sorted: Vec<f64> = latencies_ms.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        Some(Self {
+            samples: sorted.len(),
+            p50: percentile(&sorted, 50.0)?,
+            p90: percentile(&sorted, 90.0)?,
+            p99: percentile(&sorted, 99.0)?,
+            max: *sorted.last()?,
+        })
+    }
+}
+
+/// One feed's health, and the pairs priced from it.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct FeedHealth {
+    pub feed_id: String,
+    /// Pairs priced from this feed, as `BASE/QUOTE`
+    pub pairs: Vec<String>,
+    pub state: FeedState,
+    /// When the last successful fetch completed (ISO 8601)
+    pub last_success: Option<String>,
+    pub consecutive_errors: u64,
+    /// Start of the current run of consecutive errors (ISO 8601), if the feed is failing
+    pub failing_since: Option<String>,
+    pub last_error: Option<String>,
+    /// Unset until the feed has been fetched
+    pub latency_ms: Option<LatencyStats>,
+}
+
+/// Response for `GET /health/feeds`.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct FeedHealthResponse {
+    pub feeds: Vec<FeedHealth>,
+}
+
+impl FeedStatus {
+    pub fn state(&self) -> FeedState {
+        if self.consecutive_errors > 0 {
+            FeedState::Down
+        } else if self.last_success.is_some() {
+            FeedState::Up
+        } else {
+            FeedState::Unknown
+        }
+    }
+}
+
+impl FeedHealth {
+    fn new(feed: FeedStatus, pairs: Vec<String>) -> Self {
+        let state = feed.state();
+        Self {
+            latency_ms: LatencyStats::new(&feed.recent_latencies_ms),
+            feed_id: feed.feed_id,
+            pairs,
+            state,
+            last_success: feed.last_success,
+            consecutive_errors: feed.consecutive_errors,
+            failing_since: feed.failing_since,
+            last_error: feed.last_error,
+        }
+    }
+}
+
+/// GET handler — per-feed health, so monitoring can alert on a single dead feed.
+#[utoipa::path(
+    get,
+    path = "/health/feeds",
+    responses((status = 200, description = "Health of every configured feed", body = FeedHealthResponse)),
+)]
+pub async fn feed_health(State(state): State<Arc<AppState>>) -> Json<FeedHealthResponse> {
+    let pair = &state.token_pair;
+    let feeds = state
+        .feed_stats
+        .snapshot()
+        .into_iter()
+        .map(|feed| {
+            let pairs = if feed.feed_id == state.pyth_price_feed_id {
+                vec![format!("{}/{}", pair.base_token, pair.quote_token)]
+            } else {
+                Vec::new()
+            };
+            FeedHealth::new(feed, pairs)
+        })
+        .collect();
+    Json(FeedHealthResponse { feeds })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price() -> PriceData {
+        PriceData {
+            price: 310012345678,
+            expo: -8,
+            publish_time: 1700000000,
+        }
+    }
+
+    #[test]
+    fn test_unfetched_feed_is_listed() {
+        let stats = FeedStats::new(["ff61"]);
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot[0].feed_id, "ff61");
+        assert!(snapshot[0].last_success.is_none());
+    }
+
+    #[test]
+    fn test_record_success_and_errors() {
+        let stats = FeedStats::new(["ff61"]);
+        stats.record_error("ff61", 1700000001, &anyhow::anyhow!("hermes down"));
+        stats.record_error("ff61", 1700000002, &anyhow::anyhow!("hermes down"));
+
+        let feed = &stats.snapshot()[0];
+        assert_eq!(feed.error_count, 2);
+        assert_eq!(feed.consecutive_errors, 2);
+        assert_eq!(feed.last_error.as_deref(), Some("hermes down"));
+        assert_eq!(feed.failing_since_unix, Some(1700000001));
+
+        stats.record_success("ff61", 1700000003, &price());
+        let feed = &stats.snapshot()[0];
+        assert_eq!(feed.success_count, 1);
+        assert_eq!(feed.error_count, 2);
+        assert_eq!(feed.consecutive_errors, 0);
+        assert!(feed.failing_since.is_none());
+        assert_eq!(feed.last_price.as_deref(), Some("3100.12345678"));
+        assert_eq!(feed.last_success.as_deref(), Some("2023-11-14T22:13:23Z"));
+        assert_eq!(
+            feed.last_publish_time.as_deref(),
+            Some("2023-11-14T22:13:20Z")
+        );
+    }
+
+    #[test]
+    fn test_feed_health() {
+        let stats = FeedStats::new(["ff61"]);
+        let health = || FeedHealth::new(stats.snapshot().remove(0), Vec::new());
+        assert_eq!(health().state, FeedState::Unknown);
+        assert!(health().latency_ms.is_none());
+
+        for ms in 1..=200 {
+            stats.record_latency("ff61", Duration::from_millis(ms));
+        }
+        stats.record_success("ff61", 1700000001, &price());
+        let up = health();
+        assert_eq!(up.state, FeedState::Up);
+        let latency = up.latency_ms.unwrap();
+        // Only the most recent 100 fetches are kept
+        assert_eq!(latency.samples, 100);
+        assert_eq!((latency.p50, latency.max), (150.0, 200.0));
+
+        stats.record_error("ff61", 1700000002, &anyhow::anyhow!("hermes down"));
+        let down = health();
+        assert_eq!(down.state, FeedState::Down);
+        assert_eq!(down.consecutive_errors, 1);
+    }
+}
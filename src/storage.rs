@@ -0,0 +1,1650 @@
+use alloy::primitives::{keccak256, Address, Bytes, FixedBytes, B256};
+use async_trait::async_trait;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::explain::Explanation;
+use crate::PriceDirection;
+
+/// One signed quote as recorded for audit and dedup.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct AuditRecord {
+    /// ID returned to the client in the response body and `X-Quote-Id` header
+    #[schema(value_type = String, format = "uuid")]
+    pub quote_id: Uuid,
+    /// keccak256 of the signature, used to detect identical quotes issued twice
+    #[schema(value_type = String)]
+    pub signature_hash: B256,
+    pub issued_at: i64,
+    #[schema(value_type = String)]
+    pub signer: Address,
+    #[schema(value_type = String)]
+    pub counterparty: Address,
+    /// API key name the quote was issued to, if API keys are enabled
+    pub api_key: Option<String>,
+    /// `X-Taker-Id` the request identified itself with, if any
+    #[serde(default)]
+    pub taker_id: Option<String>,
+    /// Hash of the order the quote was signed for (none for quotes pushed to webhooks)
+    #[serde(default)]
+    #[schema(value_type = Option<String>)]
+    pub order_hash: Option<B256>,
+    /// Output vault the quote can be taken against, as `owner:token:vault_id` (none for quotes
+    /// not signed for an order)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_vault: Option<String>,
+    #[schema(value_type = String)]
+    pub base_token: Address,
+    #[schema(value_type = String)]
+    pub quote_token: Address,
+    pub direction: PriceDirection,
+    #[schema(value_type = Vec<String>)]
+    pub context: Vec<FixedBytes<32>>,
+    #[schema(value_type = String)]
+    pub signature: Bytes,
+    /// How the price was arrived at (none for records from before explanations were kept)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<Explanation>,
+}
+
+impl AuditRecord {
+    /// The dedup hash for a signature.
+    pub fn signature_hash(signature: &[u8]) -> B256 {
+        keccak256(signature)
+    }
+}
+
+/// The expression an owner's order nonce was last quoted with, for the nonce freshness policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NonceUse {
+    pub bytecode_hash: B256,
+    /// Unix seconds
+    pub seen_at: i64,
+}
+
+/// A feed price the leader fetched from Hermes, published for the other replicas to quote from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SharedPrice {
+    pub price: i128,
+    pub expo: i32,
+    /// Unix seconds Pyth published the price at
+    pub publish_time: i64,
+    /// Unix seconds the leader fetched it at
+    pub fetched_at: i64,
+}
+
+/// A guard tripping (circuit breaker, pricing guard, unusable feed price), recorded
+/// with a snapshot of what the server saw at the time for later forensics.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct Incident {
+    #[schema(value_type = String, format = "uuid")]
+    pub incident_id: Uuid,
+    pub occurred_at: i64,
+    /// Error code of the guard that tripped, e.g. `circuit_open`
+    pub guard: String,
+    /// Why it tripped
+    pub detail: String,
+    /// Recent prices, recent quotes and the pair configuration in effect
+    #[schema(value_type = Object)]
+    pub snapshot: serde_json::Value,
+}
+
+/// Which audit records to return from [`Storage::quotes`].
+#[derive(Debug, Clone, Default)]
+pub struct QuoteFilter {
+    /// Inclusive lower bound on `issued_at` (unix seconds)
+    pub since: Option<i64>,
+    /// Exclusive upper bound on `issued_at` (unix seconds)
+    pub until: Option<i64>,
+    /// `(base_token, quote_token)`, matching quotes in either direction
+    pub pair: Option<(Address, Address)>,
+}
+
+impl QuoteFilter {
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        self.since.is_none_or(|since| record.issued_at >= since)
+            && self.until.is_none_or(|until| record.issued_at < until)
+            && self
+                .pair
+                .is_none_or(|pair| pair == (record.base_token, record.quote_token))
+    }
+}
+
+pub(crate) fn direction_str(direction: PriceDirection) -> &'static str {
+    match direction {
+        PriceDirection::AsIs => "as_is",
+        PriceDirection::Inverted => "inverted",
+    }
+}
+
+fn parse_direction(s: &str) -> anyhow::Result<PriceDirection> {
+    match s {
+        "as_is" => Ok(PriceDirection::AsIs),
+        "inverted" => Ok(PriceDirection::Inverted),
+        other => anyhow::bail!("Unknown price direction '{}'", other),
+    }
+}
+
+/// State that must be shared between replicas: the audit trail of issued quotes
+/// (which doubles as the dedup index) and incidents, per-API-key usage counters, orders seen removed from
+/// the orderbook, the orders each order nonce was last seen with, the leases replicas elect a leader
+/// with, the feed prices the leader publishes and how long upstreams asked to be left alone.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Record an issued quote. Returns `false` if a quote with the same signature had
+    /// already been recorded (the new record is still stored under its own ID).
+    async fn record_quote(&self, record: &AuditRecord) -> anyhow::Result<bool>;
+
+    /// Look up a recorded quote by ID.
+    async fn quote(&self, quote_id: Uuid) -> anyhow::Result<Option<AuditRecord>>;
+
+    /// The first recorded quote with this signature hash, e.g. to match an on-chain fill.
+    async fn quote_by_signature(&self, signature_hash: B256)
+        -> anyhow::Result<Option<AuditRecord>>;
+
+    /// Increment and return the count for `key` on UTC day number `day`.
+    async fn increment_usage(&self, key: &str, day: u64) -> anyhow::Result<u64>;
+
+    /// Counts for `key` from UTC day number `since_day` onwards.
+    async fn usage(&self, key: &str, since_day: u64) -> anyhow::Result<BTreeMap<u64, u64>>;
+
+    /// Clear the count for `key` on UTC day number `day`.
+    async fn reset_usage(&self, key: &str, day: u64) -> anyhow::Result<()>;
+
+    /// Undo one [`Storage::increment_usage`] of the count for `key` on UTC day number `day`,
+    /// e.g. a reservation that went unused. Never takes the count below zero.
+    async fn release_usage(&self, key: &str, day: u64) -> anyhow::Result<()>;
+
+    /// Recorded quotes matching `filter`, oldest first.
+    async fn quotes(&self, filter: &QuoteFilter) -> anyhow::Result<Vec<AuditRecord>>;
+
+    /// Take or renew the lease `name` for `holder` until unix time `now + ttl_secs`. Returns
+    /// whether `holder` now holds it: it was free, expired or already `holder`'s.
+    async fn acquire_lease(
+        &self,
+        name: &str,
+        holder: &str,
+        now: u64,
+        ttl_secs: u64,
+    ) -> anyhow::Result<bool>;
+
+    /// Mark an order removed from the orderbook, or re-added if `removed` is false.
+    async fn set_order_removed(&self, order_hash: B256, removed: bool) -> anyhow::Result<()>;
+
+    /// Orders currently marked removed.
+    async fn removed_orders(&self) -> anyhow::Result<Vec<B256>>;
+
+    /// Record a tripped guard's snapshot.
+    async fn record_incident(&self, incident: &Incident) -> anyhow::Result<()>;
+
+    /// The `limit` most recent incidents, newest first.
+    async fn incidents(&self, limit: usize) -> anyhow::Result<Vec<Incident>>;
+
+    /// The last use of `owner`'s order `nonce`, if any.
+    async fn order_nonce(&self, owner: Address, nonce: B256) -> anyhow::Result<Option<NonceUse>>;
+
+    /// Record `used` as the last use of `owner`'s order `nonce`, replacing any earlier use.
+    async fn record_order_nonce(
+        &self,
+        owner: Address,
+        nonce: B256,
+        used: &NonceUse,
+    ) -> anyhow::Result<()>;
+
+    /// The last price published for `feed_id`, if any.
+    async fn shared_price(&self, feed_id: &str) -> anyhow::Result<Option<SharedPrice>>;
+
+    /// Publish `price` as the latest for `feed_id`, replacing the previous one.
+    async fn set_shared_price(&self, feed_id: &str, price: &SharedPrice) -> anyhow::Result<()>;
+
+    /// Unix seconds until which `upstream` asked not to be called, if it rate-limited a replica.
+    async fn upstream_backoff(&self, upstream: &str) -> anyhow::Result<Option<i64>>;
+
+    /// Record that `upstream` asked not to be called until unix time `until`, replacing any
+    /// earlier backoff.
+    async fn set_upstream_backoff(&self, upstream: &str, until: i64) -> anyhow::Result<()>;
+}
+
+/// Connect to the storage backend named by `url`: `redis://` / `rediss://` for Redis,
+/// anything else (`sqlite://`, `postgres://`) for SQL.
+pub async fn connect(url: &str) -> anyhow::Result<Arc<dyn Storage>> {
+    if url.starts_with("redis://") || url.starts_with("rediss://") {
+        Ok(Arc::new(RedisStorage::connect(url).await?))
+    } else {
+        Ok(Arc::new(SqlStorage::connect(url).await?))
+    }
+}
+
+/// How long [`MemoryStorage`] keeps issued quotes and order nonce uses, measured back from
+/// the newest one recorded.
+const MEMORY_RETENTION: Duration = Duration::from_secs(86_400);
+
+/// Quotes [`MemoryStorage`] keeps; the oldest are dropped first.
+const MEMORY_MAX_QUOTES: usize = 100_000;
+
+/// Order nonce uses [`MemoryStorage`] keeps; the oldest are dropped first.
+const MEMORY_MAX_ORDER_NONCES: usize = 100_000;
+
+/// Incidents [`MemoryStorage`] keeps; older ones are dropped as new ones are recorded.
+const MEMORY_MAX_INCIDENTS: usize = 1000;
+
+/// Issued quotes and their dedup index, in the order they were recorded.
+#[derive(Default)]
+struct MemoryQuotes {
+    records: HashMap<Uuid, AuditRecord>,
+    signatures: HashMap<B256, Uuid>,
+    order: VecDeque<Uuid>,
+}
+
+/// Last use of each order nonce, with every recorded use in order so the oldest can be
+/// dropped. A use later replaced by a newer one is skipped when it reaches the front.
+#[derive(Default)]
+struct MemoryOrderNonces {
+    uses: HashMap<(Address, B256), NonceUse>,
+    order: VecDeque<((Address, B256), i64)>,
+}
+
+/// Process-local storage, used when no database is configured. Quotes and order nonce uses
+/// are dropped after [`MEMORY_RETENTION`] or once there are too many, and only the latest
+/// [`MEMORY_MAX_INCIDENTS`] incidents are kept, so a long-running server's memory stays bounded.
+#[derive(Default)]
+pub struct MemoryStorage {
+    quotes: Mutex<MemoryQuotes>,
+    usage: Mutex<HashMap<String, BTreeMap<u64, u64>>>,
+    /// Holder and expiry of each lease
+    leases: Mutex<HashMap<String, (String, u64)>>,
+    removed_orders: Mutex<HashSet<B256>>,
+    incidents: Mutex<VecDeque<Incident>>,
+    order_nonces: Mutex<MemoryOrderNonces>,
+    shared_prices: Mutex<HashMap<String, SharedPrice>>,
+    upstream_backoffs: Mutex<HashMap<String, i64>>,
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn record_quote(&self, record: &AuditRecord) -> anyhow::Result<bool> {
+        let mut quotes = self.quotes.lock().unwrap();
+        let cutoff = record.issued_at - MEMORY_RETENTION.as_secs() as i64;
+        while let Some(&oldest) = quotes.order.front() {
+            let expired = quotes.records[&oldest].issued_at < cutoff;
+            if !expired && quotes.order.len() < MEMORY_MAX_QUOTES {
+                break;
+            }
+            quotes.order.pop_front();
+            let dropped = quotes.records.remove(&oldest).unwrap();
+            if quotes.signatures.get(&dropped.signature_hash) == Some(&oldest) {
+                quotes.signatures.remove(&dropped.signature_hash);
+            }
+        }
+        if quotes
+            .records
+            .insert(record.quote_id, record.clone())
+            .is_none()
+        {
+            quotes.order.push_back(record.quote_id);
+        }
+        if quotes.signatures.contains_key(&record.signature_hash) {
+            return Ok(false);
+        }
+        quotes
+            .signatures
+            .insert(record.signature_hash, record.quote_id);
+        Ok(true)
+    }
+
+    async fn quote(&self, quote_id: Uuid) -> anyhow::Result<Option<AuditRecord>> {
+        Ok(self.quotes.lock().unwrap().records.get(&quote_id).cloned())
+    }
+
+    async fn quote_by_signature(
+        &self,
+        signature_hash: B256,
+    ) -> anyhow::Result<Option<AuditRecord>> {
+        let quotes = self.quotes.lock().unwrap();
+        Ok(quotes
+            .signatures
+            .get(&signature_hash)
+            .and_then(|id| quotes.records.get(id))
+            .cloned())
+    }
+
+    async fn increment_usage(&self, key: &str, day: u64) -> anyhow::Result<u64> {
+        let mut usage = self.usage.lock().unwrap();
+        let count = usage
+            .entry(key.to_string())
+            .or_default()
+            .entry(day)
+            .or_default();
+        *count += 1;
+        Ok(*count)
+    }
+
+    async fn usage(&self, key: &str, since_day: u64) -> anyhow::Result<BTreeMap<u64, u64>> {
+        Ok(self
+            .usage
+            .lock()
+            .unwrap()
+            .get(key)
+            .map(|days| days.range(since_day..).map(|(d, c)| (*d, *c)).collect())
+            .unwrap_or_default())
+    }
+
+    async fn reset_usage(&self, key: &str, day: u64) -> anyhow::Result<()> {
+        if let Some(days) = self.usage.lock().unwrap().get_mut(key) {
+            days.remove(&day);
+        }
+        Ok(())
+    }
+
+    async fn release_usage(&self, key: &str, day: u64) -> anyhow::Result<()> {
+        let mut usage = self.usage.lock().unwrap();
+        if let Some(count) = usage.get_mut(key).and_then(|days| days.get_mut(&day)) {
+            *count = count.saturating_sub(1);
+        }
+        Ok(())
+    }
+
+    async fn quotes(&self, filter: &QuoteFilter) -> anyhow::Result<Vec<AuditRecord>> {
+        let mut records: Vec<_> = self
+            .quotes
+            .lock()
+            .unwrap()
+            .records
+            .values()
+            .filter(|r| filter.matches(r))
+            .cloned()
+            .collect();
+        records.sort_by_key(|r| r.issued_at);
+        Ok(records)
+    }
+
+    async fn acquire_lease(
+        &self,
+        name: &str,
+        holder: &str,
+        now: u64,
+        ttl_secs: u64,
+    ) -> anyhow::Result<bool> {
+        let mut leases = self.leases.lock().unwrap();
+        let lease = leases
+            .entry(name.to_string())
+            .or_insert_with(|| (holder.to_string(), 0));
+        if lease.0 != holder && lease.1 > now {
+            return Ok(false);
+        }
+        *lease = (holder.to_string(), now + ttl_secs);
+        Ok(true)
+    }
+
+    async fn set_order_removed(&self, order_hash: B256, removed: bool) -> anyhow::Result<()> {
+        let mut orders = self.removed_orders.lock().unwrap();
+        if removed {
+            orders.insert(order_hash);
+        } else {
+            orders.remove(&order_hash);
+        }
+        Ok(())
+    }
+
+    async fn removed_orders(&self) -> anyhow::Result<Vec<B256>> {
+        Ok(self
+            .removed_orders
+            .lock()
+            .unwrap()
+            .iter()
+            .copied()
+            .collect())
+    }
+
+    async fn record_incident(&self, incident: &Incident) -> anyhow::Result<()> {
+        let mut incidents = self.incidents.lock().unwrap();
+        if incidents.len() == MEMORY_MAX_INCIDENTS {
+            incidents.pop_front();
+        }
+        incidents.push_back(incident.clone());
+        Ok(())
+    }
+
+    async fn incidents(&self, limit: usize) -> anyhow::Result<Vec<Incident>> {
+        let mut incidents: Vec<_> = self.incidents.lock().unwrap().iter().cloned().collect();
+        incidents.sort_by_key(|i| std::cmp::Reverse(i.occurred_at));
+        incidents.truncate(limit);
+        Ok(incidents)
+    }
+
+    async fn order_nonce(&self, owner: Address, nonce: B256) -> anyhow::Result<Option<NonceUse>> {
+        Ok(self
+            .order_nonces
+            .lock()
+            .unwrap()
+            .uses
+            .get(&(owner, nonce))
+            .copied())
+    }
+
+    async fn record_order_nonce(
+        &self,
+        owner: Address,
+        nonce: B256,
+        used: &NonceUse,
+    ) -> anyhow::Result<()> {
+        let mut nonces = self.order_nonces.lock().unwrap();
+        let cutoff = used.seen_at - MEMORY_RETENTION.as_secs() as i64;
+        while let Some(&(key, seen_at)) = nonces.order.front() {
+            if seen_at >= cutoff && nonces.order.len() < MEMORY_MAX_ORDER_NONCES {
+                break;
+            }
+            nonces.order.pop_front();
+            if nonces.uses.get(&key).is_some_and(|u| u.seen_at == seen_at) {
+                nonces.uses.remove(&key);
+            }
+        }
+        nonces.uses.insert((owner, nonce), *used);
+        nonces.order.push_back(((owner, nonce), used.seen_at));
+        Ok(())
+    }
+
+    async fn shared_price(&self, feed_id: &str) -> anyhow::Result<Option<SharedPrice>> {
+        Ok(self.shared_prices.lock().unwrap().get(feed_id).copied())
+    }
+
+    async fn set_shared_price(&self, feed_id: &str, price: &SharedPrice) -> anyhow::Result<()> {
+        self.shared_prices
+            .lock()
+            .unwrap()
+            .insert(feed_id.to_string(), *price);
+        Ok(())
+    }
+
+    async fn upstream_backoff(&self, upstream: &str) -> anyhow::Result<Option<i64>> {
+        Ok(self
+            .upstream_backoffs
+            .lock()
+            .unwrap()
+            .get(upstream)
+            .copied())
+    }
+
+    async fn set_upstream_backoff(&self, upstream: &str, until: i64) -> anyhow::Result<()> {
+        self.upstream_backoffs
+            .lock()
+            .unwrap()
+            .insert(upstream.to_string(), until);
+        Ok(())
+    }
+}
+
+/// SQLite or Postgres storage, selected by the connection URL scheme
+/// (`sqlite://...` or `postgres://...`).
+pub struct SqlStorage {
+    pool: sqlx::AnyPool,
+}
+
+/// Schema shared by both backends (only portable types and syntax).
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS quotes (
+        quote_id TEXT PRIMARY KEY,
+        signature_hash TEXT NOT NULL,
+        issued_at BIGINT NOT NULL,
+        signer TEXT NOT NULL,
+        counterparty TEXT NOT NULL,
+        api_key TEXT,
+        base_token TEXT NOT NULL,
+        quote_token TEXT NOT NULL,
+        direction TEXT NOT NULL,
+        context TEXT NOT NULL,
+        signature TEXT NOT NULL,
+        taker_id TEXT,
+        order_hash TEXT,
+        explanation TEXT,
+        output_vault TEXT
+    )",
+    "CREATE INDEX IF NOT EXISTS quotes_issued_at ON quotes (issued_at)",
+    "CREATE INDEX IF NOT EXISTS quotes_signature_hash ON quotes (signature_hash)",
+    "CREATE TABLE IF NOT EXISTS quote_signatures (
+        signature_hash TEXT PRIMARY KEY,
+        quote_id TEXT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS api_key_usage (
+        api_key TEXT NOT NULL,
+        day BIGINT NOT NULL,
+        count BIGINT NOT NULL,
+        PRIMARY KEY (api_key, day)
+    )",
+    "CREATE TABLE IF NOT EXISTS leases (
+        name TEXT PRIMARY KEY,
+        holder TEXT NOT NULL,
+        expires_at BIGINT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS removed_orders (order_hash TEXT PRIMARY KEY)",
+    "CREATE TABLE IF NOT EXISTS incidents (
+        incident_id TEXT PRIMARY KEY,
+        occurred_at BIGINT NOT NULL,
+        guard TEXT NOT NULL,
+        detail TEXT NOT NULL,
+        snapshot TEXT NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS incidents_occurred_at ON incidents (occurred_at)",
+    "CREATE TABLE IF NOT EXISTS order_nonces (
+        owner TEXT NOT NULL,
+        nonce TEXT NOT NULL,
+        bytecode_hash TEXT NOT NULL,
+        seen_at BIGINT NOT NULL,
+        PRIMARY KEY (owner, nonce)
+    )",
+    "CREATE TABLE IF NOT EXISTS shared_prices (
+        feed_id TEXT PRIMARY KEY,
+        price TEXT NOT NULL,
+        expo BIGINT NOT NULL,
+        publish_time BIGINT NOT NULL,
+        fetched_at BIGINT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS upstream_backoffs (
+        upstream TEXT PRIMARY KEY,
+        until_at BIGINT NOT NULL
+    )",
+];
+
+/// Nullable `quotes` columns added after the table was first created.
+const ADDED_QUOTE_COLUMNS: &[&str] = &["taker_id", "order_hash", "explanation", "output_vault"];
+
+impl SqlStorage {
+    /// Connect and create the schema if it does not exist yet.
+    pub async fn connect(url: &str) -> anyhow::Result<Self> {
+        sqlx::any::install_default_drivers();
+        let pool = sqlx::any::AnyPoolOptions::new()
+            .max_connections(8)
+            .connect(url)
+            .await?;
+        let had_signatures = sqlx::query("SELECT 1 FROM quote_signatures LIMIT 0")
+            .execute(&pool)
+            .await
+            .is_ok();
+        for statement in MIGRATIONS {
+            sqlx::query(statement).execute(&pool).await?;
+        }
+        // Databases created by older versions lack the columns added since.
+        for column in ADDED_QUOTE_COLUMNS {
+            if sqlx::query(&format!("SELECT {} FROM quotes LIMIT 0", column))
+                .execute(&pool)
+                .await
+                .is_err()
+            {
+                sqlx::query(&format!("ALTER TABLE quotes ADD COLUMN {} TEXT", column))
+                    .execute(&pool)
+                    .await?;
+            }
+        }
+        // Older versions deduplicated on `quotes` alone: claim their signatures for the
+        // first quote recorded with each
+        if !had_signatures {
+            sqlx::query(
+                "INSERT INTO quote_signatures (signature_hash, quote_id)
+                 SELECT signature_hash, MIN(quote_id) FROM quotes AS q
+                 WHERE issued_at = (SELECT MIN(issued_at) FROM quotes
+                                    WHERE signature_hash = q.signature_hash)
+                 GROUP BY signature_hash",
+            )
+            .execute(&pool)
+            .await?;
+        }
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait]
+impl Storage for SqlStorage {
+    async fn record_quote(&self, record: &AuditRecord) -> anyhow::Result<bool> {
+        let context = serde_json::to_string(&record.context)?;
+        let explanation = record
+            .explanation
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
+        let mut tx = self.pool.begin().await?;
+        // The primary key decides which of concurrent quotes with a signature is first
+        let claimed = sqlx::query(
+            "INSERT INTO quote_signatures (signature_hash, quote_id) VALUES ($1, $2)
+             ON CONFLICT (signature_hash) DO NOTHING",
+        )
+        .bind(record.signature_hash.to_string())
+        .bind(record.quote_id.to_string())
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            == 1;
+        sqlx::query(
+            "INSERT INTO quotes
+                (quote_id, signature_hash, issued_at, signer, counterparty, api_key,
+                 base_token, quote_token, direction, context, signature, taker_id,
+                 order_hash, explanation, output_vault)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)",
+        )
+        .bind(record.quote_id.to_string())
+        .bind(record.signature_hash.to_string())
+        .bind(record.issued_at)
+        .bind(record.signer.to_string())
+        .bind(record.counterparty.to_string())
+        .bind(record.api_key.clone())
+        .bind(record.base_token.to_string())
+        .bind(record.quote_token.to_string())
+        .bind(direction_str(record.direction))
+        .bind(context)
+        .bind(record.signature.to_string())
+        .bind(record.taker_id.clone())
+        .bind(record.order_hash.map(|hash| hash.to_string()))
+        .bind(explanation)
+        .bind(record.output_vault.clone())
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(claimed)
+    }
+
+    async fn quote(&self, quote_id: Uuid) -> anyhow::Result<Option<AuditRecord>> {
+        let row: Option<SqlQuoteRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM quotes WHERE quote_id = $1",
+            QUOTE_COLUMNS
+        ))
+        .bind(quote_id.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(parse_quote_row).transpose()
+    }
+
+    async fn quote_by_signature(
+        &self,
+        signature_hash: B256,
+    ) -> anyhow::Result<Option<AuditRecord>> {
+        let row: Option<SqlQuoteRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM quotes WHERE signature_hash = $1 ORDER BY issued_at LIMIT 1",
+            QUOTE_COLUMNS
+        ))
+        .bind(signature_hash.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(parse_quote_row).transpose()
+    }
+
+    async fn increment_usage(&self, key: &str, day: u64) -> anyhow::Result<u64> {
+        let count: i64 = sqlx::query_scalar(
+            "INSERT INTO api_key_usage (api_key, day, count) VALUES ($1, $2, 1)
+             ON CONFLICT (api_key, day) DO UPDATE SET count = api_key_usage.count + 1
+             RETURNING count",
+        )
+        .bind(key)
+        .bind(day as i64)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count as u64)
+    }
+
+    async fn usage(&self, key: &str, since_day: u64) -> anyhow::Result<BTreeMap<u64, u64>> {
+        let rows: Vec<(i64, i64)> =
+            sqlx::query_as("SELECT day, count FROM api_key_usage WHERE api_key = $1 AND day >= $2")
+                .bind(key)
+                .bind(since_day as i64)
+                .fetch_all(&self.pool)
+                .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(day, count)| (day as u64, count as u64))
+            .collect())
+    }
+
+    async fn reset_usage(&self, key: &str, day: u64) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM api_key_usage WHERE api_key = $1 AND day = $2")
+            .bind(key)
+            .bind(day as i64)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn release_usage(&self, key: &str, day: u64) -> anyhow::Result<()> {
+        sqlx::query(
+            "UPDATE api_key_usage SET count = count - 1
+             WHERE api_key = $1 AND day = $2 AND count > 0",
+        )
+        .bind(key)
+        .bind(day as i64)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn quotes(&self, filter: &QuoteFilter) -> anyhow::Result<Vec<AuditRecord>> {
+        let mut sql = format!(
+            "SELECT {} FROM quotes WHERE issued_at >= $1 AND issued_at < $2",
+            QUOTE_COLUMNS
+        );
+        if filter.pair.is_some() {
+            sql.push_str(" AND base_token = $3 AND quote_token = $4");
+        }
+        sql.push_str(" ORDER BY issued_at");
+
+        let mut query = sqlx::query_as::<_, SqlQuoteRow>(&sql)
+            .bind(filter.since.unwrap_or(i64::MIN))
+            .bind(filter.until.unwrap_or(i64::MAX));
+        if let Some((base, quote)) = filter.pair {
+            query = query.bind(base.to_string()).bind(quote.to_string());
+        }
+        query
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(parse_quote_row)
+            .collect()
+    }
+
+    async fn acquire_lease(
+        &self,
+        name: &str,
+        holder: &str,
+        now: u64,
+        ttl_secs: u64,
+    ) -> anyhow::Result<bool> {
+        // Only overwrites the lease if it is ours or has expired
+        sqlx::query(
+            "INSERT INTO leases (name, holder, expires_at) VALUES ($1, $2, $3)
+             ON CONFLICT (name) DO UPDATE SET holder = excluded.holder,
+                 expires_at = excluded.expires_at
+             WHERE leases.holder = excluded.holder OR leases.expires_at <= $4",
+        )
+        .bind(name)
+        .bind(holder)
+        .bind((now + ttl_secs) as i64)
+        .bind(now as i64)
+        .execute(&self.pool)
+        .await?;
+        let current: String = sqlx::query_scalar("SELECT holder FROM leases WHERE name = $1")
+            .bind(name)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(current == holder)
+    }
+
+    async fn set_order_removed(&self, order_hash: B256, removed: bool) -> anyhow::Result<()> {
+        let sql = if removed {
+            "INSERT INTO removed_orders (order_hash) VALUES ($1) ON CONFLICT DO NOTHING"
+        } else {
+            "DELETE FROM removed_orders WHERE order_hash = $1"
+        };
+        sqlx::query(sql)
+            .bind(order_hash.to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn removed_orders(&self) -> anyhow::Result<Vec<B256>> {
+        let rows: Vec<String> = sqlx::query_scalar("SELECT order_hash FROM removed_orders")
+            .fetch_all(&self.pool)
+            .await?;
+        rows.iter().map(|hash| Ok(B256::from_str(hash)?)).collect()
+    }
+
+    async fn record_incident(&self, incident: &Incident) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO incidents (incident_id, occurred_at, guard, detail, snapshot)
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(incident.incident_id.to_string())
+        .bind(incident.occurred_at)
+        .bind(&incident.guard)
+        .bind(&incident.detail)
+        .bind(serde_json::to_string(&incident.snapshot)?)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn incidents(&self, limit: usize) -> anyhow::Result<Vec<Incident>> {
+        let rows: Vec<(String, i64, String, String, String)> = sqlx::query_as(
+            "SELECT incident_id, occurred_at, guard, detail, snapshot FROM incidents
+             ORDER BY occurred_at DESC LIMIT $1",
+        )
+        .bind(i64::try_from(limit).unwrap_or(i64::MAX))
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter()
+            .map(|(incident_id, occurred_at, guard, detail, snapshot)| {
+                Ok(Incident {
+                    incident_id: Uuid::from_str(&incident_id)?,
+                    occurred_at,
+                    guard,
+                    detail,
+                    snapshot: serde_json::from_str(&snapshot)?,
+                })
+            })
+            .collect()
+    }
+
+    async fn order_nonce(&self, owner: Address, nonce: B256) -> anyhow::Result<Option<NonceUse>> {
+        let row: Option<(String, i64)> = sqlx::query_as(
+            "SELECT bytecode_hash, seen_at FROM order_nonces WHERE owner = $1 AND nonce = $2",
+        )
+        .bind(owner.to_string())
+        .bind(nonce.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(|(bytecode_hash, seen_at)| {
+            Ok(NonceUse {
+                bytecode_hash: B256::from_str(&bytecode_hash)?,
+                seen_at,
+            })
+        })
+        .transpose()
+    }
+
+    async fn record_order_nonce(
+        &self,
+        owner: Address,
+        nonce: B256,
+        used: &NonceUse,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO order_nonces (owner, nonce, bytecode_hash, seen_at) VALUES ($1, $2, $3, $4)
+             ON CONFLICT (owner, nonce) DO UPDATE SET
+                 bytecode_hash = excluded.bytecode_hash, seen_at = excluded.seen_at",
+        )
+        .bind(owner.to_string())
+        .bind(nonce.to_string())
+        .bind(used.bytecode_hash.to_string())
+        .bind(used.seen_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn shared_price(&self, feed_id: &str) -> anyhow::Result<Option<SharedPrice>> {
+        let row: Option<(String, i64, i64, i64)> = sqlx::query_as(
+            "SELECT price, expo, publish_time, fetched_at FROM shared_prices WHERE feed_id = $1",
+        )
+        .bind(feed_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(|(price, expo, publish_time, fetched_at)| {
+            Ok(SharedPrice {
+                price: price.parse()?,
+                expo: expo.try_into()?,
+                publish_time,
+                fetched_at,
+            })
+        })
+        .transpose()
+    }
+
+    async fn set_shared_price(&self, feed_id: &str, price: &SharedPrice) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO shared_prices (feed_id, price, expo, publish_time, fetched_at)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (feed_id) DO UPDATE SET price = excluded.price, expo = excluded.expo,
+                 publish_time = excluded.publish_time, fetched_at = excluded.fetched_at",
+        )
+        .bind(feed_id)
+        .bind(price.price.to_string())
+        .bind(i64::from(price.expo))
+        .bind(price.publish_time)
+        .bind(price.fetched_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn upstream_backoff(&self, upstream: &str) -> anyhow::Result<Option<i64>> {
+        Ok(
+            sqlx::query_scalar("SELECT until_at FROM upstream_backoffs WHERE upstream = $1")
+                .bind(upstream)
+                .fetch_optional(&self.pool)
+                .await?,
+        )
+    }
+
+    async fn set_upstream_backoff(&self, upstream: &str, until: i64) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO upstream_backoffs (upstream, until_at) VALUES ($1, $2)
+             ON CONFLICT (upstream) DO UPDATE SET until_at = excluded.until_at",
+        )
+        .bind(upstream)
+        .bind(until)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+const QUOTE_COLUMNS: &str = "quote_id, signature_hash, issued_at, signer, counterparty, \
+    api_key, base_token, quote_token, direction, context, signature, taker_id, order_hash, \
+    explanation, output_vault";
+
+type SqlQuoteRow = (
+    String,
+    String,
+    i64,
+    String,
+    String,
+    Option<String>,
+    String,
+    String,
+    String,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+);
+
+fn parse_quote_row(row: SqlQuoteRow) -> anyhow::Result<AuditRecord> {
+    let (
+        quote_id,
+        signature_hash,
+        issued_at,
+        signer,
+        counterparty,
+        api_key,
+        base_token,
+        quote_token,
+        direction,
+        context,
+        signature,
+        taker_id,
+        order_hash,
+        explanation,
+        output_vault,
+    ) = row;
+    Ok(AuditRecord {
+        quote_id: Uuid::from_str(&quote_id)?,
+        signature_hash: B256::from_str(&signature_hash)?,
+        issued_at,
+        signer: Address::from_str(&signer)?,
+        counterparty: Address::from_str(&counterparty)?,
+        api_key,
+        taker_id,
+        order_hash: order_hash.map(|hash| B256::from_str(&hash)).transpose()?,
+        output_vault,
+        base_token: Address::from_str(&base_token)?,
+        quote_token: Address::from_str(&quote_token)?,
+        direction: parse_direction(&direction)?,
+        context: serde_json::from_str(&context)?,
+        signature: Bytes::from_str(&signature)?,
+        explanation: explanation
+            .map(|explanation| serde_json::from_str(&explanation))
+            .transpose()?,
+    })
+}
+
+/// How long Redis keeps issued quotes and usage counters.
+const REDIS_RETENTION: Duration = Duration::from_secs(31 * 86_400);
+
+/// Redis storage, for replicas that need shared dedup and quota state without a SQL
+/// database. Records expire after [`REDIS_RETENTION`], so this is not a long-term audit log.
+pub struct RedisStorage {
+    conn: redis::aio::ConnectionManager,
+}
+
+impl RedisStorage {
+    pub async fn connect(url: &str) -> anyhow::Result<Self> {
+        let conn = redis::Client::open(url)?.get_connection_manager().await?;
+        Ok(Self { conn })
+    }
+
+    fn quote_key(quote_id: &Uuid) -> String {
+        format!("oracle:quote:{}", quote_id)
+    }
+
+    fn signature_key(signature_hash: &B256) -> String {
+        format!("oracle:signature:{}", signature_hash)
+    }
+
+    fn usage_key(key: &str) -> String {
+        format!("oracle:usage:{}", key)
+    }
+
+    fn lease_key(name: &str) -> String {
+        format!("oracle:lease:{}", name)
+    }
+
+    fn order_nonce_key(owner: &Address, nonce: &B256) -> String {
+        format!("oracle:order_nonce:{}:{}", owner, nonce)
+    }
+
+    fn shared_price_key(feed_id: &str) -> String {
+        format!("oracle:price:{}", feed_id)
+    }
+
+    fn upstream_backoff_key(upstream: &str) -> String {
+        format!("oracle:backoff:{}", upstream)
+    }
+}
+
+/// How long Redis keeps a published price; replicas stop using it long before.
+const REDIS_PRICE_TTL: Duration = Duration::from_secs(300);
+
+/// How long Redis keeps an upstream backoff; far longer than any `Retry-After` worth honouring.
+const REDIS_BACKOFF_TTL: Duration = Duration::from_secs(3600);
+
+/// Renew the lease in `KEYS[1]` for holder `ARGV[1]` by `ARGV[2]` seconds if it is theirs, or
+/// take it if it is free, in one step so it can't lapse and go to another replica in between.
+const REDIS_ACQUIRE_LEASE: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("EXPIRE", KEYS[1], ARGV[2])
+end
+if redis.call("SET", KEYS[1], ARGV[1], "NX", "EX", ARGV[2]) then
+    return 1
+end
+return 0
+"#;
+
+/// Decrement day `ARGV[1]` of the usage hash in `KEYS[1]`, unless it is already zero or gone
+/// (e.g. reset since it was incremented).
+const REDIS_RELEASE_USAGE: &str = r#"
+local count = tonumber(redis.call("HGET", KEYS[1], ARGV[1]) or "0")
+if count > 0 then
+    return redis.call("HINCRBY", KEYS[1], ARGV[1], -1)
+end
+return 0
+"#;
+
+/// Set of orders seen removed from the orderbook.
+const REDIS_REMOVED_ORDERS_KEY: &str = "oracle:removed_orders";
+
+/// List of incidents, newest first.
+const REDIS_INCIDENTS_KEY: &str = "oracle:incidents";
+
+/// Incidents Redis keeps; older ones are dropped as new ones are recorded.
+const REDIS_MAX_INCIDENTS: isize = 1000;
+
+#[async_trait]
+impl Storage for RedisStorage {
+    async fn record_quote(&self, record: &AuditRecord) -> anyhow::Result<bool> {
+        let value = serde_json::to_string(record)?;
+        let (first_seen,): (Option<String>,) = redis::pipe()
+            .set_ex(
+                Self::quote_key(&record.quote_id),
+                value,
+                REDIS_RETENTION.as_secs(),
+            )
+            .ignore()
+            .cmd("SET")
+            .arg(Self::signature_key(&record.signature_hash))
+            .arg(record.quote_id.to_string())
+            .arg("NX")
+            .arg("EX")
+            .arg(REDIS_RETENTION.as_secs())
+            .query_async(&mut self.conn.clone())
+            .await?;
+        Ok(first_seen.is_some())
+    }
+
+    async fn quote(&self, quote_id: Uuid) -> anyhow::Result<Option<AuditRecord>> {
+        let value: Option<String> = self.conn.clone().get(Self::quote_key(&quote_id)).await?;
+        Ok(value.map(|v| serde_json::from_str(&v)).transpose()?)
+    }
+
+    async fn quote_by_signature(
+        &self,
+        signature_hash: B256,
+    ) -> anyhow::Result<Option<AuditRecord>> {
+        let quote_id: Option<String> = self
+            .conn
+            .clone()
+            .get(Self::signature_key(&signature_hash))
+            .await?;
+        match quote_id {
+            Some(id) => self.quote(Uuid::from_str(&id)?).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn increment_usage(&self, key: &str, day: u64) -> anyhow::Result<u64> {
+        let usage_key = Self::usage_key(key);
+        let (count,): (u64,) = redis::pipe()
+            .atomic()
+            .hincr(&usage_key, day, 1)
+            .expire(&usage_key, REDIS_RETENTION.as_secs() as i64)
+            .ignore()
+            .query_async(&mut self.conn.clone())
+            .await?;
+        Ok(count)
+    }
+
+    async fn usage(&self, key: &str, since_day: u64) -> anyhow::Result<BTreeMap<u64, u64>> {
+        let days: HashMap<u64, u64> = self.conn.clone().hgetall(Self::usage_key(key)).await?;
+        Ok(days.into_iter().filter(|(d, _)| *d >= since_day).collect())
+    }
+
+    async fn reset_usage(&self, key: &str, day: u64) -> anyhow::Result<()> {
+        let _: () = self.conn.clone().hdel(Self::usage_key(key), day).await?;
+        Ok(())
+    }
+
+    async fn release_usage(&self, key: &str, day: u64) -> anyhow::Result<()> {
+        let _: i64 = redis::Script::new(REDIS_RELEASE_USAGE)
+            .key(Self::usage_key(key))
+            .arg(day)
+            .invoke_async(&mut self.conn.clone())
+            .await?;
+        Ok(())
+    }
+
+    /// Scans every retained quote; intended for occasional exports, not request paths.
+    async fn quotes(&self, filter: &QuoteFilter) -> anyhow::Result<Vec<AuditRecord>> {
+        let mut conn = self.conn.clone();
+        let keys: Vec<String> = {
+            let mut iter: redis::AsyncIter<String> = conn.scan_match("oracle:quote:*").await?;
+            let mut keys = Vec::new();
+            while let Some(key) = iter.next_item().await {
+                keys.push(key);
+            }
+            keys
+        };
+
+        let mut records = Vec::new();
+        for chunk in keys.chunks(500) {
+            let values: Vec<Option<String>> = conn.mget(chunk).await?;
+            for value in values.into_iter().flatten() {
+                let record: AuditRecord = serde_json::from_str(&value)?;
+                if filter.matches(&record) {
+                    records.push(record);
+                }
+            }
+        }
+        records.sort_by_key(|r| r.issued_at);
+        Ok(records)
+    }
+
+    /// Relies on Redis expiring the key rather than `now`.
+    async fn acquire_lease(
+        &self,
+        name: &str,
+        holder: &str,
+        _now: u64,
+        ttl_secs: u64,
+    ) -> anyhow::Result<bool> {
+        let held: i64 = redis::Script::new(REDIS_ACQUIRE_LEASE)
+            .key(Self::lease_key(name))
+            .arg(holder)
+            .arg(ttl_secs)
+            .invoke_async(&mut self.conn.clone())
+            .await?;
+        Ok(held == 1)
+    }
+
+    async fn set_order_removed(&self, order_hash: B256, removed: bool) -> anyhow::Result<()> {
+        let mut conn = self.conn.clone();
+        let member = order_hash.to_string();
+        let _: () = if removed {
+            conn.sadd(REDIS_REMOVED_ORDERS_KEY, member).await?
+        } else {
+            conn.srem(REDIS_REMOVED_ORDERS_KEY, member).await?
+        };
+        Ok(())
+    }
+
+    async fn removed_orders(&self) -> anyhow::Result<Vec<B256>> {
+        let members: Vec<String> = self.conn.clone().smembers(REDIS_REMOVED_ORDERS_KEY).await?;
+        members
+            .iter()
+            .map(|hash| Ok(B256::from_str(hash)?))
+            .collect()
+    }
+
+    async fn record_incident(&self, incident: &Incident) -> anyhow::Result<()> {
+        let value = serde_json::to_string(incident)?;
+        let _: () = redis::pipe()
+            .lpush(REDIS_INCIDENTS_KEY, value)
+            .ignore()
+            .ltrim(REDIS_INCIDENTS_KEY, 0, REDIS_MAX_INCIDENTS - 1)
+            .ignore()
+            .query_async(&mut self.conn.clone())
+            .await?;
+        Ok(())
+    }
+
+    async fn incidents(&self, limit: usize) -> anyhow::Result<Vec<Incident>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let stop = isize::try_from(limit).unwrap_or(isize::MAX) - 1;
+        let values: Vec<String> = self
+            .conn
+            .clone()
+            .lrange(REDIS_INCIDENTS_KEY, 0, stop)
+            .await?;
+        values
+            .iter()
+            .map(|value| Ok(serde_json::from_str(value)?))
+            .collect()
+    }
+
+    async fn order_nonce(&self, owner: Address, nonce: B256) -> anyhow::Result<Option<NonceUse>> {
+        let value: Option<String> = self
+            .conn
+            .clone()
+            .get(Self::order_nonce_key(&owner, &nonce))
+            .await?;
+        value
+            .map(|value| Ok(serde_json::from_str(&value)?))
+            .transpose()
+    }
+
+    async fn record_order_nonce(
+        &self,
+        owner: Address,
+        nonce: B256,
+        used: &NonceUse,
+    ) -> anyhow::Result<()> {
+        let _: () = self
+            .conn
+            .clone()
+            .set_ex(
+                Self::order_nonce_key(&owner, &nonce),
+                serde_json::to_string(used)?,
+                REDIS_RETENTION.as_secs(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn shared_price(&self, feed_id: &str) -> anyhow::Result<Option<SharedPrice>> {
+        let value: Option<String> = self
+            .conn
+            .clone()
+            .get(Self::shared_price_key(feed_id))
+            .await?;
+        value
+            .map(|value| Ok(serde_json::from_str(&value)?))
+            .transpose()
+    }
+
+    async fn set_shared_price(&self, feed_id: &str, price: &SharedPrice) -> anyhow::Result<()> {
+        let _: () = self
+            .conn
+            .clone()
+            .set_ex(
+                Self::shared_price_key(feed_id),
+                serde_json::to_string(price)?,
+                REDIS_PRICE_TTL.as_secs(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn upstream_backoff(&self, upstream: &str) -> anyhow::Result<Option<i64>> {
+        Ok(self
+            .conn
+            .clone()
+            .get(Self::upstream_backoff_key(upstream))
+            .await?)
+    }
+
+    async fn set_upstream_backoff(&self, upstream: &str, until: i64) -> anyhow::Result<()> {
+        let _: () = self
+            .conn
+            .clone()
+            .set_ex(
+                Self::upstream_backoff_key(upstream),
+                until,
+                REDIS_BACKOFF_TTL.as_secs(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: Address = Address::repeat_byte(4);
+    const QUOTE: Address = Address::repeat_byte(5);
+
+    fn record(signature: &[u8], issued_at: i64, base_token: Address) -> AuditRecord {
+        AuditRecord {
+            quote_id: Uuid::new_v4(),
+            signature_hash: AuditRecord::signature_hash(signature),
+            issued_at,
+            signer: Address::repeat_byte(1),
+            counterparty: Address::repeat_byte(2),
+            api_key: Some("mm".to_string()),
+            taker_id: Some("desk-1".to_string()),
+            order_hash: Some(B256::repeat_byte(9)),
+            output_vault: Some("0x11:0x22:0x07".to_string()),
+            base_token,
+            quote_token: QUOTE,
+            direction: PriceDirection::Inverted,
+            context: vec![FixedBytes::repeat_byte(3)],
+            signature: Bytes::copy_from_slice(signature),
+            explanation: Some(Explanation {
+                sources: vec![crate::explain::SourcePrice {
+                    feed_id: "0xff61".to_string(),
+                    price: "3100.12".to_string(),
+                    publish_time: issued_at,
+                }],
+                aggregate: "3100.12".to_string(),
+                direction: PriceDirection::Inverted,
+                steps: vec![crate::explain::PricingStep {
+                    step: "invert?".to_string(),
+                    price: "0.000322568".to_string(),
+                }],
+                guards: vec!["warm_up".to_string(), "positive_price".to_string()],
+            }),
+        }
+    }
+
+    async fn exercise(storage: &dyn Storage) {
+        let a = record(b"sig-a", 1700000000, BASE);
+        let b = record(b"sig-b", 1700000100, BASE);
+        let c = record(b"sig-c", 1700000050, Address::repeat_byte(6));
+        let a_again = AuditRecord {
+            quote_id: Uuid::new_v4(),
+            issued_at: 1700000200,
+            ..a.clone()
+        };
+        assert!(storage.record_quote(&a).await.unwrap());
+        assert!(storage.record_quote(&b).await.unwrap());
+        assert!(storage.record_quote(&c).await.unwrap());
+        assert!(!storage.record_quote(&a_again).await.unwrap());
+
+        assert_eq!(storage.quote(b.quote_id).await.unwrap(), Some(b.clone()));
+        assert_eq!(
+            storage.quote(a_again.quote_id).await.unwrap(),
+            Some(a_again.clone())
+        );
+        assert_eq!(storage.quote(Uuid::new_v4()).await.unwrap(), None);
+        assert_eq!(
+            storage.quote_by_signature(a.signature_hash).await.unwrap(),
+            Some(a.clone())
+        );
+        assert_eq!(
+            storage
+                .quote_by_signature(AuditRecord::signature_hash(b"sig-z"))
+                .await
+                .unwrap(),
+            None
+        );
+
+        let all = storage.quotes(&QuoteFilter::default()).await.unwrap();
+        assert_eq!(all, vec![a.clone(), c.clone(), b.clone(), a_again]);
+        let filter = QuoteFilter {
+            since: Some(1700000000),
+            until: Some(1700000100),
+            pair: Some((BASE, QUOTE)),
+        };
+        assert_eq!(storage.quotes(&filter).await.unwrap(), vec![a]);
+
+        assert_eq!(storage.increment_usage("mm", 10).await.unwrap(), 1);
+        assert_eq!(storage.increment_usage("mm", 10).await.unwrap(), 2);
+        assert_eq!(storage.increment_usage("mm", 11).await.unwrap(), 1);
+        assert_eq!(storage.increment_usage("other", 11).await.unwrap(), 1);
+
+        let usage = storage.usage("mm", 0).await.unwrap();
+        assert_eq!(usage, BTreeMap::from([(10, 2), (11, 1)]));
+        let usage = storage.usage("mm", 11).await.unwrap();
+        assert_eq!(usage, BTreeMap::from([(11, 1)]));
+        assert!(storage.usage("nobody", 0).await.unwrap().is_empty());
+        storage.release_usage("mm", 10).await.unwrap();
+        let usage = storage.usage("mm", 10).await.unwrap();
+        assert_eq!(usage, BTreeMap::from([(10, 1), (11, 1)]));
+        storage.reset_usage("mm", 10).await.unwrap();
+        storage.release_usage("mm", 10).await.unwrap();
+        let usage = storage.usage("mm", 0).await.unwrap();
+        assert_eq!(usage, BTreeMap::from([(11, 1)]));
+
+        assert!(storage
+            .acquire_lease("pollers", "a", 100, 15)
+            .await
+            .unwrap());
+        assert!(!storage
+            .acquire_lease("pollers", "b", 110, 15)
+            .await
+            .unwrap());
+        assert!(storage
+            .acquire_lease("pollers", "a", 110, 15)
+            .await
+            .unwrap());
+        assert!(storage.acquire_lease("other", "b", 110, 15).await.unwrap());
+        // a's renewal at 110 runs until 125
+        assert!(!storage
+            .acquire_lease("pollers", "b", 124, 15)
+            .await
+            .unwrap());
+        assert!(storage
+            .acquire_lease("pollers", "b", 125, 15)
+            .await
+            .unwrap());
+        assert!(!storage
+            .acquire_lease("pollers", "a", 126, 15)
+            .await
+            .unwrap());
+
+        let order = B256::repeat_byte(7);
+        storage.set_order_removed(order, true).await.unwrap();
+        storage.set_order_removed(order, true).await.unwrap();
+        assert_eq!(storage.removed_orders().await.unwrap(), vec![order]);
+        storage.set_order_removed(order, false).await.unwrap();
+        assert!(storage.removed_orders().await.unwrap().is_empty());
+
+        let (owner, nonce) = (Address::repeat_byte(1), B256::repeat_byte(8));
+        assert_eq!(storage.order_nonce(owner, nonce).await.unwrap(), None);
+        let first = NonceUse {
+            bytecode_hash: B256::repeat_byte(2),
+            seen_at: 1700000000,
+        };
+        storage
+            .record_order_nonce(owner, nonce, &first)
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.order_nonce(owner, nonce).await.unwrap(),
+            Some(first)
+        );
+        let second = NonceUse {
+            seen_at: 1700000060,
+            ..first
+        };
+        storage
+            .record_order_nonce(owner, nonce, &second)
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.order_nonce(owner, nonce).await.unwrap(),
+            Some(second)
+        );
+        // Other owners' nonces are separate
+        assert_eq!(
+            storage
+                .order_nonce(Address::repeat_byte(3), nonce)
+                .await
+                .unwrap(),
+            None
+        );
+
+        assert_eq!(storage.shared_price("ff61").await.unwrap(), None);
+        let price = SharedPrice {
+            price: i128::from(i64::MAX) * 10,
+            expo: -8,
+            publish_time: 1700000000,
+            fetched_at: 1700000001,
+        };
+        storage.set_shared_price("ff61", &price).await.unwrap();
+        storage.set_shared_price("ff61", &price).await.unwrap();
+        assert_eq!(storage.shared_price("ff61").await.unwrap(), Some(price));
+
+        assert_eq!(storage.upstream_backoff("hermes").await.unwrap(), None);
+        storage
+            .set_upstream_backoff("hermes", 1700000030)
+            .await
+            .unwrap();
+        storage
+            .set_upstream_backoff("hermes", 1700000060)
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.upstream_backoff("hermes").await.unwrap(),
+            Some(1700000060)
+        );
+
+        let incident = |occurred_at, guard: &str| Incident {
+            incident_id: Uuid::new_v4(),
+            occurred_at,
+            guard: guard.to_string(),
+            detail: "Price feed is failing".to_string(),
+            snapshot: serde_json::json!({ "prices": [{ "source": "pyth", "price": "3100.12" }] }),
+        };
+        let first = incident(1700000000, "circuit_open");
+        let second = incident(1700000300, "reference_deviation");
+        storage.record_incident(&first).await.unwrap();
+        storage.record_incident(&second).await.unwrap();
+        assert_eq!(
+            storage.incidents(10).await.unwrap(),
+            vec![second.clone(), first]
+        );
+        assert_eq!(storage.incidents(1).await.unwrap(), vec![second]);
+        assert!(storage.incidents(0).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_memory_storage() {
+        exercise(&MemoryStorage::default()).await;
+    }
+
+    #[tokio::test]
+    async fn test_memory_storage_is_bounded() {
+        let storage = MemoryStorage::default();
+        let day = MEMORY_RETENTION.as_secs() as i64;
+        let old = record(b"old", 1700000000, BASE);
+        assert!(storage.record_quote(&old).await.unwrap());
+        let new = record(b"new", 1700000000 + day + 1, BASE);
+        storage.record_quote(&new).await.unwrap();
+        assert_eq!(storage.quote(old.quote_id).await.unwrap(), None);
+        assert_eq!(
+            storage
+                .quote_by_signature(old.signature_hash)
+                .await
+                .unwrap(),
+            None
+        );
+        // Dropped quotes no longer count as duplicates
+        assert!(storage
+            .record_quote(&record(b"old", 1700000000 + day + 2, BASE))
+            .await
+            .unwrap());
+
+        let (owner, nonce) = (Address::repeat_byte(1), B256::repeat_byte(8));
+        let used = NonceUse {
+            bytecode_hash: B256::repeat_byte(2),
+            seen_at: 1700000000,
+        };
+        storage
+            .record_order_nonce(owner, nonce, &used)
+            .await
+            .unwrap();
+        let later = NonceUse {
+            seen_at: 1700000000 + day + 1,
+            ..used
+        };
+        storage
+            .record_order_nonce(owner, B256::repeat_byte(9), &later)
+            .await
+            .unwrap();
+        assert_eq!(storage.order_nonce(owner, nonce).await.unwrap(), None);
+
+        for occurred_at in 0..=MEMORY_MAX_INCIDENTS as i64 {
+            let incident = Incident {
+                incident_id: Uuid::new_v4(),
+                occurred_at,
+                guard: "circuit_open".to_string(),
+                detail: "Price feed is failing".to_string(),
+                snapshot: serde_json::Value::Null,
+            };
+            storage.record_incident(&incident).await.unwrap();
+        }
+        let incidents = storage.incidents(usize::MAX).await.unwrap();
+        assert_eq!(incidents.len(), MEMORY_MAX_INCIDENTS);
+        assert_eq!(incidents.last().unwrap().occurred_at, 1);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_storage() {
+        let path = std::env::temp_dir().join(format!("oracle-test-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let storage = SqlStorage::connect(&format!("sqlite://{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+        exercise(&storage).await;
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_concurrent_duplicates_are_flagged() {
+        let path = std::env::temp_dir().join(format!("oracle-dup-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let storage = Arc::new(
+            SqlStorage::connect(&format!("sqlite://{}?mode=rwc", path.display()))
+                .await
+                .unwrap(),
+        );
+        let tasks: Vec<_> = (0..8)
+            .map(|i| {
+                let storage = storage.clone();
+                let record = AuditRecord {
+                    quote_id: Uuid::new_v4(),
+                    ..record(b"sig-a", 1700000000 + i, BASE)
+                };
+                tokio::spawn(async move { storage.record_quote(&record).await.unwrap() })
+            })
+            .collect();
+        let mut first = 0;
+        for task in tasks {
+            first += usize::from(task.await.unwrap());
+        }
+        assert_eq!(first, 1);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_adds_missing_columns() {
+        let path = std::env::temp_dir().join(format!("oracle-old-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let url = format!("sqlite://{}?mode=rwc", path.display());
+        {
+            sqlx::any::install_default_drivers();
+            let pool = sqlx::AnyPool::connect(&url).await.unwrap();
+            sqlx::query(
+                "CREATE TABLE quotes (quote_id TEXT PRIMARY KEY, signature_hash TEXT NOT NULL,
+                 issued_at BIGINT NOT NULL, signer TEXT NOT NULL, counterparty TEXT NOT NULL,
+                 api_key TEXT, base_token TEXT NOT NULL, quote_token TEXT NOT NULL,
+                 direction TEXT NOT NULL, context TEXT NOT NULL, signature TEXT NOT NULL)",
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO quotes VALUES ('old', $1, 1600000000, '', '', NULL, '', '', '', '', '')",
+            )
+            .bind(AuditRecord::signature_hash(b"sig-old").to_string())
+            .execute(&pool)
+            .await
+            .unwrap();
+            pool.close().await;
+        }
+        let storage = SqlStorage::connect(&url).await.unwrap();
+        let a = record(b"sig-a", 1700000000, BASE);
+        assert!(storage.record_quote(&a).await.unwrap());
+        assert_eq!(storage.quote(a.quote_id).await.unwrap(), Some(a));
+        // Signatures recorded before the dedup table existed still count as seen
+        assert!(!storage
+            .record_quote(&record(b"sig-old", 1700000000, BASE))
+            .await
+            .unwrap());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,273 @@
+use alloy::primitives::Address;
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{Query, State};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::StreamExt;
+
+use crate::{limits, AppError, AppState, OracleRequestError, PriceDirection, PricedContext};
+
+/// Events buffered for each subscriber; one that falls further behind skips to the latest.
+const CHANNEL_CAPACITY: usize = 16;
+
+/// Recorded as the taker ID of streamed quotes in the audit log.
+const STREAM_TAKER_ID: &str = "stream";
+
+/// One payload for every subscriber of a direction: a signed quote, or why there isn't one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamEvent {
+    /// SSE event name: `quote` or `error`
+    pub kind: &'static str,
+    /// JSON, serialized once and shared by every subscriber
+    pub data: Arc<str>,
+}
+
+/// The last event sent on a channel, replayed to new subscribers.
+#[derive(Debug, Clone)]
+struct Latest {
+    event: StreamEvent,
+    /// Publish time of the price a quote was signed from (None for errors)
+    publish_time: Option<i64>,
+    /// Unix seconds after which the quote is re-signed even if the price hasn't moved
+    refresh_at: u64,
+}
+
+#[derive(Debug)]
+struct Channel {
+    sender: broadcast::Sender<StreamEvent>,
+    latest: Mutex<Option<Latest>>,
+}
+
+impl Channel {
+    fn new() -> Self {
+        Self {
+            sender: broadcast::channel(CHANNEL_CAPACITY).0,
+            latest: Mutex::new(None),
+        }
+    }
+}
+
+/// Pushes signed quotes to Server-Sent Events subscribers. Each direction with subscribers
+/// is signed once per new feed price (or once half its expiry has passed) and the same
+/// payload goes to every subscriber, so signing cost doesn't grow with the subscriber count.
+#[derive(Debug)]
+pub struct QuoteStream {
+    interval: Duration,
+    as_is: Channel,
+    inverted: Channel,
+}
+
+impl QuoteStream {
+    /// Check for a new price every `interval`.
+    pub fn new(interval: Duration) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            !interval.is_zero(),
+            "Quote stream interval must be positive"
+        );
+        Ok(Self {
+            interval,
+            as_is: Channel::new(),
+            inverted: Channel::new(),
+        })
+    }
+
+    fn channel(&self, direction: PriceDirection) -> &Channel {
+        match direction {
+            PriceDirection::AsIs => &self.as_is,
+            PriceDirection::Inverted => &self.inverted,
+        }
+    }
+
+    pub fn subscribers(&self, direction: PriceDirection) -> usize {
+        self.channel(direction).sender.receiver_count()
+    }
+
+    /// Receive every event for `direction` from now on, starting with the latest one.
+    pub fn subscribe(
+        &self,
+        direction: PriceDirection,
+    ) -> (Option<StreamEvent>, broadcast::Receiver<StreamEvent>) {
+        let channel = self.channel(direction);
+        // Subscribe first, so nothing sent in between is missed (at worst it arrives twice)
+        let receiver = channel.sender.subscribe();
+        let latest = channel
+            .latest
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|latest| latest.event.clone());
+        (latest, receiver)
+    }
+
+    /// Sign a fresh quote for `direction` and send it to its subscribers if the price has
+    /// moved or the last quote is half expired. Does nothing without subscribers.
+    pub async fn refresh(&self, state: &AppState, direction: PriceDirection) {
+        let channel = self.channel(direction);
+        if channel.sender.receiver_count() == 0 {
+            return;
+        }
+        let now = state.clock.now().as_secs();
+        let latest = channel.latest.lock().unwrap().clone();
+        let next = match price(state, direction).await {
+            Ok(priced) => {
+                let publish_time = priced.publish_time;
+                let current = latest
+                    .as_ref()
+                    .filter(|l| l.publish_time == Some(publish_time) && now < l.refresh_at);
+                if current.is_some() {
+                    return;
+                }
+                match sign(state, priced, direction).await {
+                    Ok(event) => Latest {
+                        event,
+                        publish_time: Some(publish_time),
+                        refresh_at: now + (state.expiry_seconds / 2).max(1),
+                    },
+                    Err(err) => error_latest(&err),
+                }
+            }
+            Err(err) => error_latest(&err),
+        };
+        // Report an error once, not on every attempt
+        if next.publish_time.is_none() && latest.is_some_and(|l| l.event == next.event) {
+            return;
+        }
+        // No receivers left is fine: the next subscriber gets it as the latest event
+        let _ = channel.sender.send(next.event.clone());
+        *channel.latest.lock().unwrap() = Some(next);
+    }
+
+    /// Render subscriber counts in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP oracle_stream_subscribers Connected quote stream subscribers.\n");
+        out.push_str("# TYPE oracle_stream_subscribers gauge\n");
+        for (direction, name) in [
+            (PriceDirection::AsIs, "as_is"),
+            (PriceDirection::Inverted, "inverted"),
+        ] {
+            let _ = writeln!(
+                out,
+                "oracle_stream_subscribers{{direction=\"{}\"}} {}",
+                name,
+                self.subscribers(direction)
+            );
+        }
+        out
+    }
+}
+
+/// Price a quote for streaming, behind the same guards as `/context`.
+async fn price(state: &AppState, direction: PriceDirection) -> Result<PricedContext, AppError> {
+    crate::check_ready(state)?;
+    crate::check_clock_skew(state)?;
+    crate::check_schedule(state)?;
+    limits::check(state).await?;
+    // Streamed quotes can be taken like any other, so they're scaled the same way
+    let decimal_shift = state.pair_decimal_shift(direction).await?;
+    crate::price_context(state, direction, decimal_shift, state.expiry_seconds).await
+}
+
+async fn sign(
+    state: &AppState,
+    priced: PricedContext,
+    direction: PriceDirection,
+) -> Result<StreamEvent, AppError> {
+    let response = crate::sign_priced_context(
+        state,
+        priced,
+        direction,
+        Address::ZERO,
+        None,
+        Some(STREAM_TAKER_ID.to_string()),
+        None,
+    )
+    .await?;
+    Ok(StreamEvent {
+        kind: "quote",
+        data: serde_json::to_string(&response)
+            .map_err(anyhow::Error::from)?
+            .into(),
+    })
+}
+
+/// An `error` event carrying just the error code, since stream errors aren't tied to a
+/// request whose logs could be looked up.
+fn error_latest(err: &AppError) -> Latest {
+    if let AppError::Internal(e) = err {
+        tracing::error!("Failed to sign streamed quote: {:#}", e);
+    }
+    Latest {
+        event: StreamEvent {
+            kind: "error",
+            data: serde_json::json!({ "error": err.error_code() })
+                .to_string()
+                .into(),
+        },
+        publish_time: None,
+        refresh_at: 0,
+    }
+}
+
+/// Refresh every direction with subscribers each interval. Does nothing without a stream.
+pub fn spawn(state: Arc<AppState>) -> Option<JoinHandle<()>> {
+    let interval = state.quote_stream.as_ref()?.interval;
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            if let Some(stream) = &state.quote_stream {
+                tokio::join!(
+                    stream.refresh(&state, PriceDirection::AsIs),
+                    stream.refresh(&state, PriceDirection::Inverted),
+                );
+            }
+        }
+    }))
+}
+
+/// Query for `GET /stream`.
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct StreamQuery {
+    /// Direction to quote (default `as_is`)
+    direction: Option<PriceDirection>,
+}
+
+/// GET handler — subscribe to signed quotes for the configured pair as Server-Sent Events.
+#[utoipa::path(
+    get,
+    path = "/stream",
+    params(StreamQuery),
+    responses(
+        (status = 200, description = "`quote` events carrying an OracleResponse as each new price is signed, and `error` events carrying an error code when signing is refused", content_type = "text/event-stream"),
+        (status = 400, description = "Invalid direction", body = crate::ErrorResponse),
+        (status = 404, description = "Quote streaming is not enabled", body = crate::ErrorResponse),
+    ),
+)]
+pub async fn subscribe(
+    State(state): State<Arc<AppState>>,
+    query: Result<Query<StreamQuery>, QueryRejection>,
+) -> Result<Response, AppError> {
+    let Query(query) = query.map_err(|e| OracleRequestError::InvalidQuery(e.body_text()))?;
+    let stream = state
+        .quote_stream
+        .as_ref()
+        .ok_or_else(|| AppError::NotFound("Quote streaming is not enabled".to_string()))?;
+    let (latest, receiver) = stream.subscribe(query.direction.unwrap_or(PriceDirection::AsIs));
+    let events = tokio_stream::iter(latest)
+        // A lagging subscriber skips what it missed and carries on from the newest event
+        .chain(BroadcastStream::new(receiver).filter_map(Result::ok))
+        .map(|event| Ok::<_, Infallible>(Event::default().event(event.kind).data(&*event.data)));
+    Ok(Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response())
+}
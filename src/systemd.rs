@@ -0,0 +1,155 @@
+use std::net::TcpListener;
+
+/// First file descriptor systemd passes to socket-activated services (`SD_LISTEN_FDS_START`).
+#[cfg(unix)]
+const LISTEN_FDS_START: i32 = 3;
+
+/// A listening socket inherited from systemd, with its `FileDescriptorName=` if set.
+#[derive(Debug)]
+pub struct InheritedListener {
+    pub name: Option<String>,
+    pub listener: TcpListener,
+}
+
+/// Parse `LISTEN_PID`, `LISTEN_FDS` and `LISTEN_FDNAMES` into the number of sockets passed
+/// to process `pid` and their names. None if the sockets were meant for another process.
+fn parse_listen_env(
+    listen_pid: Option<&str>,
+    listen_fds: Option<&str>,
+    names: Option<&str>,
+    pid: u32,
+) -> anyhow::Result<Option<Vec<Option<String>>>> {
+    let (Some(listen_pid), Some(listen_fds)) = (listen_pid, listen_fds) else {
+        return Ok(None);
+    };
+    let listen_pid: u32 = listen_pid
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid LISTEN_PID '{}'", listen_pid))?;
+    if listen_pid != pid {
+        return Ok(None);
+    }
+    let count: usize = listen_fds
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid LISTEN_FDS '{}'", listen_fds))?;
+    let mut names: Vec<Option<String>> = names
+        .map(|names| {
+            names
+                .split(':')
+                .map(|name| Some(name.to_string()).filter(|n| !n.is_empty() && n != "unknown"))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.resize(count, None);
+    Ok(Some(names))
+}
+
+/// Take the listening sockets systemd passed to this process, if it was socket activated
+/// (the `LISTEN_FDS` protocol of `sd_listen_fds(3)`). The environment variables are cleared
+/// so child processes don't inherit them.
+#[cfg(unix)]
+pub fn listen_fds() -> anyhow::Result<Vec<InheritedListener>> {
+    use std::os::fd::FromRawFd;
+
+    let var = |name| std::env::var(name).ok();
+    let names = parse_listen_env(
+        var("LISTEN_PID").as_deref(),
+        var("LISTEN_FDS").as_deref(),
+        var("LISTEN_FDNAMES").as_deref(),
+        std::process::id(),
+    )?;
+    for name in ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+        std::env::remove_var(name);
+    }
+    let Some(names) = names else {
+        return Ok(Vec::new());
+    };
+    names
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| {
+            // SAFETY: systemd passes `LISTEN_FDS` open descriptors from 3 to this PID, and
+            // nothing else in the process has taken ownership of them.
+            let listener = unsafe { TcpListener::from_raw_fd(LISTEN_FDS_START + i as i32) };
+            listener.set_nonblocking(true)?;
+            Ok(InheritedListener { name, listener })
+        })
+        .collect()
+}
+
+#[cfg(not(unix))]
+pub fn listen_fds() -> anyhow::Result<Vec<InheritedListener>> {
+    Ok(Vec::new())
+}
+
+/// Send `state` (e.g. `READY=1`) to the service manager, as `sd_notify(3)` does. Returns
+/// false when not running under systemd (no `NOTIFY_SOCKET`).
+#[cfg(unix)]
+pub fn notify(state: &str) -> std::io::Result<bool> {
+    use std::os::unix::net::UnixDatagram;
+
+    let Some(path) = std::env::var_os("NOTIFY_SOCKET") else {
+        return Ok(false);
+    };
+    let socket = UnixDatagram::unbound()?;
+    let path = path.to_string_lossy();
+    match path.strip_prefix('@') {
+        #[cfg(target_os = "linux")]
+        Some(name) => {
+            use std::os::linux::net::SocketAddrExt;
+            let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            socket.send_to_addr(state.as_bytes(), &addr)?;
+        }
+        _ => {
+            socket.send_to(state.as_bytes(), path.as_ref())?;
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(not(unix))]
+pub fn notify(_state: &str) -> std::io::Result<bool> {
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_listen_env() {
+        assert_eq!(parse_listen_env(None, None, None, 42).unwrap(), None);
+        assert_eq!(
+            parse_listen_env(Some("41"), Some("1"), None, 42).unwrap(),
+            None
+        );
+        assert_eq!(
+            parse_listen_env(Some("42"), Some("2"), None, 42).unwrap(),
+            Some(vec![None, None])
+        );
+        assert_eq!(
+            parse_listen_env(Some("42"), Some("3"), Some("public:admin"), 42).unwrap(),
+            Some(vec![
+                Some("public".to_string()),
+                Some("admin".to_string()),
+                None
+            ])
+        );
+        assert!(parse_listen_env(Some("42"), Some("x"), None, 42).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_notify() {
+        let dir = std::env::temp_dir().join(format!("oracle-notify-{}", std::process::id()));
+        let _ = std::fs::remove_file(&dir);
+        let receiver = std::os::unix::net::UnixDatagram::bind(&dir).unwrap();
+        std::env::set_var("NOTIFY_SOCKET", &dir);
+        assert!(notify("READY=1").unwrap());
+        std::env::remove_var("NOTIFY_SOCKET");
+        let mut buf = [0u8; 16];
+        let len = receiver.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"READY=1");
+        assert!(!notify("READY=1").unwrap());
+        let _ = std::fs::remove_file(&dir);
+    }
+}
@@ -0,0 +1,87 @@
+use alloy::primitives::Address;
+use axum::http::HeaderMap;
+
+use crate::auth::Authenticated;
+use crate::OracleRequestError;
+
+/// Optional request header a taker uses to identify itself, for per-taker logs, metrics and
+/// audit records.
+pub const TAKER_ID_HEADER: &str = "x-taker-id";
+
+/// Longest accepted `X-Taker-Id`.
+const MAX_TAKER_ID_LEN: usize = 64;
+
+/// Read the `X-Taker-Id` header, if present. Values are limited to letters, digits and
+/// `.`, `_`, `:`, `-` so they are safe to use as metric labels and in logs.
+pub fn from_headers(headers: &HeaderMap) -> Result<Option<String>, OracleRequestError> {
+    let Some(value) = headers.get(TAKER_ID_HEADER) else {
+        return Ok(None);
+    };
+    let taker = value
+        .to_str()
+        .map_err(|_| OracleRequestError::InvalidTakerId("not ASCII".to_string()))?
+        .trim();
+    let allowed = |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | ':' | '-');
+    if taker.is_empty() || taker.len() > MAX_TAKER_ID_LEN || !taker.chars().all(allowed) {
+        return Err(OracleRequestError::InvalidTakerId(format!(
+            "must be 1 to {} letters, digits or '.', '_', ':', '-'",
+            MAX_TAKER_ID_LEN
+        )));
+    }
+    Ok(Some(taker.to_string()))
+}
+
+/// With authentication enabled, a taker may only identify as the order's counterparty or
+/// as a subject it authenticated as (JWT `sub`, API key name, client certificate).
+pub fn verify(
+    taker: &str,
+    counterparty: Address,
+    identity: Option<&Authenticated>,
+) -> Result<(), OracleRequestError> {
+    let is_counterparty = taker
+        .parse::<Address>()
+        .is_ok_and(|address| address == counterparty);
+    let is_subject = identity.is_some_and(|Authenticated(identities)| {
+        identities
+            .iter()
+            .any(|identity| identity.subject.as_deref() == Some(taker))
+    });
+    if is_counterparty || is_subject {
+        Ok(())
+    } else {
+        Err(OracleRequestError::TakerIdMismatch(taker.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Identity;
+
+    #[test]
+    fn test_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(from_headers(&headers).unwrap(), None);
+        headers.insert(TAKER_ID_HEADER, " desk-1 ".parse().unwrap());
+        assert_eq!(from_headers(&headers).unwrap().as_deref(), Some("desk-1"));
+        headers.insert(TAKER_ID_HEADER, "a b".parse().unwrap());
+        assert!(from_headers(&headers).is_err());
+        headers.insert(TAKER_ID_HEADER, "x".repeat(65).parse().unwrap());
+        assert!(from_headers(&headers).is_err());
+    }
+
+    #[test]
+    fn test_verify() {
+        let counterparty = Address::repeat_byte(7);
+        assert!(verify(&counterparty.to_string().to_lowercase(), counterparty, None).is_ok());
+        assert!(verify(&Address::repeat_byte(8).to_string(), counterparty, None).is_err());
+
+        let identity = Authenticated(vec![Identity {
+            mechanism: "jwt",
+            subject: Some("desk-1".to_string()),
+        }]);
+        assert!(verify("desk-1", counterparty, Some(&identity)).is_ok());
+        let err = verify("desk-2", counterparty, Some(&identity)).unwrap_err();
+        assert!(matches!(err, OracleRequestError::TakerIdMismatch(_)));
+    }
+}
@@ -0,0 +1,104 @@
+use std::fmt::Write;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A step of quoting a request, timed separately so a slow quote shows where the time went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    /// Decoding and validating the request body and resolving the pair
+    Decode,
+    /// Answering from the response cache instead of pricing and signing
+    CacheHit,
+    /// Fetching the feed price from Hermes
+    PriceFetch,
+    /// Building the context from the fetched price (pipeline, rounding, gas, jitter)
+    Pricing,
+    /// Signing the context, including co-signers
+    Signing,
+    /// Recording the quote in storage and against the daily limit
+    Recording,
+    /// Encoding the response body
+    Serialization,
+}
+
+impl Stage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Decode => "decode",
+            Stage::CacheHit => "cache_hit",
+            Stage::PriceFetch => "price_fetch",
+            Stage::Pricing => "pricing",
+            Stage::Signing => "signing",
+            Stage::Recording => "recording",
+            Stage::Serialization => "serialization",
+        }
+    }
+}
+
+/// Stages timed so far in the current request, in the order they finished.
+pub type Timings = Vec<(Stage, Duration)>;
+
+tokio::task_local! {
+    static TIMINGS: Arc<Mutex<Timings>>;
+}
+
+/// Run `f`, collecting the stages timed within it (on this task).
+pub async fn scope<F: Future>(f: F) -> (F::Output, Timings) {
+    let timings = Arc::new(Mutex::new(Vec::new()));
+    let output = TIMINGS.scope(timings.clone(), f).await;
+    let timings = std::mem::take(&mut *timings.lock().unwrap());
+    (output, timings)
+}
+
+/// Record `elapsed` against `stage` for the current request. A no-op outside [`scope`],
+/// e.g. when signing for quote stream subscribers.
+pub fn record(stage: Stage, elapsed: Duration) {
+    let _ = TIMINGS.try_with(|timings| timings.lock().unwrap().push((stage, elapsed)));
+}
+
+/// Await `f`, recording how long it took against `stage`.
+pub async fn time<F: Future>(stage: Stage, f: F) -> F::Output {
+    let started = Instant::now();
+    let output = f.await;
+    record(stage, started.elapsed());
+    output
+}
+
+/// `decode=0.1ms price_fetch=212.4ms ...`, for logs.
+pub fn format(timings: &[(Stage, Duration)]) -> String {
+    let mut out = String::new();
+    for (stage, elapsed) in timings {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        let _ = write!(
+            out,
+            "{}={:.1}ms",
+            stage.as_str(),
+            elapsed.as_secs_f64() * 1000.0
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scope_collects_stages() {
+        record(Stage::Decode, Duration::from_millis(1));
+        let (output, timings) = scope(async {
+            record(Stage::Decode, Duration::from_micros(120));
+            time(Stage::PriceFetch, async { 7 }).await
+        })
+        .await;
+        assert_eq!(output, 7);
+        assert_eq!(
+            timings.iter().map(|(stage, _)| *stage).collect::<Vec<_>>(),
+            [Stage::Decode, Stage::PriceFetch]
+        );
+        assert!(format(&timings).starts_with("decode=0.1ms price_fetch="));
+    }
+}
@@ -0,0 +1,56 @@
+use axum::Json;
+use serde::Serialize;
+
+/// Crate version.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Commit the binary was built from (`-dirty` with uncommitted changes), or `unknown`.
+pub const GIT_COMMIT: &str = env!("GIT_COMMIT");
+
+/// Unix seconds at which the binary was built.
+const BUILD_TIMESTAMP: &str = env!("BUILD_TIMESTAMP");
+
+/// Which build is running.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct VersionResponse {
+    pub version: &'static str,
+    pub git_commit: &'static str,
+    /// RFC 3339
+    pub build_timestamp: String,
+}
+
+impl VersionResponse {
+    pub fn current() -> Self {
+        let build_timestamp = BUILD_TIMESTAMP
+            .parse()
+            .ok()
+            .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+            .map(|time| time.to_rfc3339())
+            .unwrap_or_else(|| "unknown".to_string());
+        Self {
+            version: VERSION,
+            git_commit: GIT_COMMIT,
+            build_timestamp,
+        }
+    }
+}
+
+impl std::fmt::Display for VersionResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "rain-oracle-server {} (commit {}, built {})",
+            self.version, self.git_commit, self.build_timestamp
+        )
+    }
+}
+
+/// GET handler — the version, commit and build time of the running binary.
+#[utoipa::path(
+    get,
+    path = "/version",
+    responses((status = 200, description = "Build metadata", body = VersionResponse)),
+)]
+pub async fn version() -> Json<VersionResponse> {
+    Json(VersionResponse::current())
+}
@@ -0,0 +1,565 @@
+use alloy::primitives::{keccak256, Address, FixedBytes, LogData, B256, U256};
+use alloy::sol_types::{SolEvent, SolValue};
+use rain_math_float::Float;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::order::{
+    AddOrderV2, AddOrderV3, RemoveOrderV2, RemoveOrderV3, SignedContextV1, TakeOrderV2, TakeOrderV3,
+};
+use crate::rpc;
+use crate::storage::AuditRecord;
+use crate::AppState;
+
+/// Most blocks fetched in one `eth_getLogs` call, so a long outage doesn't produce a request
+/// the RPC refuses.
+const MAX_BLOCK_RANGE: u64 = 1000;
+
+/// Upper bounds (basis points) of the absolute slippage histogram buckets.
+const SLIPPAGE_BUCKETS: [f64; 9] = [1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0];
+
+/// First topics of the orderbook events the watcher handles.
+const EVENT_TOPICS: [B256; 6] = [
+    TakeOrderV3::SIGNATURE_HASH,
+    TakeOrderV2::SIGNATURE_HASH,
+    AddOrderV3::SIGNATURE_HASH,
+    AddOrderV2::SIGNATURE_HASH,
+    RemoveOrderV3::SIGNATURE_HASH,
+    RemoveOrderV2::SIGNATURE_HASH,
+];
+
+/// Filled quotes are remembered for this long (by issue time) to count each only once.
+const FILLED_RETENTION_SECONDS: i64 = 86_400;
+
+/// Which orderbook to watch and how.
+#[derive(Debug, Clone)]
+pub struct WatcherConfig {
+    pub rpc_url: String,
+    pub orderbook: Address,
+    pub poll_interval: Duration,
+}
+
+/// A `TakeOrder` event, normalised across orderbook versions.
+#[derive(Clone)]
+pub struct Fill {
+    pub order_hash: B256,
+    pub signed_context: Vec<SignedContextV1>,
+    /// Amount the taker received (the order's output)
+    pub input: f64,
+    /// Amount the taker paid (the order's input)
+    pub output: f64,
+}
+
+fn float_to_f64(value: B256) -> anyhow::Result<f64> {
+    Ok(Float::from(value)
+        .format()
+        .map_err(|e| anyhow::anyhow!("Invalid Rain float {}: {:?}", value, e))?
+        .parse()?)
+}
+
+fn amount_to_f64(value: U256) -> anyhow::Result<f64> {
+    Ok(value.to_string().parse()?)
+}
+
+/// An orderbook event the watcher acts on.
+#[derive(Clone)]
+pub enum OrderbookEvent {
+    Fill(Fill),
+    /// An order (re-)added, by hash
+    Added(B256),
+    /// An order removed, by hash
+    Removed(B256),
+}
+
+/// Decode an OrderBookV5 (`...V3` events) or OrderBookV4 (`...V2` events) log, or `None` for
+/// any other event.
+pub fn decode_event(log: &LogData) -> anyhow::Result<Option<OrderbookEvent>> {
+    let event = match log.topics().first() {
+        Some(&TakeOrderV3::SIGNATURE_HASH) => {
+            let event = TakeOrderV3::decode_log_data(log)?;
+            OrderbookEvent::Fill(Fill {
+                order_hash: keccak256(event.config.order.abi_encode()),
+                signed_context: event.config.signedContext,
+                input: float_to_f64(event.input)?,
+                output: float_to_f64(event.output)?,
+            })
+        }
+        Some(&TakeOrderV2::SIGNATURE_HASH) => {
+            let event = TakeOrderV2::decode_log_data(log)?;
+            OrderbookEvent::Fill(Fill {
+                order_hash: keccak256(event.config.order.abi_encode()),
+                signed_context: event.config.signedContext,
+                input: amount_to_f64(event.input)?,
+                output: amount_to_f64(event.output)?,
+            })
+        }
+        Some(&AddOrderV3::SIGNATURE_HASH) => {
+            OrderbookEvent::Added(AddOrderV3::decode_log_data(log)?.orderHash)
+        }
+        Some(&AddOrderV2::SIGNATURE_HASH) => {
+            OrderbookEvent::Added(AddOrderV2::decode_log_data(log)?.orderHash)
+        }
+        Some(&RemoveOrderV3::SIGNATURE_HASH) => {
+            OrderbookEvent::Removed(RemoveOrderV3::decode_log_data(log)?.orderHash)
+        }
+        Some(&RemoveOrderV2::SIGNATURE_HASH) => {
+            OrderbookEvent::Removed(RemoveOrderV2::decode_log_data(log)?.orderHash)
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(event))
+}
+
+/// Hashes of orders seen removed from the orderbook (and not re-added) since the watcher
+/// started. Requests for them are refused, since any take would revert.
+#[derive(Default)]
+pub struct RemovedOrders(Mutex<HashSet<B256>>);
+
+impl RemovedOrders {
+    pub fn contains(&self, order_hash: &B256) -> bool {
+        self.0.lock().unwrap().contains(order_hash)
+    }
+
+    pub fn insert(&self, order_hash: B256) {
+        self.0.lock().unwrap().insert(order_hash);
+    }
+
+    pub fn remove(&self, order_hash: &B256) {
+        self.0.lock().unwrap().remove(order_hash);
+    }
+
+    /// Replace the set, e.g. with the orders the leader's watcher recorded in storage.
+    pub fn replace(&self, orders: impl IntoIterator<Item = B256>) {
+        *self.0.lock().unwrap() = orders.into_iter().collect();
+    }
+}
+
+/// Hash of a signed context's words, to check a fill used the context a quote was issued with.
+pub fn context_hash(context: &[FixedBytes<32>]) -> B256 {
+    keccak256(context.concat())
+}
+
+/// How far the executed price (order input per order output) was from the signed price, in
+/// basis points of the signed price. Both are in the units the price was signed in.
+fn slippage_bps(record: &AuditRecord, fill: &Fill) -> anyhow::Result<f64> {
+    let price = record
+        .context
+        .first()
+        .ok_or_else(|| anyhow::anyhow!("Quote {} has an empty context", record.quote_id))?;
+    let signed = float_to_f64(*price)?;
+    anyhow::ensure!(
+        signed > 0.0 && fill.input > 0.0,
+        "Can't compute slippage for quote {}",
+        record.quote_id
+    );
+    Ok((fill.output / fill.input / signed - 1.0) * 10_000.0)
+}
+
+#[derive(Default)]
+struct FillCounters {
+    issued: u64,
+    matched: u64,
+    /// Signed by one of our signers but not found in the audit store (or not matching it)
+    unmatched: u64,
+    /// Issue time of each quote filled at least once, pruned after [`FILLED_RETENTION_SECONDS`]
+    filled: HashMap<uuid::Uuid, i64>,
+    filled_total: u64,
+    slippage_buckets: [u64; SLIPPAGE_BUCKETS.len()],
+    slippage_count: u64,
+    slippage_sum: f64,
+}
+
+/// Issued vs filled quote counters and fill slippage, rendered with the other metrics.
+#[derive(Default)]
+pub struct FillStats {
+    counters: Mutex<FillCounters>,
+}
+
+impl FillStats {
+    /// Count a newly signed quote.
+    pub fn record_issued(&self) {
+        self.counters.lock().unwrap().issued += 1;
+    }
+
+    fn record_unmatched(&self) {
+        self.counters.lock().unwrap().unmatched += 1;
+    }
+
+    fn record_fill(&self, record: &AuditRecord, slippage_bps: Option<f64>) {
+        let mut counters = self.counters.lock().unwrap();
+        counters.matched += 1;
+        if !counters.filled.contains_key(&record.quote_id) {
+            let cutoff = record.issued_at - FILLED_RETENTION_SECONDS;
+            counters.filled.retain(|_, issued_at| *issued_at >= cutoff);
+            counters.filled.insert(record.quote_id, record.issued_at);
+            counters.filled_total += 1;
+        }
+        if let Some(bps) = slippage_bps {
+            let abs = bps.abs();
+            for (bucket, bound) in counters.slippage_buckets.iter_mut().zip(SLIPPAGE_BUCKETS) {
+                if abs <= bound {
+                    *bucket += 1;
+                }
+            }
+            counters.slippage_count += 1;
+            counters.slippage_sum += abs;
+        }
+    }
+
+    /// Render the counters in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let counters = self.counters.lock().unwrap();
+        let mut out = String::new();
+        out.push_str("# HELP oracle_quotes_issued_total Quotes signed since startup.\n");
+        out.push_str("# TYPE oracle_quotes_issued_total counter\n");
+        let _ = writeln!(out, "oracle_quotes_issued_total {}", counters.issued);
+        out.push_str(
+            "# HELP oracle_quotes_filled_total Issued quotes used in at least one on-chain take.\n",
+        );
+        out.push_str("# TYPE oracle_quotes_filled_total counter\n");
+        let _ = writeln!(out, "oracle_quotes_filled_total {}", counters.filled_total);
+        out.push_str(
+            "# HELP oracle_quote_fill_rate Filled quotes as a fraction of issued quotes.\n",
+        );
+        out.push_str("# TYPE oracle_quote_fill_rate gauge\n");
+        let rate = if counters.issued == 0 {
+            0.0
+        } else {
+            counters.filled_total as f64 / counters.issued as f64
+        };
+        let _ = writeln!(out, "oracle_quote_fill_rate {}", rate);
+        out.push_str("# HELP oracle_fills_total On-chain takes carrying our signed context, by whether they matched an audit record.\n");
+        out.push_str("# TYPE oracle_fills_total counter\n");
+        let _ = writeln!(
+            out,
+            "oracle_fills_total{{matched=\"true\"}} {}",
+            counters.matched
+        );
+        let _ = writeln!(
+            out,
+            "oracle_fills_total{{matched=\"false\"}} {}",
+            counters.unmatched
+        );
+        out.push_str("# HELP oracle_fill_slippage_bps Absolute difference between executed and signed price, in basis points.\n");
+        out.push_str("# TYPE oracle_fill_slippage_bps histogram\n");
+        for (bound, count) in SLIPPAGE_BUCKETS.iter().zip(counters.slippage_buckets) {
+            let _ = writeln!(
+                out,
+                "oracle_fill_slippage_bps_bucket{{le=\"{}\"}} {}",
+                bound, count
+            );
+        }
+        let _ = writeln!(
+            out,
+            "oracle_fill_slippage_bps_bucket{{le=\"+Inf\"}} {}",
+            counters.slippage_count
+        );
+        let _ = writeln!(
+            out,
+            "oracle_fill_slippage_bps_sum {}",
+            counters.slippage_sum
+        );
+        let _ = writeln!(
+            out,
+            "oracle_fill_slippage_bps_count {}",
+            counters.slippage_count
+        );
+        out
+    }
+}
+
+/// Every address we sign with, to tell our signed contexts from other oracles'.
+fn signer_addresses(state: &AppState) -> HashSet<Address> {
+    state
+        .signers
+        .signers()
+        .into_iter()
+        .flat_map(|signer| {
+            std::iter::once(signer.address())
+                .chain(signer.members().into_iter().map(|m| m.address()))
+        })
+        .collect()
+}
+
+/// Match a fill's signed contexts to issued quotes by signature, checking the order hash
+/// and context hash agree with the audit record, and record fill metrics.
+pub async fn reconcile(state: &AppState, fill: &Fill) -> anyhow::Result<()> {
+    let ours = signer_addresses(state);
+    for signed in fill
+        .signed_context
+        .iter()
+        .filter(|signed| ours.contains(&signed.signer))
+    {
+        let record = state
+            .storage
+            .quote_by_signature(AuditRecord::signature_hash(&signed.signature))
+            .await?;
+        let Some(record) = record.filter(|record| {
+            record.order_hash.is_none_or(|hash| hash == fill.order_hash)
+                && context_hash(&record.context) == context_hash(&signed.context)
+        }) else {
+            tracing::warn!(
+                "Fill of order {} carries a context signed by {} that matches no issued quote",
+                fill.order_hash,
+                signed.signer
+            );
+            state.fills.record_unmatched();
+            continue;
+        };
+        let slippage = slippage_bps(&record, fill)
+            .inspect_err(|e| tracing::debug!("{:#}", e))
+            .ok();
+        tracing::debug!(
+            "Quote {} filled: order {} slippage {:?} bps",
+            record.quote_id,
+            fill.order_hash,
+            slippage
+        );
+        state.fills.record_fill(&record, slippage);
+    }
+    Ok(())
+}
+
+/// Apply one orderbook event to the server's state.
+pub async fn handle(state: &AppState, event: &OrderbookEvent) -> anyhow::Result<()> {
+    match event {
+        OrderbookEvent::Fill(fill) => reconcile(state, fill).await?,
+        OrderbookEvent::Added(order_hash) => {
+            state.removed_orders.remove(order_hash);
+            state.storage.set_order_removed(*order_hash, false).await?;
+        }
+        OrderbookEvent::Removed(order_hash) => {
+            tracing::info!("Order {} removed; no longer quoting it", order_hash);
+            state.removed_orders.insert(*order_hash);
+            // Shared with replicas that aren't running the watcher
+            state.storage.set_order_removed(*order_hash, true).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Fetch and handle the orderbook's events from `from` (the head, on the first poll) up
+/// to the head. Returns the next block to fetch from.
+async fn poll(
+    state: &AppState,
+    client: &reqwest::Client,
+    config: &WatcherConfig,
+    from: Option<u64>,
+) -> anyhow::Result<u64> {
+    let head = rpc::block_number_with(client, &config.rpc_url).await?;
+    let from = from.unwrap_or(head);
+    if from > head {
+        return Ok(from);
+    }
+    let to = head.min(from + MAX_BLOCK_RANGE - 1);
+    let logs = rpc::logs_with(
+        client,
+        &config.rpc_url,
+        config.orderbook,
+        &EVENT_TOPICS,
+        from,
+        to,
+    )
+    .await?;
+    for log in logs {
+        let data = LogData::new_unchecked(log.topics, log.data);
+        match decode_event(&data) {
+            Ok(Some(event)) => handle(state, &event).await?,
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Skipping undecodable orderbook log: {:#}", e),
+        }
+    }
+    Ok(to + 1)
+}
+
+/// Watch the orderbook for takes of our quotes and removed orders until the task is dropped.
+pub fn spawn(state: Arc<AppState>, config: WatcherConfig) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        let mut next_block = None;
+        let mut ticker = tokio::time::interval(config.poll_interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            if !state.is_leader() {
+                // Start from the head again if elected later
+                next_block = None;
+                continue;
+            }
+            match poll(&state, &client, &config, next_block).await {
+                Ok(next) => next_block = Some(next),
+                Err(e) => tracing::warn!("Orderbook watcher poll failed: {:#}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order::{EvaluableV4, OrderV4, TakeOrderConfigV4, IOV2};
+    use crate::tests::TEST_KEY;
+    use crate::{PriceDirection, TokenPairConfig};
+
+    fn order() -> OrderV4 {
+        OrderV4 {
+            owner: Address::repeat_byte(1),
+            evaluable: EvaluableV4 {
+                interpreter: Address::ZERO,
+                store: Address::ZERO,
+                bytecode: Default::default(),
+            },
+            validInputs: vec![IOV2 {
+                token: Address::repeat_byte(2),
+                vaultId: B256::ZERO,
+            }],
+            validOutputs: vec![IOV2 {
+                token: Address::repeat_byte(3),
+                vaultId: B256::ZERO,
+            }],
+            nonce: B256::ZERO,
+        }
+    }
+
+    fn take(signed_context: Vec<SignedContextV1>, input: &str, output: &str) -> LogData {
+        let float = |s: &str| B256::from(Float::parse(s.to_string()).unwrap());
+        TakeOrderV3 {
+            sender: Address::repeat_byte(9),
+            config: TakeOrderConfigV4 {
+                order: order(),
+                inputIOIndex: U256::ZERO,
+                outputIOIndex: U256::ZERO,
+                signedContext: signed_context,
+            },
+            input: float(input),
+            output: float(output),
+        }
+        .encode_log_data()
+    }
+
+    fn decode_fill(log: &LogData) -> Fill {
+        match decode_event(log).unwrap() {
+            Some(OrderbookEvent::Fill(fill)) => fill,
+            _ => panic!("not a fill"),
+        }
+    }
+
+    fn state() -> AppState {
+        AppState::new(
+            TEST_KEY,
+            "00",
+            5,
+            TokenPairConfig::new(
+                "0x4200000000000000000000000000000000000006",
+                "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_decode_fill() {
+        let fill = decode_fill(&take(vec![], "2", "5000"));
+        assert_eq!(fill.order_hash, keccak256(order().abi_encode()));
+        assert_eq!((fill.input, fill.output), (2.0, 5000.0));
+
+        let other = LogData::new_unchecked(vec![B256::repeat_byte(1)], Default::default());
+        assert!(decode_event(&other).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_removed_orders() {
+        let state = state();
+        let hash = keccak256(order().abi_encode());
+        let removed = RemoveOrderV3 {
+            sender: Address::repeat_byte(1),
+            orderHash: hash,
+            order: order(),
+        }
+        .encode_log_data();
+        let added = AddOrderV3 {
+            sender: Address::repeat_byte(1),
+            orderHash: hash,
+            order: order(),
+        }
+        .encode_log_data();
+
+        let event = decode_event(&removed).unwrap().unwrap();
+        assert!(matches!(event, OrderbookEvent::Removed(h) if h == hash));
+        handle(&state, &event).await.unwrap();
+        assert!(state.removed_orders.contains(&hash));
+
+        // Re-adding the same order makes it quotable again
+        handle(&state, &decode_event(&added).unwrap().unwrap())
+            .await
+            .unwrap();
+        assert!(!state.removed_orders.contains(&hash));
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_matches_issued_quote() {
+        let state = state();
+        let signer = state.signers.default_signer().address();
+        let price = B256::from(Float::parse("2500".to_string()).unwrap());
+        let record = AuditRecord {
+            quote_id: uuid::Uuid::new_v4(),
+            signature_hash: AuditRecord::signature_hash(b"sig"),
+            issued_at: 1700000000,
+            signer,
+            counterparty: Address::ZERO,
+            api_key: None,
+            taker_id: None,
+            order_hash: Some(keccak256(order().abi_encode())),
+            output_vault: None,
+            base_token: Address::repeat_byte(3),
+            quote_token: Address::repeat_byte(2),
+            direction: PriceDirection::AsIs,
+            context: vec![price],
+            signature: b"sig".into(),
+            explanation: None,
+        };
+        state.storage.record_quote(&record).await.unwrap();
+        state.fills.record_issued();
+        state.fills.record_issued();
+
+        let signed = |signature: &'static [u8]| SignedContextV1 {
+            signer,
+            context: vec![price],
+            signature: signature.into(),
+        };
+        // Taker received 2 and paid 5010: 20 bps above the signed 2500
+        let log = take(vec![signed(b"sig"), signed(b"unknown")], "2", "5010");
+        let fill = decode_fill(&log);
+        reconcile(&state, &fill).await.unwrap();
+        reconcile(&state, &fill).await.unwrap();
+
+        let out = state.fills.render();
+        assert!(out.contains("oracle_quotes_issued_total 2\n"), "{}", out);
+        assert!(out.contains("oracle_quotes_filled_total 1\n"), "{}", out);
+        assert!(out.contains("oracle_quote_fill_rate 0.5\n"), "{}", out);
+        assert!(
+            out.contains("oracle_fills_total{matched=\"true\"} 2\n"),
+            "{}",
+            out
+        );
+        assert!(
+            out.contains("oracle_fills_total{matched=\"false\"} 2\n"),
+            "{}",
+            out
+        );
+        assert!(
+            out.contains("oracle_fill_slippage_bps_bucket{le=\"10\"} 0\n"),
+            "{}",
+            out
+        );
+        assert!(
+            out.contains("oracle_fill_slippage_bps_bucket{le=\"25\"} 2\n"),
+            "{}",
+            out
+        );
+    }
+}
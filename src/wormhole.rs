@@ -0,0 +1,352 @@
+use alloy::primitives::{keccak256, Address, Signature, B256};
+
+/// Wormhole chain ID of Pythnet, where Pyth price updates are emitted.
+pub const PYTHNET_CHAIN_ID: u16 = 26;
+
+/// Emitter of Pyth's accumulator (Merkle root) messages on Pythnet.
+pub const PYTHNET_ACCUMULATOR_EMITTER: B256 =
+    alloy::primitives::b256!("e101faedac5851e32b9b23b5f9411a8c2bac4aae3ed4dd7b811dd1a72ea4aa71");
+
+/// Magic prefix of Pyth accumulator update data (`PNAU`).
+const ACCUMULATOR_MAGIC: &[u8; 4] = b"PNAU";
+/// Magic prefix of the accumulator VAA payload (`AUWV`).
+const WORMHOLE_MERKLE_MAGIC: &[u8; 4] = b"AUWV";
+const PRICE_FEED_MESSAGE: u8 = 0;
+
+/// The Wormhole guardian set whose signatures a VAA must carry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuardianSet {
+    pub index: u32,
+    pub keys: Vec<Address>,
+}
+
+/// A price from a verified Pyth price update message.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceFeedMessage {
+    pub feed_id: B256,
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+/// Big-endian reader over a byte slice, failing on truncated input.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> anyhow::Result<&'a [u8]> {
+        anyhow::ensure!(self.0.len() >= n, "Truncated price update data");
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> anyhow::Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("length checked"))
+    }
+
+    fn u8(&mut self) -> anyhow::Result<u8> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> anyhow::Result<u16> {
+        Ok(u16::from_be_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> anyhow::Result<u32> {
+        Ok(u32::from_be_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> anyhow::Result<u64> {
+        Ok(u64::from_be_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> anyhow::Result<i32> {
+        Ok(i32::from_be_bytes(self.array()?))
+    }
+
+    fn i64(&mut self) -> anyhow::Result<i64> {
+        Ok(i64::from_be_bytes(self.array()?))
+    }
+}
+
+/// keccak256 truncated to 20 bytes, as used by Pyth's accumulator Merkle tree.
+fn keccak160(parts: &[&[u8]]) -> [u8; 20] {
+    let hash = keccak256(parts.concat());
+    hash[..20].try_into().expect("20 bytes")
+}
+
+fn merkle_leaf(message: &[u8]) -> [u8; 20] {
+    keccak160(&[&[0], message])
+}
+
+fn merkle_node(a: [u8; 20], b: [u8; 20]) -> [u8; 20] {
+    let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+    keccak160(&[&[1], &lo, &hi])
+}
+
+impl GuardianSet {
+    /// Signatures needed for a VAA to be valid: more than two thirds of the guardians.
+    pub fn quorum(&self) -> usize {
+        self.keys.len() * 2 / 3 + 1
+    }
+
+    /// Check a VAA's guardian signatures and emitter, returning its payload.
+    pub fn verify_vaa<'a>(&self, vaa: &'a [u8]) -> anyhow::Result<&'a [u8]> {
+        let mut reader = Reader(vaa);
+        anyhow::ensure!(reader.u8()? == 1, "Unsupported VAA version");
+        let index = reader.u32()?;
+        anyhow::ensure!(
+            index == self.index,
+            "VAA signed by guardian set {}, expected {}",
+            index,
+            self.index
+        );
+        let mut signatures = Vec::new();
+        for _ in 0..reader.u8()? {
+            signatures.push((reader.u8()? as usize, reader.array::<65>()?));
+        }
+        let body = reader.0;
+        let digest = keccak256(keccak256(body));
+
+        let mut last = None;
+        for (guardian, signature) in &signatures {
+            anyhow::ensure!(
+                last.is_none_or(|last| *guardian > last),
+                "VAA guardian signatures are not in ascending order"
+            );
+            last = Some(*guardian);
+            let key = self
+                .keys
+                .get(*guardian)
+                .ok_or_else(|| anyhow::anyhow!("VAA signed by unknown guardian {}", guardian))?;
+            let signer = Signature::from_raw(signature)?.recover_address_from_prehash(&digest)?;
+            anyhow::ensure!(
+                signer == *key,
+                "VAA signature {} is not from guardian {}",
+                guardian,
+                key
+            );
+        }
+        anyhow::ensure!(
+            signatures.len() >= self.quorum(),
+            "VAA has {} guardian signatures, quorum is {}",
+            signatures.len(),
+            self.quorum()
+        );
+
+        let mut body = Reader(body);
+        body.take(8)?; // timestamp, nonce
+        let chain = body.u16()?;
+        let emitter = B256::from(body.array::<32>()?);
+        anyhow::ensure!(
+            chain == PYTHNET_CHAIN_ID && emitter == PYTHNET_ACCUMULATOR_EMITTER,
+            "VAA emitted by {} on chain {}, not the Pyth accumulator",
+            emitter,
+            chain
+        );
+        body.take(9)?; // sequence, consistency level
+        Ok(body.0)
+    }
+
+    /// Verify Pyth accumulator update data (Hermes' `binary` field) and return the price
+    /// message for `feed_id`: the VAA must carry a guardian quorum and the message must be
+    /// proven against the Merkle root it signs.
+    pub fn verify_update(&self, data: &[u8], feed_id: B256) -> anyhow::Result<PriceFeedMessage> {
+        let mut reader = Reader(data);
+        anyhow::ensure!(
+            reader.take(4)? == ACCUMULATOR_MAGIC,
+            "Not Pyth accumulator update data"
+        );
+        anyhow::ensure!(reader.u8()? == 1, "Unsupported accumulator major version");
+        reader.u8()?; // minor version
+        let trailing = reader.u8()? as usize;
+        reader.take(trailing)?;
+        anyhow::ensure!(reader.u8()? == 0, "Unsupported accumulator proof type");
+
+        let vaa_len = reader.u16()? as usize;
+        let mut payload = Reader(self.verify_vaa(reader.take(vaa_len)?)?);
+        anyhow::ensure!(
+            payload.take(4)? == WORMHOLE_MERKLE_MAGIC,
+            "VAA payload is not a Pyth Merkle root"
+        );
+        anyhow::ensure!(payload.u8()? == 0, "Unsupported accumulator update type");
+        payload.take(12)?; // slot, ring size
+        let root: [u8; 20] = payload.array()?;
+
+        for _ in 0..reader.u8()? {
+            let message_len = reader.u16()? as usize;
+            let message = reader.take(message_len)?;
+            let mut node = merkle_leaf(message);
+            for _ in 0..reader.u8()? {
+                node = merkle_node(node, reader.array()?);
+            }
+            let parsed = parse_price_message(message)?;
+            if parsed.feed_id == feed_id {
+                anyhow::ensure!(
+                    node == root,
+                    "Price update for {} is not proven by the signed Merkle root",
+                    feed_id
+                );
+                return Ok(parsed);
+            }
+        }
+        anyhow::bail!("Price update data has no message for feed {}", feed_id)
+    }
+}
+
+fn parse_price_message(message: &[u8]) -> anyhow::Result<PriceFeedMessage> {
+    let mut reader = Reader(message);
+    anyhow::ensure!(
+        reader.u8()? == PRICE_FEED_MESSAGE,
+        "Not a Pyth price feed message"
+    );
+    Ok(PriceFeedMessage {
+        feed_id: B256::from(reader.array::<32>()?),
+        price: reader.i64()?,
+        conf: reader.u64()?,
+        expo: reader.i32()?,
+        publish_time: reader.i64()?,
+    })
+}
+
+/// Encode accumulator update data for `messages`, signed by `guardians` (with guardian set
+/// index `index`), for tests standing in for Hermes.
+#[cfg(test)]
+pub(crate) fn encode_update(
+    index: u32,
+    guardians: &[alloy::signers::local::PrivateKeySigner],
+    messages: &[PriceFeedMessage],
+) -> Vec<u8> {
+    use alloy::signers::SignerSync;
+
+    let encoded: Vec<Vec<u8>> = messages
+        .iter()
+        .map(|m| {
+            let mut out = vec![PRICE_FEED_MESSAGE];
+            out.extend_from_slice(m.feed_id.as_slice());
+            out.extend_from_slice(&m.price.to_be_bytes());
+            out.extend_from_slice(&m.conf.to_be_bytes());
+            out.extend_from_slice(&m.expo.to_be_bytes());
+            out.extend_from_slice(&m.publish_time.to_be_bytes());
+            out.extend_from_slice(&(m.publish_time - 1).to_be_bytes());
+            out.extend_from_slice(&m.price.to_be_bytes()); // EMA price
+            out.extend_from_slice(&m.conf.to_be_bytes()); // EMA confidence
+            out
+        })
+        .collect();
+    // Perfect binary tree over the leaves, padded with empty-message leaves
+    let mut level: Vec<[u8; 20]> = encoded.iter().map(|m| merkle_leaf(m)).collect();
+    level.resize(level.len().next_power_of_two(), merkle_leaf(&[]));
+    let mut proofs = vec![Vec::new(); encoded.len()];
+    let mut positions: Vec<usize> = (0..encoded.len()).collect();
+    while level.len() > 1 {
+        for (proof, position) in proofs.iter_mut().zip(positions.iter_mut()) {
+            proof.push(level[*position ^ 1]);
+            *position /= 2;
+        }
+        level = level
+            .chunks(2)
+            .map(|pair| merkle_node(pair[0], pair[1]))
+            .collect();
+    }
+
+    let mut body = vec![0; 8];
+    body.extend_from_slice(&PYTHNET_CHAIN_ID.to_be_bytes());
+    body.extend_from_slice(PYTHNET_ACCUMULATOR_EMITTER.as_slice());
+    body.extend_from_slice(&[0; 9]);
+    body.extend_from_slice(WORMHOLE_MERKLE_MAGIC);
+    body.push(0);
+    body.extend_from_slice(&[0; 12]);
+    body.extend_from_slice(&level[0]);
+    let digest = keccak256(keccak256(&body));
+
+    let mut vaa = vec![1];
+    vaa.extend_from_slice(&index.to_be_bytes());
+    vaa.push(guardians.len() as u8);
+    for (i, guardian) in guardians.iter().enumerate() {
+        let signature = guardian.sign_hash_sync(&digest).unwrap();
+        vaa.push(i as u8);
+        vaa.extend_from_slice(&signature.as_bytes());
+    }
+    vaa.extend_from_slice(&body);
+
+    let mut data = ACCUMULATOR_MAGIC.to_vec();
+    data.extend_from_slice(&[1, 0, 0, 0]);
+    data.extend_from_slice(&(vaa.len() as u16).to_be_bytes());
+    data.extend_from_slice(&vaa);
+    data.push(encoded.len() as u8);
+    for (message, proof) in encoded.iter().zip(&proofs) {
+        data.extend_from_slice(&(message.len() as u16).to_be_bytes());
+        data.extend_from_slice(message);
+        data.push(proof.len() as u8);
+        for node in proof {
+            data.extend_from_slice(node);
+        }
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::signers::local::PrivateKeySigner;
+
+    fn message(feed: u8, price: i64) -> PriceFeedMessage {
+        PriceFeedMessage {
+            feed_id: B256::repeat_byte(feed),
+            price,
+            conf: 1_000,
+            expo: -8,
+            publish_time: 1_700_000_000,
+        }
+    }
+
+    fn guardians(n: usize) -> (Vec<PrivateKeySigner>, GuardianSet) {
+        let signers: Vec<_> = (0..n).map(|_| PrivateKeySigner::random()).collect();
+        let set = GuardianSet {
+            index: 4,
+            keys: signers.iter().map(|s| s.address()).collect(),
+        };
+        (signers, set)
+    }
+
+    #[test]
+    fn test_verify_update() {
+        let (signers, set) = guardians(4);
+        let messages = [
+            message(1, 310012345678),
+            message(2, 100_000_000),
+            message(3, 5),
+        ];
+        let data = encode_update(4, &signers[..3], &messages);
+        assert_eq!(
+            set.verify_update(&data, B256::repeat_byte(2)).unwrap(),
+            messages[1]
+        );
+        assert!(set.verify_update(&data, B256::repeat_byte(9)).is_err());
+
+        // Below quorum (3 of 4)
+        let data = encode_update(4, &signers[..2], &messages);
+        assert!(set.verify_update(&data, B256::repeat_byte(1)).is_err());
+
+        // Signed by another guardian set
+        let (others, _) = guardians(4);
+        let data = encode_update(4, &others[..3], &messages);
+        assert!(set.verify_update(&data, B256::repeat_byte(1)).is_err());
+        let data = encode_update(3, &signers[..3], &messages);
+        assert!(set.verify_update(&data, B256::repeat_byte(1)).is_err());
+    }
+
+    #[test]
+    fn test_tampered_message() {
+        let (signers, set) = guardians(1);
+        let mut data = encode_update(4, &signers, &[message(1, 310012345678), message(2, 1)]);
+        // The first message's price sits right after its type byte and feed ID
+        let vaa_len = u16::from_be_bytes([data[8], data[9]]) as usize;
+        let price_at = 10 + vaa_len + 1 + 2 + 1 + 32;
+        data[price_at + 7] ^= 1;
+        assert!(set.verify_update(&data, B256::repeat_byte(1)).is_err());
+        assert!(set.verify_update(&data, B256::repeat_byte(2)).is_ok());
+    }
+}